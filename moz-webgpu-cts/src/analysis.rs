@@ -0,0 +1,1131 @@
+//! Analysis of metadata for triage, i.e., finding tests with expectations of concern on each
+//! platform. This is what `triage` reports on, but it may also be used to build other views of
+//! the same data.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Debug, Display, Formatter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::NaiveDate;
+use enumset::EnumSetType;
+use indexmap::IndexSet;
+use serde::Serialize;
+use whippit::metadata::SectionHeader;
+
+use crate::{
+    metadata::{
+        disabled_reason_on, BuildProfile, DirFile, DirProps, DisabledReason, File, Platform,
+        Subtest, SubtestOutcome, Test, TestOutcome, TestProps,
+    },
+    quarantine,
+    severity::SeverityOrder,
+    shared::{CtsQuery, Expected, TestPath},
+    test_pattern::TestPattern,
+};
+
+/// Collects tests in `files`, metadata files in `gecko_checkout`, by their runner URL path
+/// (without a leading `/`), for [`Analysis::new`]. If `filters` are given, only tests matching
+/// any of them are collected.
+///
+/// # Panics
+///
+/// This function will panic if a path in `files` is not in `gecko_checkout`, or does not name a
+/// metadata file of the WebGPU CTS.
+pub fn tests_by_name(
+    gecko_checkout: &Path,
+    files: impl IntoIterator<Item = (Arc<PathBuf>, File)>,
+    filters: &[TestPattern],
+) -> BTreeMap<String, TaggedTest> {
+    files
+        .into_iter()
+        .flat_map(
+            |(
+                path,
+                File {
+                    properties: _,
+                    tests,
+                },
+            )| {
+                tests
+                    .into_iter()
+                    .filter_map(move |(SectionHeader(name), inner)| {
+                        let test_path = TestPath::from_metadata_test(
+                            path.strip_prefix(gecko_checkout).unwrap(),
+                            &name,
+                        )
+                        .unwrap();
+                        if !filters.is_empty()
+                            && !filters.iter().any(|filter| filter.matches(&test_path))
+                        {
+                            return None;
+                        }
+                        let url_path = test_path.runner_url_path().to_string();
+                        Some((
+                            url_path,
+                            TaggedTest {
+                                inner,
+                                orig_path: path.clone(),
+                            },
+                        ))
+                    })
+            },
+        )
+        .collect()
+}
+
+/// Returns the directories of `dir_files`, directory metadata files (i.e., `__dir__.ini`), with
+/// properties that tests under them inherit in [`Analysis::new`] (i.e., that are disabled on some
+/// configuration, or that set `expected`), with those properties.
+pub fn inherited_dir_props(
+    dir_files: impl IntoIterator<Item = (Arc<PathBuf>, DirFile)>,
+) -> Vec<(PathBuf, DirProps)> {
+    dir_files
+        .into_iter()
+        .map(|(path, file)| (path.parent().unwrap().to_owned(), file.properties))
+        .filter(|(_dir, props)| props.file.is_disabled.is_some() || props.test.expected.is_some())
+        .collect()
+}
+
+/// A test from a metadata file, tagged with the path of that file.
+#[derive(Debug)]
+pub struct TaggedTest {
+    pub orig_path: Arc<PathBuf>,
+    pub inner: Test,
+}
+
+/// Items split by whether the outcome they were found for is expected permanently or only
+/// intermittently.
+#[derive(Clone, Default)]
+pub struct PermaAndIntermittent<T> {
+    pub perma: T,
+    pub intermittent: T,
+}
+
+impl<T> Debug for PermaAndIntermittent<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            perma,
+            intermittent,
+        } = self;
+        f.debug_struct("") // the name is distracting, blank it out plz
+            .field("perma", perma)
+            .field("intermittent", intermittent)
+            .finish()
+    }
+}
+
+impl<T> PermaAndIntermittent<T> {
+    pub fn as_ref(&self) -> PermaAndIntermittent<&T> {
+        let Self {
+            perma,
+            intermittent,
+        } = self;
+        PermaAndIntermittent {
+            perma,
+            intermittent,
+        }
+    }
+
+    pub fn map<U>(self, f: impl Fn(T) -> U) -> PermaAndIntermittent<U> {
+        let Self {
+            perma,
+            intermittent,
+        } = self;
+        PermaAndIntermittent {
+            perma: f(perma),
+            intermittent: f(intermittent),
+        }
+    }
+}
+
+pub type TestSet = PermaAndIntermittent<BTreeSet<Arc<String>>>;
+pub type SubtestByTestSet = PermaAndIntermittent<BTreeMap<Arc<String>, IndexSet<Arc<String>>>>;
+
+/// Tests of interest for triage on a single platform, by the kind of issue they have. Tests are
+/// named by their runner URL path, without a leading `/`.
+#[derive(Clone, Debug, Default)]
+pub struct PerPlatformAnalysis {
+    pub tests_with_runner_errors: TestSet,
+    pub tests_with_disabled_or_skip: TestSet,
+    /// Tests with some portion whose `disabled-until` date is on or before the day of analysis.
+    pub tests_with_expired_disables: BTreeSet<Arc<String>>,
+    /// Tests quarantined at least `--quarantine-review-days` before the day of analysis.
+    pub tests_with_overdue_quarantines: BTreeSet<Arc<String>>,
+    /// Tests under a directory whose `__dir__.ini` (or that of a directory it's in) disables it
+    /// for some build profile.
+    pub tests_in_disabled_dirs: BTreeSet<Arc<String>>,
+    /// Tests with some portion marked as `disabled`, by the reason given (i.e., a bug URL).
+    pub tests_by_disabled_reason: BTreeMap<DisabledReason<'static>, BTreeSet<Arc<String>>>,
+    pub tests_with_crashes: TestSet,
+    pub subtests_with_failures_by_test: SubtestByTestSet,
+    pub subtests_with_timeouts_by_test: SubtestByTestSet,
+    /// Tests by the [rank](SeverityOrder::rank_test_outcome) of the most severe outcome expected
+    /// of some portion of them.
+    pub tests_by_most_severe_outcome: BTreeMap<usize, BTreeSet<Arc<String>>>,
+}
+
+impl PerPlatformAnalysis {
+    /// Returns every test found in some category of this analysis, with the categories it was
+    /// found in.
+    pub fn issues_by_test(&self) -> BTreeMap<Arc<String>, Vec<Issue>> {
+        let Self {
+            tests_with_runner_errors,
+            tests_with_disabled_or_skip,
+            tests_with_expired_disables,
+            tests_with_overdue_quarantines,
+            tests_in_disabled_dirs,
+            tests_by_disabled_reason: _,
+            tests_with_crashes,
+            subtests_with_failures_by_test,
+            subtests_with_timeouts_by_test,
+            tests_by_most_severe_outcome: _,
+        } = self;
+
+        let mut issues = BTreeMap::<_, Vec<_>>::new();
+        let mut insert = |tests: &mut dyn Iterator<Item = &Arc<String>>, issue| {
+            for test_name in tests {
+                issues.entry(test_name.clone()).or_default().push(issue);
+            }
+        };
+        let PermaAndIntermittent {
+            perma,
+            intermittent,
+        } = tests_with_runner_errors;
+        insert(&mut perma.iter(), Issue::PermaError);
+        insert(&mut intermittent.iter(), Issue::IntermittentError);
+        let PermaAndIntermittent {
+            perma,
+            intermittent,
+        } = tests_with_disabled_or_skip;
+        insert(&mut perma.iter().chain(intermittent), Issue::Disabled);
+        insert(
+            &mut tests_with_expired_disables.iter(),
+            Issue::ExpiredDisable,
+        );
+        insert(
+            &mut tests_with_overdue_quarantines.iter(),
+            Issue::OverdueQuarantine,
+        );
+        insert(&mut tests_in_disabled_dirs.iter(), Issue::DisabledDir);
+        let PermaAndIntermittent {
+            perma,
+            intermittent,
+        } = tests_with_crashes;
+        insert(&mut perma.iter(), Issue::PermaCrash);
+        insert(&mut intermittent.iter(), Issue::IntermittentCrash);
+        let PermaAndIntermittent {
+            perma,
+            intermittent,
+        } = subtests_with_failures_by_test;
+        insert(&mut perma.keys(), Issue::PermaFail);
+        insert(&mut intermittent.keys(), Issue::IntermittentFail);
+        let PermaAndIntermittent {
+            perma,
+            intermittent,
+        } = subtests_with_timeouts_by_test;
+        insert(&mut perma.keys(), Issue::PermaTimeout);
+        insert(&mut intermittent.keys(), Issue::IntermittentTimeout);
+        issues
+    }
+
+    /// Rolls [`Self::issues_by_test`] up by area of the CTS: the files under the first `depth`
+    /// segments of the file path of tests' CTS queries, i.e., `webgpu:api,validation,*` for
+    /// `webgpu:api,validation,render_pass,resolve:*` with a `depth` of 2; see
+    /// [`CtsQuery::file_prefix`]. Tests outside the CTS are grouped under `None`. Areas are
+    /// returned from most to fewest tests.
+    pub fn issues_by_cts_area(&self, depth: usize) -> Vec<(Option<CtsQuery>, CtsAreaIssues)> {
+        let mut areas = BTreeMap::<_, CtsAreaIssues>::new();
+        for (test_name, issues) in self.issues_by_test() {
+            let CtsAreaIssues {
+                num_tests,
+                num_tests_by_issue,
+            } = areas.entry(cts_area(&test_name, depth)).or_default();
+            *num_tests += 1;
+            for issue in issues {
+                *num_tests_by_issue.entry(issue).or_default() += 1;
+            }
+        }
+        let mut areas = areas.into_iter().collect::<Vec<_>>();
+        areas.sort_by_key(|(_area, issues)| std::cmp::Reverse(issues.num_tests));
+        areas
+    }
+}
+
+/// A category of [`PerPlatformAnalysis`] that a test may be found in; see
+/// [`PerPlatformAnalysis::issues_by_test`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Issue {
+    PermaError,
+    IntermittentError,
+    Disabled,
+    ExpiredDisable,
+    OverdueQuarantine,
+    DisabledDir,
+    PermaCrash,
+    IntermittentCrash,
+    PermaFail,
+    IntermittentFail,
+    PermaTimeout,
+    IntermittentTimeout,
+}
+
+impl Issue {
+    /// The name of this category in `triage`'s output, i.e., `perma-crash`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PermaError => "perma-error",
+            Self::IntermittentError => "intermittent-error",
+            Self::Disabled => "disabled",
+            Self::ExpiredDisable => "expired-disable",
+            Self::OverdueQuarantine => "overdue-quarantine",
+            Self::DisabledDir => "disabled-dir",
+            Self::PermaCrash => "perma-crash",
+            Self::IntermittentCrash => "intermittent-crash",
+            Self::PermaFail => "perma-fail",
+            Self::IntermittentFail => "intermittent-fail",
+            Self::PermaTimeout => "perma-timeout",
+            Self::IntermittentTimeout => "intermittent-timeout",
+        }
+    }
+
+    /// Whether this is an outcome expected of a test on every run, rather than intermittently.
+    pub fn is_perma(self) -> bool {
+        match self {
+            Self::PermaError | Self::PermaCrash | Self::PermaFail | Self::PermaTimeout => true,
+            Self::IntermittentError
+            | Self::Disabled
+            | Self::ExpiredDisable
+            | Self::OverdueQuarantine
+            | Self::DisabledDir
+            | Self::IntermittentCrash
+            | Self::IntermittentFail
+            | Self::IntermittentTimeout => false,
+        }
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Tests needing attention in an area of the CTS; see [`PerPlatformAnalysis::issues_by_cts_area`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CtsAreaIssues {
+    pub num_tests: usize,
+    /// How many tests were found in each category of [`PerPlatformAnalysis::issues_by_test`].
+    pub num_tests_by_issue: BTreeMap<Issue, usize>,
+}
+
+/// Returns the area of the CTS that `test_name`, a runner URL path, is in; see
+/// [`PerPlatformAnalysis::issues_by_cts_area`].
+fn cts_area(test_name: &str, depth: usize) -> Option<CtsQuery> {
+    let (_path, query) = test_name.split_once("?q=")?;
+    let query = query.parse::<CtsQuery>().ok()?;
+    Some(query.file_prefix(depth))
+}
+
+/// The results of [`Analysis::new`], for each platform.
+#[derive(Clone, Debug, Default)]
+pub struct Analysis {
+    pub windows: PerPlatformAnalysis,
+    pub linux: PerPlatformAnalysis,
+    pub mac_os: PerPlatformAnalysis,
+    pub android: PerPlatformAnalysis,
+}
+
+impl Analysis {
+    /// Analyzes `tests_by_name`, as returned by [`tests_by_name`], on `today`. Tests inherit
+    /// properties of directories they're under from `dir_props`, as returned by
+    /// [`inherited_dir_props`]: tests under disabled directories are counted as such, and tests
+    /// without their own `expected` take that of the nearest directory that sets it. Tests are
+    /// ranked by the most severe outcome expected of them according to `severity`, except on
+    /// platforms where they, or directories they're under, are disabled.
+    pub fn new(
+        tests_by_name: BTreeMap<String, TaggedTest>,
+        dir_props: &[(PathBuf, DirProps)],
+        today: NaiveDate,
+        quarantine_review_days: u64,
+        severity: &SeverityOrder,
+    ) -> Self {
+        let mut analysis = Self::default();
+        for (test_name, test) in tests_by_name {
+            let TaggedTest {
+                orig_path,
+                inner:
+                    Test {
+                        properties,
+                        subtests,
+                    },
+            } = test;
+
+            let TestProps {
+                is_disabled: _,
+                disabled_until,
+                keep_expectations: _,
+                quarantined,
+                bug: _,
+                expected,
+                expected_by_variant: _,
+                verbatim_expected: _,
+            } = properties;
+
+            let test_name = Arc::new(test_name);
+
+            let dir_props = || {
+                dir_props
+                    .iter()
+                    .filter(|(dir, _props)| orig_path.starts_with(dir))
+            };
+
+            // N.B.: Like `wptrunner`, the nearest directory whose `disabled` applies wins.
+            let dir_disabled_reason = |platform| {
+                dir_props()
+                    .filter_map(|(dir, props)| {
+                        let reason = [BuildProfile::Debug, BuildProfile::Optimized]
+                            .into_iter()
+                            .find_map(|build_profile| {
+                                disabled_reason_on(
+                                    props.file.is_disabled.as_ref(),
+                                    platform,
+                                    build_profile,
+                                )
+                            })?;
+                        Some((dir, reason))
+                    })
+                    .max_by_key(|(dir, _reason)| dir.components().count())
+                    .map(|(_dir, reason)| reason)
+            };
+
+            let expected = expected.or_else(|| {
+                dir_props()
+                    .filter(|(_dir, props)| props.test.expected.is_some())
+                    .max_by_key(|(dir, _props)| dir.components().count())
+                    .and_then(|(_dir, props)| props.test.expected.clone())
+            });
+
+            for platform in [
+                Platform::Windows,
+                Platform::Linux,
+                Platform::MacOs,
+                Platform::Android,
+            ] {
+                if let Some(reason) = disabled_reason(&properties, platform) {
+                    record_disabled(&mut analysis, platform, &test_name, reason);
+                }
+                if dir_disabled_reason(platform).is_some() {
+                    analysis.for_platform_mut(platform, |analysis| {
+                        analysis.tests_in_disabled_dirs.insert(test_name.clone());
+                    });
+                }
+            }
+
+            {
+                let mut most_severe = BTreeMap::<Platform, usize>::new();
+                let mut record = |platform, rank: Option<usize>| {
+                    if disabled_reason(&properties, platform).is_some()
+                        || dir_disabled_reason(platform).is_some()
+                    {
+                        return;
+                    }
+                    if let Some(rank) = rank {
+                        let worst = most_severe.entry(platform).or_insert(rank);
+                        *worst = rank.min(*worst);
+                    }
+                };
+                for ((platform, _build_profile), expected) in expected.iter().flat_map(|e| e.iter())
+                {
+                    for outcome in expected.iter() {
+                        record(platform, severity.rank_test_outcome(outcome));
+                    }
+                }
+                for Subtest {
+                    properties: subtest_properties,
+                } in subtests.values()
+                {
+                    let expected = subtest_properties.expected.iter().flat_map(|e| e.iter());
+                    for ((platform, _build_profile), expected) in expected {
+                        if disabled_reason(subtest_properties, platform).is_some() {
+                            continue;
+                        }
+                        for outcome in expected.iter() {
+                            record(platform, severity.rank_subtest_outcome(outcome));
+                        }
+                    }
+                }
+                for (platform, rank) in most_severe {
+                    analysis.for_platform_mut(platform, |analysis| {
+                        analysis
+                            .tests_by_most_severe_outcome
+                            .entry(rank)
+                            .or_default()
+                            .insert(test_name.clone());
+                    });
+                }
+            }
+
+            let is_expired = |disabled_until: Option<NaiveDate>| {
+                disabled_until.map_or(false, |until| until <= today)
+            };
+            if is_expired(disabled_until)
+                || subtests
+                    .values()
+                    .any(|subtest| is_expired(subtest.properties.disabled_until))
+            {
+                analysis.for_each_platform_mut(|analysis| {
+                    analysis
+                        .tests_with_expired_disables
+                        .insert(test_name.clone());
+                })
+            }
+
+            let is_overdue = quarantined.map_or(false, |quarantined| {
+                quarantine::is_due_for_review(quarantined, quarantine_review_days, today)
+            });
+            if is_overdue {
+                analysis.for_each_platform_mut(|analysis| {
+                    analysis
+                        .tests_with_overdue_quarantines
+                        .insert(test_name.clone());
+                })
+            }
+
+            fn insert_in_test_set<Out>(
+                poi: &mut TestSet,
+                test_name: &Arc<String>,
+                expected: Expected<Out>,
+                outcome: Out,
+            ) where
+                Out: Debug + Default + EnumSetType,
+            {
+                if expected.is_superset(&Expected::permanent(outcome)) {
+                    if expected.is_permanent() {
+                        &mut poi.perma
+                    } else {
+                        &mut poi.intermittent
+                    }
+                    .insert(test_name.clone());
+                }
+            }
+
+            fn insert_in_subtest_by_test_set<Out>(
+                poi: &mut SubtestByTestSet,
+                test_name: &Arc<String>,
+                subtest_name: &Arc<String>,
+                expected: Expected<Out>,
+                outcome: Out,
+            ) where
+                Out: Debug + Default + EnumSetType,
+            {
+                if expected.is_superset(&Expected::permanent(outcome)) {
+                    if expected.is_permanent() {
+                        &mut poi.perma
+                    } else {
+                        &mut poi.intermittent
+                    }
+                    .entry(test_name.clone())
+                    .or_default()
+                    .insert(subtest_name.clone());
+                }
+            }
+
+            if let Some(expected) = expected {
+                fn analyze_test_outcome<F>(
+                    test_name: &Arc<String>,
+                    expected: Expected<TestOutcome>,
+                    mut receiver: F,
+                ) where
+                    F: FnMut(&mut dyn FnMut(&mut PerPlatformAnalysis)),
+                {
+                    for outcome in expected.iter() {
+                        match outcome {
+                            TestOutcome::Ok => (),
+                            // We skip this because this test _should_ contain subtests with
+                            // `TIMEOUT` and `NOTRUN`, so we shouldn't actually miss anything.
+                            TestOutcome::Timeout => (),
+                            TestOutcome::Crash => receiver(&mut |analysis| {
+                                insert_in_test_set(
+                                    &mut analysis.tests_with_crashes,
+                                    test_name,
+                                    expected,
+                                    outcome,
+                                )
+                            }),
+                            TestOutcome::Error => receiver(&mut |analysis| {
+                                insert_in_test_set(
+                                    &mut analysis.tests_with_runner_errors,
+                                    test_name,
+                                    expected,
+                                    outcome,
+                                )
+                            }),
+                            TestOutcome::Skip => receiver(&mut |analysis| {
+                                insert_in_test_set(
+                                    &mut analysis.tests_with_disabled_or_skip,
+                                    test_name,
+                                    expected,
+                                    outcome,
+                                )
+                            }),
+                        }
+                    }
+                }
+
+                let apply_to_specific_platforms = |analysis: &mut Analysis, platform, expected| {
+                    analyze_test_outcome(&test_name, expected, |f| {
+                        analysis.for_platform_mut(platform, f)
+                    })
+                };
+
+                for ((platform, _build_profile), expected) in expected.iter() {
+                    apply_to_specific_platforms(&mut analysis, platform, expected)
+                }
+            }
+
+            for (subtest_name, subtest) in subtests {
+                let SectionHeader(subtest_name) = subtest_name;
+                let subtest_name = Arc::new(subtest_name);
+
+                let Subtest { properties } = subtest;
+                for platform in [
+                    Platform::Windows,
+                    Platform::Linux,
+                    Platform::MacOs,
+                    Platform::Android,
+                ] {
+                    if let Some(reason) = disabled_reason(&properties, platform) {
+                        record_disabled(&mut analysis, platform, &test_name, reason);
+                    }
+                }
+
+                let TestProps {
+                    is_disabled: _,
+                    disabled_until: _,
+                    keep_expectations: _,
+                    quarantined: _,
+                    bug: _,
+                    expected,
+                    expected_by_variant: _,
+                    verbatim_expected: _,
+                } = properties;
+
+                if let Some(expected) = expected {
+                    fn analyze_subtest_outcome<Fo>(
+                        test_name: &Arc<String>,
+                        subtest_name: &Arc<String>,
+                        expected: Expected<SubtestOutcome>,
+                        mut receiver: Fo,
+                    ) where
+                        Fo: FnMut(&mut dyn FnMut(&mut PerPlatformAnalysis)),
+                    {
+                        for outcome in expected.iter() {
+                            match outcome {
+                                SubtestOutcome::Pass => (),
+                                SubtestOutcome::Timeout | SubtestOutcome::NotRun => {
+                                    receiver(&mut |analysis| {
+                                        insert_in_subtest_by_test_set(
+                                            &mut analysis.subtests_with_timeouts_by_test,
+                                            test_name,
+                                            subtest_name,
+                                            expected,
+                                            outcome,
+                                        )
+                                    })
+                                }
+                                SubtestOutcome::Crash => receiver(&mut |analysis| {
+                                    insert_in_test_set(
+                                        &mut analysis.tests_with_crashes,
+                                        test_name,
+                                        expected,
+                                        outcome,
+                                    )
+                                }),
+                                SubtestOutcome::Fail => receiver(&mut |analysis| {
+                                    insert_in_subtest_by_test_set(
+                                        &mut analysis.subtests_with_failures_by_test,
+                                        test_name,
+                                        subtest_name,
+                                        expected,
+                                        outcome,
+                                    )
+                                }),
+                            }
+                        }
+                    }
+
+                    let apply_to_specific_platforms =
+                        |analysis: &mut Analysis, platform, expected| {
+                            analyze_subtest_outcome(&test_name, &subtest_name, expected, |f| {
+                                analysis.for_platform_mut(platform, f)
+                            })
+                        };
+
+                    for ((platform, _build_profile), expected) in expected.iter() {
+                        apply_to_specific_platforms(&mut analysis, platform, expected)
+                    }
+                }
+            }
+        }
+        analysis
+    }
+
+    pub fn for_each_platform_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut PerPlatformAnalysis),
+    {
+        let Self {
+            windows,
+            linux,
+            mac_os,
+            android,
+        } = self;
+        for analysis in [windows, linux, mac_os, android] {
+            f(analysis)
+        }
+    }
+
+    pub fn for_each_platform<F>(&self, mut f: F)
+    where
+        F: FnMut(Platform, &PerPlatformAnalysis),
+    {
+        let Self {
+            windows,
+            linux,
+            mac_os,
+            android,
+        } = self;
+        for (platform, analysis) in [
+            (Platform::Windows, windows),
+            (Platform::Linux, linux),
+            (Platform::MacOs, mac_os),
+            (Platform::Android, android),
+        ] {
+            f(platform, analysis)
+        }
+    }
+
+    pub fn for_platform_mut<F>(&mut self, platform: Platform, mut f: F)
+    where
+        F: FnMut(&mut PerPlatformAnalysis),
+    {
+        match platform {
+            Platform::Windows => f(&mut self.windows),
+            Platform::Linux => f(&mut self.linux),
+            Platform::MacOs => f(&mut self.mac_os),
+            Platform::Android => f(&mut self.android),
+        }
+    }
+}
+
+/// Returns the reason `properties` are `disabled` on `platform` with either build profile, if
+/// any.
+fn disabled_reason<Out>(
+    properties: &TestProps<Out>,
+    platform: Platform,
+) -> Option<DisabledReason<'_>>
+where
+    Out: Default + EnumSetType,
+{
+    [BuildProfile::Debug, BuildProfile::Optimized]
+        .into_iter()
+        .find_map(|build_profile| properties.disabled_reason_on(platform, build_profile))
+}
+
+fn record_disabled(
+    analysis: &mut Analysis,
+    platform: Platform,
+    test_name: &Arc<String>,
+    reason: DisabledReason<'_>,
+) {
+    analysis.for_platform_mut(platform, |analysis| {
+        analysis
+            .tests_with_disabled_or_skip
+            .perma
+            .insert(test_name.clone());
+        analysis
+            .tests_by_disabled_reason
+            .entry(reason.into_owned())
+            .or_default()
+            .insert(test_name.clone());
+    });
+}
+
+#[test]
+fn analyze_metadata() {
+    use whippit::reexport::chumsky::Parser as _;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_dir = gecko_checkout.join("testing/web-platform/mozilla/meta/webgpu");
+    let parse = |contents: &str| File::parser().parse(contents).into_result().unwrap();
+    let files = [(
+        Arc::new(meta_dir.join("cts/webgpu/cts.https.html.ini")),
+        parse(concat!(
+            "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+            "  expected:\n",
+            "    if os == \"win\": CRASH\n",
+            "  [:a]\n",
+            "    expected: [PASS, FAIL]\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,operation,buffers:*]\n",
+            "  [:b]\n",
+            "    expected: TIMEOUT\n",
+        )),
+    )];
+    let parse_dir = |contents: &str| DirFile::parser().parse(contents).into_result().unwrap();
+    let dir_files = [
+        (
+            Arc::new(meta_dir.join("cts/__dir__.ini")),
+            parse_dir("tags: [webgpu]\n"),
+        ),
+        (
+            Arc::new(meta_dir.join("cts/webgpu/__dir__.ini")),
+            parse_dir("disabled:\n  if os == \"mac\": https://bugzilla.mozilla.org/123\n"),
+        ),
+    ];
+
+    let dir_props = inherited_dir_props(dir_files);
+    assert_eq!(dir_props.len(), 1);
+    let analysis = Analysis::new(
+        tests_by_name(gecko_checkout, files, &[]),
+        &dir_props,
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+        14,
+        &SeverityOrder::default(),
+    );
+    assert!(analysis.mac_os.tests_by_most_severe_outcome.is_empty());
+    assert!(!analysis.linux.tests_by_most_severe_outcome.is_empty());
+
+    let mut issues = BTreeMap::new();
+    analysis.for_each_platform(|platform, analysis| {
+        issues.insert(platform, analysis.issues_by_test());
+    });
+    insta::assert_debug_snapshot!(issues, @r###"
+    {
+        Windows: {
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*": [
+                PermaCrash,
+                IntermittentFail,
+            ],
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*": [
+                PermaTimeout,
+            ],
+        },
+        Linux: {
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*": [
+                IntermittentFail,
+            ],
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*": [
+                PermaTimeout,
+            ],
+        },
+        MacOs: {
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*": [
+                DisabledDir,
+                IntermittentFail,
+            ],
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*": [
+                DisabledDir,
+                PermaTimeout,
+            ],
+        },
+        Android: {
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*": [
+                IntermittentFail,
+            ],
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*": [
+                PermaTimeout,
+            ],
+        },
+    }
+    "###);
+
+    let mut areas = BTreeMap::new();
+    analysis.for_each_platform(|platform, analysis| {
+        areas.insert(platform, analysis.issues_by_cts_area(2));
+    });
+    insta::assert_debug_snapshot!(areas[&Platform::Windows], @r###"
+    [
+        (
+            Some(
+                CtsQuery {
+                    suite: "webgpu",
+                    file_path: [
+                        "api",
+                        "operation",
+                    ],
+                    test_path: None,
+                    params: None,
+                    wildcard: true,
+                },
+            ),
+            CtsAreaIssues {
+                num_tests: 2,
+                num_tests_by_issue: {
+                    PermaCrash: 1,
+                    IntermittentFail: 1,
+                    PermaTimeout: 1,
+                },
+            },
+        ),
+    ]
+    "###);
+    assert_eq!(
+        cts_area(
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,validation,render_pass:a:*",
+            1
+        )
+        .map(|area| area.to_string())
+        .as_deref(),
+        Some("webgpu:api,*")
+    );
+    assert_eq!(cts_area("_mozilla/webgpu/foo.https.html", 1), None);
+
+    assert!(Issue::PermaTimeout.is_perma());
+    assert!(!Issue::IntermittentTimeout.is_perma());
+    assert_eq!(Issue::OverdueQuarantine.to_string(), "overdue-quarantine");
+    assert_eq!(
+        serde_json::to_value(Issue::OverdueQuarantine).unwrap(),
+        "overdue-quarantine"
+    );
+}
+
+#[test]
+fn analyze_inherited_expected() {
+    use whippit::reexport::chumsky::Parser as _;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_dir = gecko_checkout.join("testing/web-platform/mozilla/meta/webgpu");
+    let files = [(
+        Arc::new(meta_dir.join("cts/webgpu/cts.https.html.ini")),
+        File::parser()
+            .parse(concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  expected: TIMEOUT\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,operation,buffers:*]\n",
+                "  [:b]\n",
+                "    expected: FAIL\n",
+            ))
+            .into_result()
+            .unwrap(),
+    )];
+    let dir_files = [(
+        Arc::new(meta_dir.join("cts/__dir__.ini")),
+        DirFile::parser()
+            .parse("expected:\n  if os == \"linux\": CRASH\n")
+            .into_result()
+            .unwrap(),
+    )];
+
+    let severity = SeverityOrder::default();
+    let analysis = Analysis::new(
+        tests_by_name(gecko_checkout, files, &[]),
+        &inherited_dir_props(dir_files),
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+        14,
+        &severity,
+    );
+
+    // N.B.: Only `buffers` has no `expected` of its own to override that of its directory.
+    let crash = severity.rank_test_outcome(TestOutcome::Crash).unwrap();
+    assert_eq!(
+        analysis.linux.tests_by_most_severe_outcome[&crash]
+            .iter()
+            .map(|test_name| test_name.as_str())
+            .collect::<Vec<_>>(),
+        ["_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*"]
+    );
+    assert!(!analysis
+        .windows
+        .tests_by_most_severe_outcome
+        .contains_key(&crash));
+}
+
+#[test]
+fn analyze_filtered() {
+    use whippit::reexport::chumsky::Parser as _;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_dir = gecko_checkout.join("testing/web-platform/mozilla/meta/webgpu");
+    let files = [(
+        Arc::new(meta_dir.join("cts/webgpu/cts.https.html.ini")),
+        File::parser()
+            .parse(concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  expected: CRASH\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,operation,buffers:*]\n",
+                "  expected: CRASH\n",
+                "\n",
+                "[cts.https.html?q=webgpu:shader,execution,expression:*]\n",
+                "  expected: CRASH\n",
+            ))
+            .into_result()
+            .unwrap(),
+    )];
+
+    // N.B.: Tests matching any filter are kept, whether it's a CTS query or a test URL path.
+    let filters = [
+        "webgpu:api,operation,buffers:*".parse().unwrap(),
+        "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:shader,*"
+            .parse()
+            .unwrap(),
+    ];
+    let analysis = Analysis::new(
+        tests_by_name(gecko_checkout, files, &filters),
+        &[],
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+        14,
+        &SeverityOrder::default(),
+    );
+    assert_eq!(
+        analysis
+            .linux
+            .issues_by_test()
+            .into_keys()
+            .map(|test_name| test_name.to_string())
+            .collect::<Vec<_>>(),
+        [
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*",
+            "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:shader,execution,expression:*",
+        ]
+    );
+}
+
+#[test]
+fn analyze_by_cts_area() {
+    use whippit::reexport::chumsky::Parser as _;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_dir = gecko_checkout.join("testing/web-platform/mozilla/meta/webgpu");
+    let parse = |contents: &str| File::parser().parse(contents).into_result().unwrap();
+    let files = [
+        (
+            Arc::new(meta_dir.join("cts/webgpu/cts.https.html.ini")),
+            parse(concat!(
+                "[cts.https.html?q=webgpu:api,validation,buffer:*]\n",
+                "  expected: CRASH\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,validation,texture:*]\n",
+                "  expected: ERROR\n",
+                "\n",
+                "[cts.https.html?q=webgpu:shader,execution,expression:*]\n",
+                "  expected: [OK, CRASH]\n",
+            )),
+        ),
+        (
+            Arc::new(meta_dir.join("foo.https.html.ini")),
+            parse("[foo.https.html]\n  expected: ERROR\n"),
+        ),
+    ];
+
+    let analysis = Analysis::new(
+        tests_by_name(gecko_checkout, files, &[]),
+        &[],
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+        14,
+        &SeverityOrder::default(),
+    );
+    // N.B.: Areas with as many tests keep their order, with tests outside the CTS first.
+    let areas = analysis
+        .linux
+        .issues_by_cts_area(2)
+        .into_iter()
+        .map(|(area, issues)| (area.map(|area| area.to_string()), issues))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        areas
+            .iter()
+            .map(|(area, issues)| (area.as_deref(), issues.num_tests))
+            .collect::<Vec<_>>(),
+        [
+            (Some("webgpu:api,validation,*"), 2),
+            (None, 1),
+            (Some("webgpu:shader,execution,*"), 1),
+        ]
+    );
+    assert_eq!(
+        areas[0].1.num_tests_by_issue,
+        BTreeMap::from([(Issue::PermaError, 1), (Issue::PermaCrash, 1)])
+    );
+    assert_eq!(
+        areas[2].1.num_tests_by_issue,
+        BTreeMap::from([(Issue::IntermittentCrash, 1)])
+    );
+}
+
+#[test]
+fn analyze_android() {
+    use whippit::reexport::chumsky::Parser as _;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_dir = gecko_checkout.join("testing/web-platform/mozilla/meta/webgpu");
+    let files = [(
+        Arc::new(meta_dir.join("cts/webgpu/cts.https.html.ini")),
+        File::parser()
+            .parse(concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  expected:\n",
+                "    if os == \"android\": CRASH\n",
+                "  [:a]\n",
+                "    expected:\n",
+                "      if os == \"android\": [PASS, TIMEOUT]\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,operation,buffers:*]\n",
+                "  disabled:\n",
+                "    if os == \"android\": https://bugzilla.mozilla.org/show_bug.cgi?id=123\n",
+            ))
+            .into_result()
+            .unwrap(),
+    )];
+
+    let analysis = Analysis::new(
+        tests_by_name(gecko_checkout, files, &[]),
+        &[],
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+        14,
+        &SeverityOrder::default(),
+    );
+    let adapter = "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*";
+    let buffers = "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*";
+    assert_eq!(
+        analysis
+            .android
+            .issues_by_test()
+            .into_iter()
+            .map(|(test_name, issues)| (test_name.to_string(), issues))
+            .collect::<Vec<_>>(),
+        [
+            (
+                adapter.to_owned(),
+                vec![Issue::PermaCrash, Issue::IntermittentTimeout]
+            ),
+            (buffers.to_owned(), vec![Issue::Disabled]),
+        ]
+    );
+    assert_eq!(
+        analysis
+            .android
+            .tests_by_disabled_reason
+            .iter()
+            .map(|(reason, tests)| (reason.to_string(), tests.len()))
+            .collect::<Vec<_>>(),
+        [(
+            "https://bugzilla.mozilla.org/show_bug.cgi?id=123".to_owned(),
+            1
+        )]
+    );
+    analysis.for_each_platform(|platform, analysis| {
+        if platform != Platform::Android {
+            assert!(analysis.issues_by_test().is_empty(), "{platform:?}");
+        }
+    });
+}
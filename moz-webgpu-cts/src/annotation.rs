@@ -0,0 +1,204 @@
+//! Findings tied to locations in metadata files, rendered for CI systems to annotate.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use format::lazy_format;
+use serde_json::json;
+
+use crate::error::Error;
+
+/// The format in which [`Annotation`]s are printed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum AnnotationFormat {
+    /// [GitHub Actions workflow commands], i.e., `::error file=…,line=…::message`.
+    ///
+    /// [GitHub Actions workflow commands]: https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions
+    Github,
+    /// [Reviewdog Diagnostic Format], one diagnostic per line (`rdjsonl`).
+    ///
+    /// [Reviewdog Diagnostic Format]: https://github.com/reviewdog/reviewdog/tree/master/proto/rdf
+    Reviewdog,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AnnotationSeverity {
+    Error,
+    Warning,
+}
+
+/// A finding at a location in a file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Annotation {
+    pub path: PathBuf,
+    /// 1-based.
+    pub line: usize,
+    /// 1-based. `None` if the finding applies to a whole line.
+    pub column: Option<usize>,
+    pub severity: AnnotationSeverity,
+    pub message: String,
+}
+
+impl Annotation {
+    /// Returns annotations for each location `error` points to. Errors without a location in a
+    /// file yield none.
+    pub fn from_error(error: &Error) -> Vec<Self> {
+        match error {
+            Error::ParseMetadata { path, errors } => errors
+                .iter()
+                .map(|error| {
+                    let (line, column) = error.line_and_column();
+                    Self {
+                        path: path.to_path_buf(),
+                        line,
+                        column: Some(column),
+                        severity: AnnotationSeverity::Error,
+                        message: format!("failed to parse metadata: {error}"),
+                    }
+                })
+                .collect(),
+            Error::ParseNeverModifyList { path, line_num, .. } => vec![Self {
+                path: path.clone(),
+                line: *line_num,
+                column: None,
+                severity: AnnotationSeverity::Error,
+                message: error.to_string(),
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Renders this as a single line in `format`. `base` is stripped from [`Self::path`], if it is
+    /// a prefix, since CI systems expect paths relative to the root of the repository.
+    pub fn render<'a>(&'a self, format: AnnotationFormat, base: &'a Path) -> impl Display + 'a {
+        lazy_format!(move |f| {
+            let Self {
+                path,
+                line,
+                column,
+                severity,
+                message,
+            } = self;
+            let path = path.strip_prefix(base).unwrap_or(path);
+            let path = path.to_str().unwrap();
+            match format {
+                AnnotationFormat::Github => {
+                    let command = match severity {
+                        AnnotationSeverity::Error => "error",
+                        AnnotationSeverity::Warning => "warning",
+                    };
+                    write!(
+                        f,
+                        "::{command} file={},line={line}",
+                        GithubEscaped {
+                            text: path,
+                            is_property: true
+                        }
+                    )?;
+                    if let Some(column) = column {
+                        write!(f, ",col={column}")?;
+                    }
+                    write!(
+                        f,
+                        "::{}",
+                        GithubEscaped {
+                            text: message,
+                            is_property: false
+                        }
+                    )
+                }
+                AnnotationFormat::Reviewdog => {
+                    let mut start = json!({ "line": line });
+                    if let Some(column) = column {
+                        start["column"] = json!(column);
+                    }
+                    let severity = match severity {
+                        AnnotationSeverity::Error => "ERROR",
+                        AnnotationSeverity::Warning => "WARNING",
+                    };
+                    let diagnostic = json!({
+                        "message": message,
+                        "location": {
+                            "path": path,
+                            "range": { "start": start },
+                        },
+                        "severity": severity,
+                        "source": { "name": "moz-webgpu-cts" },
+                    });
+                    write!(f, "{diagnostic}")
+                }
+            }
+        })
+    }
+}
+
+/// Escapes text for use in a GitHub Actions workflow command, either as a property value or as
+/// the message.
+struct GithubEscaped<'a> {
+    text: &'a str,
+    is_property: bool,
+}
+
+impl Display for GithubEscaped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self { text, is_property } = self;
+        for c in text.chars() {
+            match c {
+                '%' => f.write_str("%25")?,
+                '\r' => f.write_str("%0D")?,
+                '\n' => f.write_str("%0A")?,
+                ':' if *is_property => f.write_str("%3A")?,
+                ',' if *is_property => f.write_str("%2C")?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the 1-based number of the first line that differs between `old` and `new`, or `None` if
+/// they are identical.
+pub fn first_differing_line(old: &str, new: &str) -> Option<usize> {
+    if old == new {
+        return None;
+    }
+    let mut old_lines = old.lines();
+    let mut new_lines = new.lines();
+    let mut line_num = 1usize;
+    loop {
+        match (old_lines.next(), new_lines.next()) {
+            (Some(old), Some(new)) if old == new => line_num += 1,
+            // N.B.: Only line endings differ if both run out at once.
+            (None, None) => return Some(line_num.saturating_sub(1).max(1)),
+            _ => return Some(line_num),
+        }
+    }
+}
+
+#[test]
+fn render() {
+    let annotation = Annotation {
+        path: PathBuf::from("/gecko/testing/meta/a,b.ini"),
+        line: 3,
+        column: Some(5),
+        severity: AnnotationSeverity::Error,
+        message: "100% broken:\nsee below".to_owned(),
+    };
+    let base = Path::new("/gecko");
+    insta::assert_snapshot!(
+        annotation.render(AnnotationFormat::Github, base),
+        @"::error file=testing/meta/a%2Cb.ini,line=3,col=5::100%25 broken:%0Asee below"
+    );
+    insta::assert_snapshot!(
+        annotation.render(AnnotationFormat::Reviewdog, base),
+        @r###"{"location":{"path":"testing/meta/a,b.ini","range":{"start":{"column":5,"line":3}}},"message":"100% broken:\nsee below","severity":"ERROR","source":{"name":"moz-webgpu-cts"}}"###
+    );
+
+    assert_eq!(first_differing_line("a\nb\n", "a\nb\n"), None);
+    assert_eq!(first_differing_line("a\nb\n", "a\nc\n"), Some(2));
+    assert_eq!(first_differing_line("a\nb\n", "a\nb\nc\n"), Some(3));
+    assert_eq!(first_differing_line("a\nb", "a\nb\n"), Some(2));
+}
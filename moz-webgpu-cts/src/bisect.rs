@@ -0,0 +1,820 @@
+//! Comparison of two sets of reports, i.e., from CI runs before and after a regression landed, to
+//! pinpoint the tests whose behavior changed between them, or to tally how outcomes changed
+//! overall.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+};
+
+use enumset::EnumSetType;
+use format::lazy_format;
+
+use crate::{
+    error::Error,
+    metadata::{BuildProfile, Platform, SubtestOutcome, TestOutcome},
+    report::{ExecutionReport, RunInfo, TestExecutionEntry, TestExecutionResult},
+    shared::{Browser, CtsQuery, Expected, OutputFormat, TestPath},
+};
+
+/// The result of [`bisect`].
+#[derive(Debug, Default)]
+pub struct Bisection {
+    pub num_good_runs: usize,
+    pub num_bad_runs: usize,
+    /// Changed outcomes, keyed by the [group](test_group) of their test, then their platform.
+    pub changes: BTreeMap<String, BTreeMap<Platform, Vec<OutcomeChange>>>,
+    /// Configurations that only one of the sets of reports covered, which were not compared.
+    pub uncompared: BTreeSet<(Platform, BuildProfile)>,
+}
+
+/// A test or subtest whose outcomes differed between good and bad runs in a single configuration.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OutcomeChange {
+    pub test: TestPath<'static>,
+    pub subtest: Option<String>,
+    pub build_profile: BuildProfile,
+    /// The outcomes observed in good runs, as they would be written in metadata, or `None` if the
+    /// test or subtest was not run.
+    pub good: Option<String>,
+    /// Like `good`, but for bad runs.
+    pub bad: Option<String>,
+}
+
+/// The result of [`transitions`].
+#[derive(Debug, Default)]
+pub struct Transitions {
+    pub num_before_runs: usize,
+    pub num_after_runs: usize,
+    /// How many tests went from one outcome to another in a configuration, keyed by platform.
+    pub tests: BTreeMap<Platform, TransitionCounts>,
+    /// Like `tests`, but for subtests.
+    pub subtests: BTreeMap<Platform, TransitionCounts>,
+    /// Configurations that only one of the sets of reports covered, which were not compared.
+    pub uncompared: BTreeSet<(Platform, BuildProfile)>,
+}
+
+/// Counts keyed by outcomes before and after, as they would be written in metadata, or `None`
+/// where a test or subtest was not run. Unchanged outcomes are counted, too.
+pub type TransitionCounts = BTreeMap<(Option<String>, Option<String>), usize>;
+
+type Outcomes<Out> = BTreeMap<(Platform, BuildProfile), Expected<Out>>;
+
+/// Outcomes accumulated from one set of reports.
+///
+/// N.B.: `wptrunner` subsuites are not distinguished, since a run's subsuite is not expected to
+/// change between the builds being compared.
+#[derive(Debug, Default)]
+struct Observed {
+    num_runs: usize,
+    covered: BTreeSet<(Platform, BuildProfile)>,
+    #[allow(clippy::type_complexity)]
+    tests: BTreeMap<
+        TestPath<'static>,
+        (
+            Outcomes<TestOutcome>,
+            BTreeMap<String, Outcomes<SubtestOutcome>>,
+        ),
+    >,
+}
+
+impl Observed {
+    fn from_reports<'a>(
+        browser: Browser,
+        exec_reports: impl IntoIterator<Item = Result<ExecutionReport<'a>, Error>>,
+    ) -> Result<Self, Error> {
+        let mut observed = Self::default();
+        for exec_report in exec_reports {
+            let ExecutionReport {
+                run_info:
+                    RunInfo {
+                        platform,
+                        build_profile,
+                        subsuite: _,
+                        properties: _,
+                    },
+                entries,
+                unrecognized: _,
+            } = exec_report?;
+            let config = (platform, build_profile);
+            observed.num_runs += 1;
+            observed.covered.insert(config);
+
+            fn accumulate<Out>(
+                outcomes: &mut Outcomes<Out>,
+                config: (Platform, BuildProfile),
+                outcome: Out,
+            ) where
+                Out: EnumSetType,
+            {
+                outcomes
+                    .entry(config)
+                    .and_modify(|expected| *expected |= outcome)
+                    .or_insert_with(|| Expected::permanent(outcome));
+            }
+
+            for entry in entries {
+                let TestExecutionEntry {
+                    test_name,
+                    subsuite: _,
+                    message: _,
+                    result,
+                } = entry;
+                let test_path = TestPath::from_execution_report(browser, &test_name)
+                    .unwrap()
+                    .into_owned();
+                let (outcome, subtests) = match result {
+                    TestExecutionResult::Complete { outcome, subtests } => (outcome, subtests),
+                    TestExecutionResult::JobMaybeTimedOut { subtests } => {
+                        (TestOutcome::Timeout, subtests)
+                    }
+                };
+                let (test_outcomes, subtest_outcomes) =
+                    observed.tests.entry(test_path).or_default();
+                accumulate(test_outcomes, config, outcome);
+                for subtest in subtests {
+                    let outcomes = subtest_outcomes
+                        .entry(subtest.subtest_name.into_owned())
+                        .or_default();
+                    accumulate(outcomes, config, subtest.outcome);
+                }
+            }
+        }
+        Ok(observed)
+    }
+}
+
+/// Compares outcomes in `good_reports` against those in `bad_reports`, in every configuration
+/// that both cover. Outcomes from several runs of the same configuration are merged, as with
+/// intermittent outcomes in metadata.
+///
+/// The first error yielded by either set of reports is returned immediately. Tests are resolved
+/// as run in `browser`.
+pub fn bisect<'a, 'b>(
+    browser: Browser,
+    good_reports: impl IntoIterator<Item = Result<ExecutionReport<'a>, Error>>,
+    bad_reports: impl IntoIterator<Item = Result<ExecutionReport<'b>, Error>>,
+) -> Result<Bisection, Error> {
+    let good = Observed::from_reports(browser, good_reports)?;
+    let bad = Observed::from_reports(browser, bad_reports)?;
+
+    let compared = good
+        .covered
+        .intersection(&bad.covered)
+        .copied()
+        .collect::<BTreeSet<_>>();
+    let uncompared = good
+        .covered
+        .symmetric_difference(&bad.covered)
+        .copied()
+        .collect();
+
+    let mut changes = BTreeMap::<_, BTreeMap<_, Vec<_>>>::new();
+    let test_paths = good
+        .tests
+        .keys()
+        .chain(bad.tests.keys())
+        .collect::<BTreeSet<_>>();
+    for test_path in test_paths {
+        let good = good.tests.get(test_path);
+        let bad = bad.tests.get(test_path);
+        let mut record = |subtest: Option<&String>, (platform, build_profile), good, bad| {
+            changes
+                .entry(test_group(test_path))
+                .or_default()
+                .entry(platform)
+                .or_default()
+                .push(OutcomeChange {
+                    test: test_path.clone(),
+                    subtest: subtest.cloned(),
+                    build_profile,
+                    good,
+                    bad,
+                })
+        };
+
+        diff(
+            good.map(|(outcomes, _)| outcomes),
+            bad.map(|(outcomes, _)| outcomes),
+            &compared,
+            |config, good, bad| record(None, config, good, bad),
+        );
+
+        let subtest_names = good
+            .into_iter()
+            .chain(bad)
+            .flat_map(|(_, subtests)| subtests.keys())
+            .collect::<BTreeSet<_>>();
+        for subtest_name in subtest_names {
+            diff(
+                good.and_then(|(_, subtests)| subtests.get(subtest_name)),
+                bad.and_then(|(_, subtests)| subtests.get(subtest_name)),
+                &compared,
+                |config, good, bad| record(Some(subtest_name), config, good, bad),
+            );
+        }
+    }
+
+    Ok(Bisection {
+        num_good_runs: good.num_runs,
+        num_bad_runs: bad.num_runs,
+        changes,
+        uncompared,
+    })
+}
+
+/// Calls `f` with the rendered outcomes for each configuration in `compared` whose outcomes
+/// differ between `good` and `bad`.
+fn diff<Out>(
+    good: Option<&Outcomes<Out>>,
+    bad: Option<&Outcomes<Out>>,
+    compared: &BTreeSet<(Platform, BuildProfile)>,
+    mut f: impl FnMut((Platform, BuildProfile), Option<String>, Option<String>),
+) where
+    Out: Display + EnumSetType,
+{
+    for config in compared {
+        let good = good.and_then(|outcomes| outcomes.get(config));
+        let bad = bad.and_then(|outcomes| outcomes.get(config));
+        if good != bad {
+            f(
+                *config,
+                good.map(ToString::to_string),
+                bad.map(ToString::to_string),
+            );
+        }
+    }
+}
+
+/// Tallies transitions of outcomes from `before_reports` to `after_reports`, in every
+/// configuration that both cover, to quantify the net effect of a change to Firefox or the CTS.
+/// Outcomes are merged as with [`bisect`], and transitions summed over build profiles.
+pub fn transitions<'a, 'b>(
+    browser: Browser,
+    before_reports: impl IntoIterator<Item = Result<ExecutionReport<'a>, Error>>,
+    after_reports: impl IntoIterator<Item = Result<ExecutionReport<'b>, Error>>,
+) -> Result<Transitions, Error> {
+    let before = Observed::from_reports(browser, before_reports)?;
+    let after = Observed::from_reports(browser, after_reports)?;
+
+    let compared = before
+        .covered
+        .intersection(&after.covered)
+        .copied()
+        .collect::<BTreeSet<_>>();
+    let uncompared = before
+        .covered
+        .symmetric_difference(&after.covered)
+        .copied()
+        .collect();
+
+    fn tally<Out>(
+        counts: &mut BTreeMap<Platform, TransitionCounts>,
+        before: Option<&Outcomes<Out>>,
+        after: Option<&Outcomes<Out>>,
+        compared: &BTreeSet<(Platform, BuildProfile)>,
+    ) where
+        Out: Display + EnumSetType,
+    {
+        for config @ (platform, _build_profile) in compared {
+            let before = before.and_then(|outcomes| outcomes.get(config));
+            let after = after.and_then(|outcomes| outcomes.get(config));
+            if before.is_none() && after.is_none() {
+                continue;
+            }
+            *counts
+                .entry(*platform)
+                .or_default()
+                .entry((
+                    before.map(ToString::to_string),
+                    after.map(ToString::to_string),
+                ))
+                .or_default() += 1;
+        }
+    }
+
+    let mut tests = BTreeMap::new();
+    let mut subtests = BTreeMap::new();
+    let test_paths = before
+        .tests
+        .keys()
+        .chain(after.tests.keys())
+        .collect::<BTreeSet<_>>();
+    for test_path in test_paths {
+        let before = before.tests.get(test_path);
+        let after = after.tests.get(test_path);
+        tally(
+            &mut tests,
+            before.map(|(outcomes, _)| outcomes),
+            after.map(|(outcomes, _)| outcomes),
+            &compared,
+        );
+
+        let subtest_names = before
+            .into_iter()
+            .chain(after)
+            .flat_map(|(_, subtests)| subtests.keys())
+            .collect::<BTreeSet<_>>();
+        for subtest_name in subtest_names {
+            tally(
+                &mut subtests,
+                before.and_then(|(_, subtests)| subtests.get(subtest_name)),
+                after.and_then(|(_, subtests)| subtests.get(subtest_name)),
+                &compared,
+            );
+        }
+    }
+
+    Ok(Transitions {
+        num_before_runs: before.num_runs,
+        num_after_runs: after.num_runs,
+        tests,
+        subtests,
+        uncompared,
+    })
+}
+
+/// The group under which changes to `test_path` are listed: for CTS tests, the CTS file that its
+/// query belongs to (i.e., `webgpu:api,operation,adapter`), and otherwise the directory that the
+/// test is in.
+pub fn test_group(test_path: &TestPath<'_>) -> String {
+    let cts_file = test_path
+        .variant
+        .as_deref()
+        .and_then(|variant| variant.strip_prefix("?q="))
+        .filter(|query| query.starts_with("webgpu:"))
+        .and_then(|query| query.parse::<CtsQuery>().ok())
+        .map(|query| query.file().to_string());
+    cts_file.unwrap_or_else(|| {
+        let dir = TestPath {
+            browser: test_path.browser,
+            scope: test_path.scope.clone(),
+            path: test_path.path.parent().unwrap_or(&test_path.path).into(),
+            variant: None,
+        };
+        format!("/{}", dir.runner_url_path())
+    })
+}
+
+impl Bisection {
+    /// Renders this for pasting into a regression bug, as Markdown, or into a Phabricator
+    /// revision, as Remarkup, where changes are tabulated.
+    pub fn render(&self, format: OutputFormat) -> impl Display + '_ {
+        lazy_format!(move |f| self.fmt_as(f, format))
+    }
+
+    fn fmt_as(&self, f: &mut Formatter<'_>, format: OutputFormat) -> fmt::Result {
+        let Self {
+            num_good_runs,
+            num_bad_runs,
+            changes,
+            uncompared,
+        } = self;
+
+        writeln!(
+            f,
+            "Compared {num_good_runs} good run(s) against {num_bad_runs} bad run(s)."
+        )?;
+        if !uncompared.is_empty() {
+            write!(f, "Configurations not covered by both sets of runs:")?;
+            for (idx, (platform, build_profile)) in uncompared.iter().enumerate() {
+                let sep = if idx == 0 { " " } else { ", " };
+                write!(
+                    f,
+                    "{sep}{} {}",
+                    platform.display_name(),
+                    build_profile.name()
+                )?;
+            }
+            writeln!(f)?;
+        }
+        if changes.is_empty() {
+            writeln!(f)?;
+            return writeln!(f, "No changed outcomes found.");
+        }
+
+        let outcome = |outcome: &Option<String>| match outcome {
+            Some(outcome) => format!("`{outcome}`"),
+            None => "not run".to_owned(),
+        };
+        for (group, by_platform) in changes {
+            writeln!(f)?;
+            match format {
+                OutputFormat::Text | OutputFormat::Markdown => writeln!(f, "## `{group}`")?,
+                OutputFormat::Remarkup => writeln!(f, "== `{group}` ==")?,
+            }
+            for (platform, changes) in by_platform {
+                let platform = platform.display_name();
+                writeln!(f)?;
+                match format {
+                    OutputFormat::Text | OutputFormat::Markdown => writeln!(f, "### {platform}")?,
+                    OutputFormat::Remarkup => {
+                        writeln!(f, "=== {platform} ===")?;
+                        writeln!(f)?;
+                        writeln!(f, "| Test | Subtest | Build | Good | Bad |")?;
+                        writeln!(f, "| ---- | ------- | ----- | ---- | --- |")?;
+                    }
+                }
+                if format != OutputFormat::Remarkup {
+                    writeln!(f)?;
+                }
+                for change in changes {
+                    let OutcomeChange {
+                        test,
+                        subtest,
+                        build_profile,
+                        good,
+                        bad,
+                    } = change;
+                    let build_profile = build_profile.name();
+                    let (good, bad) = (outcome(good), outcome(bad));
+                    match format {
+                        OutputFormat::Text | OutputFormat::Markdown => {
+                            write!(f, "* `{}`", test.test_name())?;
+                            if let Some(subtest) = subtest {
+                                write!(f, " › `{subtest}`")?;
+                            }
+                            writeln!(f, " ({build_profile}): {good} → {bad}")?;
+                        }
+                        OutputFormat::Remarkup => {
+                            let subtest = subtest
+                                .as_ref()
+                                .map(|subtest| format!("`{subtest}`"))
+                                .unwrap_or_default();
+                            writeln!(
+                                f,
+                                "| `{}` | {subtest} | {build_profile} | {good} | {bad} |",
+                                test.test_name()
+                            )?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Bisection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_as(f, OutputFormat::Text)
+    }
+}
+
+impl Display for Transitions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            num_before_runs,
+            num_after_runs,
+            tests,
+            subtests,
+            uncompared,
+        } = self;
+
+        writeln!(
+            f,
+            "Compared {num_before_runs} run(s) before against {num_after_runs} run(s) after."
+        )?;
+        if !uncompared.is_empty() {
+            write!(f, "Configurations not covered by both sets of runs:")?;
+            for (idx, (platform, build_profile)) in uncompared.iter().enumerate() {
+                let sep = if idx == 0 { " " } else { ", " };
+                write!(
+                    f,
+                    "{sep}{} {}",
+                    platform.display_name(),
+                    build_profile.name()
+                )?;
+            }
+            writeln!(f)?;
+        }
+        if tests.is_empty() && subtests.is_empty() {
+            writeln!(f)?;
+            return writeln!(f, "No outcomes found in compared configurations.");
+        }
+
+        let platforms = tests.keys().chain(subtests.keys()).collect::<BTreeSet<_>>();
+        for platform in platforms {
+            writeln!(f)?;
+            writeln!(f, "## {}", platform.display_name())?;
+            for (kind, counts) in [("Tests", tests), ("Subtests", subtests)] {
+                if let Some(counts) = counts.get(platform) {
+                    writeln!(f)?;
+                    fmt_matrix(f, kind, counts)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes `counts` as a Markdown table, with a row for each outcome before and a column for each
+/// outcome after, preceded by a summary of how many of the `kind` changed outcomes at all.
+fn fmt_matrix(f: &mut Formatter<'_>, kind: &str, counts: &TransitionCounts) -> fmt::Result {
+    let label = |outcome: &Option<String>| match outcome {
+        Some(outcome) => format!("`{outcome}`"),
+        None => "not run".to_owned(),
+    };
+    // N.B.: "not run" goes last, rather than first, like `None` would.
+    fn sorted<'a>(outcomes: impl Iterator<Item = &'a Option<String>>) -> Vec<&'a Option<String>> {
+        let outcomes = outcomes
+            .map(|outcome| (outcome.is_none(), outcome))
+            .collect::<BTreeSet<_>>();
+        outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+    }
+    let rows = sorted(counts.keys().map(|(before, _after)| before));
+    let columns = sorted(counts.keys().map(|(_before, after)| after));
+
+    let total = counts.values().sum::<usize>();
+    let changed = counts
+        .iter()
+        .filter(|((before, after), _count)| before != after)
+        .map(|(_, count)| count)
+        .sum::<usize>();
+    writeln!(f, "{kind}: {changed} of {total} changed outcome(s)")?;
+    writeln!(f)?;
+
+    let corner = "Before \\ After";
+    let column_labels = columns.iter().map(|after| label(after)).collect::<Vec<_>>();
+    let first_width = rows
+        .iter()
+        .map(|before| label(before).chars().count())
+        .chain([corner.chars().count()])
+        .max()
+        .unwrap();
+    let count = |before: &Option<String>, after: &Option<String>| {
+        counts
+            .get(&(before.clone(), after.clone()))
+            .copied()
+            .unwrap_or(0)
+    };
+    let widths = columns
+        .iter()
+        .zip(&column_labels)
+        .map(|(after, label)| {
+            rows.iter()
+                .map(|before| count(before, after).to_string().len())
+                .chain([label.chars().count()])
+                .max()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    write!(f, "| {corner:first_width$} |")?;
+    for (label, width) in column_labels.iter().zip(&widths) {
+        write!(f, " {label:width$} |")?;
+    }
+    writeln!(f)?;
+    write!(f, "| {} |", "-".repeat(first_width))?;
+    for width in &widths {
+        write!(f, " {}: |", "-".repeat(width - 1))?;
+    }
+    writeln!(f)?;
+    for before in &rows {
+        write!(f, "| {:first_width$} |", label(before))?;
+        for (after, width) in columns.iter().zip(&widths) {
+            write!(f, " {:>width$} |", count(before, after))?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn changes() {
+    use std::path::Path;
+
+    let report = |os: &str, debug: bool, results: &str| {
+        format!(
+            r#"{{
+                "run_info": {{
+                    "os": "{os}",
+                    "processor": "x86_64",
+                    "win11_2009": true,
+                    "debug": {debug}
+                }},
+                "results": {results}
+            }}"#
+        )
+    };
+    let good = [
+        report(
+            "linux",
+            false,
+            r#"[
+                {
+                    "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:info:*",
+                    "status": "OK",
+                    "subtests": [
+                        { "name": ":a", "status": "PASS" },
+                        { "name": ":b", "status": "FAIL" }
+                    ]
+                },
+                {
+                    "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,labels:*",
+                    "status": "OK",
+                    "subtests": [{ "name": ":a", "status": "PASS" }]
+                }
+            ]"#,
+        ),
+        report(
+            "linux",
+            false,
+            r#"[
+                {
+                    "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:info:*",
+                    "status": "OK",
+                    "subtests": [
+                        { "name": ":a", "status": "PASS" },
+                        { "name": ":b", "status": "PASS" }
+                    ]
+                }
+            ]"#,
+        ),
+        report("mac", true, "[]"),
+    ];
+    let bad = [
+        report(
+            "linux",
+            false,
+            r#"[
+                {
+                    "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:info:*",
+                    "status": "OK",
+                    "subtests": [
+                        { "name": ":a", "status": "FAIL" },
+                        { "name": ":b", "status": "FAIL" }
+                    ]
+                },
+                {
+                    "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,labels:*",
+                    "status": "CRASH",
+                    "subtests": []
+                },
+                {
+                    "test": "/_mozilla/webgpu/webgpu/foo.https.html",
+                    "status": "TIMEOUT",
+                    "subtests": []
+                }
+            ]"#,
+        ),
+        report("win", false, "[]"),
+    ];
+    fn parse(contents: &[String]) -> Vec<Result<ExecutionReport<'_>, Error>> {
+        contents
+            .iter()
+            .map(|contents| {
+                ExecutionReport::from_json(
+                    Path::new("wptreport.json"),
+                    contents,
+                    &Default::default(),
+                )
+            })
+            .collect()
+    }
+
+    let bisection = bisect(Browser::Firefox, parse(&good), parse(&bad)).unwrap();
+    insta::assert_snapshot!(bisection, @r###"
+    Compared 3 good run(s) against 2 bad run(s).
+    Configurations not covered by both sets of runs: Windows opt, macOS debug
+
+    ## `/_mozilla/webgpu/webgpu`
+
+    ### Linux
+
+    * `foo.https.html` (opt): not run → `TIMEOUT`
+
+    ## `webgpu:api,operation,adapter`
+
+    ### Linux
+
+    * `cts.https.html?q=webgpu:api,operation,adapter:info:*` › `:a` (opt): `PASS` → `FAIL`
+    * `cts.https.html?q=webgpu:api,operation,adapter:info:*` › `:b` (opt): `[PASS, FAIL]` → `FAIL`
+
+    ## `webgpu:api,operation,labels`
+
+    ### Linux
+
+    * `cts.https.html?q=webgpu:api,operation,labels:*` (opt): `OK` → `CRASH`
+    * `cts.https.html?q=webgpu:api,operation,labels:*` › `:a` (opt): `PASS` → not run
+    "###);
+    insta::assert_snapshot!(bisection.render(OutputFormat::Remarkup), @r###"
+    Compared 3 good run(s) against 2 bad run(s).
+    Configurations not covered by both sets of runs: Windows opt, macOS debug
+
+    == `/_mozilla/webgpu/webgpu` ==
+
+    === Linux ===
+
+    | Test | Subtest | Build | Good | Bad |
+    | ---- | ------- | ----- | ---- | --- |
+    | `foo.https.html` |  | opt | not run | `TIMEOUT` |
+
+    == `webgpu:api,operation,adapter` ==
+
+    === Linux ===
+
+    | Test | Subtest | Build | Good | Bad |
+    | ---- | ------- | ----- | ---- | --- |
+    | `cts.https.html?q=webgpu:api,operation,adapter:info:*` | `:a` | opt | `PASS` | `FAIL` |
+    | `cts.https.html?q=webgpu:api,operation,adapter:info:*` | `:b` | opt | `[PASS, FAIL]` | `FAIL` |
+
+    == `webgpu:api,operation,labels` ==
+
+    === Linux ===
+
+    | Test | Subtest | Build | Good | Bad |
+    | ---- | ------- | ----- | ---- | --- |
+    | `cts.https.html?q=webgpu:api,operation,labels:*` |  | opt | `OK` | `CRASH` |
+    | `cts.https.html?q=webgpu:api,operation,labels:*` | `:a` | opt | `PASS` | not run |
+    "###);
+}
+
+#[test]
+fn transition_counts() {
+    use std::path::Path;
+
+    let report = |os: &str, subtests: &[(&str, &str)]| {
+        let subtests = subtests
+            .iter()
+            .map(|(name, status)| format!(r#"{{ "name": "{name}", "status": "{status}" }}"#))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            r#"{{
+                "run_info": {{
+                    "os": "{os}",
+                    "processor": "x86_64",
+                    "win11_2009": true,
+                    "debug": false
+                }},
+                "results": [
+                    {{
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,a:*",
+                        "status": "OK",
+                        "subtests": [{subtests}]
+                    }}
+                ]
+            }}"#
+        )
+    };
+    let before = [
+        report(
+            "linux",
+            &[
+                (":a", "PASS"),
+                (":b", "PASS"),
+                (":c", "FAIL"),
+                (":d", "PASS"),
+            ],
+        ),
+        report("win", &[(":a", "PASS")]),
+    ];
+    let after = [
+        report(
+            "linux",
+            &[
+                (":a", "PASS"),
+                (":b", "FAIL"),
+                (":c", "TIMEOUT"),
+                (":e", "PASS"),
+            ],
+        ),
+        report("linux", &[(":a", "PASS"), (":b", "FAIL"), (":c", "FAIL")]),
+        report("mac", &[(":a", "PASS")]),
+    ];
+    fn parse(contents: &[String]) -> Vec<Result<ExecutionReport<'_>, Error>> {
+        contents
+            .iter()
+            .map(|contents| {
+                ExecutionReport::from_json(
+                    Path::new("wptreport.json"),
+                    contents,
+                    &Default::default(),
+                )
+            })
+            .collect()
+    }
+
+    let transitions = transitions(Browser::Firefox, parse(&before), parse(&after)).unwrap();
+    insta::assert_snapshot!(transitions, @r###"
+    Compared 2 run(s) before against 3 run(s) after.
+    Configurations not covered by both sets of runs: Windows opt, macOS opt
+
+    ## Linux
+
+    Tests: 0 of 1 changed outcome(s)
+
+    | Before \ After | `OK` |
+    | -------------- | ---: |
+    | `OK`           |    1 |
+
+    Subtests: 4 of 5 changed outcome(s)
+
+    | Before \ After | `FAIL` | `PASS` | `[FAIL, TIMEOUT]` | not run |
+    | -------------- | -----: | -----: | ----------------: | ------: |
+    | `FAIL`         |      0 |      0 |                 1 |       0 |
+    | `PASS`         |      1 |      1 |                 0 |       1 |
+    | not run        |      0 |      1 |                 0 |       0 |
+    "###);
+}
@@ -0,0 +1,446 @@
+//! Filing of bugs in [Bugzilla] for tests expected to permanently fail or crash (i.e., with
+//! `file-bugs`), so that every such expectation is tracked by a bug linked from metadata, and
+//! finding the open bugs already tracking tests (i.e., with `triage --bugzilla`).
+//!
+//! [Bugzilla]: https://bugzilla.mozilla.org
+
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display, Formatter},
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use format::lazy_format;
+use indexmap::IndexMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use whippit::metadata::SectionHeader;
+
+use crate::{
+    download,
+    error::Error,
+    metadata::{File, Platform},
+    search::{self, ConfigurationExpectation, SearchFilters, SearchHit},
+    shared::TestPath,
+    test_pattern::TestPattern,
+};
+
+/// The Bugzilla instance tracking Firefox's bugs.
+pub const BUGZILLA_URL: &str = "https://bugzilla.mozilla.org";
+
+/// A test with no `bug` property, expected to permanently fail or crash, either itself or in any
+/// of its subtests, in some configuration where it's not disabled. Found by [`bug_candidates`].
+#[derive(Clone, Debug)]
+pub struct BugCandidate {
+    pub test: TestPath<'static>,
+    /// The test and subtests expected to permanently fail or crash, with only the configurations
+    /// they do so in.
+    pub failures: Vec<SearchHit>,
+}
+
+impl BugCandidate {
+    /// The platforms on which any of [`BugCandidate::failures`] fail or crash.
+    pub fn platforms(&self) -> BTreeSet<Platform> {
+        self.failures
+            .iter()
+            .flat_map(|hit| &hit.configurations)
+            .map(|configuration| configuration.platform)
+            .collect()
+    }
+
+    /// A one-line summary of the bug, naming the test by its CTS query, if it has one.
+    pub fn summary(&self) -> String {
+        let Self { test, failures } = self;
+        let outcomes = failures
+            .iter()
+            .flat_map(|hit| &hit.configurations)
+            .flat_map(|configuration| &configuration.expected)
+            .map(String::as_str)
+            .collect::<BTreeSet<_>>();
+        let verb = match (outcomes.contains("FAIL"), outcomes.contains("CRASH")) {
+            (true, true) => "Permanent FAIL and CRASH",
+            (false, true) => "Permanent CRASH",
+            _ => "Permanent FAIL",
+        };
+        let test = match test.cts_query() {
+            Some(query) => query.to_string(),
+            None => format!("/{}", test.runner_url_path()),
+        };
+        let platforms = self
+            .platforms()
+            .into_iter()
+            .map(|platform| format!("{platform:?}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{verb} in WebGPU CTS `{test}` on {platforms}")
+    }
+
+    /// The first comment of the bug, with the test's URL path and metadata path, and the
+    /// expectations of [`BugCandidate::failures`].
+    pub fn description(&self) -> impl Display + '_ {
+        lazy_format!(move |f| {
+            let Self { test, failures } = self;
+            writeln!(
+                f,
+                "The WebGPU CTS test `/{}` is expected to permanently fail or crash in \
+                 `{}`:",
+                test.runner_url_path(),
+                test.rel_metadata_path(),
+            )?;
+            writeln!(f)?;
+            writeln!(f, "```")?;
+            for hit in failures {
+                write!(f, "{hit}")?;
+            }
+            writeln!(f, "```")
+        })
+    }
+}
+
+/// Finds [`BugCandidate`]s among tests in `files` matching any of `patterns`, or all tests if
+/// `patterns` is empty.
+pub fn bug_candidates(
+    gecko_checkout: &Path,
+    files: &IndexMap<Arc<PathBuf>, File>,
+    patterns: &[TestPattern],
+) -> Vec<BugCandidate> {
+    let filters = SearchFilters {
+        outcomes: vec!["FAIL".parse().unwrap(), "CRASH".parse().unwrap()],
+        disabled: Some(false),
+        patterns: patterns.to_owned(),
+        ..Default::default()
+    };
+    let has_bug = |test: &TestPath<'_>| {
+        let path = gecko_checkout.join(test.rel_metadata_path().to_string());
+        files
+            .get(&path)
+            .and_then(|file| file.tests.get(&SectionHeader(test.test_name().to_string())))
+            .map_or(false, |test| test.properties.bug.is_some())
+    };
+
+    let mut candidates = Vec::<BugCandidate>::new();
+    for mut hit in search::search(gecko_checkout, files, &filters) {
+        hit.configurations
+            .retain(|ConfigurationExpectation { expected, .. }| expected.len() == 1);
+        if hit.configurations.is_empty() {
+            continue;
+        }
+        match candidates.last_mut() {
+            Some(candidate) if candidate.test == hit.test => candidate.failures.push(hit),
+            _ if has_bug(&hit.test) => (),
+            _ => candidates.push(BugCandidate {
+                test: hit.test.clone(),
+                failures: vec![hit],
+            }),
+        }
+    }
+    candidates
+}
+
+/// The fields of a bug to file with [`Bugzilla::file_bug`].
+#[derive(Clone, Debug, Serialize)]
+pub struct NewBug {
+    pub product: String,
+    pub component: String,
+    pub summary: String,
+    pub description: String,
+    pub version: String,
+    #[serde(rename = "type")]
+    pub bug_type: String,
+}
+
+/// An open bug, as listed by [`Bugzilla::open_bugs`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OpenBug {
+    pub id: u64,
+    pub summary: String,
+    #[serde(default)]
+    pub whiteboard: String,
+}
+
+/// A client for the parts of the Bugzilla REST API used to file and list bugs. Requests are
+/// anonymous without an API key, which only suffices to list public bugs.
+#[derive(Clone)]
+pub struct Bugzilla {
+    base_url: String,
+    api_key: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl Bugzilla {
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            api_key,
+            agent: download::agent(),
+        }
+    }
+
+    /// Files `bug`, returning its ID.
+    pub fn file_bug(&self, bug: &NewBug) -> Result<u64, Error> {
+        #[derive(Deserialize)]
+        struct Created {
+            id: u64,
+        }
+
+        let url = format!("{}/rest/bug", self.base_url);
+        tracing::debug!("posting to {url}");
+        let response = self
+            .request("POST", &url)
+            .set("Content-Type", "application/json")
+            .send_string(&serde_json::to_string(bug).unwrap());
+        let Created { id } = read_json(url, response)?;
+        Ok(id)
+    }
+
+    /// Lists the open bugs in `component` of `product`.
+    pub fn open_bugs(&self, product: &str, component: &str) -> Result<Vec<OpenBug>, Error> {
+        #[derive(Deserialize)]
+        struct Page {
+            bugs: Vec<OpenBug>,
+        }
+
+        const PAGE_SIZE: usize = 500;
+        let url = format!("{}/rest/bug", self.base_url);
+        let mut bugs = Vec::new();
+        loop {
+            tracing::debug!("requesting {url} from offset {}", bugs.len());
+            let response = self
+                .request("GET", &url)
+                .query("product", product)
+                .query("component", component)
+                .query("resolution", "---")
+                .query("include_fields", "id,summary,whiteboard")
+                .query("limit", &PAGE_SIZE.to_string())
+                .query("offset", &bugs.len().to_string())
+                .call();
+            let Page { bugs: page } = read_json(url.clone(), response)?;
+            let num_bugs = page.len();
+            bugs.extend(page);
+            if num_bugs < PAGE_SIZE {
+                break;
+            }
+        }
+        Ok(bugs)
+    }
+
+    /// Returns the URL of the bug with ID `id`, as written to `bug` properties in metadata.
+    pub fn bug_url(&self, id: u64) -> String {
+        format!("{}/show_bug.cgi?id={id}", self.base_url)
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = self.agent.request(method, url);
+        match &self.api_key {
+            Some(api_key) => request.set("X-BUGZILLA-API-KEY", api_key),
+            None => request,
+        }
+    }
+}
+
+fn read_json<T>(url: String, response: Result<ureq::Response, ureq::Error>) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    let response = response.map_err(|source| Error::BugzillaRequest {
+        url: url.clone(),
+        source: Box::new(source),
+    })?;
+    let mut body = String::new();
+    response
+        .into_reader()
+        .read_to_string(&mut body)
+        .map_err(|source| Error::ReadBugzillaResponse {
+            url: url.clone(),
+            source,
+        })?;
+    serde_json::from_str(&body).map_err(|source| Error::ParseBugzillaResponse { url, source })
+}
+
+/// Returns the ID of the bug a `bug` property refers to, i.e., `1234567` for
+/// `https://bugzilla.mozilla.org/show_bug.cgi?id=1234567`, `bug 1234567`, or `1234567`.
+pub fn bug_id(bug: &str) -> Option<u64> {
+    bug.trim()
+        .rsplit(|c: char| !c.is_ascii_digit())
+        .next()
+        .filter(|id| !id.is_empty())?
+        .parse()
+        .ok()
+}
+
+/// Open bugs found with [`Bugzilla::open_bugs`], for finding those tracking tests.
+#[derive(Clone, Debug, Default)]
+pub struct KnownBugs {
+    bugs: Vec<OpenBug>,
+}
+
+impl KnownBugs {
+    pub fn new(bugs: Vec<OpenBug>) -> Self {
+        Self { bugs }
+    }
+
+    /// Returns an open bug tracking `test`: the bug one of `bug_properties` (i.e., of the test
+    /// and its subtests) refers to, if open, or else one whose summary or whiteboard mentions
+    /// the test's CTS query (i.e., `webgpu:api,operation,adapter:*`, as in a whiteboard tag like
+    /// `[webgpu-cts:webgpu:api,operation,adapter:*]`) or URL path.
+    pub fn tracking<'a>(
+        &self,
+        test: &TestPath<'_>,
+        bug_properties: impl IntoIterator<Item = &'a str>,
+    ) -> Option<&OpenBug> {
+        let Self { bugs } = self;
+        for id in bug_properties.into_iter().filter_map(bug_id) {
+            if let Some(bug) = bugs.iter().find(|bug| bug.id == id) {
+                return Some(bug);
+            }
+        }
+        let names = [
+            test.cts_query().map(|query| query.to_string()),
+            Some(format!("/{}", test.runner_url_path())),
+        ];
+        bugs.iter().find(|bug| {
+            names.iter().flatten().any(|name| {
+                bug.summary.contains(name.as_str()) || bug.whiteboard.contains(name.as_str())
+            })
+        })
+    }
+}
+
+impl Display for NewBug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            product,
+            component,
+            summary,
+            description,
+            version: _,
+            bug_type: _,
+        } = self;
+        writeln!(f, "{product} :: {component}: {summary}")?;
+        for line in description.lines() {
+            writeln!(f, "  {line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn find_bug_candidates() {
+    use crate::{
+        metadata::{BuildProfile, FileBuilder, SubtestBuilder, SubtestOutcome, TestBuilder},
+        shared::Expected,
+    };
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = Arc::new(
+        gecko_checkout
+            .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+            .join("cts.https.html.ini"),
+    );
+    let mut file =
+        FileBuilder::new()
+            .test(
+                TestBuilder::new("cts.https.html?q=webgpu:api,operation,adapter:*")
+                    .subtest(SubtestBuilder::new(":a").expected_on(
+                        Platform::Linux,
+                        BuildProfile::Debug,
+                        Expected::permanent(SubtestOutcome::Fail),
+                    ))
+                    .subtest(SubtestBuilder::new(":b").expected(
+                        Expected::new(SubtestOutcome::Pass | SubtestOutcome::Fail).unwrap(),
+                    )),
+            )
+            .test(
+                TestBuilder::new("cts.https.html?q=webgpu:api,operation,buffers:*").subtest(
+                    SubtestBuilder::new(":c").expected(Expected::permanent(SubtestOutcome::Fail)),
+                ),
+            )
+            .build()
+            .unwrap();
+    file.tests
+        .get_mut(&SectionHeader(
+            "cts.https.html?q=webgpu:api,operation,buffers:*".to_owned(),
+        ))
+        .unwrap()
+        .properties
+        .bug = Some(format!("{BUGZILLA_URL}/show_bug.cgi?id=1234567"));
+    let files = [(meta_path, file)].into_iter().collect();
+
+    let candidates = bug_candidates(gecko_checkout, &files, &[]);
+    assert_eq!(candidates.len(), 1);
+    let candidate = &candidates[0];
+    assert_eq!(candidate.platforms(), [Platform::Linux].into());
+    insta::assert_snapshot!(candidate.summary(), @"Permanent FAIL in WebGPU CTS `webgpu:api,operation,adapter:*` on Linux");
+    insta::assert_snapshot!(candidate.description(), @r###"
+    The WebGPU CTS test `/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*` is expected to permanently fail or crash in `testing/web-platform/mozilla/meta/webgpu/cts/webgpu/cts.https.html.ini`:
+
+    ```
+    /_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:* [:a]
+      Linux Debug: FAIL
+    ```
+    "###);
+
+    assert_eq!(
+        Bugzilla::new(&format!("{BUGZILLA_URL}/"), None).bug_url(1234567),
+        "https://bugzilla.mozilla.org/show_bug.cgi?id=1234567"
+    );
+}
+
+#[test]
+fn find_tracking_bugs() {
+    use crate::shared::Browser;
+
+    let test_path = |query| {
+        TestPath::from_execution_report(
+            Browser::Firefox,
+            &format!("/_mozilla/webgpu/cts/webgpu/cts.https.html?q={query}"),
+        )
+        .unwrap()
+        .into_owned()
+    };
+    let known_bugs = KnownBugs::new(vec![
+        OpenBug {
+            id: 1,
+            summary: "Permanent FAIL in WebGPU CTS `webgpu:api,operation,adapter:*`".to_owned(),
+            whiteboard: String::new(),
+        },
+        OpenBug {
+            id: 2,
+            summary: "Buffers are broken".to_owned(),
+            whiteboard: "[webgpu-cts:webgpu:api,operation,buffers:*]".to_owned(),
+        },
+        OpenBug {
+            id: 3,
+            summary: "Something else".to_owned(),
+            whiteboard: String::new(),
+        },
+    ]);
+    let tracking = |query, bugs: &[&str]| {
+        known_bugs
+            .tracking(&test_path(query), bugs.iter().copied())
+            .map(|bug| bug.id)
+    };
+
+    assert_eq!(tracking("webgpu:api,operation,adapter:*", &[]), Some(1));
+    assert_eq!(tracking("webgpu:api,operation,buffers:*", &[]), Some(2));
+    assert_eq!(
+        tracking(
+            "webgpu:api,operation,buffers:*",
+            &["https://bugzilla.mozilla.org/show_bug.cgi?id=3"]
+        ),
+        Some(3)
+    );
+    assert_eq!(tracking("webgpu:api,operation,queue:*", &["bug 4"]), None);
+    assert_eq!(
+        tracking("webgpu:api,operation,adapter,requestDevice:*", &[]),
+        None
+    );
+
+    assert_eq!(
+        bug_id("https://bugzilla.mozilla.org/show_bug.cgi?id=1234567"),
+        Some(1234567)
+    );
+    assert_eq!(bug_id("bug 1234567 "), Some(1234567));
+    assert_eq!(bug_id("true"), None);
+}
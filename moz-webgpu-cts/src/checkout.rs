@@ -0,0 +1,340 @@
+//! Reading and writing files in a Gecko checkout.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+use indicatif::{ParallelProgressIterator, ProgressBar};
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use wax::Glob;
+use whippit::reexport::chumsky;
+
+use crate::{
+    collation,
+    error::{Error, MetadataParseError},
+    filesystem::FileSystem,
+    metadata, progress,
+    shared::Browser,
+};
+
+/// Search for a `mozilla-central` checkout either via Mercurial or Git, iterating from the CWD to
+/// its parent directories.
+pub fn search_for_moz_central_ckt() -> Result<PathBuf, Error> {
+    use lets_find_up::{find_up_with, FindUpKind, FindUpOptions};
+
+    let find_up_opts = || FindUpOptions {
+        cwd: Path::new("."),
+        kind: FindUpKind::Dir,
+    };
+    let find_up = |repo_tech_name, root_dir_name| {
+        tracing::debug!("searching for {repo_tech_name} checkout of `mozilla-central`…");
+        match find_up_with(root_dir_name, find_up_opts()) {
+            Ok(loc_opt) => loc_opt.map(|mut dir| {
+                dir.pop();
+                dir
+            }),
+            Err(e) => {
+                // N.B.: Don't give up yet; a checkout might still be found with the other VCS.
+                tracing::warn!(
+                    "failed to search for {repo_tech_name} checkout of `mozilla-central`: {e}"
+                );
+                None
+            }
+        }
+    };
+    let gecko_source_root = find_up("Mercurial", ".hg")
+        .or_else(|| find_up("Git", ".git"))
+        .ok_or(Error::CheckoutNotFound)?;
+
+    tracing::info!(
+        "detected Gecko repository root at {}",
+        gecko_source_root.display()
+    );
+
+    Ok(gecko_source_root)
+}
+
+/// Returns an iterator over all WebGPU CTS metadata files in `gecko_checkout`, a checkout of
+/// `browser`, parsed. Directory metadata (i.e., `__dir__.ini`) is skipped.
+pub fn read_and_parse_all_metadata<'a>(
+    fs: &'a dyn FileSystem,
+    gecko_checkout: &Path,
+    browser: Browser,
+) -> impl Iterator<Item = Result<(Arc<PathBuf>, metadata::File), Error>> + 'a {
+    read_and_parse_metadata_matching(fs, gecko_checkout, browser, &Glob::new("**/*.ini").unwrap())
+}
+
+/// Like [`read_and_parse_all_metadata`], but only for files matching `glob`, relative to the
+/// directory containing WebGPU CTS metadata (i.e., `**/shader/**`). Matching files that are not
+/// metadata (i.e., without an `.ini` extension) are skipped.
+pub fn read_and_parse_metadata_matching<'a>(
+    fs: &'a dyn FileSystem,
+    gecko_checkout: &Path,
+    browser: Browser,
+    glob: &Glob<'_>,
+) -> impl Iterator<Item = Result<(Arc<PathBuf>, metadata::File), Error>> + 'a {
+    let webgpu_cts_meta_parent_dir = browser.webgpu_cts_meta_dir(gecko_checkout);
+
+    let raw_metadata_files =
+        read_gecko_files_at(fs, gecko_checkout, &webgpu_cts_meta_parent_dir, glob)
+            .filter(|res| {
+                res.as_ref().map_or(true, |(path, _file_contents)| {
+                    !path.ends_with("__dir__.ini")
+                        && path
+                            .extension()
+                            .map_or(false, |extension| extension == "ini")
+                })
+            })
+            .collect::<Vec<_>>();
+
+    if !raw_metadata_files.is_empty() {
+        tracing::info!("parsing metadata…");
+    }
+    let progress = progress::bar("parsing metadata", Some(raw_metadata_files.len()));
+    parse_all_metadata(raw_metadata_files, progress).into_iter()
+}
+
+/// Returns an iterator over all directory metadata files (i.e., `__dir__.ini`) for the WebGPU CTS
+/// in `gecko_checkout`, a checkout of `browser`, parsed with [`parse_dir_metadata`].
+pub fn read_and_parse_all_dir_metadata<'a>(
+    fs: &'a dyn FileSystem,
+    gecko_checkout: &Path,
+    browser: Browser,
+) -> impl Iterator<Item = Result<(Arc<PathBuf>, metadata::DirFile), Error>> + 'a {
+    let webgpu_cts_meta_parent_dir = browser.webgpu_cts_meta_dir(gecko_checkout);
+
+    read_gecko_files_at(
+        fs,
+        gecko_checkout,
+        &webgpu_cts_meta_parent_dir,
+        &Glob::new("**/__dir__.ini").unwrap(),
+    )
+    .collect::<Vec<_>>()
+    .into_iter()
+    .map(|res| res.and_then(|(path, file_contents)| parse_dir_metadata(path, file_contents)))
+}
+
+/// Parses each of `raw_files` with [`parse_metadata`], in parallel, returning results in the same
+/// order regardless of which finished first. Errors from reading files are passed through.
+fn parse_all_metadata(
+    raw_files: Vec<Result<(PathBuf, String), Error>>,
+    progress: ProgressBar,
+) -> Vec<Result<(Arc<PathBuf>, metadata::File), Error>> {
+    raw_files
+        .into_par_iter()
+        .progress_with(progress)
+        .map(|res| res.and_then(|(path, file_contents)| parse_metadata(path, file_contents)))
+        .collect()
+}
+
+/// Returns the directory where this tool keeps state between invocations, under the same state
+/// directory as `mach` (i.e., `$MOZBUILD_STATE_PATH`, or `~/.mozbuild` if unset).
+pub fn state_dir() -> Result<PathBuf, Error> {
+    let mozbuild_state_dir = std::env::var_os("MOZBUILD_STATE_PATH")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(|home| PathBuf::from(home).join(".mozbuild"))
+        })
+        .ok_or(Error::StateDirNotFound)?;
+    Ok(mozbuild_state_dir.join("moz-webgpu-cts"))
+}
+
+/// Incremented whenever the structure of [`metadata::File`] changes, so that metadata cached by
+/// an older version of this tool is parsed again.
+const PARSE_CACHE_VERSION: u32 = 1;
+
+static PARSE_CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Caches metadata parsed by [`parse_metadata`] in `dir` for the rest of this process, keyed by a
+/// hash of file contents, and loads cached metadata instead of parsing files with the same
+/// contents again. Has no effect if set already.
+///
+/// The cache is read and written directly on disk, regardless of the [`FileSystem`] metadata is
+/// read from. Failing to write to it is not an error.
+pub fn enable_parse_cache(dir: PathBuf) {
+    let _ = PARSE_CACHE_DIR.set(dir);
+}
+
+/// Parses `file_contents` as metadata read from `path`.
+pub fn parse_metadata(
+    path: PathBuf,
+    file_contents: String,
+) -> Result<(Arc<PathBuf>, metadata::File), Error> {
+    let path = Arc::new(path);
+
+    let cache_path = PARSE_CACHE_DIR.get().map(|dir| {
+        let mut hasher = DefaultHasher::new();
+        (
+            PARSE_CACHE_VERSION,
+            env!("CARGO_PKG_VERSION"),
+            &file_contents,
+        )
+            .hash(&mut hasher);
+        dir.join(format!("{:016x}.json", hasher.finish()))
+    });
+    let cached = cache_path
+        .as_ref()
+        .and_then(|cache_path| fs::read(cache_path).ok())
+        .and_then(|cached| serde_json::from_slice(&cached).ok());
+    if let Some(file) = cached {
+        tracing::debug!("using cached parse of metadata at {}", path.display());
+        return Ok((path, file));
+    }
+
+    let file_contents = Arc::new(file_contents);
+
+    tracing::debug!("parsing metadata at {}", path.display());
+    let res = chumsky::Parser::parse(&metadata::File::parser(), &*file_contents)
+        .into_result()
+        .map_err(|errors| parse_metadata_error(&path, &file_contents, errors))
+        .map(|file| (path, file));
+    if let (Some(cache_path), Ok((_path, file))) = (cache_path, &res) {
+        let written = fs::create_dir_all(cache_path.parent().unwrap())
+            .and_then(|()| fs::write(&cache_path, serde_json::to_vec(file).unwrap()));
+        if let Err(e) = written {
+            tracing::warn!(
+                "failed to cache parsed metadata at {}: {e}",
+                cache_path.display()
+            );
+        }
+    }
+    res
+}
+
+/// Parses `file_contents`, the contents of the directory metadata file (i.e., `__dir__.ini`) at
+/// `path`, like [`parse_metadata`] does test metadata files, but without caching, since there are
+/// few of them.
+pub fn parse_dir_metadata(
+    path: PathBuf,
+    file_contents: String,
+) -> Result<(Arc<PathBuf>, metadata::DirFile), Error> {
+    let path = Arc::new(path);
+    let file_contents = Arc::new(file_contents);
+
+    tracing::debug!("parsing directory metadata at {}", path.display());
+    let res = chumsky::Parser::parse(&metadata::DirFile::parser(), &*file_contents)
+        .into_result()
+        .map_err(|errors| parse_metadata_error(&path, &file_contents, errors))
+        .map(|file| (path, file));
+    res
+}
+
+fn parse_metadata_error(
+    path: &Arc<PathBuf>,
+    file_contents: &Arc<String>,
+    errors: Vec<chumsky::prelude::Rich<'_, char>>,
+) -> Error {
+    Error::ParseMetadata {
+        errors: errors
+            .into_iter()
+            .map(|error| MetadataParseError::new(path, file_contents, error))
+            .collect(),
+        path: path.clone(),
+    }
+}
+
+/// Returns a list of files found by searching for `glob` in `base`, sorted by the current
+/// [`collation`].
+/// `gecko_checkout` is stripped as a prefix from the absolute paths recorded into `log` entries
+/// emitted by this function.
+///
+/// # Returns
+///
+/// An iterator over [`Result`]s containing either a Gecko file's path and contents as a UTF-8
+/// string, or an error encountered while enumerating or reading files.
+///
+/// # Panics
+///
+/// This function will panick if `gecko_checkout` cannot be stripped as a prefix of `base`.
+fn read_gecko_files_at<'a>(
+    fs: &'a dyn FileSystem,
+    gecko_checkout: &Path,
+    base: &Path,
+    glob: &Glob<'_>,
+) -> impl Iterator<Item = Result<(PathBuf, String), Error>> + 'a {
+    tracing::info!("reading {glob} files at {}", base.display());
+    let mut enumeration_errs = Vec::new();
+    let mut paths = fs
+        .glob(base, glob)
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Ok(path) => Some(path),
+            Err(e) => {
+                enumeration_errs.push(e);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let collation = collation::current();
+    paths.sort_by(|a, b| collation.compare_paths(a, b));
+    let paths = paths;
+
+    tracing::debug!(
+        "working with these files: {:#?}",
+        paths
+            .iter()
+            .map(|f| f.strip_prefix(gecko_checkout).unwrap())
+            .collect::<std::collections::BTreeSet<_>>()
+    );
+
+    let iter = paths.into_iter().map(move |path| -> Result<_, _> {
+        tracing::debug!("reading from {}…", path.display());
+        let file_contents = fs.read_to_string(&path)?;
+        Ok((path, file_contents))
+    });
+
+    let (read_err_iter, file_read_iter) = if enumeration_errs.is_empty() {
+        (None, Some(iter))
+    } else {
+        (Some(enumeration_errs.into_iter().map(Err)), None)
+    };
+
+    read_err_iter
+        .into_iter()
+        .flatten()
+        .chain(file_read_iter.into_iter().flatten())
+}
+
+#[test]
+fn metadata_matching() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_dir = Browser::Firefox.webgpu_cts_meta_dir(gecko_checkout);
+    let test = "[cts.https.html?q=webgpu:shader,execution,*]\n  expected: FAIL\n";
+    let fs = [
+        (
+            meta_dir.join("cts/webgpu/shader/execution/cts.https.html.ini"),
+            test,
+        ),
+        (
+            meta_dir.join("cts/webgpu/shader/__dir__.ini"),
+            "tags: [webgpu]\n",
+        ),
+        (meta_dir.join("cts/webgpu/shader/README.md"), ""),
+        (meta_dir.join("cts/webgpu/api/cts.https.html.ini"), test),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    // N.B.: Directory metadata and files that aren't metadata are skipped, even if they match.
+    let paths = read_and_parse_metadata_matching(
+        &fs,
+        gecko_checkout,
+        Browser::Firefox,
+        &Glob::new("**/shader/**").unwrap(),
+    )
+    .map(|res| res.unwrap().0.strip_prefix(&meta_dir).unwrap().to_owned())
+    .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [Path::new("cts/webgpu/shader/execution/cts.https.html.ini")]
+    );
+}
@@ -0,0 +1,180 @@
+//! Removal of metadata that states what `wptrunner` would assume without it, i.e., `expected: OK`
+//! lines, or subtests whose only expectation is `PASS`.
+
+use std::{path::PathBuf, sync::Arc};
+
+use enumset::EnumSetType;
+use indexmap::IndexMap;
+
+use crate::{
+    dedupe,
+    metadata::{File, TestProps},
+};
+
+/// The changes made by [`clean`].
+#[derive(Debug, Default)]
+pub struct Cleaned {
+    /// Files with anything removed from them, which should be written back, unless in
+    /// `emptied`.
+    pub changed_files: Vec<Arc<PathBuf>>,
+    /// Files left without any tests or properties, which should be removed.
+    pub emptied: Vec<PathBuf>,
+    /// The number of `expected` properties (or conditions in them) removed.
+    pub num_expectations: usize,
+    /// The number of subtest sections removed.
+    pub num_subtests: usize,
+    /// The number of test sections removed.
+    pub num_tests: usize,
+}
+
+/// Removes redundant metadata from `files`: expectations equal to the default outcome (or to the
+/// outcomes their conditions fall through to), then subtest sections left without properties,
+/// and then test sections left without properties or subtests. Files left without tests or
+/// properties are listed in [`Cleaned::emptied`].
+///
+/// `update-expected` does much of the same for tests it reconciles; this does it for all metadata,
+/// without reports.
+pub fn clean(files: &mut IndexMap<Arc<PathBuf>, File>) -> Cleaned {
+    let mut cleaned = Cleaned::default();
+    for (path, file) in files.iter_mut() {
+        let mut changed = false;
+        file.tests.retain(|_name, test| {
+            if clean_props(&mut test.properties) {
+                cleaned.num_expectations += 1;
+                changed = true;
+            }
+            test.subtests.retain(|_name, subtest| {
+                if clean_props(&mut subtest.properties) {
+                    cleaned.num_expectations += 1;
+                    changed = true;
+                }
+                let keep = subtest.properties != Default::default();
+                if !keep {
+                    cleaned.num_subtests += 1;
+                    changed = true;
+                }
+                keep
+            });
+            let keep = !test.subtests.is_empty() || test.properties != Default::default();
+            if !keep {
+                cleaned.num_tests += 1;
+                changed = true;
+            }
+            keep
+        });
+        if !changed {
+            continue;
+        }
+        if file.tests.is_empty() && dedupe::is_empty(&file.properties) {
+            cleaned.emptied.push(path.to_path_buf());
+        }
+        cleaned.changed_files.push(path.clone());
+    }
+    cleaned
+}
+
+/// Removes the expectations of `props` that are redundant, returning whether any were.
+fn clean_props<Out>(props: &mut TestProps<Out>) -> bool
+where
+    Out: Default + EnumSetType,
+{
+    if props.verbatim_expected.is_some() {
+        return false;
+    }
+    let num_variants = props.expected_by_variant.len();
+    props.prune_redundant_variants();
+    let mut removed = props.expected_by_variant.len() != num_variants;
+    // N.B.: Variants fall through to the default outcome without an unconditional expectation,
+    // so removing a default one doesn't change what they resolve to.
+    if props.expected == Some(Default::default()) {
+        props.expected = None;
+        removed = true;
+    }
+    removed
+}
+
+#[test]
+fn clean_redundant_metadata() {
+    use std::path::Path;
+
+    use crate::{checkout::parse_metadata, metadata::format_file};
+
+    let meta_dir = Path::new("/gecko/testing/web-platform/mozilla/meta/webgpu/cts/webgpu");
+    let files = [
+        (
+            "api/cts.https.html.ini",
+            concat!(
+                "[cts.https.html?q=webgpu:api,a:*]\n",
+                "  expected: OK\n",
+                "\n",
+                "  [:pass]\n",
+                "    expected: PASS\n",
+                "\n",
+                "  [:fail]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "  [:disabled]\n",
+                "    disabled: true\n",
+                "    expected: PASS\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,b:*]\n",
+                "  expected:\n",
+                "    if os == \"win\": TIMEOUT\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,c:*]\n",
+                "  [:]\n",
+            ),
+        ),
+        (
+            "shader/cts.https.html.ini",
+            concat!(
+                "[cts.https.html?q=webgpu:shader,a:*]\n",
+                "  expected: OK\n",
+                "\n",
+                "  [:]\n",
+                "    expected: PASS\n",
+            ),
+        ),
+        (
+            "util/cts.https.html.ini",
+            concat!(
+                "[cts.https.html?q=webgpu:util,a:*]\n",
+                "  expected: CRASH\n",
+            ),
+        ),
+    ];
+    let mut files = files
+        .into_iter()
+        .map(|(path, contents)| parse_metadata(meta_dir.join(path), contents.to_owned()).unwrap())
+        .collect::<IndexMap<_, _>>();
+
+    let cleaned = clean(&mut files);
+    assert_eq!(
+        cleaned.changed_files,
+        [
+            files.get_index(0).unwrap().0.clone(),
+            files.get_index(1).unwrap().0.clone(),
+        ]
+    );
+    assert_eq!(
+        cleaned.emptied,
+        [meta_dir.join("shader/cts.https.html.ini")]
+    );
+    assert_eq!(cleaned.num_expectations, 5);
+    assert_eq!(cleaned.num_subtests, 3);
+    assert_eq!(cleaned.num_tests, 2);
+
+    insta::assert_snapshot!(format_file(&files[0]), @r###"
+    [cts.https.html?q=webgpu:api,a:*]
+      [:disabled]
+        disabled: true
+
+      [:fail]
+        expected: FAIL
+
+
+    [cts.https.html?q=webgpu:api,b:*]
+      expected:
+        if os == "win": TIMEOUT
+    "###);
+}
@@ -0,0 +1,138 @@
+//! Orderings of names (i.e., of metadata files, sections, and entries of list outputs) that
+//! depend on neither the platform nor the locale, so that output is the same byte for byte
+//! wherever it's produced.
+
+use std::{cmp::Ordering, path::Path, sync::OnceLock};
+
+use clap::ValueEnum;
+
+/// How names are ordered, as chosen with `--sort`.
+///
+/// Every collation is total: names that only compare equal after ignoring case or leading zeros
+/// are then ordered by code point.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Collation {
+    /// By Unicode code point, i.e., `Z` before `a`, and `size=16` before `size=4`.
+    #[default]
+    Bytes,
+    /// Like `bytes`, but with runs of ASCII digits compared by their numeric value, i.e.,
+    /// `size=4` before `size=16`.
+    Natural,
+    /// Like `natural`, but ignoring ASCII case, i.e., `a` before `Z`.
+    NaturalIgnoreCase,
+}
+
+static CURRENT: OnceLock<Collation> = OnceLock::new();
+
+/// Sets the collation returned by [`current`] for the rest of this process. Has no effect if it
+/// was set already.
+pub fn set(collation: Collation) {
+    let _ = CURRENT.set(collation);
+}
+
+/// Returns the collation set with [`set`], or [`Collation::Bytes`] if none was.
+pub fn current() -> Collation {
+    CURRENT.get().copied().unwrap_or_default()
+}
+
+impl Collation {
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        let collated = match self {
+            Self::Bytes => Ordering::Equal,
+            Self::Natural => compare_natural(a, b, false),
+            Self::NaturalIgnoreCase => compare_natural(a, b, true),
+        };
+        collated.then_with(|| a.cmp(b))
+    }
+
+    /// Compares paths component by component, so that the order doesn't depend on the
+    /// platform's path separator.
+    pub fn compare_paths(self, a: &Path, b: &Path) -> Ordering {
+        let mut a = a.components();
+        let mut b = b.components();
+        loop {
+            match (a.next(), b.next()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(a), Some(b)) => {
+                    let ordering = self.compare(
+                        &a.as_os_str().to_string_lossy(),
+                        &b.as_os_str().to_string_lossy(),
+                    );
+                    if ordering.is_ne() {
+                        return ordering;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn compare_natural(a: &str, b: &str, ignore_case: bool) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        let (a_char, b_char) = match (a.peek(), b.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_char), Some(b_char)) => (*a_char, *b_char),
+        };
+        if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+            let digits = |chars: &mut std::iter::Peekable<std::str::Chars<'_>>| {
+                let mut run = String::new();
+                while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                    run.push(c);
+                }
+                run
+            };
+            let (a_run, b_run) = (digits(&mut a), digits(&mut b));
+            let (a_num, b_num) = (a_run.trim_start_matches('0'), b_run.trim_start_matches('0'));
+            let ordering = a_num.len().cmp(&b_num.len()).then_with(|| a_num.cmp(b_num));
+            if ordering.is_ne() {
+                return ordering;
+            }
+            continue;
+        }
+        let (a_char, b_char) = if ignore_case {
+            (a_char.to_ascii_lowercase(), b_char.to_ascii_lowercase())
+        } else {
+            (a_char, b_char)
+        };
+        let ordering = a_char.cmp(&b_char);
+        if ordering.is_ne() {
+            return ordering;
+        }
+        a.next();
+        b.next();
+    }
+}
+
+#[test]
+fn collations() {
+    let sorted = |collation: Collation, names: &[&'static str]| {
+        let mut names = names.to_vec();
+        names.sort_by(|a, b| collation.compare(a, b));
+        names
+    };
+    let names = [":size=16", ":size=4", ":size=04", ":Size=8", ":size=4;a=1"];
+    assert_eq!(
+        sorted(Collation::Bytes, &names),
+        [":Size=8", ":size=04", ":size=16", ":size=4", ":size=4;a=1"]
+    );
+    assert_eq!(
+        sorted(Collation::Natural, &names),
+        [":Size=8", ":size=04", ":size=4", ":size=4;a=1", ":size=16"]
+    );
+    assert_eq!(
+        sorted(Collation::NaturalIgnoreCase, &names),
+        [":size=04", ":size=4", ":size=4;a=1", ":Size=8", ":size=16"]
+    );
+
+    assert_eq!(
+        Collation::Bytes.compare_paths(Path::new("a/b/c.ini"), Path::new("a/b0.ini")),
+        Ordering::Less,
+        "paths should be compared by component, regardless of the separator"
+    );
+}
@@ -0,0 +1,192 @@
+//! Project-level defaults for command-line options, read from [`CONFIG_FILE_NAME`] at the root of
+//! a Gecko checkout, so that teams can commit a shared configuration.
+//!
+//! ```toml
+//! browser = "firefox"
+//!
+//! [update-expected]
+//! preset = "merge"
+//! glob = ["../reports/**/wptreport.json"]
+//! never-modify = "testing/web-platform/mozilla/meta/webgpu/never-modify.txt"
+//!
+//! [triage]
+//! severity = "CRASH,TIMEOUT"
+//! owners = "testing/web-platform/mozilla/meta/webgpu/owners.txt"
+//! file = "**/shader/**"
+//! ```
+//!
+//! Options given on the command line take precedence over those set here. Paths are relative to
+//! the checkout, except for report globs, which are matched from the working directory like those
+//! given with `--glob`.
+
+use std::{
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use clap::ValueEnum;
+use serde::{de::Error as _, Deserialize, Deserializer};
+
+use crate::{
+    error::Error, filesystem::FileSystem, script::PresetArg, severity::SeverityOrder,
+    shared::Browser,
+};
+
+/// The name of the configuration file looked for at the root of a Gecko checkout.
+pub const CONFIG_FILE_NAME: &str = ".moz-webgpu-cts.toml";
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// The default of `--browser`.
+    #[serde(default, deserialize_with = "value_enum")]
+    pub browser: Option<Browser>,
+    #[serde(default)]
+    pub update_expected: UpdateExpectedConfig,
+    #[serde(default)]
+    pub triage: TriageConfig,
+}
+
+/// Defaults for `update-expected`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct UpdateExpectedConfig {
+    /// The default of `--preset`.
+    #[serde(default, deserialize_with = "from_str")]
+    pub preset: Option<PresetArg>,
+    /// Report globs used when no reports are given on the command line.
+    #[serde(default)]
+    pub glob: Vec<String>,
+    /// The default of `--never-modify`.
+    pub never_modify: Option<PathBuf>,
+}
+
+/// Defaults for `triage`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct TriageConfig {
+    /// The default of `--severity`.
+    #[serde(default, deserialize_with = "from_str")]
+    pub severity: Option<SeverityOrder>,
+    /// The default of `--owners`.
+    pub owners: Option<PathBuf>,
+    /// The default of `--file`.
+    pub file: Option<String>,
+}
+
+impl Config {
+    /// Reads the configuration in `gecko_checkout`, or returns an empty configuration if it has
+    /// none. Paths in it are resolved against `gecko_checkout`.
+    pub fn load(fs: &dyn FileSystem, gecko_checkout: &Path) -> Result<Self, Error> {
+        let path = gecko_checkout.join(CONFIG_FILE_NAME);
+        let contents = match fs.read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(Error::ReadFile { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(e) => return Err(e),
+        };
+        let mut config = toml::from_str::<Self>(&contents)
+            .map_err(|source| Error::ParseConfig { path, source })?;
+
+        let Self {
+            browser: _,
+            update_expected:
+                UpdateExpectedConfig {
+                    preset: _,
+                    glob: _,
+                    never_modify,
+                },
+            triage:
+                TriageConfig {
+                    severity: _,
+                    owners,
+                    file: _,
+                },
+        } = &mut config;
+        for path in [never_modify, owners].into_iter().flatten() {
+            *path = gecko_checkout.join(&*path);
+        }
+        Ok(config)
+    }
+}
+
+/// Deserializes a value from a string, as it would be given on the command line.
+fn from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map(Some).map_err(D::Error::custom)
+}
+
+/// Like [`from_str`], but for [`ValueEnum`]s.
+fn value_enum<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ValueEnum,
+{
+    let s = String::deserialize(deserializer)?;
+    T::from_str(&s, false).map(Some).map_err(D::Error::custom)
+}
+
+#[test]
+fn load() {
+    use crate::{filesystem::MemoryFileSystem, process_reports::ReportProcessingPreset};
+
+    let gecko_checkout = Path::new("gecko");
+    let fs = MemoryFileSystem::new();
+    assert_eq!(
+        Config::load(&fs, gecko_checkout).unwrap(),
+        Config::default()
+    );
+
+    let fs = [(
+        gecko_checkout.join(CONFIG_FILE_NAME),
+        concat!(
+            "browser = \"chromium\"\n",
+            "\n",
+            "[update-expected]\n",
+            "preset = \"same-fx\"\n",
+            "glob = [\"reports/**/*.json\"]\n",
+            "never-modify = \"never-modify.txt\"\n",
+            "\n",
+            "[triage]\n",
+            "severity = \"CRASH,TIMEOUT\"\n",
+        ),
+    )]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+    let config = Config::load(&fs, gecko_checkout).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            browser: Some(Browser::Chromium),
+            update_expected: UpdateExpectedConfig {
+                preset: Some(PresetArg::Builtin(ReportProcessingPreset::Merge)),
+                glob: vec!["reports/**/*.json".to_owned()],
+                never_modify: Some(gecko_checkout.join("never-modify.txt")),
+            },
+            triage: TriageConfig {
+                severity: Some("CRASH,TIMEOUT".parse().unwrap()),
+                owners: None,
+                file: None,
+            },
+        }
+    );
+
+    let fs = [(
+        gecko_checkout.join(CONFIG_FILE_NAME),
+        "[update-expected]\npreset = \"nope\"\n",
+    )]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+    assert!(matches!(
+        Config::load(&fs, gecko_checkout),
+        Err(Error::ParseConfig { .. })
+    ));
+}
@@ -0,0 +1,309 @@
+//! Detection and merging of CTS tests with sections in more than one metadata file, i.e., after
+//! moving metadata between files by hand.
+//!
+//! When reconciling, `update-expected` keeps the last section read for a test, so that is also
+//! the canonical location tests are merged into here.
+
+use std::{
+    collections::{btree_map::Entry, BTreeSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use indexmap::IndexMap;
+use whippit::metadata::SectionHeader;
+
+use crate::{
+    metadata::{File, FileProps, Test, TestProps},
+    shared::TestPath,
+};
+
+/// A CTS test with sections in more than one metadata file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DuplicateTest {
+    /// The query of the test (i.e., `webgpu:api,operation,*`).
+    pub cts_path: String,
+    /// The files with a section for the test, and the section's name in each, in the order they
+    /// were read. The last is the canonical location.
+    pub locations: Vec<(Arc<PathBuf>, SectionHeader)>,
+}
+
+impl DuplicateTest {
+    pub fn canonical(&self) -> &(Arc<PathBuf>, SectionHeader) {
+        self.locations.last().unwrap()
+    }
+}
+
+/// Returns all CTS tests with sections in more than one of `files`, in the order their first
+/// duplicate section was found.
+pub fn find_duplicate_tests(
+    gecko_checkout: &Path,
+    files: &IndexMap<Arc<PathBuf>, File>,
+) -> Vec<DuplicateTest> {
+    let mut locations_by_cts_path = IndexMap::<String, Vec<(Arc<PathBuf>, SectionHeader)>>::new();
+    for (path, file) in files {
+        let Ok(rel_path) = path.strip_prefix(gecko_checkout) else {
+            continue;
+        };
+        for name in file.tests.keys() {
+            let Some(cts_path) = TestPath::from_metadata_test(rel_path, &name.0)
+                .ok()
+                .and_then(|test_path| test_path.cts_path())
+            else {
+                continue;
+            };
+            locations_by_cts_path
+                .entry(cts_path)
+                .or_default()
+                .push((path.clone(), name.clone()));
+        }
+    }
+
+    let mut dupes = locations_by_cts_path
+        .into_iter()
+        .filter(|(_cts_path, locations)| locations.len() > 1)
+        .map(|(cts_path, locations)| DuplicateTest {
+            cts_path,
+            locations,
+        })
+        .collect::<Vec<_>>();
+    dupes.sort_by_key(|dupe| {
+        let (path, name) = &dupe.locations[1];
+        (files.get_index_of(path), name.clone())
+    });
+    dupes
+}
+
+/// Merges the sections of each of `dupes` into its canonical location, removing the others.
+/// Sections read later take precedence: test properties are taken from the last section that
+/// sets any, and subtests from the last section that has them.
+///
+/// Returns files left without any tests or properties, which should be removed.
+pub fn dedupe(files: &mut IndexMap<Arc<PathBuf>, File>, dupes: &[DuplicateTest]) -> Vec<PathBuf> {
+    let mut touched = BTreeSet::new();
+    for dupe in dupes {
+        let mut merged = Test::default();
+        for (path, name) in &dupe.locations {
+            let test = files.get_mut(path).unwrap().tests.remove(name).unwrap();
+            merge_test(&mut merged, test);
+            touched.insert(path.clone());
+        }
+        let (path, name) = dupe.canonical();
+        files
+            .get_mut(path)
+            .unwrap()
+            .tests
+            .insert(name.clone(), merged);
+    }
+
+    touched
+        .into_iter()
+        .filter(|path| {
+            let File { properties, tests } = &files[path];
+            tests.is_empty() && is_empty(properties)
+        })
+        .map(|path| path.to_path_buf())
+        .collect()
+}
+
+/// Merges `test` into `merged`, with `test` taking precedence: its properties replace those of
+/// `merged` if it sets any, and its subtests replace those of the same name. Returns whether
+/// anything replaced differed from what replaced it.
+fn merge_test(merged: &mut Test, test: Test) -> bool {
+    let Test {
+        properties,
+        subtests,
+    } = test;
+    let mut conflicting = false;
+    if properties != TestProps::default() {
+        conflicting |= merged.properties != TestProps::default() && merged.properties != properties;
+        merged.properties = properties;
+    }
+    for (name, subtest) in subtests {
+        if let Some(replaced) = merged.subtests.insert(name, subtest.clone()) {
+            conflicting |= replaced.properties != subtest.properties;
+        }
+    }
+    conflicting
+}
+
+/// The result of [`merge_files`].
+#[derive(Debug, Default)]
+pub struct MergedFiles {
+    pub file: File,
+    /// Sections found in more than one of the merged files.
+    pub duplicates: BTreeSet<SectionHeader>,
+    /// Sections in `duplicates` for which the files disagree on the properties of the test or one
+    /// of its subtests, so that only those of the last file setting them were kept.
+    pub conflicts: BTreeSet<SectionHeader>,
+}
+
+/// Merges `files` (i.e., metadata for the same test file, left scattered by manual edits or a
+/// botched rebase) into one, with later files taking precedence: sections in more than one file
+/// are merged like [`dedupe`] does, and file properties are taken from the last file that sets
+/// any.
+pub fn merge_files(files: impl IntoIterator<Item = File>) -> MergedFiles {
+    let mut merged = MergedFiles::default();
+    for File { properties, tests } in files {
+        if !is_empty(&properties) {
+            merged.file.properties = properties;
+        }
+        for (name, test) in tests {
+            match merged.file.tests.entry(name) {
+                Entry::Vacant(entry) => {
+                    entry.insert(test);
+                }
+                Entry::Occupied(mut entry) => {
+                    if merge_test(entry.get_mut(), test) {
+                        merged.conflicts.insert(entry.key().clone());
+                    }
+                    merged.duplicates.insert(entry.key().clone());
+                }
+            }
+        }
+    }
+    merged
+}
+
+/// Returns whether `props` sets nothing, i.e., so that a file without tests can be removed.
+pub(crate) fn is_empty(props: &FileProps) -> bool {
+    let FileProps {
+        is_disabled,
+        prefs,
+        tags,
+        implementation_status,
+    } = props;
+    is_disabled.is_none() && prefs.is_none() && tags.is_none() && implementation_status.is_none()
+}
+
+#[test]
+fn merge_duplicates() {
+    use crate::{checkout::parse_metadata, metadata::format_file};
+
+    let gecko_checkout = Path::new("/gecko");
+    let files = [
+        (
+            "webgpu/cts/webgpu/api/cts.https.html.ini",
+            concat!(
+                "[cts.https.html?q=webgpu:api,a:*]\n",
+                "  expected: TIMEOUT\n",
+                "\n",
+                "  [:old]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "  [:both]\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            "webgpu/chunked/1/cts.https.html.ini",
+            concat!(
+                "[cts.https.html?q=webgpu:api,a:*]\n",
+                "  [:both]\n",
+                "    expected: PASS\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,b:*]\n",
+                "  expected: CRASH\n",
+            ),
+        ),
+    ];
+    let mut files = files
+        .into_iter()
+        .map(|(path, contents)| {
+            let path = gecko_checkout
+                .join("testing/web-platform/mozilla/meta")
+                .join(path);
+            parse_metadata(path, contents.to_owned()).unwrap()
+        })
+        .collect::<IndexMap<_, _>>();
+
+    let dupes = find_duplicate_tests(gecko_checkout, &files);
+    let [dupe] = &dupes[..] else {
+        panic!("expected exactly one duplicate, got {dupes:#?}");
+    };
+    assert_eq!(dupe.cts_path, "webgpu:api,a:*");
+    assert_eq!(dupe.locations.len(), 2);
+
+    let emptied = dedupe(&mut files, &dupes);
+    assert_eq!(emptied, [files.get_index(0).unwrap().0.to_path_buf()]);
+    assert!(find_duplicate_tests(gecko_checkout, &files).is_empty());
+    insta::assert_snapshot!(format_file(&files[1]), @r###"
+    [cts.https.html?q=webgpu:api,a:*]
+      expected: TIMEOUT
+      [:both]
+
+      [:old]
+        expected: FAIL
+
+
+    [cts.https.html?q=webgpu:api,b:*]
+      expected: CRASH
+    "###);
+}
+
+#[test]
+fn merge_scattered_files() {
+    use crate::{checkout::parse_metadata, metadata::format_file};
+
+    let files = [
+        concat!(
+            "[cts.https.html?q=webgpu:api,a:*]\n",
+            "  expected: TIMEOUT\n",
+            "\n",
+            "  [:x]\n",
+            "    expected: FAIL\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,b:*]\n",
+            "  expected: CRASH\n",
+        ),
+        concat!(
+            "[cts.https.html?q=webgpu:api,a:*]\n",
+            "  [:x]\n",
+            "    expected: [PASS, FAIL]\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,b:*]\n",
+            "  expected: CRASH\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,c:*]\n",
+            "  expected: ERROR\n",
+        ),
+    ];
+    let files = files.into_iter().map(|contents| {
+        let path = PathBuf::from("cts.https.html.ini");
+        parse_metadata(path, contents.to_owned()).unwrap().1
+    });
+
+    let MergedFiles {
+        file,
+        duplicates,
+        conflicts,
+    } = merge_files(files);
+    let names = |sections: &BTreeSet<SectionHeader>| {
+        sections
+            .iter()
+            .map(|name| name.0.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(&duplicates),
+        [
+            "cts.https.html?q=webgpu:api,a:*",
+            "cts.https.html?q=webgpu:api,b:*",
+        ]
+    );
+    assert_eq!(names(&conflicts), ["cts.https.html?q=webgpu:api,a:*"]);
+    insta::assert_snapshot!(format_file(&file), @r###"
+    [cts.https.html?q=webgpu:api,a:*]
+      expected: TIMEOUT
+      [:x]
+        expected: [PASS, FAIL]
+
+
+    [cts.https.html?q=webgpu:api,b:*]
+      expected: CRASH
+
+
+    [cts.https.html?q=webgpu:api,c:*]
+      expected: ERROR
+    "###);
+}
@@ -0,0 +1,352 @@
+//! Comparison of the expectations in two sets of metadata, i.e., from two checkouts, or two
+//! revisions of one.
+
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use enumset::EnumSetType;
+use indexmap::IndexMap;
+use strum::IntoEnumIterator;
+use whippit::metadata::SectionHeader;
+
+use crate::{
+    metadata::{BuildProfile, File, Platform, Subtest, SubtestOutcome, Test, TestOutcome},
+    shared::{Expected, FullyExpandedExpectedPropertyValue, TestPath},
+};
+
+/// The differences in expectations between two sets of metadata. Tests are matched by their CTS
+/// query where they have one, so tests moved between metadata files are compared, rather than
+/// being considered removed and added.
+#[derive(Clone, Debug, Default)]
+pub struct MetadataDiff {
+    pub added_tests: Vec<TestPath<'static>>,
+    pub removed_tests: Vec<TestPath<'static>>,
+    pub changed_tests: Vec<TestDiff>,
+}
+
+/// The differences in expectations of a test present in both sets of metadata.
+#[derive(Clone, Debug)]
+pub struct TestDiff {
+    /// Where the test is found in the newer metadata.
+    pub test: TestPath<'static>,
+    pub expected: Vec<ExpectedChange<TestOutcome>>,
+    pub added_subtests: Vec<String>,
+    pub removed_subtests: Vec<String>,
+    pub changed_subtests: Vec<(String, Vec<ExpectedChange<SubtestOutcome>>)>,
+}
+
+/// A change in the expected outcomes of a test or subtest for a single configuration.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ExpectedChange<Out>
+where
+    Out: EnumSetType,
+{
+    pub platform: Platform,
+    pub build_profile: BuildProfile,
+    pub old: Expected<Out>,
+    pub new: Expected<Out>,
+}
+
+impl MetadataDiff {
+    pub fn is_empty(&self) -> bool {
+        let Self {
+            added_tests,
+            removed_tests,
+            changed_tests,
+        } = self;
+        added_tests.is_empty() && removed_tests.is_empty() && changed_tests.is_empty()
+    }
+}
+
+/// Compares the expectations in `old` and `new`, each a set of metadata files read from the
+/// Gecko checkout at `old_checkout` or `new_checkout` (i.e., with
+/// [`crate::checkout::read_and_parse_all_metadata`]).
+pub fn diff(
+    old_checkout: &Path,
+    old: IndexMap<Arc<PathBuf>, File>,
+    new_checkout: &Path,
+    new: IndexMap<Arc<PathBuf>, File>,
+) -> MetadataDiff {
+    fn tests_by_key(
+        checkout: &Path,
+        files: IndexMap<Arc<PathBuf>, File>,
+    ) -> IndexMap<String, (TestPath<'static>, Test)> {
+        let mut tests = IndexMap::new();
+        for (path, file) in files {
+            let rel_path = path.strip_prefix(checkout).unwrap_or(&path);
+            for (SectionHeader(name), test) in file.tests {
+                let Ok(test_path) = TestPath::from_metadata_test(rel_path, &name) else {
+                    continue;
+                };
+                let test_path = test_path.into_owned();
+                let key = test_path
+                    .cts_path()
+                    .unwrap_or_else(|| test_path.runner_url_path().to_string());
+                tests.insert(key, (test_path, test));
+            }
+        }
+        tests
+    }
+
+    let mut old = tests_by_key(old_checkout, old);
+    let new = tests_by_key(new_checkout, new);
+
+    let mut diff = MetadataDiff::default();
+    for (key, (test_path, new_test)) in new {
+        let Some((_old_path, old_test)) = old.swap_remove(&key) else {
+            diff.added_tests.push(test_path);
+            continue;
+        };
+        if let Some(test_diff) = diff_test(test_path, &old_test, &new_test) {
+            diff.changed_tests.push(test_diff);
+        }
+    }
+    diff.removed_tests
+        .extend(old.into_values().map(|(test_path, _test)| test_path));
+
+    let MetadataDiff {
+        added_tests,
+        removed_tests,
+        changed_tests,
+    } = &mut diff;
+    added_tests.sort();
+    removed_tests.sort();
+    changed_tests.sort_by(|a, b| a.test.cmp(&b.test));
+    diff
+}
+
+fn diff_test(test: TestPath<'static>, old: &Test, new: &Test) -> Option<TestDiff> {
+    let expected = diff_expected(
+        old.properties.expected.as_ref(),
+        new.properties.expected.as_ref(),
+    );
+
+    let subtest_names = old
+        .subtests
+        .keys()
+        .chain(new.subtests.keys())
+        .collect::<BTreeSet<_>>();
+    let mut added_subtests = Vec::new();
+    let mut removed_subtests = Vec::new();
+    let mut changed_subtests = Vec::new();
+    for name in subtest_names {
+        let SectionHeader(subtest_name) = name;
+        match (old.subtests.get(name), new.subtests.get(name)) {
+            (Some(old), Some(new)) => {
+                let (Subtest { properties: old }, Subtest { properties: new }) = (old, new);
+                let changes = diff_expected(old.expected.as_ref(), new.expected.as_ref());
+                if !changes.is_empty() {
+                    changed_subtests.push((subtest_name.clone(), changes));
+                }
+            }
+            (None, Some(_new)) => added_subtests.push(subtest_name.clone()),
+            (Some(_old), None) => removed_subtests.push(subtest_name.clone()),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    (!expected.is_empty()
+        || !added_subtests.is_empty()
+        || !removed_subtests.is_empty()
+        || !changed_subtests.is_empty())
+    .then_some(TestDiff {
+        test,
+        expected,
+        added_subtests,
+        removed_subtests,
+        changed_subtests,
+    })
+}
+
+/// Compares expected outcomes for every configuration, treating unset expectations as the
+/// default outcome.
+fn diff_expected<Out>(
+    old: Option<&FullyExpandedExpectedPropertyValue<Out>>,
+    new: Option<&FullyExpandedExpectedPropertyValue<Out>>,
+) -> Vec<ExpectedChange<Out>>
+where
+    Out: Default + EnumSetType,
+{
+    let get =
+        |expected: Option<&FullyExpandedExpectedPropertyValue<Out>>, platform, build_profile| {
+            expected.map_or_else(Expected::default, |expected| {
+                expected.get(platform, build_profile)
+            })
+        };
+    Platform::iter()
+        .flat_map(|platform| {
+            BuildProfile::iter().map(move |build_profile| (platform, build_profile))
+        })
+        .map(|(platform, build_profile)| ExpectedChange {
+            platform,
+            build_profile,
+            old: get(old, platform, build_profile),
+            new: get(new, platform, build_profile),
+        })
+        .filter(|change| change.old != change.new)
+        .collect()
+}
+
+impl Display for MetadataDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn write_changes<Out>(
+            f: &mut Formatter<'_>,
+            indent: &str,
+            changes: &[ExpectedChange<Out>],
+        ) -> fmt::Result
+        where
+            Out: Display + EnumSetType,
+        {
+            for change in changes {
+                let ExpectedChange {
+                    platform,
+                    build_profile,
+                    old,
+                    new,
+                } = change;
+                writeln!(f, "{indent}{platform:?} {build_profile:?}: {old} -> {new}")?;
+            }
+            Ok(())
+        }
+
+        let Self {
+            added_tests,
+            removed_tests,
+            changed_tests,
+        } = self;
+
+        writeln!(
+            f,
+            "{} test(s) added, {} removed, and {} with changed expectations",
+            added_tests.len(),
+            removed_tests.len(),
+            changed_tests.len()
+        )?;
+        for (heading, tests) in [("added", added_tests), ("removed", removed_tests)] {
+            if !tests.is_empty() {
+                writeln!(f, "\n{heading}:")?;
+                for test in tests {
+                    writeln!(f, "  {}", test.runner_url_path())?;
+                }
+            }
+        }
+        if !changed_tests.is_empty() {
+            writeln!(f, "\nchanged:")?;
+        }
+        for test_diff in changed_tests {
+            let TestDiff {
+                test,
+                expected,
+                added_subtests,
+                removed_subtests,
+                changed_subtests,
+            } = test_diff;
+            writeln!(f, "  {}", test.runner_url_path())?;
+            write_changes(f, "    ", expected)?;
+            for name in added_subtests {
+                writeln!(f, "    [{name}] added")?;
+            }
+            for name in removed_subtests {
+                writeln!(f, "    [{name}] removed")?;
+            }
+            for (name, changes) in changed_subtests {
+                writeln!(f, "    [{name}]")?;
+                write_changes(f, "      ", changes)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn diff_expectations() {
+    use crate::checkout::parse_metadata;
+
+    let read = |checkout: &Path, files: &[(&str, &str)]| {
+        files
+            .iter()
+            .map(|(path, contents)| {
+                let path = checkout
+                    .join("testing/web-platform/mozilla/meta/webgpu")
+                    .join(path);
+                parse_metadata(path, (*contents).to_owned()).unwrap()
+            })
+            .collect::<IndexMap<_, _>>()
+    };
+    let old_checkout = Path::new("/old");
+    let old = read(
+        old_checkout,
+        &[(
+            "cts/webgpu/api/cts.https.html.ini",
+            concat!(
+                "[cts.https.html?q=webgpu:api,a:*]\n",
+                "  [:changed]\n",
+                "    expected:\n",
+                "      if os == \"win\": FAIL\n",
+                "\n",
+                "  [:removed]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,removed:*]\n",
+                "  expected: TIMEOUT\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,same:*]\n",
+                "  expected: CRASH\n",
+            ),
+        )],
+    );
+    let new_checkout = Path::new("/new");
+    let new = read(
+        new_checkout,
+        &[
+            (
+                "cts/webgpu/api/cts.https.html.ini",
+                concat!(
+                    "[cts.https.html?q=webgpu:api,a:*]\n",
+                    "  expected:\n",
+                    "    if os == \"mac\" and debug: TIMEOUT\n",
+                    "  [:added]\n",
+                    "\n",
+                    "  [:changed]\n",
+                    "    expected:\n",
+                    "      if os == \"win\": [PASS, FAIL]\n",
+                    "\n",
+                    "[cts.https.html?q=webgpu:api,added:*]\n",
+                    "  expected: CRASH\n",
+                ),
+            ),
+            (
+                "chunked/1/cts.https.html.ini",
+                concat!(
+                    "[cts.https.html?q=webgpu:api,same:*]\n",
+                    "  expected: CRASH\n",
+                ),
+            ),
+        ],
+    );
+
+    let diff = diff(old_checkout, old, new_checkout, new);
+    insta::assert_snapshot!(diff, @r###"
+    1 test(s) added, 1 removed, and 1 with changed expectations
+
+    added:
+      _mozilla/webgpu/cts/webgpu/api/cts.https.html?q=webgpu:api,added:*
+
+    removed:
+      _mozilla/webgpu/cts/webgpu/api/cts.https.html?q=webgpu:api,removed:*
+
+    changed:
+      _mozilla/webgpu/cts/webgpu/api/cts.https.html?q=webgpu:api,a:*
+        MacOs Debug: OK -> TIMEOUT
+        [:added] added
+        [:removed] removed
+        [:changed]
+          Windows Debug: FAIL -> [PASS, FAIL]
+          Windows Optimized: FAIL -> [PASS, FAIL]
+    "###);
+    assert!(!diff.is_empty());
+}
@@ -0,0 +1,134 @@
+//! Downloading of reports given as URLs instead of paths, so that CI artifact URLs can be passed
+//! straight to the command line.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::error::Error;
+
+/// Returns whether `path`, as given on the command line, is an HTTP(S) URL to download.
+pub fn is_url(path: &Path) -> bool {
+    path.to_str().map_or(false, |path| {
+        path.starts_with("https://") || path.starts_with("http://")
+    })
+}
+
+/// Builds an HTTP agent that gives up on unresponsive servers, but not on large downloads.
+pub fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(30))
+        .timeout_read(Duration::from_secs(60))
+        .build()
+}
+
+/// Returns where [`download`] saves `url` in `dir`: a file named after the last segment of the
+/// URL's path, in a directory named after a hash of the URL, since reports from different CI
+/// tasks usually have the same file name.
+pub fn download_path(url: &str, dir: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    let file_name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|url| url.rsplit('/').next())
+        .filter(|file_name| !file_name.is_empty())
+        .unwrap_or("wptreport.json");
+    dir.join(format!("{:016x}", hasher.finish()))
+        .join(file_name)
+}
+
+/// Downloads `url` into `dir`, returning the path of the downloaded file (see [`download_path`]).
+/// The response is streamed to disk, rather than buffered in memory. If `url` was already
+/// downloaded into `dir` by an earlier invocation, it's not downloaded again.
+///
+/// Downloads are written to a `.part` file next to the final path first, and only moved there once
+/// complete, so that an interrupted download is never mistaken for a complete one. The `.part`
+/// file is removed if downloading fails.
+pub fn download(agent: &ureq::Agent, url: &str, dir: &Path) -> Result<PathBuf, Error> {
+    let path = download_path(url, dir);
+    if path.is_file() {
+        tracing::debug!("using {url} downloaded earlier to {}", path.display());
+        return Ok(path);
+    }
+    tracing::info!("downloading {url}…");
+    let response = agent.get(url).call().map_err(|source| Error::Download {
+        url: url.to_owned(),
+        source: Box::new(source),
+    })?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|source| Error::CreateParentDirs {
+            path: path.clone(),
+            source,
+        })?;
+    }
+    let part_path = {
+        let mut part_path = path.clone().into_os_string();
+        part_path.push(".part");
+        PathBuf::from(part_path)
+    };
+    let written = fs::File::create(&part_path)
+        .map_err(|source| Error::WriteFile {
+            path: part_path.clone(),
+            source,
+        })
+        .and_then(|mut file| {
+            io::copy(&mut response.into_reader(), &mut file).map_err(|source| Error::ReadDownload {
+                url: url.to_owned(),
+                source,
+            })
+        })
+        .and_then(|_len| {
+            fs::rename(&part_path, &path).map_err(|source| Error::WriteFile {
+                path: path.clone(),
+                source,
+            })
+        });
+    if let Err(e) = written {
+        // N.B.: This fails if the file was never created, which is fine.
+        let _ = fs::remove_file(&part_path);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+#[test]
+fn download_paths() {
+    assert!(is_url(Path::new("https://example.com/wptreport.json")));
+    assert!(!is_url(Path::new("wptreport.json")));
+    assert!(!is_url(Path::new("/tmp/https://wptreport.json")));
+
+    let dir = Path::new("downloads");
+    let a = download_path(
+        "https://firefox-ci-tc.services.mozilla.com/api/queue/v1/task/A/artifacts/public/test_info/wptreport.json",
+        dir,
+    );
+    let b = download_path(
+        "https://firefox-ci-tc.services.mozilla.com/api/queue/v1/task/B/artifacts/public/test_info/wptreport.json",
+        dir,
+    );
+    assert_ne!(a, b);
+    assert!(a.starts_with(dir));
+    assert!(a.ends_with("wptreport.json"));
+    assert!(download_path("https://example.com/reports.zip?raw=1", dir).ends_with("reports.zip"));
+    assert!(download_path("https://example.com/", dir).ends_with("wptreport.json"));
+}
+
+#[test]
+fn download_reuses_earlier_downloads() {
+    let dir = std::env::temp_dir().join(format!("moz-webgpu-cts-download-{}", std::process::id()));
+    // N.B.: Nothing listens on this port, so this would fail if it were downloaded again.
+    let url = "http://127.0.0.1:9/wptreport.json";
+    let path = download_path(url, &dir);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, "{}").unwrap();
+
+    let downloaded = download(&agent(), url, &dir);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(downloaded.unwrap(), path);
+}
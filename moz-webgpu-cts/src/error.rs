@@ -0,0 +1,413 @@
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    io,
+    path::PathBuf,
+    process::ExitStatus,
+    sync::Arc,
+};
+
+use format::lazy_format;
+use joinery::JoinableIterator;
+use miette::{Diagnostic, LabeledSpan, NamedSource, Severity, SourceCode, SourceSpan};
+use whippit::{metadata::SectionHeader, reexport::chumsky::prelude::Rich};
+
+use crate::{
+    metadata::ConditionMismatch,
+    report::{UnknownPlatform, Unrecognized},
+};
+
+/// The error type for all fallible operations in this crate.
+///
+/// Errors are not reported anywhere by this crate; it is the responsibility of callers (i.e., the
+/// CLI) to render them, usually via [`miette::Report`].
+#[derive(Debug, Diagnostic, thiserror::Error)]
+pub enum Error {
+    #[error("failed to find a Mercurial or Git repository in the CWD or any of its parents")]
+    #[diagnostic(help("specify a Gecko checkout explicitly with `--gecko-checkout`"))]
+    CheckoutNotFound,
+    #[error("failed to parse WPT report glob")]
+    ParseReportGlob {
+        #[related]
+        diagnostics: Vec<GlobDiagnostic>,
+    },
+    #[error("failed to parse metadata file glob")]
+    ParseMetadataGlob {
+        #[related]
+        diagnostics: Vec<GlobDiagnostic>,
+    },
+    #[error(
+        "failed to enumerate files for glob `{glob}`{}",
+        path.as_ref().map(|p| format!(" at path {}", p.display())).unwrap_or_default()
+    )]
+    EnumerateFiles {
+        glob: String,
+        path: Option<PathBuf>,
+        #[source]
+        source: wax::WalkError,
+    },
+    #[error("reports were specified exclusively via glob search, but none were found")]
+    NoReportsFound,
+    #[error("failed to read {}", path.display())]
+    ReadFile {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to parse metadata at {}", path.display())]
+    ParseMetadata {
+        path: Arc<PathBuf>,
+        #[related]
+        errors: Vec<MetadataParseError>,
+    },
+    #[error("failed to read WPT execution report from {}", path.display())]
+    ParseReport {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error(
+        "WPT execution report at {} is for a platform not recognized by this tool ({run_info})",
+        path.display()
+    )]
+    #[diagnostic(help("use `--platform-map` to attribute it to a platform"))]
+    UnknownPlatform {
+        path: PathBuf,
+        run_info: UnknownPlatform,
+    },
+    #[error(
+        "WPT execution report at {} contains data not recognized by this tool: {}",
+        path.display(),
+        unrecognized.iter().join_with(", ")
+    )]
+    #[diagnostic(help(
+        "omit `--deny-unknown-report-fields` to ignore unrecognized data, or update this tool"
+    ))]
+    UnrecognizedReportContents {
+        path: PathBuf,
+        unrecognized: Vec<Unrecognized>,
+    },
+    #[error(
+        "failed to parse line {line_num} of never-modify list at {}: {line:?}",
+        path.display()
+    )]
+    #[diagnostic(help(
+        "expected a test URL path starting with `/`, a CTS query starting with `webgpu:`, or a comment starting with `#`"
+    ))]
+    ParseNeverModifyList {
+        path: PathBuf,
+        line_num: usize,
+        line: String,
+    },
+    #[error(
+        "failed to parse line {line_num} of owners file at {}: {reason}: {line:?}",
+        path.display()
+    )]
+    #[diagnostic(help(
+        "expected `<PATTERN> <OWNER>`, where `<PATTERN>` is a test URL path or a CTS query, or a comment starting with `#`"
+    ))]
+    ParseOwners {
+        path: PathBuf,
+        line_num: usize,
+        line: String,
+        reason: String,
+    },
+    #[error(
+        "failed to parse line {line_num} of rename map at {}: {reason}: {line:?}",
+        path.display()
+    )]
+    #[diagnostic(help(
+        "expected `<OLD> <NEW>`, where both are test URL paths or CTS queries, or a comment starting with `#`"
+    ))]
+    ParseRenameMap {
+        path: PathBuf,
+        line_num: usize,
+        line: String,
+        reason: String,
+    },
+    #[error(
+        "failed to parse line {line_num} of reconciliation script at {}: {reason}: {line:?}",
+        path.display()
+    )]
+    #[diagnostic(help(
+        "expected `preset <PRESET>`, `keep <OUTCOME>`, `merge <OUTCOME>`, or a comment starting with `#`"
+    ))]
+    ParseStrategyScript {
+        path: PathBuf,
+        line_num: usize,
+        line: String,
+        reason: String,
+    },
+    #[error("error while ensuring parent directories exist for `{}`", path.display())]
+    CreateParentDirs {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("error while writing to `{}`", path.display())]
+    WriteFile {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to write one or more metadata files")]
+    WriteMetadata {
+        #[related]
+        errors: Vec<Error>,
+    },
+    #[error("cancelled by user")]
+    Cancelled,
+    #[error("cancelled by user after writing {num_done} of {num_total} metadata file(s)")]
+    #[diagnostic(help(
+        "files already written are complete; run `update-expected --resume` to write the rest"
+    ))]
+    WriteCancelled {
+        num_done: usize,
+        num_total: usize,
+        #[related]
+        errors: Vec<Error>,
+    },
+    #[error("failed to run editor `{editor}`")]
+    #[diagnostic(help("set `EDITOR` to the command of an editor to use"))]
+    RunEditor {
+        editor: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("editor `{editor}` exited unsuccessfully ({status})")]
+    EditorFailed { editor: String, status: ExitStatus },
+    #[error(
+        "expected edited metadata to contain only the section for `{}`, found {}",
+        expected.0,
+        found.iter().map(|name| lazy_format!("`{}`", name.0)).join_with(", ")
+    )]
+    EditedUnexpectedTests {
+        expected: SectionHeader,
+        found: Vec<SectionHeader>,
+    },
+    #[error("failed to run `{program}`")]
+    #[diagnostic(help("`{program}` must be installed to read files at a revision"))]
+    RunVcs {
+        program: &'static str,
+        #[source]
+        source: io::Error,
+    },
+    #[error("`{command}` exited unsuccessfully ({status}): {stderr}")]
+    VcsFailed {
+        command: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+    #[error("failed to remove now-empty metadata file {}", path.display())]
+    RemoveFile {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to parse configuration at {}", path.display())]
+    ParseConfig {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("failed to parse session state at {}", path.display())]
+    #[diagnostic(help("delete the file to start the session over"))]
+    ParseSession {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("failed to parse manifest of pending metadata writes at {}", path.display())]
+    #[diagnostic(help("delete the file, and process reports again"))]
+    ParseWriteManifest {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("failed to parse CTS listing at {}", path.display())]
+    #[diagnostic(help(
+        "expected `listing_meta.json` from a CTS build, or a file with one query per line"
+    ))]
+    ParseCtsListing {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("failed to download {url}")]
+    Download {
+        url: String,
+        #[source]
+        source: Box<ureq::Error>,
+    },
+    #[error("failed to read response while downloading {url}")]
+    ReadDownload {
+        url: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to request {url} from Taskcluster")]
+    TaskclusterRequest {
+        url: String,
+        #[source]
+        source: Box<ureq::Error>,
+    },
+    #[error("failed to read response to {url} from Taskcluster")]
+    ReadTaskclusterResponse {
+        url: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to parse response to {url} from Taskcluster")]
+    ParseTaskclusterResponse {
+        url: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("`BUGZILLA_API_KEY` is not set")]
+    #[diagnostic(help(
+        "create an API key in Bugzilla's preferences, or pass `--dry-run` to list bugs without \
+         filing them"
+    ))]
+    BugzillaApiKeyNotSet,
+    #[error("failed to request {url} from Bugzilla")]
+    #[diagnostic(help("check that `BUGZILLA_API_KEY` is set to a valid API key"))]
+    BugzillaRequest {
+        url: String,
+        #[source]
+        source: Box<ureq::Error>,
+    },
+    #[error("failed to read response to {url} from Bugzilla")]
+    ReadBugzillaResponse {
+        url: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to parse response to {url} from Bugzilla")]
+    ParseBugzillaResponse {
+        url: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("failed to lock metadata via {}", path.display())]
+    LockMetadata {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error(
+        "metadata is locked by another invocation{} via {}",
+        holder.map(|pid| format!(" (process {pid})")).unwrap_or_default(),
+        path.display()
+    )]
+    #[diagnostic(help(
+        "wait for it to finish, or pass `--no-lock` if you are sure nothing else is writing metadata"
+    ))]
+    MetadataLocked { path: PathBuf, holder: Option<u32> },
+    #[error(
+        "metadata for {} would not be read back as intended once written:\n{}",
+        path.display(),
+        mismatches.iter().map(|mismatch| lazy_format!("  {mismatch}")).join_with("\n")
+    )]
+    #[diagnostic(help(
+        "this is a bug in this tool; please report it, with the metadata and reports processed"
+    ))]
+    UnfaithfulConditions {
+        path: PathBuf,
+        mismatches: Vec<ConditionMismatch>,
+    },
+    #[error("failed to find a directory to store state in")]
+    #[diagnostic(help("set `MOZBUILD_STATE_PATH` or `HOME`"))]
+    StateDirNotFound,
+    #[error("failed to create trace output file at {}", path.display())]
+    CreateTraceOutput {
+        path: PathBuf,
+        #[source]
+        source: tracing_flame::Error,
+    },
+}
+
+/// A single syntax error found while parsing a [`crate::metadata::File`], with enough context to
+/// render a source snippet.
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("{inner}")]
+pub struct MetadataParseError {
+    #[label]
+    span: SourceSpan,
+    #[source_code]
+    source_code: NamedSource,
+    inner: Rich<'static, char>,
+}
+
+impl MetadataParseError {
+    pub fn new(path: &Arc<PathBuf>, file_contents: &Arc<String>, error: Rich<'_, char>) -> Self {
+        let span = error.span();
+        Self {
+            source_code: NamedSource::new(path.to_str().unwrap(), file_contents.clone()),
+            span: SourceSpan::new(span.start.into(), (span.end - span.start).into()),
+            inner: error.into_owned(),
+        }
+    }
+
+    /// The 1-based line and column at which this error starts.
+    pub fn line_and_column(&self) -> (usize, usize) {
+        let contents = self
+            .source_code
+            .read_span(&self.span, 0, 0)
+            .expect("internal error: metadata parse error span out of bounds");
+        (contents.line() + 1, contents.column() + 1)
+    }
+}
+
+/// A diagnostic emitted by [`wax`] while parsing a glob.
+pub struct GlobDiagnostic(pub Box<dyn Diagnostic + Send + Sync>);
+
+impl Debug for GlobDiagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for GlobDiagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for GlobDiagnostic {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl Diagnostic for GlobDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.0.code()
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.0.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.0.help()
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.0.url()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.0.source_code()
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        self.0.labels()
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        self.0.related()
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        self.0.diagnostic_source()
+    }
+}
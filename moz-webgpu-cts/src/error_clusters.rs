@@ -0,0 +1,232 @@
+//! Clustering of `ERROR` outcomes in reports by their messages, so that different problems (i.e.,
+//! device loss, validation errors, or harness crashes) can be told apart in triage, rather than
+//! being counted as one.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    error::Error,
+    metadata::{Platform, TestOutcome},
+    report::{ExecutionReport, RunInfo, TestExecutionEntry, TestExecutionResult},
+    shared::{Browser, TestPath},
+};
+
+/// What [`normalize_message`] returns for `ERROR`s reported without a message.
+pub const NO_MESSAGE: &str = "(no message)";
+
+/// Reduces `message` to its first line, with hexadecimal numbers (i.e., addresses) replaced by
+/// `ADDR` and other numbers replaced by `N`, so that messages differing only in those group
+/// together.
+pub fn normalize_message(message: &str) -> String {
+    let message = message.lines().map(str::trim).find(|line| !line.is_empty());
+    let Some(message) = message else {
+        return NO_MESSAGE.to_owned();
+    };
+
+    let mut normalized = String::with_capacity(message.len());
+    let mut chars = message.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        if !c.is_ascii_digit() {
+            normalized.push(c);
+            continue;
+        }
+        let rest = &message[idx..];
+        let is_hex = rest.starts_with("0x") || rest.starts_with("0X");
+        if is_hex {
+            chars.next();
+        }
+        while chars
+            .next_if(|(_idx, c)| {
+                if is_hex {
+                    c.is_ascii_hexdigit()
+                } else {
+                    c.is_ascii_digit()
+                }
+            })
+            .is_some()
+        {}
+        normalized.push_str(if is_hex { "ADDR" } else { "N" });
+    }
+    normalized
+}
+
+/// Tests whose execution reported `ERROR` with the same [normalized](normalize_message) message.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ErrorCluster {
+    /// Tests in this cluster, as run by `wptrunner`, i.e.,
+    /// `/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter,info:*`.
+    pub tests: BTreeSet<String>,
+    /// How many times a test in this cluster reported `ERROR` with its message, across all runs.
+    pub occurrences: usize,
+}
+
+/// [`ErrorCluster`]s found in reports, per platform and normalized message.
+#[derive(Clone, Debug, Default)]
+pub struct ErrorClusters(BTreeMap<Platform, BTreeMap<String, ErrorCluster>>);
+
+impl ErrorClusters {
+    /// Clusters `ERROR` outcomes of tests in `exec_reports`, resolved as run in `browser`.
+    pub fn from_reports<'a>(
+        browser: Browser,
+        exec_reports: impl IntoIterator<Item = Result<ExecutionReport<'a>, Error>>,
+    ) -> Result<Self, Error> {
+        let mut clusters = BTreeMap::<Platform, BTreeMap<String, ErrorCluster>>::new();
+        for exec_report in exec_reports {
+            let ExecutionReport {
+                run_info:
+                    RunInfo {
+                        platform,
+                        build_profile: _,
+                        subsuite: _,
+                        properties: _,
+                    },
+                entries,
+                unrecognized: _,
+            } = exec_report?;
+            for entry in entries {
+                let TestExecutionEntry {
+                    test_name,
+                    subsuite: _,
+                    message,
+                    result,
+                } = entry;
+                let TestExecutionResult::Complete {
+                    outcome: TestOutcome::Error,
+                    subtests: _,
+                } = result
+                else {
+                    continue;
+                };
+                let test_path = TestPath::from_execution_report(browser, &test_name).unwrap();
+                let cluster = clusters
+                    .entry(platform)
+                    .or_default()
+                    .entry(normalize_message(message.as_deref().unwrap_or_default()))
+                    .or_default();
+                cluster
+                    .tests
+                    .insert(format!("/{}", test_path.runner_url_path()));
+                cluster.occurrences += 1;
+            }
+        }
+        Ok(Self(clusters))
+    }
+
+    /// Returns clusters found for `platform` by normalized message, from most to fewest tests.
+    pub fn for_platform(&self, platform: Platform) -> Vec<(&str, &ErrorCluster)> {
+        let mut clusters = self
+            .0
+            .get(&platform)
+            .into_iter()
+            .flatten()
+            .map(|(message, cluster)| (&**message, cluster))
+            .collect::<Vec<_>>();
+        clusters.sort_by_key(|(_message, cluster)| std::cmp::Reverse(cluster.tests.len()));
+        clusters
+    }
+}
+
+#[test]
+fn normalize_messages() {
+    assert_eq!(
+        normalize_message("Device lost at 0x7ffd5a3c (reason 2)\nstack:\n  at foo.js:12:3"),
+        "Device lost at ADDR (reason N)"
+    );
+    assert_eq!(
+        normalize_message("\n  Validation error: buffer 17 is too small: 256 < 1024  "),
+        "Validation error: buffer N is too small: N < N"
+    );
+    assert_eq!(normalize_message("rgba8unorm"), "rgbaNunorm");
+    assert_eq!(normalize_message(""), NO_MESSAGE);
+}
+
+#[test]
+fn cluster_errors() {
+    use crate::metadata::BuildProfile;
+
+    let run = |platform, entries: Vec<(&'static str, TestOutcome, Option<&'static str>)>| {
+        Ok(ExecutionReport {
+            run_info: RunInfo {
+                platform,
+                build_profile: BuildProfile::Debug,
+                subsuite: None,
+                properties: Default::default(),
+            },
+            entries: entries
+                .into_iter()
+                .map(|(query, outcome, message)| TestExecutionEntry {
+                    test_name: format!("/_mozilla/webgpu/cts/webgpu/cts.https.html?q={query}")
+                        .into(),
+                    subsuite: None,
+                    message: message.map(Into::into),
+                    result: TestExecutionResult::Complete {
+                        outcome,
+                        subtests: vec![],
+                    },
+                })
+                .collect(),
+            unrecognized: Default::default(),
+        })
+    };
+    let clusters = ErrorClusters::from_reports(
+        Browser::Firefox,
+        [
+            run(
+                Platform::Windows,
+                vec![
+                    (
+                        "webgpu:a:*",
+                        TestOutcome::Error,
+                        Some("Device lost at 0x1f"),
+                    ),
+                    (
+                        "webgpu:b:*",
+                        TestOutcome::Error,
+                        Some("Device lost at 0x2e"),
+                    ),
+                    ("webgpu:c:*", TestOutcome::Error, None),
+                    ("webgpu:d:*", TestOutcome::Ok, None),
+                ],
+            ),
+            run(
+                Platform::Windows,
+                vec![(
+                    "webgpu:a:*",
+                    TestOutcome::Error,
+                    Some("Device lost at 0x3d"),
+                )],
+            ),
+            run(
+                Platform::Linux,
+                vec![("webgpu:a:*", TestOutcome::Error, Some("harness crashed"))],
+            ),
+        ],
+    )
+    .unwrap();
+
+    insta::assert_debug_snapshot!(clusters.for_platform(Platform::Windows), @r###"
+    [
+        (
+            "Device lost at ADDR",
+            ErrorCluster {
+                tests: {
+                    "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:a:*",
+                    "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:b:*",
+                },
+                occurrences: 3,
+            },
+        ),
+        (
+            "(no message)",
+            ErrorCluster {
+                tests: {
+                    "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:c:*",
+                },
+                occurrences: 1,
+            },
+        ),
+    ]
+    "###);
+    assert_eq!(clusters.for_platform(Platform::Linux).len(), 1);
+    assert!(clusters.for_platform(Platform::MacOs).is_empty());
+}
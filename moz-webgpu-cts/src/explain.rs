@@ -0,0 +1,653 @@
+//! Step-by-step explanations of how [`reconcile`] changes the expectations of a single test, for
+//! auditing a [`ReportProcessingPreset`] without reading its implementation.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Debug, Display, Formatter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use enumset::{EnumSet, EnumSetType};
+use indexmap::IndexMap;
+use strum::IntoEnumIterator;
+use whippit::metadata::SectionHeader;
+
+use crate::{
+    error::Error,
+    metadata::{BuildProfile, File, Platform, SubtestOutcome, Test, TestOutcome, TestProps},
+    process_reports::{
+        reconcile, taint_subtest_timeouts_by_suspicion, ReconcileOptions, Reconciliation,
+        ReportProcessingPreset,
+    },
+    report::{ExecutionReport, TestExecutionEntry, TestExecutionResult},
+    shared::{Expected, TestPath},
+    strategy::{PruneStaleIntermittents, ResetTimeouts},
+    test_pattern::TestPattern,
+};
+
+/// The result of [`explain`].
+#[derive(Debug)]
+pub struct Explanation {
+    pub test: TestPath<'static>,
+    pub preset: ReportProcessingPreset,
+    /// The number of runs processed, including those that did not run the test.
+    pub num_runs: usize,
+    pub fate: Fate,
+    /// Steps for the test itself. Empty unless `fate` is [`Fate::Reconciled`].
+    pub test_steps: Vec<Step>,
+    /// Like `test_steps`, but for each subtest, by name.
+    pub subtest_steps: BTreeMap<String, Vec<Step>>,
+    /// Whether the test was run in a `wptrunner` subsuite, whose expectations are reconciled
+    /// separately, and not explained.
+    pub has_subsuite_runs: bool,
+}
+
+/// What happens to a test's metadata as a whole.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Fate {
+    /// Expectations are resolved per configuration, as described by [`Step`]s.
+    Reconciled,
+    /// The test has `keep_expectations` set, so its metadata is left untouched.
+    KeptExpectations,
+    /// The test was quarantined on the given day, so its metadata is left untouched.
+    Quarantined(NaiveDate),
+    /// The test was not found in any report, so its metadata is removed.
+    Removed,
+    /// The test was found neither in metadata nor in any report.
+    NotFound,
+}
+
+/// How the expectations of a test or subtest are resolved for a single configuration.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Step {
+    pub platform: Platform,
+    pub build_profile: BuildProfile,
+    /// Expected outcomes in current metadata, or `None` if no expectations are set.
+    pub current: Option<String>,
+    /// Outcomes observed in reports, or `None` if the test or subtest was not run.
+    pub reported: Option<String>,
+    pub resolution: Resolution,
+    /// Whether `TIMEOUT` and `NOTRUN` were made to be expected together afterwards; see
+    /// [`taint_subtest_timeouts_by_suspicion`].
+    pub tainted: bool,
+    /// Expected outcomes after reconciliation.
+    pub resolved: String,
+}
+
+/// The rule of a [`ReportProcessingPreset`] applied in a [`Step`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Resolution {
+    KeptExpectations,
+    NoExpectations,
+    ResetAll,
+    AlreadyExpected,
+    Contradictory,
+    NotReported,
+    Merged,
+    Observed,
+    Unobserved,
+    TimeoutsInvolved,
+    TimeoutsNotInvolved,
+    NotIntermittent,
+    StaleIntermittentsPruned,
+}
+
+impl Display for Resolution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::KeptExpectations => {
+                "`keep_expectations` is set, so current expectations are kept"
+            }
+            Self::NoExpectations => "no expectations are set, so reported outcomes are taken",
+            Self::ResetAll => "reported outcomes replace current expectations",
+            Self::AlreadyExpected => {
+                "reported outcomes are already expected, so current expectations are kept"
+            }
+            Self::Contradictory => {
+                "reported outcomes contradict current expectations, so they replace them"
+            }
+            Self::NotReported => "not reported, so current expectations are kept",
+            Self::Merged => "reported outcomes are merged into current expectations",
+            Self::Observed => concat!(
+                "configuration is covered by reports, ",
+                "so reported outcomes replace current expectations"
+            ),
+            Self::Unobserved => concat!(
+                "configuration is not covered by any report, ",
+                "so current expectations are kept"
+            ),
+            Self::TimeoutsInvolved => concat!(
+                "current expectations or reported outcomes involve timeouts, ",
+                "so reported outcomes replace current expectations"
+            ),
+            Self::TimeoutsNotInvolved => concat!(
+                "neither current expectations nor reported outcomes involve timeouts, ",
+                "so current expectations are kept"
+            ),
+            Self::NotIntermittent => "current expectations aren't intermittent, so they are kept",
+            Self::StaleIntermittentsPruned => concat!(
+                "current expectations are intermittent, ",
+                "so outcomes that weren't reported are removed"
+            ),
+        })
+    }
+}
+
+type Outcomes<Out> = BTreeMap<(Platform, BuildProfile), Expected<Out>>;
+
+/// Explains how `test` is reconciled against `exec_reports` with `preset`, as
+/// [`crate::process_reports::update_expected`] would.
+///
+/// The outcome of each configuration is the one computed by [`reconcile`]; [`Resolution`]s
+/// describe which rule of `preset` produced it.
+pub fn explain<'a>(
+    gecko_checkout: &Path,
+    meta_files_by_path: IndexMap<Arc<PathBuf>, File>,
+    exec_reports: impl IntoIterator<Item = Result<ExecutionReport<'a>, Error>>,
+    test: &TestPath<'_>,
+    preset: ReportProcessingPreset,
+) -> Result<Explanation, Error> {
+    let current = find_test(
+        gecko_checkout,
+        meta_files_by_path
+            .iter()
+            .map(|(path, file)| (path.as_path(), file)),
+        test,
+    );
+
+    let mut num_runs = 0;
+    let mut covered = BTreeSet::new();
+    let mut has_subsuite_runs = false;
+    let mut reported_test = Outcomes::<TestOutcome>::new();
+    let mut reported_subtests = BTreeMap::<String, Outcomes<SubtestOutcome>>::new();
+    let mut runs = Vec::new();
+    for exec_report in exec_reports {
+        let mut exec_report = exec_report?;
+        num_runs += 1;
+
+        let run_info = &exec_report.run_info;
+        let config = (run_info.platform, run_info.build_profile);
+        let run_in_subsuite = run_info.subsuite.is_some();
+        if !run_in_subsuite {
+            covered.insert(config);
+        }
+
+        fn accumulate<Out>(
+            outcomes: &mut Outcomes<Out>,
+            config: (Platform, BuildProfile),
+            outcome: Out,
+        ) where
+            Out: EnumSetType,
+        {
+            outcomes
+                .entry(config)
+                .and_modify(|expected| *expected |= outcome)
+                .or_insert_with(|| Expected::permanent(outcome));
+        }
+
+        // N.B.: Other tests' entries are dropped, so that only `test` is reconciled below.
+        exec_report.entries.retain(|entry| {
+            let TestExecutionEntry {
+                test_name,
+                subsuite,
+                message: _,
+                result,
+            } = entry;
+            let is_test = TestPath::from_execution_report(test.browser, test_name)
+                .map_or(false, |reported| same_test(&reported, test));
+            if !is_test {
+                return false;
+            }
+            if run_in_subsuite || subsuite.is_some() {
+                has_subsuite_runs = true;
+                return true;
+            }
+
+            let (outcome, subtests) = match result {
+                TestExecutionResult::Complete { outcome, subtests } => (*outcome, subtests),
+                TestExecutionResult::JobMaybeTimedOut { subtests } => {
+                    (TestOutcome::Timeout, subtests)
+                }
+            };
+            accumulate(&mut reported_test, config, outcome);
+            for subtest in subtests {
+                let outcomes = reported_subtests
+                    .entry(subtest.subtest_name.clone().into_owned())
+                    .or_default();
+                accumulate(outcomes, config, subtest.outcome);
+            }
+            true
+        });
+        runs.push(Ok(exec_report));
+    }
+
+    let Reconciliation {
+        files,
+        reported: _,
+        summary: _,
+    } = reconcile(
+        gecko_checkout,
+        meta_files_by_path,
+        runs,
+        &ReconcileOptions {
+            strategy: preset.strategy(),
+            only_tests: Some(vec![TestPattern::UrlGlob(format!(
+                "/{}",
+                test.runner_url_path()
+            ))]),
+            browser: test.browser,
+            ..Default::default()
+        },
+    )?;
+    let resolved = find_test(
+        gecko_checkout,
+        files.iter().map(|(path, file)| (path.as_path(), file)),
+        test,
+    );
+
+    let fate = if current.is_none() && reported_test.is_empty() {
+        Fate::NotFound
+    } else if current
+        .as_ref()
+        .map_or(false, |test| test.properties.keep_expectations)
+    {
+        Fate::KeptExpectations
+    } else if let Some(date) = current
+        .as_ref()
+        .and_then(|test| test.properties.quarantined)
+    {
+        Fate::Quarantined(date)
+    } else if resolved.is_none() && reported_test.is_empty() {
+        Fate::Removed
+    } else {
+        Fate::Reconciled
+    };
+
+    let mut test_steps = Vec::new();
+    let mut subtest_steps = BTreeMap::new();
+    if fate == Fate::Reconciled {
+        test_steps = explain_entry(
+            preset,
+            current.as_ref().map(|test| &test.properties),
+            Some(&reported_test),
+            resolved.as_ref().map(|test| &test.properties),
+            &covered,
+            ResetTimeouts::TEST_OUTCOMES,
+            |_expected| (),
+        );
+
+        let subtest_props = |test: &Option<Test>, name: &str| {
+            test.as_ref()
+                .and_then(|test| test.subtests.get(&SectionHeader(name.to_owned())))
+                .map(|subtest| subtest.properties.clone())
+        };
+        let subtest_names = current
+            .iter()
+            .flat_map(|test| test.subtests.keys().map(|SectionHeader(name)| name))
+            .chain(reported_subtests.keys())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        for name in subtest_names {
+            let steps = explain_entry(
+                preset,
+                subtest_props(&current, &name).as_ref(),
+                reported_subtests.get(&name),
+                subtest_props(&resolved, &name).as_ref(),
+                &covered,
+                ResetTimeouts::SUBTEST_OUTCOMES,
+                taint_subtest_timeouts_by_suspicion,
+            );
+            subtest_steps.insert(name, steps);
+        }
+    }
+
+    Ok(Explanation {
+        test: test.clone().into_owned(),
+        preset,
+        num_runs,
+        fate,
+        test_steps,
+        subtest_steps,
+        has_subsuite_runs,
+    })
+}
+
+/// Whether `a` and `b` are the same test for the purposes of [`reconcile`], which identifies CTS
+/// tests by their query alone.
+pub(crate) fn same_test(a: &TestPath<'_>, b: &TestPath<'_>) -> bool {
+    fn cts_query<'a>(test_path: &'a TestPath<'_>) -> Option<&'a str> {
+        test_path
+            .variant
+            .as_deref()
+            .and_then(|variant| variant.strip_prefix("?q="))
+            .filter(|query| query.starts_with("webgpu:"))
+            .filter(|_query| test_path.path.ends_with("cts.https.html"))
+    }
+    match (cts_query(a), cts_query(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn find_test<'a>(
+    gecko_checkout: &Path,
+    files: impl IntoIterator<Item = (&'a Path, &'a File)>,
+    test: &TestPath<'_>,
+) -> Option<Test> {
+    files.into_iter().find_map(|(path, file)| {
+        let rel_path = path.strip_prefix(gecko_checkout).unwrap();
+        file.tests
+            .iter()
+            .find(|(SectionHeader(name), _test)| {
+                TestPath::from_metadata_test(rel_path, name)
+                    .map_or(false, |meta_path| same_test(&meta_path, test))
+            })
+            .map(|(_name, test)| test.clone())
+    })
+}
+
+fn explain_entry<Out>(
+    preset: ReportProcessingPreset,
+    current: Option<&TestProps<Out>>,
+    reported: Option<&Outcomes<Out>>,
+    resolved: Option<&TestProps<Out>>,
+    covered: &BTreeSet<(Platform, BuildProfile)>,
+    timeouts: EnumSet<Out>,
+    adjust: fn(&mut Expected<Out>),
+) -> Vec<Step>
+where
+    Out: Debug + Default + Display + EnumSetType,
+{
+    let keep_expectations = current.map_or(false, |props| props.keep_expectations);
+    let current = current.and_then(|props| props.expected.as_ref());
+    let resolved = resolved.and_then(|props| props.expected.as_ref());
+
+    let configs = Platform::iter().flat_map(|platform| {
+        BuildProfile::iter().map(move |build_profile| (platform, build_profile))
+    });
+    configs
+        .map(|(platform, build_profile)| {
+            let config = (platform, build_profile);
+            let current = current.map(|expected| expected.get(platform, build_profile));
+            let reported = reported.and_then(|reported| reported.get(&config)).copied();
+
+            let resolution = match (preset, current, reported) {
+                _ if keep_expectations => Resolution::KeptExpectations,
+                (_, None, _) => Resolution::NoExpectations,
+                (ReportProcessingPreset::ResetAll, Some(_), _) => Resolution::ResetAll,
+                (
+                    ReportProcessingPreset::ResetContradictory
+                    | ReportProcessingPreset::Merge
+                    | ReportProcessingPreset::ResetTimeouts
+                    | ReportProcessingPreset::PruneStaleIntermittents,
+                    Some(_),
+                    None,
+                ) => Resolution::NotReported,
+                (ReportProcessingPreset::ResetContradictory, Some(current), Some(reported)) => {
+                    if current.is_superset(&reported) {
+                        Resolution::AlreadyExpected
+                    } else {
+                        Resolution::Contradictory
+                    }
+                }
+                (ReportProcessingPreset::Merge, Some(_), Some(_)) => Resolution::Merged,
+                (ReportProcessingPreset::PruneUnobserved, Some(_), _) => {
+                    if covered.contains(&config) {
+                        Resolution::Observed
+                    } else {
+                        Resolution::Unobserved
+                    }
+                }
+                (ReportProcessingPreset::PruneStaleIntermittents, Some(current), Some(_)) => {
+                    if current.is_permanent() {
+                        Resolution::NotIntermittent
+                    } else {
+                        Resolution::StaleIntermittentsPruned
+                    }
+                }
+                (ReportProcessingPreset::ResetTimeouts, Some(current), Some(reported)) => {
+                    if ResetTimeouts::applies(timeouts, current, reported) {
+                        Resolution::TimeoutsInvolved
+                    } else {
+                        Resolution::TimeoutsNotInvolved
+                    }
+                }
+            };
+
+            let by_rule = match resolution {
+                Resolution::KeptExpectations
+                | Resolution::AlreadyExpected
+                | Resolution::NotReported
+                | Resolution::Unobserved
+                | Resolution::TimeoutsNotInvolved
+                | Resolution::NotIntermittent => current.unwrap_or_default(),
+                Resolution::NoExpectations
+                | Resolution::ResetAll
+                | Resolution::Contradictory
+                | Resolution::Observed
+                | Resolution::TimeoutsInvolved => reported.unwrap_or_default(),
+                Resolution::Merged => current.unwrap_or_default() | reported.unwrap_or_default(),
+                Resolution::StaleIntermittentsPruned => PruneStaleIntermittents::prune(
+                    current.unwrap_or_default(),
+                    reported.unwrap_or_default(),
+                ),
+            };
+            let mut adjusted = by_rule;
+            if !keep_expectations {
+                adjust(&mut adjusted);
+            }
+
+            Step {
+                platform,
+                build_profile,
+                current: current.map(|expected| expected.to_string()),
+                reported: reported.map(|expected| expected.to_string()),
+                resolution,
+                tainted: adjusted != by_rule,
+                resolved: resolved
+                    .map(|expected| expected.get(platform, build_profile))
+                    .unwrap_or_default()
+                    .to_string(),
+            }
+        })
+        .collect()
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            test,
+            preset,
+            num_runs,
+            fate,
+            test_steps,
+            subtest_steps,
+            has_subsuite_runs,
+        } = self;
+
+        let preset = preset.to_possible_value().unwrap();
+        writeln!(
+            f,
+            "Reconciling `/{}` with `{}`, given {num_runs} run(s):",
+            test.runner_url_path(),
+            preset.get_name()
+        )?;
+        match fate {
+            Fate::Reconciled => (),
+            Fate::KeptExpectations => {
+                return writeln!(
+                    f,
+                    "`keep_expectations` is set for this test, so its metadata is left untouched."
+                )
+            }
+            Fate::Quarantined(date) => {
+                return writeln!(
+                    f,
+                    "This test was quarantined on {date}, so its metadata is left untouched."
+                )
+            }
+            Fate::Removed => {
+                return writeln!(
+                    f,
+                    "This test was not found in any report, so its metadata is removed."
+                )
+            }
+            Fate::NotFound => {
+                return writeln!(f, "This test was not found in metadata or any report.")
+            }
+        }
+
+        fn write_steps(
+            f: &mut Formatter<'_>,
+            heading: &dyn Display,
+            steps: &[Step],
+        ) -> fmt::Result {
+            writeln!(f)?;
+            writeln!(f, "{heading}:")?;
+            for step in steps {
+                let Step {
+                    platform,
+                    build_profile,
+                    current,
+                    reported,
+                    resolution,
+                    tainted,
+                    resolved,
+                } = step;
+                let platform = platform.display_name();
+                let build_profile = build_profile.name();
+                let current = current
+                    .as_ref()
+                    .map_or_else(|| "not set".to_owned(), |current| format!("`{current}`"));
+                let reported = reported
+                    .as_ref()
+                    .map_or_else(|| "not run".to_owned(), |reported| format!("`{reported}`"));
+                write!(
+                    f,
+                    "  {platform} {build_profile}: current {current}, reported {reported}; {resolution}"
+                )?;
+                if *tainted {
+                    write!(f, ", and `TIMEOUT` and `NOTRUN` are expected together")?;
+                }
+                writeln!(f, "; resolved to `{resolved}`")?;
+            }
+            Ok(())
+        }
+
+        write_steps(f, &"Test", test_steps)?;
+        for (name, steps) in subtest_steps {
+            write_steps(f, &format_args!("Subtest `{name}`"), steps)?;
+        }
+
+        if *has_subsuite_runs {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "N.B.: This test was also run in `wptrunner` subsuites, which are not explained."
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn explain_reset_contradictory() {
+    use crate::{
+        metadata::{FileBuilder, SubtestBuilder, TestBuilder},
+        report::{RunInfo, SubtestExecutionResult},
+        shared::Browser,
+    };
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let meta_file = FileBuilder::new()
+        .test(
+            TestBuilder::new("cts.https.html?q=webgpu:api,operation,adapter:*")
+                .subtest(SubtestBuilder::new(":a").expected(
+                    Expected::intermittent(SubtestOutcome::Pass | SubtestOutcome::Fail).unwrap(),
+                ))
+                .subtest(SubtestBuilder::new(":b").keep_expectations()),
+        )
+        .build()
+        .unwrap();
+
+    let test_name = "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*";
+    let report = |platform, outcome_a| ExecutionReport {
+        run_info: RunInfo {
+            platform,
+            build_profile: BuildProfile::Debug,
+            subsuite: None,
+            properties: Default::default(),
+        },
+        entries: vec![TestExecutionEntry {
+            test_name: test_name.into(),
+            subsuite: None,
+            message: None,
+            result: TestExecutionResult::Complete {
+                outcome: TestOutcome::Ok,
+                subtests: vec![
+                    SubtestExecutionResult {
+                        subtest_name: ":a".into(),
+                        outcome: outcome_a,
+                    },
+                    SubtestExecutionResult {
+                        subtest_name: ":b".into(),
+                        outcome: SubtestOutcome::Fail,
+                    },
+                ],
+            },
+        }],
+        unrecognized: Default::default(),
+    };
+
+    let explanation = explain(
+        gecko_checkout,
+        [(Arc::new(meta_path), meta_file)].into_iter().collect(),
+        [
+            Ok(report(Platform::Windows, SubtestOutcome::Fail)),
+            Ok(report(Platform::Linux, SubtestOutcome::Timeout)),
+        ],
+        &TestPath::from_execution_report(Browser::Firefox, test_name).unwrap(),
+        ReportProcessingPreset::ResetContradictory,
+    )
+    .unwrap();
+    assert_eq!(explanation.fate, Fate::Reconciled);
+    insta::assert_snapshot!(explanation.to_string(), @r###"
+    Reconciling `/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*` with `reset-contradictory`, given 2 run(s):
+
+    Test:
+      Windows debug: current not set, reported `OK`; no expectations are set, so reported outcomes are taken; resolved to `OK`
+      Windows opt: current not set, reported not run; no expectations are set, so reported outcomes are taken; resolved to `OK`
+      Linux debug: current not set, reported `OK`; no expectations are set, so reported outcomes are taken; resolved to `OK`
+      Linux opt: current not set, reported not run; no expectations are set, so reported outcomes are taken; resolved to `OK`
+      macOS debug: current not set, reported not run; no expectations are set, so reported outcomes are taken; resolved to `OK`
+      macOS opt: current not set, reported not run; no expectations are set, so reported outcomes are taken; resolved to `OK`
+      Android debug: current not set, reported not run; no expectations are set, so reported outcomes are taken; resolved to `OK`
+      Android opt: current not set, reported not run; no expectations are set, so reported outcomes are taken; resolved to `OK`
+
+    Subtest `:a`:
+      Windows debug: current `[PASS, FAIL]`, reported `FAIL`; reported outcomes are already expected, so current expectations are kept; resolved to `[PASS, FAIL]`
+      Windows opt: current `[PASS, FAIL]`, reported not run; not reported, so current expectations are kept; resolved to `[PASS, FAIL]`
+      Linux debug: current `[PASS, FAIL]`, reported `TIMEOUT`; reported outcomes contradict current expectations, so they replace them, and `TIMEOUT` and `NOTRUN` are expected together; resolved to `[TIMEOUT, NOTRUN]`
+      Linux opt: current `[PASS, FAIL]`, reported not run; not reported, so current expectations are kept; resolved to `[PASS, FAIL]`
+      macOS debug: current `[PASS, FAIL]`, reported not run; not reported, so current expectations are kept; resolved to `[PASS, FAIL]`
+      macOS opt: current `[PASS, FAIL]`, reported not run; not reported, so current expectations are kept; resolved to `[PASS, FAIL]`
+      Android debug: current `[PASS, FAIL]`, reported not run; not reported, so current expectations are kept; resolved to `[PASS, FAIL]`
+      Android opt: current `[PASS, FAIL]`, reported not run; not reported, so current expectations are kept; resolved to `[PASS, FAIL]`
+
+    Subtest `:b`:
+      Windows debug: current not set, reported `FAIL`; `keep_expectations` is set, so current expectations are kept; resolved to `PASS`
+      Windows opt: current not set, reported not run; `keep_expectations` is set, so current expectations are kept; resolved to `PASS`
+      Linux debug: current not set, reported `FAIL`; `keep_expectations` is set, so current expectations are kept; resolved to `PASS`
+      Linux opt: current not set, reported not run; `keep_expectations` is set, so current expectations are kept; resolved to `PASS`
+      macOS debug: current not set, reported not run; `keep_expectations` is set, so current expectations are kept; resolved to `PASS`
+      macOS opt: current not set, reported not run; `keep_expectations` is set, so current expectations are kept; resolved to `PASS`
+      Android debug: current not set, reported not run; `keep_expectations` is set, so current expectations are kept; resolved to `PASS`
+      Android opt: current not set, reported not run; `keep_expectations` is set, so current expectations are kept; resolved to `PASS`
+    "###);
+}
@@ -0,0 +1,459 @@
+//! Abstractions over the file system used to discover, read, and write metadata and reports.
+
+use std::{
+    collections::BTreeMap,
+    ffi::{OsStr, OsString},
+    fmt::{self, Display},
+    fs,
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Mutex,
+};
+
+use joinery::JoinableIterator;
+use wax::Glob;
+
+use crate::error::Error;
+
+/// The operations this crate needs from a file system. Implemented by [`RealFileSystem`] for
+/// actual use, [`RevisionFileSystem`] for reading from version control, and [`MemoryFileSystem`]
+/// for tests.
+pub trait FileSystem: Send + Sync {
+    /// Enumerates files under `base` whose paths (relative to `base`) match `glob`, in no
+    /// particular order.
+    fn glob(&self, base: &Path, glob: &Glob<'_>) -> Vec<Result<PathBuf, Error>>;
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error>;
+
+    fn read_to_string(&self, path: &Path) -> Result<String, Error>;
+
+    /// Writes `contents` to `path`, creating parent directories as necessary. Implementations
+    /// should ensure that `path` is never observable with partially written contents.
+    fn write(&self, path: &Path, contents: &dyn Display) -> Result<(), Error>;
+
+    /// Removes the file at `path`. Succeeds if no file exists at `path`.
+    fn remove_file(&self, path: &Path) -> Result<(), Error>;
+}
+
+/// A [`FileSystem`] that delegates to [`std::fs`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    fn glob(&self, base: &Path, glob: &Glob<'_>) -> Vec<Result<PathBuf, Error>> {
+        glob.walk(base)
+            .map(|entry| match entry {
+                Ok(entry) => Ok(entry.into_path()),
+                Err(source) => Err(Error::EnumerateFiles {
+                    glob: glob.to_string(),
+                    path: source.path().map(ToOwned::to_owned),
+                    source,
+                }),
+            })
+            .collect()
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        fs::read(path).map_err(|source| Error::ReadFile {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, Error> {
+        fs::read_to_string(path).map_err(|source| Error::ReadFile {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    fn write(&self, path: &Path, contents: &dyn Display) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|source| Error::CreateParentDirs {
+                path: path.to_owned(),
+                source,
+            })?;
+        }
+        let write_err = |source| Error::WriteFile {
+            path: path.to_owned(),
+            source,
+        };
+
+        // Write to a sibling file first, and then move it over the destination, so that an
+        // interrupted write never leaves `path` half-written.
+        let tmp_path = {
+            let mut file_name = OsString::from(".");
+            file_name.push(path.file_name().unwrap_or_default());
+            file_name.push(".tmp");
+            path.with_file_name(file_name)
+        };
+        let res = (|| {
+            let mut out = fs::File::create(&tmp_path).map(BufWriter::new)?;
+            use io::Write;
+            write!(&mut out, "{contents}")?;
+            out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            fs::rename(&tmp_path, path)
+        })();
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        res.map_err(write_err)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), Error> {
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(source) => match source.kind() {
+                io::ErrorKind::NotFound => Ok(()),
+                _ => Err(Error::RemoveFile {
+                    path: path.to_owned(),
+                    source,
+                }),
+            },
+        }
+    }
+}
+
+/// A read-only [`FileSystem`] over the files at a revision of a Git or Mercurial checkout, read
+/// with `git` or `hg` without checking the revision out.
+///
+/// Paths are resolved relative to the root of the checkout, as if the revision were checked out
+/// there.
+#[derive(Clone, Debug)]
+pub struct RevisionFileSystem {
+    checkout: PathBuf,
+    rev: String,
+    vcs: Vcs,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Vcs {
+    Git,
+    Mercurial,
+}
+
+/// The most recent change to a file in version control. See [`RevisionFileSystem::last_change`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LastChange {
+    /// The abbreviated ID of the change's commit.
+    pub rev: String,
+    /// The date the change was authored, as `YYYY-MM-DD`.
+    pub date: String,
+    /// The first line of the change's commit message.
+    pub summary: String,
+}
+
+impl Display for LastChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { rev, date, summary } = self;
+        write!(f, "{rev} {date} {summary}")
+    }
+}
+
+impl RevisionFileSystem {
+    /// Returns a file system over the files at `rev` in `checkout`, which is read with `hg` if it
+    /// is a Mercurial checkout, or `git` otherwise.
+    pub fn new(checkout: PathBuf, rev: String) -> Self {
+        let vcs = if checkout.join(".hg").is_dir() {
+            Vcs::Mercurial
+        } else {
+            Vcs::Git
+        };
+        Self { checkout, rev, vcs }
+    }
+
+    /// Like [`Self::new`], but for the revision currently checked out in `checkout`.
+    pub fn checked_out(checkout: PathBuf) -> Self {
+        let mut this = Self::new(checkout, String::new());
+        this.rev = match this.vcs {
+            Vcs::Git => "HEAD",
+            Vcs::Mercurial => ".",
+        }
+        .to_owned();
+        this
+    }
+
+    /// Returns the most recent change to `path` in this revision or its ancestors, or `None` if
+    /// no change to `path` has been committed.
+    pub fn last_change(&self, path: &Path) -> Result<Option<LastChange>, Error> {
+        let Self { rev, vcs, .. } = self;
+        let rel_path = self.rel_path(path);
+        let args = match vcs {
+            Vcs::Git => vec![
+                "log".to_owned(),
+                "-1".to_owned(),
+                "--format=%h%x00%as%x00%s".to_owned(),
+                rev.clone(),
+                "--".to_owned(),
+                rel_path,
+            ],
+            Vcs::Mercurial => vec![
+                "log".to_owned(),
+                "-l".to_owned(),
+                "1".to_owned(),
+                "-r".to_owned(),
+                format!("reverse(::{rev})"),
+                "--template".to_owned(),
+                "{node|short}\\0{date|shortdate}\\0{desc|firstline}".to_owned(),
+                "--".to_owned(),
+                rel_path,
+            ],
+        };
+        let output = self.run(&args.iter().map(OsStr::new).collect::<Vec<_>>())?;
+        let output = String::from_utf8_lossy(&output);
+        let mut fields = output.trim_end().splitn(3, '\0');
+        Ok(match (fields.next(), fields.next(), fields.next()) {
+            (Some(rev), Some(date), Some(summary)) if !rev.is_empty() => Some(LastChange {
+                rev: rev.to_owned(),
+                date: date.to_owned(),
+                summary: summary.to_owned(),
+            }),
+            _ => None,
+        })
+    }
+
+    fn run(&self, args: &[&OsStr]) -> Result<Vec<u8>, Error> {
+        let program = match self.vcs {
+            Vcs::Git => "git",
+            Vcs::Mercurial => "hg",
+        };
+        let output = Command::new(program)
+            .current_dir(&self.checkout)
+            .args(args)
+            .output()
+            .map_err(|source| Error::RunVcs { program, source })?;
+        if !output.status.success() {
+            return Err(Error::VcsFailed {
+                command: format!(
+                    "{program} {}",
+                    args.iter().map(|arg| arg.to_string_lossy()).join_with(' ')
+                ),
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            });
+        }
+        Ok(output.stdout)
+    }
+
+    /// Returns `path` relative to the root of the checkout, with `/` as separators, as `git` and
+    /// `hg` expect.
+    fn rel_path(&self, path: &Path) -> String {
+        let rel_path = path.strip_prefix(&self.checkout).unwrap_or(path);
+        rel_path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .join_with('/')
+            .to_string()
+    }
+
+    fn read_only_err(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "{} is read from a revision, which is read-only",
+                path.display()
+            ),
+        )
+    }
+}
+
+impl FileSystem for RevisionFileSystem {
+    fn glob(&self, base: &Path, glob: &Glob<'_>) -> Vec<Result<PathBuf, Error>> {
+        let Self { rev, vcs, .. } = self;
+        let rel_base = self.rel_path(base);
+        let rel_base = if rel_base.is_empty() { "." } else { &rel_base };
+        let args = match vcs {
+            Vcs::Git => vec!["ls-tree", "-r", "--name-only", "-z", rev, "--", rel_base],
+            Vcs::Mercurial => vec!["files", "-0", "-r", rev, "--", rel_base],
+        };
+        let args = args.into_iter().map(OsStr::new).collect::<Vec<_>>();
+        let listing = match self.run(&args) {
+            Ok(listing) => listing,
+            Err(e) => return vec![Err(e)],
+        };
+        listing
+            .split(|&byte| byte == 0)
+            .filter(|rel_path| !rel_path.is_empty())
+            .map(|rel_path| self.checkout.join(&*String::from_utf8_lossy(rel_path)))
+            .filter(|path| {
+                path.strip_prefix(base)
+                    .map_or(false, |rel_path| glob.is_match(rel_path))
+            })
+            .map(Ok)
+            .collect()
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        let Self { rev, vcs, .. } = self;
+        let rel_path = self.rel_path(path);
+        match vcs {
+            Vcs::Git => self.run(&[OsStr::new("show"), OsStr::new(&format!("{rev}:{rel_path}"))]),
+            Vcs::Mercurial => self.run(&[
+                OsStr::new("cat"),
+                OsStr::new("-r"),
+                OsStr::new(rev),
+                OsStr::new("--"),
+                OsStr::new(&rel_path),
+            ]),
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, Error> {
+        String::from_utf8(self.read(path)?).map_err(|e| Error::ReadFile {
+            path: path.to_owned(),
+            source: io::Error::new(io::ErrorKind::InvalidData, e),
+        })
+    }
+
+    fn write(&self, path: &Path, _contents: &dyn Display) -> Result<(), Error> {
+        Err(Error::WriteFile {
+            path: path.to_owned(),
+            source: Self::read_only_err(path),
+        })
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), Error> {
+        Err(Error::RemoveFile {
+            path: path.to_owned(),
+            source: Self::read_only_err(path),
+        })
+    }
+}
+
+/// An in-memory [`FileSystem`], useful for exercising code with synthetic Gecko checkouts.
+///
+/// Directories are implicit; any path with a file under it is considered to exist.
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+    files: Mutex<BTreeMap<PathBuf, String>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a snapshot of all files currently stored.
+    pub fn files(&self) -> BTreeMap<PathBuf, String> {
+        self.files.lock().unwrap().clone()
+    }
+}
+
+impl<P, C> FromIterator<(P, C)> for MemoryFileSystem
+where
+    P: Into<PathBuf>,
+    C: Into<String>,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (P, C)>,
+    {
+        Self {
+            files: Mutex::new(
+                iter.into_iter()
+                    .map(|(path, contents)| (path.into(), contents.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn glob(&self, base: &Path, glob: &Glob<'_>) -> Vec<Result<PathBuf, Error>> {
+        self.files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|path| {
+                path.strip_prefix(base)
+                    .map_or(false, |rel_path| glob.is_match(rel_path))
+            })
+            .cloned()
+            .map(Ok)
+            .collect()
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        self.read_to_string(path).map(String::into_bytes)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, Error> {
+        self.files
+            .lock()
+            .unwrap()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Error::ReadFile {
+                path: path.to_owned(),
+                source: io::ErrorKind::NotFound.into(),
+            })
+    }
+
+    fn write(&self, path: &Path, contents: &dyn Display) -> Result<(), Error> {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_owned(), contents.to_string());
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), Error> {
+        self.files.lock().unwrap().remove(path);
+        Ok(())
+    }
+}
+
+#[test]
+fn memory_glob() {
+    let fs = [
+        ("meta/a.ini", ""),
+        ("meta/b/c.ini", ""),
+        ("meta/b/d.json", ""),
+        ("other/e.ini", ""),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let mut found = fs
+        .glob(Path::new("meta"), &Glob::new("**/*.ini").unwrap())
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    found.sort();
+    assert_eq!(found, [Path::new("meta/a.ini"), Path::new("meta/b/c.ini")]);
+}
+
+#[test]
+fn real_write_is_atomic() {
+    let dir = std::env::temp_dir().join(format!("moz-webgpu-cts-write-{}", std::process::id()));
+    let path = dir.join("meta/cts.https.html.ini");
+    let list_dir = || {
+        let mut names = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    let fs = RealFileSystem;
+    fs.write(&path, &"expected: FAIL\n").unwrap();
+    fs.write(&path, &"expected: PASS\n").unwrap();
+    assert_eq!(fs.read_to_string(&path).unwrap(), "expected: PASS\n");
+    assert_eq!(list_dir(), [OsStr::new("cts.https.html.ini")]);
+
+    // A write that can't be moved into place leaves no temporary file behind.
+    let blocked = path.with_file_name("blocked.ini");
+    fs::create_dir(&blocked).unwrap();
+    assert!(matches!(
+        fs.write(&blocked, &"expected: PASS\n"),
+        Err(Error::WriteFile { .. })
+    ));
+    assert_eq!(
+        list_dir(),
+        [OsStr::new("blocked.ini"), OsStr::new("cts.https.html.ini")]
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
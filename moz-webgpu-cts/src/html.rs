@@ -0,0 +1,209 @@
+//! Self-contained HTML reports (i.e., of `triage --export-html`), for linking from CI runs to
+//! people who don't use this tool. Pages have no external resources: styles and the script that
+//! sorts tables are inlined.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Write as _},
+};
+
+use chrono::NaiveDate;
+use format::lazy_format;
+use joinery::JoinableIterator;
+
+use crate::analysis::Analysis;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+summary { font-size: 1.25em; font-weight: bold; cursor: pointer; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.5em; text-align: left; vertical-align: top; }
+th { background: #eee; cursor: pointer; user-select: none; }
+td.count { text-align: right; }
+code { word-break: break-all; }
+";
+
+/// Sorts a table by the column whose header was clicked, toggling between ascending and
+/// descending order. Cells are compared as numbers if they all are.
+const SCRIPT: &str = "\
+document.querySelectorAll('table.sortable th').forEach((th) => {
+  th.addEventListener('click', () => {
+    const table = th.closest('table');
+    const body = table.tBodies[0];
+    const idx = Array.from(th.parentNode.children).indexOf(th);
+    const asc = th.dataset.order !== 'asc';
+    th.parentNode.querySelectorAll('th').forEach((other) => delete other.dataset.order);
+    th.dataset.order = asc ? 'asc' : 'desc';
+    const key = (row) => row.children[idx].textContent.trim();
+    const rows = Array.from(body.rows);
+    const numeric = rows.every((row) => key(row) !== '' && !isNaN(key(row)));
+    rows.sort((a, b) => {
+      const [x, y] = [key(a), key(b)];
+      const ordering = numeric ? x - y : x.localeCompare(y);
+      return asc ? ordering : -ordering;
+    });
+    rows.forEach((row) => body.appendChild(row));
+  });
+});
+";
+
+/// Renders `analysis`, made on `date`, as a page with a collapsible section for each platform,
+/// each with counts of tests by issue and a sortable table of tests needing attention.
+/// `owners_by_test` and `bugs_by_test` are keyed by test like
+/// [`PerPlatformAnalysis::issues_by_test`].
+///
+/// [`PerPlatformAnalysis::issues_by_test`]: crate::analysis::PerPlatformAnalysis::issues_by_test
+pub fn triage_report(
+    analysis: &Analysis,
+    owners_by_test: &BTreeMap<String, String>,
+    bugs_by_test: &BTreeMap<String, BTreeSet<String>>,
+    date: NaiveDate,
+) -> String {
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>").unwrap();
+    writeln!(html, "<html lang=\"en\">").unwrap();
+    writeln!(html, "<head>").unwrap();
+    writeln!(html, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(html, "<title>WebGPU CTS triage, {date}</title>").unwrap();
+    writeln!(html, "<style>\n{STYLE}</style>").unwrap();
+    writeln!(html, "</head>").unwrap();
+    writeln!(html, "<body>").unwrap();
+    writeln!(html, "<h1>WebGPU CTS triage, {date}</h1>").unwrap();
+
+    analysis.for_each_platform(|platform, analysis| {
+        let issues_by_test = analysis.issues_by_test();
+        let mut num_tests_by_issue = BTreeMap::<_, usize>::new();
+        for issue in issues_by_test.values().flatten() {
+            *num_tests_by_issue.entry(*issue).or_default() += 1;
+        }
+
+        writeln!(html, "<details open>").unwrap();
+        writeln!(
+            html,
+            "<summary>{platform:?}: {} test(s) needing attention</summary>",
+            issues_by_test.len()
+        )
+        .unwrap();
+        if issues_by_test.is_empty() {
+            writeln!(html, "</details>").unwrap();
+            return;
+        }
+
+        writeln!(html, "<table class=\"sortable\">").unwrap();
+        writeln!(html, "<thead><tr><th>Issue</th><th>Tests</th></tr></thead>").unwrap();
+        writeln!(html, "<tbody>").unwrap();
+        for (issue, num_tests) in num_tests_by_issue {
+            writeln!(
+                html,
+                "<tr><td>{issue}</td><td class=\"count\">{num_tests}</td></tr>"
+            )
+            .unwrap();
+        }
+        writeln!(html, "</tbody>").unwrap();
+        writeln!(html, "</table>").unwrap();
+
+        writeln!(html, "<table class=\"sortable\">").unwrap();
+        writeln!(
+            html,
+            "<thead><tr><th>Test</th><th>Issues</th><th>Owner</th><th>Bugs</th></tr></thead>"
+        )
+        .unwrap();
+        writeln!(html, "<tbody>").unwrap();
+        for (test_name, issues) in &issues_by_test {
+            let owner = owners_by_test
+                .get(&***test_name)
+                .map(|owner| escape(owner).to_string())
+                .unwrap_or_default();
+            let bugs = bugs_by_test
+                .get(&***test_name)
+                .into_iter()
+                .flatten()
+                .map(|bug| escape(bug).to_string())
+                .collect::<Vec<_>>()
+                .join("<br>");
+            writeln!(
+                html,
+                "<tr><td><code>/{}</code></td><td>{}</td><td>{owner}</td><td>{bugs}</td></tr>",
+                escape(test_name),
+                issues.iter().join_with(", "),
+            )
+            .unwrap();
+        }
+        writeln!(html, "</tbody>").unwrap();
+        writeln!(html, "</table>").unwrap();
+        writeln!(html, "</details>").unwrap();
+    });
+
+    writeln!(html, "<script>\n{SCRIPT}</script>").unwrap();
+    writeln!(html, "</body>").unwrap();
+    writeln!(html, "</html>").unwrap();
+    html
+}
+
+/// Escapes `text` for use in HTML content or attribute values.
+fn escape(text: &str) -> impl Display + '_ {
+    lazy_format!(move |f| {
+        for c in text.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&#39;")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn triage_report_html() {
+    use std::{path::Path, sync::Arc};
+
+    use crate::{
+        analysis::tests_by_name,
+        metadata::{FileBuilder, SubtestBuilder, SubtestOutcome, TestBuilder},
+        severity::SeverityOrder,
+        shared::Expected,
+    };
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = Arc::new(
+        gecko_checkout
+            .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+            .join("cts.https.html.ini"),
+    );
+    let file = FileBuilder::new()
+        .test(
+            TestBuilder::new(r#"cts.https.html?q=webgpu:api,operation,adapter:a:label="<b>";*"#)
+                .subtest(
+                    SubtestBuilder::new(":").expected(Expected::permanent(SubtestOutcome::Fail)),
+                ),
+        )
+        .build()
+        .unwrap();
+    let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let analysis = Analysis::new(
+        tests_by_name(gecko_checkout, [(meta_path, file)], &[]),
+        &[],
+        date,
+        14,
+        &SeverityOrder::default(),
+    );
+    let test_name = concat!(
+        "_mozilla/webgpu/cts/webgpu/cts.https.html",
+        r#"?q=webgpu:api,operation,adapter:a:label="<b>";*"#
+    );
+    let owners_by_test = [(test_name.to_owned(), "gpu & co".to_owned())].into();
+    let html = triage_report(&analysis, &owners_by_test, &BTreeMap::new(), date);
+
+    assert!(html.contains("<title>WebGPU CTS triage, 2024-06-01</title>"));
+    assert!(html.contains("<summary>Windows: 1 test(s) needing attention</summary>"));
+    assert!(html.contains(r#"<tr><td>perma-fail</td><td class="count">1</td></tr>"#));
+    assert!(html.contains(concat!(
+        "<tr><td><code>/_mozilla/webgpu/cts/webgpu/cts.https.html",
+        "?q=webgpu:api,operation,adapter:a:label=&quot;&lt;b&gt;&quot;;*</code></td>",
+        "<td>perma-fail</td><td>gpu &amp; co</td><td></td></tr>"
+    )));
+}
@@ -0,0 +1,42 @@
+pub mod analysis;
+pub mod annotation;
+pub mod bisect;
+pub mod bugzilla;
+pub mod checkout;
+pub mod clean;
+pub mod collation;
+pub mod config;
+pub mod dedupe;
+pub mod diff;
+pub mod download;
+pub mod error;
+pub mod error_clusters;
+pub mod explain;
+pub mod filesystem;
+pub mod html;
+pub mod lock;
+pub mod metadata;
+pub mod migrate;
+pub mod never_modify;
+pub mod owners;
+pub mod pool_skew;
+pub mod process_reports;
+pub mod profile;
+pub mod progress;
+pub mod quarantine;
+pub mod report;
+pub mod repro;
+pub mod rerun;
+pub mod resume;
+pub mod script;
+pub mod search;
+pub mod session;
+pub mod severity;
+pub mod shared;
+pub mod snapshot;
+pub mod stats;
+pub mod strategy;
+pub mod table;
+pub mod taskcluster;
+pub mod test_pattern;
+pub mod validate;
@@ -0,0 +1,97 @@
+//! An advisory lock on a metadata directory, so that concurrent invocations that write metadata
+//! (i.e., a stray CI job and a local run) don't interleave their writes.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, Write},
+    path::Path,
+};
+
+use fs2::FileExt;
+
+use crate::error::Error;
+
+/// The name of the lock file created by [`MetadataLock::acquire`].
+pub const LOCK_FILE_NAME: &str = ".moz-webgpu-cts.lock";
+
+/// Held for as long as metadata in a directory may be written. The lock is released when this is
+/// dropped. The lock is also released by the OS if this process exits without dropping it, so a
+/// crash never leaves a directory locked.
+///
+/// The lock file itself is never removed: another process may have opened it already, and would
+/// then lock a file that's no longer at its path while a third process locks a new one there.
+#[derive(Debug)]
+pub struct MetadataLock {
+    file: File,
+}
+
+impl MetadataLock {
+    /// Locks `meta_dir`, failing immediately if another process holds the lock already. The ID of
+    /// this process is written to the lock file, so that it can be reported to others.
+    pub fn acquire(meta_dir: &Path) -> Result<Self, Error> {
+        let path = meta_dir.join(LOCK_FILE_NAME);
+        let lock_err = |source| Error::LockMetadata {
+            path: path.clone(),
+            source,
+        };
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(lock_err)?;
+        if let Err(e) = file.try_lock_exclusive() {
+            if e.kind() != fs2::lock_contended_error().kind() {
+                return Err(lock_err(e));
+            }
+            let mut holder = String::new();
+            let _ = file.read_to_string(&mut holder);
+            return Err(Error::MetadataLocked {
+                path,
+                holder: holder.trim().parse().ok(),
+            });
+        }
+
+        file.set_len(0)
+            .and_then(|()| file.rewind())
+            .and_then(|()| write!(file, "{}", std::process::id()))
+            .map_err(lock_err)?;
+        tracing::debug!("locked metadata via {}", path.display());
+        Ok(Self { file })
+    }
+}
+
+impl Drop for MetadataLock {
+    fn drop(&mut self) {
+        let Self { file } = self;
+        // N.B.: The ID of this process is cleared first, so that it's never reported as holding
+        // the lock after releasing it.
+        let _ = file.set_len(0);
+        let _ = file.unlock();
+    }
+}
+
+#[test]
+fn excludes_concurrent_holders() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("moz-webgpu-cts-lock-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let lock = MetadataLock::acquire(&dir).unwrap();
+    match MetadataLock::acquire(&dir) {
+        Err(Error::MetadataLocked { path, holder }) => {
+            assert_eq!(path, dir.join(LOCK_FILE_NAME));
+            assert_eq!(holder, Some(std::process::id()));
+        }
+        other => panic!("expected the directory to be locked, got {other:?}"),
+    }
+    drop(lock);
+    assert_eq!(fs::read_to_string(dir.join(LOCK_FILE_NAME)).unwrap(), "");
+
+    drop(MetadataLock::acquire(&dir).unwrap());
+    assert!(dir.join(LOCK_FILE_NAME).exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,61 +1,130 @@
-mod metadata;
-mod process_reports;
-mod report;
-mod shared;
-
-use self::{
-    metadata::{
-        BuildProfile, File, FileProps, Platform, Subtest, SubtestOutcome, Test, TestOutcome,
-        TestProps,
+use moz_webgpu_cts::{
+    analysis::{self, Analysis, CtsAreaIssues, PerPlatformAnalysis, PermaAndIntermittent},
+    annotation::{first_differing_line, Annotation, AnnotationFormat, AnnotationSeverity},
+    bisect::{bisect, transitions},
+    bugzilla::{self, BugCandidate, Bugzilla, KnownBugs, NewBug, OpenBug, BUGZILLA_URL},
+    checkout::{
+        self, parse_metadata, read_and_parse_all_dir_metadata, read_and_parse_all_metadata,
+        read_and_parse_metadata_matching, search_for_moz_central_ckt, state_dir,
     },
-    process_reports::{Entry, TestEntry},
-    report::{
-        ExecutionReport, RunInfo, SubtestExecutionResult, TestExecutionEntry, TestExecutionResult,
+    clean::{self, Cleaned},
+    collation::{self, Collation},
+    config::{Config, TriageConfig, UpdateExpectedConfig},
+    dedupe::{self, DuplicateTest, MergedFiles},
+    diff::diff,
+    download,
+    error::{Error, GlobDiagnostic},
+    error_clusters::ErrorClusters,
+    explain::explain,
+    filesystem::{FileSystem, RealFileSystem, RevisionFileSystem},
+    html,
+    lock::MetadataLock,
+    metadata::{self, BuildProfile, DisabledReason, Platform, SubtestOutcome, Test, TestOutcome},
+    migrate::{self, Migrated, Migration, RenameMap},
+    never_modify::NeverModifyList,
+    owners::Owners,
+    pool_skew::{pool_skew, DEFAULT_POOL_KEYS},
+    process_reports::{
+        self, taint_subtest_timeouts_by_suspicion, ReconcileOptions, ReconciliationSummary,
+        Relocation, ReportProcessingPreset, ReviewDecision, Sample, TestChange, TestPart,
     },
-    shared::{Expected, FullyExpandedExpectedPropertyValue, TestPath},
+    profile::{Profile, ProfileLayer},
+    progress,
+    quarantine::{self, QuarantineAction, Quarantined},
+    report::{ParseOptions, PlatformOverride, ReportFormat, WEBGPU_BACKEND_KEY},
+    repro::failing_tests,
+    rerun::{FlakeRates, RerunManifest},
+    resume::{RelocatedTest, WriteManifest},
+    script::{OutcomeName, OutcomePolicy, PresetArg, PresetOverride, RuleAction},
+    search::{search, SearchFilters},
+    session::Session,
+    severity::SeverityOrder,
+    shared::{Browser, OutputFormat, TestPath},
+    snapshot::snapshot_repro,
+    stats::{FileHealth, Stats},
+    strategy::{PerLevel, ReconciliationStrategy},
+    table::{self, TableFormat},
+    taskcluster::{self, Push, Taskcluster, FIREFOX_CI_ROOT_URL},
+    test_pattern::TestPattern,
+    validate::{validate, TestListing},
 };
 
 use std::{
     collections::{BTreeMap, BTreeSet},
-    fmt::{self, Debug, Display, Formatter},
+    fmt::{Debug, Display},
     fs,
-    hash::Hash,
-    io::{self, BufReader, BufWriter},
+    io::{self, BufWriter, IsTerminal},
+    num::NonZeroUsize,
     path::{Path, PathBuf},
     process::ExitCode,
+    str::FromStr,
     sync::{
         atomic::{self, AtomicBool},
-        mpsc::channel,
         Arc,
     },
 };
 
-use camino::Utf8PathBuf;
-use clap::{Parser, ValueEnum};
-use enumset::EnumSetType;
+use chrono::{Local, NaiveDate};
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
 use format::lazy_format;
-use indexmap::{IndexMap, IndexSet};
-use itertools::Itertools;
+use indexmap::IndexMap;
 use joinery::JoinableIterator;
-use miette::{miette, Diagnostic, IntoDiagnostic, NamedSource, Report, SourceSpan, WrapErr};
-use path_dsl::path;
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use miette::Report;
+use tracing_flame::{FlameLayer, FlushGuard};
 use wax::Glob;
-use whippit::{
-    metadata::SectionHeader,
-    reexport::chumsky::{self, prelude::Rich},
-};
+use whippit::metadata::SectionHeader;
 
+/// Options not given on the command line are taken from `.moz-webgpu-cts.toml` at the root of the
+/// Gecko checkout, if it exists, which may set defaults for `--browser`, and some options of
+/// `update-expected` and `triage`.
+///
+/// Subcommands that select tests take test patterns, each of which is either a test URL path, in
+/// which `*` matches anything (i.e., `/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,*`),
+/// or a CTS query, which matches tests nested under it if it ends in `*` (i.e.,
+/// `webgpu:api,operation,*`).
 #[derive(Debug, Parser)]
 #[command(about, version)]
 struct Cli {
     #[clap(long)]
     gecko_checkout: Option<PathBuf>,
+    /// The browser `--gecko-checkout` is a checkout of, which determines where tests and their
+    /// metadata are found.
+    #[clap(value_enum, long, default_value_t = Default::default())]
+    browser: Browser,
+    /// Write timings of each phase of execution to the given file as folded stack traces, suitable
+    /// for rendering with `inferno-flamegraph` and similar tools.
+    #[clap(long, value_name = "PATH")]
+    trace_out: Option<PathBuf>,
+    /// At exit, print peak memory usage, and the time taken, throughput, and counts of entries
+    /// processed in each phase of execution to `stderr`.
+    #[clap(long)]
+    profile: bool,
+    /// Show progress bars with counts and estimated time left on `stderr` for long phases of
+    /// execution, like parsing metadata and reports, reconciling, and writing metadata.
+    #[clap(long)]
+    progress: bool,
+    /// Cache metadata after parsing it, and load cached metadata instead of parsing files that
+    /// haven't changed since. Useful for running several subcommands in sequence on a large
+    /// checkout.
+    ///
+    /// Parsed metadata is stored in `$MOZBUILD_STATE_PATH/moz-webgpu-cts/metadata-cache/`, where
+    /// `$MOZBUILD_STATE_PATH` defaults to `~/.mozbuild`.
+    #[clap(long)]
+    cache_metadata: bool,
+    /// Don't lock the metadata directory while writing to it. Without this, subcommands that
+    /// write metadata fail instead of running concurrently with another invocation that does.
+    #[clap(long)]
+    no_lock: bool,
+    /// How to order metadata files as they're read, sections in metadata as it's written, and
+    /// names in list outputs. Every option is independent of the platform and locale.
+    #[clap(value_enum, long, default_value_t = Default::default())]
+    sort: Collation,
     #[clap(subcommand)]
     subcommand: Subcommand,
 }
 
 #[derive(Debug, Parser)]
+#[allow(clippy::large_enum_variant)]
 enum Subcommand {
     /// Adjust expected test outcomes in metadata, optionally using `wptreport.json` reports from
     /// CI runs covering Firefox's implementation of WebGPU.
@@ -76,9 +145,18 @@ enum Subcommand {
     /// With both steps, you may delete the local copies of these reports after being processed
     /// with `process-reports`. You should not need to re-process them unless you have made an
     /// error in following these steps.
+    ///
+    /// Platforms without reports keep the expectations they had, Android included. So once
+    /// reports make expectations differ between platforms, each platform whose expectation isn't
+    /// the default gets a condition of its own: an unconditional `expected: FAIL` rewritten after
+    /// Linux reports show `PASS` gains `if os == "android": FAIL`, along with conditions for
+    /// Windows and macOS, even if no Android reports were given.
     #[clap(alias = "process-reports")]
     UpdateExpected {
-        /// Direct paths to report files to be processed.
+        /// Direct paths to report files to be processed, or HTTP(S) URLs to download them from
+        /// (i.e., of CI artifacts). Downloads are kept in
+        /// `$MOZBUILD_STATE_PATH/moz-webgpu-cts/downloads/`, where `$MOZBUILD_STATE_PATH` defaults
+        /// to `~/.mozbuild`, and are not downloaded again.
         report_paths: Vec<PathBuf>,
         /// Cross-platform [`wax` globs] to enumerate report files to be processed.
         ///
@@ -88,28 +166,742 @@ enum Subcommand {
         /// [`wax` globs]: https://github.com/olson-sean-k/wax/blob/master/README.md#patterns
         #[clap(long = "glob", value_name = "REPORT_GLOB")]
         report_globs: Vec<String>,
-        /// The heuristic for resolving differences between current metadata and processed reports.
+        /// Also process reports from WebGPU test tasks in this Taskcluster task group, downloading
+        /// them first. May be repeated.
+        ///
+        /// Downloaded reports are kept in `$MOZBUILD_STATE_PATH/moz-webgpu-cts/taskcluster/`,
+        /// where `$MOZBUILD_STATE_PATH` defaults to `~/.mozbuild`, and are not downloaded again.
+        #[clap(long = "taskcluster-task-group", value_name = "TASK_GROUP_ID")]
+        taskcluster_task_groups: Vec<String>,
+        /// Like `--taskcluster-task-group`, but for the task group of a push, found via the
+        /// Taskcluster index: `[PROJECT:]REVISION`, where `PROJECT` defaults to `try`. May be
+        /// repeated.
+        #[clap(long, value_name = "[PROJECT:]REVISION")]
+        from_push: Vec<Push>,
+        /// The root URL of the Taskcluster deployment to fetch reports from.
+        #[clap(long, value_name = "URL", default_value = FIREFOX_CI_ROOT_URL)]
+        taskcluster_root_url: String,
+        /// The heuristic for resolving differences between current metadata and processed reports:
+        /// one of `reset-contradictory` (alias: `new-fx`), `merge` (alias: `same-fx`),
+        /// `reset-all`, `prune-unobserved`, `reset-timeouts`, or `prune-stale-intermittents`.
+        ///
+        /// Alternatively, `script:<FILE>` adjusts one of these per outcome with rules in `FILE`,
+        /// one per line, i.e., `preset new-fx`, `keep CRASH` to never remove `CRASH` expectations,
+        /// or `merge TIMEOUT` to merge reported outcomes whenever `TIMEOUT` is reported.
+        #[clap(long, default_value = "reset-contradictory")]
+        preset: PresetArg,
+        /// Like `--preset`, but for subtests only, leaving `--preset` to resolve tests' own
+        /// expectations. For instance, `--preset merge --subtest-preset reset-contradictory`
+        /// accumulates intermittent harness statuses while still replacing CTS case results
+        /// that contradict reports.
+        #[clap(long, value_name = "PRESET")]
+        subtest_preset: Option<PresetArg>,
+        /// Use `PRESET` instead of `--preset` and `--subtest-preset` for tests matching `PATTERN`
+        /// and their subtests, where `PATTERN` is a test pattern (see `moz-webgpu-cts --help`).
+        /// For instance, `--preset reset-all --preset-override 'webgpu:api,validation,*=merge'`
+        /// resets most expectations while merging into those of a subsystem known to be
+        /// intermittent. May be repeated; the first matching override wins.
+        #[clap(long, value_name = "PATTERN=PRESET")]
+        preset_override: Vec<PresetOverride>,
+        /// Never remove expected outcomes of this kind (i.e., `CRASH`), regardless of `--preset`.
+        /// May be repeated.
+        #[clap(long, value_name = "OUTCOME")]
+        never_remove: Vec<OutcomeName>,
+        /// Whenever an outcome of this kind (i.e., `TIMEOUT`) is reported, add reported outcomes to
+        /// expected ones instead of resolving them with `--preset`. May be repeated.
+        #[clap(long, value_name = "OUTCOME")]
+        always_merge: Vec<OutcomeName>,
+        /// A file listing tests whose metadata must be left untouched, one test pattern (see
+        /// `moz-webgpu-cts --help`) per line. Lines starting with `#` are ignored.
+        #[clap(long, value_name = "FILE")]
+        never_modify: Option<PathBuf>,
+        /// Fail on reports containing fields or statuses not recognized by this tool, instead of
+        /// ignoring them with a warning.
+        #[clap(long)]
+        deny_unknown_report_fields: bool,
+        /// Attribute runs whose `run_info` has `KEY` set to `VALUE` to `PLATFORM` (one of `win`,
+        /// `linux`, `mac`, or `android`), i.e., `--platform-map os=win11:win`. Useful for reports from
+        /// worker images that the built-in rules don't recognize. May be specified multiple times;
+        /// the first matching mapping wins.
+        #[clap(long = "platform-map", value_name = "KEY=VALUE:PLATFORM")]
+        platform_overrides: Vec<PlatformOverride>,
+        /// Parse reports as this format (one of `wptreport`, `mozlog`, or `chromium`), rather than
+        /// detecting it from their contents. `mozlog` reads `wptrunner`'s `raw.log` action streams,
+        /// for CI jobs that don't archive `wptreport.json` files. `chromium` reads Chromium's JSON
+        /// test results (i.e., `full_results.json`), which is never detected, and usually needs
+        /// `--platform-map` to attribute runs to a platform. Compressed files and archives are
+        /// unpacked regardless.
+        #[clap(long, value_name = "FORMAT")]
+        report_format: Option<ReportFormat>,
+        /// Reconcile expectations separately for runs with different values of this `run_info`
+        /// key (i.e., `fission` or `swgl`), writing them with conditions on the key, like
+        /// `if fission and os == "win": FAIL`. Runs in different `subsuite`s are always
+        /// reconciled separately. May be repeated.
+        #[clap(long = "run-info-key", value_name = "KEY")]
+        run_info_keys: Vec<String>,
+        /// Reconcile expectations separately for each WebGPU backend (i.e., Vulkan and D3D12 on
+        /// Windows), as identified by `webgpu_backend` in `run_info`, writing them with conditions
+        /// like `if os == "win" and webgpu_backend == "vulkan": FAIL`. Shorthand for
+        /// `--run-info-key webgpu_backend`.
+        #[clap(long)]
+        split_by_backend: bool,
+        /// Remove disables whose `disabled-until` date is today or earlier, and reset their
+        /// expected outcomes to those reported.
+        #[clap(long)]
+        reenable_expired: bool,
+        /// Only process a sample of tests found in reports, leaving the metadata of other tests
+        /// untouched: either a fraction of tests from 0 to 1, sampled pseudo-randomly (but the same
+        /// in every invocation), or the first `N` tests found in reports. Useful for quickly trying
+        /// out options against a large set of reports before processing all of it.
+        #[clap(long, value_name = "FRACTION|N")]
+        sample: Option<Sample>,
+        /// Ignore runs on platforms other than these (each one of `win`, `linux`, `mac`, or
+        /// `android`, with `windows` and `macos` also accepted), even if they are in reports, so
+        /// that expectations for other platforms are left as they are, even with `--preset
+        /// reset-all`. May be repeated, or given as a comma-separated list, i.e., `--only-platform
+        /// win,linux`.
+        ///
+        /// Tests not run on the given platforms are kept, along with their expectations there,
+        /// since they may only run on others.
+        #[clap(long, value_name = "PLATFORM", value_delimiter = ',')]
+        only_platform: Vec<Platform>,
+        /// Like `--only-platform`, but for build profiles (one of `debug` or `opt`). For instance,
+        /// `--only-build-profile debug` updates debug expectations from a debug-only try push
+        /// without touching opt ones, and `--only-platform linux --only-build-profile debug`
+        /// updates just the expectations for Linux debug builds from a folder of reports from all
+        /// configurations.
+        #[clap(long, value_name = "PROFILE", value_delimiter = ',')]
+        only_build_profile: Vec<BuildProfile>,
+        /// Disable tests wherever they're expected to permanently crash after reconciliation, by
+        /// adding a condition for each such platform (and build profile) to their `disabled`
+        /// property, with `REASON` (i.e., a bug URL) as its value, or `true` if not given.
+        /// Crashing tests destabilize CI, so this saves disabling them by hand afterwards.
+        #[clap(
+            long,
+            value_name = "REASON",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        disable_perma_crash: Option<String>,
+        /// Cache a compact summary of each report after parsing it, and parse cached summaries
+        /// instead of reports they summarize. Useful for processing the same reports again after
+        /// an error, since parsing large reports can take much longer than parsing their
+        /// summaries.
+        ///
+        /// Summaries are stored in `$MOZBUILD_STATE_PATH/moz-webgpu-cts/report-summaries/`,
+        /// where `$MOZBUILD_STATE_PATH` defaults to `~/.mozbuild`.
+        #[clap(long)]
+        cache_summaries: bool,
+        /// Read, parse, and process reports one at a time, rather than reading all of them into
+        /// memory up front. Useful for processing many large reports (i.e., for the full CTS) on
+        /// machines with little memory, at the cost of reading reports serially.
+        #[clap(long, conflicts_with = "cache_summaries")]
+        stream_reports: bool,
+        /// Record the configurations (platform and build profile) covered by processed reports
+        /// in a session named `NAME`, and warn about configurations that no invocation in the
+        /// session has covered yet. Useful for processing reports across several invocations,
+        /// i.e., as CI runs complete.
+        ///
+        /// Sessions are stored in `$MOZBUILD_STATE_PATH/moz-webgpu-cts/sessions/`, where
+        /// `MOZBUILD_STATE_PATH` defaults to `~/.mozbuild`. Delete a session's file to start it
+        /// over.
+        #[clap(long, value_name = "NAME")]
+        session: Option<String>,
+        /// Write without asking for confirmation, even if more than `--confirm-above` of metadata
+        /// files would change.
+        #[clap(long, short = 'y')]
+        yes: bool,
+        /// The fraction of existing metadata files, from 0 to 1, that may be rewritten or removed
+        /// before confirmation is required. This guards against, i.e., running `--preset
+        /// reset-all` against reports from only some runs. Without a terminal to ask on, writing
+        /// is refused instead.
+        #[clap(long, value_name = "FRACTION", default_value_t = 0.5)]
+        confirm_above: f64,
+        /// Walk through each test whose metadata would change, alongside the outcomes reported
+        /// for it in each configuration, and choose whether to accept the change, skip it, or
+        /// edit the new metadata in `$EDITOR`, akin to `git add -p`. Changes can be split to
+        /// decide on each subtest separately.
+        #[clap(long)]
+        interactive: bool,
+        /// Finish writing metadata from an earlier invocation that failed partway through writing
+        /// (i.e., because of a full disk, or missing permissions), without processing reports
+        /// again.
+        #[clap(
+            long,
+            conflicts_with_all = [
+                "report_paths",
+                "report_globs",
+                "taskcluster_task_groups",
+                "from_push",
+            ],
+        )]
+        resume: bool,
+        /// Process reports, but print a unified diff of the metadata files that would change on
+        /// `stdout` instead of writing them. Exits unsuccessfully if any would change.
+        #[clap(long)]
+        dry_run: bool,
+        /// After processing reports, list tests with metadata that no report had an entry for
+        /// (i.e., because they were renamed or removed from the CTS), along with the
+        /// configurations that reports covered and those they didn't. Deleting an orphan's
+        /// metadata is only safe if reports covered every configuration it runs in.
+        #[clap(long)]
+        report_orphans: bool,
+    },
+    /// Remove disables from tests matching the given patterns, and reset their expected outcomes
+    /// to those in the given reports. Other tests are left untouched.
+    ///
+    /// This is the counterpart to adding `disabled: true` to a test; use it once the reason for
+    /// a disable has been fixed.
+    Enable {
+        /// Tests to re-enable, as test patterns (see `moz-webgpu-cts --help`).
+        #[clap(required = true)]
+        tests: Vec<TestPattern>,
+        /// Direct paths or HTTP(S) URLs to report files to be processed.
+        #[clap(long = "report", value_name = "REPORT_PATH")]
+        report_paths: Vec<PathBuf>,
+        /// Cross-platform `wax` globs to enumerate report files to be processed. See
+        /// `update-expected --glob`.
+        #[clap(long = "glob", value_name = "REPORT_GLOB")]
+        report_globs: Vec<String>,
+        /// The heuristic for resolving differences between current metadata and processed reports
+        /// in tests that are not disabled. See `update-expected --preset`.
+        #[clap(long, default_value = "reset-contradictory")]
+        preset: PresetArg,
+        /// See `update-expected --subtest-preset`.
+        #[clap(long, value_name = "PRESET")]
+        subtest_preset: Option<PresetArg>,
+        /// See `update-expected --never-remove`.
+        #[clap(long, value_name = "OUTCOME")]
+        never_remove: Vec<OutcomeName>,
+        /// See `update-expected --always-merge`.
+        #[clap(long, value_name = "OUTCOME")]
+        always_merge: Vec<OutcomeName>,
+        /// Only remove `disabled` conditions whose reason refers to this bug, given by its number
+        /// or URL. Tests and subtests disabled for other reasons stay disabled.
+        #[clap(long, value_name = "N|URL")]
+        bug: Option<BugArg>,
+    },
+    /// Quarantine tests that have become flaky: mark them with `quarantined: <DATE>`, and widen
+    /// the expectations of them and their subtests to every outcome, so that they can't fail
+    /// until someone reviews them.
+    ///
+    /// `update-expected` leaves quarantined tests untouched, and `triage` lists those quarantined
+    /// for longer than its `--quarantine-review-days`. Tests without a section in metadata (i.e.,
+    /// because they pass everywhere) are given one if named by a test URL path without
+    /// wildcards, besides the `*` ending a CTS query.
+    Quarantine {
+        /// Tests to quarantine, as test patterns (see `moz-webgpu-cts --help`).
+        #[clap(required = true)]
+        tests: Vec<TestPattern>,
+        /// The day tests were quarantined on, as `YYYY-MM-DD`. Defaults to today.
+        #[clap(long, value_name = "YYYY-MM-DD")]
+        date: Option<NaiveDate>,
+        /// Remove the quarantine of tests instead. Their expectations are left widened until
+        /// reset, i.e., with `update-expected --preset reset-all`.
+        #[clap(long, conflicts_with = "date")]
+        release: bool,
+    },
+    /// File bugs in Bugzilla for tests expected to permanently fail or crash that have no `bug`
+    /// property yet, and write the URLs of filed bugs to their `bug` properties.
+    ///
+    /// Filing more than `--confirm-above` bugs at once needs confirmation, since tests that
+    /// predate `bug` properties have none; use `--filter` to file bugs only for tests newly
+    /// marked as failing.
+    ///
+    /// A bug is filed for each test, listing the expectations of it and its subtests in each
+    /// configuration where they permanently fail or crash, unless disabled there. Bugs are filed
+    /// with the API key in the `BUGZILLA_API_KEY` environment variable.
+    FileBugs {
+        /// Tests to file bugs for, as test patterns (see `moz-webgpu-cts --help`). Defaults to all
+        /// tests.
+        #[clap(long = "filter", value_name = "TEST")]
+        filters: Vec<TestPattern>,
+        /// The product to file bugs in.
+        #[clap(long, default_value = "Core")]
+        product: String,
+        /// The component to file bugs in.
+        #[clap(long, default_value = "Graphics: WebGPU")]
+        component: String,
+        /// The URL of the Bugzilla instance to file bugs in.
+        #[clap(long, value_name = "URL", default_value = BUGZILLA_URL)]
+        bugzilla_url: String,
+        /// Print the bugs that would be filed, without filing them or changing metadata.
+        #[clap(long)]
+        dry_run: bool,
+        /// File bugs without asking for confirmation, even if more than `--confirm-above` would
+        /// be filed.
+        #[clap(long, short = 'y')]
+        yes: bool,
+        /// The number of bugs that may be filed before confirmation is required. This guards
+        /// against, i.e., filing a bug for every test that fails without a `bug` yet, rather than
+        /// only for those matching `--filter`. Without a terminal to ask on, filing is refused
+        /// instead.
+        #[clap(long, value_name = "COUNT", default_value_t = 5)]
+        confirm_above: usize,
+    },
+    /// Compare reports from runs without a regression ("good") against reports from runs with it
+    /// ("bad"), and list the tests and subtests whose outcomes differ, grouped by CTS file and
+    /// platform.
+    ///
+    /// Output is Markdown, suitable for pasting into a regression bug. Only configurations covered
+    /// by both sets of reports are compared.
+    BisectReports {
+        /// Direct paths or HTTP(S) URLs to report files from good runs.
+        #[clap(
+            long = "good",
+            value_name = "REPORT_PATH",
+            required_unless_present = "good_globs"
+        )]
+        good_paths: Vec<PathBuf>,
+        /// Cross-platform `wax` globs to enumerate report files from good runs. See
+        /// `update-expected --glob`.
+        #[clap(long = "good-glob", value_name = "REPORT_GLOB")]
+        good_globs: Vec<String>,
+        /// Direct paths or HTTP(S) URLs to report files from bad runs.
+        #[clap(
+            long = "bad",
+            value_name = "REPORT_PATH",
+            required_unless_present = "bad_globs"
+        )]
+        bad_paths: Vec<PathBuf>,
+        /// Like `--good-glob`, but for bad runs.
+        #[clap(long = "bad-glob", value_name = "REPORT_GLOB")]
+        bad_globs: Vec<String>,
+        /// See `update-expected --platform-map`.
+        #[clap(long = "platform-map", value_name = "KEY=VALUE:PLATFORM")]
+        platform_overrides: Vec<PlatformOverride>,
+        #[clap(value_enum, long, default_value_t = Default::default())]
+        format: OutputFormat,
+    },
+    /// Compare reports from runs before a change to Firefox or the CTS against reports from runs
+    /// after it, and print, per platform, how many tests and subtests went from each outcome to
+    /// each other outcome, i.e., how many went from `PASS` to `FAIL`.
+    ///
+    /// Output is Markdown. Only configurations covered by both sets of reports are compared.
+    Transitions {
+        /// Direct paths or HTTP(S) URLs to report files from runs before the change.
+        #[clap(
+            long = "before",
+            value_name = "REPORT_PATH",
+            required_unless_present = "before_globs"
+        )]
+        before_paths: Vec<PathBuf>,
+        /// Cross-platform `wax` globs to enumerate report files from runs before the change. See
+        /// `update-expected --glob`.
+        #[clap(long = "before-glob", value_name = "REPORT_GLOB")]
+        before_globs: Vec<String>,
+        /// Direct paths or HTTP(S) URLs to report files from runs after the change.
+        #[clap(
+            long = "after",
+            value_name = "REPORT_PATH",
+            required_unless_present = "after_globs"
+        )]
+        after_paths: Vec<PathBuf>,
+        /// Like `--before-glob`, but for runs after the change.
+        #[clap(long = "after-glob", value_name = "REPORT_GLOB")]
+        after_globs: Vec<String>,
+        /// See `update-expected --platform-map`.
+        #[clap(long = "platform-map", value_name = "KEY=VALUE:PLATFORM")]
+        platform_overrides: Vec<PlatformOverride>,
+    },
+    /// Explain, step by step, how `update-expected` would change the expectations of a single
+    /// test, without writing anything: for each configuration of the test and its subtests,
+    /// current and reported outcomes, the rule of the preset that resolves them, and the result.
+    Explain {
+        /// The test to explain, as a test URL path, i.e.,
+        /// `/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*`.
+        test: String,
+        /// Direct paths or HTTP(S) URLs to report files to be processed.
+        #[clap(long = "report", value_name = "REPORT_PATH")]
+        report_paths: Vec<PathBuf>,
+        /// Cross-platform `wax` globs to enumerate report files to be processed. See
+        /// `update-expected --glob`.
+        #[clap(long = "glob", value_name = "REPORT_GLOB")]
+        report_globs: Vec<String>,
+        /// See `update-expected --preset`. Scripts are not supported.
         #[clap(long, default_value = "reset-contradictory")]
         preset: ReportProcessingPreset,
+        /// See `update-expected --platform-map`.
+        #[clap(long = "platform-map", value_name = "KEY=VALUE:PLATFORM")]
+        platform_overrides: Vec<PlatformOverride>,
+    },
+    /// Bundle the metadata and report entries relevant to a single test into a gzipped tarball,
+    /// to attach to bugs filed against this tool. Reports are redacted; see `--run-info-key`.
+    SnapshotRepro {
+        /// The test to snapshot, as a test URL path, i.e.,
+        /// `/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*`.
+        test: String,
+        /// Direct paths or HTTP(S) URLs to report files to be processed.
+        #[clap(long = "report", value_name = "REPORT_PATH")]
+        report_paths: Vec<PathBuf>,
+        /// Cross-platform `wax` globs to enumerate report files to be processed. See
+        /// `update-expected --glob`.
+        #[clap(long = "glob", value_name = "REPORT_GLOB")]
+        report_globs: Vec<String>,
+        /// See `update-expected --platform-map`. Not needed to process the snapshot's reports.
+        #[clap(long = "platform-map", value_name = "KEY=VALUE:PLATFORM")]
+        platform_overrides: Vec<PlatformOverride>,
+        /// A `run_info` key to keep in reports, besides those that determine their platform and
+        /// build profile. Others are removed. May be repeated.
+        #[clap(long = "run-info-key", value_name = "KEY")]
+        run_info_keys: Vec<String>,
+        /// Where to write the tarball.
+        #[clap(long, short, default_value = "moz-webgpu-cts-repro.tar.gz")]
+        output: PathBuf,
     },
-    /// Parse test metadata, apply automated fixups, and re-emit it in normalized form.
+    /// Parse test metadata, apply automated fixups, and re-emit it in normalized form. Directory
+    /// metadata (i.e., `__dir__.ini`) is normalized, too.
     #[clap(name = "fixup", alias = "fmt")]
-    Fixup,
+    Fixup {
+        /// Report metadata files that are not already fixed up and normalized, instead of writing
+        /// to them, and fail if there are any.
+        #[clap(long)]
+        check: bool,
+        /// Also print findings, including parse errors, as annotations on `stdout` for CI systems
+        /// to attach to the lines of metadata that need attention.
+        #[clap(long, value_name = "FORMAT")]
+        annotate: Option<AnnotationFormat>,
+        /// Merge CTS tests with sections in more than one metadata file into the last of them,
+        /// which is the one `update-expected` keeps, and remove files left empty. Duplicates are
+        /// otherwise only reported. With `--check`, fail if there are any duplicates.
+        #[clap(long)]
+        dedupe: bool,
+    },
+    /// Remove metadata that states what `wptrunner` assumes without it: expectations of the
+    /// default outcome (i.e., `expected: OK` for tests, or `expected: PASS` for subtests), and
+    /// then subtest and test sections left without properties. Files left empty are removed.
+    ///
+    /// `update-expected` does much of this for the tests it processes; this does it for all
+    /// metadata, without any reports.
+    Clean {
+        /// Report how much would be removed, and from which files, without changing metadata.
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Move the metadata of tests that were renamed or moved (i.e., by a CTS refactor) to their
+    /// new names, keeping the expectations of them and their subtests. Sections are moved between
+    /// metadata files where needed, and files left empty are removed. Fails if a test can't be
+    /// moved, since its new name already has a section.
+    Migrate {
+        /// A file listing renames, one per line, as `<OLD> <NEW>`, where both are either CTS
+        /// queries or test URL paths. Names ending in `*` rename every test under them, i.e.,
+        /// `webgpu:api,operation,buffer,* webgpu:api,operation,buffers,*`. Lines starting with `#`
+        /// are ignored.
+        renames: PathBuf,
+        /// Print the tests that would be moved, without changing metadata.
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Merge several metadata files covering the same test file (i.e., left scattered by manual
+    /// edits or a botched rebase) into the last of them, and remove the others.
+    ///
+    /// Sections of a test in more than one file are merged like `fixup --dedupe` does: later files
+    /// take precedence, with test properties taken from the last section setting any, and
+    /// subtests from the last section that has them. Sections whose files disagree are reported.
+    MergeFiles {
+        /// The metadata files to merge, in order of increasing precedence. The last is the one
+        /// merged into.
+        #[clap(required = true, num_args = 2..)]
+        files: Vec<PathBuf>,
+        /// Report the sections that would be merged, without changing metadata.
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Compare expectations in metadata between two checkouts, or two revisions of one: tests
+    /// added and removed, and changes in expected outcomes of tests and subtests, per platform and
+    /// build profile.
+    Diff {
+        /// The metadata to compare against: either a path to a Gecko checkout, or `rev:REV` for a
+        /// revision of the checkout given by `--gecko-checkout` (i.e., `rev:central`), read with
+        /// `hg` or `git`.
+        old: MetadataSource,
+        /// The metadata to compare, in the same form as `OLD`. Defaults to the working tree of the
+        /// checkout given by `--gecko-checkout`.
+        new: Option<MetadataSource>,
+    },
+    /// Check metadata against the tests that exist, reporting sections for tests that no longer
+    /// exist (i.e., after a CTS update), CTS queries that are malformed, and sections that the
+    /// harness would never apply, because the URL it runs their test at doesn't map back to them.
+    /// Fails if any are found.
+    Validate {
+        /// Enumerate CTS tests from this listing, instead of from the variants in test files in
+        /// the Gecko checkout: either `listing_meta.json` from a CTS build, or a file with one
+        /// CTS query per line. Lines starting with `#` are ignored.
+        #[clap(long, value_name = "FILE")]
+        listing: Option<PathBuf>,
+    },
+    /// Print statistics about expectations in metadata, for tracking its health over time: for
+    /// each platform and build profile, how many tests and subtests are expected to pass versus
+    /// fail, time out, crash, etc., and how many subtests are expected to have one, two, or more
+    /// outcomes, which is a proxy for how much flaky behavior has accumulated. Tests without
+    /// metadata are expected to pass, and aren't counted.
+    Stats {
+        /// Instead, list metadata files with failing, intermittent, or disabled tests or subtests,
+        /// with counts of each, from most to fewest in total, and the most recent change to each
+        /// file in version control.
+        #[clap(long)]
+        by_file: bool,
+    },
+    /// List tests and subtests whose expectations match all of the given filters, with their
+    /// expected outcomes in each matching configuration, i.e., `search --outcome CRASH --platform
+    /// linux --build-profile debug` for those expected to crash in Linux debug builds.
+    ///
+    /// Expectations are resolved per configuration, so conditions needn't be read by hand. Tests
+    /// without metadata are expected to pass everywhere, and aren't listed.
+    Search {
+        /// Only list tests matching any of these test patterns (see `moz-webgpu-cts --help`).
+        /// Subtests of matching tests are listed, too.
+        tests: Vec<TestPattern>,
+        /// Only list configurations in which this outcome is expected, as spelled in metadata
+        /// (i.e., `CRASH`). May be repeated, in which case any of them is matched.
+        #[clap(long, value_name = "OUTCOME")]
+        outcome: Vec<OutcomeName>,
+        /// Only list configurations on this platform (one of `win`, `linux`, `mac`, or
+        /// `android`). May be repeated.
+        #[clap(long)]
+        platform: Vec<Platform>,
+        /// Only list configurations with this build profile (one of `debug` or `opt`). May be
+        /// repeated.
+        #[clap(long, value_name = "PROFILE")]
+        build_profile: Vec<BuildProfile>,
+        /// Only list configurations that are disabled. Subtests are disabled wherever their test
+        /// is.
+        #[clap(long)]
+        disabled: bool,
+        /// Only list configurations that aren't disabled.
+        #[clap(long, conflicts_with = "disabled")]
+        enabled: bool,
+        /// Print a table with one row for each outcome expected in each configuration instead,
+        /// for pivoting in spreadsheets.
+        #[clap(long, value_name = "FORMAT")]
+        table: Option<TableFormat>,
+    },
+    /// Print a JSON manifest of tests expected to have more than one outcome in some
+    /// configuration, with a suggested number of times to run each again (i.e., with `wptrunner
+    /// --rerun`) to confirm or retire their intermittent expectations.
+    ///
+    /// Suggestions are based on how often each test's outcomes varied in the given reports:
+    /// enough runs to observe its rarest outcome at least once with 95% confidence. Tests whose
+    /// outcomes did not vary, or that aren't found in reports, are suggested `--max-repeat` runs.
+    RerunManifest {
+        /// Direct paths or HTTP(S) URLs to report files from runs to compute flake rates from.
+        report_paths: Vec<PathBuf>,
+        /// Cross-platform `wax` globs to enumerate report files. See `update-expected --glob`.
+        #[clap(long = "glob", value_name = "REPORT_GLOB")]
+        report_globs: Vec<String>,
+        /// See `update-expected --platform-map`.
+        #[clap(long = "platform-map", value_name = "KEY=VALUE:PLATFORM")]
+        platform_overrides: Vec<PlatformOverride>,
+        /// The most runs to suggest for any test.
+        #[clap(long, value_name = "N", default_value_t = 50)]
+        max_repeat: usize,
+    },
+    /// Compare outcomes between the worker pools that ran the same configuration in the given
+    /// reports, and list tests and subtests whose outcomes differed between pools.
+    ///
+    /// Outcomes that vary only between pools are often not intermittent at all, but caused by
+    /// differences between pools' environments, like mismatched driver versions. Runs whose
+    /// `run_info` doesn't identify a pool are skipped.
+    PoolSkew {
+        /// Direct paths or HTTP(S) URLs to report files to compare.
+        report_paths: Vec<PathBuf>,
+        /// Cross-platform `wax` globs to enumerate report files. See `update-expected --glob`.
+        #[clap(long = "glob", value_name = "REPORT_GLOB")]
+        report_globs: Vec<String>,
+        /// See `update-expected --platform-map`.
+        #[clap(long = "platform-map", value_name = "KEY=VALUE:PLATFORM")]
+        platform_overrides: Vec<PlatformOverride>,
+        /// A `run_info` key whose value identifies the worker pool of a run. May be specified
+        /// more than once, in which case the first key present in a run is used. Defaults to
+        /// `worker_pool` and `worker_type`.
+        #[clap(long = "pool-key", value_name = "KEY")]
+        pool_keys: Vec<String>,
+    },
+    /// For CTS tests with failing subtests in the given reports, print the narrowest CTS query
+    /// that selects exactly the subtests that failed (i.e.,
+    /// `webgpu:api,operation,foo:bar:format="r8unorm";*`), so that just the failing slice of a
+    /// test can be run locally.
+    ///
+    /// Queries are made of the parameters shared by all failing subtests of a test. Tests where
+    /// those also select some passing subtest are listed without a query.
+    ReproQueries {
+        /// Direct paths or HTTP(S) URLs to report files to find failing subtests in.
+        report_paths: Vec<PathBuf>,
+        /// Cross-platform `wax` globs to enumerate report files. See `update-expected --glob`.
+        #[clap(long = "glob", value_name = "REPORT_GLOB")]
+        report_globs: Vec<String>,
+        /// See `update-expected --platform-map`.
+        #[clap(long = "platform-map", value_name = "KEY=VALUE:PLATFORM")]
+        platform_overrides: Vec<PlatformOverride>,
+        /// Only consider runs on this platform (one of `win`, `linux`, `mac`, or `android`).
+        #[clap(long)]
+        platform: Option<Platform>,
+        /// Only list tests with at least this many failing subtests.
+        #[clap(long, value_name = "N", default_value_t = 2)]
+        min_failing: usize,
+    },
     Triage {
         #[clap(value_enum, long, default_value_t = Default::default())]
         on_zero_item: OnZeroItem,
+        #[clap(value_enum, long, default_value_t = Default::default())]
+        format: OutputFormat,
+        /// With `--format markdown`, the origin that test names link to, i.e., where
+        /// `wptserve` serves tests over HTTPS.
+        #[clap(
+            long,
+            value_name = "URL",
+            default_value = "https://web-platform.test:8443"
+        )]
+        test_origin: String,
+        /// Only analyze metadata files matching this [`wax` glob], relative to the directory
+        /// containing WebGPU CTS metadata (i.e., `**/shader/**`).
+        ///
+        /// [`wax` glob]: https://github.com/olson-sean-k/wax/blob/master/README.md#patterns
+        #[clap(long = "file", value_name = "GLOB")]
+        file_glob: Option<String>,
+        /// Only analyze tests matching this test pattern (see `moz-webgpu-cts --help`), i.e.,
+        /// `webgpu:api,operation,command_buffer,*`, so that one area of the CTS can be triaged at
+        /// a time. May be repeated, in which case tests matching any of them are analyzed. Applies
+        /// to `ERROR`s grouped from reports, too.
+        #[clap(long = "filter", value_name = "TEST")]
+        filters: Vec<TestPattern>,
+        /// Also count tests needing attention by area of the CTS, from most to fewest: the files
+        /// under this many segments of the file path of their CTS queries, i.e., `webgpu:api,*`
+        /// with 1, or `webgpu:api,validation,*` with 2.
+        #[clap(long, value_name = "DEPTH")]
+        by_cts_area: Option<NonZeroUsize>,
+        /// The outcomes that count as bad, from most to least severe, separated by commas (i.e.,
+        /// `ERROR,CRASH,TIMEOUT`). Tests are also counted by the most severe of these that they
+        /// expect anywhere.
+        ///
+        /// This also decides priorities: tests expecting an outcome in the more severe half of
+        /// the ordering are high priority where they expect it permanently, and medium priority
+        /// where they expect it intermittently. Other outcomes are one priority lower, and those
+        /// not listed are left out.
+        #[clap(long, value_name = "OUTCOMES", default_value_t = Default::default())]
+        severity: SeverityOrder,
+        /// Direct paths or HTTP(S) URLs to report files whose `ERROR` outcomes should be grouped
+        /// by message, with numbers and addresses ignored, so that different problems are
+        /// counted separately.
+        report_paths: Vec<PathBuf>,
+        /// Cross-platform `wax` globs to enumerate report files. See `update-expected --glob`.
+        #[clap(long = "glob", value_name = "REPORT_GLOB")]
+        report_globs: Vec<String>,
+        /// See `update-expected --platform-map`.
+        #[clap(long = "platform-map", value_name = "KEY=VALUE:PLATFORM")]
+        platform_overrides: Vec<PlatformOverride>,
+        /// A file mapping tests to their owners, with one `<PATTERN> <OWNER>` pair per line,
+        /// where `<PATTERN>` is as in `update-expected --never-modify` (i.e.,
+        /// `webgpu:shader,execution,* shader-team`). The first matching pattern determines a
+        /// test's owner. Tests are counted by owner, and owners are included in `--export-json`.
+        #[clap(long, value_name = "FILE")]
+        owners: Option<PathBuf>,
+        /// Also write the tests found in each category, by platform, to this file as JSON.
+        #[clap(long, value_name = "FILE")]
+        export_json: Option<PathBuf>,
+        /// Also write a self-contained HTML page to this file, with a collapsible section for
+        /// each platform, counts of tests by issue, and sortable tables of tests needing
+        /// attention, i.e., for linking from CI runs.
+        #[clap(long, value_name = "FILE")]
+        export_html: Option<PathBuf>,
+        /// Also write the expectations of every test analyzed to this file, with one row for
+        /// each outcome expected in each configuration, for pivoting in spreadsheets. Written as
+        /// TSV if the file name ends in `.tsv`, and as CSV otherwise.
+        #[clap(long, value_name = "FILE")]
+        export_table: Option<PathBuf>,
+        /// List tests quarantined (see `quarantine`) at least this many days ago as due for
+        /// review.
+        #[clap(long, value_name = "DAYS", default_value_t = 14)]
+        quarantine_review_days: u64,
+        /// Query Bugzilla for open bugs in `--bugzilla-product` and `--bugzilla-component`, and
+        /// count tests needing attention by whether one tracks them: one their `bug` properties
+        /// refer to, or one whose summary or whiteboard mentions their CTS query or URL path.
+        /// Tests permanently failing with no open bug are listed first, as the highest priority.
+        ///
+        /// The API key in the `BUGZILLA_API_KEY` environment variable is used, if set, so that
+        /// non-public bugs are found, too.
+        #[clap(long)]
+        bugzilla: bool,
+        /// The URL of the Bugzilla instance to query with `--bugzilla`.
+        #[clap(long, value_name = "URL", default_value = BUGZILLA_URL)]
+        bugzilla_url: String,
+        /// The product to query with `--bugzilla`.
+        #[clap(long, value_name = "PRODUCT", default_value = "Core")]
+        bugzilla_product: String,
+        /// The component to query with `--bugzilla`.
+        #[clap(long, value_name = "COMPONENT", default_value = "Graphics: WebGPU")]
+        bugzilla_component: String,
+    },
+    /// Print a completion script for a shell to `stdout`, i.e., for Bash, with `moz-webgpu-cts
+    /// completions bash > ~/.local/share/bash-completion/completions/moz-webgpu-cts`.
+    ///
+    /// Values of options like `--browser` and `--preset` are completed, too.
+    Completions {
+        #[clap(value_enum)]
+        shell: clap_complete::Shell,
     },
 }
 
-#[derive(Clone, Copy, Debug, ValueEnum)]
-enum ReportProcessingPreset {
-    /// alias: `new-fx`
-    #[value(alias("new-fx"))]
-    ResetContradictory,
-    /// alias: `same-fx`
-    #[value(alias("same-fx"))]
-    Merge,
-    ResetAll,
+impl Subcommand {
+    /// Whether this subcommand may write metadata, and so must hold a [`MetadataLock`].
+    fn writes_metadata(&self) -> bool {
+        match self {
+            Self::UpdateExpected { dry_run, .. } => !dry_run,
+            Self::Enable { .. } | Self::Quarantine { .. } => true,
+            Self::FileBugs { dry_run, .. } => !dry_run,
+            Self::Fixup { check, .. } => !check,
+            Self::Clean { dry_run }
+            | Self::Migrate { dry_run, .. }
+            | Self::MergeFiles { dry_run, .. } => !dry_run,
+            Self::BisectReports { .. }
+            | Self::Transitions { .. }
+            | Self::Explain { .. }
+            | Self::SnapshotRepro { .. }
+            | Self::Diff { .. }
+            | Self::Validate { .. }
+            | Self::Stats { .. }
+            | Self::Search { .. }
+            | Self::RerunManifest { .. }
+            | Self::PoolSkew { .. }
+            | Self::ReproQueries { .. }
+            | Self::Triage { .. }
+            | Self::Completions { .. } => false,
+        }
+    }
+}
+
+/// Where `diff` reads metadata from.
+#[derive(Clone, Debug)]
+enum MetadataSource {
+    Checkout(PathBuf),
+    Revision(String),
+}
+
+impl FromStr for MetadataSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("rev:") {
+            Some("") => Err("expected a revision after `rev:`".to_owned()),
+            Some(rev) => Ok(Self::Revision(rev.to_owned())),
+            None => Ok(Self::Checkout(s.into())),
+        }
+    }
+}
+
+/// A bug given by its number or URL; see [`bugzilla::bug_id`].
+#[derive(Clone, Copy, Debug)]
+struct BugArg(u64);
+
+impl FromStr for BugArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        bugzilla::bug_id(s)
+            .map(Self)
+            .ok_or_else(|| format!("expected a bug number or URL, got {s:?}"))
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default, ValueEnum)]
@@ -119,994 +911,2418 @@ enum OnZeroItem {
     Hide,
 }
 
+/// Set when the user requests cancellation via Ctrl-C.
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
 fn main() -> ExitCode {
-    env_logger::builder()
-        .filter_level(log::LevelFilter::Info)
-        .parse_default_env()
-        .init();
-    run(Cli::parse())
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let TracingGuards {
+        flush_guard: _flush_guard,
+        profile,
+    } = match init_tracing(cli.trace_out.as_deref(), cli.profile) {
+        Ok(guards) => guards,
+        Err(e) => {
+            render_error(e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let handler_res = ctrlc::set_handler(|| {
+        let already_cancelled = CANCELLED.swap(true, atomic::Ordering::Relaxed);
+        if already_cancelled {
+            std::process::exit(130);
+        }
+        tracing::warn!(concat!(
+            "cancellation requested, finishing in-flight work; ",
+            "press Ctrl-C again to exit immediately"
+        ));
+    });
+    if let Err(e) = handler_res {
+        tracing::warn!("failed to install Ctrl-C handler: {e}");
+    }
+
+    if cli.progress {
+        progress::enable();
+    }
+
+    let exit_code = run(cli, &matches);
+    if let Some(profile) = profile {
+        eprint!("{profile}");
+    }
+    exit_code
 }
 
-fn run(cli: Cli) -> ExitCode {
-    let Cli {
-        gecko_checkout,
-        subcommand,
-    } = cli;
+struct TracingGuards {
+    flush_guard: Option<FlushGuard<BufWriter<fs::File>>>,
+    profile: Option<Profile>,
+}
 
-    let gecko_checkout = match gecko_checkout
-        .map(Ok)
-        .unwrap_or_else(search_for_moz_central_ckt)
-    {
-        Ok(ckt_path) => ckt_path,
-        Err(AlreadyReportedToCommandline) => return ExitCode::FAILURE,
+/// Sets up logging to `stderr`, filtered by the `RUST_LOG` environment variable, and (optionally)
+/// collection of span timings into `trace_out` and a [`Profile`].
+///
+/// The returned flush guard must be kept alive until the program exits, so that all span timings
+/// are flushed to `trace_out`.
+fn init_tracing(trace_out: Option<&Path>, profile: bool) -> Result<TracingGuards, Error> {
+    use tracing_subscriber::{filter::LevelFilter, fmt, prelude::*, EnvFilter};
+
+    let fmt_layer = fmt::layer().with_writer(io::stderr).with_filter(
+        EnvFilter::builder()
+            .with_default_directive(LevelFilter::INFO.into())
+            .from_env_lossy(),
+    );
+
+    let (flame_layer, flush_guard) = match trace_out {
+        Some(path) => {
+            let (layer, guard) =
+                FlameLayer::with_file(path).map_err(|source| Error::CreateTraceOutput {
+                    path: path.to_owned(),
+                    source,
+                })?;
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
     };
 
-    match subcommand {
+    let (profile_layer, profile) = if profile {
+        let (layer, profile) = ProfileLayer::new();
+        (Some(layer), Some(profile))
+    } else {
+        (None, None)
+    };
+
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(flame_layer)
+        .with(profile_layer)
+        .init();
+
+    Ok(TracingGuards {
+        flush_guard,
+        profile,
+    })
+}
+
+/// Fills in options of `cli` that were not given on the command line, according to `matches`,
+/// from `config`.
+fn apply_config(cli: &mut Cli, config: Config, matches: &ArgMatches) {
+    let given = |matches: &ArgMatches, id: &str| {
+        matches!(
+            matches.value_source(id),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        )
+    };
+    let Config {
+        browser,
+        update_expected,
+        triage,
+    } = config;
+
+    if let Some(browser) = browser.filter(|_| !given(matches, "browser")) {
+        cli.browser = browser;
+    }
+
+    let Some((_name, matches)) = matches.subcommand() else {
+        return;
+    };
+    match &mut cli.subcommand {
         Subcommand::UpdateExpected {
             report_globs,
             report_paths,
+            taskcluster_task_groups,
+            from_push,
             preset,
+            never_modify,
+            ..
         } => {
-            let report_globs = {
-                let mut found_glob_parse_err = false;
-                let globs = report_globs
-                    .into_iter()
-                    .filter_map(|glob| match Glob::diagnosed(&glob) {
-                        Ok((glob, _diagnostics)) => Some(glob.into_owned().partition()),
-                        Err(diagnostics) => {
-                            found_glob_parse_err = true;
-                            let error_reports = diagnostics
-                                .into_iter()
-                                .filter(|diag| {
-                                    // N.B.: There should be at least one of these!
-                                    diag.severity()
-                                        .map_or(true, |sev| sev == miette::Severity::Error)
-                                })
-                                .map(Report::new_boxed);
-                            for report in error_reports {
-                                eprintln!("{report:?}");
-                            }
-                            None
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                if found_glob_parse_err {
-                    log::error!("failed to parse one or more WPT report globs; bailing");
-                    return ExitCode::FAILURE;
-                }
+            let UpdateExpectedConfig {
+                preset: config_preset,
+                glob,
+                never_modify: config_never_modify,
+            } = update_expected;
+            if let Some(config_preset) = config_preset.filter(|_| !given(matches, "preset")) {
+                *preset = config_preset;
+            }
+            let no_reports_given = report_globs.is_empty()
+                && report_paths.is_empty()
+                && taskcluster_task_groups.is_empty()
+                && from_push.is_empty();
+            if no_reports_given {
+                *report_globs = glob;
+            }
+            if never_modify.is_none() {
+                *never_modify = config_never_modify;
+            }
+        }
+        Subcommand::Triage {
+            file_glob,
+            severity,
+            owners,
+            ..
+        } => {
+            let TriageConfig {
+                severity: config_severity,
+                owners: config_owners,
+                file,
+            } = triage;
+            if let Some(config_severity) = config_severity.filter(|_| !given(matches, "severity")) {
+                *severity = config_severity;
+            }
+            if owners.is_none() {
+                *owners = config_owners;
+            }
+            if file_glob.is_none() {
+                *file_glob = file;
+            }
+        }
+        _ => (),
+    }
+}
 
-                globs
-            };
+/// Renders `error` to `stderr`.
+fn render_error(error: Error) {
+    eprintln!("{:?}", Report::new(error));
+}
 
-            let report_paths_from_glob = {
-                let mut found_glob_walk_err = false;
-                let files = report_globs
-                    .iter()
-                    .flat_map(|(base_path, glob)| {
-                        glob.walk(base_path)
-                            .filter_map(|entry| match entry {
-                                Ok(entry) => Some(entry.into_path()),
-                                Err(e) => {
-                                    found_glob_walk_err = true;
-                                    let ctx_msg = if let Some(path) = e.path() {
-                                        format!(
-                                            "failed to enumerate files for glob `{}` at path {}",
-                                            glob,
-                                            path.display()
-                                        )
-                                    } else {
-                                        format!("failed to enumerate files for glob `{glob}`")
-                                    };
-                                    let e = Report::msg(e).wrap_err(ctx_msg);
-                                    eprintln!("{e:?}");
-                                    None
-                                }
-                            })
-                            .collect::<Vec<_>>() // OPT: Can we get rid of this somehow?
-                    })
-                    .collect::<Vec<_>>();
+fn render_reconciliation_summary(summary: &ReconciliationSummary) {
+    let ReconciliationSummary {
+        num_reports,
+        num_skipped_runs,
+        num_ignored_runs,
+        num_ignored_entries: _,
+        num_unsampled_entries: _,
+        configurations: _,
+        new_tests,
+        removed_tests,
+        orphaned_tests: _,
+        relocated_tests,
+        reenabled_tests,
+        disabled_tests,
+        protected_tests,
+        new_files,
+        emptied_files,
+        warnings,
+    } = summary;
+
+    for warning in warnings {
+        tracing::warn!("{warning}");
+    }
+    for test_path in new_tests {
+        tracing::info!("new test entry: {test_path:?}");
+    }
+    for test_path in removed_tests {
+        tracing::warn!("removing metadata after no entries found in reports for {test_path:?}");
+    }
+    for Relocation { from, to } in relocated_tests {
+        tracing::debug!(
+            "relocating {} from {} to {}",
+            to.runner_url_path(),
+            from.rel_metadata_path(),
+            to.rel_metadata_path()
+        );
+    }
+    for test_path in reenabled_tests {
+        tracing::info!("removing disable(s) in {test_path:?}");
+    }
+    for test_path in disabled_tests {
+        tracing::warn!("disabling {test_path:?} where it's expected to permanently crash");
+    }
+    for test_path in protected_tests {
+        tracing::info!("leaving metadata untouched per never-modify list: {test_path:?}");
+    }
+    for path in new_files {
+        tracing::warn!("creating new metadata file `{}`", path.display());
+    }
+    for path in emptied_files {
+        tracing::info!("removing now-empty metadata file {}", path.display());
+    }
 
-                if found_glob_walk_err {
-                    log::error!(concat!(
-                        "failed to enumerate files with WPT report globs, ",
-                        "see above for more details"
-                    ));
-                    return ExitCode::FAILURE;
-                }
+    tracing::info!(
+        concat!(
+            "processed {} report(s) ({} skipped, {} ignored): {} new test(s), ",
+            "{} removed test(s), {} relocated test(s), {} re-enabled test(s), ",
+            "{} disabled test(s), {} protected test(s), {} new file(s), {} removed file(s), ",
+            "{} warning(s)"
+        ),
+        num_reports,
+        num_skipped_runs,
+        num_ignored_runs,
+        new_tests.len(),
+        removed_tests.len(),
+        relocated_tests.len(),
+        reenabled_tests.len(),
+        disabled_tests.len(),
+        protected_tests.len(),
+        new_files.len(),
+        emptied_files.len(),
+        warnings.len(),
+    );
 
-                files
-            };
+    let exclusions = summary.exclusions();
+    if !exclusions.is_empty() {
+        tracing::warn!("left out of reconciliation: {exclusions}");
+    }
+}
 
-            if report_paths_from_glob.is_empty() && !report_globs.is_empty() {
-                if report_paths.is_empty() {
-                    log::error!(concat!(
-                        "reports were specified exclusively via glob search, ",
-                        "but none were found; bailing"
-                    ));
-                    return ExitCode::FAILURE;
-                } else {
-                    log::warn!(concat!(
-                        "report were specified via path and glob search, ",
-                        "but none were found via glob; ",
-                        "continuing with report paths"
-                    ))
-                }
-            }
+/// Prints tests in `summary` whose metadata no report had an entry for to `stdout`, for
+/// `--report-orphans`.
+fn render_orphans(summary: &ReconciliationSummary) {
+    let ReconciliationSummary {
+        configurations,
+        removed_tests,
+        orphaned_tests,
+        ..
+    } = summary;
+
+    fn render_configs(configs: &[(Platform, BuildProfile)]) -> impl Display + '_ {
+        lazy_format!(|f| if configs.is_empty() {
+            write!(f, "none")
+        } else {
+            let configs = configs
+                .iter()
+                .map(|(platform, build_profile)| lazy_format!("{platform:?} {build_profile:?}"));
+            write!(f, "{}", configs.join_with(", "))
+        })
+    }
+    let covered = configurations.iter().copied().collect::<Vec<_>>();
+    let uncovered = summary.uncovered_configurations().collect::<Vec<_>>();
+    println!("covered configurations: {}", render_configs(&covered));
+    println!("uncovered configurations: {}", render_configs(&uncovered));
+
+    println!("{} orphaned test(s):", orphaned_tests.len());
+    for test_path in orphaned_tests {
+        let fate = if removed_tests.contains(test_path) {
+            "removed"
+        } else {
+            "kept"
+        };
+        println!("  {} ({fate})", test_path.runner_url_path());
+    }
+    if !orphaned_tests.is_empty() {
+        if uncovered.is_empty() {
+            println!("reports covered every configuration, so deleting these should be safe");
+        } else {
+            println!("reports missed some configurations, where these may still run");
+        }
+    }
+}
 
-            let exec_report_paths = report_paths
+/// Gathers report paths given directly and via globs, rendering any errors encountered. Reports
+/// given as URLs are downloaded first. Returns `None` if the caller should bail.
+fn collect_report_paths(
+    fs: &impl FileSystem,
+    report_paths: Vec<PathBuf>,
+    report_globs: Vec<String>,
+) -> Option<Vec<PathBuf>> {
+    let report_paths = if report_paths.iter().any(|path| download::is_url(path)) {
+        let downloaded = state_dir().and_then(|state_dir| {
+            let dir = state_dir.join("downloads");
+            let agent = download::agent();
+            report_paths
                 .into_iter()
-                .chain(report_paths_from_glob)
-                .collect::<Vec<_>>();
-
-            log::trace!("working with the following WPT report files: {exec_report_paths:#?}");
-            log::info!("working with {} WPT report files", exec_report_paths.len());
-
-            let meta_files_by_path = match read_and_parse_all_metadata(&gecko_checkout)
-                .collect::<Result<IndexMap<_, _>, _>>()
-            {
-                Ok(paths) => paths,
-                Err(AlreadyReportedToCommandline) => return ExitCode::FAILURE,
-            };
-
-            #[derive(Debug, Default)]
-            struct EntryByCtsPath<'a> {
-                metadata_path: Option<TestPath<'a>>,
-                reported_path: Option<TestPath<'a>>,
-                entry: TestEntry,
+                .map(|path| match path.to_str() {
+                    Some(url) if download::is_url(&path) => download::download(&agent, url, &dir),
+                    _ => Ok(path),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        });
+        match downloaded {
+            Ok(report_paths) => report_paths,
+            Err(e) => {
+                render_error(e);
+                return None;
             }
+        }
+    } else {
+        report_paths
+    };
 
-            fn cts_path(test_path: &TestPath<'_>) -> Option<String> {
-                test_path
-                    .variant
-                    .as_ref()
-                    .filter(|v| v.starts_with("?q=webgpu:"))
-                    .map(|v| v.strip_prefix("?q=").unwrap().to_owned())
-                    .filter(|_q| test_path.path.ends_with("cts.https.html"))
-            }
+    let report_globs = {
+        let mut found_glob_parse_err = false;
+        let globs = report_globs
+            .into_iter()
+            .filter_map(|glob| match Glob::diagnosed(&glob) {
+                Ok((glob, _diagnostics)) => Some(glob.into_owned().partition()),
+                Err(diagnostics) => {
+                    found_glob_parse_err = true;
+                    let diagnostics = diagnostics
+                        .into_iter()
+                        .filter(|diag| {
+                            // N.B.: There should be at least one of these!
+                            diag.severity()
+                                .map_or(true, |sev| sev == miette::Severity::Error)
+                        })
+                        .map(GlobDiagnostic)
+                        .collect();
+                    render_error(Error::ParseReportGlob { diagnostics });
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
 
-            let mut file_props_by_file = IndexMap::<Utf8PathBuf, FileProps>::default();
-            let mut entries_by_cts_path = IndexMap::<String, EntryByCtsPath<'_>>::default();
-            let mut other_entries_by_test = IndexMap::<TestPath<'_>, TestEntry>::default();
-            let old_meta_file_paths = meta_files_by_path.keys().cloned().collect::<Vec<_>>();
+        if found_glob_parse_err {
+            tracing::error!("failed to parse one or more WPT report globs; bailing");
+            return None;
+        }
 
-            log::info!("loading metadata for comparison to reports…");
-            for (path, file) in meta_files_by_path {
-                let File { properties, tests } = file;
+        globs
+    };
 
-                let file_rel_path = path.strip_prefix(&gecko_checkout).unwrap();
+    let report_paths_from_glob = {
+        let mut found_glob_walk_err = false;
+        let files = report_globs
+            .iter()
+            .flat_map(|(base_path, glob)| {
+                fs.glob(base_path, glob)
+                    .into_iter()
+                    .filter_map(|entry| match entry {
+                        Ok(path) => Some(path),
+                        Err(e) => {
+                            found_glob_walk_err = true;
+                            render_error(e);
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>() // OPT: Can we get rid of this somehow?
+            })
+            .collect::<Vec<_>>();
 
-                file_props_by_file.insert(
-                    Utf8PathBuf::from(file_rel_path.to_str().unwrap()),
-                    properties,
-                );
+        if found_glob_walk_err {
+            tracing::error!(concat!(
+                "failed to enumerate files with WPT report globs, ",
+                "see above for more details"
+            ));
+            return None;
+        }
 
-                for (SectionHeader(name), test) in tests {
-                    let Test {
-                        properties,
-                        subtests,
-                    } = test;
+        files
+    };
 
-                    let test_path = TestPath::from_fx_metadata_test(file_rel_path, &name).unwrap();
+    if report_paths_from_glob.is_empty() && !report_globs.is_empty() {
+        if report_paths.is_empty() {
+            render_error(Error::NoReportsFound);
+            return None;
+        } else {
+            tracing::warn!(concat!(
+                "report were specified via path and glob search, ",
+                "but none were found via glob; ",
+                "continuing with report paths"
+            ))
+        }
+    }
 
-                    let freak_out_do_nothing = |what: &dyn Display| {
-                        log::error!("hoo boy, not sure what to do yet: {what}")
-                    };
+    let exec_report_paths = report_paths
+        .into_iter()
+        .chain(report_paths_from_glob)
+        .collect::<Vec<_>>();
 
-                    let mut reported_dupe_already = false;
-                    let mut dupe_err = || {
-                        if !reported_dupe_already {
-                            freak_out_do_nothing(&format_args!(
-                                concat!(
-                                    "duplicate entry for {:?}",
-                                    "discarding previous entries with ",
-                                    "this and further dupes"
-                                ),
-                                test_path
-                            ))
-                        }
-                        reported_dupe_already = true;
-                    };
+    tracing::trace!("working with the following WPT report files: {exec_report_paths:#?}");
+    tracing::info!("working with {} WPT report files", exec_report_paths.len());
 
-                    let TestEntry {
-                        entry: test_entry,
-                        subtests: subtest_entries,
-                    } = if let Some(cts_path) = cts_path(&test_path) {
-                        let entry = entries_by_cts_path.entry(cts_path).or_default();
-                        if let Some(_old) =
-                            entry.metadata_path.replace(test_path.clone().into_owned())
-                        {
-                            dupe_err();
-                        }
-                        &mut entry.entry
-                    } else {
-                        other_entries_by_test
-                            .entry(test_path.clone().into_owned())
-                            .or_default()
-                    };
+    Some(exec_report_paths)
+}
+
+/// Downloads reports from WebGPU test tasks in `task_groups`, and in the task groups of `pushes`,
+/// returning their paths.
+fn fetch_taskcluster_reports(
+    fs: &dyn FileSystem,
+    root_url: &str,
+    task_groups: Vec<String>,
+    pushes: &[Push],
+) -> Result<Vec<PathBuf>, Error> {
+    let taskcluster = Taskcluster::new(root_url);
+    let dir = state_dir()?.join("taskcluster");
+    let mut task_groups = task_groups;
+    for push in pushes {
+        let task_group = taskcluster.task_group_of_push(push)?;
+        tracing::info!("found task group {task_group} for push {push}");
+        task_groups.push(task_group);
+    }
+    let mut paths = Vec::new();
+    for task_group in task_groups {
+        paths.extend(taskcluster::fetch_reports(
+            fs,
+            &taskcluster,
+            &task_group,
+            &dir,
+            &CANCELLED,
+        )?);
+    }
+    Ok(paths)
+}
 
-                    let test_path = &test_path;
+/// Builds the strategy selected by `--preset`, `--subtest-preset`, `--never-remove`, and
+/// `--always-merge`.
+fn reconciliation_strategy(
+    fs: &dyn FileSystem,
+    preset: &PresetArg,
+    subtest_preset: Option<&PresetArg>,
+    never_remove: &[OutcomeName],
+    always_merge: &[OutcomeName],
+) -> Result<Arc<dyn ReconciliationStrategy>, Error> {
+    let mut strategy = preset.strategy(fs)?;
+    if let Some(subtest_preset) = subtest_preset {
+        strategy = Arc::new(PerLevel {
+            tests: strategy,
+            subtests: subtest_preset.strategy(fs)?,
+        });
+    }
+    if never_remove.is_empty() && always_merge.is_empty() {
+        return Ok(strategy);
+    }
+    let mut policy = OutcomePolicy::new(strategy);
+    for outcome in never_remove {
+        policy.add_rule(RuleAction::Keep, *outcome);
+    }
+    for outcome in always_merge {
+        policy.add_rule(RuleAction::Merge, *outcome);
+    }
+    Ok(Arc::new(policy))
+}
 
-                    if let Some(_old) = test_entry.meta_props.replace(properties) {
-                        dupe_err();
-                    }
+/// Builds the strategies selected by `--preset-override`, with `--never-remove` and
+/// `--always-merge` applied to each as they are to `--preset`.
+#[allow(clippy::type_complexity)]
+fn strategy_overrides(
+    fs: &dyn FileSystem,
+    preset_overrides: Vec<PresetOverride>,
+    never_remove: &[OutcomeName],
+    always_merge: &[OutcomeName],
+) -> Result<Vec<(TestPattern, Arc<dyn ReconciliationStrategy>)>, Error> {
+    preset_overrides
+        .into_iter()
+        .map(|PresetOverride { pattern, preset }| {
+            let strategy = reconciliation_strategy(fs, &preset, None, never_remove, always_merge)?;
+            Ok((pattern, strategy))
+        })
+        .collect()
+}
 
-                    for (SectionHeader(subtest_name), subtest) in subtests {
-                        let Subtest { properties } = subtest;
-                        let subtest_entry =
-                            subtest_entries.entry(subtest_name.clone()).or_default();
-                        if let Some(_old) = subtest_entry.meta_props.replace(properties) {
-                            if !reported_dupe_already {
-                                freak_out_do_nothing(&format_args!(
-                                    concat!(
-                                        "duplicate subtest in {:?} named {:?}, ",
-                                        "discarding previous entries with ",
-                                        "this and further dupes"
-                                    ),
-                                    test_path, subtest_name
-                                ));
-                            }
-                        }
-                    }
-                }
+/// Asks on the terminal what to do with `change` for `update-expected --interactive`. Once
+/// asked to accept or skip all remaining changes, `rest` is set to the decision for them.
+fn review_change(change: &TestChange<'_>, rest: &mut Option<bool>) -> ReviewDecision {
+    match *rest {
+        Some(true) => return ReviewDecision::Accept,
+        Some(false) => return ReviewDecision::Skip,
+        None => (),
+    }
+    println!("{change}");
+    loop {
+        eprint!("Accept this change [y,n,e,s,a,q,?]? ");
+        let mut answer = String::new();
+        if io::stdin()
+            .read_line(&mut answer)
+            .map_or(true, |num_read| num_read == 0)
+        {
+            *rest = Some(false);
+            return ReviewDecision::Skip;
+        }
+        match answer.trim() {
+            "y" => return ReviewDecision::Accept,
+            "n" => return ReviewDecision::Skip,
+            "a" => {
+                *rest = Some(true);
+                return ReviewDecision::Accept;
             }
+            "q" => {
+                *rest = Some(false);
+                return ReviewDecision::Skip;
+            }
+            "e" => match edit_test(change) {
+                Ok(test) => return ReviewDecision::Replace(test),
+                Err(e) => render_error(e),
+            },
+            "s" => match change {
+                TestChange {
+                    part: TestPart::Whole,
+                    old: Some(_),
+                    new: Some(_),
+                    ..
+                } => return ReviewDecision::Split,
+                _ => eprintln!(
+                    "only changes to whole tests that exist before and after can be split"
+                ),
+            },
+            _ => eprintln!(concat!(
+                "y - accept this change\n",
+                "n - skip this change, keeping current metadata\n",
+                "e - edit the new metadata in `$EDITOR`; remove the section to remove the test\n",
+                "s - split this change into changes to the test's properties and each subtest\n",
+                "a - accept this and all remaining changes\n",
+                "q - skip this and all remaining changes\n",
+                "? - print help",
+            )),
+        }
+    }
+}
 
-            log::info!("gathering reported test outcomes for reconciliation with metadata…");
-
-            let using_reports = !exec_report_paths.is_empty();
+/// Opens the new metadata of `change` (or its current metadata, if the test is to be removed) in
+/// `$EDITOR` (or `vi`, if unset), and parses the result.
+fn edit_test(change: &TestChange<'_>) -> Result<Option<Test>, Error> {
+    let TestChange {
+        path: _,
+        name,
+        part: _,
+        old,
+        new,
+        reported: _,
+    } = change;
+    let contents = new
+        .or(*old)
+        .map(|test| metadata::format_test(name, test).to_string())
+        .unwrap_or_default();
+    let temp_file = TempFile::create("moz-webgpu-cts-edit", ".ini", &contents)?;
+    let path = &temp_file.path;
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_owned());
+    let mut args = editor.split_whitespace();
+    let program = args.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .map_err(|source| Error::RunEditor {
+            editor: editor.clone(),
+            source,
+        })?;
+    if !status.success() {
+        return Err(Error::EditorFailed { editor, status });
+    }
 
-            let (exec_reports_sender, exec_reports_receiver) = channel();
-            exec_report_paths
-                .into_par_iter()
-                .for_each_with(exec_reports_sender, |sender, path| {
-                    let res = fs::File::open(&path)
-                        .map(BufReader::new)
-                        .map_err(Report::msg)
-                        .wrap_err("failed to open file")
-                        .and_then(|reader| {
-                            serde_json::from_reader::<_, ExecutionReport>(reader)
-                                .into_diagnostic()
-                                .wrap_err("failed to parse JSON")
-                        })
-                        .wrap_err_with(|| {
-                            format!(
-                                "failed to read WPT execution report from {}",
-                                path.display()
-                            )
-                        })
-                        .map(|parsed| (path, parsed))
-                        .map_err(|e| {
-                            log::error!("{e:?}");
-                            AlreadyReportedToCommandline
-                        });
-                    let _ = sender.send(res);
-                });
+    let contents = RealFileSystem.read_to_string(path)?;
+    let (_path, mut file) = parse_metadata(path.clone(), contents)?;
+    let test = file.tests.remove(*name);
+    if !file.tests.is_empty() {
+        return Err(Error::EditedUnexpectedTests {
+            expected: (*name).clone(),
+            found: file.tests.into_keys().collect(),
+        });
+    }
+    Ok(test)
+}
 
-            for res in exec_reports_receiver {
-                let (_path, exec_report) = match res {
-                    Ok(ok) => ok,
-                    Err(AlreadyReportedToCommandline) => return ExitCode::FAILURE,
-                };
+/// A file created exclusively in the temporary directory, and removed when dropped.
+struct TempFile {
+    path: PathBuf,
+}
 
-                let ExecutionReport {
-                    run_info:
-                        RunInfo {
-                            platform,
-                            build_profile,
-                        },
-                    entries,
-                } = exec_report;
-
-                for entry in entries {
-                    let TestExecutionEntry { test_name, result } = entry;
-
-                    let test_path = TestPath::from_execution_report(&test_name).unwrap();
-                    let TestEntry {
-                        entry: test_entry,
-                        subtests: subtest_entries,
-                    } = if let Some(cts_path) = cts_path(&test_path) {
-                        let entry = entries_by_cts_path.entry(cts_path).or_default();
-                        if let Some(old) =
-                            entry.reported_path.replace(test_path.clone().into_owned())
-                        {
-                            if old != test_path {
-                                log::warn!(
-                                    concat!(
-                                        "found test execution entry containing the same ",
-                                        "CTS test path as another, ",
-                                        "discarding previous entries with ",
-                                        "this and further dupes; entries:\n",
-                                        "older: {:#?}\n",
-                                        "newer: {:#?}\n",
-                                    ),
-                                    old,
-                                    test_path
-                                )
-                            }
-                        }
-                        &mut entry.entry
-                    } else {
-                        other_entries_by_test
-                            .entry(test_path.clone().into_owned())
-                            .or_default()
-                    };
+impl TempFile {
+    /// Creates a file named `{prefix}-…{suffix}` in the temporary directory with `contents`. Names
+    /// that are already taken are skipped, rather than overwritten.
+    fn create(prefix: &str, suffix: &str, contents: &str) -> Result<Self, Error> {
+        use io::Write as _;
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let dir = std::env::temp_dir();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.subsec_nanos());
+        let mut attempt = 0u32;
+        let (path, mut file) = loop {
+            let path = dir.join(format!(
+                "{prefix}-{}-{nanos}-{attempt}{suffix}",
+                std::process::id()
+            ));
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(file) => break (path, file),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+                Err(source) => return Err(Error::WriteFile { path, source }),
+            }
+        };
+        let temp_file = Self { path };
+        file.write_all(contents.as_bytes())
+            .map_err(|source| Error::WriteFile {
+                path: temp_file.path.clone(),
+                source,
+            })?;
+        Ok(temp_file)
+    }
+}
 
-                    let (reported_outcome, reported_subtests) = match result {
-                        TestExecutionResult::Complete { outcome, subtests } => (outcome, subtests),
-                        TestExecutionResult::JobMaybeTimedOut { status, subtests } => {
-                            if !status.is_empty() {
-                                log::warn!(
-                                    concat!(
-                                        "expected an empty `status` field for {:?}, ",
-                                        "but found the {:?} status"
-                                    ),
-                                    test_path,
-                                    status,
-                                )
-                            }
-                            (TestOutcome::Timeout, subtests)
-                        }
-                    };
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if let Err(e) = RealFileSystem.remove_file(&self.path) {
+            render_error(e);
+        }
+    }
+}
 
-                    fn accumulate<Out>(
-                        recorded: &mut BTreeMap<Platform, BTreeMap<BuildProfile, Expected<Out>>>,
-                        platform: Platform,
-                        build_profile: BuildProfile,
-                        reported_outcome: Out,
-                    ) where
-                        Out: Default + EnumSetType + Hash,
-                    {
-                        match recorded.entry(platform).or_default().entry(build_profile) {
-                            std::collections::btree_map::Entry::Vacant(entry) => {
-                                entry.insert(Expected::permanent(reported_outcome));
-                            }
-                            std::collections::btree_map::Entry::Occupied(mut entry) => {
-                                *entry.get_mut() |= reported_outcome
-                            }
+/// Asks `question` on the terminal, and returns whether it was answered with yes. Returns `false`
+/// without asking if standard input is not a terminal.
+fn confirm(question: &str) -> bool {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        tracing::warn!("{question} (not asking, since standard input is not a terminal)");
+        return false;
+    }
+    eprint!("{question} [y/N] ");
+    let mut answer = String::new();
+    if stdin.read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+/// Performs the writes in `manifest`. If any fail, saves those still pending for `update-expected
+/// --resume`. Otherwise, removes any manifest saved by an earlier failure.
+fn write_recoverably(fs: &dyn FileSystem, mut manifest: WriteManifest) -> ExitCode {
+    let manifest_path = WriteManifest::path();
+    match manifest.write(fs, &CANCELLED) {
+        Ok(()) => {
+            if let Ok(path) = manifest_path {
+                if let Err(e) = fs.remove_file(&path) {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            }
+            summarize_relocations(&manifest.relocations);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            render_error(e);
+            // Resuming may happen from another working directory.
+            for write in &mut manifest.pending {
+                if let Ok(path) = std::path::absolute(&write.path) {
+                    write.path = path;
+                }
+            }
+            match manifest_path.and_then(|path| manifest.save(fs, &path).map(|()| path)) {
+                Ok(path) => tracing::info!(
+                    "saved {} pending metadata write(s) to {}; run `update-expected --resume` to \
+                    retry them",
+                    manifest.pending.len(),
+                    path.display()
+                ),
+                Err(e) => render_error(e),
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Logs `relocations`, grouped by the metadata files tests were moved between.
+fn summarize_relocations(relocations: &[RelocatedTest]) {
+    if relocations.is_empty() {
+        return;
+    }
+    let mut tests_by_move = BTreeMap::<_, Vec<_>>::new();
+    for RelocatedTest { test, from, to } in relocations {
+        tests_by_move.entry((from, to)).or_default().push(test);
+    }
+    tracing::info!(
+        "relocated {} test(s) between metadata files:{}",
+        relocations.len(),
+        lazy_format!(|f| {
+            for ((from, to), tests) in &tests_by_move {
+                write!(f, "\n  {} -> {}", from.display(), to.display())?;
+                for test in tests {
+                    write!(f, "\n    {test}")?;
+                }
+            }
+            Ok(())
+        })
+    );
+}
+
+/// Options for [`update_expected_and_write`] beyond those for processing reports.
+#[derive(Debug, Default)]
+struct WriteOptions<'a> {
+    /// The name of the `--session` to record processed configurations in.
+    session: Option<&'a str>,
+    /// The fraction of metadata files that may change before asking for confirmation, or `None`
+    /// to never ask.
+    confirm_above: Option<f64>,
+    /// Whether to review each change with `--interactive`.
+    interactive: bool,
+    /// Whether to print a diff of changes with `--dry-run`, instead of writing them.
+    dry_run: bool,
+    /// Whether to list tests missing from reports with `--report-orphans`.
+    report_orphans: bool,
+}
+
+fn update_expected_and_write(
+    fs: &impl FileSystem,
+    gecko_checkout: &Path,
+    exec_report_paths: Vec<PathBuf>,
+    options: &ReconcileOptions,
+    report_options: &ParseOptions,
+    write_options: &WriteOptions<'_>,
+) -> ExitCode {
+    let &WriteOptions {
+        session,
+        confirm_above,
+        interactive,
+        dry_run,
+        report_orphans,
+    } = write_options;
+    if interactive && !io::stdin().is_terminal() {
+        tracing::error!("`--interactive` requires standard input to be a terminal");
+        return ExitCode::FAILURE;
+    }
+
+    let session = match session
+        .map(|name| {
+            let path = Session::path(name)?;
+            let session = Session::load(fs, &path)?;
+            Ok((name, path, session))
+        })
+        .transpose()
+    {
+        Ok(session) => session,
+        Err(e) => {
+            render_error(e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut reconciliation = match process_reports::update_expected(
+        fs,
+        gecko_checkout,
+        exec_report_paths,
+        options,
+        report_options,
+        &CANCELLED,
+    ) {
+        Ok(reconciliation) => reconciliation,
+        Err(e) => {
+            render_error(e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    render_reconciliation_summary(&reconciliation.summary);
+    if report_orphans {
+        render_orphans(&reconciliation.summary);
+    }
+
+    if interactive {
+        let current = match read_and_parse_all_metadata(fs, gecko_checkout, options.browser)
+            .collect::<Result<IndexMap<_, _>, _>>()
+        {
+            Ok(current) => current,
+            Err(e) => {
+                render_error(e);
+                return ExitCode::FAILURE;
+            }
+        };
+        let mut rest = None;
+        reconciliation.review(&current, |change| review_change(change, &mut rest));
+    }
+
+    let impact = match reconciliation.impact(fs) {
+        Ok(impact) => impact,
+        Err(e) => {
+            render_error(e);
+            return ExitCode::FAILURE;
+        }
+    };
+    tracing::info!("impact of writing metadata: {impact}");
+    if dry_run {
+        return match reconciliation
+            .into_write_manifest()
+            .diff(fs, gecko_checkout)
+        {
+            Ok(diff) if diff.is_empty() => {
+                tracing::info!("no metadata files would change");
+                ExitCode::SUCCESS
+            }
+            Ok(diff) => {
+                print!("{diff}");
+                ExitCode::FAILURE
+            }
+            Err(e) => {
+                render_error(e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if let Some(confirm_above) = confirm_above {
+        let fraction = impact.fraction_changed();
+        if fraction > confirm_above
+            && !confirm(&format!(
+                "{:.0}% of existing metadata files will change, more than the {:.0}% allowed by \
+                `--confirm-above`; write anyway?",
+                fraction * 100.0,
+                confirm_above * 100.0,
+            ))
+        {
+            tracing::error!("not writing metadata; pass `--yes` to write it regardless");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let configurations = reconciliation.summary.configurations.clone();
+    if write_recoverably(fs, reconciliation.into_write_manifest()) == ExitCode::FAILURE {
+        return ExitCode::FAILURE;
+    }
+
+    if let Some((name, path, mut session)) = session {
+        session.processed.extend(configurations);
+        if let Err(e) = session.save(fs, &path) {
+            render_error(e);
+            return ExitCode::FAILURE;
+        }
+        let missing = session.missing().collect::<Vec<_>>();
+        if missing.is_empty() {
+            tracing::info!("session `{name}` has covered all configurations");
+        } else {
+            tracing::warn!(
+                "session `{name}` has not covered these configurations yet: {}",
+                missing
+                    .iter()
+                    .map(|(platform, build_profile)| lazy_format!("{platform:?} {build_profile:?}"))
+                    .join_with(", ")
+            );
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Prints the [`FileHealth`] of each of `files` with any entries needing attention, from most to
+/// fewest, alongside the most recent change to each in the version control of `gecko_checkout`.
+fn render_file_health(gecko_checkout: &Path, files: &IndexMap<Arc<PathBuf>, metadata::File>) {
+    let mut by_file = files
+        .iter()
+        .map(|(path, file)| (path, FileHealth::from_file(file)))
+        .filter(|(_path, health)| health.total() > 0)
+        .collect::<Vec<_>>();
+    by_file.sort_by(|(a_path, a), (b_path, b)| {
+        b.total()
+            .cmp(&a.total())
+            .then_with(|| collation::current().compare_paths(a_path, b_path))
+    });
+
+    let vcs = RevisionFileSystem::checked_out(gecko_checkout.to_owned());
+    let mut vcs_failed = false;
+    println!(
+        "{:>8} {:>12} {:>8}  file (last change)",
+        "failing", "intermittent", "disabled"
+    );
+    for (path, health) in by_file {
+        let last_change = if vcs_failed {
+            None
+        } else {
+            vcs.last_change(path).unwrap_or_else(|e| {
+                tracing::warn!("not showing last changes to files: {e}");
+                vcs_failed = true;
+                None
+            })
+        };
+        let FileHealth {
+            failing,
+            intermittent,
+            disabled,
+        } = health;
+        let rel_path = path.strip_prefix(gecko_checkout).unwrap_or(path);
+        print!(
+            "{failing:>8} {intermittent:>12} {disabled:>8}  {}",
+            rel_path.display()
+        );
+        match last_change {
+            Some(last_change) => println!(" ({last_change})"),
+            None => println!(),
+        }
+    }
+}
+
+fn run(mut cli: Cli, matches: &ArgMatches) -> ExitCode {
+    let fs = RealFileSystem;
+
+    // N.B.: Completions don't need a checkout, so they're generated before looking for one.
+    if let Subcommand::Completions { shell } = cli.subcommand {
+        let mut cmd = Cli::command();
+        let bin_name = cmd.get_name().to_owned();
+        clap_complete::generate(shell, &mut cmd, bin_name, &mut io::stdout());
+        return ExitCode::SUCCESS;
+    }
+
+    let gecko_checkout = match cli
+        .gecko_checkout
+        .take()
+        .map(Ok)
+        .unwrap_or_else(search_for_moz_central_ckt)
+    {
+        Ok(ckt_path) => ckt_path,
+        Err(e) => {
+            render_error(e);
+            return ExitCode::FAILURE;
+        }
+    };
+    match Config::load(&fs, &gecko_checkout) {
+        Ok(config) => apply_config(&mut cli, config, matches),
+        Err(e) => {
+            render_error(e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let Cli {
+        gecko_checkout: _,
+        browser,
+        trace_out: _,
+        profile: _,
+        progress: _,
+        cache_metadata,
+        no_lock,
+        sort,
+        subcommand,
+    } = cli;
+    collation::set(sort);
+    if cache_metadata {
+        match state_dir() {
+            Ok(dir) => checkout::enable_parse_cache(dir.join("metadata-cache")),
+            Err(e) => {
+                render_error(e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let _metadata_lock = if subcommand.writes_metadata() && !no_lock {
+        match MetadataLock::acquire(&browser.webgpu_cts_meta_dir(&gecko_checkout)) {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                render_error(e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+
+    match subcommand {
+        Subcommand::UpdateExpected {
+            report_globs,
+            report_paths,
+            taskcluster_task_groups,
+            from_push,
+            taskcluster_root_url,
+            preset,
+            subtest_preset,
+            preset_override,
+            never_remove,
+            always_merge,
+            never_modify,
+            deny_unknown_report_fields,
+            platform_overrides,
+            report_format,
+            mut run_info_keys,
+            split_by_backend,
+            reenable_expired,
+            sample,
+            only_platform,
+            only_build_profile,
+            disable_perma_crash,
+            cache_summaries,
+            stream_reports,
+            session,
+            yes,
+            confirm_above,
+            interactive,
+            resume,
+            dry_run,
+            report_orphans,
+        } => {
+            if resume {
+                let manifest =
+                    match WriteManifest::path().and_then(|path| WriteManifest::load(&fs, &path)) {
+                        Ok(Some(manifest)) => manifest,
+                        Ok(None) => {
+                            tracing::error!("no pending metadata writes to resume");
+                            return ExitCode::FAILURE;
+                        }
+                        Err(e) => {
+                            render_error(e);
+                            return ExitCode::FAILURE;
                         }
+                    };
+                tracing::info!(
+                    "resuming {} pending metadata write(s)",
+                    manifest.pending.len()
+                );
+                return write_recoverably(&fs, manifest);
+            }
+            if split_by_backend {
+                run_info_keys.push(WEBGPU_BACKEND_KEY.to_owned());
+            }
+            let strategies = reconciliation_strategy(
+                &fs,
+                &preset,
+                subtest_preset.as_ref(),
+                &never_remove,
+                &always_merge,
+            )
+            .and_then(|strategy| {
+                let overrides =
+                    strategy_overrides(&fs, preset_override, &never_remove, &always_merge)?;
+                Ok((strategy, overrides))
+            });
+            let (strategy, strategy_overrides) = match strategies {
+                Ok(strategies) => strategies,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let never_modify = match never_modify {
+                Some(path) => match fs
+                    .read_to_string(&path)
+                    .and_then(|contents| NeverModifyList::parse(&path, &contents))
+                {
+                    Ok(never_modify) => never_modify,
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
                     }
-                    accumulate(
-                        &mut test_entry.reported,
-                        platform,
-                        build_profile,
-                        reported_outcome,
-                    );
+                },
+                None => NeverModifyList::default(),
+            };
+            let summary_cache = if cache_summaries {
+                match state_dir() {
+                    Ok(dir) => Some(dir.join("report-summaries")),
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
+                    }
+                }
+            } else {
+                None
+            };
 
-                    for reported_subtest in reported_subtests {
-                        let SubtestExecutionResult {
-                            subtest_name,
-                            outcome,
-                        } = reported_subtest;
-
-                        accumulate(
-                            &mut subtest_entries
-                                .entry(subtest_name.clone())
-                                .or_default()
-                                .reported,
-                            platform,
-                            build_profile,
-                            outcome,
-                        );
+            let Some(mut exec_report_paths) = collect_report_paths(&fs, report_paths, report_globs)
+            else {
+                return ExitCode::FAILURE;
+            };
+            if !taskcluster_task_groups.is_empty() || !from_push.is_empty() {
+                match fetch_taskcluster_reports(
+                    &fs,
+                    &taskcluster_root_url,
+                    taskcluster_task_groups,
+                    &from_push,
+                ) {
+                    Ok(paths) => exec_report_paths.extend(paths),
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
                     }
                 }
             }
 
-            log::info!("metadata and reports gathered, now reconciling outcomes…");
-
-            let mut found_reconciliation_err = false;
-            let entries_by_cts_path = entries_by_cts_path.into_iter().map(|(_name, entry)| {
-                let EntryByCtsPath {
-                    metadata_path,
-                    reported_path,
-                    entry,
-                } = entry;
-                let output_path = if let Some((meta, rep)) = metadata_path
-                    .as_ref()
-                    .zip(reported_path.as_ref())
-                    .filter(|(meta, rep)| meta != rep)
-                {
-                    log::info!(
-                        concat!(
-                            "metadata path for test is different from ",
-                            "reported execution; relocating…\n",
-                            "…metadata: {:#?}\n",
-                            "…reported: {:#?}\n"
-                        ),
-                        meta,
-                        rep
-                    );
-                    reported_path
-                } else {
-                    metadata_path.or(reported_path)
-                };
+            update_expected_and_write(
+                &fs,
+                &gecko_checkout,
+                exec_report_paths,
+                &ReconcileOptions {
+                    strategy,
+                    strategy_overrides,
+                    never_modify,
+                    reenable_expired_as_of: reenable_expired.then(|| Local::now().date_naive()),
+                    sample,
+                    only_platforms: only_platform,
+                    only_build_profiles: only_build_profile,
+                    browser,
+                    run_info_keys: run_info_keys.into_iter().collect(),
+                    disable_perma_crashes: disable_perma_crash,
+                    ..Default::default()
+                },
+                &ParseOptions {
+                    deny_unrecognized: deny_unknown_report_fields,
+                    platform_overrides,
+                    summary_cache,
+                    stream: stream_reports,
+                    format: report_format,
+                },
+                &WriteOptions {
+                    session: session.as_deref(),
+                    confirm_above: (!yes).then_some(confirm_above),
+                    interactive,
+                    dry_run,
+                    report_orphans,
+                },
+            )
+        }
+        Subcommand::Enable {
+            tests,
+            report_paths,
+            report_globs,
+            preset,
+            subtest_preset,
+            never_remove,
+            always_merge,
+            bug,
+        } => {
+            let strategy = match reconciliation_strategy(
+                &fs,
+                &preset,
+                subtest_preset.as_ref(),
+                &never_remove,
+                &always_merge,
+            ) {
+                Ok(strategy) => strategy,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let Some(exec_report_paths) = collect_report_paths(&fs, report_paths, report_globs)
+            else {
+                return ExitCode::FAILURE;
+            };
 
-                (
-                    output_path.expect(concat!(
-                        "internal error: CTS path entry created without at least one ",
-                        "report or metadata path specified"
-                    )),
-                    entry,
-                )
-            });
-            let recombined_tests_iter = entries_by_cts_path
-                .chain(other_entries_by_test)
-                .filter_map(|(test_path, test_entry)| {
-                    fn reconcile<Out>(
-                        entry: Entry<Out>,
-                        preset: ReportProcessingPreset,
-                    ) -> TestProps<Out>
-                    where
-                        Out: Debug + Default + EnumSetType,
-                    {
-                        let Entry {
-                            meta_props,
-                            reported,
-                        } = entry;
-
-                        let mut meta_props = meta_props.unwrap_or_default();
-                        let reconciled = 'resolve: {
-                            let reported = |platform, build_profile| {
-                                reported
-                                    .get(&platform)
-                                    .and_then(|rep| rep.get(&build_profile))
-                                    .copied()
-                            };
-                            let all_reported = || {
-                                FullyExpandedExpectedPropertyValue::from_query(
-                                    |platform, build_profile| {
-                                        reported(platform, build_profile).unwrap_or_default()
-                                    },
-                                )
-                            };
-                            let resolve = match preset {
-                                ReportProcessingPreset::ResetAll => {
-                                    break 'resolve all_reported();
-                                }
-                                ReportProcessingPreset::ResetContradictory => {
-                                    |meta: Expected<_>, rep: Option<Expected<_>>| {
-                                        rep.filter(|rep| !meta.is_superset(rep)).unwrap_or(meta)
-                                    }
-                                }
-                                ReportProcessingPreset::Merge => |meta, rep| match rep {
-                                    Some(rep) => meta | rep,
-                                    None => meta,
-                                },
-                            };
-
-                            if let Some(meta_expected) = meta_props.expected {
-                                FullyExpandedExpectedPropertyValue::from_query(
-                                    |platform, build_profile| {
-                                        resolve(
-                                            meta_expected.get(platform, build_profile),
-                                            reported(platform, build_profile),
-                                        )
-                                    },
-                                )
-                            } else {
-                                all_reported()
-                            }
-                        };
-                        meta_props.expected = Some(reconciled);
-                        meta_props
+            update_expected_and_write(
+                &fs,
+                &gecko_checkout,
+                exec_report_paths,
+                &ReconcileOptions {
+                    strategy,
+                    reenable: tests.clone(),
+                    reenable_bug: bug.map(|BugArg(id)| id),
+                    only_tests: Some(tests),
+                    browser,
+                    ..Default::default()
+                },
+                &Default::default(),
+                &Default::default(),
+            )
+        }
+        Subcommand::Quarantine {
+            tests,
+            date,
+            release,
+        } => {
+            let mut files = IndexMap::new();
+            let mut err_found = false;
+            for res in read_and_parse_all_metadata(&fs, &gecko_checkout, browser) {
+                match res {
+                    Ok((path, file)) => {
+                        files.insert(path, file);
                     }
+                    Err(e) => {
+                        render_error(e);
+                        err_found = true;
+                    }
+                }
+            }
+            if err_found {
+                return ExitCode::FAILURE;
+            }
 
-                    let TestEntry {
-                        entry: test_entry,
-                        subtests: subtest_entries,
-                    } = test_entry;
+            let action = if release {
+                QuarantineAction::Release
+            } else {
+                QuarantineAction::Quarantine(date.unwrap_or_else(|| Local::now().date_naive()))
+            };
+            let Quarantined {
+                changed_files,
+                num_tests,
+                unmatched,
+            } = quarantine::quarantine(&gecko_checkout, &mut files, browser, &tests, action);
+            for pattern in unmatched {
+                tracing::warn!("{pattern} matched no tests in metadata");
+            }
 
-                    if test_entry.meta_props.is_none() {
-                        log::info!("new test entry: {test_path:?}")
+            for path in changed_files {
+                if let Err(e) = fs.write(&path, &metadata::format_file(&files[&path])) {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            }
+            match action {
+                QuarantineAction::Quarantine(date) => {
+                    tracing::info!("quarantined {num_tests} test(s) on {date}")
+                }
+                QuarantineAction::Release => tracing::info!("released {num_tests} test(s)"),
+            }
+            ExitCode::SUCCESS
+        }
+        Subcommand::FileBugs {
+            filters,
+            product,
+            component,
+            bugzilla_url,
+            dry_run,
+            yes,
+            confirm_above,
+        } => {
+            let bugzilla = if dry_run {
+                None
+            } else {
+                match std::env::var("BUGZILLA_API_KEY") {
+                    Ok(api_key) if !api_key.is_empty() => {
+                        Some(Bugzilla::new(&bugzilla_url, Some(api_key)))
+                    }
+                    _ => {
+                        render_error(Error::BugzillaApiKeyNotSet);
+                        return ExitCode::FAILURE;
                     }
+                }
+            };
+            let mut files = match read_and_parse_all_metadata(&fs, &gecko_checkout, browser)
+                .collect::<Result<IndexMap<_, _>, _>>()
+            {
+                Ok(files) => files,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
 
-                    if test_entry.reported.is_empty() && using_reports {
-                        let test_path = &test_path;
-                        let msg = lazy_format!("no entries found in reports for {:?}", test_path);
-                        match preset {
-                            ReportProcessingPreset::Merge => log::warn!("{msg}"),
-                            ReportProcessingPreset::ResetAll
-                            | ReportProcessingPreset::ResetContradictory => {
-                                log::warn!("removing metadata after {msg}");
-                                return None;
-                            }
-                        }
+            let candidates = bugzilla::bug_candidates(&gecko_checkout, &files, &filters);
+            if candidates.is_empty() {
+                tracing::info!("no tests need bugs filed");
+                return ExitCode::SUCCESS;
+            }
+            if !dry_run
+                && !yes
+                && candidates.len() > confirm_above
+                && !confirm(&format!(
+                    "{} bugs will be filed, more than the {confirm_above} allowed by \
+                    `--confirm-above`; file them anyway?",
+                    candidates.len(),
+                ))
+            {
+                tracing::error!(
+                    "not filing bugs; narrow them down with `--filter`, or pass `--yes` to file \
+                    them regardless"
+                );
+                return ExitCode::FAILURE;
+            }
+            let mut changed_files = BTreeSet::new();
+            let mut num_filed = 0;
+            for candidate in &candidates {
+                let bug = NewBug {
+                    product: product.clone(),
+                    component: component.clone(),
+                    summary: candidate.summary(),
+                    description: candidate.description().to_string(),
+                    version: "unspecified".to_owned(),
+                    bug_type: "defect".to_owned(),
+                };
+                let Some(bugzilla) = &bugzilla else {
+                    println!("{bug}");
+                    continue;
+                };
+                let id = match bugzilla.file_bug(&bug) {
+                    Ok(id) => id,
+                    Err(e) => {
+                        render_error(e);
+                        break;
                     }
+                };
+                let url = bugzilla.bug_url(id);
+                tracing::info!("filed {url} for /{}", candidate.test.runner_url_path());
+                num_filed += 1;
+
+                let BugCandidate { test, failures: _ } = candidate;
+                let path = Arc::new(gecko_checkout.join(test.rel_metadata_path().to_string()));
+                // N.B.: Candidates are only ever found in `files`.
+                let test = files
+                    .get_mut(&path)
+                    .and_then(|file| {
+                        file.tests
+                            .get_mut(&SectionHeader(test.test_name().to_string()))
+                    })
+                    .expect("internal error: bug candidate not found in metadata it came from");
+                test.properties.bug = Some(url);
+                changed_files.insert(path);
+            }
 
-                    let properties = reconcile(test_entry, preset);
+            // N.B.: Bugs already filed are written back even if filing a later one failed, so
+            // that they aren't filed twice.
+            for path in changed_files {
+                if let Err(e) = fs.write(&path, &metadata::format_file(&files[&path])) {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            }
+            if dry_run {
+                tracing::info!("would file {} bug(s)", candidates.len());
+                ExitCode::SUCCESS
+            } else if num_filed < candidates.len() {
+                tracing::error!(
+                    "filed {num_filed} of {} bug(s); rerun to file the rest",
+                    candidates.len()
+                );
+                ExitCode::FAILURE
+            } else {
+                tracing::info!("filed {num_filed} bug(s)");
+                ExitCode::SUCCESS
+            }
+        }
+        Subcommand::BisectReports {
+            good_paths,
+            good_globs,
+            bad_paths,
+            bad_globs,
+            platform_overrides,
+            format,
+        } => {
+            let Some(good_paths) = collect_report_paths(&fs, good_paths, good_globs) else {
+                return ExitCode::FAILURE;
+            };
+            let Some(bad_paths) = collect_report_paths(&fs, bad_paths, bad_globs) else {
+                return ExitCode::FAILURE;
+            };
 
-                    let mut subtests = BTreeMap::new();
-                    for (subtest_name, subtest) in subtest_entries {
-                        let subtest_name = SectionHeader(subtest_name);
-                        if subtests.contains_key(&subtest_name) {
-                            found_reconciliation_err = true;
-                            log::error!("internal error: duplicate test path {test_path:?}");
-                        }
+            let read = |paths| {
+                process_reports::read_and_parse_reports(
+                    &fs,
+                    paths,
+                    platform_overrides.clone(),
+                    &CANCELLED,
+                )
+            };
+            let (good, bad) = match read(good_paths).and_then(|good| Ok((good, read(bad_paths)?))) {
+                Ok(runs) => runs,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
 
-                        let mut properties = reconcile(subtest, preset);
+            match bisect(browser, good, bad) {
+                Ok(bisection) => {
+                    print!("{}", bisection.render(format));
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    render_error(e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Subcommand::Transitions {
+            before_paths,
+            before_globs,
+            after_paths,
+            after_globs,
+            platform_overrides,
+        } => {
+            let Some(before_paths) = collect_report_paths(&fs, before_paths, before_globs) else {
+                return ExitCode::FAILURE;
+            };
+            let Some(after_paths) = collect_report_paths(&fs, after_paths, after_globs) else {
+                return ExitCode::FAILURE;
+            };
 
-                        for (_, expected) in properties.expected.as_mut().unwrap().iter_mut() {
-                            taint_subtest_timeouts_by_suspicion(expected);
-                        }
+            let read = |paths| {
+                process_reports::read_and_parse_reports(
+                    &fs,
+                    paths,
+                    platform_overrides.clone(),
+                    &CANCELLED,
+                )
+            };
+            let (before, after) =
+                match read(before_paths).and_then(|before| Ok((before, read(after_paths)?))) {
+                    Ok(runs) => runs,
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
+                    }
+                };
+
+            match transitions(browser, before, after) {
+                Ok(transitions) => {
+                    print!("{transitions}");
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    render_error(e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Subcommand::Explain {
+            test,
+            report_paths,
+            report_globs,
+            preset,
+            platform_overrides,
+        } => {
+            let test = match TestPath::from_execution_report(browser, &test) {
+                Ok(test) => test,
+                Err(e) => {
+                    tracing::error!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            let Some(exec_report_paths) = collect_report_paths(&fs, report_paths, report_globs)
+            else {
+                return ExitCode::FAILURE;
+            };
+
+            let meta_files_by_path =
+                match read_and_parse_all_metadata(&fs, &gecko_checkout, browser)
+                    .collect::<Result<IndexMap<_, _>, _>>()
+                {
+                    Ok(files) => files,
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
+                    }
+                };
+            let runs = match process_reports::read_and_parse_reports(
+                &fs,
+                exec_report_paths,
+                platform_overrides,
+                &CANCELLED,
+            ) {
+                Ok(runs) => runs,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            match explain(&gecko_checkout, meta_files_by_path, runs, &test, preset) {
+                Ok(explanation) => {
+                    print!("{explanation}");
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    render_error(e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Subcommand::SnapshotRepro {
+            test,
+            report_paths,
+            report_globs,
+            platform_overrides,
+            run_info_keys,
+            output,
+        } => {
+            let test = match TestPath::from_execution_report(browser, &test) {
+                Ok(test) => test,
+                Err(e) => {
+                    tracing::error!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            let Some(exec_report_paths) = collect_report_paths(&fs, report_paths, report_globs)
+            else {
+                return ExitCode::FAILURE;
+            };
 
-                        subtests.insert(subtest_name, Subtest { properties });
+            let meta_files_by_path =
+                match read_and_parse_all_metadata(&fs, &gecko_checkout, browser)
+                    .collect::<Result<IndexMap<_, _>, _>>()
+                {
+                    Ok(files) => files,
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
                     }
-
-                    if subtests.is_empty() && properties == Default::default() {
-                        None
-                    } else {
-                        Some((test_path, (properties, subtests)))
+                };
+            let dir_files_by_path =
+                match read_and_parse_all_dir_metadata(&fs, &gecko_checkout, browser)
+                    .collect::<Result<IndexMap<_, _>, _>>()
+                {
+                    Ok(files) => files,
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
                     }
-                });
-
-            log::info!(
-                "outcome reconciliation complete, gathering tests back into new metadata files…"
-            );
+                };
+            let runs = match process_reports::read_and_parse_reports(
+                &fs,
+                exec_report_paths,
+                platform_overrides,
+                &CANCELLED,
+            ) {
+                Ok(runs) => runs,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
 
-            let mut files = BTreeMap::<PathBuf, File>::new();
-            for (test_path, (properties, subtests)) in recombined_tests_iter {
-                let name = test_path.test_name().to_string();
-                let rel_path = Utf8PathBuf::from(test_path.rel_metadata_path_fx().to_string());
-                let path = gecko_checkout.join(&rel_path);
-                let file = files.entry(path).or_insert_with(|| File {
-                    properties: file_props_by_file
-                        .get(&rel_path)
-                        .cloned()
-                        .unwrap_or_else(|| {
-                            log::warn!("creating new metadata file for `{rel_path}`");
-                            Default::default()
-                        }),
-                    tests: Default::default(),
-                });
-                file.tests.insert(
-                    SectionHeader(name),
-                    Test {
-                        properties,
-                        subtests,
-                    },
+            let snapshot = match snapshot_repro(
+                &gecko_checkout,
+                &meta_files_by_path,
+                &dir_files_by_path,
+                runs,
+                &test,
+                &run_info_keys.into_iter().collect(),
+            ) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            if snapshot.num_metadata_files == 0 && snapshot.num_reports == 0 {
+                tracing::error!(
+                    "`{}` was found in neither metadata nor reports, not writing a snapshot",
+                    test.runner_url_path()
                 );
+                return ExitCode::FAILURE;
             }
-
-            for old_meta_file_path in old_meta_file_paths {
-                files
-                    .entry(Arc::into_inner(old_meta_file_path).unwrap())
-                    .or_default();
+            if let Err(source) = fs::write(&output, snapshot.to_tar_gz()) {
+                render_error(Error::WriteFile {
+                    path: output,
+                    source,
+                });
+                return ExitCode::FAILURE;
             }
-
-            files.retain(|path, file| {
-                let is_empty = file.tests.is_empty();
-                if is_empty {
-                    log::info!("removing now-empty metadata file {}", path.display());
-                    match fs::remove_file(path) {
-                        Ok(()) => (),
-                        Err(e) => match e.kind() {
-                            io::ErrorKind::NotFound => (),
-                            _ => log::error!(
-                                "failed to remove now-empty metadata file {}",
-                                path.display()
-                            ),
-                        },
+            println!(
+                "Wrote {} metadata file(s) and {} report(s) to {}.",
+                snapshot.num_metadata_files,
+                snapshot.num_reports,
+                output.display()
+            );
+            ExitCode::SUCCESS
+        }
+        Subcommand::Fixup {
+            check,
+            annotate,
+            dedupe,
+        } => {
+            let emit_annotations = |annotations: Vec<Annotation>| {
+                if let Some(format) = annotate {
+                    for annotation in annotations {
+                        println!("{}", annotation.render(format, &gecko_checkout));
                     }
                 }
-                !is_empty
-            });
-
-            log::info!("gathering of new metadata files completed, writing to file system…");
+            };
 
-            for (path, file) in files {
-                log::debug!("writing new metadata to {}", path.display());
-                match write_to_file(&path, metadata::format_file(&file)) {
-                    Ok(()) => (),
-                    Err(AlreadyReportedToCommandline) => {
-                        found_reconciliation_err = true;
+            if check {
+                tracing::info!("checking that metadata is fixed up…");
+            } else {
+                tracing::info!("fixing up metadata in-place…");
+            }
+            let mut files = IndexMap::new();
+            let mut parse_errs = Vec::new();
+            for res in read_and_parse_all_metadata(&fs, &gecko_checkout, browser)
+                .take_while(|_| !CANCELLED.load(atomic::Ordering::Relaxed))
+            {
+                match res {
+                    Ok((path, file)) => {
+                        files.insert(path, file);
+                    }
+                    Err(e) => parse_errs.push(e),
+                }
+            }
+            // N.B.: Directory metadata has no tests, so it's only normalized.
+            let mut dir_files = IndexMap::new();
+            for res in read_and_parse_all_dir_metadata(&fs, &gecko_checkout, browser)
+                .take_while(|_| !CANCELLED.load(atomic::Ordering::Relaxed))
+            {
+                match res {
+                    Ok((path, file)) => {
+                        dir_files.insert(path, file);
                     }
+                    Err(e) => parse_errs.push(e),
                 }
             }
 
-            if found_reconciliation_err {
-                log::error!(concat!(
-                    "one or more errors found while reconciling, ",
-                    "exiting with failure; see above for more details"
-                ));
-                return ExitCode::FAILURE;
+            let dupes = dedupe::find_duplicate_tests(&gecko_checkout, &files);
+            let merge_dupes = dedupe && !check;
+            for dupe in &dupes {
+                let DuplicateTest {
+                    cts_path,
+                    locations,
+                } = dupe;
+                let (canonical, _name) = dupe.canonical();
+                tracing::warn!(
+                    "{cts_path} has sections in {} metadata files: {}; {}",
+                    locations.len(),
+                    locations
+                        .iter()
+                        .map(|(path, _name)| path.display())
+                        .join_with(", "),
+                    lazy_format!(|f| if merge_dupes {
+                        write!(f, "merging them into {}", canonical.display())
+                    } else {
+                        write!(
+                            f,
+                            "run `moz-webgpu-cts fixup --dedupe` to merge them into {}",
+                            canonical.display()
+                        )
+                    }),
+                );
+                if merge_dupes {
+                    continue;
+                }
+                let annotations = locations[..locations.len() - 1]
+                    .iter()
+                    .map(|(path, name)| {
+                        let header = format!("[{}]", name.escaped());
+                        let line = fs
+                            .read_to_string(path)
+                            .ok()
+                            .and_then(|contents| {
+                                contents.lines().position(|line| line.trim_end() == header)
+                            })
+                            .map_or(1, |idx| idx + 1);
+                        Annotation {
+                            path: path.to_path_buf(),
+                            line,
+                            column: None,
+                            severity: if dedupe {
+                                AnnotationSeverity::Error
+                            } else {
+                                AnnotationSeverity::Warning
+                            },
+                            message: format!(
+                                "test also has a section in {}, which takes precedence; run \
+                                `moz-webgpu-cts fixup --dedupe` to merge them",
+                                canonical.display()
+                            ),
+                        }
+                    })
+                    .collect();
+                emit_annotations(annotations);
             }
+            let emptied = if merge_dupes {
+                dedupe::dedupe(&mut files, &dupes)
+            } else {
+                Vec::new()
+            };
 
-            ExitCode::SUCCESS
-        }
-        Subcommand::Fixup => {
-            log::info!("fixing up metadata in-place…");
-            let err_found = read_and_parse_all_metadata(&gecko_checkout)
-                .map(|res| {
-                    res.and_then(|(path, mut file)| {
-                        for test in file.tests.values_mut() {
-                            for subtest in &mut test.subtests.values_mut() {
-                                if let Some(expected) = subtest.properties.expected.as_mut() {
-                                    for (_, expected) in expected.iter_mut() {
-                                        taint_subtest_timeouts_by_suspicion(expected);
-                                    }
+            let check_or_write =
+                |path: &Path, fixed_up: &dyn Display| -> Result<Option<Annotation>, Error> {
+                    if check {
+                        let original = fs.read_to_string(path)?;
+                        Ok(
+                            first_differing_line(&original, &fixed_up.to_string()).map(|line| {
+                                Annotation {
+                                    path: path.to_path_buf(),
+                                    line,
+                                    column: None,
+                                    severity: AnnotationSeverity::Error,
+                                    message: "metadata is not fixed up; run `moz-webgpu-cts fixup`"
+                                        .to_owned(),
+                                }
+                            }),
+                        )
+                    } else {
+                        fs.write(path, fixed_up).map(|()| None)
+                    }
+                };
+            let (err_found, num_unfixed) = parse_errs
+                .into_iter()
+                .map(Err)
+                .chain(files.into_iter().map(|(path, mut file)| {
+                    if emptied.contains(&*path) {
+                        tracing::info!("removing {}, since it has no tests left", path.display());
+                        return fs.remove_file(&path).map(|()| None);
+                    }
+
+                    for test in file.tests.values_mut() {
+                        for subtest in &mut test.subtests.values_mut() {
+                            if let Some(expected) = subtest.properties.expected.as_mut() {
+                                for (_, expected) in expected.iter_mut() {
+                                    taint_subtest_timeouts_by_suspicion(expected);
                                 }
                             }
                         }
+                    }
 
-                        write_to_file(&path, metadata::format_file(&file))
-                    })
-                })
-                .fold(false, |err_found, res| match res {
-                    Ok(()) => err_found,
-                    Err(AlreadyReportedToCommandline) => true,
+                    check_or_write(&path, &metadata::format_file(&file))
+                }))
+                .chain(
+                    dir_files
+                        .iter()
+                        .map(|(path, file)| check_or_write(path, &metadata::format_dir_file(file))),
+                )
+                .fold((false, 0), |(err_found, num_unfixed), res| match res {
+                    Ok(None) => (err_found, num_unfixed),
+                    Ok(Some(unfixed)) => {
+                        tracing::error!(
+                            "{} needs fixing up, starting at line {}",
+                            unfixed.path.display(),
+                            unfixed.line
+                        );
+                        emit_annotations(vec![unfixed]);
+                        (err_found, num_unfixed + 1)
+                    }
+                    Err(e) => {
+                        emit_annotations(Annotation::from_error(&e));
+                        render_error(e);
+                        (true, num_unfixed)
+                    }
                 });
-            if err_found {
-                log::error!(concat!(
+            if CANCELLED.load(atomic::Ordering::Relaxed) {
+                render_error(Error::Cancelled);
+                ExitCode::FAILURE
+            } else if err_found {
+                tracing::error!(concat!(
                     "found one or more failures while fixing up metadata, ",
                     "see above for more details"
                 ));
                 ExitCode::FAILURE
+            } else if num_unfixed > 0 {
+                tracing::error!("{num_unfixed} metadata file(s) need fixing up");
+                ExitCode::FAILURE
+            } else if dedupe && check && !dupes.is_empty() {
+                tracing::error!("{} test(s) have duplicate sections", dupes.len());
+                ExitCode::FAILURE
             } else {
                 ExitCode::SUCCESS
             }
         }
-        Subcommand::Triage { on_zero_item } => {
-            #[derive(Debug)]
-            struct TaggedTest {
-                #[allow(unused)]
-                orig_path: Arc<PathBuf>,
-                inner: Test,
-            }
-            let mut err_found = false;
-            let tests_by_name = read_and_parse_all_metadata(&gecko_checkout)
-                .map_ok(
-                    |(
-                        path,
-                        metadata::File {
-                            properties: _,
-                            tests,
-                        },
-                    )| {
-                        tests.into_iter().map({
-                            let gecko_checkout = &gecko_checkout;
-                            move |(name, inner)| {
-                                let SectionHeader(name) = &name;
-                                let test_path = TestPath::from_fx_metadata_test(
-                                    path.strip_prefix(gecko_checkout).unwrap(),
-                                    name,
-                                )
-                                .unwrap();
-                                let url_path = test_path.runner_url_path().to_string();
-                                (
-                                    url_path,
-                                    TaggedTest {
-                                        inner,
-                                        orig_path: path.clone(),
-                                    },
-                                )
-                            }
-                        })
-                    },
-                )
-                .flatten_ok()
-                .filter_map(|res| match res {
-                    Ok(ok) => Some(ok),
-                    Err(AlreadyReportedToCommandline) => {
-                        err_found = true;
-                        None
+        Subcommand::Clean { dry_run } => {
+            let mut files = match read_and_parse_all_metadata(&fs, &gecko_checkout, browser)
+                .collect::<Result<IndexMap<_, _>, _>>()
+            {
+                Ok(files) => files,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            let Cleaned {
+                changed_files,
+                emptied,
+                num_expectations,
+                num_subtests,
+                num_tests,
+            } = clean::clean(&mut files);
+            let summary = format!(
+                "{num_expectations} expectation(s), {num_subtests} subtest(s), and {num_tests} \
+                test(s) from {} file(s), {} of which are left empty",
+                changed_files.len(),
+                emptied.len()
+            );
+            if dry_run {
+                for path in &changed_files {
+                    if emptied.contains(&**path) {
+                        println!("{} (would be removed)", path.display());
+                    } else {
+                        println!("{}", path.display());
                     }
-                })
-                .collect::<BTreeMap<_, _>>();
-            if err_found {
-                return ExitCode::FAILURE;
+                }
+                tracing::info!("would remove {summary}");
+                return ExitCode::SUCCESS;
             }
 
-            log::info!(concat!(
-                "finished parsing of interesting properties ",
-                "from metadata files, analyzing results…"
-            ));
-
-            #[derive(Clone, Default)]
-            struct PermaAndIntermittent<T> {
-                perma: T,
-                intermittent: T,
+            for path in &changed_files {
+                let res = if emptied.contains(&**path) {
+                    tracing::info!("removing {}, since it has no tests left", path.display());
+                    fs.remove_file(path)
+                } else {
+                    fs.write(path, &metadata::format_file(&files[path]))
+                };
+                if let Err(e) = res {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
             }
-
-            impl<T> Debug for PermaAndIntermittent<T>
-            where
-                T: Debug,
+            tracing::info!("removed {summary}");
+            ExitCode::SUCCESS
+        }
+        Subcommand::Migrate { renames, dry_run } => {
+            let renames = match fs
+                .read_to_string(&renames)
+                .and_then(|contents| RenameMap::parse(&renames, &contents))
             {
-                fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-                    let Self {
-                        perma,
-                        intermittent,
-                    } = self;
-                    f.debug_struct("") // the name is distracting, blank it out plz
-                        .field("perma", perma)
-                        .field("intermittent", intermittent)
-                        .finish()
-                }
-            }
-
-            impl<T> PermaAndIntermittent<T> {
-                pub fn as_ref(&self) -> PermaAndIntermittent<&T> {
-                    let Self {
-                        perma,
-                        intermittent,
-                    } = self;
-                    PermaAndIntermittent {
-                        perma,
-                        intermittent,
-                    }
+                Ok(renames) => renames,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let mut files = match read_and_parse_all_metadata(&fs, &gecko_checkout, browser)
+                .collect::<Result<IndexMap<_, _>, _>>()
+            {
+                Ok(files) => files,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
                 }
+            };
 
-                pub fn map<U>(self, f: impl Fn(T) -> U) -> PermaAndIntermittent<U> {
-                    let Self {
-                        perma,
-                        intermittent,
-                    } = self;
-                    PermaAndIntermittent {
-                        perma: f(perma),
-                        intermittent: f(intermittent),
-                    }
+            let Migrated {
+                changed_files,
+                emptied,
+                moved,
+                conflicts,
+                invalid,
+                unused,
+            } = migrate::migrate(&gecko_checkout, &mut files, browser, &renames);
+            for rename in unused {
+                tracing::warn!("`{rename}` matched no tests in metadata");
+            }
+            for (test, new_url_path) in invalid {
+                tracing::warn!(
+                    "not moving /{}, since {new_url_path} is not a test URL path with metadata",
+                    test.runner_url_path()
+                );
+            }
+            for Migration { from, to } in &conflicts {
+                tracing::warn!(
+                    "not moving /{} to /{}, since it would replace another test's section",
+                    from.runner_url_path(),
+                    to.runner_url_path()
+                );
+            }
+            for Migration { from, to } in &moved {
+                let from_file = from.rel_metadata_path().to_string();
+                let to_file = to.rel_metadata_path().to_string();
+                if from_file == to_file {
+                    tracing::info!(
+                        "moving /{} to /{}",
+                        from.runner_url_path(),
+                        to.runner_url_path()
+                    );
+                } else {
+                    tracing::info!(
+                        "moving /{} to /{} (from {from_file} to {to_file})",
+                        from.runner_url_path(),
+                        to.runner_url_path()
+                    );
                 }
             }
-
-            type TestSet = PermaAndIntermittent<BTreeSet<Arc<String>>>;
-            type SubtestByTestSet =
-                PermaAndIntermittent<BTreeMap<Arc<String>, IndexSet<Arc<String>>>>;
-
-            #[derive(Clone, Debug, Default)]
-            struct PerPlatformAnalysis {
-                tests_with_runner_errors: TestSet,
-                tests_with_disabled_or_skip: TestSet,
-                tests_with_crashes: TestSet,
-                subtests_with_failures_by_test: SubtestByTestSet,
-                subtests_with_timeouts_by_test: SubtestByTestSet,
+            if dry_run {
+                tracing::info!("would move {} test(s)", moved.len());
+                return ExitCode::SUCCESS;
             }
 
-            #[derive(Clone, Debug, Default)]
-            struct Analysis {
-                windows: PerPlatformAnalysis,
-                linux: PerPlatformAnalysis,
-                mac_os: PerPlatformAnalysis,
+            for path in changed_files {
+                let res = if emptied.contains(&*path) {
+                    tracing::info!("removing {}, since it has no tests left", path.display());
+                    fs.remove_file(&path)
+                } else {
+                    fs.write(&path, &metadata::format_file(&files[&path]))
+                };
+                if let Err(e) = res {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
             }
-
-            impl Analysis {
-                pub fn for_each_platform_mut<F>(&mut self, mut f: F)
-                where
-                    F: FnMut(&mut PerPlatformAnalysis),
+            tracing::info!("moved {} test(s)", moved.len());
+            if conflicts.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Subcommand::MergeFiles {
+            files: paths,
+            dry_run,
+        } => {
+            let mut files = Vec::new();
+            for path in &paths {
+                match fs
+                    .read_to_string(path)
+                    .and_then(|contents| parse_metadata(path.clone(), contents))
                 {
-                    let Self {
-                        windows,
-                        linux,
-                        mac_os,
-                    } = self;
-                    for analysis in [windows, linux, mac_os] {
-                        f(analysis)
+                    Ok((_path, file)) => files.push(file),
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
                     }
                 }
+            }
+            let (into, merged_paths) = paths.split_last().unwrap();
+
+            let MergedFiles {
+                file,
+                duplicates,
+                conflicts,
+            } = dedupe::merge_files(files);
+            for name in &duplicates {
+                if conflicts.contains(name) {
+                    tracing::warn!(
+                        "[{}] differs between files; keeping what the last of them sets",
+                        name.escaped()
+                    );
+                } else {
+                    tracing::info!("merging duplicate sections of [{}]", name.escaped());
+                }
+            }
+            let test_file_name = into
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".ini"));
+            for name in file.tests.keys() {
+                let (base_name, _variant) = name.0.split_once('?').unwrap_or((name.0.as_str(), ""));
+                if test_file_name != Some(base_name) {
+                    tracing::warn!(
+                        "[{}] doesn't name a test in the test file of {}, so the harness never \
+                        applies it",
+                        name.escaped(),
+                        into.display()
+                    );
+                }
+            }
+            if dry_run {
+                tracing::info!(
+                    "would merge {} file(s) into {}, with {} duplicate section(s)",
+                    merged_paths.len(),
+                    into.display(),
+                    duplicates.len()
+                );
+                return ExitCode::SUCCESS;
+            }
 
-                pub fn for_each_platform<F>(&self, mut f: F)
-                where
-                    F: FnMut(Platform, &PerPlatformAnalysis),
+            if let Err(e) = fs.write(into, &metadata::format_file(&file)) {
+                render_error(e);
+                return ExitCode::FAILURE;
+            }
+            for path in merged_paths.iter().filter(|path| *path != into) {
+                tracing::info!(
+                    "removing {}, since its tests were merged into {}",
+                    path.display(),
+                    into.display()
+                );
+                if let Err(e) = fs.remove_file(path) {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            }
+            tracing::info!(
+                "merged {} file(s) into {}, with {} duplicate section(s)",
+                merged_paths.len(),
+                into.display(),
+                duplicates.len()
+            );
+            ExitCode::SUCCESS
+        }
+        Subcommand::Diff { old, new } => {
+            let read_metadata = |source: &MetadataSource| {
+                let (checkout, fs): (_, Box<dyn FileSystem>) = match source {
+                    MetadataSource::Checkout(path) => (path.clone(), Box::new(RealFileSystem)),
+                    MetadataSource::Revision(rev) => (
+                        gecko_checkout.clone(),
+                        Box::new(RevisionFileSystem::new(gecko_checkout.clone(), rev.clone())),
+                    ),
+                };
+                read_and_parse_all_metadata(&*fs, &checkout, browser)
+                    .collect::<Result<IndexMap<_, _>, _>>()
+                    .map(|files| (checkout, files))
+            };
+            let new = new.unwrap_or_else(|| MetadataSource::Checkout(gecko_checkout.clone()));
+            match read_metadata(&old).and_then(|old| Ok((old, read_metadata(&new)?))) {
+                Ok(((old_checkout, old), (new_checkout, new))) => {
+                    print!("{}", diff(&old_checkout, old, &new_checkout, new));
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    render_error(e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Subcommand::Validate { listing } => {
+            let listing = match listing {
+                Some(path) => match fs
+                    .read_to_string(&path)
+                    .and_then(|contents| TestListing::parse_queries(&path, &contents))
                 {
-                    let Self {
-                        windows,
-                        linux,
-                        mac_os,
-                    } = self;
-                    for (platform, analysis) in [
-                        (Platform::Windows, windows),
-                        (Platform::Linux, linux),
-                        (Platform::MacOs, mac_os),
-                    ] {
-                        f(platform, analysis)
+                    Ok(listing) => listing,
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
                     }
+                },
+                None => TestListing::TestFiles,
+            };
+            let findings = read_and_parse_all_metadata(&fs, &gecko_checkout, browser)
+                .collect::<Result<IndexMap<_, _>, _>>()
+                .and_then(|files| validate(&fs, &gecko_checkout, &files, &listing));
+            match findings {
+                Ok(findings) if findings.is_empty() => {
+                    tracing::info!("all metadata corresponds to existing tests");
+                    ExitCode::SUCCESS
                 }
-
-                pub fn for_platform_mut<F>(&mut self, platform: Platform, mut f: F)
-                where
-                    F: FnMut(&mut PerPlatformAnalysis),
-                {
-                    match platform {
-                        Platform::Windows => f(&mut self.windows),
-                        Platform::Linux => f(&mut self.linux),
-                        Platform::MacOs => f(&mut self.mac_os),
+                Ok(findings) => {
+                    for finding in &findings {
+                        tracing::error!("{finding}");
                     }
+                    tracing::error!("found {} problem(s) with metadata", findings.len());
+                    ExitCode::FAILURE
+                }
+                Err(e) => {
+                    render_error(e);
+                    ExitCode::FAILURE
                 }
             }
-
-            let mut analysis = Analysis::default();
-            for (test_name, test) in tests_by_name {
-                let TaggedTest {
-                    orig_path: _,
-                    inner:
-                        Test {
-                            properties,
-                            subtests,
-                        },
-                } = test;
-
-                let TestProps {
-                    is_disabled,
-                    expected,
-                } = properties;
-
-                let test_name = Arc::new(test_name);
-
-                if is_disabled {
-                    analysis.for_each_platform_mut(|analysis| {
-                        analysis
-                            .tests_with_disabled_or_skip
-                            .perma
-                            .insert(test_name.clone());
-                    })
+        }
+        Subcommand::Stats { by_file } => {
+            let files = match read_and_parse_all_metadata(&fs, &gecko_checkout, browser)
+                .collect::<Result<IndexMap<_, _>, _>>()
+            {
+                Ok(files) => files,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            if by_file {
+                render_file_health(&gecko_checkout, &files);
+            } else {
+                print!("{}", Stats::from_files(files.values()));
+            }
+            ExitCode::SUCCESS
+        }
+        Subcommand::Search {
+            tests,
+            outcome,
+            platform,
+            build_profile,
+            disabled,
+            enabled,
+            table,
+        } => {
+            let files = match read_and_parse_all_metadata(&fs, &gecko_checkout, browser)
+                .collect::<Result<IndexMap<_, _>, _>>()
+            {
+                Ok(files) => files,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let filters = SearchFilters {
+                outcomes: outcome,
+                platforms: platform,
+                build_profiles: build_profile,
+                disabled: (disabled || enabled).then_some(disabled),
+                patterns: tests,
+            };
+            let hits = search(&gecko_checkout, &files, &filters);
+            if let Some(format) = table {
+                print!("{}", table::expectations(format, &hits));
+                return ExitCode::SUCCESS;
+            }
+            if hits.is_empty() {
+                println!("No matching tests or subtests found.");
+            }
+            for hit in hits {
+                print!("{hit}");
+            }
+            ExitCode::SUCCESS
+        }
+        Subcommand::RerunManifest {
+            report_paths,
+            report_globs,
+            platform_overrides,
+            max_repeat,
+        } => {
+            let Some(report_paths) = collect_report_paths(&fs, report_paths, report_globs) else {
+                return ExitCode::FAILURE;
+            };
+            let files = match read_and_parse_all_metadata(&fs, &gecko_checkout, browser)
+                .collect::<Result<IndexMap<_, _>, _>>()
+            {
+                Ok(files) => files,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let runs = match process_reports::read_and_parse_reports(
+                &fs,
+                report_paths,
+                platform_overrides,
+                &CANCELLED,
+            ) {
+                Ok(runs) => runs,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let flake_rates = match FlakeRates::from_reports(browser, runs) {
+                Ok(flake_rates) => flake_rates,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let manifest = RerunManifest::new(&files, &gecko_checkout, &flake_rates, max_repeat);
+            println!("{}", serde_json::to_string_pretty(&manifest).unwrap());
+            ExitCode::SUCCESS
+        }
+        Subcommand::PoolSkew {
+            report_paths,
+            report_globs,
+            platform_overrides,
+            mut pool_keys,
+        } => {
+            let Some(report_paths) = collect_report_paths(&fs, report_paths, report_globs) else {
+                return ExitCode::FAILURE;
+            };
+            if pool_keys.is_empty() {
+                pool_keys = DEFAULT_POOL_KEYS
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect();
+            }
+            let runs = match process_reports::read_and_parse_reports(
+                &fs,
+                report_paths,
+                platform_overrides,
+                &CANCELLED,
+            ) {
+                Ok(runs) => runs,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            match pool_skew(browser, runs, &pool_keys) {
+                Ok(skew) => {
+                    print!("{skew}");
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    render_error(e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Subcommand::ReproQueries {
+            report_paths,
+            report_globs,
+            platform_overrides,
+            platform,
+            min_failing,
+        } => {
+            let Some(report_paths) = collect_report_paths(&fs, report_paths, report_globs) else {
+                return ExitCode::FAILURE;
+            };
+            let runs = match process_reports::read_and_parse_reports(
+                &fs,
+                report_paths,
+                platform_overrides,
+                &CANCELLED,
+            ) {
+                Ok(runs) => runs,
+                Err(e) => {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            match failing_tests(browser, runs, platform, min_failing) {
+                Ok(tests) => {
+                    if tests.is_empty() {
+                        println!("No tests with {min_failing} or more failing subtests found.");
+                    }
+                    for test in tests {
+                        print!("{test}");
+                    }
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    render_error(e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Subcommand::Completions { .. } => {
+            unreachable!("completions are generated before looking for a checkout")
+        }
+        Subcommand::Triage {
+            on_zero_item,
+            format,
+            test_origin,
+            file_glob,
+            filters,
+            by_cts_area,
+            severity,
+            report_paths,
+            report_globs,
+            platform_overrides,
+            owners,
+            export_json,
+            export_html,
+            export_table,
+            quarantine_review_days,
+            bugzilla,
+            bugzilla_url,
+            bugzilla_product,
+            bugzilla_component,
+        } => {
+            let file_glob = match Glob::diagnosed(file_glob.as_deref().unwrap_or("**/*.ini")) {
+                Ok((glob, _diagnostics)) => glob,
+                Err(diagnostics) => {
+                    let diagnostics = diagnostics
+                        .into_iter()
+                        .filter(|diag| {
+                            // N.B.: There should be at least one of these!
+                            diag.severity()
+                                .map_or(true, |sev| sev == miette::Severity::Error)
+                        })
+                        .map(GlobDiagnostic)
+                        .collect();
+                    render_error(Error::ParseMetadataGlob { diagnostics });
+                    return ExitCode::FAILURE;
                 }
+            };
 
-                fn insert_in_test_set<Out>(
-                    poi: &mut TestSet,
-                    test_name: &Arc<String>,
-                    expected: Expected<Out>,
-                    outcome: Out,
-                ) where
-                    Out: Debug + Default + EnumSetType,
-                {
-                    if expected.is_superset(&Expected::permanent(outcome)) {
-                        if expected.is_permanent() {
-                            &mut poi.perma
-                        } else {
-                            &mut poi.intermittent
-                        }
-                        .insert(test_name.clone());
+            let mut err_found = false;
+            let files = read_and_parse_metadata_matching(&fs, &gecko_checkout, browser, &file_glob)
+                .filter_map(|res| match res {
+                    Ok(ok) => Some(ok),
+                    Err(e) => {
+                        render_error(e);
+                        err_found = true;
+                        None
                     }
-                }
-
-                fn insert_in_subtest_by_test_set<Out>(
-                    poi: &mut SubtestByTestSet,
-                    test_name: &Arc<String>,
-                    subtest_name: &Arc<String>,
-                    expected: Expected<Out>,
-                    outcome: Out,
-                ) where
-                    Out: Debug + Default + EnumSetType,
-                {
-                    if expected.is_superset(&Expected::permanent(outcome)) {
-                        if expected.is_permanent() {
-                            &mut poi.perma
-                        } else {
-                            &mut poi.intermittent
-                        }
-                        .entry(test_name.clone())
-                        .or_default()
-                        .insert(subtest_name.clone());
+                })
+                .collect::<Vec<_>>();
+            let dir_files = read_and_parse_all_dir_metadata(&fs, &gecko_checkout, browser)
+                .filter_map(|res| match res {
+                    Ok(ok) => Some(ok),
+                    Err(e) => {
+                        render_error(e);
+                        err_found = true;
+                        None
                     }
-                }
-
-                if let Some(expected) = expected {
-                    fn analyze_test_outcome<F>(
-                        test_name: &Arc<String>,
-                        expected: Expected<TestOutcome>,
-                        mut receiver: F,
-                    ) where
-                        F: FnMut(&mut dyn FnMut(&mut PerPlatformAnalysis)),
-                    {
-                        for outcome in expected.iter() {
-                            match outcome {
-                                TestOutcome::Ok => (),
-                                // We skip this because this test _should_ contain subtests with
-                                // `TIMEOUT` and `NOTRUN`, so we shouldn't actually miss anything.
-                                TestOutcome::Timeout => (),
-                                TestOutcome::Crash => receiver(&mut |analysis| {
-                                    insert_in_test_set(
-                                        &mut analysis.tests_with_crashes,
-                                        test_name,
-                                        expected,
-                                        outcome,
-                                    )
-                                }),
-                                TestOutcome::Error => receiver(&mut |analysis| {
-                                    insert_in_test_set(
-                                        &mut analysis.tests_with_runner_errors,
-                                        test_name,
-                                        expected,
-                                        outcome,
-                                    )
-                                }),
-                                TestOutcome::Skip => receiver(&mut |analysis| {
-                                    insert_in_test_set(
-                                        &mut analysis.tests_with_disabled_or_skip,
-                                        test_name,
-                                        expected,
-                                        outcome,
-                                    )
-                                }),
-                            }
-                        }
+                })
+                .collect::<Vec<_>>();
+            if err_found {
+                return ExitCode::FAILURE;
+            }
+            let expectations = export_table.as_ref().map(|_path| {
+                let filters = SearchFilters {
+                    patterns: filters.clone(),
+                    ..Default::default()
+                };
+                search(
+                    &gecko_checkout,
+                    files.iter().map(|(path, file)| (path, file)),
+                    &filters,
+                )
+            });
+            let tests_by_name = analysis::tests_by_name(&gecko_checkout, files, &filters);
+            let dir_props = analysis::inherited_dir_props(dir_files);
+            let owners = match owners {
+                Some(path) => match fs
+                    .read_to_string(&path)
+                    .and_then(|contents| Owners::parse(&path, &contents))
+                {
+                    Ok(owners) => owners,
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
                     }
+                },
+                None => Owners::default(),
+            };
+            let bugs_by_test = tests_by_name
+                .iter()
+                .filter_map(|(url_path, test)| {
+                    let Test {
+                        properties,
+                        subtests,
+                    } = &test.inner;
+                    let bugs = std::iter::once(&properties.bug)
+                        .chain(subtests.values().map(|subtest| &subtest.properties.bug))
+                        .flatten()
+                        .cloned()
+                        .collect::<BTreeSet<_>>();
+                    (!bugs.is_empty()).then(|| (url_path.clone(), bugs))
+                })
+                .collect::<BTreeMap<_, _>>();
+            let owners_by_test = tests_by_name
+                .keys()
+                .filter_map(|url_path| {
+                    let test_url_path = format!("/{url_path}");
+                    let test_path =
+                        TestPath::from_execution_report(browser, &test_url_path).ok()?;
+                    let owner = owners.owner_of(&test_path)?;
+                    Some((url_path.clone(), owner.to_owned()))
+                })
+                .collect::<BTreeMap<_, _>>();
 
-                    let apply_to_specific_platforms =
-                        |analysis: &mut Analysis, platform, expected| {
-                            analyze_test_outcome(&test_name, expected, |f| {
-                                analysis.for_platform_mut(platform, f)
-                            })
-                        };
-
-                    for ((platform, _build_profile), expected) in expected.iter() {
-                        apply_to_specific_platforms(&mut analysis, platform, expected)
+            let known_bugs = if bugzilla {
+                let api_key = std::env::var("BUGZILLA_API_KEY")
+                    .ok()
+                    .filter(|api_key| !api_key.is_empty());
+                let client = Bugzilla::new(&bugzilla_url, api_key);
+                tracing::info!("querying open bugs in {bugzilla_product} :: {bugzilla_component}…");
+                match client.open_bugs(&bugzilla_product, &bugzilla_component) {
+                    Ok(bugs) => Some((client, KnownBugs::new(bugs))),
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
                     }
                 }
+            } else {
+                None
+            };
+            let open_bugs_by_test = known_bugs.as_ref().map(|(client, known_bugs)| {
+                tests_by_name
+                    .keys()
+                    .filter_map(|url_path| {
+                        let test_url_path = format!("/{url_path}");
+                        let test_path =
+                            TestPath::from_execution_report(browser, &test_url_path).ok()?;
+                        let bugs = bugs_by_test.get(url_path).into_iter().flatten();
+                        let OpenBug { id, summary, .. } =
+                            known_bugs.tracking(&test_path, bugs.map(|bug| &**bug))?;
+                        Some((url_path.clone(), (client.bug_url(*id), summary.clone())))
+                    })
+                    .collect::<BTreeMap<_, _>>()
+            });
 
-                for (subtest_name, subtest) in subtests {
-                    let SectionHeader(subtest_name) = subtest_name;
-                    let subtest_name = Arc::new(subtest_name);
-
-                    let Subtest { properties } = subtest;
-                    let TestProps {
-                        is_disabled,
-                        expected,
-                    } = properties;
-
-                    if is_disabled {
-                        analysis
-                            .windows
-                            .tests_with_disabled_or_skip
-                            .perma
-                            .insert(test_name.clone());
+            let error_clusters = if report_paths.is_empty() && report_globs.is_empty() {
+                ErrorClusters::default()
+            } else {
+                let Some(report_paths) = collect_report_paths(&fs, report_paths, report_globs)
+                else {
+                    return ExitCode::FAILURE;
+                };
+                let runs = match process_reports::read_and_parse_reports(
+                    &fs,
+                    report_paths,
+                    platform_overrides,
+                    &CANCELLED,
+                ) {
+                    Ok(runs) => runs,
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
                     }
-
-                    if let Some(expected) = expected {
-                        fn analyze_subtest_outcome<Fo>(
-                            test_name: &Arc<String>,
-                            subtest_name: &Arc<String>,
-                            expected: Expected<SubtestOutcome>,
-                            mut receiver: Fo,
-                        ) where
-                            Fo: FnMut(&mut dyn FnMut(&mut PerPlatformAnalysis)),
-                        {
-                            for outcome in expected.iter() {
-                                match outcome {
-                                    SubtestOutcome::Pass => (),
-                                    SubtestOutcome::Timeout | SubtestOutcome::NotRun => {
-                                        receiver(&mut |analysis| {
-                                            insert_in_subtest_by_test_set(
-                                                &mut analysis.subtests_with_timeouts_by_test,
-                                                test_name,
-                                                subtest_name,
-                                                expected,
-                                                outcome,
-                                            )
+                };
+                let runs =
+                    runs.into_iter().map(|run| {
+                        run.map(|mut run| {
+                            if !filters.is_empty() {
+                                run.entries.retain(|entry| {
+                                    TestPath::from_execution_report(browser, &entry.test_name)
+                                        .map_or(true, |test_path| {
+                                            filters.iter().any(|filter| filter.matches(&test_path))
                                         })
-                                    }
-                                    SubtestOutcome::Crash => receiver(&mut |analysis| {
-                                        insert_in_test_set(
-                                            &mut analysis.tests_with_crashes,
-                                            test_name,
-                                            expected,
-                                            outcome,
-                                        )
-                                    }),
-                                    SubtestOutcome::Fail => receiver(&mut |analysis| {
-                                        insert_in_subtest_by_test_set(
-                                            &mut analysis.subtests_with_failures_by_test,
-                                            test_name,
-                                            subtest_name,
-                                            expected,
-                                            outcome,
-                                        )
-                                    }),
-                                }
+                                });
                             }
-                        }
-
-                        let apply_to_specific_platforms =
-                            |analysis: &mut Analysis, platform, expected| {
-                                analyze_subtest_outcome(&test_name, &subtest_name, expected, |f| {
-                                    analysis.for_platform_mut(platform, f)
-                                })
-                            };
-
-                        for ((platform, _build_profile), expected) in expected.iter() {
-                            apply_to_specific_platforms(&mut analysis, platform, expected)
-                        }
+                            run
+                        })
+                    });
+                match ErrorClusters::from_reports(browser, runs) {
+                    Ok(error_clusters) => error_clusters,
+                    Err(e) => {
+                        render_error(e);
+                        return ExitCode::FAILURE;
                     }
                 }
-            }
-            log::info!("finished analysis, printing to `stdout`…");
+            };
+
+            tracing::info!(concat!(
+                "finished parsing of interesting properties ",
+                "from metadata files, analyzing results…"
+            ));
+
+            let analysis = Analysis::new(
+                tests_by_name,
+                &dir_props,
+                Local::now().date_naive(),
+                quarantine_review_days,
+                &severity,
+            );
+            tracing::info!("finished analysis, printing to `stdout`…");
             analysis.for_each_platform(|platform, analysis| {
                 let show_zero_count_item = match on_zero_item {
                     OnZeroItem::Show => true,
@@ -1115,9 +3331,14 @@ fn run(cli: Cli) -> ExitCode {
                 let PerPlatformAnalysis {
                     tests_with_runner_errors,
                     tests_with_disabled_or_skip,
+                    tests_with_expired_disables,
+                    tests_with_overdue_quarantines,
+                    tests_in_disabled_dirs,
+                    tests_by_disabled_reason,
                     tests_with_crashes,
                     subtests_with_failures_by_test,
                     subtests_with_timeouts_by_test,
+                    tests_by_most_severe_outcome,
                 } = analysis;
 
                 let PermaAndIntermittent {
@@ -1149,8 +3370,33 @@ fn run(cli: Cli) -> ExitCode {
                     .then_some(lazy_format!(
                         "{num_tests_with_disabled} test(s) with some portion marked as `disabled`"
                     ));
+                let num_tests_with_expired_disables = tests_with_expired_disables.len();
+                let tests_with_expired_disables = (show_zero_count_item
+                    || num_tests_with_expired_disables > 0)
+                    .then_some(lazy_format!(
+                        concat!(
+                            "{} test(s) with some portion's `disabled-until` date passed, ",
+                            "due for re-evaluation"
+                        ),
+                        num_tests_with_expired_disables
+                    ));
+                let num_tests_with_overdue_quarantines = tests_with_overdue_quarantines.len();
+                let tests_with_overdue_quarantines = (show_zero_count_item
+                    || num_tests_with_overdue_quarantines > 0)
+                    .then_some(lazy_format!(
+                        "{} test(s) quarantined {} or more day(s) ago, due for review",
+                        num_tests_with_overdue_quarantines,
+                        quarantine_review_days,
+                    ));
+                let num_tests_in_disabled_dirs = tests_in_disabled_dirs.len();
+                let tests_in_disabled_dirs = (show_zero_count_item
+                    || num_tests_in_disabled_dirs > 0)
+                    .then_some(lazy_format!(
+                        "{} test(s) in directories disabled by `__dir__.ini`",
+                        num_tests_in_disabled_dirs
+                    ));
                 if num_tests_with_intermittent_disabled > 0 {
-                    log::warn!(
+                    tracing::warn!(
                         concat!(
                             "found {} intermittent `SKIP` outcomes, which we don't understand ",
                             "yet; figure it out! The tests: {:#?}"
@@ -1257,18 +3503,192 @@ fn run(cli: Cli) -> ExitCode {
                         )
                     }));
 
-                fn priority_section<'a, const SIZE: usize>(
+                let tests_by_most_severe_outcome = severity
+                    .outcomes()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(rank, outcome)| {
+                        let num_tests = tests_by_most_severe_outcome
+                            .get(&rank)
+                            .map_or(0, BTreeSet::len);
+                        (show_zero_count_item || num_tests > 0).then_some(lazy_format!(
+                            "{} test(s) with some portion expecting `{}` at worst",
+                            num_tests,
+                            outcome
+                        ))
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut num_tests_by_owner = BTreeMap::<Option<&str>, usize>::new();
+                if !owners.is_empty() {
+                    for test_name in analysis.issues_by_test().keys() {
+                        let owner = owners_by_test.get(&***test_name).map(|owner| &**owner);
+                        *num_tests_by_owner.entry(owner).or_default() += 1;
+                    }
+                }
+                let tests_by_owner = num_tests_by_owner
+                    .into_iter()
+                    .map(|(owner, num_tests)| {
+                        lazy_format!(move |f| match owner {
+                            Some(owner) => {
+                                write!(f, "{num_tests} test(s) needing attention owned by {owner}")
+                            }
+                            None =>
+                                write!(f, "{num_tests} test(s) needing attention with no owner"),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut num_tests_by_bug = BTreeMap::<Option<&str>, usize>::new();
+                for test_name in analysis.issues_by_test().keys() {
+                    match bugs_by_test.get(&***test_name) {
+                        Some(bugs) => {
+                            for bug in bugs {
+                                *num_tests_by_bug.entry(Some(bug)).or_default() += 1;
+                            }
+                        }
+                        None => *num_tests_by_bug.entry(None).or_default() += 1,
+                    }
+                }
+                let tests_by_bug = num_tests_by_bug
+                    .into_iter()
+                    .filter(|(bug, _num_tests)| show_zero_count_item || bug.is_some())
+                    .map(|(bug, num_tests)| {
+                        lazy_format!(move |f| match bug {
+                            Some(bug) => {
+                                write!(f, "{num_tests} test(s) needing attention filed as {bug}")
+                            }
+                            None => {
+                                write!(f, "{num_tests} test(s) needing attention with no `bug`")
+                            }
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut num_tests_by_open_bug = BTreeMap::<Option<&(String, String)>, usize>::new();
+                let mut untracked_perma_failures = Vec::new();
+                if let Some(open_bugs_by_test) = &open_bugs_by_test {
+                    for (test_name, issues) in analysis.issues_by_test() {
+                        let open_bug = open_bugs_by_test.get(&*test_name);
+                        *num_tests_by_open_bug.entry(open_bug).or_default() += 1;
+                        let perma_issues = issues
+                            .into_iter()
+                            .filter(|issue| issue.is_perma())
+                            .collect::<Vec<_>>();
+                        if open_bug.is_none() && !perma_issues.is_empty() {
+                            untracked_perma_failures.push((test_name, perma_issues));
+                        }
+                    }
+                }
+                let tests_by_open_bug = num_tests_by_open_bug
+                    .into_iter()
+                    .map(|(open_bug, num_tests)| {
+                        lazy_format!(move |f| match open_bug {
+                            Some((url, summary)) => write!(
+                                f,
+                                "{num_tests} test(s) needing attention tracked by {url}: {summary}"
+                            ),
+                            None =>
+                                write!(f, "{num_tests} test(s) needing attention with no open bug"),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let untracked_perma_failures = untracked_perma_failures
+                    .into_iter()
+                    .map(|(test_name, issues)| {
+                        let url = (format == OutputFormat::Markdown)
+                            .then(|| test_url(&test_origin, &test_name));
+                        let issues = issues.iter().join_with(", ").to_string();
+                        lazy_format!(move |f| match &url {
+                            Some(url) => write!(f, "[`/{test_name}`]({url}): {issues}"),
+                            None => write!(f, "`/{test_name}`: {issues}"),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                let tests_by_cts_area = by_cts_area
+                    .map(|depth| analysis.issues_by_cts_area(depth.get()))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(area, issues)| {
+                        let CtsAreaIssues {
+                            num_tests,
+                            num_tests_by_issue,
+                        } = issues;
+                        let issues = num_tests_by_issue
+                            .into_iter()
+                            .map(|(issue, num_tests)| format!("{num_tests} {issue}"))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        lazy_format!(move |f| match &area {
+                            Some(area) => write!(
+                                f,
+                                "{num_tests} test(s) needing attention in `{area}`: {issues}"
+                            ),
+                            None => write!(
+                                f,
+                                "{num_tests} test(s) needing attention outside the CTS: {issues}"
+                            ),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut tests_by_disabled_reason = tests_by_disabled_reason
+                    .iter()
+                    .map(|(reason, tests)| (reason, tests.len()))
+                    .collect::<Vec<_>>();
+                tests_by_disabled_reason.sort_by(|(_, a), (_, b)| b.cmp(a));
+                let tests_by_disabled_reason = tests_by_disabled_reason
+                    .into_iter()
+                    .map(|(reason, num_tests)| {
+                        lazy_format!(move |f| match reason {
+                            DisabledReason::Unspecified =>
+                                write!(f, "{num_tests} test(s) disabled with no reason given"),
+                            DisabledReason::Given(reason) =>
+                                write!(f, "{num_tests} test(s) disabled because {reason}"),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                let errors_by_message = error_clusters
+                    .for_platform(platform)
+                    .into_iter()
+                    .map(|(message, cluster)| {
+                        lazy_format!(
+                            "{} test(s) reporting `ERROR` with {:?}, {} time(s) total",
+                            cluster.tests.len(),
+                            message,
+                            cluster.occurrences
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                fn section<'a>(
+                    format: OutputFormat,
                     name: &'static str,
-                    items: [Option<&'a dyn Display>; SIZE],
+                    items: Vec<Option<&'a dyn Display>>,
                 ) -> Option<Box<dyn Display + 'a>> {
                     items.iter().any(Option::is_some).then(move || {
                         Box::new(lazy_format!(move |f| {
-                            let items = items
-                                .iter()
-                                .filter_map(|opt| *opt)
-                                .map(|item| lazy_format!("\n    {item}"))
-                                .join_with("");
-                            write!(f, "\n  {name} PRIORITY:{items}")
+                            let items = items.iter().filter_map(|opt| *opt);
+                            match format {
+                                OutputFormat::Text => {
+                                    let items = items
+                                        .map(|item| lazy_format!("\n    {item}"))
+                                        .join_with("");
+                                    write!(f, "\n  {name}:{items}")
+                                }
+                                OutputFormat::Remarkup => {
+                                    let items =
+                                        items.map(|item| lazy_format!("\n- {item}")).join_with("");
+                                    write!(f, "\n\n**{name}:**\n{items}")
+                                }
+                                OutputFormat::Markdown => {
+                                    let items =
+                                        items.map(|item| lazy_format!("\n- {item}")).join_with("");
+                                    write!(f, "\n\n### {name}\n{items}")
+                                }
+                            }
                         })) as Box<dyn Display>
                     })
                 }
@@ -1278,283 +3698,319 @@ fn run(cli: Cli) -> ExitCode {
                 {
                     item.map(|disp| disp as &dyn Display)
                 }
+                // N.B.: Other formats rely on the full analysis printed afterwards.
+                let tests_needing_attention = if format == OutputFormat::Markdown {
+                    analysis
+                        .issues_by_test()
+                        .into_iter()
+                        .map(|(test_name, issues)| {
+                            let url = test_url(&test_origin, &test_name);
+                            let issues = issues.iter().join_with(", ").to_string();
+                            lazy_format!(move |f| write!(f, "[`/{test_name}`]({url}): {issues}"))
+                        })
+                        .collect::<Vec<_>>()
+                } else {
+                    Vec::new()
+                };
+
+                #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+                enum Priority {
+                    High,
+                    Medium,
+                    Low,
+                }
+                let num_severe = severity.outcomes().len() / 2;
+                let ranked = |rank: Option<usize>, intermittent: bool, item| {
+                    rank.map(|rank| {
+                        let priority = match (rank < num_severe, intermittent) {
+                            (true, false) => Priority::High,
+                            (true, true) | (false, false) => Priority::Medium,
+                            (false, true) => Priority::Low,
+                        };
+                        (priority, rank, item)
+                    })
+                };
+                let crash_rank = [
+                    severity.rank_test_outcome(TestOutcome::Crash),
+                    severity.rank_subtest_outcome(SubtestOutcome::Crash),
+                ]
+                .into_iter()
+                .flatten()
+                .min();
+                let error_rank = severity.rank_test_outcome(TestOutcome::Error);
+                let timeout_rank = [
+                    severity.rank_subtest_outcome(SubtestOutcome::Timeout),
+                    severity.rank_subtest_outcome(SubtestOutcome::NotRun),
+                ]
+                .into_iter()
+                .flatten()
+                .min();
+                let fail_rank = severity.rank_subtest_outcome(SubtestOutcome::Fail);
+                let mut ranked_items = [
+                    ranked(
+                        error_rank,
+                        false,
+                        item(tests_with_perma_runner_errors.as_ref()),
+                    ),
+                    ranked(crash_rank, false, item(tests_with_perma_crashes.as_ref())),
+                    ranked(
+                        timeout_rank,
+                        false,
+                        item(tests_with_perma_timeouts_somewhere.as_ref()),
+                    ),
+                    ranked(fail_rank, false, item(tests_with_perma_failures.as_ref())),
+                    ranked(
+                        error_rank,
+                        true,
+                        item(tests_with_intermittent_runner_errors.as_ref()),
+                    ),
+                    ranked(
+                        crash_rank,
+                        true,
+                        item(tests_with_intermittent_crashes.as_ref()),
+                    ),
+                    ranked(
+                        timeout_rank,
+                        true,
+                        item(tests_with_intermittent_timeouts_somewhere.as_ref()),
+                    ),
+                    ranked(
+                        fail_rank,
+                        true,
+                        item(tests_with_intermittent_failures.as_ref()),
+                    ),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+                ranked_items.sort_by_key(|(priority, rank, _item)| (*priority, *rank));
+                let ranked_items = &ranked_items;
+                let with_priority = move |priority| {
+                    ranked_items
+                        .iter()
+                        .filter(move |(p, _rank, _item)| *p == priority)
+                        .map(|(_priority, _rank, item)| *item)
+                };
+
                 let sections = [
-                    priority_section(
-                        "HIGH",
+                    section(
+                        format,
+                        "UNTRACKED PERMA-FAILURES",
+                        untracked_perma_failures
+                            .iter()
+                            .map(|test| Some(test as &dyn Display))
+                            .collect(),
+                    ),
+                    section(
+                        format,
+                        "HIGH PRIORITY",
                         [
-                            item(tests_with_perma_runner_errors.as_ref()),
                             item(tests_with_disabled.as_ref()),
-                            item(tests_with_perma_crashes.as_ref()),
-                        ],
+                            item(tests_with_expired_disables.as_ref()),
+                            item(tests_with_overdue_quarantines.as_ref()),
+                            item(tests_in_disabled_dirs.as_ref()),
+                        ]
+                        .into_iter()
+                        .chain(with_priority(Priority::High))
+                        .collect(),
                     ),
-                    priority_section(
-                        "MEDIUM",
-                        [
-                            item(tests_with_perma_failures.as_ref()),
-                            item(tests_with_perma_timeouts_somewhere.as_ref()),
-                            item(tests_with_intermittent_crashes.as_ref()),
-                            item(tests_with_intermittent_runner_errors.as_ref()),
-                        ],
+                    section(
+                        format,
+                        "MEDIUM PRIORITY",
+                        with_priority(Priority::Medium).collect(),
                     ),
-                    priority_section(
-                        "LOW",
-                        [
-                            item(tests_with_intermittent_timeouts_somewhere.as_ref()),
-                            item(tests_with_intermittent_failures.as_ref()),
-                        ],
+                    section(
+                        format,
+                        "LOW PRIORITY",
+                        with_priority(Priority::Low).collect(),
+                    ),
+                    section(
+                        format,
+                        "BY SEVERITY",
+                        tests_by_most_severe_outcome
+                            .iter()
+                            .map(|tests| Some(tests as &dyn Display))
+                            .collect(),
+                    ),
+                    section(
+                        format,
+                        "BY OWNER",
+                        tests_by_owner
+                            .iter()
+                            .map(|tests| Some(tests as &dyn Display))
+                            .collect(),
+                    ),
+                    section(
+                        format,
+                        "BY BUG",
+                        tests_by_bug
+                            .iter()
+                            .map(|tests| Some(tests as &dyn Display))
+                            .collect(),
+                    ),
+                    section(
+                        format,
+                        "BY OPEN BUG",
+                        tests_by_open_bug
+                            .iter()
+                            .map(|tests| Some(tests as &dyn Display))
+                            .collect(),
+                    ),
+                    section(
+                        format,
+                        "BY CTS AREA",
+                        tests_by_cts_area
+                            .iter()
+                            .map(|tests| Some(tests as &dyn Display))
+                            .collect(),
+                    ),
+                    section(
+                        format,
+                        "DISABLED BY REASON",
+                        tests_by_disabled_reason
+                            .iter()
+                            .map(|tests| Some(tests as &dyn Display))
+                            .collect(),
+                    ),
+                    section(
+                        format,
+                        "ERRORS BY MESSAGE",
+                        errors_by_message
+                            .iter()
+                            .map(|errors| Some(errors as &dyn Display))
+                            .collect(),
+                    ),
+                    section(
+                        format,
+                        "TESTS NEEDING ATTENTION",
+                        tests_needing_attention
+                            .iter()
+                            .map(|test| Some(test as &dyn Display))
+                            .collect(),
                     ),
                 ];
                 let sections = sections.iter().filter_map(Option::as_ref).join_with("");
-                println!("{platform:?}:{sections}")
+                match format {
+                    OutputFormat::Text => println!("{platform:?}:{sections}"),
+                    OutputFormat::Remarkup => println!("== {platform:?} =={sections}\n"),
+                    OutputFormat::Markdown => println!("## {platform:?}{sections}\n"),
+                }
             });
-            println!("Full analysis: {analysis:#?}");
-            ExitCode::SUCCESS
-        }
-    }
-}
-
-fn read_and_parse_all_metadata(
-    gecko_checkout: &Path,
-) -> impl Iterator<Item = Result<(Arc<PathBuf>, metadata::File), AlreadyReportedToCommandline>> {
-    let webgpu_cts_meta_parent_dir =
-        path!(gecko_checkout | "testing" | "web-platform" | "mozilla" | "meta" | "webgpu");
-
-    let raw_metadata_files =
-        read_gecko_files_at(gecko_checkout, &webgpu_cts_meta_parent_dir, "**/*.ini");
-
-    let mut started_parsing = false;
-    raw_metadata_files.filter_map(move |res| {
-        res.and_then(|(path, file_contents)| {
-            if path.ends_with("__dir__.ini") {
-                return Ok(None);
+            match format {
+                OutputFormat::Text => println!("Full analysis: {analysis:#?}"),
+                // N.B.: `lines` makes Phabricator collapse long code blocks into a scrollable box.
+                OutputFormat::Remarkup => {
+                    println!("```lang=text, name=Full analysis, lines=20\n{analysis:#?}\n```")
+                }
+                // N.B.: Tests are already listed by platform, and GitHub collapses `<details>`.
+                OutputFormat::Markdown => println!(
+                    concat!(
+                        "<details><summary>Full analysis</summary>\n\n",
+                        "```text\n{:#?}\n```\n\n</details>"
+                    ),
+                    analysis
+                ),
             }
-
-            let path = Arc::new(path);
-            let file_contents = Arc::new(file_contents);
-
-            if !started_parsing {
-                log::info!("parsing metadata…");
-                started_parsing = true;
+            if let Some(path) = export_json {
+                let mut export = serde_json::Map::new();
+                analysis.for_each_platform(|platform, analysis| {
+                    let tests = analysis
+                        .issues_by_test()
+                        .into_iter()
+                        .map(|(test_name, issues)| {
+                            serde_json::json!({
+                                "test": format!("/{test_name}"),
+                                "owner": owners_by_test.get(&*test_name),
+                                "bugs": bugs_by_test.get(&*test_name),
+                                "open_bug": open_bugs_by_test
+                                    .as_ref()
+                                    .and_then(|open_bugs| open_bugs.get(&*test_name))
+                                    .map(|(url, _summary)| url),
+                                "issues": issues,
+                            })
+                        })
+                        .collect();
+                    export.insert(format!("{platform:?}"), serde_json::Value::Array(tests));
+                });
+                let export = serde_json::to_string_pretty(&export).unwrap();
+                if let Err(e) = fs.write(&path, &export) {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
             }
-
-            log::debug!("parsing metadata at {}", path.display());
-            let res = match chumsky::Parser::parse(&metadata::File::parser(), &*file_contents)
-                .into_result()
-            {
-                Err(errors) => {
-                    render_metadata_parse_errors(&path, &file_contents, errors);
-                    Err(AlreadyReportedToCommandline)
+            if let (Some(path), Some(expectations)) = (export_table, expectations) {
+                let format = TableFormat::for_path(&path);
+                if let Err(e) = fs.write(&path, &table::expectations(format, &expectations)) {
+                    render_error(e);
+                    return ExitCode::FAILURE;
                 }
-                Ok(file) => Ok((path, file)),
-            };
-            Some(res).transpose()
-        })
-        .transpose()
-    })
-}
-
-fn render_metadata_parse_errors<'a>(
-    path: &Arc<PathBuf>,
-    file_contents: &Arc<String>,
-    errors: impl IntoIterator<Item = Rich<'a, char>>,
-) {
-    #[derive(Debug, Diagnostic, thiserror::Error)]
-    #[error("{inner}")]
-    struct ParseError {
-        #[label]
-        span: SourceSpan,
-        #[source_code]
-        source_code: NamedSource,
-        inner: Rich<'static, char>,
-    }
-    let source_code = file_contents.clone();
-    for error in errors {
-        let span = error.span();
-        let error = ParseError {
-            source_code: NamedSource::new(path.to_str().unwrap(), source_code.clone()),
-            inner: error.clone().into_owned(),
-            span: SourceSpan::new(span.start.into(), (span.end - span.start).into()),
-        };
-        let error = Report::new(error);
-        eprintln!("{error:?}");
-    }
-}
-
-/// Returns a "naturally" sorted list of files found by searching for `glob_pattern` in `base`.
-/// `gecko_checkout` is stripped as a prefix from the absolute paths recorded into `log` entries
-/// emitted by this function.
-///
-/// # Returns
-///
-/// An iterator over [`Result`]s containing either a Gecko file's path and contents as a UTF-8
-/// string, or the sentinel of an error encountered for the same file that is already reported to
-/// the command line.
-///
-/// # Panics
-///
-/// This function will panick if `gecko_checkout` cannot be stripped as a prefix of `base`.
-fn read_gecko_files_at(
-    gecko_checkout: &Path,
-    base: &Path,
-    glob_pattern: &str,
-) -> impl Iterator<Item = Result<(PathBuf, String), AlreadyReportedToCommandline>> {
-    log::info!("reading {glob_pattern} files at {}", base.display());
-    let mut found_read_err = false;
-    let mut paths = Glob::new(glob_pattern)
-        .unwrap()
-        .walk(base)
-        .filter_map(|entry| match entry {
-            Ok(entry) => Some(entry.path().to_owned()),
-            Err(e) => {
-                let path_disp = e
-                    .path()
-                    .map(|p| format!(" in {}", p.strip_prefix(gecko_checkout).unwrap().display()));
-                let path_disp: &dyn Display = match path_disp.as_ref() {
-                    Some(disp) => disp,
-                    None => &"",
-                };
-                log::error!(
-                    "failed to enumerate {glob_pattern} files{}\n  caused by: {e}",
-                    path_disp
+            }
+            if let Some(path) = export_html {
+                let html = html::triage_report(
+                    &analysis,
+                    &owners_by_test,
+                    &bugs_by_test,
+                    Local::now().date_naive(),
                 );
-                found_read_err = true;
-                None
+                if let Err(e) = fs.write(&path, &html) {
+                    render_error(e);
+                    return ExitCode::FAILURE;
+                }
             }
-        })
-        .collect::<Vec<_>>();
-
-    paths.sort_by(|a, b| natord::compare(a.to_str().unwrap(), b.to_str().unwrap()));
-    let paths = paths;
-
-    log::debug!(
-        "working with these files: {:#?}",
-        paths
-            .iter()
-            .map(|f| f.strip_prefix(gecko_checkout).unwrap())
-            .collect::<std::collections::BTreeSet<_>>()
-    );
-
-    let iter = paths.into_iter().map(|path| -> Result<_, _> {
-        log::debug!("reading from {}…", path.display());
-        fs::read_to_string(&path)
-            .map_err(|e| {
-                log::error!("failed to read {path:?}: {e}");
-                AlreadyReportedToCommandline
-            })
-            .map(|file_contents| (path, file_contents))
-    });
-
-    let (read_err_iter, file_read_iter) = if found_read_err {
-        (Some(Err(AlreadyReportedToCommandline)), None)
-    } else {
-        (None, Some(iter))
-    };
-
-    read_err_iter
-        .into_iter()
-        .chain(file_read_iter.into_iter().flatten())
+            ExitCode::SUCCESS
+        }
+    }
 }
 
-/// Search for a `mozilla-central` checkout either via Mercurial or Git, iterating from the CWD to
-/// its parent directories.
-///
-/// This function reports to `log` automatically, so no meaningful [`Err`] value is returned.
-fn search_for_moz_central_ckt() -> Result<PathBuf, AlreadyReportedToCommandline> {
-    use lets_find_up::{find_up_with, FindUpKind, FindUpOptions};
-
-    let find_up_opts = || FindUpOptions {
-        cwd: Path::new("."),
-        kind: FindUpKind::Dir,
-    };
-    let find_up = |repo_tech_name, root_dir_name| {
-        log::debug!("searching for {repo_tech_name} checkout of `mozilla-central`…");
-        let err = || {
-            miette!(
-                "failed to find a {} repository ({:?}) in {}",
-                repo_tech_name,
-                root_dir_name,
-                "any of current working directory and its parent directories",
-            )
-        };
-        find_up_with(root_dir_name, find_up_opts())
-            .map_err(Report::msg)
-            .wrap_err_with(err)
-            .and_then(|loc_opt| loc_opt.ok_or_else(err))
-            .map(|mut dir| {
-                dir.pop();
-                dir
-            })
-    };
-    let gecko_source_root =
-        find_up("Mercurial", ".hg").or_else(|e| match find_up("Git", ".git") {
-            Ok(path) => {
-                log::debug!("{e:?}");
-                Ok(path)
-            }
-            Err(e2) => {
-                log::warn!("{e:?}");
-                log::warn!("{e2:?}");
-                log::error!("failed to find a Gecko repository root");
-                Err(AlreadyReportedToCommandline)
-            }
-        })?;
-
-    log::info!(
-        "detected Gecko repository root at {}",
-        gecko_source_root.display()
-    );
-
-    Ok(gecko_source_root)
+/// Returns the URL of the test at `test_url_path` (i.e.,
+/// `_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,*`) on `origin`, escaped for use as a
+/// link in Markdown.
+fn test_url(origin: &str, test_url_path: &str) -> String {
+    let mut url = format!("{}/", origin.trim_end_matches('/'));
+    for c in test_url_path.chars() {
+        match c {
+            ' ' | '"' | '(' | ')' | '<' | '>' => url.push_str(&format!("%{:02X}", c as u32)),
+            c => url.push(c),
+        }
+    }
+    url
 }
 
-struct AlreadyReportedToCommandline;
-
-fn write_to_file(path: &Path, contents: impl Display) -> Result<(), AlreadyReportedToCommandline> {
-    let report_to_cmd_line = |e| {
-        log::error!("{e}");
-        AlreadyReportedToCommandline
+#[test]
+fn only_platform_and_build_profile_lists() {
+    let cli = Cli::try_parse_from([
+        "moz-webgpu-cts",
+        "update-expected",
+        "--only-platform",
+        "windows,linux",
+        "--only-platform=macos",
+        "--only-build-profile",
+        "debug,opt",
+    ])
+    .unwrap();
+    let (only_platform, only_build_profile) = match cli.subcommand {
+        Subcommand::UpdateExpected {
+            only_platform,
+            only_build_profile,
+            ..
+        } => (only_platform, only_build_profile),
+        other => panic!("expected `update-expected`, got {other:?}"),
     };
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(Report::msg)
-            .wrap_err_with(|| {
-                format!(
-                    "error while ensuring parent directories exist for `{}`",
-                    path.display()
-                )
-            })
-            .map_err(report_to_cmd_line)?;
-    }
-    let mut out = fs::File::create(path)
-        .map(BufWriter::new)
-        .map_err(Report::msg)
-        .wrap_err_with(|| format!("error while creating new file at `{}`", path.display()))
-        .map_err(report_to_cmd_line)?;
-    use io::Write;
-    write!(&mut out, "{contents}")
-        .map_err(Report::msg)
-        .wrap_err_with(|| format!("error while writing to `{}`", path.display()))
-        .map_err(report_to_cmd_line)
-}
+    assert_eq!(
+        only_platform,
+        [Platform::Windows, Platform::Linux, Platform::MacOs]
+    );
+    assert_eq!(
+        only_build_profile,
+        [BuildProfile::Debug, BuildProfile::Optimized]
+    );
 
-/// Ensure that _both_ `TIMEOUT` and `NOTRUN` are in outcomes if at least one of them are present.
-///
-/// This transformation is desirable for reaching convergence quickly in tests where it may require
-/// a high number of test runs to empirically observe all places where `TIMEOUT`s may occur. The
-/// motivating example in Firefox's test runs are tests with a large matrix of subtests that are
-/// deterministic if executed, but consistently exceed the timeout window offered by the test
-/// runner.
-fn taint_subtest_timeouts_by_suspicion(expected: &mut Expected<SubtestOutcome>) {
-    let timeout_and_notrun =
-        Expected::intermittent(SubtestOutcome::Timeout | SubtestOutcome::NotRun).unwrap();
-    if !expected.is_disjoint(timeout_and_notrun.inner())
-        && !expected.is_superset(&timeout_and_notrun)
-    {
-        static PRINTED_WARNING: AtomicBool = AtomicBool::new(false);
-        let already_printed_warning = PRINTED_WARNING.swap(true, atomic::Ordering::Relaxed);
-        if !already_printed_warning {
-            log::info!("encountered at least one case where taint-by-suspicion is being applied…")
-        }
-        *expected |= SubtestOutcome::Timeout | SubtestOutcome::NotRun;
-    }
+    let err = Cli::try_parse_from([
+        "moz-webgpu-cts",
+        "update-expected",
+        "--only-platform",
+        "win,os2",
+    ])
+    .unwrap_err();
+    assert!(err.to_string().contains("unrecognized platform \"os2\""));
 }
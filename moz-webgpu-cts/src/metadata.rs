@@ -1,14 +1,18 @@
 use std::{
-    collections::BTreeMap,
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Display, Formatter},
     hash::Hash,
+    str::FromStr,
 };
 
+use chrono::NaiveDate;
 use enum_map::Enum;
 use enumset::EnumSetType;
 use format::lazy_format;
 use joinery::JoinableIterator;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use strum::EnumIter;
 use whippit::{
     metadata::{
@@ -24,19 +28,29 @@ use whippit::{
         prelude::Rich,
         primitive::{any, choice, end, group, just, one_of},
         span::SimpleSpan,
+        text::digits,
         text::{ascii, inline_whitespace, keyword, newline},
         Boxed, IterParser, Parser,
     },
 };
 
-use crate::shared::{
-    Expected, FullyExpandedExpectedPropertyValue, MaybeCollapsed, NormalizedExpectedPropertyValue,
+use crate::{
+    bugzilla::bug_id,
+    collation::{self, Collation},
+    shared::{
+        Expected, FullyExpandedExpectedPropertyValue, MaybeCollapsed,
+        NormalizedExpectedPropertyValue,
+    },
 };
 
 #[cfg(test)]
 use insta::assert_debug_snapshot;
 
-#[derive(Clone, Debug, Default)]
+mod builder;
+
+pub use self::builder::{BuildError, FileBuilder, SubtestBuilder, TestBuilder};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct File {
     pub properties: FileProps,
     pub tests: BTreeMap<SectionHeader, Test>,
@@ -58,9 +72,13 @@ impl<'a> metadata::File<'a> for File {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+/// The value of a `disabled` property: why tests are disabled (i.e., `true`, or a bug URL), possibly
+/// only under some conditions.
+pub type DisabledValue = PropertyValue<Expr<Value<'static>>, String>;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct FileProps {
-    pub is_disabled: Option<PropertyValue<Expr<Value<'static>>, String>>,
+    pub is_disabled: Option<DisabledValue>,
     #[allow(clippy::type_complexity)]
     pub prefs: Option<PropertyValue<Expr<Value<'static>>, Vec<(String, String)>>>,
     pub tags: Option<PropertyValue<Expr<Value<'static>>, Vec<String>>>,
@@ -198,6 +216,219 @@ impl<'a> Properties<'a> for FileProps {
     }
 }
 
+impl FileProps {
+    /// Returns whether `disabled` applies to tests run on `platform` with `build_profile`; see
+    /// [`disabled_reason_on`].
+    pub fn is_disabled_on(&self, platform: Platform, build_profile: BuildProfile) -> bool {
+        disabled_reason_on(self.is_disabled.as_ref(), platform, build_profile).is_some()
+    }
+}
+
+/// A directory metadata file (i.e., `__dir__.ini`), which has no test sections.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DirFile {
+    pub properties: DirProps,
+}
+
+impl DirFile {
+    pub fn parser<'a>() -> impl Parser<'a, &'a str, DirFile, ParseError<'a>> {
+        file_parser()
+    }
+}
+
+impl<'a> metadata::File<'a> for DirFile {
+    type Properties = DirProps;
+    type Tests = NoTests;
+
+    fn new(properties: Self::Properties, _tests: Self::Tests) -> Self {
+        Self { properties }
+    }
+}
+
+/// The test sections of a [`DirFile`], of which there must be none.
+#[derive(Debug, Default)]
+pub struct NoTests;
+
+impl<'a> metadata::Tests<'a> for NoTests {
+    type Test = Test;
+
+    fn add_test(
+        &mut self,
+        name: SectionHeader,
+        _test: Self::Test,
+        span: SimpleSpan,
+        emitter: &mut Emitter<Rich<'a, char>>,
+    ) {
+        emitter.emit(Rich::custom(
+            span,
+            format!(
+                "directory metadata can't have test sections, but found `[{}]`",
+                name.escaped()
+            ),
+        ));
+    }
+}
+
+/// The properties of a directory metadata file (i.e., `__dir__.ini`). They apply to every test
+/// under the directory, unless a nearer directory, or the test itself, sets them, too.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DirProps {
+    /// Properties that may also be at the top of a test's metadata file (i.e., `disabled` or
+    /// `prefs`).
+    pub file: FileProps,
+    /// Properties that tests may also have (i.e., `expected` or `bug`).
+    pub test: TestProps<TestOutcome>,
+    /// Any other properties (i.e., `lsan-allowed` or `leak-threshold`), by name, kept as written,
+    /// in order.
+    pub other: Vec<(String, PropertyValue<Expr<Value<'static>>, String>)>,
+}
+
+impl<'a> Properties<'a> for DirProps {
+    type ParsedProperty = DirProp;
+
+    fn property_parser(
+        helper: &mut PropertiesParseHelper<'a>,
+    ) -> Boxed<'a, 'a, &'a str, Self::ParsedProperty, ParseError<'a>> {
+        let file = FileProps::property_parser(helper).map(DirProp::File);
+        let test = TestProps::<TestOutcome>::property_parser(helper).map(DirProp::Test);
+        let other = helper
+            .parser(
+                ascii::ident()
+                    .or(just('-').to_slice())
+                    .repeated()
+                    .at_least(1)
+                    .to_slice()
+                    .map(|s: &str| s.to_owned()),
+                Expr::parser(Value::parser().map(|expr| expr.to_static())),
+                any()
+                    .and_is(newline().or(end()).not())
+                    .repeated()
+                    .at_least(1)
+                    .to_slice()
+                    .map(|s: &str| s.to_owned()),
+            )
+            .map_with(|(name, val), e| DirProp::Other {
+                span: e.span(),
+                name,
+                val,
+            });
+        choice((file, test, other)).boxed()
+    }
+
+    fn add_property(&mut self, prop: Self::ParsedProperty, emitter: &mut Emitter<Rich<'a, char>>) {
+        let Self { file, test, other } = self;
+        match prop {
+            DirProp::File(prop) => file.add_property(prop, emitter),
+            DirProp::Test(prop) => test.insert(prop, emitter),
+            DirProp::Other { span, name, val } => {
+                if let Some((_name, old)) = other.iter_mut().find(|(other, _val)| *other == name) {
+                    emitter.emit(Rich::custom(
+                        span,
+                        format!("duplicate `{name}` property detected; discarding oldest"),
+                    ));
+                    *old = val;
+                } else {
+                    other.push((name, val));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum DirProp {
+    File((SimpleSpan, FileProp)),
+    Test(TestProp<TestOutcome>),
+    Other {
+        span: SimpleSpan,
+        name: String,
+        val: PropertyValue<Expr<Value<'static>>, String>,
+    },
+}
+
+/// Why a `disabled` property disables tests, as returned by [`disabled_reason_on`]. Displayed as
+/// the value of `disabled` it was parsed from.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum DisabledReason<'a> {
+    /// `disabled: true`, which gives no reason.
+    Unspecified,
+    /// Any other value, usually a bug URL.
+    Given(Cow<'a, str>),
+}
+
+impl<'a> DisabledReason<'a> {
+    fn new(value: &'a str) -> Self {
+        match value {
+            "true" => Self::Unspecified,
+            reason => Self::Given(Cow::Borrowed(reason)),
+        }
+    }
+
+    pub fn into_owned(self) -> DisabledReason<'static> {
+        match self {
+            Self::Unspecified => DisabledReason::Unspecified,
+            Self::Given(reason) => DisabledReason::Given(Cow::Owned(reason.into_owned())),
+        }
+    }
+}
+
+impl Display for DisabledReason<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unspecified => f.write_str("true"),
+            Self::Given(reason) => f.write_str(reason),
+        }
+    }
+}
+
+/// Returns the reason given by `disabled` for tests run on `platform` with `build_profile`, or
+/// `None` if it doesn't apply to them.
+///
+/// Only `os` and `debug` are evaluated; other variables (i.e., `release_or_beta`) are assumed to
+/// be false, since CI runs of WebGPU tests happen on Nightly.
+pub fn disabled_reason_on(
+    disabled: Option<&DisabledValue>,
+    platform: Platform,
+    build_profile: BuildProfile,
+) -> Option<DisabledReason<'_>> {
+    fn eval(cond: &Expr<Value<'_>>, platform: Platform, build_profile: BuildProfile) -> bool {
+        match cond {
+            Expr::Value(Value::Variable(var)) => {
+                var == "debug" && build_profile == BuildProfile::Debug
+            }
+            Expr::Value(Value::Literal(_)) => false,
+            Expr::And(lhs, rhs) => {
+                eval(lhs, platform, build_profile) && eval(rhs, platform, build_profile)
+            }
+            Expr::Not(cond) => !eval(cond, platform, build_profile),
+            Expr::Eq(lhs, rhs) => match (&**lhs, &**rhs) {
+                (
+                    Expr::Value(Value::Variable(var)),
+                    Expr::Value(Value::Literal(Literal::String(lit))),
+                )
+                | (
+                    Expr::Value(Value::Literal(Literal::String(lit))),
+                    Expr::Value(Value::Variable(var)),
+                ) if var == "os" => lit == platform.os_name(),
+                _ => false,
+            },
+        }
+    }
+
+    let reason = match disabled? {
+        PropertyValue::Unconditional(reason) => reason,
+        PropertyValue::Conditional(ConditionalValue {
+            conditions,
+            fallback,
+        }) => conditions
+            .iter()
+            .find(|(cond, _reason)| eval(cond, platform, build_profile))
+            .map(|(_cond, reason)| reason)
+            .or(fallback.as_ref())?,
+    };
+    Some(DisabledReason::new(reason))
+}
+
 #[test]
 fn file_props() {
     let parser = FileProps::property_parser(&mut PropertiesParseHelper::new(0));
@@ -449,6 +680,271 @@ disabled:
         );
 }
 
+#[test]
+fn file_props_is_disabled_on() {
+    let var = |name: &'static str| Expr::Value(Value::Variable(name.into()));
+    let os_eq = |os: &'static str| {
+        Expr::Eq(
+            Box::new(var("os")),
+            Box::new(Expr::Value(Value::Literal(Literal::String(os.into())))),
+        )
+    };
+    let props = FileProps {
+        is_disabled: Some(PropertyValue::Conditional(ConditionalValue {
+            conditions: vec![
+                (
+                    Expr::And(Box::new(os_eq("win")), Box::new(var("debug"))),
+                    "https://bugzilla.mozilla.org/show_bug.cgi?id=1234567".to_owned(),
+                ),
+                (
+                    os_eq("mac"),
+                    "https://bugzilla.mozilla.org/show_bug.cgi?id=7654321".to_owned(),
+                ),
+                (var("release_or_beta"), "nope".to_owned()),
+            ],
+            fallback: None,
+        })),
+        ..Default::default()
+    };
+
+    assert!(props.is_disabled_on(Platform::Windows, BuildProfile::Debug));
+    assert!(!props.is_disabled_on(Platform::Windows, BuildProfile::Optimized));
+    assert!(props.is_disabled_on(Platform::MacOs, BuildProfile::Debug));
+    assert!(props.is_disabled_on(Platform::MacOs, BuildProfile::Optimized));
+    assert!(!props.is_disabled_on(Platform::Linux, BuildProfile::Debug));
+    assert!(!props.is_disabled_on(Platform::Linux, BuildProfile::Optimized));
+
+    assert!(!FileProps::default().is_disabled_on(Platform::Linux, BuildProfile::Debug));
+}
+
+#[test]
+fn test_props_disabled_reasons() {
+    let contents = concat!(
+        "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+        "  disabled:\n",
+        "    if os == \"win\" and debug: https://bugzilla.mozilla.org/show_bug.cgi?id=1234567\n",
+        "  [:a]\n",
+        "    disabled: flaky on every platform\n",
+        "\n",
+        "  [:b]\n",
+        "    disabled: true\n",
+    );
+    let file = File::parser().parse(contents).into_result().unwrap();
+    assert_eq!(format_file(&file).to_string(), contents);
+
+    let test = &file.tests.values().next().unwrap();
+    assert_eq!(
+        test.properties
+            .disabled_reason_on(Platform::Windows, BuildProfile::Debug),
+        Some(DisabledReason::Given(
+            "https://bugzilla.mozilla.org/show_bug.cgi?id=1234567".into()
+        ))
+    );
+    assert!(!test
+        .properties
+        .is_disabled_on(Platform::Windows, BuildProfile::Optimized));
+    let subtest_reasons = test
+        .subtests
+        .values()
+        .map(|subtest| {
+            subtest
+                .properties
+                .disabled_reason_on(Platform::Linux, BuildProfile::Optimized)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        subtest_reasons,
+        [
+            Some(DisabledReason::Given("flaky on every platform".into())),
+            Some(DisabledReason::Unspecified)
+        ]
+    );
+}
+
+#[test]
+fn test_props_disable_on() {
+    let parse = |contents| File::parser().parse(contents).into_result().unwrap();
+    let mut file = parse(concat!(
+        "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+        "  disabled:\n",
+        "    if os == \"win\" and debug: https://bugzilla.mozilla.org/show_bug.cgi?id=1234567\n",
+        "\n",
+        "[cts.https.html?q=webgpu:api,operation,buffers:*]\n",
+        "  expected: CRASH\n",
+    ));
+    let crashing = [
+        (Platform::Windows, BuildProfile::Debug),
+        (Platform::Windows, BuildProfile::Optimized),
+        (Platform::Linux, BuildProfile::Debug),
+        (Platform::MacOs, BuildProfile::Debug),
+        (Platform::MacOs, BuildProfile::Optimized),
+    ];
+    for test in file.tests.values_mut() {
+        assert!(test
+            .properties
+            .disable_on(crashing, "https://example.com/bug"));
+        assert!(!test
+            .properties
+            .disable_on(crashing, "https://example.com/bug"));
+    }
+    insta::assert_snapshot!(format_file(&file), @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      disabled:
+        if os == "win" and debug: https://bugzilla.mozilla.org/show_bug.cgi?id=1234567
+        if os == "win" and not debug: https://example.com/bug
+        if os == "linux" and debug: https://example.com/bug
+        if os == "mac": https://example.com/bug
+
+
+    [cts.https.html?q=webgpu:api,operation,buffers:*]
+      disabled:
+        if os == "win": https://example.com/bug
+        if os == "linux" and debug: https://example.com/bug
+        if os == "mac": https://example.com/bug
+      expected: CRASH
+    "###);
+}
+
+#[test]
+fn written_conditions_read_back() {
+    let parse = |contents| File::parser().parse(contents).into_result().unwrap();
+    let intended = parse(concat!(
+        "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+        "  [:]\n",
+        "    expected:\n",
+        "      if fission and webgpu_backend == \"vulkan\" and os == \"win\" and debug: FAIL\n",
+        "      if os == \"linux\": [PASS, FAIL]\n",
+    ));
+    let written = format_file(&intended).to_string();
+    let read_back = parse(&written);
+    assert_eq!(condition_mismatches(&intended, &read_back), []);
+
+    let collapsed = parse(concat!(
+        "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+        "  [:]\n",
+        "    expected:\n",
+        "      if os == \"win\" and debug: FAIL\n",
+        "      if os == \"linux\": [PASS, FAIL]\n",
+    ));
+    let mismatches = condition_mismatches(&intended, &collapsed)
+        .iter()
+        .map(|mismatch| mismatch.to_string())
+        .join_with("\n")
+        .to_string();
+    insta::assert_snapshot!(mismatches, @r###"
+    `[cts.https.html?q=webgpu:api,operation,adapter:*]` › `[:]` (os == "win" and debug): intended `PASS`, read back as `FAIL`
+    "###);
+}
+
+#[test]
+fn unrecognized_conditions_round_trip() {
+    let parse = |contents| File::parser().parse(contents).into_result().unwrap();
+    let file = parse(concat!(
+        "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+        "  expected:\n",
+        "    if os == \"win\": TIMEOUT\n",
+        "    if not (os == \"mac\"): CRASH\n",
+        "    if os == \"freebsd\" and debug: ERROR\n",
+        "  [:]\n",
+        "    expected:\n",
+        "      if os == \"ios\": FAIL\n",
+        "\n",
+        "[cts.https.html?q=webgpu:api,operation,buffers:*]\n",
+        "  expected:\n",
+        "    if os == \"win\": TIMEOUT\n",
+    ));
+    let mut tests = file.tests.values();
+    let test = tests.next().unwrap();
+    assert!(test.properties.verbatim_expected.is_some());
+    assert_eq!(
+        test.properties.expected_for(&Default::default()).unwrap()
+            [(Platform::Windows, BuildProfile::Debug)],
+        Expected::permanent(TestOutcome::Timeout)
+    );
+    let subtest = test.subtests.values().next().unwrap();
+    assert!(subtest.properties.verbatim_expected.is_some());
+    assert!(tests.next().unwrap().properties.verbatim_expected.is_none());
+
+    // N.B.: Reordering the `not (…)` condition would change what Windows runs resolve to.
+    let written = format_file(&file).to_string();
+    insta::assert_snapshot!(written, @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      expected:
+        if os == "win": TIMEOUT
+        if not (os == "mac"): CRASH
+        if os == "freebsd" and debug: ERROR
+      [:]
+        expected:
+          if os == "ios": FAIL
+
+
+    [cts.https.html?q=webgpu:api,operation,buffers:*]
+      expected:
+        if os == "win": TIMEOUT
+    "###);
+    assert_eq!(format_file(&parse(&written)).to_string(), written);
+}
+
+#[test]
+fn dir_file_round_trip() {
+    let contents = concat!(
+        "prefs: [dom.webgpu.enabled:true]\n",
+        "bug: https://bugzilla.mozilla.org/show_bug.cgi?id=1234567\n",
+        "expected:\n",
+        "  if os == \"win\": CRASH\n",
+        "lsan-allowed: [Alloc, Create]\n",
+        "leak-threshold:\n",
+        "  if os == \"mac\": [tab:51200]\n",
+    );
+    let file = DirFile::parser().parse(contents).into_result().unwrap();
+    let DirProps {
+        file: _,
+        test,
+        other,
+    } = &file.properties;
+    assert_eq!(
+        test.expected.as_ref().unwrap()[(Platform::Windows, BuildProfile::Optimized)],
+        Expected::permanent(TestOutcome::Crash)
+    );
+    assert_eq!(
+        other.iter().map(|(name, _val)| &**name).collect::<Vec<_>>(),
+        ["lsan-allowed", "leak-threshold"]
+    );
+    assert_eq!(format_dir_file(&file).to_string(), contents);
+
+    assert!(DirFile::parser()
+        .parse("[cts.https.html]\n  expected: CRASH\n")
+        .into_result()
+        .is_err());
+}
+
+#[test]
+fn json_round_trip() {
+    let contents = concat!(
+        "prefs: [dom.webgpu.enabled:true]\n",
+        "tags: [webgpu]\n",
+        "\n",
+        "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+        "  disabled:\n",
+        "    if os == \"mac\": https://bugzilla.mozilla.org/show_bug.cgi?id=1234567\n",
+        "  disabled-until: 2024-06-01\n",
+        "  quarantined: 2024-05-01\n",
+        "  expected:\n",
+        "    if os == \"win\": [OK, CRASH]\n",
+        "  [:]\n",
+        "    expected:\n",
+        "      if fission and os == \"linux\": FAIL\n",
+        "      if os == \"win\" and debug: [PASS, TIMEOUT]\n",
+    );
+    let file = File::parser().parse(contents).into_result().unwrap();
+    let json = serde_json::to_string(&file).unwrap();
+    let read_back = serde_json::from_str::<File>(&json).unwrap();
+    assert_eq!(
+        format_file(&read_back).to_string(),
+        format_file(&file).to_string()
+    );
+}
+
 #[derive(Clone, Debug)]
 pub enum FileProp {
     Prefs(PropertyValue<Expr<Value<'static>>, Vec<(String, String)>>),
@@ -457,67 +953,71 @@ pub enum FileProp {
     ImplementationStatus(PropertyValue<Expr<Value<'static>>, ImplementationStatus>),
 }
 
-fn format_file_properties(props: &FileProps) -> impl Display + '_ {
-    fn write_prop_val<'a, V>(
-        prop_name: &'a str,
-        val: &'a PropertyValue<Expr<Value>, V>,
-        disp_rhs: impl Fn(&V, &mut Formatter<'_>) -> fmt::Result + 'a,
-        f: &mut Formatter<'_>,
-    ) -> fmt::Result {
-        fn disp_condition(cond: &Expr<Value<'_>>, f: &mut Formatter<'_>) -> fmt::Result {
-            match cond {
-                Expr::Value(val) => match val {
-                    Value::Variable(var) => write!(f, "{var}"),
-                    Value::Literal(lit) => match lit {
-                        Literal::String(s) => write!(f, "{s:?}"),
-                    },
+/// Writes a property named `prop_name` with value `val`, whose right-hand sides are written with
+/// `disp_rhs`. Lines are prefixed by `indent`.
+fn write_prop_val<'a, V>(
+    indent: &'a str,
+    prop_name: &'a str,
+    val: &'a PropertyValue<Expr<Value>, V>,
+    disp_rhs: impl Fn(&V, &mut Formatter<'_>) -> fmt::Result + 'a,
+    f: &mut Formatter<'_>,
+) -> fmt::Result {
+    fn disp_condition(cond: &Expr<Value<'_>>, f: &mut Formatter<'_>) -> fmt::Result {
+        match cond {
+            Expr::Value(val) => match val {
+                Value::Variable(var) => write!(f, "{var}"),
+                Value::Literal(lit) => match lit {
+                    Literal::String(s) => write!(f, "{s:?}"),
                 },
-                Expr::And(lhs, rhs) => {
-                    disp_condition(lhs, f)?;
-                    write!(f, " and ")?;
-                    disp_condition(rhs, f)
-                }
-                Expr::Not(cond) => {
-                    write!(f, "not ")?;
-                    disp_condition(cond, f)
-                }
-                // TODO: almost certainly not gonna be correct with precedence rules. Eek!
-                Expr::Eq(rhs, lhs) => {
-                    disp_condition(rhs, f)?;
-                    write!(f, " == ")?;
-                    disp_condition(lhs, f)
-                }
+            },
+            Expr::And(lhs, rhs) => {
+                disp_condition(lhs, f)?;
+                write!(f, " and ")?;
+                disp_condition(rhs, f)
+            }
+            Expr::Not(cond) => {
+                write!(f, "not ")?;
+                disp_condition(cond, f)
+            }
+            // TODO: almost certainly not gonna be correct with precedence rules. Eek!
+            Expr::Eq(rhs, lhs) => {
+                disp_condition(rhs, f)?;
+                write!(f, " == ")?;
+                disp_condition(lhs, f)
             }
         }
+    }
 
-        write!(f, "{prop_name}:")?;
-        match val {
-            PropertyValue::Unconditional(val) => {
-                write!(f, " ")?;
-                disp_rhs(val, f)?;
+    write!(f, "{indent}{prop_name}:")?;
+    match val {
+        PropertyValue::Unconditional(val) => {
+            write!(f, " ")?;
+            disp_rhs(val, f)?;
+            writeln!(f)?;
+        }
+        PropertyValue::Conditional(ConditionalValue {
+            conditions,
+            fallback,
+        }) => {
+            writeln!(f)?;
+            for (condition, rhs) in conditions {
+                write!(f, "{indent}  if ")?;
+                disp_condition(condition, f)?;
+                write!(f, ": ")?;
+                disp_rhs(rhs, f)?;
                 writeln!(f)?;
             }
-            PropertyValue::Conditional(ConditionalValue {
-                conditions,
-                fallback,
-            }) => {
+            if let Some(fallback) = fallback {
+                write!(f, "{indent}  ")?;
+                disp_rhs(fallback, f)?;
                 writeln!(f)?;
-                for (condition, rhs) in conditions {
-                    write!(f, "  if ")?;
-                    disp_condition(condition, f)?;
-                    write!(f, ": ")?;
-                    disp_rhs(rhs, f)?;
-                    writeln!(f)?;
-                }
-                if let Some(fallback) = fallback {
-                    write!(f, "  ")?;
-                    disp_rhs(fallback, f)?;
-                    writeln!(f)?;
-                }
             }
         }
-        Ok(())
     }
+    Ok(())
+}
+
+fn format_file_properties(props: &FileProps) -> impl Display + '_ {
     lazy_format!(|f| {
         let FileProps {
             implementation_status,
@@ -528,6 +1028,7 @@ fn format_file_properties(props: &FileProps) -> impl Display + '_ {
 
         if let Some(implementation_status) = implementation_status {
             write_prop_val(
+                "",
                 "implementation-status",
                 implementation_status,
                 Display::fmt,
@@ -537,6 +1038,7 @@ fn format_file_properties(props: &FileProps) -> impl Display + '_ {
 
         if let Some(prefs) = prefs {
             write_prop_val(
+                "",
                 "prefs",
                 prefs,
                 |prefs: &Vec<_>, f| {
@@ -552,6 +1054,7 @@ fn format_file_properties(props: &FileProps) -> impl Display + '_ {
 
         if let Some(tags) = tags {
             write_prop_val(
+                "",
                 "tags",
                 tags,
                 |tags: &Vec<_>, f| write!(f, "[{}]", tags.iter().join_with(", ")),
@@ -560,14 +1063,14 @@ fn format_file_properties(props: &FileProps) -> impl Display + '_ {
         }
 
         if let Some(is_disabled) = is_disabled {
-            write_prop_val("disabled", is_disabled, Display::fmt, f)?;
+            write_prop_val("", "disabled", is_disabled, Display::fmt, f)?;
         }
 
         Ok(())
     })
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
 pub enum ImplementationStatus {
     /// Indicates that functionality governing test(s) is implemented or currently being
     /// implemented, and generally expected to conform to tests.
@@ -616,7 +1119,7 @@ impl<'a> metadata::Tests<'a> for Tests {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Test {
     pub properties: TestProps<TestOutcome>,
     pub subtests: BTreeMap<SectionHeader, Subtest>,
@@ -663,7 +1166,7 @@ impl<'a> metadata::Subtests<'a> for Subtests {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Subtest {
     pub properties: TestProps<SubtestOutcome>,
 }
@@ -676,19 +1179,40 @@ impl<'a> metadata::Subtest<'a> for Subtest {
     }
 }
 
+/// Formats `file` as it would be written to disk, with sections in the order of the current
+/// [`collation`].
 pub fn format_file(file: &File) -> impl Display + '_ {
     lazy_format!(|f| {
         let File { properties, tests } = file;
         let properties = format_file_properties(properties);
-        let tests = tests
-            .iter()
+        let tests = collated(tests)
             .map(|(name, test)| format_test(name, test))
             .join_with("\n\n");
         write!(f, "{properties}{tests}")
     })
 }
 
-fn format_test<'a>(name: &'a SectionHeader, test: &'a Test) -> impl Display + 'a {
+/// Formats `file`, a directory metadata file, as it would be written to disk.
+pub fn format_dir_file(file: &DirFile) -> impl Display + '_ {
+    lazy_format!(|f| {
+        let DirFile {
+            properties: DirProps { file, test, other },
+        } = file;
+        write!(
+            f,
+            "{}{}",
+            format_file_properties(file),
+            format_test_properties(0, test)
+        )?;
+        for (name, val) in other {
+            write_prop_val("", name, val, Display::fmt, f)?;
+        }
+        Ok(())
+    })
+}
+
+/// Formats a single test section, as it appears in the output of [`format_file`].
+pub fn format_test<'a>(name: &'a SectionHeader, test: &'a Test) -> impl Display + 'a {
     lazy_format!(|f| {
         let Test {
             subtests,
@@ -700,8 +1224,7 @@ fn format_test<'a>(name: &'a SectionHeader, test: &'a Test) -> impl Display + 'a
             "[{}]\n{}{}",
             name.escaped(),
             format_test_properties(1, properties),
-            subtests
-                .iter()
+            collated(subtests)
                 .map(|(name, subtest)| {
                     let Subtest { properties } = subtest;
                     lazy_format!(move |f| write!(
@@ -716,6 +1239,153 @@ fn format_test<'a>(name: &'a SectionHeader, test: &'a Test) -> impl Display + 'a
     })
 }
 
+/// A test or subtest whose expected outcomes in some configuration differ between the metadata
+/// intended to be written and that metadata as read back, like `wptrunner` would read it: the
+/// first matching condition wins, falling back to the unconditional value, and then to the
+/// default outcome. See [`condition_mismatches`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConditionMismatch {
+    pub test: SectionHeader,
+    pub subtest: Option<SectionHeader>,
+    pub variant: RunInfoVariant,
+    pub platform: Platform,
+    pub build_profile: BuildProfile,
+    /// The intended outcomes, as they would be written in metadata.
+    pub intended: String,
+    /// Like `intended`, but as read back.
+    pub read_back: String,
+}
+
+impl Display for ConditionMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            test,
+            subtest,
+            variant,
+            platform,
+            build_profile,
+            intended,
+            read_back,
+        } = self;
+        write!(f, "`[{}]`", test.0)?;
+        if let Some(subtest) = subtest {
+            write!(f, " › `[{}]`", subtest.0)?;
+        }
+        write!(f, " (")?;
+        if !variant.is_empty() {
+            write!(f, "{variant} and ")?;
+        }
+        let platform = platform.os_name();
+        let build_profile = match build_profile {
+            BuildProfile::Debug => "debug",
+            BuildProfile::Optimized => "not debug",
+        };
+        write!(
+            f,
+            "os == {platform:?} and {build_profile}): intended `{intended}`, read back as \
+            `{read_back}`"
+        )
+    }
+}
+
+/// Compares expected outcomes of every test and subtest in `intended` against those in
+/// `read_back`, which should be `intended` written with [`format_file`] and parsed again, in
+/// every configuration and [`RunInfoVariant`] that either distinguishes. This catches conditions
+/// that were collapsed into something that `wptrunner` would evaluate differently.
+pub fn condition_mismatches(intended: &File, read_back: &File) -> Vec<ConditionMismatch> {
+    fn compare<Out>(
+        intended: &TestProps<Out>,
+        read_back: Option<&TestProps<Out>>,
+        mut f: impl FnMut(RunInfoVariant, Platform, BuildProfile, String, String),
+    ) where
+        Out: Default + Display + EnumSetType,
+    {
+        let default_props = TestProps::default();
+        let read_back = read_back.unwrap_or(&default_props);
+        let variants = [RunInfoVariant::default()]
+            .into_iter()
+            .chain(intended.expected_by_variant.keys().cloned())
+            .chain(read_back.expected_by_variant.keys().cloned())
+            .collect::<BTreeSet<_>>();
+        for variant in variants {
+            let intended_exps = intended.expected_for(&variant).unwrap_or_default();
+            let read_back_exps = read_back.expected_for(&variant).unwrap_or_default();
+            for ((platform, build_profile), intended) in intended_exps.iter() {
+                let read_back = read_back_exps[(platform, build_profile)];
+                if intended != read_back {
+                    f(
+                        variant.clone(),
+                        platform,
+                        build_profile,
+                        intended.to_string(),
+                        read_back.to_string(),
+                    );
+                }
+            }
+        }
+    }
+
+    let mut mismatches = Vec::new();
+    for (test_name, test) in &intended.tests {
+        let read_back_test = read_back.tests.get(test_name);
+        let mut record = |subtest: Option<&SectionHeader>,
+                          variant,
+                          platform,
+                          build_profile,
+                          intended,
+                          read_back| {
+            mismatches.push(ConditionMismatch {
+                test: test_name.clone(),
+                subtest: subtest.cloned(),
+                variant,
+                platform,
+                build_profile,
+                intended,
+                read_back,
+            })
+        };
+        compare(
+            &test.properties,
+            read_back_test.map(|test| &test.properties),
+            |variant, platform, build_profile, intended, read_back| {
+                record(None, variant, platform, build_profile, intended, read_back)
+            },
+        );
+        for (subtest_name, subtest) in &test.subtests {
+            compare(
+                &subtest.properties,
+                read_back_test
+                    .and_then(|test| test.subtests.get(subtest_name))
+                    .map(|subtest| &subtest.properties),
+                |variant, platform, build_profile, intended, read_back| {
+                    record(
+                        Some(subtest_name),
+                        variant,
+                        platform,
+                        build_profile,
+                        intended,
+                        read_back,
+                    )
+                },
+            );
+        }
+    }
+    mismatches
+}
+
+/// Iterates over `sections` in the order of the current [`collation`].
+fn collated<T>(
+    sections: &BTreeMap<SectionHeader, T>,
+) -> impl Iterator<Item = (&SectionHeader, &T)> + Clone {
+    let collation = collation::current();
+    let mut sections = sections.iter().collect::<Vec<_>>();
+    // N.B.: `BTreeMap`s are already in the order of `Collation::Bytes`.
+    if collation != Collation::Bytes {
+        sections.sort_by(|(a, _), (b, _)| collation.compare(&a.0, &b.0));
+    }
+    sections.into_iter()
+}
+
 fn format_test_properties<Out>(indentation: u8, property: &TestProps<Out>) -> impl Display + '_
 where
     Out: Default + Display + EnumSetType + Eq + PartialEq,
@@ -730,77 +1400,163 @@ where
         ));
         let TestProps {
             is_disabled,
+            disabled_until,
+            keep_expectations,
+            quarantined,
+            bug,
             expected,
+            expected_by_variant,
+            verbatim_expected,
         } = property;
 
-        if *is_disabled {
-            writeln!(f, "{indent}disabled: true")?;
+        if let Some(is_disabled) = is_disabled {
+            write_prop_val(
+                &indent.to_string(),
+                "disabled",
+                is_disabled,
+                Display::fmt,
+                f,
+            )?;
         }
 
-        if let Some(exps) = expected {
-            fn if_not_default<Out>(
-                exp: &Expected<Out>,
-                f: impl FnOnce() -> fmt::Result,
-            ) -> fmt::Result
-            where
-                Out: Default + EnumSetType + Eq + PartialEq,
-            {
-                if exp != &Default::default() {
-                    f()
-                } else {
-                    Ok(())
-                }
+        if let Some(disabled_until) = disabled_until {
+            writeln!(f, "{indent}disabled-until: {disabled_until}")?;
+        }
+
+        if *keep_expectations {
+            writeln!(f, "{indent}keep-expectations: true")?;
+        }
+
+        if let Some(quarantined) = quarantined {
+            writeln!(f, "{indent}quarantined: {quarantined}")?;
+        }
+
+        if let Some(bug) = bug {
+            writeln!(f, "{indent}bug: {bug}")?;
+        }
+
+        let expected_key = lazy_format!("{indent}expected");
+        let r#if = lazy_format!("{indent}  if");
+
+        if let Some(ConditionalValue {
+            conditions,
+            fallback,
+        }) = verbatim_expected
+        {
+            writeln!(f, "{expected_key}:")?;
+            for (condition, exps) in conditions {
+                writeln!(f, "{if} {condition}: {exps}")?;
+            }
+            if let Some(fallback) = fallback {
+                writeln!(f, "{indent}  {fallback}")?;
             }
+            return Ok(());
+        }
 
-            let expected = lazy_format!("{indent}expected");
-            let r#if = lazy_format!("{indent}  if");
-            let disp_build_profile = |build_profile| match build_profile {
-                BuildProfile::Debug => "debug",
-                BuildProfile::Optimized => "not debug",
-            };
-            let exps = NormalizedExpectedPropertyValue::from_fully_expanded(*exps);
-            match exps.inner() {
-                MaybeCollapsed::Collapsed(exps) => match exps {
-                    MaybeCollapsed::Collapsed(exps) => {
-                        if_not_default(exps, || writeln!(f, "{expected}: {exps}"))?;
+        if expected.is_none() && expected_by_variant.is_empty() {
+            return Ok(());
+        }
+
+        let write_conditions =
+            |f: &mut Formatter<'_>,
+             variant: Option<&RunInfoVariant>,
+             exps: &NormalizedExpectedPropertyValue<Out>,
+             fallthrough: &FullyExpandedExpectedPropertyValue<Out>| {
+                let mut leaves = Vec::new();
+                match exps.inner() {
+                    MaybeCollapsed::Collapsed(MaybeCollapsed::Collapsed(exps)) => {
+                        leaves.push((None, None, exps))
                     }
-                    MaybeCollapsed::Expanded(by_build_profile) => {
-                        writeln!(f, "{expected}:")?;
+                    MaybeCollapsed::Collapsed(MaybeCollapsed::Expanded(by_build_profile)) => {
                         debug_assert!(!by_build_profile.is_empty());
-                        for (build_profile, exps) in by_build_profile {
-                            let build_profile = disp_build_profile(*build_profile);
-                            if_not_default(exps, || writeln!(f, "{if} {build_profile}: {exps}"))?;
-                        }
+                        leaves.extend(
+                            by_build_profile
+                                .iter()
+                                .map(|(bp, exps)| (None, Some(bp), exps)),
+                        )
                     }
-                },
-                MaybeCollapsed::Expanded(by_platform) => {
-                    writeln!(f, "{expected}:")?;
-                    debug_assert!(!by_platform.is_empty());
-                    for (platform, exps) in by_platform {
-                        let platform = {
-                            let platform_str = match platform {
-                                Platform::Windows => "win",
-                                Platform::Linux => "linux",
-                                Platform::MacOs => "mac",
-                            };
-                            lazy_format!(move |f| write!(f, "os == {platform_str:?}"))
-                        };
-                        match exps {
-                            MaybeCollapsed::Collapsed(exps) => {
-                                if_not_default(exps, || writeln!(f, "{if} {platform}: {exps}"))?
-                            }
-                            MaybeCollapsed::Expanded(by_build_profile) => {
-                                debug_assert!(!by_build_profile.is_empty());
-                                for (build_profile, exps) in by_build_profile {
-                                    let build_profile = disp_build_profile(*build_profile);
-                                    if_not_default(exps, || {
-                                        writeln!(f, "{if} {platform} and {build_profile}: {exps}")
-                                    })?;
+                    MaybeCollapsed::Expanded(by_platform) => {
+                        debug_assert!(!by_platform.is_empty());
+                        for (platform, exps) in by_platform {
+                            match exps {
+                                MaybeCollapsed::Collapsed(exps) => {
+                                    leaves.push((Some(platform), None, exps))
+                                }
+                                MaybeCollapsed::Expanded(by_build_profile) => {
+                                    debug_assert!(!by_build_profile.is_empty());
+                                    leaves.extend(
+                                        by_build_profile
+                                            .iter()
+                                            .map(|(bp, exps)| (Some(platform), Some(bp), exps)),
+                                    )
                                 }
                             }
                         }
                     }
                 }
+                for (platform, build_profile, exps) in leaves {
+                    let redundant = fallthrough
+                        .iter()
+                        .filter(|((p, bp), _exps)| {
+                            platform.map_or(true, |p2| p2 == p)
+                                && build_profile.map_or(true, |bp2| bp2 == bp)
+                        })
+                        .all(|(_config, fallthrough)| fallthrough == *exps);
+                    if redundant {
+                        continue;
+                    }
+                    let variant = variant.map(|variant| variant.to_string());
+                    let platform =
+                        platform.map(|platform| format!("os == {:?}", platform.os_name()));
+                    let build_profile = build_profile.map(|build_profile| match build_profile {
+                        BuildProfile::Debug => "debug".to_owned(),
+                        BuildProfile::Optimized => "not debug".to_owned(),
+                    });
+                    let condition = [variant, platform, build_profile]
+                        .into_iter()
+                        .flatten()
+                        .join_with(" and ");
+                    writeln!(f, "{if} {condition}: {exps}")?;
+                }
+                Ok(())
+            };
+
+        let default_exps = FullyExpandedExpectedPropertyValue::default();
+        let exps = expected.map(NormalizedExpectedPropertyValue::from_fully_expanded);
+        if expected_by_variant.is_empty() {
+            if let Some(MaybeCollapsed::Collapsed(MaybeCollapsed::Collapsed(exps))) =
+                exps.as_ref().map(|exps| exps.inner())
+            {
+                if exps != &Default::default() {
+                    writeln!(f, "{expected_key}: {exps}")?;
+                }
+                return Ok(());
+            }
+        }
+
+        writeln!(f, "{expected_key}:")?;
+        // N.B.: The first matching condition wins, so variant-specific conditions must come
+        // before those of less specific variants, which they fall through to.
+        let mut variants = expected_by_variant.iter().collect::<Vec<_>>();
+        variants.sort_by_key(|(variant, _exps)| Reverse(variant.0.len()));
+        for (variant, variant_exps) in variants {
+            let variant_exps = NormalizedExpectedPropertyValue::from_fully_expanded(*variant_exps);
+            let fallthrough = TestProps::expected_with_fewer_keys(
+                *expected,
+                expected_by_variant,
+                variant,
+                variant.0.len(),
+            )
+            .unwrap_or_default();
+            write_conditions(f, Some(variant), &variant_exps, &fallthrough)?;
+        }
+        if let Some(exps) = &exps {
+            if let MaybeCollapsed::Collapsed(MaybeCollapsed::Collapsed(exps)) = exps.inner() {
+                if exps != &Default::default() {
+                    writeln!(f, "{indent}  {exps}")?;
+                }
+            } else {
+                write_conditions(f, None, exps, &default_exps)?;
             }
         }
 
@@ -808,26 +1564,160 @@ where
     })
 }
 
-#[derive(Clone, Copy, Debug, Enum, EnumIter, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(
+    Clone, Copy, Debug, Deserialize, Enum, EnumIter, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
 pub enum Platform {
+    #[serde(rename = "win")]
     Windows,
+    #[serde(rename = "linux")]
     Linux,
+    #[serde(rename = "mac")]
     MacOs,
+    /// GeckoView, on Android.
+    #[serde(rename = "android")]
+    Android,
+}
+
+impl Platform {
+    /// The value of `os` in `run_info`, and in conditions of metadata, for this platform; the
+    /// inverse of [`Platform::from_str`].
+    pub fn os_name(self) -> &'static str {
+        match self {
+            Self::Windows => "win",
+            Self::Linux => "linux",
+            Self::MacOs => "mac",
+            Self::Android => "android",
+        }
+    }
+
+    /// The name of this platform in prose (i.e., `macOS`).
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Windows => "Windows",
+            Self::Linux => "Linux",
+            Self::MacOs => "macOS",
+            Self::Android => "Android",
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, Enum, EnumIter, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// Parsed from the values of `os` in `run_info`: one of `win`, `linux`, `mac`, or `android`. The
+/// longer names `windows` and `macos` are accepted too, since they're what's often typed on the
+/// command line.
+impl FromStr for Platform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "win" | "windows" => Ok(Self::Windows),
+            "linux" => Ok(Self::Linux),
+            "mac" | "macos" => Ok(Self::MacOs),
+            "android" => Ok(Self::Android),
+            other => Err(format!(
+                concat!(
+                    "unrecognized platform {:?}; ",
+                    "expected one of `win` (or `windows`), `linux`, `mac` (or `macos`), ",
+                    "or `android`"
+                ),
+                other
+            )),
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Deserialize, Enum, EnumIter, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
 pub enum BuildProfile {
     Debug,
     Optimized,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct TestProps<Out>
-where
-    Out: EnumSetType,
-{
-    pub is_disabled: bool,
-    pub expected: Option<FullyExpandedExpectedPropertyValue<Out>>,
+impl BuildProfile {
+    /// The name of this build profile (i.e., `opt`); the inverse of [`BuildProfile::from_str`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Optimized => "opt",
+        }
+    }
+}
+
+impl FromStr for BuildProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "debug" => Ok(Self::Debug),
+            "opt" => Ok(Self::Optimized),
+            other => Err(format!(
+                "unrecognized build profile {other:?}; expected one of `debug` or `opt`"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TestProps<Out>
+where
+    Out: EnumSetType,
+{
+    /// Set by `disabled: …`, with the reason tests are disabled (i.e., `true`, or a bug URL), kept
+    /// as written. See [`TestProps::disabled_reason_on`].
+    pub is_disabled: Option<DisabledValue>,
+    /// Set by `disabled-until: YYYY-MM-DD`, alongside `disabled: true`, which marks a disable as
+    /// due for re-evaluation on the given date.
+    pub disabled_until: Option<NaiveDate>,
+    /// Set by `keep-expectations: true`, which marks a section as hand-curated, so that
+    /// `update-expected` never modifies it.
+    pub keep_expectations: bool,
+    /// Set by `quarantined: YYYY-MM-DD`, with the day a test was quarantined by `quarantine`.
+    /// Like [`TestProps::keep_expectations`], `update-expected` leaves quarantined tests
+    /// untouched; `triage` lists those due for review.
+    pub quarantined: Option<NaiveDate>,
+    /// Set by `bug: …`, usually a link to the bug tracking this section's expectations (i.e.,
+    /// `https://bugzilla.mozilla.org/show_bug.cgi?id=1234567`). Kept as written.
+    pub bug: Option<String>,
+    pub expected: Option<FullyExpandedExpectedPropertyValue<Out>>,
+    /// Expected outcomes for runs in a [`RunInfoVariant`], where they differ from those the
+    /// variant falls through to (see [`TestProps::expected_for`]).
+    #[serde(with = "entries")]
+    pub expected_by_variant: BTreeMap<RunInfoVariant, FullyExpandedExpectedPropertyValue<Out>>,
+    /// Set by a conditional `expected: …` with conditions that this tool can't resolve (i.e., on
+    /// an `os` besides those of [`Platform`], or `not (…)`), with all of its conditions as
+    /// written, in order. It's written back as it is, instead of [`Self::expected`] and
+    /// [`Self::expected_by_variant`], which only approximate it as if unresolved conditions
+    /// applied to no runs, and `update-expected` leaves the section's expectations untouched.
+    #[serde(default)]
+    pub verbatim_expected: Option<ConditionalValue<String, Expected<Out>>>,
+}
+
+/// (De)serializes a map as a sequence of entries, for maps with keys that JSON can't use as object
+/// keys (i.e., [`RunInfoVariant`]s).
+mod entries {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(map: &BTreeMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<BTreeMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Ord,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::<(K, V)>::deserialize(deserializer).map(|entries| entries.into_iter().collect())
+    }
 }
 
 impl<Out> Default for TestProps<Out>
@@ -836,9 +1726,180 @@ where
 {
     fn default() -> Self {
         Self {
-            is_disabled: false,
+            is_disabled: None,
+            disabled_until: None,
+            keep_expectations: false,
+            quarantined: None,
+            bug: None,
             expected: None,
+            expected_by_variant: BTreeMap::new(),
+            verbatim_expected: None,
+        }
+    }
+}
+
+impl<Out> TestProps<Out>
+where
+    Out: Default + EnumSetType,
+{
+    /// Returns the reason given by `disabled` for runs on `platform` with `build_profile`, or
+    /// `None` if they aren't disabled; see [`disabled_reason_on`].
+    pub fn disabled_reason_on(
+        &self,
+        platform: Platform,
+        build_profile: BuildProfile,
+    ) -> Option<DisabledReason<'_>> {
+        disabled_reason_on(self.is_disabled.as_ref(), platform, build_profile)
+    }
+
+    pub fn is_disabled_on(&self, platform: Platform, build_profile: BuildProfile) -> bool {
+        self.disabled_reason_on(platform, build_profile).is_some()
+    }
+
+    /// Returns the expected outcomes of runs in `variant`: those of the most specific variant in
+    /// `expected_by_variant` whose conditions `variant` satisfies, or `expected` otherwise.
+    pub fn expected_for(
+        &self,
+        variant: &RunInfoVariant,
+    ) -> Option<FullyExpandedExpectedPropertyValue<Out>> {
+        Self::expected_with_fewer_keys(
+            self.expected,
+            &self.expected_by_variant,
+            variant,
+            usize::MAX,
+        )
+    }
+
+    /// Like [`Self::expected_for`], but only considers variants with fewer than `max_keys` keys.
+    fn expected_with_fewer_keys(
+        expected: Option<FullyExpandedExpectedPropertyValue<Out>>,
+        expected_by_variant: &BTreeMap<RunInfoVariant, FullyExpandedExpectedPropertyValue<Out>>,
+        variant: &RunInfoVariant,
+        max_keys: usize,
+    ) -> Option<FullyExpandedExpectedPropertyValue<Out>> {
+        expected_by_variant
+            .iter()
+            .filter(|(conditions, _exps)| {
+                conditions.0.len() < max_keys && variant.satisfies(conditions)
+            })
+            .max_by_key(|(conditions, _exps)| conditions.0.len())
+            .map(|(_conditions, exps)| *exps)
+            .or(expected)
+    }
+
+    /// Removes entries of `expected_by_variant` that are the same as what their variant would
+    /// fall through to without them.
+    pub fn prune_redundant_variants(&mut self) {
+        Self::prune_variants(self.expected, &mut self.expected_by_variant);
+    }
+
+    fn prune_variants(
+        expected: Option<FullyExpandedExpectedPropertyValue<Out>>,
+        expected_by_variant: &mut BTreeMap<RunInfoVariant, FullyExpandedExpectedPropertyValue<Out>>,
+    ) {
+        // N.B.: Less specific variants are pruned first, since more specific ones may fall
+        // through to them.
+        let mut variants = expected_by_variant.keys().cloned().collect::<Vec<_>>();
+        variants.sort_by_key(|variant| variant.0.len());
+        for variant in variants {
+            let fallthrough = Self::expected_with_fewer_keys(
+                expected,
+                expected_by_variant,
+                &variant,
+                variant.0.len(),
+            )
+            .unwrap_or_default();
+            if expected_by_variant[&variant] == fallthrough {
+                expected_by_variant.remove(&variant);
+            }
+        }
+    }
+
+    /// Disables runs in `configs` that aren't disabled already with `reason` (i.e., a bug URL),
+    /// by adding a condition to `disabled` for each platform they're on, after any conditions it
+    /// has already. Returns whether any runs were disabled.
+    pub fn disable_on(
+        &mut self,
+        configs: impl IntoIterator<Item = (Platform, BuildProfile)>,
+        reason: &str,
+    ) -> bool {
+        let mut by_platform = BTreeMap::<Platform, BTreeSet<BuildProfile>>::new();
+        for (platform, build_profile) in configs {
+            if !self.is_disabled_on(platform, build_profile) {
+                by_platform
+                    .entry(platform)
+                    .or_default()
+                    .insert(build_profile);
+            }
+        }
+        if by_platform.is_empty() {
+            return false;
+        }
+
+        let var = |name: &'static str| Expr::Value(Value::Variable(name.into()));
+        let new_conditions = by_platform.into_iter().map(|(platform, build_profiles)| {
+            let os_eq = Expr::Eq(
+                Box::new(var("os")),
+                Box::new(Expr::Value(Value::Literal(Literal::String(
+                    platform.os_name().into(),
+                )))),
+            );
+            let build_profiles = Vec::from_iter(build_profiles);
+            let condition = match build_profiles[..] {
+                [BuildProfile::Debug] => Expr::And(Box::new(os_eq), Box::new(var("debug"))),
+                [BuildProfile::Optimized] => {
+                    Expr::And(Box::new(os_eq), Box::new(Expr::Not(Box::new(var("debug")))))
+                }
+                _ => os_eq,
+            };
+            (condition, reason.to_owned())
+        });
+        match &mut self.is_disabled {
+            Some(PropertyValue::Conditional(ConditionalValue { conditions, .. })) => {
+                conditions.extend(new_conditions)
+            }
+            // N.B.: An unconditional `disabled` would already disable every run.
+            Some(PropertyValue::Unconditional(_)) => unreachable!(),
+            is_disabled @ None => {
+                *is_disabled = Some(PropertyValue::Conditional(ConditionalValue {
+                    conditions: new_conditions.collect(),
+                    fallback: None,
+                }))
+            }
+        }
+        true
+    }
+
+    /// Removes the parts of `disabled` whose reason refers to bug `id` (see [`bug_id`]), leaving
+    /// runs disabled for other reasons disabled. If no part of `disabled` is left, `disabled-until`
+    /// is removed as well. Returns whether anything was removed.
+    pub fn enable_for_bug(&mut self, id: u64) -> bool {
+        let for_bug = |reason: &String| bug_id(reason) == Some(id);
+        let (removed, now_empty) = match &mut self.is_disabled {
+            None => (false, false),
+            Some(PropertyValue::Unconditional(reason)) => {
+                let removed = for_bug(reason);
+                (removed, removed)
+            }
+            Some(PropertyValue::Conditional(ConditionalValue {
+                conditions,
+                fallback,
+            })) => {
+                let num_conditions = conditions.len();
+                conditions.retain(|(_cond, reason)| !for_bug(reason));
+                let mut removed = conditions.len() != num_conditions;
+                if fallback.as_ref().is_some_and(for_bug) {
+                    *fallback = None;
+                    removed = true;
+                }
+                (removed, conditions.is_empty() && fallback.is_none())
+            }
+        };
+        if now_empty {
+            self.is_disabled = None;
+            self.disabled_until = None;
         }
+        removed
     }
 }
 
@@ -849,7 +1910,13 @@ where
     fn insert(&mut self, prop: TestProp<Out>, emitter: &mut Emitter<Rich<'a, char>>) {
         let Self {
             is_disabled,
+            disabled_until,
+            keep_expectations,
+            quarantined,
+            bug,
             expected,
+            expected_by_variant,
+            verbatim_expected,
         } = self;
 
         let TestProp { kind, span } = prop;
@@ -869,38 +1936,118 @@ where
                             conditions,
                             fallback,
                         } = val;
+                        if conditions
+                            .iter()
+                            .any(|(condition, _val)| condition.applicability.is_err())
+                        {
+                            *verbatim_expected = Some(ConditionalValue {
+                                conditions: conditions
+                                    .iter()
+                                    .map(|(condition, val)| (condition.source.clone(), *val))
+                                    .collect(),
+                                fallback,
+                            });
+                        }
+                        let conditions = conditions
+                            .into_iter()
+                            .filter_map(|(condition, val)| {
+                                Some((condition.applicability.ok()?, val))
+                            })
+                            .collect::<Vec<_>>();
                         if conditions.is_empty() {
-                            FullyExpandedExpectedPropertyValue::uniform(fallback.expect(concat!(
-                                "at least one condition or fallback not present ",
-                                "in conditional `expected` property value"
-                            )))
+                            // N.B.: There's no fallback if no conditions could be resolved.
+                            FullyExpandedExpectedPropertyValue::uniform(
+                                fallback.unwrap_or_default(),
+                            )
                         } else {
                             let fallback = fallback.unwrap_or_default();
-                            FullyExpandedExpectedPropertyValue::from_query(|p, bp| {
-                                let mut matched = None;
-
-                                for (applicability, val) in &conditions {
-                                    let Applicability {
-                                        platform,
-                                        build_profile,
-                                    } = applicability;
-                                    if platform.as_ref().map_or(true, |p2| *p2 == p)
-                                        && build_profile.as_ref().map_or(true, |bp2| *bp2 == bp)
+                            // N.B.: Like `wptrunner`, the first matching condition wins.
+                            let expand = |variant: &RunInfoVariant| {
+                                FullyExpandedExpectedPropertyValue::from_query(|p, bp| {
+                                    conditions
+                                        .iter()
+                                        .find(|(applicability, _val)| {
+                                            let Applicability {
+                                                platform,
+                                                build_profile,
+                                                run_info,
+                                            } = applicability;
+                                            platform.as_ref().map_or(true, |p2| *p2 == p)
+                                                && build_profile
+                                                    .as_ref()
+                                                    .map_or(true, |bp2| *bp2 == bp)
+                                                && variant.satisfies(run_info)
+                                        })
+                                        .map_or(fallback, |(_applicability, val)| *val)
+                                })
+                            };
+                            let default = expand(&RunInfoVariant::default());
+                            // N.B.: `not KEY` conditions also apply to runs without `KEY`, so
+                            // runs with `KEY` are expanded separately.
+                            for (applicability, _val) in &conditions {
+                                let run_info = &applicability.run_info;
+                                let negated = RunInfoVariant(
+                                    run_info
+                                        .0
+                                        .iter()
+                                        .map(|(key, val)| match val {
+                                            RunInfoValue::Bool(false) => {
+                                                (key.clone(), RunInfoValue::Bool(true))
+                                            }
+                                            val => (key.clone(), val.clone()),
+                                        })
+                                        .collect(),
+                                );
+                                for variant in [run_info, &negated] {
+                                    if !variant.is_empty()
+                                        && !expected_by_variant.contains_key(variant)
                                     {
-                                        matched = Some(*val);
+                                        expected_by_variant
+                                            .insert(variant.clone(), expand(variant));
                                     }
                                 }
-                                matched.unwrap_or(fallback)
-                            })
+                            }
+                            default
                         }
                     }
                 });
+                Self::prune_variants(*expected, expected_by_variant);
             }
-            TestPropKind::Disabled => {
-                if *is_disabled {
+            TestPropKind::Disabled(new_is_disabled) => {
+                if is_disabled.is_some() {
                     emitter.emit(Rich::custom(span, "duplicate `disabled` key detected"))
                 }
-                *is_disabled = true;
+                *is_disabled = Some(new_is_disabled);
+            }
+            TestPropKind::DisabledUntil(date) => {
+                if disabled_until.is_some() {
+                    emitter.emit(Rich::custom(
+                        span,
+                        "duplicate `disabled-until` key detected",
+                    ))
+                }
+                *disabled_until = Some(date);
+            }
+            TestPropKind::KeepExpectations => {
+                if *keep_expectations {
+                    emitter.emit(Rich::custom(
+                        span,
+                        "duplicate `keep-expectations` key detected",
+                    ))
+                }
+                *keep_expectations = true;
+            }
+            TestPropKind::Quarantined(date) => {
+                if quarantined.is_some() {
+                    emitter.emit(Rich::custom(span, "duplicate `quarantined` key detected"))
+                }
+                *quarantined = Some(date);
+            }
+            TestPropKind::Bug(new_bug) => {
+                if bug.is_some() {
+                    emitter.emit(Rich::custom(span, "duplicate `bug` key detected"))
+                }
+                *bug = Some(new_bug);
             }
         }
     }
@@ -910,6 +2057,66 @@ where
 pub struct Applicability {
     pub platform: Option<Platform>,
     pub build_profile: Option<BuildProfile>,
+    /// Conditions on other `run_info` keys, i.e., `subsuite == "webgpu-long"` or `not fission`.
+    pub run_info: RunInfoVariant,
+}
+
+/// A condition of a property as written (`source`), resolved to the runs it applies to, or to
+/// the reason it couldn't be, i.e., testing an `os` besides those of [`Platform`].
+#[derive(Clone, Debug)]
+struct Condition {
+    source: String,
+    applicability: Result<Applicability, String>,
+}
+
+/// The value of a `run_info` key that conditions may test, i.e., `true` for `fission`, or
+/// `"webgpu-long"` for `subsuite == "webgpu-long"`.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(untagged)]
+pub enum RunInfoValue {
+    Bool(bool),
+    String(String),
+}
+
+/// Values of `run_info` keys besides those that determine [`Platform`] and [`BuildProfile`],
+/// i.e., `subsuite`, `fission`, or `swgl`. Identifies runs whose expectations are kept separately
+/// in [`TestProps::expected_by_variant`], or, in an [`Applicability`], the runs that a condition
+/// applies to.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(transparent)]
+pub struct RunInfoVariant(pub BTreeMap<String, RunInfoValue>);
+
+impl RunInfoVariant {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns whether runs in this variant satisfy all of `conditions`. Like `wptrunner`, a key
+    /// missing from this variant satisfies `not KEY`.
+    pub fn satisfies(&self, conditions: &Self) -> bool {
+        conditions
+            .0
+            .iter()
+            .all(|(key, condition)| match (condition, self.0.get(key)) {
+                (RunInfoValue::Bool(false), value) => {
+                    value.map_or(true, |value| *value == RunInfoValue::Bool(false))
+                }
+                (condition, value) => value == Some(condition),
+            })
+    }
+}
+
+impl Display for RunInfoVariant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let conditions = self.0.iter().map(|(key, value)| {
+            lazy_format!(move |f| match value {
+                RunInfoValue::Bool(true) => write!(f, "{key}"),
+                RunInfoValue::Bool(false) => write!(f, "not {key}"),
+                RunInfoValue::String(value) => write!(f, "{key} == {value:?}"),
+            })
+        });
+        write!(f, "{}", conditions.join_with(" and "))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -926,8 +2133,12 @@ enum TestPropKind<Out>
 where
     Out: EnumSetType,
 {
-    Expected(PropertyValue<Applicability, Expected<Out>>),
-    Disabled,
+    Expected(PropertyValue<Condition, Expected<Out>>),
+    Disabled(DisabledValue),
+    DisabledUntil(NaiveDate),
+    KeepExpectations,
+    Quarantined(NaiveDate),
+    Bug(String),
 }
 
 impl<Out> TestProp<Out>
@@ -942,122 +2153,153 @@ where
         Out: Eq + Hash + PartialEq,
         P: Clone + Parser<'a, &'a str, Out, ParseError<'a>>,
     {
-        let conditional_term = Expr::parser(Value::parser()).validate(|prop_val, e, emitter| {
+        let condition = Expr::parser(Value::parser()).validate(|prop_val, e, emitter| {
             let mut acc = Applicability::default();
-            let try_match_var =
-                |acc: &mut Applicability, val: &_, inverted: bool, emitter: &mut Emitter<_>| {
-                    match val {
-                        Value::Variable(var_name) if var_name == "debug" => {
-                            let build_profile = if inverted {
-                                BuildProfile::Optimized
-                            } else {
-                                BuildProfile::Debug
-                            };
-                            if let Some(_old) = acc.build_profile.replace(build_profile) {
-                                emitter.emit(Rich::custom(
-                                    e.span(),
-                                    "multiple `debug` conditions specified, discarding oldest",
-                                ))
-                            }
+            // N.B.: Clauses that can't be resolved return why, so that the whole condition can be
+            // kept as written instead.
+            let try_match_var = |acc: &mut Applicability,
+                                 val: &_,
+                                 inverted: bool,
+                                 emitter: &mut Emitter<_>|
+             -> Result<(), String> {
+                match val {
+                    Value::Variable(var_name) if var_name == "debug" => {
+                        let build_profile = if inverted {
+                            BuildProfile::Optimized
+                        } else {
+                            BuildProfile::Debug
+                        };
+                        if let Some(_old) = acc.build_profile.replace(build_profile) {
+                            emitter.emit(Rich::custom(
+                                e.span(),
+                                "multiple `debug` conditions specified, discarding oldest",
+                            ))
                         }
-                        _ => {
+                    }
+                    Value::Variable(var_name) if var_name != "os" => {
+                        let value = RunInfoValue::Bool(!inverted);
+                        if let Some(_old) = acc.run_info.0.insert(var_name.to_string(), value) {
                             emitter.emit(Rich::custom(
                                 e.span(),
                                 format!(
-                                    concat!(
-                                        "{:?} is not a variable evaluatable with ",
-                                        "tests and subtests for WebGPU's purposes, discarding"
-                                    ),
-                                    e.slice()
+                                    "multiple `{var_name}` conditions specified, discarding \
+                                    oldest"
                                 ),
-                            ));
+                            ))
                         }
                     }
-                };
+                    _ => {
+                        return Err(format!(
+                            concat!(
+                                "{:?} is not a variable evaluatable with ",
+                                "tests and subtests for WebGPU's purposes, discarding"
+                            ),
+                            e.slice()
+                        ));
+                    }
+                }
+                Ok(())
+            };
 
-            let try_match_eq =
-                |acc: &mut Applicability, lhs: &_, rhs: &_, emitter: &mut Emitter<_>| match (
-                    lhs, rhs,
-                ) {
+            let try_match_eq = |acc: &mut Applicability,
+                                lhs: &_,
+                                rhs: &_,
+                                emitter: &mut Emitter<_>|
+             -> Result<(), String> {
+                match (lhs, rhs) {
                     (
                         Expr::Value(Value::Variable(var)),
                         Expr::Value(Value::Literal(Literal::String(lit))),
                     ) if var == "os" => {
                         let platform = match &**lit {
-                            "mac" => Some(Platform::MacOs),
-                            "linux" => Some(Platform::Linux),
-                            "win" => Some(Platform::Windows),
-                            _ => None,
-                        };
-                        if let Some(platform) = platform {
-                            if let Some(_old) = acc.platform.replace(platform) {
-                                emitter.emit(Rich::custom(
-                                    e.span(),
-                                    "multiple `os` conditions specified, discarding oldest",
-                                ))
-                            }
-                        } else {
-                            emitter.emit(Rich::custom(
-                                e.span(),
-                                format!(
+                            "mac" => Platform::MacOs,
+                            "linux" => Platform::Linux,
+                            "win" => Platform::Windows,
+                            "android" => Platform::Android,
+                            _ => {
+                                return Err(format!(
                                     "{lit:?}{}",
                                     concat!(
                                         " is not a platform that the WebGPU ",
                                         "team recognizes, discarding"
                                     )
-                                ),
+                                ))
+                            }
+                        };
+                        if let Some(_old) = acc.platform.replace(platform) {
+                            emitter.emit(Rich::custom(
+                                e.span(),
+                                "multiple `os` conditions specified, discarding oldest",
+                            ))
+                        }
+                    }
+                    (
+                        Expr::Value(Value::Variable(var)),
+                        Expr::Value(Value::Literal(Literal::String(lit))),
+                    ) => {
+                        let value = RunInfoValue::String(lit.to_string());
+                        if let Some(_old) = acc.run_info.0.insert(var.to_string(), value) {
+                            emitter.emit(Rich::custom(
+                                e.span(),
+                                format!("multiple `{var}` conditions specified, discarding oldest"),
                             ))
                         }
                     }
-                    _ => emitter.emit(Rich::custom(
-                        e.span(),
-                        format!(
+                    _ => {
+                        return Err(format!(
                             "{:?}{}",
                             concat!(
                                 " is not an comparison evaluatable ",
                                 "for WebGPU's purposes, discarding"
                             ),
                             e.slice()
-                        ),
-                    )),
-                };
-            match &prop_val {
-                Expr::Value(v) => try_match_var(&mut acc, v, false, emitter),
-                Expr::And(lhs, rhs) => {
-                    for term in [lhs, rhs] {
-                        match &**term {
-                            Expr::Value(v) => try_match_var(&mut acc, v, false, emitter),
-                            Expr::And(_, _) => emitter.emit(Rich::custom(
-                                e.span(),
-                                "`and` clauses too deep here, discarding",
-                            )),
-                            Expr::Not(term) => match &**term {
-                                Expr::Value(v) => try_match_var(&mut acc, v, true, emitter),
-                                _ => emitter.emit(Rich::custom(
-                                    e.span(),
-                                    "conditional clause inside `not` too deep here, discarding",
-                                )),
-                            },
-                            Expr::Eq(lhs, rhs) => try_match_eq(&mut acc, &**lhs, &**rhs, emitter),
-                        }
+                        ))
                     }
                 }
-                Expr::Not(term) => match &**term {
-                    Expr::Value(v) => try_match_var(&mut acc, v, true, emitter),
-                    _ => emitter.emit(Rich::custom(
-                        e.span(),
-                        "conditional clause inside `not` too deep here, discarding",
-                    )),
-                },
-                Expr::Eq(lhs, rhs) => try_match_eq(&mut acc, &**lhs, &**rhs, emitter),
+                Ok(())
             };
-            acc
+            // N.B.: `and` chains may be of any length, since conditions on `run_info` keys other
+            // than `os` and `debug` may precede both.
+            let mut terms = vec![&prop_val];
+            while let Some(term) = terms.pop() {
+                let matched =
+                    match term {
+                        Expr::Value(v) => try_match_var(&mut acc, v, false, emitter),
+                        Expr::And(lhs, rhs) => {
+                            terms.push(rhs);
+                            terms.push(lhs);
+                            Ok(())
+                        }
+                        Expr::Not(term) => match &**term {
+                            Expr::Value(v) => try_match_var(&mut acc, v, true, emitter),
+                            _ => Err("conditional clause inside `not` too deep here, discarding"
+                                .to_owned()),
+                        },
+                        Expr::Eq(lhs, rhs) => try_match_eq(&mut acc, &**lhs, &**rhs, emitter),
+                    };
+                if let Err(reason) = matched {
+                    return Condition {
+                        source: e.slice().trim().to_owned(),
+                        applicability: Err(reason),
+                    };
+                }
+            }
+            Condition {
+                source: e.slice().trim().to_owned(),
+                applicability: Ok(acc),
+            }
+        });
+        let conditional_term = condition.clone().validate(|condition, e, emitter| {
+            condition.applicability.unwrap_or_else(|reason| {
+                emitter.emit(Rich::custom(e.span(), reason));
+                Applicability::default()
+            })
         });
         choice((
             helper
                 .parser(
                     just("expected").to(()),
-                    conditional_term.clone(),
+                    condition,
                     choice((
                         outcome_parser.clone().map(Expected::permanent),
                         outcome_parser
@@ -1084,7 +2326,57 @@ where
             helper
                 .parser(
                     just("disabled").to(()),
-                    conditional_term,
+                    // N.B.: Conditions are kept as written, rather than resolved to an
+                    // `Applicability`, so they can be re-emitted verbatim.
+                    Expr::parser(Value::parser().map(|expr| expr.to_static())),
+                    any()
+                        .and_is(newline().or(end()).not())
+                        .repeated()
+                        .at_least(1)
+                        .to_slice()
+                        .map(|s: &str| s.trim().to_owned()),
+                )
+                .map_with(|((), val), e| TestProp {
+                    span: e.span(),
+                    kind: TestPropKind::Disabled(val),
+                }),
+            helper
+                .parser(
+                    just("disabled-until").to(()),
+                    conditional_term.clone(),
+                    group((digits(10), just('-'), digits(10), just('-'), digits(10)))
+                        .to_slice()
+                        .try_map(|date: &str, span| {
+                            NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| {
+                                Rich::custom(span, format!("invalid date {date:?}: {e}"))
+                            })
+                        }),
+                )
+                .validate(|((), val), e, emitter| {
+                    let date = match val {
+                        PropertyValue::Unconditional(date) => date,
+                        PropertyValue::Conditional(ConditionalValue {
+                            conditions,
+                            fallback,
+                        }) => {
+                            emitter.emit(Rich::custom(
+                                e.span(),
+                                "conditional rules for `disabled-until` aren't supported",
+                            ));
+                            fallback
+                                .or_else(|| conditions.first().map(|(_cond, date)| *date))
+                                .unwrap()
+                        }
+                    };
+                    TestProp {
+                        span: e.span(),
+                        kind: TestPropKind::DisabledUntil(date),
+                    }
+                }),
+            helper
+                .parser(
+                    just("keep-expectations").to(()),
+                    conditional_term.clone(),
                     just("true").to(()),
                 )
                 .validate(|((), val), e, emitter| {
@@ -1093,20 +2385,85 @@ where
                         PropertyValue::Conditional { .. } => {
                             emitter.emit(Rich::custom(
                                 e.span(),
-                                "conditional rules for `disabled` aren't supported yet",
+                                "conditional rules for `keep-expectations` aren't supported",
+                            ));
+                        }
+                    }
+                    TestProp {
+                        span: e.span(),
+                        kind: TestPropKind::KeepExpectations,
+                    }
+                }),
+            helper
+                .parser(
+                    just("quarantined").to(()),
+                    conditional_term.clone(),
+                    group((digits(10), just('-'), digits(10), just('-'), digits(10)))
+                        .to_slice()
+                        .try_map(|date: &str, span| {
+                            NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| {
+                                Rich::custom(span, format!("invalid date {date:?}: {e}"))
+                            })
+                        }),
+                )
+                .validate(|((), val), e, emitter| {
+                    let date = match val {
+                        PropertyValue::Unconditional(date) => date,
+                        PropertyValue::Conditional(ConditionalValue {
+                            conditions,
+                            fallback,
+                        }) => {
+                            emitter.emit(Rich::custom(
+                                e.span(),
+                                "conditional rules for `quarantined` aren't supported",
                             ));
+                            fallback
+                                .or_else(|| conditions.first().map(|(_cond, date)| *date))
+                                .unwrap()
                         }
+                    };
+                    TestProp {
+                        span: e.span(),
+                        kind: TestPropKind::Quarantined(date),
                     }
+                }),
+            helper
+                .parser(
+                    just("bug").to(()),
+                    conditional_term,
+                    any()
+                        .and_is(newline().or(end()).not())
+                        .repeated()
+                        .at_least(1)
+                        .to_slice()
+                        .map(|s: &str| s.trim().to_owned()),
+                )
+                .validate(|((), val), e, emitter| {
+                    let bug = match val {
+                        PropertyValue::Unconditional(bug) => bug,
+                        PropertyValue::Conditional(ConditionalValue {
+                            mut conditions,
+                            fallback,
+                        }) => {
+                            emitter.emit(Rich::custom(
+                                e.span(),
+                                "conditional rules for `bug` aren't supported",
+                            ));
+                            fallback
+                                .or_else(|| conditions.pop().map(|(_cond, bug)| bug))
+                                .unwrap()
+                        }
+                    };
                     TestProp {
                         span: e.span(),
-                        kind: TestPropKind::Disabled,
+                        kind: TestPropKind::Bug(bug),
                     }
                 }),
         ))
     }
 }
 
-#[derive(Debug, Deserialize, EnumSetType, Hash)]
+#[derive(Debug, Deserialize, EnumSetType, Hash, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum TestOutcome {
     Ok,
@@ -1161,7 +2518,7 @@ impl<'a> Properties<'a> for TestProps<TestOutcome> {
     }
 }
 
-#[derive(Debug, Deserialize, EnumSetType, Hash)]
+#[derive(Debug, Deserialize, EnumSetType, Hash, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum SubtestOutcome {
     Pass,
@@ -1247,8 +2604,14 @@ r#"
                 tests: {
                     "asdf": Test {
                         properties: TestProps {
-                            is_disabled: false,
+                            is_disabled: None,
+                            disabled_until: None,
+                            keep_expectations: false,
+                            quarantined: None,
+                            bug: None,
                             expected: None,
+                            expected_by_variant: {},
+                            verbatim_expected: None,
                         },
                         subtests: {},
                     },
@@ -1280,14 +2643,26 @@ r#"
                 tests: {
                     "asdf": Test {
                         properties: TestProps {
-                            is_disabled: false,
+                            is_disabled: None,
+                            disabled_until: None,
+                            keep_expectations: false,
+                            quarantined: None,
+                            bug: None,
                             expected: None,
+                            expected_by_variant: {},
+                            verbatim_expected: None,
                         },
                         subtests: {
                             "blarg": Subtest {
                                 properties: TestProps {
-                                    is_disabled: false,
+                                    is_disabled: None,
+                                    disabled_until: None,
+                                    keep_expectations: false,
+                                    quarantined: None,
+                                    bug: None,
                                     expected: None,
+                                    expected_by_variant: {},
+                                    verbatim_expected: None,
                                 },
                             },
                         },
@@ -1321,13 +2696,23 @@ r#"
                 tests: {
                     "asdf": Test {
                         properties: TestProps {
-                            is_disabled: false,
+                            is_disabled: None,
+                            disabled_until: None,
+                            keep_expectations: false,
+                            quarantined: None,
+                            bug: None,
                             expected: None,
+                            expected_by_variant: {},
+                            verbatim_expected: None,
                         },
                         subtests: {
                             "blarg": Subtest {
                                 properties: TestProps {
-                                    is_disabled: false,
+                                    is_disabled: None,
+                                    disabled_until: None,
+                                    keep_expectations: false,
+                                    quarantined: None,
+                                    bug: None,
                                     expected: Some(
                                         FullyExpandedExpectedPropertyValue(
                                             {
@@ -1355,9 +2740,19 @@ r#"
                                                         Pass,
                                                     ],
                                                 },
+                                                Android: {
+                                                    Debug: [
+                                                        Pass,
+                                                    ],
+                                                    Optimized: [
+                                                        Pass,
+                                                    ],
+                                                },
                                             },
                                         ),
                                     ),
+                                    expected_by_variant: {},
+                                    verbatim_expected: None,
                                 },
                             },
                         },
@@ -1405,13 +2800,23 @@ r#"
                 "asdf",
                 Test {
                     properties: TestProps {
-                        is_disabled: false,
+                        is_disabled: None,
+                        disabled_until: None,
+                        keep_expectations: false,
+                        quarantined: None,
+                        bug: None,
                         expected: None,
+                        expected_by_variant: {},
+                        verbatim_expected: None,
                     },
                     subtests: {
                         "blarg": Subtest {
                             properties: TestProps {
-                                is_disabled: false,
+                                is_disabled: None,
+                                disabled_until: None,
+                                keep_expectations: false,
+                                quarantined: None,
+                                bug: None,
                                 expected: Some(
                                     FullyExpandedExpectedPropertyValue(
                                         {
@@ -1445,9 +2850,21 @@ r#"
                                                     Fail,
                                                 ],
                                             },
+                                            Android: {
+                                                Debug: [
+                                                    Pass,
+                                                    Fail,
+                                                ],
+                                                Optimized: [
+                                                    Pass,
+                                                    Fail,
+                                                ],
+                                            },
                                         },
                                     ),
                                 ),
+                                expected_by_variant: {},
+                                verbatim_expected: None,
                             },
                         },
                     },
@@ -1475,7 +2892,11 @@ r#"
                 "asdf",
                 Test {
                     properties: TestProps {
-                        is_disabled: false,
+                        is_disabled: None,
+                        disabled_until: None,
+                        keep_expectations: false,
+                        quarantined: None,
+                        bug: None,
                         expected: Some(
                             FullyExpandedExpectedPropertyValue(
                                 {
@@ -1503,14 +2924,28 @@ r#"
                                             Ok,
                                         ],
                                     },
+                                    Android: {
+                                        Debug: [
+                                            Ok,
+                                        ],
+                                        Optimized: [
+                                            Ok,
+                                        ],
+                                    },
                                 },
                             ),
                         ),
+                        expected_by_variant: {},
+                        verbatim_expected: None,
                     },
                     subtests: {
                         "blarg": Subtest {
                             properties: TestProps {
-                                is_disabled: false,
+                                is_disabled: None,
+                                disabled_until: None,
+                                keep_expectations: false,
+                                quarantined: None,
+                                bug: None,
                                 expected: Some(
                                     FullyExpandedExpectedPropertyValue(
                                         {
@@ -1538,9 +2973,19 @@ r#"
                                                     Pass,
                                                 ],
                                             },
+                                            Android: {
+                                                Debug: [
+                                                    Pass,
+                                                ],
+                                                Optimized: [
+                                                    Pass,
+                                                ],
+                                            },
                                         },
                                     ),
                                 ),
+                                expected_by_variant: {},
+                                verbatim_expected: None,
                             },
                         },
                     },
@@ -1568,13 +3013,23 @@ r#"
                 "asdf",
                 Test {
                     properties: TestProps {
-                        is_disabled: false,
+                        is_disabled: None,
+                        disabled_until: None,
+                        keep_expectations: false,
+                        quarantined: None,
+                        bug: None,
                         expected: None,
+                        expected_by_variant: {},
+                        verbatim_expected: None,
                     },
                     subtests: {
                         "blarg": Subtest {
                             properties: TestProps {
-                                is_disabled: false,
+                                is_disabled: None,
+                                disabled_until: None,
+                                keep_expectations: false,
+                                quarantined: None,
+                                bug: None,
                                 expected: Some(
                                     FullyExpandedExpectedPropertyValue(
                                         {
@@ -1602,9 +3057,19 @@ r#"
                                                     Pass,
                                                 ],
                                             },
+                                            Android: {
+                                                Debug: [
+                                                    Pass,
+                                                ],
+                                                Optimized: [
+                                                    Pass,
+                                                ],
+                                            },
                                         },
                                     ),
                                 ),
+                                expected_by_variant: {},
+                                verbatim_expected: None,
                             },
                         },
                     },
@@ -1633,13 +3098,23 @@ r#"
                 "asdf",
                 Test {
                     properties: TestProps {
-                        is_disabled: false,
+                        is_disabled: None,
+                        disabled_until: None,
+                        keep_expectations: false,
+                        quarantined: None,
+                        bug: None,
                         expected: None,
+                        expected_by_variant: {},
+                        verbatim_expected: None,
                     },
                     subtests: {
                         "blarg": Subtest {
                             properties: TestProps {
-                                is_disabled: false,
+                                is_disabled: None,
+                                disabled_until: None,
+                                keep_expectations: false,
+                                quarantined: None,
+                                bug: None,
                                 expected: Some(
                                     FullyExpandedExpectedPropertyValue(
                                         {
@@ -1667,9 +3142,19 @@ r#"
                                                     Timeout,
                                                 ],
                                             },
+                                            Android: {
+                                                Debug: [
+                                                    Timeout,
+                                                ],
+                                                Optimized: [
+                                                    Timeout,
+                                                ],
+                                            },
                                         },
                                     ),
                                 ),
+                                expected_by_variant: {},
+                                verbatim_expected: None,
                             },
                         },
                     },
@@ -1696,13 +3181,23 @@ r#"
                 "cts.https.html?q=webgpu:api,validation,buffer,destroy:twice:*",
                 Test {
                     properties: TestProps {
-                        is_disabled: false,
+                        is_disabled: None,
+                        disabled_until: None,
+                        keep_expectations: false,
+                        quarantined: None,
+                        bug: None,
                         expected: None,
+                        expected_by_variant: {},
+                        verbatim_expected: None,
                     },
                     subtests: {
                         ":": Subtest {
                             properties: TestProps {
-                                is_disabled: false,
+                                is_disabled: None,
+                                disabled_until: None,
+                                keep_expectations: false,
+                                quarantined: None,
+                                bug: None,
                                 expected: Some(
                                     FullyExpandedExpectedPropertyValue(
                                         {
@@ -1730,9 +3225,19 @@ r#"
                                                     Fail,
                                                 ],
                                             },
+                                            Android: {
+                                                Debug: [
+                                                    Pass,
+                                                ],
+                                                Optimized: [
+                                                    Pass,
+                                                ],
+                                            },
                                         },
                                     ),
                                 ),
+                                expected_by_variant: {},
+                                verbatim_expected: None,
                             },
                         },
                     },
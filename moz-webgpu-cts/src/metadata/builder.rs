@@ -0,0 +1,414 @@
+//! Programmatic construction of [`File`]s, for tooling that wants to emit metadata (i.e., with
+//! [`format_file`]) without templating strings by hand.
+//!
+//! [`format_file`]: crate::metadata::format_file
+
+use std::collections::{btree_map, BTreeMap};
+
+use chrono::NaiveDate;
+use enumset::EnumSetType;
+use whippit::metadata::{properties::PropertyValue, SectionHeader};
+
+use crate::{
+    metadata::{
+        BuildProfile, File, FileProps, ImplementationStatus, Platform, Subtest, SubtestOutcome,
+        Test, TestOutcome, TestProps,
+    },
+    shared::{Expected, FullyExpandedExpectedPropertyValue},
+};
+
+/// Builds a [`File`], validating that the result can be round-tripped through
+/// [`format_file`](crate::metadata::format_file) and [`File::parser`].
+#[derive(Clone, Debug, Default)]
+pub struct FileBuilder {
+    properties: FileProps,
+    tests: Vec<TestBuilder>,
+}
+
+impl FileBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets an unconditional `prefs` property.
+    pub fn prefs<I, K, V>(mut self, prefs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.properties.prefs = Some(PropertyValue::Unconditional(
+            prefs
+                .into_iter()
+                .map(|(key, val)| (key.into(), val.into()))
+                .collect(),
+        ));
+        self
+    }
+
+    /// Sets an unconditional `tags` property.
+    pub fn tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.properties.tags = Some(PropertyValue::Unconditional(
+            tags.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Sets an unconditional `disabled` property, with `reason` as its value.
+    pub fn disabled(mut self, reason: impl Into<String>) -> Self {
+        self.properties.is_disabled = Some(PropertyValue::Unconditional(reason.into()));
+        self
+    }
+
+    /// Sets an unconditional `implementation-status` property.
+    pub fn implementation_status(mut self, status: ImplementationStatus) -> Self {
+        self.properties.implementation_status = Some(PropertyValue::Unconditional(status));
+        self
+    }
+
+    pub fn test(mut self, test: TestBuilder) -> Self {
+        self.tests.push(test);
+        self
+    }
+
+    pub fn build(self) -> Result<File, BuildError> {
+        let Self { properties, tests } = self;
+
+        if let Some(PropertyValue::Unconditional(tags)) = properties.tags.as_ref() {
+            if tags.is_empty() {
+                return Err(BuildError::EmptyTags);
+            }
+        }
+
+        let mut built_tests = BTreeMap::new();
+        for test in tests {
+            let (name, test) = test.build()?;
+            match built_tests.entry(name) {
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(test);
+                }
+                btree_map::Entry::Occupied(entry) => {
+                    let SectionHeader(name) = entry.key();
+                    return Err(BuildError::DuplicateTest(name.clone()));
+                }
+            }
+        }
+
+        Ok(File {
+            properties,
+            tests: built_tests,
+        })
+    }
+}
+
+/// Builds a [`Test`] for use with [`FileBuilder::test`].
+#[derive(Clone, Debug)]
+pub struct TestBuilder {
+    name: String,
+    properties: TestProps<TestOutcome>,
+    subtests: Vec<SubtestBuilder>,
+}
+
+impl TestBuilder {
+    /// `name` is the test's section header, i.e., a file name with an optional variant (like
+    /// `cts.https.html?q=webgpu:api,operation,buffers,map:*`).
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            properties: Default::default(),
+            subtests: Vec::new(),
+        }
+    }
+
+    pub fn disabled(self) -> Self {
+        self.disabled_because("true")
+    }
+
+    /// Sets an unconditional `disabled` property, with `reason` (i.e., a bug URL) as its value.
+    pub fn disabled_because(mut self, reason: impl Into<String>) -> Self {
+        self.properties.is_disabled = Some(PropertyValue::Unconditional(reason.into()));
+        self
+    }
+
+    /// Marks this section's disable as due for re-evaluation on `date`; see
+    /// [`TestProps::disabled_until`].
+    pub fn disabled_until(mut self, date: NaiveDate) -> Self {
+        self.properties
+            .is_disabled
+            .get_or_insert_with(|| PropertyValue::Unconditional("true".to_owned()));
+        self.properties.disabled_until = Some(date);
+        self
+    }
+
+    pub fn keep_expectations(mut self) -> Self {
+        self.properties.keep_expectations = true;
+        self
+    }
+
+    /// See [`TestProps::quarantined`].
+    pub fn quarantined(mut self, date: NaiveDate) -> Self {
+        self.properties.quarantined = Some(date);
+        self
+    }
+
+    /// Sets the same expected outcome(s) for all platforms and build profiles.
+    pub fn expected(mut self, expected: Expected<TestOutcome>) -> Self {
+        set_expected(&mut self.properties, None, expected);
+        self
+    }
+
+    /// Sets expected outcome(s) for a single platform and build profile. Configurations left
+    /// unspecified default to `OK`.
+    pub fn expected_on(
+        mut self,
+        platform: Platform,
+        build_profile: BuildProfile,
+        expected: Expected<TestOutcome>,
+    ) -> Self {
+        set_expected(
+            &mut self.properties,
+            Some((platform, build_profile)),
+            expected,
+        );
+        self
+    }
+
+    pub fn subtest(mut self, subtest: SubtestBuilder) -> Self {
+        self.subtests.push(subtest);
+        self
+    }
+
+    fn build(self) -> Result<(SectionHeader, Test), BuildError> {
+        let Self {
+            name,
+            properties,
+            subtests,
+        } = self;
+
+        check_section_header(&name)?;
+
+        let mut built_subtests = BTreeMap::new();
+        for subtest in subtests {
+            let SubtestBuilder {
+                name: subtest_name,
+                properties,
+            } = subtest;
+            check_section_header(&subtest_name)?;
+            match built_subtests.entry(SectionHeader(subtest_name)) {
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(Subtest { properties });
+                }
+                btree_map::Entry::Occupied(entry) => {
+                    let SectionHeader(subtest) = entry.key();
+                    return Err(BuildError::DuplicateSubtest {
+                        test: name,
+                        subtest: subtest.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok((
+            SectionHeader(name),
+            Test {
+                properties,
+                subtests: built_subtests,
+            },
+        ))
+    }
+}
+
+/// Builds a [`Subtest`] for use with [`TestBuilder::subtest`].
+#[derive(Clone, Debug)]
+pub struct SubtestBuilder {
+    name: String,
+    properties: TestProps<SubtestOutcome>,
+}
+
+impl SubtestBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            properties: Default::default(),
+        }
+    }
+
+    pub fn disabled(self) -> Self {
+        self.disabled_because("true")
+    }
+
+    /// Sets an unconditional `disabled` property, with `reason` (i.e., a bug URL) as its value.
+    pub fn disabled_because(mut self, reason: impl Into<String>) -> Self {
+        self.properties.is_disabled = Some(PropertyValue::Unconditional(reason.into()));
+        self
+    }
+
+    /// Marks this section's disable as due for re-evaluation on `date`; see
+    /// [`TestProps::disabled_until`].
+    pub fn disabled_until(mut self, date: NaiveDate) -> Self {
+        self.properties
+            .is_disabled
+            .get_or_insert_with(|| PropertyValue::Unconditional("true".to_owned()));
+        self.properties.disabled_until = Some(date);
+        self
+    }
+
+    pub fn keep_expectations(mut self) -> Self {
+        self.properties.keep_expectations = true;
+        self
+    }
+
+    /// Sets the same expected outcome(s) for all platforms and build profiles.
+    pub fn expected(mut self, expected: Expected<SubtestOutcome>) -> Self {
+        set_expected(&mut self.properties, None, expected);
+        self
+    }
+
+    /// Sets expected outcome(s) for a single platform and build profile. Configurations left
+    /// unspecified default to `PASS`.
+    pub fn expected_on(
+        mut self,
+        platform: Platform,
+        build_profile: BuildProfile,
+        expected: Expected<SubtestOutcome>,
+    ) -> Self {
+        set_expected(
+            &mut self.properties,
+            Some((platform, build_profile)),
+            expected,
+        );
+        self
+    }
+}
+
+fn set_expected<Out>(
+    properties: &mut TestProps<Out>,
+    config: Option<(Platform, BuildProfile)>,
+    expected: Expected<Out>,
+) where
+    Out: Default + EnumSetType,
+{
+    match config {
+        None => {
+            properties.expected = Some(FullyExpandedExpectedPropertyValue::uniform(expected));
+        }
+        Some(config) => {
+            properties.expected.get_or_insert_with(Default::default)[config] = expected;
+        }
+    }
+}
+
+fn check_section_header(name: &str) -> Result<(), BuildError> {
+    if name.is_empty() || name.chars().any(char::is_control) {
+        Err(BuildError::InvalidSectionHeader(name.to_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+/// An error returned by [`FileBuilder::build`].
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum BuildError {
+    #[error("duplicate test {0:?}")]
+    DuplicateTest(String),
+    #[error("duplicate subtest {subtest:?} in test {test:?}")]
+    DuplicateSubtest { test: String, subtest: String },
+    #[error(
+        "{0:?} is not a valid section header; it must be non-empty and free of control characters"
+    )]
+    InvalidSectionHeader(String),
+    #[error("`tags` property specified with no tags")]
+    EmptyTags,
+}
+
+#[test]
+fn build_and_format() {
+    use crate::metadata::format_file;
+
+    let file = FileBuilder::new()
+        .tags(["webgpu"])
+        .test(
+            TestBuilder::new("cts.https.html?q=webgpu:api,operation,buffers,map:*")
+                .expected_on(
+                    Platform::Windows,
+                    BuildProfile::Debug,
+                    Expected::permanent(TestOutcome::Timeout),
+                )
+                .subtest(
+                    SubtestBuilder::new(":mapAsync=1")
+                        .expected(Expected::permanent(SubtestOutcome::Fail)),
+                )
+                .subtest(SubtestBuilder::new(":mapAsync=2").expected_on(
+                    Platform::Linux,
+                    BuildProfile::Optimized,
+                    Expected::intermittent(SubtestOutcome::Pass | SubtestOutcome::Fail).unwrap(),
+                )),
+        )
+        .test(TestBuilder::new("cts.https.html?q=webgpu:api,operation,adapter:*").disabled())
+        .build()
+        .unwrap();
+
+    insta::assert_snapshot!(format_file(&file), @r###"
+    tags: [webgpu]
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      disabled: true
+
+
+    [cts.https.html?q=webgpu:api,operation,buffers,map:*]
+      expected:
+        if os == "win" and debug: TIMEOUT
+      [:mapAsync=1]
+        expected: FAIL
+
+      [:mapAsync=2]
+        expected:
+          if os == "linux" and not debug: [PASS, FAIL]
+    "###);
+}
+
+#[test]
+fn build_rejects_invalid() {
+    assert_eq!(
+        FileBuilder::new()
+            .test(TestBuilder::new("cts.https.html"))
+            .test(TestBuilder::new("cts.https.html"))
+            .build()
+            .unwrap_err(),
+        BuildError::DuplicateTest("cts.https.html".to_owned()),
+    );
+
+    assert_eq!(
+        FileBuilder::new()
+            .test(
+                TestBuilder::new("cts.https.html")
+                    .subtest(SubtestBuilder::new("blarg"))
+                    .subtest(SubtestBuilder::new("blarg"))
+            )
+            .build()
+            .unwrap_err(),
+        BuildError::DuplicateSubtest {
+            test: "cts.https.html".to_owned(),
+            subtest: "blarg".to_owned(),
+        },
+    );
+
+    assert_eq!(
+        FileBuilder::new()
+            .test(TestBuilder::new("cts.https.html\n"))
+            .build()
+            .unwrap_err(),
+        BuildError::InvalidSectionHeader("cts.https.html\n".to_owned()),
+    );
+
+    assert_eq!(
+        FileBuilder::new()
+            .tags(Vec::<String>::new())
+            .build()
+            .unwrap_err(),
+        BuildError::EmptyTags,
+    );
+}
@@ -0,0 +1,438 @@
+//! Migration of metadata for tests that were renamed or moved (i.e., by a CTS refactor), so that
+//! their expectations follow them instead of being left behind in orphaned sections.
+
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use indexmap::IndexMap;
+use whippit::metadata::SectionHeader;
+
+use crate::{
+    dedupe,
+    error::Error,
+    metadata::File,
+    shared::{Browser, TestPath},
+};
+
+/// A rename of tests from `OLD` to `NEW`, parsed from `<OLD> <NEW>`, where both are either CTS
+/// queries (i.e., `webgpu:api,operation,buffers,map:*`) or test URL paths (i.e.,
+/// `/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers,map:*`).
+///
+/// If `OLD` ends with `*`, so must `NEW`, and every test whose query or URL path starts with
+/// `OLD` up to its `*` is renamed by replacing that part with `NEW` up to its `*`. For instance,
+/// `webgpu:api,operation,buffer,* webgpu:api,operation,buffers,*` renames
+/// `webgpu:api,operation,buffer,map:*` to `webgpu:api,operation,buffers,map:*`. Otherwise, `OLD`
+/// must match a test's query or URL path exactly.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rename {
+    from: String,
+    to: String,
+    kind: RenameKind,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum RenameKind {
+    CtsQuery,
+    UrlPath,
+}
+
+impl Rename {
+    /// Returns the new test URL path of the test at `test_path`, if this renames it.
+    pub fn apply(&self, test_path: &TestPath<'_>) -> Option<String> {
+        let Self { from, to, kind } = self;
+        let url_path = format!("/{}", test_path.runner_url_path());
+        let rename = |name: &str| match from.strip_suffix('*') {
+            Some(prefix) => name
+                .strip_prefix(prefix)
+                .map(|rest| format!("{}{rest}", to.strip_suffix('*').unwrap())),
+            None => (name == from).then(|| to.clone()),
+        };
+        match kind {
+            RenameKind::CtsQuery => {
+                let cts_path = test_path.cts_path()?;
+                let renamed = rename(&cts_path)?;
+                let base = url_path.strip_suffix(&*cts_path).unwrap();
+                Some(format!("{base}{renamed}"))
+            }
+            RenameKind::UrlPath => rename(&url_path),
+        }
+    }
+}
+
+impl FromStr for Rename {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s
+            .split_once(char::is_whitespace)
+            .map(|(from, to)| (from, to.trim()))
+            .ok_or_else(|| "expected a new name after the old one".to_owned())?;
+        let kind_of = |name: &str| {
+            if name.starts_with("webgpu:") {
+                Ok(RenameKind::CtsQuery)
+            } else if name.starts_with('/') {
+                Ok(RenameKind::UrlPath)
+            } else {
+                Err(format!(
+                    "expected a test URL path starting with `/` or a CTS query starting with \
+                    `webgpu:`, got {name:?}"
+                ))
+            }
+        };
+        let kind = kind_of(from)?;
+        if kind_of(to)? != kind {
+            return Err("expected both names to be CTS queries, or both test URL paths".to_owned());
+        }
+        for name in [from, to] {
+            if name.trim_end_matches('*').contains('*') {
+                return Err(format!("`*` may only end a name, got {name:?}"));
+            }
+        }
+        if from.ends_with('*') != to.ends_with('*') {
+            return Err("expected both names to end with `*`, or neither".to_owned());
+        }
+        Ok(Self {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            kind,
+        })
+    }
+}
+
+impl Display for Rename {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self { from, to, kind: _ } = self;
+        write!(f, "{from} {to}")
+    }
+}
+
+/// [`Rename`]s parsed from a file with one per line. Blank lines and lines starting with `#` are
+/// ignored. The first rename matching a test determines its new name.
+#[derive(Clone, Debug, Default)]
+pub struct RenameMap {
+    renames: Vec<Rename>,
+}
+
+impl RenameMap {
+    pub fn parse(path: &Path, contents: &str) -> Result<Self, Error> {
+        let renames = contents
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|(_line_num, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_num, line)| {
+                line.parse().map_err(|reason| Error::ParseRenameMap {
+                    path: path.to_owned(),
+                    line_num,
+                    line: line.to_owned(),
+                    reason,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { renames })
+    }
+
+    pub fn renames(&self) -> &[Rename] {
+        &self.renames
+    }
+}
+
+/// A test moved by [`migrate`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Migration {
+    pub from: TestPath<'static>,
+    pub to: TestPath<'static>,
+}
+
+/// The changes made by [`migrate`].
+#[derive(Debug, Default)]
+pub struct Migrated<'a> {
+    /// Files with tests moved into or out of them, which should be written back, unless in
+    /// `emptied`.
+    pub changed_files: BTreeSet<Arc<PathBuf>>,
+    /// Files left without any tests or properties, which should be removed.
+    pub emptied: Vec<PathBuf>,
+    pub moved: Vec<Migration>,
+    /// Tests that were not moved, since their new name already has a section that isn't moved
+    /// itself, or another test is moved to it, too.
+    pub conflicts: Vec<Migration>,
+    /// Tests whose new test URL path doesn't map to metadata, and the path.
+    pub invalid: Vec<(TestPath<'static>, String)>,
+    /// Renames that matched no test.
+    pub unused: Vec<&'a Rename>,
+}
+
+/// Moves the sections of tests in `files` renamed by `renames` to their new names, along with
+/// their expectations and those of their subtests. Sections are moved between files where the new
+/// name of a test belongs in a different metadata file than the old one.
+pub fn migrate<'a>(
+    gecko_checkout: &Path,
+    files: &mut IndexMap<Arc<PathBuf>, File>,
+    browser: Browser,
+    renames: &'a RenameMap,
+) -> Migrated<'a> {
+    struct Planned {
+        from: (Arc<PathBuf>, SectionHeader),
+        to: (Arc<PathBuf>, SectionHeader),
+        migration: Migration,
+    }
+
+    let mut migrated = Migrated::default();
+    let mut used = vec![false; renames.renames.len()];
+    let mut planned = Vec::new();
+    for (path, file) in files.iter() {
+        let Ok(rel_path) = path.strip_prefix(gecko_checkout) else {
+            continue;
+        };
+        for name in file.tests.keys() {
+            let Ok(test_path) = TestPath::from_metadata_test(rel_path, &name.0) else {
+                continue;
+            };
+            let Some((idx, new_url_path)) = renames
+                .renames
+                .iter()
+                .enumerate()
+                .find_map(|(idx, rename)| Some((idx, rename.apply(&test_path)?)))
+            else {
+                continue;
+            };
+            used[idx] = true;
+            let new_test_path = TestPath::from_execution_report(browser, &new_url_path)
+                .map(TestPath::into_owned)
+                .ok();
+            let Some(new_test_path) = new_test_path else {
+                migrated
+                    .invalid
+                    .push((test_path.into_owned(), new_url_path));
+                continue;
+            };
+            if test_path == new_test_path {
+                continue;
+            }
+            let new_path =
+                Arc::new(gecko_checkout.join(new_test_path.rel_metadata_path().to_string()));
+            let new_name = SectionHeader(new_test_path.test_name().to_string());
+            planned.push(Planned {
+                from: (path.clone(), name.clone()),
+                to: (new_path, new_name),
+                migration: Migration {
+                    from: test_path.into_owned(),
+                    to: new_test_path,
+                },
+            });
+        }
+    }
+    migrated.unused = renames
+        .renames
+        .iter()
+        .zip(used)
+        .filter(|(_rename, used)| !used)
+        .map(|(rename, _used)| rename)
+        .collect();
+
+    // N.B.: A test may be moved to where another test is moved away from, so conflicts are found
+    // until none are left.
+    let mut conflicting = vec![false; planned.len()];
+    loop {
+        let moving = planned
+            .iter()
+            .zip(&conflicting)
+            .filter(|(_planned, conflicting)| !**conflicting)
+            .map(|(planned, _conflicting)| &planned.from)
+            .collect::<BTreeSet<_>>();
+        let mut claimed = BTreeSet::new();
+        let mut found_conflict = false;
+        for (planned, conflicting) in planned.iter().zip(&mut conflicting) {
+            if *conflicting {
+                continue;
+            }
+            let (path, name) = &planned.to;
+            let occupied = files
+                .get(path)
+                .map_or(false, |file| file.tests.contains_key(name))
+                && !moving.contains(&planned.to);
+            if occupied || !claimed.insert(&planned.to) {
+                *conflicting = true;
+                found_conflict = true;
+            }
+        }
+        if !found_conflict {
+            break;
+        }
+    }
+
+    let mut moved = Vec::new();
+    for (planned, conflicting) in planned.into_iter().zip(conflicting) {
+        let Planned {
+            from,
+            to,
+            migration,
+        } = planned;
+        if conflicting {
+            migrated.conflicts.push(migration);
+            continue;
+        }
+        let (path, name) = &from;
+        let test = files.get_mut(path).unwrap().tests.remove(name).unwrap();
+        migrated.changed_files.insert(path.clone());
+        moved.push((to, test));
+        migrated.moved.push(migration);
+    }
+    for ((path, name), test) in moved {
+        files
+            .entry(path.clone())
+            .or_default()
+            .tests
+            .insert(name, test);
+        migrated.changed_files.insert(path);
+    }
+
+    migrated.emptied = migrated
+        .changed_files
+        .iter()
+        .filter(|path| {
+            let File { properties, tests } = &files[*path];
+            tests.is_empty() && dedupe::is_empty(properties)
+        })
+        .map(|path| path.to_path_buf())
+        .collect();
+
+    migrated
+}
+
+#[test]
+fn parse_renames() {
+    let map = RenameMap::parse(
+        Path::new("renames.txt"),
+        concat!(
+            "# Buffers were split up.\n",
+            "webgpu:api,operation,buffer,* webgpu:api,operation,buffers,*\n",
+            "\n",
+            "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:a:* ",
+            "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:b:*\n",
+        ),
+    )
+    .unwrap();
+    assert_eq!(map.renames().len(), 2);
+    assert_eq!(
+        map.renames()[0].to_string(),
+        "webgpu:api,operation,buffer,* webgpu:api,operation,buffers,*"
+    );
+
+    for line in [
+        "webgpu:api,*",
+        "webgpu:api,* /_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,*",
+        "webgpu:api,* webgpu:apis:",
+        "webgpu:a*i:* webgpu:api:*",
+        "api,* webgpu:api,*",
+    ] {
+        assert!(line.parse::<Rename>().is_err(), "{line:?} should not parse");
+    }
+    assert!(matches!(
+        RenameMap::parse(
+            Path::new("renames.txt"),
+            "webgpu:a:* webgpu:b:*\nwebgpu:c:*\n"
+        ),
+        Err(Error::ParseRenameMap { line_num: 2, .. })
+    ));
+}
+
+#[test]
+fn migrate_tests() {
+    use crate::{
+        metadata::{
+            format_file, FileBuilder, SubtestBuilder, SubtestOutcome, TestBuilder, TestOutcome,
+        },
+        shared::Expected,
+    };
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_dir = gecko_checkout.join("testing/web-platform/mozilla/meta/webgpu");
+    let cts_path = Arc::new(meta_dir.join("cts/webgpu/cts.https.html.ini"));
+    let file = FileBuilder::new()
+        .test(
+            TestBuilder::new("cts.https.html?q=webgpu:api,operation,buffer,map:*")
+                .expected(Expected::permanent(TestOutcome::Timeout))
+                .subtest(
+                    SubtestBuilder::new(":a").expected(Expected::permanent(SubtestOutcome::Fail)),
+                ),
+        )
+        .test(TestBuilder::new(
+            "cts.https.html?q=webgpu:api,operation,buffer,unmap:*",
+        ))
+        .test(TestBuilder::new(
+            "cts.https.html?q=webgpu:api,operation,buffers,unmap:*",
+        ))
+        .test(
+            TestBuilder::new("cts.https.html?q=webgpu:api,operation,adapter:*")
+                .expected(Expected::permanent(TestOutcome::Crash)),
+        )
+        .build()
+        .unwrap();
+    let mut files = [(cts_path.clone(), file)]
+        .into_iter()
+        .collect::<IndexMap<_, _>>();
+
+    let renames = RenameMap::parse(
+        Path::new("renames.txt"),
+        concat!(
+            "webgpu:api,operation,buffer,* webgpu:api,operation,buffers,*\n",
+            "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:* ",
+            "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapters:*\n",
+            "webgpu:api,operation,nope:* webgpu:api,operation,still_nope:*\n",
+        ),
+    )
+    .unwrap();
+    let migrated = migrate(gecko_checkout, &mut files, Browser::Firefox, &renames);
+
+    let queries = |migrations: &[Migration]| {
+        migrations
+            .iter()
+            .map(|Migration { from, to }| (from.cts_path().unwrap(), to.cts_path().unwrap()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        queries(&migrated.moved),
+        [
+            (
+                "webgpu:api,operation,adapter:*".to_owned(),
+                "webgpu:api,operation,adapters:*".to_owned()
+            ),
+            (
+                "webgpu:api,operation,buffer,map:*".to_owned(),
+                "webgpu:api,operation,buffers,map:*".to_owned()
+            ),
+        ]
+    );
+    assert_eq!(
+        queries(&migrated.conflicts),
+        [(
+            "webgpu:api,operation,buffer,unmap:*".to_owned(),
+            "webgpu:api,operation,buffers,unmap:*".to_owned()
+        )]
+    );
+    assert_eq!(migrated.unused, [&renames.renames()[2]]);
+    assert_eq!(migrated.changed_files, [cts_path.clone()].into());
+    assert!(migrated.emptied.is_empty());
+    insta::assert_snapshot!(format_file(&files[&cts_path]), @r###"
+    [cts.https.html?q=webgpu:api,operation,adapters:*]
+      expected: CRASH
+
+
+    [cts.https.html?q=webgpu:api,operation,buffer,unmap:*]
+
+
+    [cts.https.html?q=webgpu:api,operation,buffers,map:*]
+      expected: TIMEOUT
+      [:a]
+        expected: FAIL
+
+
+    [cts.https.html?q=webgpu:api,operation,buffers,unmap:*]
+    "###);
+}
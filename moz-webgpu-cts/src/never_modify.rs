@@ -0,0 +1,98 @@
+//! Lists of tests whose metadata [`reconcile`] must leave untouched.
+//!
+//! [`reconcile`]: crate::process_reports::reconcile
+
+use std::path::Path;
+
+use crate::{error::Error, shared::TestPath, test_pattern::TestPattern};
+
+/// A list of [`TestPattern`]s matching tests whose metadata must not be modified, parsed from a
+/// file with one pattern per line. Blank lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct NeverModifyList {
+    patterns: Vec<TestPattern>,
+}
+
+impl NeverModifyList {
+    pub fn parse(path: &Path, contents: &str) -> Result<Self, Error> {
+        let patterns = contents
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|(_line_num, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_num, line)| {
+                line.parse().map_err(|_e| Error::ParseNeverModifyList {
+                    path: path.to_owned(),
+                    line_num,
+                    line: line.to_owned(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns the index of the first pattern matching `test_path`, if any.
+    pub fn find_match(&self, test_path: &TestPath<'_>) -> Option<usize> {
+        self.patterns
+            .iter()
+            .position(|pattern| pattern.matches(test_path))
+    }
+
+    /// Returns the pattern at `idx`, as returned by [`Self::find_match`].
+    pub fn pattern(&self, idx: usize) -> &TestPattern {
+        &self.patterns[idx]
+    }
+
+    pub fn num_patterns(&self) -> usize {
+        self.patterns.len()
+    }
+}
+
+#[test]
+fn matching() {
+    use crate::shared::Browser;
+
+    let list = NeverModifyList::parse(
+        Path::new("never-modify.txt"),
+        concat!(
+            "# Bad worker pool, see bug 1234567.\n",
+            "webgpu:api,operation,buffers,*\n",
+            "\n",
+            "  webgpu:api,operation,adapter:requestDevice:*  \n",
+            "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:shader,*,atomic*\n",
+            "/css/*\n",
+        ),
+    )
+    .unwrap();
+    assert_eq!(list.num_patterns(), 4);
+
+    let find = |test_url_path: &str| {
+        list.find_match(&TestPath::from_execution_report(Browser::Firefox, test_url_path).unwrap())
+    };
+    let cts = |query| format!("/_mozilla/webgpu/cts/webgpu/cts.https.html?q={query}");
+
+    assert_eq!(find(&cts("webgpu:api,operation,buffers,map:*")), Some(0));
+    assert_eq!(
+        find(&cts("webgpu:api,operation,adapter:requestDevice:*")),
+        Some(1)
+    );
+    assert_eq!(find(&cts("webgpu:api,operation,adapter:info:*")), None);
+    assert_eq!(
+        find(&cts(
+            "webgpu:shader,execution,expression,call,builtin,atomics:*"
+        )),
+        Some(2)
+    );
+    assert_eq!(find("/css/foo.html"), Some(3));
+    assert_eq!(find("/_mozilla/css/foo.html"), None);
+
+    let err = NeverModifyList::parse(Path::new("never-modify.txt"), "\nwebgpu:*\nfoo\n");
+    assert!(matches!(
+        err,
+        Err(Error::ParseNeverModifyList { line_num: 3, .. })
+    ));
+}
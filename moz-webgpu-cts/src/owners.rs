@@ -0,0 +1,96 @@
+//! Mappings of tests to the teams or people that own them, so that triaged failures can be routed
+//! to them.
+
+use std::path::Path;
+
+use crate::{error::Error, shared::TestPath, test_pattern::TestPattern};
+
+/// Owners of tests, parsed from a file with one `<PATTERN> <OWNER>` pair per line, where
+/// `<PATTERN>` is a [`TestPattern`] (usually a CTS query prefix, i.e.,
+/// `webgpu:shader,execution,*`) and `<OWNER>` is the rest of the line. Blank lines and lines
+/// starting with `#` are ignored.
+///
+/// The first pattern matching a test determines its owner, so more specific patterns should come
+/// before those they are nested under.
+#[derive(Clone, Debug, Default)]
+pub struct Owners {
+    rules: Vec<(TestPattern, String)>,
+}
+
+impl Owners {
+    pub fn parse(path: &Path, contents: &str) -> Result<Self, Error> {
+        let rules = contents
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|(_line_num, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_num, line)| {
+                let err = |reason: String| Error::ParseOwners {
+                    path: path.to_owned(),
+                    line_num,
+                    line: line.to_owned(),
+                    reason,
+                };
+                let (pattern, owner) = line
+                    .split_once(char::is_whitespace)
+                    .map(|(pattern, owner)| (pattern, owner.trim()))
+                    .ok_or_else(|| err("expected an owner after the pattern".to_owned()))?;
+                Ok((pattern.parse().map_err(err)?, owner.to_owned()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the owner of `test_path`, as given by the first pattern matching it.
+    pub fn owner_of(&self, test_path: &TestPath<'_>) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|(pattern, _owner)| pattern.matches(test_path))
+            .map(|(_pattern, owner)| &**owner)
+    }
+}
+
+#[test]
+fn owners() {
+    use crate::shared::Browser;
+
+    let owners = Owners::parse(
+        Path::new("owners.txt"),
+        concat!(
+            "# Atomics are handled separately.\n",
+            "webgpu:shader,execution,expression,call,builtin,atomics* Shader Atomics\n",
+            "webgpu:shader,*   shader-team\n",
+            "\n",
+            "webgpu:api,* api-team\n",
+        ),
+    )
+    .unwrap();
+
+    let owner = |query: &str| {
+        let test_url_path = format!("/_mozilla/webgpu/cts/webgpu/cts.https.html?q={query}");
+        let test_path = TestPath::from_execution_report(Browser::Firefox, &test_url_path).unwrap();
+        owners.owner_of(&test_path).map(ToOwned::to_owned)
+    };
+    assert_eq!(
+        owner("webgpu:shader,execution,expression,call,builtin,atomics:*").as_deref(),
+        Some("Shader Atomics")
+    );
+    assert_eq!(
+        owner("webgpu:shader,validation,parse:*").as_deref(),
+        Some("shader-team")
+    );
+    assert_eq!(
+        owner("webgpu:api,operation,adapter:*").as_deref(),
+        Some("api-team")
+    );
+    assert_eq!(owner("webgpu:web_platform,canvas:*"), None);
+
+    let err = Owners::parse(Path::new("owners.txt"), "webgpu:api,* api-team\nwebgpu:*\n");
+    assert!(matches!(err, Err(Error::ParseOwners { line_num: 2, .. })));
+    let err = Owners::parse(Path::new("owners.txt"), "api,* api-team\n");
+    assert!(matches!(err, Err(Error::ParseOwners { line_num: 1, .. })));
+}
@@ -0,0 +1,310 @@
+//! Comparison of outcomes between the worker pools that ran the same configuration, to find
+//! "intermittent" outcomes that are really differences between pools' environments (i.e.,
+//! mismatched driver versions).
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+};
+
+use enumset::EnumSetType;
+
+use crate::{
+    error::Error,
+    metadata::{BuildProfile, Platform, RunInfoValue, SubtestOutcome, TestOutcome},
+    report::{ExecutionReport, RunInfo, TestExecutionEntry, TestExecutionResult},
+    shared::{Browser, Expected, TestPath},
+};
+
+/// The `run_info` keys that identify the worker pool of a run by default, in order of preference.
+pub const DEFAULT_POOL_KEYS: &[&str] = &["worker_pool", "worker_type"];
+
+/// Returns the worker pool that `run_info` says its run was on: the string value of the first of
+/// `pool_keys` present in it.
+pub fn pool_of<'a>(run_info: &'a RunInfo, pool_keys: &[String]) -> Option<&'a str> {
+    pool_keys
+        .iter()
+        .find_map(|key| match run_info.properties.0.get(key)? {
+            RunInfoValue::String(pool) => Some(&**pool),
+            RunInfoValue::Bool(_) => None,
+        })
+}
+
+/// The result of [`pool_skew`].
+#[derive(Debug, Default)]
+pub struct PoolSkew {
+    /// The number of runs on each pool, by configuration.
+    pub runs_by_pool: BTreeMap<(Platform, BuildProfile), BTreeMap<String, usize>>,
+    /// The number of runs ignored because no pool was found in their `run_info`.
+    pub num_runs_without_pool: usize,
+    /// Tests and subtests whose outcomes differed between pools, by configuration.
+    pub disagreements: BTreeMap<(Platform, BuildProfile), Vec<PoolDisagreement>>,
+}
+
+/// A test or subtest whose outcomes differed between pools running the same configuration.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolDisagreement {
+    pub test: TestPath<'static>,
+    pub subtest: Option<String>,
+    /// The outcomes observed on each pool that ran the test, as they would be written in
+    /// metadata.
+    pub outcomes_by_pool: BTreeMap<String, String>,
+}
+
+type Outcomes<Out> = BTreeMap<(Platform, BuildProfile), BTreeMap<String, Expected<Out>>>;
+
+/// Compares outcomes of tests in `exec_reports` between the worker pools that ran each
+/// configuration, as identified by [`pool_of`] with `pool_keys`. Outcomes from several runs on the
+/// same pool are merged, as with intermittent outcomes in metadata, and reported if they differ
+/// from those of any other pool. Tests are resolved as run in `browser`.
+pub fn pool_skew<'a>(
+    browser: Browser,
+    exec_reports: impl IntoIterator<Item = Result<ExecutionReport<'a>, Error>>,
+    pool_keys: &[String],
+) -> Result<PoolSkew, Error> {
+    fn accumulate<Out>(
+        outcomes: &mut Outcomes<Out>,
+        config: (Platform, BuildProfile),
+        pool: &str,
+        outcome: Out,
+    ) where
+        Out: EnumSetType,
+    {
+        outcomes
+            .entry(config)
+            .or_default()
+            .entry(pool.to_owned())
+            .and_modify(|expected| *expected |= outcome)
+            .or_insert_with(|| Expected::permanent(outcome));
+    }
+
+    let mut skew = PoolSkew::default();
+    #[allow(clippy::type_complexity)]
+    let mut tests = BTreeMap::<
+        TestPath<'static>,
+        (
+            Outcomes<TestOutcome>,
+            BTreeMap<String, Outcomes<SubtestOutcome>>,
+        ),
+    >::new();
+    for exec_report in exec_reports {
+        let ExecutionReport {
+            run_info,
+            entries,
+            unrecognized: _,
+        } = exec_report?;
+        let Some(pool) = pool_of(&run_info, pool_keys) else {
+            skew.num_runs_without_pool += 1;
+            continue;
+        };
+        let RunInfo {
+            platform,
+            build_profile,
+            subsuite: _,
+            properties: _,
+        } = run_info;
+        let config = (platform, build_profile);
+        *skew
+            .runs_by_pool
+            .entry(config)
+            .or_default()
+            .entry(pool.to_owned())
+            .or_default() += 1;
+
+        for entry in entries {
+            let TestExecutionEntry {
+                test_name,
+                subsuite: _,
+                message: _,
+                result,
+            } = entry;
+            let test_path = TestPath::from_execution_report(browser, &test_name)
+                .unwrap()
+                .into_owned();
+            let (outcome, subtests) = match result {
+                TestExecutionResult::Complete { outcome, subtests } => (outcome, subtests),
+                TestExecutionResult::JobMaybeTimedOut { subtests } => {
+                    (TestOutcome::Timeout, subtests)
+                }
+            };
+            let (test_outcomes, subtest_outcomes) = tests.entry(test_path).or_default();
+            accumulate(test_outcomes, config, pool, outcome);
+            for subtest in subtests {
+                let outcomes = subtest_outcomes
+                    .entry(subtest.subtest_name.into_owned())
+                    .or_default();
+                accumulate(outcomes, config, pool, subtest.outcome);
+            }
+        }
+    }
+
+    fn disagreements<Out>(
+        outcomes: &Outcomes<Out>,
+        mut f: impl FnMut((Platform, BuildProfile), BTreeMap<String, String>),
+    ) where
+        Out: Display + EnumSetType,
+    {
+        for (config, by_pool) in outcomes {
+            let mut outcomes = by_pool.values();
+            let first = outcomes.next();
+            if outcomes.any(|expected| Some(expected) != first) {
+                let rendered = by_pool
+                    .iter()
+                    .map(|(pool, expected)| (pool.clone(), expected.to_string()))
+                    .collect();
+                f(*config, rendered);
+            }
+        }
+    }
+
+    for (test_path, (test_outcomes, subtest_outcomes)) in tests {
+        let mut record = |subtest: Option<&String>, config, outcomes_by_pool| {
+            skew.disagreements
+                .entry(config)
+                .or_default()
+                .push(PoolDisagreement {
+                    test: test_path.clone(),
+                    subtest: subtest.cloned(),
+                    outcomes_by_pool,
+                })
+        };
+        disagreements(&test_outcomes, |config, outcomes| {
+            record(None, config, outcomes)
+        });
+        for (subtest_name, outcomes) in &subtest_outcomes {
+            disagreements(outcomes, |config, outcomes| {
+                record(Some(subtest_name), config, outcomes)
+            });
+        }
+    }
+    Ok(skew)
+}
+
+impl Display for PoolSkew {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            runs_by_pool,
+            num_runs_without_pool,
+            disagreements,
+        } = self;
+
+        let num_pools = runs_by_pool
+            .values()
+            .flat_map(|by_pool| by_pool.keys())
+            .collect::<BTreeSet<_>>()
+            .len();
+        let num_runs = runs_by_pool
+            .values()
+            .flat_map(|by_pool| by_pool.values())
+            .sum::<usize>();
+        writeln!(
+            f,
+            "Compared {num_runs} run(s) across {num_pools} worker pool(s)."
+        )?;
+        if *num_runs_without_pool > 0 {
+            writeln!(
+                f,
+                "Skipped {num_runs_without_pool} run(s) with no worker pool in their `run_info`."
+            )?;
+        }
+        if disagreements.is_empty() {
+            writeln!(f)?;
+            return writeln!(f, "No outcomes differed between worker pools.");
+        }
+
+        for ((platform, build_profile), disagreements) in disagreements {
+            writeln!(f)?;
+            writeln!(f, "## {platform:?} {build_profile:?}")?;
+            writeln!(f)?;
+            let pools = &runs_by_pool[&(*platform, *build_profile)];
+            write!(f, "Pools:")?;
+            for (idx, (pool, num_runs)) in pools.iter().enumerate() {
+                let sep = if idx == 0 { " " } else { ", " };
+                write!(f, "{sep}`{pool}` ({num_runs} run(s))")?;
+            }
+            writeln!(f)?;
+            writeln!(f)?;
+            for disagreement in disagreements {
+                let PoolDisagreement {
+                    test,
+                    subtest,
+                    outcomes_by_pool,
+                } = disagreement;
+                write!(f, "* `{}`", test.test_name())?;
+                if let Some(subtest) = subtest {
+                    write!(f, " › `{subtest}`")?;
+                }
+                write!(f, ":")?;
+                for (idx, (pool, outcomes)) in outcomes_by_pool.iter().enumerate() {
+                    let sep = if idx == 0 { " " } else { ", " };
+                    write!(f, "{sep}`{pool}` → `{outcomes}`")?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn disagreements_between_pools() {
+    use std::path::Path;
+
+    use crate::report::ParseOptions;
+
+    let report = |pool: Option<&str>, outcome: &str| {
+        let pool = pool
+            .map(|pool| format!(r#""worker_pool": "{pool}","#))
+            .unwrap_or_default();
+        format!(
+            r#"{{
+                "run_info": {{
+                    {pool}
+                    "os": "win",
+                    "processor": "x86_64",
+                    "win11_2009": true,
+                    "debug": false
+                }},
+                "results": [
+                    {{
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,a:*",
+                        "status": "OK",
+                        "subtests": [
+                            {{ "name": ":skewed", "status": "{outcome}" }},
+                            {{ "name": ":stable", "status": "PASS" }}
+                        ]
+                    }}
+                ]
+            }}"#
+        )
+    };
+    let reports = [
+        report(Some("gpu-nvidia-old"), "FAIL"),
+        report(Some("gpu-nvidia-old"), "FAIL"),
+        report(Some("gpu-nvidia-new"), "PASS"),
+        report(None, "TIMEOUT"),
+    ];
+    let runs = reports.iter().map(|contents| {
+        ExecutionReport::from_json(
+            Path::new("wptreport.json"),
+            contents,
+            &ParseOptions::default(),
+        )
+    });
+
+    let pool_keys = DEFAULT_POOL_KEYS
+        .iter()
+        .map(|key| key.to_string())
+        .collect::<Vec<_>>();
+    let skew = pool_skew(Browser::Firefox, runs, &pool_keys).unwrap();
+    insta::assert_snapshot!(skew, @r###"
+    Compared 3 run(s) across 2 worker pool(s).
+    Skipped 1 run(s) with no worker pool in their `run_info`.
+
+    ## Windows Optimized
+
+    Pools: `gpu-nvidia-new` (1 run(s)), `gpu-nvidia-old` (2 run(s))
+
+    * `cts.https.html?q=webgpu:api,a:*` › `:skewed`: `gpu-nvidia-new` → `PASS`, `gpu-nvidia-old` → `FAIL`
+    "###);
+}
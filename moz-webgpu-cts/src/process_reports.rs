@@ -1,23 +1,4237 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::{self, Debug, Display, Formatter},
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{self, AtomicBool, AtomicUsize},
+        Arc,
+    },
+};
 
+use camino::Utf8PathBuf;
+use chrono::NaiveDate;
+use clap::ValueEnum;
 use enumset::EnumSetType;
+use format::lazy_format;
+use indexmap::IndexMap;
+use indicatif::{ParallelProgressIterator, ProgressIterator};
+use rayon::prelude::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use strum::IntoEnumIterator;
+use tracing::{field, Span};
+use whippit::metadata::SectionHeader;
 
 use crate::{
-    metadata::{BuildProfile, Platform, SubtestOutcome, TestOutcome, TestProps},
-    shared::Expected,
+    checkout::{parse_metadata, read_and_parse_all_metadata},
+    error::Error,
+    filesystem::FileSystem,
+    metadata::{
+        condition_mismatches, format_file, format_test, BuildProfile, File, FileProps, Platform,
+        RunInfoValue, RunInfoVariant, Subtest, SubtestOutcome, Test, TestOutcome, TestProps,
+    },
+    never_modify::NeverModifyList,
+    progress,
+    report::{
+        extract_reports, stream_all_from_json, summarize_runs, ExecutionReport, ParseOptions,
+        PlatformOverride, ReportFile, ReportFormat, RunInfo, RunPart, SubtestExecutionResult,
+        TestExecutionEntry, TestExecutionResult, UnknownPlatform, Unrecognized, SUMMARY_VERSION,
+    },
+    resume::{PendingWrite, RelocatedTest, WriteManifest},
+    shared::{Browser, Expected, FullyExpandedExpectedPropertyValue, TestPath},
+    strategy::{
+        ConfigOutcomes, Merge, PruneStaleIntermittents, PruneUnobserved, ReconciliationStrategy,
+        ResetAll, ResetContradictory, ResetTimeouts, ResolveWith,
+    },
+    test_pattern::TestPattern,
 };
 
 #[derive(Debug, Default)]
-pub(crate) struct Entry<Out>
+pub struct Entry<Out>
 where
     Out: EnumSetType,
 {
     pub meta_props: Option<TestProps<Out>>,
     pub reported: BTreeMap<Platform, BTreeMap<BuildProfile, Expected<Out>>>,
+    /// Like `reported`, but for runs in a [`RunInfoVariant`] other than the default (empty) one.
+    pub reported_by_variant:
+        BTreeMap<RunInfoVariant, BTreeMap<Platform, BTreeMap<BuildProfile, Expected<Out>>>>,
 }
 
 #[derive(Debug, Default)]
-pub(crate) struct TestEntry {
+pub struct TestEntry {
     pub entry: Entry<TestOutcome>,
     pub subtests: BTreeMap<String, Entry<SubtestOutcome>>,
 }
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ReportProcessingPreset {
+    /// alias: `new-fx`
+    #[default]
+    #[value(alias("new-fx"))]
+    ResetContradictory,
+    /// alias: `same-fx`
+    #[value(alias("same-fx"))]
+    Merge,
+    ResetAll,
+    /// Replace expected outcomes with those observed for configurations that reports were
+    /// provided for, and leave other configurations alone. Useful for pruning intermittent
+    /// outcomes that no longer occur, given a complete set of reports.
+    PruneUnobserved,
+    /// Replace expected outcomes with those observed only where either involves `TIMEOUT` or
+    /// `NOTRUN`, leaving other expectations alone. Useful after changing the runner's timeout
+    /// budget.
+    ResetTimeouts,
+    /// Remove outcomes of intermittent expectations that weren't observed, collapsing them to
+    /// permanent ones where possible, and leave other expectations alone. The inverse of
+    /// `merge`, useful after fixes land.
+    PruneStaleIntermittents,
+}
+
+impl ReportProcessingPreset {
+    /// Returns the [`ReconciliationStrategy`] implementing this preset.
+    pub fn strategy(self) -> Arc<dyn ReconciliationStrategy> {
+        match self {
+            Self::ResetContradictory => Arc::new(ResetContradictory),
+            Self::Merge => Arc::new(Merge),
+            Self::ResetAll => Arc::new(ResetAll),
+            Self::PruneUnobserved => Arc::new(PruneUnobserved),
+            Self::ResetTimeouts => Arc::new(ResetTimeouts),
+            Self::PruneStaleIntermittents => Arc::new(PruneStaleIntermittents),
+        }
+    }
+}
+
+/// Options for [`reconcile`].
+#[derive(Clone, Debug)]
+pub struct ReconcileOptions {
+    /// The heuristic for resolving differences between current metadata and reports. Defaults to
+    /// that of [`ReportProcessingPreset::default`].
+    pub strategy: Arc<dyn ReconciliationStrategy>,
+    /// Strategies used instead of `strategy` for tests matching a pattern, and their subtests.
+    /// The first matching pattern wins.
+    pub strategy_overrides: Vec<(TestPattern, Arc<dyn ReconciliationStrategy>)>,
+    /// Tests whose metadata is kept exactly as it is, including its location. They are not added
+    /// if they have no metadata, and are listed in [`ReconciliationSummary::protected_tests`].
+    pub never_modify: NeverModifyList,
+    /// If set, sections with a `disabled-until` date on or before this have their disable
+    /// removed, and their expected outcomes reset to those reported, as if with [`ResetAll`].
+    /// They are listed in
+    /// [`ReconciliationSummary::reenabled_tests`].
+    pub reenable_expired_as_of: Option<NaiveDate>,
+    /// Tests whose sections, in the test itself or its subtests, have their disable removed and
+    /// their expected outcomes reset to those reported, as with `reenable_expired_as_of`.
+    pub reenable: Vec<TestPattern>,
+    /// If set, `reenable` only removes the parts of `disabled` whose reason refers to this bug;
+    /// see [`TestProps::enable_for_bug`]. Sections disabled for other reasons stay disabled.
+    pub reenable_bug: Option<u64>,
+    /// If set, only tests matching one of these patterns are reconciled. All other tests are
+    /// kept exactly as they are, without being listed in the [`ReconciliationSummary`].
+    pub only_tests: Option<Vec<TestPattern>>,
+    /// If set, only a sample of tests found in reports are reconciled, as if with `only_tests`.
+    pub sample: Option<Sample>,
+    /// If non-empty, runs on other platforms are ignored, as if they weren't in reports. See
+    /// `only_build_profiles`.
+    pub only_platforms: Vec<Platform>,
+    /// If non-empty, runs with other build profiles are ignored, as if they weren't in reports.
+    /// Expectations in ignored configurations are kept as they are, whatever the strategy, as are
+    /// tests (and their expectations in selected configurations) missing from runs that weren't
+    /// ignored, since they may only be run in ignored configurations.
+    pub only_build_profiles: Vec<BuildProfile>,
+    /// The browser whose checkout metadata is reconciled in, which determines where tests found
+    /// in reports have their metadata.
+    pub browser: Browser,
+    /// `run_info` keys, besides `subsuite`, whose values in reports distinguish runs whose
+    /// expectations are reconciled separately, i.e., `fission` or `swgl`. Expectations of such
+    /// runs are written with conditions on these keys, i.e., `if fission and os == "win": FAIL`.
+    pub run_info_keys: BTreeSet<String>,
+    /// If set, tests whose reconciled expected outcome is a permanent `CRASH` in some selected
+    /// configuration are disabled there, with this as the reason given by `disabled` (i.e., a bug
+    /// URL). Only test-level expectations without conditions on `run_info_keys` are considered.
+    /// Disabled tests are listed in [`ReconciliationSummary::disabled_tests`].
+    pub disable_perma_crashes: Option<String>,
+}
+
+/// A subset of tests to process from reports, for quickly trying out options against a large set
+/// of reports. See [`ReconcileOptions::sample`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sample {
+    /// A pseudo-random fraction of tests, from 0 to 1. The same tests are sampled from every
+    /// report, and in every invocation.
+    Fraction(f64),
+    /// The first `N` tests found in reports, in the order reports are given.
+    Count(usize),
+}
+
+impl FromStr for Sample {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(count) = s.parse() {
+            return Ok(Self::Count(count));
+        }
+        match s.parse() {
+            Ok(fraction) if (0.0..=1.0).contains(&fraction) => Ok(Self::Fraction(fraction)),
+            _ => Err(format!(
+                "expected a fraction from 0 to 1, or a whole number of tests, got {s:?}"
+            )),
+        }
+    }
+}
+
+/// The tests admitted so far by a [`Sample`], keyed by CTS query, or by test URL path for tests
+/// outside of the CTS, so that relocated tests are sampled consistently.
+#[derive(Debug)]
+struct Sampled {
+    sample: Sample,
+    tests: HashSet<String>,
+}
+
+impl Sampled {
+    fn admit(&mut self, key: String) -> bool {
+        let Self { sample, tests } = self;
+        if tests.contains(&key) {
+            return true;
+        }
+        let admitted = match *sample {
+            Sample::Fraction(fraction) => {
+                let mut hasher = StableHasher::default();
+                key.hash(&mut hasher);
+                (hasher.finish() as f64 / u64::MAX as f64) < fraction
+            }
+            Sample::Count(count) => tests.len() < count,
+        };
+        if admitted {
+            tests.insert(key);
+        }
+        admitted
+    }
+}
+
+/// A [`Hasher`] whose output depends only on what's hashed (64-bit FNV-1a), unlike that of
+/// [`std::collections::hash_map::DefaultHasher`], which may change between Rust releases. Used
+/// for hashes that must agree between invocations, like those picking a [`Sample::Fraction`] of
+/// tests, or naming files in [`ParseOptions::summary_cache`].
+#[derive(Debug)]
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    // N.B.: Lengths of slices and strings are hashed as `usize`s, whose width differs between
+    // targets.
+    fn write_usize(&mut self, i: usize) {
+        self.write(&(i as u64).to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for ReconcileOptions {
+    fn default() -> Self {
+        Self {
+            strategy: ReportProcessingPreset::default().strategy(),
+            strategy_overrides: Default::default(),
+            never_modify: Default::default(),
+            reenable_expired_as_of: Default::default(),
+            reenable: Default::default(),
+            reenable_bug: Default::default(),
+            only_tests: Default::default(),
+            sample: Default::default(),
+            only_platforms: Default::default(),
+            only_build_profiles: Default::default(),
+            browser: Default::default(),
+            run_info_keys: Default::default(),
+            disable_perma_crashes: Default::default(),
+        }
+    }
+}
+
+/// The result of [`reconcile`]: new metadata to be written, and a summary of how it differs from
+/// the metadata it was computed from.
+#[derive(Debug)]
+pub struct Reconciliation {
+    /// New metadata files, keyed by absolute path. Files with no tests have had all of their tests
+    /// removed, and should be deleted.
+    pub files: BTreeMap<PathBuf, File>,
+    /// Outcomes reported for tests in `files`, keyed by path and test name, for showing alongside
+    /// changes in [`Self::review`].
+    pub reported: BTreeMap<(PathBuf, SectionHeader), ReportedOutcomes>,
+    pub summary: ReconciliationSummary,
+}
+
+/// Outcomes reported for a test and its subtests, by configuration. Only runs in the default
+/// [`RunInfoVariant`] are included.
+#[derive(Clone, Debug, Default)]
+pub struct ReportedOutcomes {
+    pub test: BTreeMap<Platform, BTreeMap<BuildProfile, Expected<TestOutcome>>>,
+    pub subtests: BTreeMap<
+        SectionHeader,
+        BTreeMap<Platform, BTreeMap<BuildProfile, Expected<SubtestOutcome>>>,
+    >,
+}
+
+#[derive(Debug, Default)]
+pub struct ReconciliationSummary {
+    /// The number of runs processed. Report files containing several merged runs count once per
+    /// run.
+    pub num_reports: usize,
+    /// The number of runs ignored because their platform was not recognized. Not included in
+    /// `num_reports`.
+    pub num_skipped_runs: usize,
+    /// The number of runs ignored because of [`ReconcileOptions::only_platforms`] or
+    /// [`ReconcileOptions::only_build_profiles`]. Not included in `num_reports`.
+    pub num_ignored_runs: usize,
+    /// The number of entries in runs counted by `num_ignored_runs`.
+    pub num_ignored_entries: usize,
+    /// The number of entries for tests left out of [`ReconcileOptions::sample`].
+    pub num_unsampled_entries: usize,
+    /// Configurations covered by at least one processed run.
+    pub configurations: BTreeSet<(Platform, BuildProfile)>,
+    /// Tests found in reports, but not in metadata.
+    pub new_tests: Vec<TestPath<'static>>,
+    /// Tests whose metadata was removed because they were not found in any report.
+    pub removed_tests: Vec<TestPath<'static>>,
+    /// Tests with metadata that no processed run had an entry for, including those in
+    /// `removed_tests`, and those whose metadata was kept (i.e., because of the preset in use, or
+    /// because runs in some configurations were ignored). Empty if no runs were processed.
+    pub orphaned_tests: Vec<TestPath<'static>>,
+    pub relocated_tests: Vec<Relocation>,
+    /// Tests with a disable, in the test itself or one of its subtests, that was removed because
+    /// its `disabled-until` date had passed or the test matched [`ReconcileOptions::reenable`].
+    pub reenabled_tests: Vec<TestPath<'static>>,
+    /// Tests disabled where they're expected to permanently crash; see
+    /// [`ReconcileOptions::disable_perma_crashes`].
+    pub disabled_tests: Vec<TestPath<'static>>,
+    /// Tests matched by the [`NeverModifyList`] given to [`reconcile`], whose metadata was left
+    /// untouched.
+    pub protected_tests: Vec<TestPath<'static>>,
+    /// Metadata files that did not exist before reconciliation.
+    pub new_files: Vec<PathBuf>,
+    /// Metadata files that no longer contain any tests.
+    pub emptied_files: Vec<PathBuf>,
+    pub warnings: Vec<Warning>,
+}
+
+impl ReconciliationSummary {
+    /// Accounts for report data that was read, but left out of reconciliation.
+    pub fn exclusions(&self) -> Exclusions {
+        let Self {
+            num_reports: _,
+            num_skipped_runs,
+            num_ignored_runs,
+            num_ignored_entries,
+            num_unsampled_entries,
+            configurations: _,
+            new_tests: _,
+            removed_tests: _,
+            orphaned_tests: _,
+            relocated_tests: _,
+            reenabled_tests: _,
+            disabled_tests: _,
+            protected_tests: _,
+            new_files: _,
+            emptied_files: _,
+            warnings,
+        } = self;
+        let count_warnings =
+            |pred: fn(&Warning) -> bool| warnings.iter().filter(|w| pred(w)).count();
+        Exclusions {
+            unsupported_files: count_warnings(|w| matches!(w, Warning::SkippedReportFile { .. })),
+            duplicate_files: count_warnings(|w| matches!(w, Warning::DuplicateReport { .. })),
+            unknown_platform_runs: *num_skipped_runs,
+            ignored_runs: *num_ignored_runs,
+            ignored_entries: *num_ignored_entries,
+            unsampled_entries: *num_unsampled_entries,
+        }
+    }
+
+    /// Returns the configurations that no processed run covered, in which tests in
+    /// `orphaned_tests` may still run.
+    pub fn uncovered_configurations(&self) -> impl Iterator<Item = (Platform, BuildProfile)> + '_ {
+        Platform::iter()
+            .flat_map(|platform| {
+                BuildProfile::iter().map(move |build_profile| (platform, build_profile))
+            })
+            .filter(|config| !self.configurations.contains(config))
+    }
+}
+
+/// Report data left out of reconciliation, by why it was left out; see
+/// [`ReconciliationSummary::exclusions`].
+///
+/// Each kind of data is counted in the most specific unit available: entries of runs that weren't
+/// parsed (i.e., those on unknown platforms) can't be counted.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Exclusions {
+    /// Files that were not in a supported format.
+    pub unsupported_files: usize,
+    /// Files with the same contents as one read before them.
+    pub duplicate_files: usize,
+    /// Runs on a platform that was not recognized.
+    pub unknown_platform_runs: usize,
+    /// Runs in configurations not selected with [`ReconcileOptions::only_platforms`] or
+    /// [`ReconcileOptions::only_build_profiles`], and the entries in them.
+    pub ignored_runs: usize,
+    /// Entries in `ignored_runs`.
+    pub ignored_entries: usize,
+    /// Entries for tests left out of [`ReconcileOptions::sample`].
+    pub unsampled_entries: usize,
+}
+
+impl Exclusions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Display for Exclusions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            unsupported_files,
+            duplicate_files,
+            unknown_platform_runs,
+            ignored_runs,
+            ignored_entries,
+            unsampled_entries,
+        } = *self;
+        if self.is_empty() {
+            return write!(f, "nothing");
+        }
+        let reasons = [
+            (
+                unsupported_files,
+                "file(s) in an unsupported format".to_owned(),
+            ),
+            (duplicate_files, "duplicate file(s)".to_owned()),
+            (
+                unknown_platform_runs,
+                "run(s) on an unrecognized platform".to_owned(),
+            ),
+            (
+                ignored_runs,
+                format!(
+                    "run(s) with {ignored_entries} entries in configurations not selected with \
+                    `--only-platform` or `--only-build-profile`"
+                ),
+            ),
+            (
+                unsampled_entries,
+                "entries for tests left out of `--sample`".to_owned(),
+            ),
+        ];
+        let mut first = true;
+        for (count, reason) in reasons {
+            if count == 0 {
+                continue;
+            }
+            if !first {
+                write!(f, ", ")?;
+            }
+            first = false;
+            write!(f, "{count} {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A CTS test whose metadata was found in a different file than the one its reported execution
+/// implies. Its section is moved to the file reported, and removed from the one it was found in.
+#[derive(Clone, Debug)]
+pub struct Relocation {
+    pub from: TestPath<'static>,
+    pub to: TestPath<'static>,
+}
+
+/// A non-fatal problem found while running [`reconcile`].
+#[derive(Clone, Debug)]
+pub enum Warning {
+    /// A test had sections in more than one metadata file; see [`crate::dedupe`].
+    DuplicateMetadataTest {
+        test: TestPath<'static>,
+        /// Where the section before `test`'s was found.
+        previous: TestPath<'static>,
+    },
+    DuplicateMetadataSubtest {
+        test: TestPath<'static>,
+        subtest: String,
+    },
+    DuplicateReportedCtsPath {
+        older: TestPath<'static>,
+        newer: TestPath<'static>,
+    },
+    /// Runs in a report were for a platform that was not recognized, and were ignored.
+    SkippedUnknownPlatform {
+        path: PathBuf,
+        run_info: UnknownPlatform,
+        num_runs: usize,
+    },
+    /// A file given as a report was not in a supported format, and was ignored.
+    SkippedReportFile {
+        path: PathBuf,
+        format: Option<ReportFormat>,
+    },
+    /// A report had the same contents as one read before it, and was ignored.
+    DuplicateReport { path: PathBuf, original: PathBuf },
+    /// Data in one or more reports was not recognized, and was ignored. Reported once per kind of
+    /// data.
+    UnrecognizedReportContents(Unrecognized),
+    /// A test present in metadata was not found in any report, but was kept because of the
+    /// [`ReportProcessingPreset`] in use.
+    NotReported { test: TestPath<'static> },
+    /// A pattern in the [`NeverModifyList`] given to [`reconcile`] did not match any test.
+    UnusedNeverModifyPattern { pattern: String },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateMetadataTest { test, previous } => write!(
+                f,
+                concat!(
+                    "duplicate entry for {:?} in {} (also in {}), ",
+                    "discarding previous entries with this and further dupes; ",
+                    "use `fixup --dedupe` to merge them"
+                ),
+                test,
+                test.rel_metadata_path(),
+                previous.rel_metadata_path(),
+            ),
+            Self::DuplicateMetadataSubtest { test, subtest } => write!(
+                f,
+                concat!(
+                    "duplicate subtest in {:?} named {:?}, ",
+                    "discarding previous entries with this and further dupes"
+                ),
+                test, subtest
+            ),
+            Self::DuplicateReportedCtsPath { older, newer } => write!(
+                f,
+                concat!(
+                    "found test execution entry containing the same CTS test path as another, ",
+                    "discarding previous entries with this and further dupes; entries:\n",
+                    "older: {:#?}\n",
+                    "newer: {:#?}\n",
+                ),
+                older, newer
+            ),
+            Self::SkippedUnknownPlatform {
+                path,
+                run_info,
+                num_runs,
+            } => write!(
+                f,
+                concat!(
+                    "skipping {} run(s) in {} for a platform not recognized by this tool ",
+                    "({}); use `--platform-map` to attribute them to a platform"
+                ),
+                num_runs,
+                path.display(),
+                run_info
+            ),
+            Self::SkippedReportFile { path, format } => match format {
+                Some(format) => write!(
+                    f,
+                    "skipping {}, since {format} reports are not supported",
+                    path.display()
+                ),
+                None => write!(
+                    f,
+                    "skipping {}, since it's not in a recognized report format",
+                    path.display()
+                ),
+            },
+            Self::DuplicateReport { path, original } => write!(
+                f,
+                "skipping {}, since it has the same contents as {}",
+                path.display(),
+                original.display()
+            ),
+            Self::UnrecognizedReportContents(unrecognized) => {
+                write!(f, "ignored unrecognized {unrecognized} in reports")
+            }
+            Self::NotReported { test } => write!(f, "no entries found in reports for {test:?}"),
+            Self::UnusedNeverModifyPattern { pattern } => {
+                write!(f, "never-modify pattern `{pattern}` did not match any test")
+            }
+        }
+    }
+}
+
+/// A change to the metadata of a single test proposed by a [`Reconciliation`], presented for
+/// review by [`Reconciliation::review`]. Renders as the test's old and new sections, with lines
+/// prefixed by `-` and `+` respectively.
+#[derive(Debug)]
+pub struct TestChange<'a> {
+    pub path: &'a Path,
+    pub name: &'a SectionHeader,
+    /// The part of the test this change is limited to. `old` and `new` contain only that part.
+    pub part: TestPart<'a>,
+    /// The test's current metadata, if it has any.
+    pub old: Option<&'a Test>,
+    /// The test's new metadata, or `None` if it is to be removed.
+    pub new: Option<&'a Test>,
+    /// The outcomes reported for the test, if it was found in any report.
+    pub reported: Option<&'a ReportedOutcomes>,
+}
+
+/// The part of a test that a [`TestChange`] is limited to. A change to a whole test can be
+/// [split](ReviewDecision::Split) into changes to its own properties and to each of its subtests.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TestPart<'a> {
+    Whole,
+    Properties,
+    Subtest(&'a SectionHeader),
+}
+
+impl Display for TestChange<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            path,
+            name,
+            part,
+            old,
+            new,
+            reported,
+        } = self;
+        match part {
+            TestPart::Whole => writeln!(f, "{}", path.display())?,
+            TestPart::Properties => writeln!(f, "{} (test properties only)", path.display())?,
+            TestPart::Subtest(subtest) => {
+                writeln!(f, "{} (subtest [{}] only)", path.display(), subtest.0)?
+            }
+        }
+        for (prefix, test) in [('-', old), ('+', new)] {
+            if let Some(test) = test {
+                for line in format_test(name, test).to_string().lines() {
+                    writeln!(f, "{prefix}{line}")?;
+                }
+            }
+        }
+
+        let Some(ReportedOutcomes {
+            test: reported_test,
+            subtests: reported_subtests,
+        }) = reported
+        else {
+            return writeln!(f, "not reported");
+        };
+        writeln!(f, "reported:")?;
+        fn write_outcomes<Out>(
+            f: &mut fmt::Formatter<'_>,
+            prefix: &dyn Display,
+            reported: &BTreeMap<Platform, BTreeMap<BuildProfile, Expected<Out>>>,
+        ) -> fmt::Result
+        where
+            Out: Display + EnumSetType,
+        {
+            for (platform, by_build_profile) in reported {
+                for (build_profile, expected) in by_build_profile {
+                    writeln!(f, "  {prefix}{platform:?} {build_profile:?}: {expected}")?;
+                }
+            }
+            Ok(())
+        }
+        if matches!(part, TestPart::Whole | TestPart::Properties) {
+            write_outcomes(f, &"", reported_test)?;
+        }
+        let subtest_changed = |subtest| {
+            let properties = |test: Option<&'_ Test>| {
+                test.and_then(|test| test.subtests.get(subtest))
+                    .map(|subtest| subtest.properties.clone())
+            };
+            properties(*old) != properties(*new)
+        };
+        for (subtest, reported) in reported_subtests {
+            let shown = match part {
+                TestPart::Whole => subtest_changed(subtest),
+                TestPart::Properties => false,
+                TestPart::Subtest(part) => *part == subtest,
+            };
+            if shown {
+                write_outcomes(f, &lazy_format!("[{}] ", subtest.0), reported)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What to do with a [`TestChange`] under review.
+#[derive(Debug)]
+pub enum ReviewDecision {
+    Accept,
+    /// Keep the test's current metadata.
+    Skip,
+    /// Use the given metadata instead, or remove the test if `None`. When reviewing only part of
+    /// a test, only that part of the given metadata is used, and `None` removes only that part.
+    Replace(Option<Test>),
+    /// Review the test's own properties and each of its subtests as separate changes. Only
+    /// applies to changes to [whole](TestPart::Whole) tests that are neither added nor removed;
+    /// otherwise, the change is accepted.
+    Split,
+}
+
+/// How many metadata files [`Reconciliation::write_to`] would change, by kind of change.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Impact {
+    pub num_rewritten: usize,
+    pub num_created: usize,
+    pub num_removed: usize,
+    pub num_unchanged: usize,
+}
+
+impl Impact {
+    /// The fraction of existing files that would be rewritten or removed, from 0 to 1.
+    pub fn fraction_changed(&self) -> f64 {
+        let Self {
+            num_rewritten,
+            num_created: _,
+            num_removed,
+            num_unchanged,
+        } = *self;
+        let num_existing = num_rewritten + num_removed + num_unchanged;
+        if num_existing == 0 {
+            0.0
+        } else {
+            (num_rewritten + num_removed) as f64 / num_existing as f64
+        }
+    }
+}
+
+impl Display for Impact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            num_rewritten,
+            num_created,
+            num_removed,
+            num_unchanged,
+        } = self;
+        write!(
+            f,
+            "{num_rewritten} file(s) rewritten, {num_created} created, and {num_removed} removed; \
+            {num_unchanged} unchanged"
+        )
+    }
+}
+
+impl Reconciliation {
+    /// Calls `decide` with each change to a test's metadata, relative to `current`, and applies
+    /// its decision. Changes are presented in order of file path, then test name.
+    pub fn review(
+        &mut self,
+        current: &IndexMap<Arc<PathBuf>, File>,
+        mut decide: impl FnMut(&TestChange<'_>) -> ReviewDecision,
+    ) {
+        let Self {
+            files,
+            reported,
+            summary: _,
+        } = self;
+        for (path, file) in files {
+            let old_tests = current.get(path).map(|file| &file.tests);
+            let names = old_tests
+                .into_iter()
+                .flat_map(|tests| tests.keys())
+                .chain(file.tests.keys())
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            for name in names {
+                let old = old_tests.and_then(|tests| tests.get(&name));
+                let new = file.tests.get(&name);
+                let render =
+                    |test: Option<&Test>| test.map(|test| format_test(&name, test).to_string());
+                if render(old) == render(new) {
+                    continue;
+                }
+                let reported = reported.get(&(path.clone(), name.clone()));
+                let decision = decide(&TestChange {
+                    path,
+                    name: &name,
+                    part: TestPart::Whole,
+                    old,
+                    new,
+                    reported,
+                });
+                let replacement = match (decision, old, new) {
+                    (ReviewDecision::Accept, _, _) => continue,
+                    (ReviewDecision::Skip, _, _) => old.cloned(),
+                    (ReviewDecision::Replace(test), _, _) => test,
+                    (ReviewDecision::Split, Some(old), Some(new)) => Some(Self::review_parts(
+                        path,
+                        &name,
+                        old,
+                        new,
+                        reported,
+                        &mut decide,
+                    )),
+                    (ReviewDecision::Split, _, _) => continue,
+                };
+                match replacement {
+                    Some(test) => {
+                        file.tests.insert(name, test);
+                    }
+                    None => {
+                        file.tests.remove(&name);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Calls `decide` with changes to the properties of test `name` and each of its subtests
+    /// separately, returning the test with the decisions applied.
+    fn review_parts(
+        path: &Path,
+        name: &SectionHeader,
+        old: &Test,
+        new: &Test,
+        reported: Option<&ReportedOutcomes>,
+        decide: &mut impl FnMut(&TestChange<'_>) -> ReviewDecision,
+    ) -> Test {
+        let mut reviewed = new.clone();
+        let render = |test: Option<&Test>| test.map(|test| format_test(name, test).to_string());
+        let only_properties = |test: &Test| Test {
+            properties: test.properties.clone(),
+            subtests: Default::default(),
+        };
+        let (old_properties, new_properties) = (only_properties(old), only_properties(new));
+        if render(Some(&old_properties)) != render(Some(&new_properties)) {
+            let decision = decide(&TestChange {
+                path,
+                name,
+                part: TestPart::Properties,
+                old: Some(&old_properties),
+                new: Some(&new_properties),
+                reported,
+            });
+            match decision {
+                ReviewDecision::Accept | ReviewDecision::Split => (),
+                ReviewDecision::Skip => reviewed.properties = old.properties.clone(),
+                ReviewDecision::Replace(test) => {
+                    reviewed.properties = test.map(|test| test.properties).unwrap_or_default()
+                }
+            }
+        }
+
+        let subtest_names = old
+            .subtests
+            .keys()
+            .chain(new.subtests.keys())
+            .collect::<BTreeSet<_>>();
+        for subtest_name in subtest_names {
+            let only_subtest = |test: &Test| {
+                let subtest = test.subtests.get(subtest_name)?;
+                Some(Test {
+                    properties: Default::default(),
+                    subtests: [(subtest_name.clone(), subtest.clone())].into(),
+                })
+            };
+            let (old_subtest, new_subtest) = (only_subtest(old), only_subtest(new));
+            if render(old_subtest.as_ref()) == render(new_subtest.as_ref()) {
+                continue;
+            }
+            let decision = decide(&TestChange {
+                path,
+                name,
+                part: TestPart::Subtest(subtest_name),
+                old: old_subtest.as_ref(),
+                new: new_subtest.as_ref(),
+                reported,
+            });
+            let replacement = match decision {
+                ReviewDecision::Accept | ReviewDecision::Split => continue,
+                ReviewDecision::Skip => old.subtests.get(subtest_name).cloned(),
+                ReviewDecision::Replace(test) => {
+                    test.and_then(|mut test| test.subtests.remove(subtest_name))
+                }
+            };
+            match replacement {
+                Some(subtest) => {
+                    reviewed.subtests.insert(subtest_name.clone(), subtest);
+                }
+                None => {
+                    reviewed.subtests.remove(subtest_name);
+                }
+            }
+        }
+        reviewed
+    }
+
+    /// Compares new metadata against files currently in `fs`, without writing anything, to
+    /// estimate the impact of [`Self::write_to`].
+    pub fn impact(&self, fs: &dyn FileSystem) -> Result<Impact, Error> {
+        let mut impact = Impact::default();
+        for (path, file) in &self.files {
+            let current = match fs.read_to_string(path) {
+                Ok(contents) => Some(contents),
+                Err(Error::ReadFile { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                    None
+                }
+                Err(e) => return Err(e),
+            };
+            let counter = match current {
+                None if file.tests.is_empty() => continue,
+                None => &mut impact.num_created,
+                Some(_) if file.tests.is_empty() => &mut impact.num_removed,
+                Some(current) if current == format_file(file).to_string() => {
+                    &mut impact.num_unchanged
+                }
+                Some(_) => &mut impact.num_rewritten,
+            };
+            *counter += 1;
+        }
+        Ok(impact)
+    }
+
+    /// Renders new metadata files into a [`WriteManifest`] with all writes pending. Files that are
+    /// now empty are to be removed.
+    pub fn into_write_manifest(self) -> WriteManifest {
+        let Self {
+            files,
+            reported: _,
+            summary,
+        } = self;
+        WriteManifest {
+            completed: Vec::new(),
+            pending: files
+                .into_iter()
+                .map(|(path, file)| PendingWrite {
+                    contents: (!file.tests.is_empty()).then(|| format_file(&file).to_string()),
+                    path,
+                })
+                .collect(),
+            relocations: summary
+                .relocated_tests
+                .iter()
+                .map(|Relocation { from, to }| RelocatedTest {
+                    test: to.runner_url_path().to_string(),
+                    from: from.rel_metadata_path().to_string().into(),
+                    to: to.rel_metadata_path().to_string().into(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Writes new metadata files to `fs`, and removes files that are now empty. Writes to all files
+    /// are attempted, even if some fail. See [`WriteManifest::write`] for details, and to recover
+    /// from failures.
+    pub fn write_to(self, fs: &dyn FileSystem, cancelled: &AtomicBool) -> Result<(), Error> {
+        self.into_write_manifest().write(fs, cancelled)
+    }
+}
+
+/// Reads all metadata in `gecko_checkout` and reports at `exec_report_paths` from `fs`, and
+/// [`reconcile`]s them.
+///
+/// Reports are parsed according to `report_options`; see [`ExecutionReport::from_json`].
+///
+/// If `cancelled` is set while reports are being read, no further reports are read, and
+/// [`Error::Cancelled`] is returned.
+pub fn update_expected(
+    fs: &dyn FileSystem,
+    gecko_checkout: &Path,
+    exec_report_paths: Vec<PathBuf>,
+    options: &ReconcileOptions,
+    report_options: &ParseOptions,
+    cancelled: &AtomicBool,
+) -> Result<Reconciliation, Error> {
+    let meta_files_by_path = {
+        let span = tracing::info_span!("read_metadata", num_files = field::Empty).entered();
+        let files = read_and_parse_all_metadata(fs, gecko_checkout, options.browser)
+            .collect::<Result<IndexMap<_, _>, _>>()?;
+        span.record("num_files", files.len());
+        files
+    };
+
+    if report_options.stream {
+        return update_expected_streaming(
+            fs,
+            gecko_checkout,
+            meta_files_by_path,
+            exec_report_paths,
+            options,
+            report_options,
+            cancelled,
+        );
+    }
+
+    let ReportFiles { mut files, skipped } = read_report_files(fs, exec_report_paths, cancelled)?;
+    let ParsedReports {
+        runs: exec_reports,
+        unknown_platforms,
+    } = match &report_options.summary_cache {
+        Some(cache_dir) => parse_report_files_with_cache(fs, cache_dir, &mut files, report_options),
+        None => parse_report_files(&files, report_options),
+    };
+
+    let mut reconciliation = reconcile(gecko_checkout, meta_files_by_path, exec_reports, options)?;
+    record_skipped(
+        &mut reconciliation.summary,
+        unknown_platforms
+            .into_iter()
+            .map(|((path, run_info), num_runs)| ((path.to_owned(), run_info), num_runs)),
+        skipped,
+    );
+    Ok(reconciliation)
+}
+
+/// Like [`update_expected`] with [`ParseOptions::stream`] set: each report's entries are fed to
+/// [`reconcile`] as they are parsed (see [`stream_all_from_json`]), and each report is dropped
+/// before the next is read.
+fn update_expected_streaming(
+    fs: &dyn FileSystem,
+    gecko_checkout: &Path,
+    meta_files_by_path: IndexMap<Arc<PathBuf>, File>,
+    exec_report_paths: Vec<PathBuf>,
+    options: &ReconcileOptions,
+    report_options: &ParseOptions,
+    cancelled: &AtomicBool,
+) -> Result<Reconciliation, Error> {
+    let mut skipped = Vec::new();
+    let mut unknown_platforms = BTreeMap::<(PathBuf, UnknownPlatform), usize>::new();
+    let mut duplicates = DuplicateReports::<(PathBuf, PathBuf)>::default();
+    let exec_report_paths = dedupe_paths(exec_report_paths);
+    let progress = progress::bar("streaming reports", Some(exec_report_paths.len()));
+    let mut reconciliation =
+        reconcile_with(gecko_checkout, meta_files_by_path, options, |record| {
+            for source in exec_report_paths.into_iter().progress_with(progress) {
+                if cancelled.load(atomic::Ordering::Relaxed) {
+                    return Err(Error::Cancelled);
+                }
+                let _entered =
+                    tracing::info_span!("read_report", path = %source.display()).entered();
+                for file in extract_reports(&source, fs.read(&source)?)? {
+                    let ReportFile {
+                        path,
+                        format,
+                        contents,
+                    } = file;
+                    if !matches!(format, Some(ReportFormat::WptReport | ReportFormat::MozLog)) {
+                        skipped.push(Warning::SkippedReportFile { path, format });
+                        continue;
+                    }
+                    // Reports are dropped once they're streamed, so telling apart reports that
+                    // merely hash the same means reading the one seen first again.
+                    let original = duplicates.find_or_insert(
+                        &contents,
+                        (source.clone(), path.clone()),
+                        |(other_source, other_path)| {
+                            let other = extract_reports(other_source, fs.read(other_source)?)?;
+                            Ok(other.into_iter().any(|other| {
+                                other.path == *other_path && other.contents == contents
+                            }))
+                        },
+                    )?;
+                    if let Some((_source, original)) = original {
+                        tracing::debug!("skipping duplicate report {}", path.display());
+                        skipped.push(Warning::DuplicateReport {
+                            path,
+                            original: original.clone(),
+                        });
+                        continue;
+                    }
+                    for unknown in stream_all_from_json(&path, &contents, report_options, record)? {
+                        *unknown_platforms
+                            .entry((path.clone(), unknown))
+                            .or_default() += 1;
+                    }
+                }
+            }
+            Ok(())
+        })?;
+    record_skipped(&mut reconciliation.summary, unknown_platforms, skipped);
+    Ok(reconciliation)
+}
+
+/// Records runs on unknown platforms and `skipped` files in `summary`.
+fn record_skipped(
+    summary: &mut ReconciliationSummary,
+    unknown_platforms: impl IntoIterator<Item = ((PathBuf, UnknownPlatform), usize)>,
+    skipped: Vec<Warning>,
+) {
+    for ((path, run_info), num_runs) in unknown_platforms {
+        summary.num_skipped_runs += num_runs;
+        summary.warnings.push(Warning::SkippedUnknownPlatform {
+            path,
+            run_info,
+            num_runs,
+        });
+    }
+    summary.warnings.extend(skipped);
+}
+
+/// Report files read by [`read_report_files`].
+#[derive(Debug)]
+pub struct ReportFiles {
+    /// The paths and contents of reports found (i.e., `wptreport.json` files or `mozlog` action
+    /// streams), after decompressing and unpacking archives.
+    pub files: Vec<(PathBuf, String)>,
+    /// Files that were ignored because they were not in a supported format, or duplicated the
+    /// contents of another file.
+    pub skipped: Vec<Warning>,
+}
+
+/// Reads reports at `exec_report_paths` from `fs`, extracting any compressed files and archives.
+/// Reports with the same contents as one found earlier are skipped.
+///
+/// If `cancelled` is set while reports are being read, no further reports are read, and
+/// [`Error::Cancelled`] is returned.
+pub fn read_report_files(
+    fs: &dyn FileSystem,
+    exec_report_paths: Vec<PathBuf>,
+    cancelled: &AtomicBool,
+) -> Result<ReportFiles, Error> {
+    let exec_report_paths = dedupe_paths(exec_report_paths);
+    let span = tracing::info_span!(
+        "read_reports",
+        num_reports = exec_report_paths.len(),
+        num_bytes = field::Empty
+    );
+    let num_bytes = AtomicUsize::new(0);
+    let progress = progress::bar("reading reports", Some(exec_report_paths.len()));
+    let report_contents = exec_report_paths
+        .into_par_iter()
+        .progress_with(progress)
+        .map(|path| {
+            if cancelled.load(atomic::Ordering::Relaxed) {
+                return Err(Error::Cancelled);
+            }
+            let _entered =
+                tracing::info_span!(parent: &span, "read_report", path = %path.display()).entered();
+            let contents = fs.read(&path)?;
+            num_bytes.fetch_add(contents.len(), atomic::Ordering::Relaxed);
+            extract_reports(&path, contents)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    span.record("num_bytes", num_bytes.into_inner());
+    drop(span);
+
+    let mut skipped = Vec::new();
+    let files = report_contents
+        .into_iter()
+        .flatten()
+        .filter_map(|file| {
+            let ReportFile {
+                path,
+                format,
+                contents,
+            } = file;
+            match format {
+                Some(ReportFormat::WptReport | ReportFormat::MozLog) => {
+                    Some(match String::from_utf8(contents) {
+                        Ok(contents) => Ok((path, contents)),
+                        Err(e) => Err(Error::ReadFile {
+                            path,
+                            source: io::Error::new(io::ErrorKind::InvalidData, e),
+                        }),
+                    })
+                }
+                format => {
+                    skipped.push(Warning::SkippedReportFile { path, format });
+                    None
+                }
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut duplicates = DuplicateReports::<usize>::default();
+    let mut unique_files = Vec::<(PathBuf, String)>::with_capacity(files.len());
+    for (path, contents) in files {
+        let original = duplicates
+            .find_or_insert(contents.as_bytes(), unique_files.len(), |&idx| {
+                Ok(unique_files[idx].1 == contents)
+            })?
+            .copied();
+        if let Some(idx) = original {
+            tracing::debug!("skipping duplicate report {}", path.display());
+            skipped.push(Warning::DuplicateReport {
+                path,
+                original: unique_files[idx].0.clone(),
+            });
+            continue;
+        }
+        unique_files.push((path, contents));
+    }
+
+    Ok(ReportFiles {
+        files: unique_files,
+        skipped,
+    })
+}
+
+/// Drops all but the first of identical `paths`, so that a report given more than once isn't taken
+/// for a duplicate of itself.
+fn dedupe_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    paths
+        .into_iter()
+        .filter(|path| seen.insert(path.clone()))
+        .collect()
+}
+
+/// Finds reports with the same contents as one seen before them.
+///
+/// N.B.: The same report may be given more than once, i.e., via both a path and an overlapping
+/// glob, or as separately downloaded copies of the same artifact. Counting it more than once
+/// would skew reconciliation towards its outcomes.
+#[derive(Debug)]
+struct DuplicateReports<K> {
+    keys_by_hash: HashMap<u64, Vec<K>>,
+}
+
+impl<K> Default for DuplicateReports<K> {
+    fn default() -> Self {
+        Self {
+            keys_by_hash: HashMap::new(),
+        }
+    }
+}
+
+impl<K> DuplicateReports<K> {
+    /// Returns the key of a report seen before with the same `contents`, if any; otherwise,
+    /// records `contents` under `key`.
+    ///
+    /// Reports whose contents hash the same are only taken as duplicates if `is_same` confirms
+    /// that the report recorded under its key has the same `contents`.
+    fn find_or_insert(
+        &mut self,
+        contents: &[u8],
+        key: K,
+        mut is_same: impl FnMut(&K) -> Result<bool, Error>,
+    ) -> Result<Option<&K>, Error> {
+        let hash = {
+            let mut hasher = StableHasher::default();
+            contents.hash(&mut hasher);
+            hasher.finish()
+        };
+        let same_hash = self.keys_by_hash.entry(hash).or_default();
+        let mut original = None;
+        for (idx, other) in same_hash.iter().enumerate() {
+            if is_same(other)? {
+                original = Some(idx);
+                break;
+            }
+        }
+        Ok(match original {
+            Some(idx) => Some(&same_hash[idx]),
+            None => {
+                same_hash.push(key);
+                None
+            }
+        })
+    }
+}
+
+/// Runs parsed by [`parse_report_files`].
+#[derive(Debug)]
+pub struct ParsedReports<'a> {
+    /// Runs whose platform was recognized, or errors for files that could not be parsed.
+    pub runs: Vec<Result<ExecutionReport<'a>, Error>>,
+    /// The number of runs skipped per file and unrecognized platform.
+    pub unknown_platforms: BTreeMap<(&'a Path, UnknownPlatform), usize>,
+}
+
+/// Parses all runs in `files`, as read by [`read_report_files`], according to `report_options`;
+/// see [`ExecutionReport::from_json`].
+pub fn parse_report_files<'a>(
+    files: &'a [(PathBuf, String)],
+    report_options: &ParseOptions,
+) -> ParsedReports<'a> {
+    collect_parsed_reports(files, parse_each_report_file(files, report_options))
+}
+
+/// Reads and parses all runs in reports at `exec_report_paths`, for subcommands that inspect
+/// reports rather than reconciling them with metadata. Unlike [`read_report_files`] and
+/// [`parse_report_files`], skipped files and runs on unknown platforms are logged as warnings,
+/// rather than returned, and runs don't borrow from the files they were parsed from.
+pub fn read_and_parse_reports(
+    fs: &dyn FileSystem,
+    exec_report_paths: Vec<PathBuf>,
+    platform_overrides: Vec<PlatformOverride>,
+    cancelled: &AtomicBool,
+) -> Result<Vec<Result<ExecutionReport<'static>, Error>>, Error> {
+    let ReportFiles { files, skipped } = read_report_files(fs, exec_report_paths, cancelled)?;
+    for warning in skipped {
+        tracing::warn!("{warning}");
+    }
+
+    let ParsedReports {
+        runs,
+        unknown_platforms,
+    } = parse_report_files(
+        &files,
+        &ParseOptions {
+            deny_unrecognized: false,
+            platform_overrides,
+            summary_cache: None,
+            stream: false,
+            format: None,
+        },
+    );
+    for ((path, run_info), num_runs) in unknown_platforms {
+        let warning = Warning::SkippedUnknownPlatform {
+            path: path.to_owned(),
+            run_info,
+            num_runs,
+        };
+        tracing::warn!("{warning}");
+    }
+    Ok(runs
+        .into_iter()
+        .map(|run| run.map(ExecutionReport::into_owned))
+        .collect())
+}
+
+type ParsedRuns<'a> = Result<Vec<Result<ExecutionReport<'a>, UnknownPlatform>>, Error>;
+
+fn parse_each_report_file<'a>(
+    files: &'a [(PathBuf, String)],
+    report_options: &ParseOptions,
+) -> Vec<ParsedRuns<'a>> {
+    // N.B.: Parsed reports borrow from `files`, so all of them are parsed up front.
+    let span = tracing::info_span!("parse_reports");
+    files
+        .par_iter()
+        .progress_with(progress::bar("parsing reports", Some(files.len())))
+        .map(|(path, contents)| {
+            let _entered =
+                tracing::info_span!(parent: &span, "parse_report", path = %path.display())
+                    .entered();
+            ExecutionReport::all_from_json(path, contents, report_options)
+        })
+        .collect()
+}
+
+fn collect_parsed_reports<'a>(
+    files: &'a [(PathBuf, String)],
+    parsed: Vec<ParsedRuns<'a>>,
+) -> ParsedReports<'a> {
+    let mut unknown_platforms = BTreeMap::<(&Path, UnknownPlatform), usize>::new();
+    let runs = files
+        .iter()
+        .zip(parsed)
+        .flat_map(|((path, _contents), res)| match res {
+            Ok(runs) => runs
+                .into_iter()
+                .filter_map(|run| match run {
+                    Ok(run) => Some(Ok(run)),
+                    Err(unknown) => {
+                        *unknown_platforms.entry((path, unknown)).or_default() += 1;
+                        None
+                    }
+                })
+                .collect::<Vec<_>>(),
+            Err(e) => vec![Err(e)],
+        })
+        .collect::<Vec<_>>();
+
+    ParsedReports {
+        runs,
+        unknown_platforms,
+    }
+}
+
+/// Like [`parse_report_files`], but replaces the contents of files in `files` with summaries in
+/// `cache_dir` where available, and caches summaries of files that were parsed in full. See
+/// [`ParseOptions::summary_cache`].
+fn parse_report_files_with_cache<'a>(
+    fs: &dyn FileSystem,
+    cache_dir: &Path,
+    files: &'a mut [(PathBuf, String)],
+    report_options: &ParseOptions,
+) -> ParsedReports<'a> {
+    let cache_key = |contents: &str| {
+        let ParseOptions {
+            deny_unrecognized,
+            platform_overrides,
+            summary_cache: _,
+            stream: _,
+            format,
+        } = report_options;
+        let mut hasher = StableHasher::default();
+        (
+            SUMMARY_VERSION,
+            contents,
+            deny_unrecognized,
+            platform_overrides,
+            format,
+        )
+            .hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let mut uncached = Vec::new();
+    for (path, contents) in files.iter_mut() {
+        let cache_path = cache_dir.join(format!("{:016x}.json", cache_key(contents)));
+        match fs.read_to_string(&cache_path) {
+            Ok(summary) => {
+                tracing::debug!(
+                    "using summary {} for report {}",
+                    cache_path.display(),
+                    path.display()
+                );
+                *contents = summary;
+                uncached.push(None);
+            }
+            Err(_) => uncached.push(Some(cache_path)),
+        }
+    }
+    let num_cached = uncached.iter().filter(|path| path.is_none()).count();
+    if num_cached > 0 {
+        tracing::info!("using cached summaries of {num_cached} report(s)");
+    }
+
+    let files = &*files;
+    let parsed = parse_each_report_file(files, report_options);
+    for (cache_path, runs) in uncached.into_iter().zip(&parsed) {
+        if let (Some(cache_path), Ok(runs)) = (cache_path, runs) {
+            if let Err(e) = fs.write(&cache_path, &summarize_runs(runs)) {
+                tracing::warn!("failed to cache report summary: {e}");
+            }
+        }
+    }
+    collect_parsed_reports(files, parsed)
+}
+
+/// Reconciles `exec_reports` against `meta_files_by_path` according to `options`.
+///
+/// No files are read from or written to `gecko_checkout`; it is only used to compute paths. The
+/// first error yielded by `exec_reports` is returned immediately.
+pub fn reconcile<'a>(
+    gecko_checkout: &Path,
+    meta_files_by_path: IndexMap<Arc<PathBuf>, File>,
+    exec_reports: impl IntoIterator<Item = Result<ExecutionReport<'a>, Error>>,
+    options: &ReconcileOptions,
+) -> Result<Reconciliation, Error> {
+    reconcile_with(gecko_checkout, meta_files_by_path, options, |record| {
+        for exec_report in exec_reports {
+            let ExecutionReport {
+                run_info,
+                entries,
+                unrecognized,
+            } = exec_report?;
+            record(RunPart::Start(run_info));
+            for entry in entries {
+                record(RunPart::Entry(entry));
+            }
+            record(RunPart::End(unrecognized));
+        }
+        Ok(())
+    })
+}
+
+/// Like [`reconcile`], but with reported runs passed to `record` by `feed`, part by part, rather
+/// than taken as a whole.
+#[tracing::instrument(skip_all, fields(num_reports, num_tests))]
+fn reconcile_with<'a>(
+    gecko_checkout: &Path,
+    meta_files_by_path: IndexMap<Arc<PathBuf>, File>,
+    options: &ReconcileOptions,
+    feed: impl FnOnce(&mut dyn FnMut(RunPart<'a>)) -> Result<(), Error>,
+) -> Result<Reconciliation, Error> {
+    let ReconcileOptions {
+        strategy,
+        strategy_overrides,
+        never_modify,
+        reenable_expired_as_of,
+        reenable,
+        reenable_bug,
+        only_tests,
+        sample,
+        only_platforms,
+        only_build_profiles,
+        browser,
+        run_info_keys,
+        disable_perma_crashes,
+    } = options;
+    let mut summary = ReconciliationSummary::default();
+
+    #[derive(Debug, Default)]
+    struct EntryByCtsPath<'a> {
+        metadata_path: Option<TestPath<'a>>,
+        reported_path: Option<TestPath<'a>>,
+        entry: TestEntry,
+    }
+
+    fn sample_key(test_path: &TestPath<'_>) -> String {
+        test_path
+            .cts_path()
+            .unwrap_or_else(|| test_path.runner_url_path().to_string())
+    }
+
+    let mut file_props_by_file = IndexMap::<Utf8PathBuf, FileProps>::default();
+    let mut entries_by_cts_path = IndexMap::<String, EntryByCtsPath<'_>>::default();
+    let mut other_entries_by_test = IndexMap::<TestPath<'_>, TestEntry>::default();
+    let old_meta_file_paths = meta_files_by_path.keys().cloned().collect::<Vec<_>>();
+
+    tracing::info!("loading metadata for comparison to reports…");
+    for (path, file) in meta_files_by_path {
+        let File { properties, tests } = file;
+
+        let file_rel_path = path.strip_prefix(gecko_checkout).unwrap();
+
+        file_props_by_file.insert(
+            Utf8PathBuf::from(file_rel_path.to_str().unwrap()),
+            properties,
+        );
+
+        for (SectionHeader(name), test) in tests {
+            let Test {
+                properties,
+                subtests,
+            } = test;
+
+            let test_path = TestPath::from_metadata_test(file_rel_path, &name).unwrap();
+
+            let mut reported_dupe_already = false;
+            let mut dupe_err = |warnings: &mut Vec<_>, previous: &TestPath<'_>| {
+                if !reported_dupe_already {
+                    warnings.push(Warning::DuplicateMetadataTest {
+                        test: test_path.clone().into_owned(),
+                        previous: previous.clone().into_owned(),
+                    });
+                }
+                reported_dupe_already = true;
+            };
+
+            let TestEntry {
+                entry: test_entry,
+                subtests: subtest_entries,
+            } = if let Some(cts_path) = test_path.cts_path() {
+                let entry = entries_by_cts_path.entry(cts_path).or_default();
+                if let Some(old) = entry.metadata_path.replace(test_path.clone().into_owned()) {
+                    dupe_err(&mut summary.warnings, &old);
+                }
+                &mut entry.entry
+            } else {
+                other_entries_by_test
+                    .entry(test_path.clone().into_owned())
+                    .or_default()
+            };
+
+            if let Some(_old) = test_entry.meta_props.replace(properties) {
+                dupe_err(&mut summary.warnings, &test_path);
+            }
+
+            for (SectionHeader(subtest_name), subtest) in subtests {
+                let Subtest { properties } = subtest;
+                let subtest_entry = subtest_entries.entry(subtest_name.clone()).or_default();
+                if let Some(_old) = subtest_entry.meta_props.replace(properties) {
+                    if !reported_dupe_already {
+                        summary.warnings.push(Warning::DuplicateMetadataSubtest {
+                            test: test_path.clone().into_owned(),
+                            subtest: subtest_name,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    tracing::info!("gathering reported test outcomes for reconciliation with metadata…");
+
+    let mut unrecognized = BTreeSet::new();
+    // Configurations, by variant, that at least one report covers.
+    let mut covered = BTreeMap::<RunInfoVariant, BTreeSet<(Platform, BuildProfile)>>::new();
+    let mut sampled = sample.map(|sample| Sampled {
+        sample,
+        tests: HashSet::new(),
+    });
+
+    let is_selected = |platform: Platform, build_profile: BuildProfile| {
+        (only_platforms.is_empty() || only_platforms.contains(&platform))
+            && (only_build_profiles.is_empty() || only_build_profiles.contains(&build_profile))
+    };
+
+    /// The run whose entries are being recorded.
+    struct ReportedRun {
+        platform: Platform,
+        build_profile: BuildProfile,
+        subsuite: Option<String>,
+        properties: RunInfoVariant,
+        /// `properties` narrowed to `run_info_keys`, in `subsuite`.
+        variant: RunInfoVariant,
+        selected: bool,
+    }
+
+    fn variant_in_subsuite(
+        properties: &RunInfoVariant,
+        run_info_keys: &BTreeSet<String>,
+        subsuite: Option<&str>,
+    ) -> RunInfoVariant {
+        let mut variant = properties.clone();
+        variant.0.retain(|key, _value| run_info_keys.contains(key));
+        if let Some(subsuite) = subsuite {
+            let subsuite = RunInfoValue::String(subsuite.to_owned());
+            variant.0.insert("subsuite".to_owned(), subsuite);
+        }
+        variant
+    }
+
+    fn accumulate<Out>(
+        entry: &mut Entry<Out>,
+        variant: &RunInfoVariant,
+        platform: Platform,
+        build_profile: BuildProfile,
+        reported_outcome: Out,
+    ) where
+        Out: Default + EnumSetType + Hash,
+    {
+        let recorded = if variant.is_empty() {
+            &mut entry.reported
+        } else {
+            match entry.reported_by_variant.get_mut(variant) {
+                Some(recorded) => recorded,
+                None => entry
+                    .reported_by_variant
+                    .entry(variant.clone())
+                    .or_default(),
+            }
+        };
+        match recorded.entry(platform).or_default().entry(build_profile) {
+            std::collections::btree_map::Entry::Vacant(entry) => {
+                entry.insert(Expected::permanent(reported_outcome));
+            }
+            std::collections::btree_map::Entry::Occupied(mut entry) => {
+                *entry.get_mut() |= reported_outcome
+            }
+        }
+    }
+
+    let mut run = None::<ReportedRun>;
+    let mut record = |part: RunPart<'a>| match part {
+        RunPart::Start(run_info) => {
+            let RunInfo {
+                platform,
+                build_profile,
+                subsuite,
+                properties,
+            } = run_info;
+            let selected = is_selected(platform, build_profile);
+            let variant = variant_in_subsuite(&properties, run_info_keys, subsuite.as_deref());
+            if selected {
+                summary.num_reports += 1;
+                summary.configurations.insert((platform, build_profile));
+                covered
+                    .entry(variant.clone())
+                    .or_default()
+                    .insert((platform, build_profile));
+            } else {
+                summary.num_ignored_runs += 1;
+            }
+            run = Some(ReportedRun {
+                platform,
+                build_profile,
+                subsuite,
+                properties,
+                variant,
+                selected,
+            });
+        }
+        RunPart::End(report_unrecognized) => {
+            let run = run
+                .take()
+                .expect("internal error: run ended before it started");
+            if run.selected {
+                unrecognized.extend(report_unrecognized);
+            }
+        }
+        RunPart::Entry(entry) => {
+            let ReportedRun {
+                platform,
+                build_profile,
+                subsuite: run_subsuite,
+                properties,
+                variant: run_variant,
+                selected,
+            } = run
+                .as_ref()
+                .expect("internal error: entry recorded outside of a run");
+            let (platform, build_profile) = (*platform, *build_profile);
+            if !selected {
+                summary.num_ignored_entries += 1;
+                return;
+            }
+
+            let TestExecutionEntry {
+                test_name,
+                subsuite,
+                message: _,
+                result,
+            } = entry;
+            let subsuite = subsuite.as_deref().or(run_subsuite.as_deref());
+            let entry_variant;
+            let variant = if subsuite != run_subsuite.as_deref() {
+                entry_variant = variant_in_subsuite(properties, run_info_keys, subsuite);
+                covered
+                    .entry(entry_variant.clone())
+                    .or_default()
+                    .insert((platform, build_profile));
+                &entry_variant
+            } else {
+                run_variant
+            };
+
+            let test_path = TestPath::from_execution_report(*browser, &test_name).unwrap();
+            if let Some(sampled) = &mut sampled {
+                if !sampled.admit(sample_key(&test_path)) {
+                    summary.num_unsampled_entries += 1;
+                    return;
+                }
+            }
+            let TestEntry {
+                entry: test_entry,
+                subtests: subtest_entries,
+            } = if let Some(cts_path) = test_path.cts_path() {
+                let entry = entries_by_cts_path.entry(cts_path).or_default();
+                if let Some(old) = entry.reported_path.replace(test_path.clone().into_owned()) {
+                    if old != test_path {
+                        summary.warnings.push(Warning::DuplicateReportedCtsPath {
+                            older: old,
+                            newer: test_path.clone().into_owned(),
+                        });
+                    }
+                }
+                &mut entry.entry
+            } else {
+                other_entries_by_test
+                    .entry(test_path.clone().into_owned())
+                    .or_default()
+            };
+
+            let (reported_outcome, reported_subtests) = match result {
+                TestExecutionResult::Complete { outcome, subtests } => (outcome, subtests),
+                TestExecutionResult::JobMaybeTimedOut { subtests } => {
+                    (TestOutcome::Timeout, subtests)
+                }
+            };
+
+            accumulate(
+                test_entry,
+                variant,
+                platform,
+                build_profile,
+                reported_outcome,
+            );
+
+            for reported_subtest in reported_subtests {
+                let SubtestExecutionResult {
+                    subtest_name,
+                    outcome,
+                } = reported_subtest;
+
+                let subtest_entry = match subtest_entries.get_mut(&*subtest_name) {
+                    Some(entry) => entry,
+                    None => subtest_entries
+                        .entry(subtest_name.into_owned())
+                        .or_default(),
+                };
+                accumulate(subtest_entry, variant, platform, build_profile, outcome);
+            }
+        }
+    };
+    feed(&mut record)?;
+
+    let using_reports = summary.num_reports > 0;
+    let filtering_configurations = !only_platforms.is_empty() || !only_build_profiles.is_empty();
+    let in_scope = |test_path: &TestPath<'_>| {
+        only_tests.as_ref().map_or(true, |patterns| {
+            patterns.iter().any(|pattern| pattern.matches(test_path))
+        }) && sampled.as_ref().map_or(true, |sampled| {
+            sampled.tests.contains(&sample_key(test_path))
+        })
+    };
+
+    let num_tests = entries_by_cts_path.len() + other_entries_by_test.len();
+    let span = Span::current();
+    span.record("num_reports", summary.num_reports);
+    span.record("num_tests", num_tests);
+
+    tracing::info!("metadata and reports gathered, now reconciling outcomes…");
+
+    let mut relocated_tests = Vec::new();
+    let entries_by_cts_path = entries_by_cts_path.into_iter().map(|(_name, entry)| {
+        let EntryByCtsPath {
+            metadata_path,
+            reported_path,
+            entry,
+        } = entry;
+        let output_path = if let Some((meta, rep)) = metadata_path
+            .as_ref()
+            .zip(reported_path.as_ref())
+            .filter(|(meta, rep)| {
+                meta != rep && in_scope(meta) && never_modify.find_match(meta).is_none()
+            }) {
+            relocated_tests.push(Relocation {
+                from: meta.clone(),
+                to: rep.clone(),
+            });
+            reported_path
+        } else {
+            metadata_path.or(reported_path)
+        };
+
+        (
+            output_path.expect(concat!(
+                "internal error: CTS path entry created without at least one ",
+                "report or metadata path specified"
+            )),
+            entry,
+        )
+    });
+    let mut new_tests = Vec::new();
+    let mut removed_tests = Vec::new();
+    let mut orphaned_tests = Vec::new();
+    let mut protected_tests = Vec::new();
+    let mut reenabled_tests = Vec::new();
+    let mut disabled_tests = Vec::new();
+    let mut used_never_modify_patterns = BTreeSet::new();
+    let mut warnings = Vec::new();
+    let recombined_tests_iter = entries_by_cts_path
+        .chain(other_entries_by_test)
+        .progress_with(progress::bar("reconciling", Some(num_tests)))
+        .filter_map(|(test_path, test_entry)| {
+            fn reconcile_entry<Out>(
+                entry: Entry<Out>,
+                strategy: &dyn ReconciliationStrategy,
+                covered: &BTreeMap<RunInfoVariant, BTreeSet<(Platform, BuildProfile)>>,
+                is_selected: &dyn Fn(Platform, BuildProfile) -> bool,
+                keep_unreported: bool,
+            ) -> TestProps<Out>
+            where
+                Out: Debug + Default + ResolveWith,
+            {
+                let Entry {
+                    meta_props,
+                    reported,
+                    reported_by_variant,
+                } = entry;
+
+                let meta_props = match meta_props {
+                    Some(meta_props)
+                        if meta_props.keep_expectations
+                            || meta_props.verbatim_expected.is_some() =>
+                    {
+                        return meta_props
+                    }
+                    meta_props => meta_props,
+                };
+
+                let resolve_expected =
+                    |meta_expected: Option<FullyExpandedExpectedPropertyValue<Out>>,
+                     reported: &BTreeMap<Platform, BTreeMap<BuildProfile, Expected<Out>>>,
+                     covered: Option<&BTreeSet<(Platform, BuildProfile)>>| {
+                        let reported = |platform, build_profile| {
+                            reported
+                                .get(&platform)
+                                .and_then(|rep| rep.get(&build_profile))
+                                .copied()
+                        };
+                        let all_reported = || {
+                            FullyExpandedExpectedPropertyValue::from_query(
+                                |platform, build_profile| {
+                                    reported(platform, build_profile).unwrap_or_default()
+                                },
+                            )
+                        };
+                        if let Some(meta_expected) = meta_expected {
+                            FullyExpandedExpectedPropertyValue::from_query(
+                                |platform, build_profile| {
+                                    let current = meta_expected.get(platform, build_profile);
+                                    // N.B.: With `--only-platform` or `--only-build-profile`, a
+                                    // selected configuration may simply not have run the test.
+                                    if !is_selected(platform, build_profile)
+                                        || (keep_unreported
+                                            && reported(platform, build_profile).is_none())
+                                    {
+                                        return current;
+                                    }
+                                    Out::resolve_with(
+                                        strategy,
+                                        ConfigOutcomes {
+                                            platform,
+                                            build_profile,
+                                            current,
+                                            reported: reported(platform, build_profile),
+                                            covered: covered.map_or(false, |covered| {
+                                                covered.contains(&(platform, build_profile))
+                                            }),
+                                        },
+                                    )
+                                },
+                            )
+                        } else {
+                            all_reported()
+                        }
+                    };
+
+                let mut meta_props = meta_props.unwrap_or_default();
+                let reconciled = resolve_expected(
+                    meta_props.expected,
+                    &reported,
+                    covered.get(&RunInfoVariant::default()),
+                );
+                // N.B.: Variants not found in any report are left as they are.
+                let reconciled_by_variant = reported_by_variant
+                    .into_iter()
+                    .map(|(variant, reported)| {
+                        let meta_expected = meta_props.expected_for(&variant);
+                        let reconciled =
+                            resolve_expected(meta_expected, &reported, covered.get(&variant));
+                        (variant, reconciled)
+                    })
+                    .collect::<Vec<_>>();
+                meta_props.expected_by_variant.extend(reconciled_by_variant);
+                meta_props.expected = Some(reconciled);
+                meta_props.prune_redundant_variants();
+                meta_props
+            }
+
+            let TestEntry {
+                entry: mut test_entry,
+                subtests: subtest_entries,
+            } = test_entry;
+            let reported = ReportedOutcomes {
+                test: test_entry.reported.clone(),
+                subtests: subtest_entries
+                    .iter()
+                    .filter(|(_name, subtest)| !subtest.reported.is_empty())
+                    .map(|(name, subtest)| (SectionHeader(name.clone()), subtest.reported.clone()))
+                    .collect(),
+            };
+
+            if using_reports
+                && test_entry.reported.is_empty()
+                && test_entry.reported_by_variant.is_empty()
+                && test_entry.meta_props.is_some()
+                && in_scope(&test_path)
+            {
+                orphaned_tests.push(test_path.clone());
+            }
+
+            // N.B.: Out-of-scope tests are skipped before checking the never-modify list, so
+            // that they aren't reported as protected.
+            let protected = in_scope(&test_path)
+                && never_modify.find_match(&test_path).map_or(false, |idx| {
+                    used_never_modify_patterns.insert(idx);
+                    protected_tests.push(test_path.clone());
+                    true
+                });
+            let keep_expectations = test_entry.meta_props.as_ref().map_or(false, |properties| {
+                properties.keep_expectations || properties.quarantined.is_some()
+            });
+            if !in_scope(&test_path) || protected || keep_expectations {
+                let properties = test_entry.meta_props?;
+                let subtests = subtest_entries
+                    .into_iter()
+                    .filter_map(|(subtest_name, subtest)| {
+                        let properties = subtest.meta_props?;
+                        Some((SectionHeader(subtest_name), Subtest { properties }))
+                    })
+                    .collect();
+                return Some((test_path, (properties, subtests, reported)));
+            }
+
+            if test_entry.meta_props.is_none() {
+                new_tests.push(test_path.clone());
+            }
+
+            let strategy = strategy_overrides
+                .iter()
+                .find(|(pattern, _strategy)| pattern.matches(&test_path))
+                .map_or(strategy, |(_pattern, strategy)| strategy);
+
+            if test_entry.reported.is_empty()
+                && test_entry.reported_by_variant.is_empty()
+                && using_reports
+            {
+                if filtering_configurations {
+                    // N.B.: The test may well be run in configurations that were ignored.
+                } else if strategy.keeps_unreported_tests() {
+                    warnings.push(Warning::NotReported {
+                        test: test_path.clone(),
+                    })
+                } else {
+                    removed_tests.push(test_path);
+                    return None;
+                }
+            }
+
+            /// Removes the disable from `entry` if its `disabled-until` date is on or before
+            /// `today`, or if `force` is set and it is disabled, returning the strategy to
+            /// reconcile it with. If `only_bug` is set, forcing only removes the parts of the
+            /// disable that refer to that bug. Subtests of a re-enabled test are reset along with
+            /// it.
+            fn reenable_if_due<'a, Out>(
+                entry: &mut Entry<Out>,
+                today: Option<NaiveDate>,
+                force: bool,
+                only_bug: Option<u64>,
+                strategy: &'a dyn ReconciliationStrategy,
+                reenabled: &mut bool,
+            ) -> &'a dyn ReconciliationStrategy
+            where
+                Out: Default + EnumSetType,
+            {
+                let Some(meta_props) = entry.meta_props.as_mut() else {
+                    return strategy;
+                };
+                let expired = today
+                    .zip(meta_props.disabled_until)
+                    .map_or(false, |(today, until)| until <= today);
+                let forced = force && meta_props.is_disabled.is_some();
+                if !expired && !forced {
+                    return strategy;
+                }
+                if let (false, Some(id)) = (expired, only_bug) {
+                    if !meta_props.enable_for_bug(id) {
+                        return strategy;
+                    }
+                    *reenabled = true;
+                    return &ResetAll;
+                }
+                meta_props.is_disabled = None;
+                meta_props.disabled_until = None;
+                *reenabled = true;
+                &ResetAll
+            }
+
+            let force_reenable = reenable.iter().any(|pattern| pattern.matches(&test_path));
+            let mut reenabled = false;
+            let test_strategy = reenable_if_due(
+                &mut test_entry,
+                *reenable_expired_as_of,
+                force_reenable,
+                *reenable_bug,
+                &**strategy,
+                &mut reenabled,
+            );
+            let mut properties = reconcile_entry(
+                test_entry,
+                test_strategy,
+                &covered,
+                &is_selected,
+                filtering_configurations,
+            );
+
+            let mut subtests = BTreeMap::new();
+            for (subtest_name, mut subtest) in subtest_entries {
+                let subtest_strategy = reenable_if_due(
+                    &mut subtest,
+                    *reenable_expired_as_of,
+                    force_reenable,
+                    *reenable_bug,
+                    test_strategy,
+                    &mut reenabled,
+                );
+                let mut properties = reconcile_entry(
+                    subtest,
+                    subtest_strategy,
+                    &covered,
+                    &is_selected,
+                    filtering_configurations,
+                );
+
+                let TestProps {
+                    is_disabled: _,
+                    disabled_until: _,
+                    keep_expectations,
+                    quarantined: _,
+                    bug: _,
+                    expected,
+                    expected_by_variant,
+                    verbatim_expected: _,
+                } = &mut properties;
+                if !*keep_expectations {
+                    for expected in expected.iter_mut().chain(expected_by_variant.values_mut()) {
+                        for (_, expected) in expected.iter_mut() {
+                            taint_subtest_timeouts_by_suspicion(expected);
+                        }
+                    }
+                }
+
+                subtests.insert(SectionHeader(subtest_name), Subtest { properties });
+            }
+
+            if reenabled {
+                reenabled_tests.push(test_path.clone());
+            }
+
+            if let Some(reason) = disable_perma_crashes {
+                let crashing = properties
+                    .expected
+                    .iter()
+                    .flat_map(|expected| expected.iter())
+                    .filter(|&((platform, build_profile), expected)| {
+                        is_selected(platform, build_profile)
+                            && expected == Expected::permanent(TestOutcome::Crash)
+                    })
+                    .map(|(config, _expected)| config)
+                    .collect::<Vec<_>>();
+                if properties.disable_on(crashing, reason) {
+                    disabled_tests.push(test_path.clone());
+                }
+            }
+
+            if subtests.is_empty() && properties == Default::default() {
+                None
+            } else {
+                Some((test_path, (properties, subtests, reported)))
+            }
+        });
+
+    tracing::info!(
+        "outcome reconciliation complete, gathering tests back into new metadata files…"
+    );
+
+    let mut files = BTreeMap::<PathBuf, File>::new();
+    let mut reported_by_test = BTreeMap::new();
+    let mut new_files = Vec::new();
+    for (test_path, (properties, subtests, reported)) in recombined_tests_iter {
+        let name = test_path.test_name().to_string();
+        let rel_path = Utf8PathBuf::from(test_path.rel_metadata_path().to_string());
+        let path = gecko_checkout.join(&rel_path);
+        if !reported.test.is_empty() || !reported.subtests.is_empty() {
+            reported_by_test.insert((path.clone(), SectionHeader(name.clone())), reported);
+        }
+        let file = files.entry(path.clone()).or_insert_with(|| File {
+            properties: file_props_by_file
+                .get(&rel_path)
+                .cloned()
+                .unwrap_or_else(|| {
+                    new_files.push(path);
+                    Default::default()
+                }),
+            tests: Default::default(),
+        });
+        file.tests.insert(
+            SectionHeader(name),
+            Test {
+                properties,
+                subtests,
+            },
+        );
+    }
+
+    for old_meta_file_path in old_meta_file_paths {
+        files
+            .entry(Arc::into_inner(old_meta_file_path).unwrap())
+            .or_default();
+    }
+
+    summary.emptied_files = files
+        .iter()
+        .filter(|(_path, file)| file.tests.is_empty())
+        .map(|(path, _file)| path.clone())
+        .collect();
+    summary.new_tests = new_tests;
+    summary.removed_tests = removed_tests;
+    summary.orphaned_tests = orphaned_tests;
+    summary.relocated_tests = relocated_tests;
+    summary.reenabled_tests = reenabled_tests;
+    summary.disabled_tests = disabled_tests;
+    summary.protected_tests = protected_tests;
+    summary.new_files = new_files;
+    summary.warnings.extend(warnings);
+    summary.warnings.extend(
+        (0..never_modify.num_patterns())
+            .filter(|idx| !used_never_modify_patterns.contains(idx))
+            .map(|idx| Warning::UnusedNeverModifyPattern {
+                pattern: never_modify.pattern(idx).to_string(),
+            }),
+    );
+    summary.warnings.extend(
+        unrecognized
+            .into_iter()
+            .map(Warning::UnrecognizedReportContents),
+    );
+
+    files
+        .par_iter()
+        .try_for_each(|(path, file)| check_conditions(path, file))?;
+
+    Ok(Reconciliation {
+        files,
+        reported: reported_by_test,
+        summary,
+    })
+}
+
+/// Checks that `file`, once written to `path`, would be read back with the expected outcomes it
+/// was written with. See [`condition_mismatches`].
+fn check_conditions(path: &Path, file: &File) -> Result<(), Error> {
+    if file.tests.is_empty() {
+        return Ok(());
+    }
+    let (_path, read_back) = parse_metadata(path.to_owned(), format_file(file).to_string())?;
+    let mismatches = condition_mismatches(file, &read_back);
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::UnfaithfulConditions {
+            path: path.to_owned(),
+            mismatches,
+        })
+    }
+}
+
+/// Ensure that _both_ `TIMEOUT` and `NOTRUN` are in outcomes if at least one of them are present.
+///
+/// This transformation is desirable for reaching convergence quickly in tests where it may require
+/// a high number of test runs to empirically observe all places where `TIMEOUT`s may occur. The
+/// motivating example in Firefox's test runs are tests with a large matrix of subtests that are
+/// deterministic if executed, but consistently exceed the timeout window offered by the test
+/// runner.
+pub fn taint_subtest_timeouts_by_suspicion(expected: &mut Expected<SubtestOutcome>) {
+    let timeout_and_notrun =
+        Expected::intermittent(SubtestOutcome::Timeout | SubtestOutcome::NotRun).unwrap();
+    if !expected.is_disjoint(timeout_and_notrun.inner())
+        && !expected.is_superset(&timeout_and_notrun)
+    {
+        static PRINTED_WARNING: AtomicBool = AtomicBool::new(false);
+        let already_printed_warning = PRINTED_WARNING.swap(true, atomic::Ordering::Relaxed);
+        if !already_printed_warning {
+            tracing::info!(
+                "encountered at least one case where taint-by-suspicion is being applied…"
+            )
+        }
+        *expected |= SubtestOutcome::Timeout | SubtestOutcome::NotRun;
+    }
+}
+
+#[test]
+fn reconcile_summary() {
+    use crate::metadata::{FileBuilder, TestBuilder};
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let meta_file = FileBuilder::new()
+        .test(TestBuilder::new(
+            "cts.https.html?q=webgpu:api,operation,adapter:*",
+        ))
+        .test(TestBuilder::new(
+            "cts.https.html?q=webgpu:api,operation,buffers,map:*",
+        ))
+        .build()
+        .unwrap();
+
+    let report = || ExecutionReport {
+        run_info: RunInfo {
+            platform: Platform::Windows,
+            build_profile: BuildProfile::Debug,
+            subsuite: None,
+            properties: Default::default(),
+        },
+        entries: [
+            "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+            "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,labels:*",
+        ]
+        .into_iter()
+        .map(|test_name| TestExecutionEntry {
+            test_name: test_name.into(),
+            subsuite: None,
+            message: None,
+            result: TestExecutionResult::Complete {
+                outcome: TestOutcome::Ok,
+                subtests: vec![SubtestExecutionResult {
+                    subtest_name: ":".into(),
+                    outcome: SubtestOutcome::Pass,
+                }],
+            },
+        })
+        .collect(),
+        unrecognized: Default::default(),
+    };
+
+    let reconcile = |never_modify: NeverModifyList| {
+        reconcile(
+            gecko_checkout,
+            [(Arc::new(meta_path.clone()), meta_file.clone())]
+                .into_iter()
+                .collect(),
+            [Ok(report())],
+            &ReconcileOptions {
+                strategy: ReportProcessingPreset::ResetContradictory.strategy(),
+                never_modify,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    };
+
+    let Reconciliation {
+        files,
+        reported: _,
+        summary,
+    } = reconcile(Default::default());
+
+    let test_names = |tests: &[TestPath<'_>]| {
+        tests
+            .iter()
+            .map(|test| test.test_name().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(summary.num_reports, 1);
+    assert_eq!(
+        test_names(&summary.new_tests),
+        ["cts.https.html?q=webgpu:api,operation,labels:*"]
+    );
+    assert_eq!(
+        test_names(&summary.removed_tests),
+        ["cts.https.html?q=webgpu:api,operation,buffers,map:*"]
+    );
+    assert_eq!(summary.orphaned_tests, summary.removed_tests);
+    assert!(summary.relocated_tests.is_empty());
+    assert!(summary.new_files.is_empty());
+    assert!(summary.emptied_files.is_empty());
+    assert!(summary.warnings.is_empty());
+    assert_eq!(files.keys().collect::<Vec<_>>(), [&meta_path]);
+
+    let never_modify = NeverModifyList::parse(
+        Path::new("never-modify.txt"),
+        concat!(
+            "webgpu:api,operation,buffers,*\n",
+            "webgpu:api,operation,labels:*\n",
+            "webgpu:api,validation,*\n",
+        ),
+    )
+    .unwrap();
+    let Reconciliation {
+        files,
+        reported: _,
+        summary,
+    } = reconcile(never_modify);
+    assert!(summary.new_tests.is_empty());
+    assert!(summary.removed_tests.is_empty());
+    assert_eq!(
+        test_names(&summary.protected_tests),
+        [
+            "cts.https.html?q=webgpu:api,operation,buffers,map:*",
+            "cts.https.html?q=webgpu:api,operation,labels:*",
+        ]
+    );
+    assert_eq!(
+        summary
+            .warnings
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        ["never-modify pattern `webgpu:api,validation,*` did not match any test"]
+    );
+    assert_eq!(
+        files[&meta_path]
+            .tests
+            .keys()
+            .map(|SectionHeader(name)| name.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "cts.https.html?q=webgpu:api,operation,adapter:*",
+            "cts.https.html?q=webgpu:api,operation,buffers,map:*",
+        ]
+    );
+}
+
+#[test]
+fn update_expected_in_memory() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+    let ios_report_path = PathBuf::from("ios/wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    }
+                ]
+            }"#,
+        ),
+        (
+            ios_report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "ios",
+                    "processor": "aarch64",
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "TIMEOUT" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path, ios_report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::Merge.strategy(),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    assert_eq!(reconciliation.summary.num_reports, 1);
+    assert_eq!(reconciliation.summary.num_skipped_runs, 1);
+    assert_eq!(
+        reconciliation
+            .summary
+            .warnings
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        [concat!(
+            "skipping 1 run(s) in ios/wptreport.json for a platform not recognized by this ",
+            "tool (os = \"ios\", processor = \"aarch64\", win11_2009 = false); ",
+            "use `--platform-map` to attribute them to a platform"
+        )]
+    );
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:]
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and debug: FAIL
+          if os == "linux" and not debug: [PASS, FAIL]
+          if os == "mac": FAIL
+          if os == "android": FAIL
+    "###);
+}
+
+#[test]
+fn read_report_files_dedupe() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let report = |status| {
+        format!(
+            r#"{{
+                "run_info": {{ "os": "linux", "processor": "x86_64", "debug": false }},
+                "results": [{{ "test": "/foo.html", "status": "{status}", "subtests": [] }}]
+            }}"#
+        )
+    };
+    let fs = [
+        ("a/wptreport.json", report("OK")),
+        ("b/wptreport.json", report("OK")),
+        ("c/wptreport.json", report("ERROR")),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let ReportFiles { files, skipped } = read_report_files(
+        &fs,
+        ["a", "b", "a", "c"]
+            .into_iter()
+            .map(|dir| Path::new(dir).join("wptreport.json"))
+            .collect(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    assert_eq!(
+        files
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>(),
+        [Path::new("a/wptreport.json"), Path::new("c/wptreport.json")]
+    );
+    assert_eq!(
+        skipped.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["skipping b/wptreport.json, since it has the same contents as a/wptreport.json"]
+    );
+}
+
+#[test]
+fn read_report_files_cancelled() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let fs = [("wptreport.json", "{}")]
+        .into_iter()
+        .collect::<MemoryFileSystem>();
+    let read = |cancelled| {
+        read_report_files(
+            &fs,
+            vec![PathBuf::from("wptreport.json")],
+            &AtomicBool::new(cancelled),
+        )
+    };
+    assert_eq!(read(false).unwrap().files.len(), 1);
+    assert!(matches!(read(true), Err(Error::Cancelled)));
+}
+
+#[test]
+fn update_expected_subsuite() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:]\n",
+                "    expected:\n",
+                "      if subsuite == \"webgpu-long\" and os == \"mac\": PASS\n",
+                "      FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false,
+                    "subsuite": "webgpu-long"
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::ResetContradictory.strategy(),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:]
+        expected:
+          if subsuite == "webgpu-long" and os == "linux" and not debug: PASS
+          if subsuite == "webgpu-long" and os == "mac": PASS
+          FAIL
+    "###);
+}
+
+#[test]
+fn update_expected_run_info_keys() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:]\n",
+                "    expected:\n",
+                "      if fission and os == \"win\": FAIL\n",
+                "      if headless: TIMEOUT\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false,
+                    "fission": true,
+                    "headless": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "FAIL" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::ResetContradictory.strategy(),
+            run_info_keys: ["fission".to_owned()].into_iter().collect(),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:]
+        expected:
+          if fission and os == "win": FAIL
+          if fission and os == "linux" and not debug: FAIL
+          if headless: [TIMEOUT, NOTRUN]
+    "###);
+}
+
+#[test]
+fn update_expected_webgpu_backends() {
+    use crate::{filesystem::MemoryFileSystem, report::WEBGPU_BACKEND_KEY};
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report = |backend: &str, outcome: &str| {
+        format!(
+            r#"{{
+                "run_info": {{
+                    "os": "win",
+                    "processor": "x86_64",
+                    "win11_2009": true,
+                    "debug": false,
+                    "webgpu_backend": "{backend}"
+                }},
+                "results": [
+                    {{
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{{ "name": ":", "status": "{outcome}" }}]
+                    }}
+                ]
+            }}"#
+        )
+    };
+    let report_paths = vec![
+        PathBuf::from("wptreport-d3d12.json"),
+        PathBuf::from("wptreport-vulkan.json"),
+    ];
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:]\n",
+                "    expected: PASS\n",
+            )
+            .to_owned(),
+        ),
+        (report_paths[0].clone(), report("DX12", "PASS")),
+        (report_paths[1].clone(), report("Vulkan", "FAIL")),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        report_paths,
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::ResetContradictory.strategy(),
+            run_info_keys: [WEBGPU_BACKEND_KEY.to_owned()].into_iter().collect(),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:]
+        expected:
+          if webgpu_backend == "vulkan" and os == "win" and not debug: FAIL
+    "###);
+}
+
+#[test]
+fn update_expected_only_configurations() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report = |os: &str, debug: bool, results: &[(&str, &str)]| {
+        let results = results
+            .iter()
+            .map(|(test, outcome)| {
+                format!(
+                    r#"{{
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,{test}:*",
+                        "status": "OK",
+                        "subtests": [{{ "name": ":", "status": "{outcome}" }}]
+                    }}"#
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#"{{
+                "run_info": {{
+                    "os": "{os}",
+                    "processor": "x86_64",
+                    "win11_2009": true,
+                    "debug": {debug}
+                }},
+                "results": [{results}]
+            }}"#
+        )
+    };
+    let report_paths = vec![
+        PathBuf::from("wptreport-linux-debug.json"),
+        PathBuf::from("wptreport-win-opt.json"),
+    ];
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,operation,buffers:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+            )
+            .to_owned(),
+        ),
+        (
+            report_paths[0].clone(),
+            report("linux", true, &[("adapter", "PASS")]),
+        ),
+        (
+            report_paths[1].clone(),
+            report("win", false, &[("adapter", "TIMEOUT"), ("buffers", "PASS")]),
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        report_paths,
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::ResetAll.strategy(),
+            only_platforms: vec![Platform::Linux],
+            only_build_profiles: vec![BuildProfile::Debug],
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    assert_eq!(reconciliation.summary.num_reports, 1);
+    assert_eq!(reconciliation.summary.num_ignored_runs, 1);
+    assert_eq!(
+        reconciliation.summary.exclusions().to_string(),
+        concat!(
+            "1 run(s) with 2 entries in configurations not selected with `--only-platform` or ",
+            "`--only-build-profile`"
+        )
+    );
+    assert!(reconciliation.summary.removed_tests.is_empty());
+    assert_eq!(
+        reconciliation
+            .summary
+            .orphaned_tests
+            .iter()
+            .map(|test| test.test_name().to_string())
+            .collect::<Vec<_>>(),
+        ["cts.https.html?q=webgpu:api,operation,buffers:*"]
+    );
+    assert_eq!(
+        reconciliation.summary.uncovered_configurations().count(),
+        Platform::iter().count() * BuildProfile::iter().count() - 1
+    );
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:]
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and not debug: FAIL
+          if os == "mac": FAIL
+          if os == "android": FAIL
+
+
+    [cts.https.html?q=webgpu:api,operation,buffers:*]
+      [:]
+        expected: FAIL
+    "###);
+}
+
+#[test]
+fn update_expected_streamed() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report = |os: &str, outcome: &str| {
+        format!(
+            r#"{{
+                "run_info": {{ "os": "{os}", "processor": "x86_64", "win11_2009": true, "debug": false }},
+                "results": [{{
+                    "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                    "status": "OK",
+                    "subtests": [{{ "name": ":", "status": "{outcome}" }}]
+                }}]
+            }}"#
+        )
+    };
+    let report_paths = vec![
+        PathBuf::from("wptreport-win.json"),
+        PathBuf::from("wptreport-linux.json"),
+        PathBuf::from("wptreport-linux-copy.json"),
+        PathBuf::from("wptreport-os2.json"),
+    ];
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+            )
+            .to_owned(),
+        ),
+        (report_paths[0].clone(), report("win", "PASS")),
+        (report_paths[1].clone(), report("linux", "FAIL")),
+        (report_paths[2].clone(), report("linux", "FAIL")),
+        (report_paths[3].clone(), report("os2", "FAIL")),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let update = |stream| {
+        update_expected(
+            &fs,
+            gecko_checkout,
+            // A path given twice is read once, rather than taken for a duplicate of itself.
+            report_paths
+                .iter()
+                .chain(&report_paths[..1])
+                .cloned()
+                .collect(),
+            &ReconcileOptions {
+                strategy: ReportProcessingPreset::ResetContradictory.strategy(),
+                ..Default::default()
+            },
+            &ParseOptions {
+                stream,
+                ..Default::default()
+            },
+            &AtomicBool::new(false),
+        )
+        .unwrap()
+    };
+    let (buffered, streamed) = (update(false), update(true));
+    assert_eq!(streamed.summary.num_reports, 2);
+    assert_eq!(streamed.summary.num_skipped_runs, 1);
+    assert_eq!(streamed.summary.exclusions().duplicate_files, 1);
+    assert_eq!(streamed.summary.exclusions(), buffered.summary.exclusions());
+    let render = |reconciliation: &Reconciliation| {
+        reconciliation
+            .files
+            .values()
+            .map(|file| format_file(file).to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(render(&streamed), render(&buffered));
+}
+
+#[test]
+fn update_expected_prune_unobserved() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:]\n",
+                "    expected: [PASS, FAIL]\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::PruneUnobserved.strategy(),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    assert_eq!(
+        reconciliation.impact(&fs).unwrap(),
+        Impact {
+            num_rewritten: 1,
+            ..Default::default()
+        }
+    );
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:]
+        expected:
+          if os == "win": [PASS, FAIL]
+          if os == "linux" and debug: [PASS, FAIL]
+          if os == "mac": [PASS, FAIL]
+          if os == "android": [PASS, FAIL]
+    "###);
+}
+
+#[test]
+fn update_expected_reset_timeouts() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:timed_out]\n",
+                "    expected: TIMEOUT\n",
+                "\n",
+                "  [:failing]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "  [:passing]\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [
+                            { "name": ":timed_out", "status": "PASS" },
+                            { "name": ":failing", "status": "PASS" },
+                            { "name": ":passing", "status": "NOTRUN" }
+                        ]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::ResetTimeouts.strategy(),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:failing]
+        expected: FAIL
+
+      [:passing]
+        expected:
+          if os == "linux" and not debug: [TIMEOUT, NOTRUN]
+
+      [:timed_out]
+        expected:
+          if os == "win": [TIMEOUT, NOTRUN]
+          if os == "linux" and debug: [TIMEOUT, NOTRUN]
+          if os == "mac": [TIMEOUT, NOTRUN]
+          if os == "android": [TIMEOUT, NOTRUN]
+    "###);
+}
+
+#[test]
+fn update_expected_prune_stale_intermittents() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report = |b_outcome: &str| {
+        format!(
+            r#"{{
+                "run_info": {{
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                }},
+                "results": [
+                    {{
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [
+                            {{ "name": ":a", "status": "PASS" }},
+                            {{ "name": ":b", "status": "{b_outcome}" }},
+                            {{ "name": ":c", "status": "CRASH" }}
+                        ]
+                    }}
+                ]
+            }}"#
+        )
+    };
+    let report_paths = vec![
+        PathBuf::from("wptreport-1.json"),
+        PathBuf::from("wptreport-2.json"),
+    ];
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:a]\n",
+                "    expected: [PASS, FAIL]\n",
+                "\n",
+                "  [:b]\n",
+                "    expected: [PASS, FAIL, TIMEOUT, NOTRUN]\n",
+                "\n",
+                "  [:c]\n",
+                "    expected: [PASS, FAIL]\n",
+            )
+            .to_owned(),
+        ),
+        (report_paths[0].clone(), report("FAIL")),
+        (report_paths[1].clone(), report("TIMEOUT")),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        report_paths,
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::PruneStaleIntermittents.strategy(),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:a]
+        expected:
+          if os == "win": [PASS, FAIL]
+          if os == "linux" and debug: [PASS, FAIL]
+          if os == "mac": [PASS, FAIL]
+          if os == "android": [PASS, FAIL]
+
+      [:b]
+        expected:
+          if os == "win": [PASS, FAIL, TIMEOUT, NOTRUN]
+          if os == "linux" and debug: [PASS, FAIL, TIMEOUT, NOTRUN]
+          if os == "linux" and not debug: [FAIL, TIMEOUT, NOTRUN]
+          if os == "mac": [PASS, FAIL, TIMEOUT, NOTRUN]
+          if os == "android": [PASS, FAIL, TIMEOUT, NOTRUN]
+
+      [:c]
+        expected: [PASS, FAIL]
+    "###);
+}
+
+#[test]
+fn update_expected_per_level() {
+    use crate::{
+        filesystem::MemoryFileSystem,
+        strategy::{Merge, PerLevel, ResetContradictory},
+    };
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  expected: TIMEOUT\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: Arc::new(PerLevel {
+                tests: Arc::new(Merge),
+                subtests: Arc::new(ResetContradictory),
+            }),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      expected:
+        if os == "win": TIMEOUT
+        if os == "linux" and debug: TIMEOUT
+        if os == "linux" and not debug: [OK, TIMEOUT]
+        if os == "mac": TIMEOUT
+        if os == "android": TIMEOUT
+      [:]
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and debug: FAIL
+          if os == "mac": FAIL
+          if os == "android": FAIL
+    "###);
+}
+
+#[test]
+fn update_expected_strategy_overrides() {
+    use crate::{
+        filesystem::MemoryFileSystem,
+        strategy::{Merge, ResetAll},
+    };
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,validation,buffers:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    },
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,validation,buffers:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: Arc::new(ResetAll),
+            strategy_overrides: vec![("webgpu:api,validation,*".parse().unwrap(), Arc::new(Merge))],
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:]
+
+
+    [cts.https.html?q=webgpu:api,validation,buffers:*]
+      [:]
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and debug: FAIL
+          if os == "linux" and not debug: [PASS, FAIL]
+          if os == "mac": FAIL
+          if os == "android": FAIL
+    "###);
+}
+
+#[test]
+fn update_expected_review() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,operation,buffers:*]\n",
+                "  [:a]\n",
+                "    expected: FAIL\n",
+                "  [:b]\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    },
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*",
+                        "status": "OK",
+                        "subtests": [
+                            { "name": ":a", "status": "PASS" },
+                            { "name": ":b", "status": "PASS" }
+                        ]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let mut reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &Default::default(),
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    let current = read_and_parse_all_metadata(&fs, gecko_checkout, Browser::Firefox)
+        .collect::<Result<IndexMap<_, _>, _>>()
+        .unwrap();
+    let mut changes = Vec::new();
+    reconciliation.review(&current, |change| {
+        changes.push(change.to_string());
+        match changes.len() {
+            1 | 3 => ReviewDecision::Accept,
+            2 => ReviewDecision::Split,
+            _ => ReviewDecision::Skip,
+        }
+    });
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(changes[0], @r###"
+    gecko/testing/web-platform/mozilla/meta/webgpu/cts/webgpu/cts.https.html.ini
+    -[cts.https.html?q=webgpu:api,operation,adapter:*]
+    -  [:]
+    -    expected: FAIL
+    +[cts.https.html?q=webgpu:api,operation,adapter:*]
+    +  [:]
+    +    expected:
+    +      if os == "win": FAIL
+    +      if os == "linux" and debug: FAIL
+    +      if os == "mac": FAIL
+    +      if os == "android": FAIL
+    reported:
+      Linux Optimized: OK
+      [:] Linux Optimized: PASS
+    "###);
+    insta::assert_snapshot!(changes[2], @r###"
+    gecko/testing/web-platform/mozilla/meta/webgpu/cts/webgpu/cts.https.html.ini (subtest [:a] only)
+    -[cts.https.html?q=webgpu:api,operation,buffers:*]
+    -  [:a]
+    -    expected: FAIL
+    +[cts.https.html?q=webgpu:api,operation,buffers:*]
+    +  [:a]
+    +    expected:
+    +      if os == "win": FAIL
+    +      if os == "linux" and debug: FAIL
+    +      if os == "mac": FAIL
+    +      if os == "android": FAIL
+    reported:
+      [:a] Linux Optimized: PASS
+    "###);
+    assert_eq!(changes.len(), 4);
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:]
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and debug: FAIL
+          if os == "mac": FAIL
+          if os == "android": FAIL
+
+
+    [cts.https.html?q=webgpu:api,operation,buffers:*]
+      [:a]
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and debug: FAIL
+          if os == "mac": FAIL
+          if os == "android": FAIL
+
+      [:b]
+        expected: FAIL
+    "###);
+}
+
+#[test]
+fn update_expected_sample() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,operation,buffers:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    },
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            sample: Some(Sample::Count(1)),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    // Only the first test in reports is sampled.
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:]
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and debug: FAIL
+          if os == "mac": FAIL
+          if os == "android": FAIL
+
+
+    [cts.https.html?q=webgpu:api,operation,buffers:*]
+      [:]
+        expected: FAIL
+    "###);
+
+    assert_eq!("0.25".parse(), Ok(Sample::Fraction(0.25)));
+    assert_eq!("100".parse(), Ok(Sample::Count(100)));
+    assert!("1.5".parse::<Sample>().is_err());
+}
+
+#[test]
+fn stable_hasher() {
+    // Test vectors from the reference FNV implementation.
+    let hash = |bytes: &[u8]| {
+        let mut hasher = StableHasher::default();
+        hasher.write(bytes);
+        hasher.finish()
+    };
+    assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+}
+
+#[test]
+fn update_expected_keep_expectations() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  keep-expectations: true\n",
+                "  [:a]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,operation,labels:*]\n",
+                "  [:a]\n",
+                "    keep-expectations: true\n",
+                "    expected: FAIL\n",
+                "\n",
+                "  [:b]\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [
+                            { "name": ":a", "status": "PASS" },
+                            { "name": ":new", "status": "PASS" }
+                        ]
+                    },
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,labels:*",
+                        "status": "OK",
+                        "subtests": [
+                            { "name": ":a", "status": "PASS" },
+                            { "name": ":b", "status": "PASS" }
+                        ]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::ResetAll.strategy(),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      keep-expectations: true
+      [:a]
+        expected: FAIL
+
+
+    [cts.https.html?q=webgpu:api,operation,labels:*]
+      [:a]
+        keep-expectations: true
+        expected: FAIL
+
+      [:b]
+    "###);
+}
+
+#[test]
+fn update_expected_keeps_bugs() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  bug: https://bugzilla.mozilla.org/show_bug.cgi?id=1234567\n",
+                "  [:a]\n",
+                "    bug: https://bugzilla.mozilla.org/show_bug.cgi?id=7654321\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [
+                            { "name": ":a", "status": "PASS" }
+                        ]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::ResetAll.strategy(),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      bug: https://bugzilla.mozilla.org/show_bug.cgi?id=1234567
+      [:a]
+        bug: https://bugzilla.mozilla.org/show_bug.cgi?id=7654321
+    "###);
+}
+
+#[test]
+fn update_expected_reenable_expired() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  disabled: true\n",
+                "  disabled-until: 2024-06-01\n",
+                "  [:a]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,operation,labels:*]\n",
+                "  [:a]\n",
+                "    disabled: true\n",
+                "    disabled-until: 2024-06-02\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":a", "status": "PASS" }]
+                    },
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,labels:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":a", "status": "PASS" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::Merge.strategy(),
+            reenable_expired_as_of: NaiveDate::from_ymd_opt(2024, 6, 1),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    assert_eq!(
+        reconciliation
+            .summary
+            .reenabled_tests
+            .iter()
+            .map(|test| test.test_name().to_string())
+            .collect::<Vec<_>>(),
+        ["cts.https.html?q=webgpu:api,operation,adapter:*"]
+    );
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:a]
+
+
+    [cts.https.html?q=webgpu:api,operation,labels:*]
+      [:a]
+        disabled: true
+        disabled-until: 2024-06-02
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and debug: FAIL
+          if os == "linux" and not debug: [PASS, FAIL]
+          if os == "mac": FAIL
+          if os == "android": FAIL
+    "###);
+}
+
+#[test]
+fn update_expected_reenable_only_matching() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:a]\n",
+                "    disabled: true\n",
+                "    expected: FAIL\n",
+                "\n",
+                "  [:b]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,operation,labels:*]\n",
+                "  disabled: true\n",
+                "  [:a]\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [
+                            { "name": ":a", "status": "PASS" },
+                            { "name": ":b", "status": "PASS" }
+                        ]
+                    },
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,labels:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":a", "status": "PASS" }]
+                    },
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":a", "status": "PASS" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let patterns = vec!["webgpu:api,operation,adapter:*".parse().unwrap()];
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::Merge.strategy(),
+            reenable: patterns.clone(),
+            only_tests: Some(patterns),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    let ReconciliationSummary {
+        new_tests,
+        reenabled_tests,
+        ..
+    } = &reconciliation.summary;
+    assert!(new_tests.is_empty());
+    assert_eq!(
+        reenabled_tests
+            .iter()
+            .map(|test| test.test_name().to_string())
+            .collect::<Vec<_>>(),
+        ["cts.https.html?q=webgpu:api,operation,adapter:*"]
+    );
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    insta::assert_snapshot!(fs.files()[&meta_path], @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:a]
+
+      [:b]
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and debug: FAIL
+          if os == "linux" and not debug: [PASS, FAIL]
+          if os == "mac": FAIL
+          if os == "android": FAIL
+
+
+    [cts.https.html?q=webgpu:api,operation,labels:*]
+      disabled: true
+      [:a]
+        expected: FAIL
+    "###);
+}
+
+#[test]
+fn update_expected_reenable_bug() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:a]\n",
+                "    disabled: https://bugzilla.mozilla.org/show_bug.cgi?id=111\n",
+                "    expected: FAIL\n",
+                "\n",
+                "  [:b]\n",
+                "    disabled: https://bugzilla.mozilla.org/show_bug.cgi?id=222\n",
+                "    expected: FAIL\n",
+                "\n",
+                "  [:c]\n",
+                "    disabled:\n",
+                "      if os == \"linux\": https://bugzilla.mozilla.org/show_bug.cgi?id=111\n",
+                "      if os == \"win\": https://bugzilla.mozilla.org/show_bug.cgi?id=222\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": [
+                            { "name": ":a", "status": "PASS" },
+                            { "name": ":b", "status": "PASS" },
+                            { "name": ":c", "status": "PASS" }
+                        ]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let patterns = vec!["webgpu:api,operation,adapter:*".parse().unwrap()];
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::Merge.strategy(),
+            reenable: patterns.clone(),
+            reenable_bug: Some(111),
+            only_tests: Some(patterns),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    assert_eq!(reconciliation.summary.reenabled_tests.len(), 1);
+
+    let test = reconciliation.files[&meta_path]
+        .tests
+        .values()
+        .next()
+        .unwrap();
+    let disabled_reason = |subtest: &str, platform| {
+        test.subtests[&SectionHeader(subtest.to_owned())]
+            .properties
+            .disabled_reason_on(platform, BuildProfile::Optimized)
+            .map(|reason| reason.to_string())
+    };
+    let bug_222 = Some("https://bugzilla.mozilla.org/show_bug.cgi?id=222".to_owned());
+    assert_eq!(disabled_reason(":a", Platform::Linux), None);
+    // Disabled for a different bug, so left as it is.
+    assert_eq!(disabled_reason(":b", Platform::Linux), bug_222);
+    assert_eq!(disabled_reason(":c", Platform::Linux), None);
+    assert_eq!(disabled_reason(":c", Platform::Windows), bug_222);
+}
+
+#[test]
+fn update_expected_disable_perma_crashes() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+        .join("cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            meta_path.clone(),
+            concat!(
+                "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+                "  [:a]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,operation,labels:*]\n",
+                "  [:a]\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "CRASH",
+                        "subtests": []
+                    },
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,labels:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":a", "status": "FAIL" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::ResetContradictory.strategy(),
+            disable_perma_crashes: Some("https://example.com/bug".to_owned()),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    assert_eq!(
+        reconciliation
+            .summary
+            .disabled_tests
+            .iter()
+            .map(|test| test.test_name().to_string())
+            .collect::<Vec<_>>(),
+        ["cts.https.html?q=webgpu:api,operation,adapter:*"]
+    );
+    reconciliation
+        .write_to(&fs, &AtomicBool::new(false))
+        .unwrap();
+
+    // N.B.: Only the configuration that crashed is disabled.
+    let written = fs.files().remove(&meta_path).unwrap();
+    assert!(written.contains(concat!(
+        "  disabled:\n",
+        "    if os == \"linux\" and not debug: https://example.com/bug\n",
+    )));
+    assert_eq!(written.matches("https://example.com/bug").count(), 1);
+}
+
+#[test]
+fn update_expected_relocation() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_dir = Path::new("testing/web-platform/mozilla/meta/webgpu");
+    let old_path = meta_dir.join("chunked/1/cts.https.html.ini");
+    let new_path = meta_dir.join("cts/webgpu/cts.https.html.ini");
+    let report_path = PathBuf::from("wptreport.json");
+
+    let fs = [
+        (
+            gecko_checkout.join(&old_path),
+            concat!(
+                "[cts.https.html?q=webgpu:api,moved:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+                "\n",
+                "[cts.https.html?q=webgpu:api,stays:*]\n",
+                "  [:]\n",
+                "    expected: FAIL\n",
+            ),
+        ),
+        (
+            report_path.clone(),
+            r#"{
+                "run_info": {
+                    "os": "linux",
+                    "processor": "x86_64",
+                    "win11_2009": false,
+                    "debug": false
+                },
+                "results": [
+                    {
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,moved:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    },
+                    {
+                        "test": "/_mozilla/webgpu/chunked/1/cts.https.html?q=webgpu:api,stays:*",
+                        "status": "OK",
+                        "subtests": [{ "name": ":", "status": "PASS" }]
+                    }
+                ]
+            }"#,
+        ),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+
+    let reconciliation = update_expected(
+        &fs,
+        gecko_checkout,
+        vec![report_path],
+        &ReconcileOptions {
+            strategy: ReportProcessingPreset::Merge.strategy(),
+            ..Default::default()
+        },
+        &Default::default(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    assert_eq!(reconciliation.summary.relocated_tests.len(), 1);
+
+    let mut manifest = reconciliation.into_write_manifest();
+    assert_eq!(
+        manifest.relocations,
+        [RelocatedTest {
+            test: "_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,moved:*".to_owned(),
+            from: old_path.clone(),
+            to: new_path.clone(),
+        }]
+    );
+    manifest.write(&fs, &AtomicBool::new(false)).unwrap();
+
+    let files = fs.files();
+    insta::assert_snapshot!(files[&gecko_checkout.join(&old_path)], @r###"
+    [cts.https.html?q=webgpu:api,stays:*]
+      [:]
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and debug: FAIL
+          if os == "linux" and not debug: [PASS, FAIL]
+          if os == "mac": FAIL
+          if os == "android": FAIL
+    "###);
+    insta::assert_snapshot!(files[&gecko_checkout.join(&new_path)], @r###"
+    [cts.https.html?q=webgpu:api,moved:*]
+      [:]
+        expected:
+          if os == "win": FAIL
+          if os == "linux" and debug: FAIL
+          if os == "linux" and not debug: [PASS, FAIL]
+          if os == "mac": FAIL
+          if os == "android": FAIL
+    "###);
+}
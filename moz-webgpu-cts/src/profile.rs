@@ -0,0 +1,261 @@
+//! Collection of timings and counts from `tracing` spans, for reporting performance data with
+//! `--profile`.
+//!
+//! Each span name is treated as a phase of execution. Numeric fields recorded on spans (i.e.,
+//! `num_files`) are summed per phase, and rendered alongside their throughput. Fields named
+//! `num_bytes` are rendered as data sizes.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use indexmap::IndexMap;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// A [`Layer`] that aggregates span timings and fields into its [`Profile`].
+pub struct ProfileLayer {
+    profile: Profile,
+}
+
+/// Statistics gathered by a [`ProfileLayer`], in the order their phases were first completed.
+/// Renders as a human-readable report.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    phases: Arc<Mutex<IndexMap<&'static str, PhaseStats>>>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PhaseStats {
+    /// The name of the phase this one was nested in, if any.
+    pub parent: Option<&'static str>,
+    /// The number of spans with this name that were closed.
+    pub count: usize,
+    /// The time from creation to closing of each span, summed.
+    pub elapsed: Duration,
+    /// Numeric fields recorded on spans, summed.
+    pub fields: IndexMap<&'static str, u64>,
+}
+
+impl ProfileLayer {
+    pub fn new() -> (Self, Profile) {
+        let profile = Profile::default();
+        (
+            Self {
+                profile: profile.clone(),
+            },
+            profile,
+        )
+    }
+}
+
+impl Profile {
+    /// Returns a copy of the statistics gathered so far.
+    pub fn phases(&self) -> IndexMap<&'static str, PhaseStats> {
+        self.phases.lock().unwrap().clone()
+    }
+}
+
+/// Stored in each span's extensions until it is closed.
+struct SpanTiming {
+    start: Instant,
+    fields: IndexMap<&'static str, u64>,
+}
+
+struct FieldVisitor<'a>(&'a mut IndexMap<&'static str, u64>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name(), value);
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        if let Ok(value) = value.try_into() {
+            self.record_u64(field, value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+}
+
+impl<S> Layer<S> for ProfileLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = IndexMap::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        span.extensions_mut().insert(SpanTiming {
+            start: Instant::now(),
+            fields,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(timing) = extensions.get_mut::<SpanTiming>() {
+            values.record(&mut FieldVisitor(&mut timing.fields));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(SpanTiming { start, fields }) = span.extensions_mut().remove::<SpanTiming>()
+        else {
+            return;
+        };
+
+        let mut phases = self.profile.phases.lock().unwrap();
+        let stats = phases.entry(span.name()).or_insert_with(|| PhaseStats {
+            parent: span.parent().map(|parent| parent.name()),
+            ..Default::default()
+        });
+        stats.count += 1;
+        stats.elapsed += start.elapsed();
+        for (name, value) in fields {
+            *stats.fields.entry(name).or_default() += value;
+        }
+    }
+}
+
+/// Returns the peak resident set size of this process, in bytes, if the platform exposes it.
+pub fn peak_memory_bytes() -> Option<u64> {
+    if cfg!(target_os = "linux") {
+        // See `proc(5)`.
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let kib = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))?
+            .trim()
+            .strip_suffix("kB")?
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some(kib * 1024)
+    } else {
+        None
+    }
+}
+
+struct Bytes(f64);
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self(bytes) = *self;
+        let mib = bytes / (1024.0 * 1024.0);
+        write!(f, "{mib:.1} MiB")
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let phases = self.phases();
+
+        write!(f, "profile:\n  peak memory: ")?;
+        match peak_memory_bytes() {
+            Some(bytes) => writeln!(f, "{}", Bytes(bytes as f64))?,
+            None => writeln!(f, "unavailable on this platform")?,
+        }
+
+        fn write_phase(
+            f: &mut Formatter<'_>,
+            phases: &IndexMap<&'static str, PhaseStats>,
+            name: &'static str,
+            depth: usize,
+        ) -> fmt::Result {
+            let PhaseStats {
+                parent: _,
+                count,
+                elapsed,
+                fields,
+            } = &phases[name];
+            let indent = "  ".repeat(depth + 1);
+            let secs = elapsed.as_secs_f64();
+            write!(f, "{indent}{name}: {secs:.3}s")?;
+            if *count > 1 {
+                write!(f, " total over {count} spans")?;
+            }
+            for (field, value) in fields {
+                let per_sec = *value as f64 / secs.max(f64::EPSILON);
+                if *field == "num_bytes" {
+                    let bytes = *value as f64;
+                    write!(f, ", {} ({}/s)", Bytes(bytes), Bytes(per_sec))?;
+                } else {
+                    write!(f, ", {field}: {value} ({per_sec:.1}/s)")?;
+                }
+            }
+            writeln!(f)?;
+
+            for (child, stats) in phases {
+                if stats.parent == Some(name) && *child != name {
+                    write_phase(f, phases, child, depth + 1)?;
+                }
+            }
+            Ok(())
+        }
+
+        for (name, stats) in &phases {
+            let is_root = stats
+                .parent
+                .map_or(true, |parent| !phases.contains_key(parent));
+            if is_root {
+                write_phase(f, &phases, name, 0)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn aggregates_spans() {
+    use tracing_subscriber::prelude::*;
+
+    let (layer, profile) = ProfileLayer::new();
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("read_reports", num_bytes = tracing::field::Empty);
+        for _ in 0..3 {
+            let _entered = tracing::info_span!(parent: &span, "read_report").entered();
+        }
+        span.record("num_bytes", 2048);
+        drop(span);
+
+        let _entered = tracing::info_span!("reconcile", num_tests = 5).entered();
+    });
+
+    let phases = profile
+        .phases()
+        .into_iter()
+        .map(|(name, stats)| {
+            let PhaseStats {
+                parent,
+                count,
+                elapsed: _,
+                fields,
+            } = stats;
+            (name, parent, count, fields.into_iter().collect::<Vec<_>>())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        phases,
+        [
+            ("read_report", Some("read_reports"), 3, vec![]),
+            ("read_reports", None, 1, vec![("num_bytes", 2048)]),
+            ("reconcile", None, 1, vec![("num_tests", 5)]),
+        ]
+    );
+}
@@ -0,0 +1,36 @@
+//! Progress bars for long phases of execution (i.e., parsing metadata and reports), shown on
+//! `stderr` with `--progress`.
+
+use std::sync::atomic::{self, AtomicBool};
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Shows bars returned by [`bar`] for the rest of this process.
+pub fn enable() {
+    ENABLED.store(true, atomic::Ordering::Relaxed);
+}
+
+/// Returns a progress bar for `phase`, with `len` steps if known in advance, or a spinner
+/// counting steps otherwise. The bar is hidden unless enabled with [`enable`], or if `stderr` is
+/// not a terminal.
+pub fn bar(phase: &'static str, len: Option<usize>) -> ProgressBar {
+    if !ENABLED.load(atomic::Ordering::Relaxed) {
+        return ProgressBar::hidden();
+    }
+    let (bar, template) = match len {
+        Some(len) => (
+            ProgressBar::new(len.try_into().unwrap()),
+            "{prefix:>20} [{bar:40}] {human_pos}/{human_len} ({per_sec}, {eta} left)",
+        ),
+        None => (
+            ProgressBar::new_spinner(),
+            "{prefix:>20} {spinner} {human_pos} ({per_sec})",
+        ),
+    };
+    let style = ProgressStyle::with_template(template)
+        .unwrap()
+        .progress_chars("=> ");
+    bar.with_style(style).with_prefix(phase)
+}
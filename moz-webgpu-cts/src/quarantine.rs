@@ -0,0 +1,225 @@
+//! Quarantining of tests that have become flaky: expecting every outcome of them until someone
+//! reviews them, so that they neither fail CI nor have their expectations narrowed meanwhile.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::{Days, NaiveDate};
+use enumset::{EnumSet, EnumSetType};
+use indexmap::IndexMap;
+use whippit::metadata::SectionHeader;
+
+use crate::{
+    metadata::{File, Test, TestProps},
+    shared::{Browser, Expected, FullyExpandedExpectedPropertyValue, TestPath},
+    test_pattern::TestPattern,
+};
+
+/// What [`quarantine`] does to matching tests.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QuarantineAction {
+    /// Marks tests as quarantined on the given day, and widens the expectations of them and
+    /// their subtests to every outcome, on every platform and build profile.
+    Quarantine(NaiveDate),
+    /// Removes the quarantine of tests. Their expectations are left as they are, so they stay
+    /// widened until reset (i.e., with `update-expected --preset reset-all`).
+    Release,
+}
+
+/// The changes made by [`quarantine`].
+#[derive(Debug, Default)]
+pub struct Quarantined<'a> {
+    /// Files with changed tests, which should be written back.
+    pub changed_files: BTreeSet<Arc<PathBuf>>,
+    pub num_tests: usize,
+    /// Patterns that matched no test, either in metadata or, for test URL paths naming a single
+    /// test, as a new section.
+    pub unmatched: Vec<&'a TestPattern>,
+}
+
+/// Applies `action` to tests in `files` matching any of `patterns`.
+///
+/// Tests that pass everywhere have no section in metadata. When quarantining, a section is
+/// added for each test URL path in `patterns` that matched no existing section and names a
+/// single test, i.e., has no `*` besides the one ending a CTS query (`…?q=webgpu:foo:bar:*`).
+/// Subtests missing from metadata are still expected to pass.
+pub fn quarantine<'a>(
+    gecko_checkout: &Path,
+    files: &mut IndexMap<Arc<PathBuf>, File>,
+    browser: Browser,
+    patterns: &'a [TestPattern],
+    action: QuarantineAction,
+) -> Quarantined<'a> {
+    let mut quarantined = Quarantined::default();
+    let mut matched = vec![false; patterns.len()];
+
+    for (path, file) in files.iter_mut() {
+        let Ok(rel_path) = path.strip_prefix(gecko_checkout) else {
+            continue;
+        };
+        for (SectionHeader(name), test) in &mut file.tests {
+            let Ok(test_path) = TestPath::from_metadata_test(rel_path, name) else {
+                continue;
+            };
+            let mut is_match = false;
+            for (pattern, matched) in patterns.iter().zip(&mut matched) {
+                if pattern.matches(&test_path) {
+                    *matched = true;
+                    is_match = true;
+                }
+            }
+            if is_match && apply(test, action) {
+                quarantined.changed_files.insert(path.clone());
+                quarantined.num_tests += 1;
+            }
+        }
+    }
+
+    for (pattern, matched) in patterns.iter().zip(matched) {
+        if matched {
+            continue;
+        }
+        let new_test_path = match (pattern, action) {
+            (TestPattern::UrlGlob(url), QuarantineAction::Quarantine(_))
+                if !url.strip_suffix(":*").unwrap_or(url).contains('*') =>
+            {
+                TestPath::from_execution_report(browser, url).ok()
+            }
+            _ => None,
+        };
+        let Some(test_path) = new_test_path else {
+            quarantined.unmatched.push(pattern);
+            continue;
+        };
+        let path = Arc::new(gecko_checkout.join(test_path.rel_metadata_path().to_string()));
+        let test = files
+            .entry(path.clone())
+            .or_default()
+            .tests
+            .entry(SectionHeader(test_path.test_name().to_string()))
+            .or_default();
+        apply(test, action);
+        quarantined.changed_files.insert(path);
+        quarantined.num_tests += 1;
+    }
+
+    quarantined
+}
+
+/// Applies `action` to `test`, returning whether it changed.
+fn apply(test: &mut Test, action: QuarantineAction) -> bool {
+    match action {
+        QuarantineAction::Quarantine(date) => {
+            let Test {
+                properties,
+                subtests,
+            } = test;
+            properties.quarantined = Some(date);
+            widen(properties);
+            for subtest in subtests.values_mut() {
+                widen(&mut subtest.properties);
+            }
+            true
+        }
+        QuarantineAction::Release => test.properties.quarantined.take().is_some(),
+    }
+}
+
+fn widen<Out>(properties: &mut TestProps<Out>)
+where
+    Out: Default + EnumSetType,
+{
+    properties.expected = Some(FullyExpandedExpectedPropertyValue::uniform(
+        Expected::new(EnumSet::all()).unwrap(),
+    ));
+    properties.expected_by_variant.clear();
+    properties.verbatim_expected = None;
+}
+
+/// Returns whether a test quarantined on `quarantined` is due for review on `today`, i.e., has
+/// been quarantined for at least `review_days`.
+pub fn is_due_for_review(quarantined: NaiveDate, review_days: u64, today: NaiveDate) -> bool {
+    quarantined
+        .checked_add_days(Days::new(review_days))
+        .map_or(false, |due| due <= today)
+}
+
+#[test]
+fn quarantine_and_release() {
+    use crate::metadata::{format_file, FileBuilder, SubtestBuilder, TestBuilder, TestOutcome};
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = Arc::new(
+        gecko_checkout
+            .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+            .join("cts.https.html.ini"),
+    );
+    let file = FileBuilder::new()
+        .test(
+            TestBuilder::new("cts.https.html?q=webgpu:api,operation,adapter:*")
+                .expected(Expected::permanent(TestOutcome::Timeout))
+                .subtest(SubtestBuilder::new(":a")),
+        )
+        .test(TestBuilder::new(
+            "cts.https.html?q=webgpu:api,operation,buffers:*",
+        ))
+        .build()
+        .unwrap();
+    let mut files = [(meta_path.clone(), file)].into_iter().collect();
+
+    let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let patterns = [
+        "webgpu:api,operation,adapter:*",
+        "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,validation,queue:*",
+        "webgpu:api,validation,nope:*",
+    ]
+    .map(|pattern| pattern.parse::<TestPattern>().unwrap());
+    let quarantined = quarantine(
+        gecko_checkout,
+        &mut files,
+        Browser::Firefox,
+        &patterns,
+        QuarantineAction::Quarantine(date),
+    );
+    assert_eq!(quarantined.num_tests, 2);
+    assert_eq!(quarantined.changed_files, [meta_path.clone()].into());
+    assert_eq!(quarantined.unmatched, [&patterns[2]]);
+    insta::assert_snapshot!(format_file(&files[&meta_path]), @r###"
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      quarantined: 2024-06-01
+      expected: [OK, TIMEOUT, CRASH, ERROR, SKIP]
+      [:a]
+        expected: [PASS, FAIL, TIMEOUT, CRASH, NOTRUN]
+
+
+    [cts.https.html?q=webgpu:api,operation,buffers:*]
+
+
+    [cts.https.html?q=webgpu:api,validation,queue:*]
+      quarantined: 2024-06-01
+      expected: [OK, TIMEOUT, CRASH, ERROR, SKIP]
+    "###);
+
+    let released = quarantine(
+        gecko_checkout,
+        &mut files,
+        Browser::Firefox,
+        &patterns[..1],
+        QuarantineAction::Release,
+    );
+    assert_eq!(released.num_tests, 1);
+    assert!(files[&meta_path]
+        .tests
+        .values()
+        .next()
+        .unwrap()
+        .properties
+        .quarantined
+        .is_none());
+
+    assert!(!is_due_for_review(date, 14, date + Days::new(13)));
+    assert!(is_due_for_review(date, 14, date + Days::new(14)));
+}
@@ -1,54 +1,874 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeSet,
+    fmt::{self, Display, Formatter},
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
 use serde::{
-    de::{Deserializer, Error},
-    Deserialize,
+    de::{
+        value, DeserializeOwned, DeserializeSeed, Error as _, IgnoredAny, IntoDeserializer,
+        MapAccess, SeqAccess, Visitor,
+    },
+    Deserialize, Deserializer, Serialize,
 };
 
-use crate::metadata::{BuildProfile, Platform, SubtestOutcome, TestOutcome};
+use serde_json::{value::RawValue, Map, Value};
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct ExecutionReport {
+use crate::{
+    error::Error,
+    metadata::{BuildProfile, Platform, RunInfoValue, RunInfoVariant, SubtestOutcome, TestOutcome},
+};
+
+mod chromium;
+mod input;
+mod mozlog;
+
+pub use self::input::{extract_reports, ReportFile, ReportFormat};
+
+/// A `wptreport.json` file.
+///
+/// Strings are borrowed from the buffer this was parsed from wherever possible, since reports
+/// commonly contain hundreds of thousands of subtest names.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ExecutionReport<'a> {
     pub run_info: RunInfo,
-    #[serde(rename = "results")]
-    pub entries: Vec<TestExecutionEntry>,
+    #[serde(borrow)]
+    pub entries: Vec<TestExecutionEntry<'a>>,
+    /// Data found in the report that this tool doesn't understand, and ignored.
+    pub unrecognized: BTreeSet<Unrecognized>,
 }
 
+/// Options for parsing [`ExecutionReport`]s.
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// See [`ExecutionReport::from_json`].
+    pub deny_unrecognized: bool,
+    /// Checked in order before the built-in rules for attributing a run to a [`Platform`]. The
+    /// first matching override wins.
+    pub platform_overrides: Vec<PlatformOverride>,
+    /// If set, [`update_expected`] caches a summary of the runs parsed from each report in this
+    /// directory, keyed by the report's contents and the other options here, and parses cached
+    /// summaries instead of reports whenever possible.
+    ///
+    /// [`update_expected`]: crate::process_reports::update_expected
+    pub summary_cache: Option<PathBuf>,
+    /// If set, [`update_expected`] reads reports one at a time, and accumulates each entry of a
+    /// report as soon as it is parsed, rather than parsing whole reports first; see
+    /// [`stream_all_from_json`]. Memory use is then bounded by the size of the largest report
+    /// file, rather than growing with the number of reports and their parsed entries. Reports are
+    /// read serially, duplicate reports are told apart by a hash of their contents alone, and
+    /// `summary_cache` is not used.
+    ///
+    /// [`update_expected`]: crate::process_reports::update_expected
+    pub stream: bool,
+    /// If set, reports are parsed as this format, rather than one detected from their contents;
+    /// see [`ReportFormat::sniff`].
+    pub format: Option<ReportFormat>,
+}
+
+impl<'a> ExecutionReport<'a> {
+    /// Parses `contents` as a `wptreport.json` file. `path` is only used for error reporting.
+    ///
+    /// Newer versions of `wptrunner` may add fields and statuses to reports. By default, these are
+    /// ignored and recorded in [`Self::unrecognized`]; tests and subtests with unrecognized
+    /// statuses are omitted from [`Self::entries`]. If [`ParseOptions::deny_unrecognized`] is set,
+    /// [`Error::UnrecognizedReportContents`] is returned instead.
+    ///
+    /// If the run's platform isn't recognized, [`Error::UnknownPlatform`] is returned.
+    pub fn from_json(
+        path: &Path,
+        contents: &'a str,
+        options: &ParseOptions,
+    ) -> Result<Self, Error> {
+        Self::from_json_or_unknown_platform(path, contents, options)?.map_err(|run_info| {
+            Error::UnknownPlatform {
+                path: path.to_owned(),
+                run_info,
+            }
+        })
+    }
+
+    /// Copies all data borrowed from the buffer this was parsed from, so that the buffer can be
+    /// dropped.
+    pub fn into_owned(self) -> ExecutionReport<'static> {
+        let Self {
+            run_info,
+            entries,
+            unrecognized,
+        } = self;
+        ExecutionReport {
+            run_info,
+            entries: entries
+                .into_iter()
+                .map(TestExecutionEntry::into_owned)
+                .collect(),
+            unrecognized,
+        }
+    }
+
+    fn from_json_or_unknown_platform(
+        path: &Path,
+        contents: &'a str,
+        options: &ParseOptions,
+    ) -> Result<Result<Self, UnknownPlatform>, Error> {
+        let mut unrecognized = BTreeSet::new();
+        let raw = deserialize_tracking_unrecognized(contents, "", &mut unrecognized).map_err(
+            |source| Error::ParseReport {
+                path: path.to_owned(),
+                source,
+            },
+        )?;
+        Self::from_raw(path, raw, unrecognized, options)
+    }
+
+    /// Parses `contents` as one or more runs' worth of `wptreport.json` data, sniffing which of
+    /// the following layouts is in use:
+    ///
+    /// * A single report, as written by `wptrunner`.
+    /// * Several reports merged into one file, either as a top-level array of reports or as
+    ///   reports concatenated one after another.
+    /// * Newline-delimited JSON, where a line with only `run_info` starts a new run, and
+    ///   subsequent lines are single entries of its `results`.
+    /// * A summary of runs parsed earlier, as written by [`summarize_runs`].
+    /// * A `mozlog` action stream, i.e., `wptrunner`'s `raw.log`, from which one run is
+    ///   reconstructed per suite, or any file if [`ParseOptions::format`] is
+    ///   [`ReportFormat::MozLog`].
+    /// * Chromium's JSON test results, only if [`ParseOptions::format`] is
+    ///   [`ReportFormat::Chromium`].
+    ///
+    /// Each run is parsed as its own [`ExecutionReport`], so that entries are attributed to the
+    /// correct [`RunInfo`]. Runs whose platform isn't recognized are returned as
+    /// [`UnknownPlatform`]s, rather than failing the whole file. See [`Self::from_json`] for
+    /// details on other arguments.
+    pub fn all_from_json(
+        path: &Path,
+        contents: &'a str,
+        options: &ParseOptions,
+    ) -> Result<Vec<Result<Self, UnknownPlatform>>, Error> {
+        let parse_err = |source| Error::ParseReport {
+            path: path.to_owned(),
+            source,
+        };
+
+        if contents.starts_with(SUMMARY_PREFIX) {
+            let ReportSummary { version, runs } =
+                serde_json::from_str(contents).map_err(parse_err)?;
+            if version != SUMMARY_VERSION {
+                return Err(parse_err(serde::de::Error::custom(format_args!(
+                    "unsupported summary version {version}, expected {SUMMARY_VERSION}"
+                ))));
+            }
+            return Ok(runs);
+        }
+
+        let format = options
+            .format
+            .or_else(|| ReportFormat::sniff(contents.as_bytes()));
+        match format {
+            Some(ReportFormat::MozLog) => return mozlog::runs_from_mozlog(path, contents, options),
+            Some(ReportFormat::Chromium) => {
+                return chromium::runs_from_chromium(path, contents, options)
+            }
+            _ => (),
+        }
+
+        let values: Vec<&'a RawValue> = if contents.trim_start().starts_with('[') {
+            serde_json::from_str(contents).map_err(parse_err)?
+        } else {
+            serde_json::Deserializer::from_str(contents)
+                .into_iter()
+                .collect::<Result<_, _>>()
+                .map_err(parse_err)?
+        };
+        if let [] | [_] = &values[..] {
+            // N.B.: This is by far the most common case, so avoid sniffing. If there are no
+            // values, let the usual parsing machinery produce an error. A lone value may still be
+            // wrapped in an array, so only it is parsed.
+            let contents = values.first().map_or(contents, |value| value.get());
+            return Self::from_json_or_unknown_platform(path, contents, options)
+                .map(|run| vec![run]);
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Sniffed {
+            run_info: Option<IgnoredAny>,
+            results: Option<IgnoredAny>,
+            test: Option<IgnoredAny>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct RunHeader {
+            run_info: RawRunInfo,
+        }
+
+        let mut runs = Vec::new();
+        let mut streamed_run = None::<(RawExecutionReport<'a>, BTreeSet<Unrecognized>)>;
+        for value in values {
+            let value = value.get();
+            let Sniffed {
+                run_info,
+                results,
+                test,
+            } = serde_json::from_str(value).map_err(parse_err)?;
+
+            if test.is_some() {
+                let Some((run, unrecognized)) = &mut streamed_run else {
+                    return Err(parse_err(serde_json::Error::custom(
+                        "found a test entry before any `run_info`",
+                    )));
+                };
+                let entry = deserialize_tracking_unrecognized(value, "results[]", unrecognized)
+                    .map_err(parse_err)?;
+                run.results.push(entry);
+                continue;
+            }
+
+            if let Some((run, unrecognized)) = streamed_run.take() {
+                runs.push(Self::from_raw(path, run, unrecognized, options)?);
+            }
+            match (run_info, results) {
+                (_, Some(_)) => {
+                    runs.push(Self::from_json_or_unknown_platform(path, value, options)?)
+                }
+                (Some(_), None) => {
+                    let mut unrecognized = BTreeSet::new();
+                    let RunHeader { run_info } =
+                        deserialize_tracking_unrecognized(value, "", &mut unrecognized)
+                            .map_err(parse_err)?;
+                    let run = RawExecutionReport {
+                        run_info,
+                        results: Vec::new(),
+                    };
+                    streamed_run = Some((run, unrecognized));
+                }
+                (None, None) => {
+                    return Err(parse_err(serde_json::Error::custom(
+                        "expected a report, `run_info`, or test entry",
+                    )))
+                }
+            }
+        }
+        if let Some((run, unrecognized)) = streamed_run {
+            runs.push(Self::from_raw(path, run, unrecognized, options)?);
+        }
+
+        Ok(runs)
+    }
+
+    fn from_raw(
+        path: &Path,
+        raw: RawExecutionReport<'a>,
+        mut unrecognized: BTreeSet<Unrecognized>,
+        options: &ParseOptions,
+    ) -> Result<Result<Self, UnknownPlatform>, Error> {
+        let ParseOptions {
+            deny_unrecognized,
+            platform_overrides,
+            summary_cache: _,
+            stream: _,
+            format: _,
+        } = options;
+        let RawExecutionReport { run_info, results } = raw;
+
+        let run_info = match run_info.resolve(platform_overrides) {
+            Ok(Ok(run_info)) => run_info,
+            Ok(Err(unknown)) => return Ok(Err(unknown)),
+            Err(source) => {
+                return Err(Error::ParseReport {
+                    path: path.to_owned(),
+                    source,
+                })
+            }
+        };
+
+        let entries = results
+            .into_iter()
+            .filter_map(|entry| entry_from_raw(entry, &mut unrecognized))
+            .collect();
+
+        if *deny_unrecognized && !unrecognized.is_empty() {
+            return Err(Error::UnrecognizedReportContents {
+                path: path.to_owned(),
+                unrecognized: unrecognized.into_iter().collect(),
+            });
+        }
+
+        Ok(Ok(Self {
+            run_info,
+            entries,
+            unrecognized,
+        }))
+    }
+}
+
+/// Part of a run, as passed to the callback of [`stream_from_json`].
 #[derive(Debug)]
-pub(crate) struct RunInfo {
-    pub platform: Platform,
-    pub build_profile: BuildProfile,
+pub enum RunPart<'a> {
+    /// Starts a run. Every [`Self::Entry`] up to the next [`Self::End`] belongs to it.
+    Start(RunInfo),
+    Entry(TestExecutionEntry<'a>),
+    /// Ends a run, with what was found in it that this tool doesn't understand; see
+    /// [`ExecutionReport::unrecognized`].
+    End(BTreeSet<Unrecognized>),
+}
+
+/// Parses a `wptreport.json` file from `reader` like [`ExecutionReport::from_json`], but passes
+/// each entry of its `results` to `record` as soon as it is parsed, rather than collecting them
+/// all first. Only a single report, as written by `wptrunner`, is accepted; see
+/// [`ExecutionReport::all_from_json`] for other layouts.
+///
+/// Entries are only held in memory if `results` comes before `run_info`, since entries can't be
+/// attributed to a run before its `run_info` is known. If the run's platform isn't recognized,
+/// nothing is passed to `record`, and `Ok(Err(…))` is returned. If
+/// [`ParseOptions::deny_unrecognized`] is set, an error may still be returned after entries were
+/// passed to `record`.
+pub fn stream_from_json(
+    path: &Path,
+    reader: impl io::Read,
+    options: &ParseOptions,
+    record: &mut dyn FnMut(RunPart<'static>),
+) -> Result<Result<(), UnknownPlatform>, Error> {
+    let ParseOptions {
+        deny_unrecognized,
+        platform_overrides,
+        summary_cache: _,
+        stream: _,
+        format: _,
+    } = options;
+    let parse_err = |source| Error::ParseReport {
+        path: path.to_owned(),
+        source,
+    };
+
+    let mut unrecognized = BTreeSet::new();
+    let mut unrecognized_fields = BTreeSet::new();
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let mut track_fields = track_unrecognized_fields("", &mut unrecognized_fields);
+    let run = StreamedReport {
+        platform_overrides,
+        unrecognized: &mut unrecognized,
+        record: &mut *record,
+    }
+    .deserialize(serde_ignored::Deserializer::new(
+        &mut deserializer,
+        &mut track_fields,
+    ))
+    .map_err(parse_err)?;
+    deserializer.end().map_err(parse_err)?;
+
+    if let Err(unknown) = run {
+        return Ok(Err(unknown));
+    }
+    unrecognized.extend(unrecognized_fields);
+    if *deny_unrecognized && !unrecognized.is_empty() {
+        return Err(Error::UnrecognizedReportContents {
+            path: path.to_owned(),
+            unrecognized: unrecognized.into_iter().collect(),
+        });
+    }
+    record(RunPart::End(unrecognized));
+    Ok(Ok(()))
 }
 
-impl<'de> Deserialize<'de> for RunInfo {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+/// Parses `contents` like [`ExecutionReport::all_from_json`], but passes each run's parts to
+/// `record` rather than returning the runs. A single report, as written by `wptrunner`, is parsed
+/// incrementally with [`stream_from_json`]; other layouts are parsed in full first. Runs whose
+/// platform isn't recognized are returned instead.
+pub fn stream_all_from_json(
+    path: &Path,
+    contents: &[u8],
+    options: &ParseOptions,
+    record: &mut dyn FnMut(RunPart<'static>),
+) -> Result<Vec<UnknownPlatform>, Error> {
+    let is_single_report = matches!(options.format, None | Some(ReportFormat::WptReport))
+        && matches!(ReportFormat::sniff(contents), Some(ReportFormat::WptReport))
+        && !contents.starts_with(SUMMARY_PREFIX.as_bytes())
+        && contents.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{')
+        && serde_json::Deserializer::from_slice(contents)
+            .into_iter::<IgnoredAny>()
+            .nth(1)
+            .is_none();
+    if is_single_report {
+        return Ok(stream_from_json(path, contents, options, record)?
+            .err()
+            .into_iter()
+            .collect());
+    }
+
+    let contents = std::str::from_utf8(contents).map_err(|e| Error::ReadFile {
+        path: path.to_owned(),
+        source: io::Error::new(io::ErrorKind::InvalidData, e),
+    })?;
+    let mut unknown_platforms = Vec::new();
+    for run in ExecutionReport::all_from_json(path, contents, options)? {
+        match run {
+            Ok(ExecutionReport {
+                run_info,
+                entries,
+                unrecognized,
+            }) => {
+                record(RunPart::Start(run_info));
+                for entry in entries {
+                    record(RunPart::Entry(entry.into_owned()));
+                }
+                record(RunPart::End(unrecognized));
+            }
+            Err(unknown) => unknown_platforms.push(unknown),
+        }
+    }
+    Ok(unknown_platforms)
+}
+
+/// Deserializes a report for [`stream_from_json`], passing its parts to `record` along the way.
+struct StreamedReport<'s> {
+    platform_overrides: &'s [PlatformOverride],
+    unrecognized: &'s mut BTreeSet<Unrecognized>,
+    record: &'s mut dyn FnMut(RunPart<'static>),
+}
+
+impl<'de> DeserializeSeed<'de> for StreamedReport<'_> {
+    type Value = Result<(), UnknownPlatform>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: Deserializer<'de>,
     {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for StreamedReport<'_> {
+    type Value = Result<(), UnknownPlatform>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a `wptreport.json` report")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let Self {
+            platform_overrides,
+            unrecognized,
+            record,
+        } = self;
+
+        let mut run = None::<Result<(), UnknownPlatform>>;
+        let mut found_results = false;
+        let mut held_results = None::<Vec<RawTestExecutionEntry<'de>>>;
+        while let Some(key) = map.next_key::<String>()? {
+            match &*key {
+                "run_info" => {
+                    if run.is_some() {
+                        return Err(A::Error::duplicate_field("run_info"));
+                    }
+                    let run_info = map
+                        .next_value::<RawRunInfo>()?
+                        .resolve(platform_overrides)
+                        .map_err(A::Error::custom)?;
+                    run = Some(run_info.map(|run_info| record(RunPart::Start(run_info))));
+                    if let (Some(Ok(())), Some(results)) = (&run, held_results.take()) {
+                        for entry in results {
+                            if let Some(entry) = entry_from_raw(entry, unrecognized) {
+                                record(RunPart::Entry(entry.into_owned()));
+                            }
+                        }
+                    }
+                }
+                "results" => {
+                    if found_results {
+                        return Err(A::Error::duplicate_field("results"));
+                    }
+                    found_results = true;
+                    match &run {
+                        Some(run) => map.next_value_seed(StreamedResults {
+                            skip: run.is_err(),
+                            unrecognized: &mut *unrecognized,
+                            record: &mut *record,
+                        })?,
+                        None => held_results = Some(map.next_value()?),
+                    }
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        if !found_results {
+            return Err(A::Error::missing_field("results"));
+        }
+        run.ok_or_else(|| A::Error::missing_field("run_info"))
+    }
+}
+
+/// Deserializes a report's `results` for [`StreamedReport`], passing each entry to `record`
+/// unless `skip` is set.
+struct StreamedResults<'s> {
+    skip: bool,
+    unrecognized: &'s mut BTreeSet<Unrecognized>,
+    record: &'s mut dyn FnMut(RunPart<'static>),
+}
+
+impl<'de> DeserializeSeed<'de> for StreamedResults<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for StreamedResults<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a list of test results")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let Self {
+            skip,
+            unrecognized,
+            record,
+        } = self;
+        while let Some(entry) = seq.next_element::<RawTestExecutionEntry<'de>>()? {
+            if skip {
+                continue;
+            }
+            if let Some(entry) = entry_from_raw(entry, unrecognized) {
+                record(RunPart::Entry(entry.into_owned()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Converts `entry`, as found in a report's `results`, to a [`TestExecutionEntry`]. Returns `None`
+/// if the test's status isn't recognized. Unrecognized test and subtest statuses are recorded in
+/// `unrecognized`.
+fn entry_from_raw<'a>(
+    entry: RawTestExecutionEntry<'a>,
+    unrecognized: &mut BTreeSet<Unrecognized>,
+) -> Option<TestExecutionEntry<'a>> {
+    let RawTestExecutionEntry {
+        test_name,
+        subsuite,
+        status,
+        message,
+        subtests,
+    } = entry;
+
+    let subtests = subtests
+        .into_iter()
+        .filter_map(|subtest| {
+            let RawSubtestExecutionResult {
+                subtest_name,
+                status,
+            } = subtest;
+            match parse_status(&status) {
+                Some(outcome) => Some(SubtestExecutionResult {
+                    subtest_name,
+                    outcome,
+                }),
+                None => {
+                    unrecognized.insert(Unrecognized::SubtestStatus(status.into()));
+                    None
+                }
+            }
+        })
+        .collect();
+
+    let result = if status.is_empty() {
+        TestExecutionResult::JobMaybeTimedOut { subtests }
+    } else if let Some(outcome) = parse_status(&status) {
+        TestExecutionResult::Complete { outcome, subtests }
+    } else {
+        unrecognized.insert(Unrecognized::TestStatus(status.into()));
+        return None;
+    };
+
+    let message = message.filter(|_| {
+        matches!(
+            result,
+            TestExecutionResult::Complete {
+                outcome: TestOutcome::Error,
+                ..
+            }
+        )
+    });
+
+    Some(TestExecutionEntry {
+        test_name,
+        subsuite: Some(subsuite).filter(|subsuite| !subsuite.is_empty()),
+        message,
+        result,
+    })
+}
+
+/// The start of every [`ReportSummary`] serialized as JSON, by which summaries are told apart from
+/// reports.
+const SUMMARY_PREFIX: &str = "{\"moz_webgpu_cts_summary\":";
+
+/// Incremented whenever the format of [`ReportSummary`] changes.
+pub(crate) const SUMMARY_VERSION: u32 = 3;
+
+/// Runs parsed from a report, in a compact form that is much quicker to parse again than the
+/// report itself. Parsed by [`ExecutionReport::all_from_json`] like any report.
+#[derive(Debug, Deserialize)]
+struct ReportSummary<'a> {
+    #[serde(rename = "moz_webgpu_cts_summary")]
+    version: u32,
+    #[serde(borrow)]
+    runs: Vec<Result<ExecutionReport<'a>, UnknownPlatform>>,
+}
+
+/// Renders `runs`, as parsed by [`ExecutionReport::all_from_json`], as a summary that parses to
+/// the same runs with the same [`ParseOptions`].
+pub fn summarize_runs(runs: &[Result<ExecutionReport<'_>, UnknownPlatform>]) -> String {
+    #[derive(Serialize)]
+    struct ReportSummaryRef<'r, 'a> {
+        moz_webgpu_cts_summary: u32,
+        runs: &'r [Result<ExecutionReport<'a>, UnknownPlatform>],
+    }
+
+    serde_json::to_string(&ReportSummaryRef {
+        moz_webgpu_cts_summary: SUMMARY_VERSION,
+        runs,
+    })
+    .unwrap()
+}
+
+/// Deserializes `contents`, recording fields not known to be emitted by `wptrunner` in
+/// `unrecognized`. Field paths are recorded relative to `prefix`, i.e., `results[]` when
+/// deserializing a single test entry.
+fn deserialize_tracking_unrecognized<'a, T>(
+    contents: &'a str,
+    prefix: &str,
+    unrecognized: &mut BTreeSet<Unrecognized>,
+) -> Result<T, serde_json::Error>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer = serde_json::Deserializer::from_str(contents);
+    let parsed = serde_ignored::deserialize(
+        &mut deserializer,
+        track_unrecognized_fields(prefix, unrecognized),
+    )?;
+    deserializer.end()?;
+    Ok(parsed)
+}
+
+/// Returns a callback for [`serde_ignored`] that records ignored fields in `unrecognized`, as
+/// described by [`deserialize_tracking_unrecognized`].
+fn track_unrecognized_fields<'u>(
+    prefix: &'u str,
+    unrecognized: &'u mut BTreeSet<Unrecognized>,
+) -> impl FnMut(serde_ignored::Path<'_>) + 'u {
+    move |field| {
+        let field = match (prefix, field_pattern(&field)) {
+            ("", field) => field,
+            (prefix, field) => format!("{prefix}.{field}"),
+        };
+        if !KNOWN_UNUSED_FIELDS.contains(&&*field) {
+            unrecognized.insert(Unrecognized::Field(field));
+        }
+    }
+}
+
+/// Fields that `wptrunner` emits, but that this tool has no use for.
+const KNOWN_UNUSED_FIELDS: &[&str] = &[
+    "time_start",
+    "time_end",
+    "lsan_leaks",
+    "mozleak",
+    "results[].duration",
+    "results[].expected",
+    "results[].known_intermittent",
+    "results[].asserts",
+    "results[].screenshots",
+    "results[].subtests[].message",
+    "results[].subtests[].expected",
+    "results[].subtests[].known_intermittent",
+];
+
+/// Renders `path` as a dotted field path, with `[]` standing in for any array index, i.e.,
+/// `results[].subtests[].name`.
+fn field_pattern(path: &serde_ignored::Path<'_>) -> String {
+    use serde_ignored::Path;
+
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, index: _ } => format!("{}[]", field_pattern(parent)),
+        Path::Map { parent, key } => {
+            let parent = field_pattern(parent);
+            if parent.is_empty() {
+                key.clone()
+            } else {
+                format!("{parent}.{key}")
+            }
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => field_pattern(parent),
+    }
+}
+
+fn parse_status<Out>(status: &str) -> Option<Out>
+where
+    Out: DeserializeOwned,
+{
+    let deserializer: value::StrDeserializer<'_, value::Error> = status.into_deserializer();
+    Out::deserialize(deserializer).ok()
+}
+
+/// Something found in an [`ExecutionReport`] that this tool doesn't know how to handle.
+#[derive(Clone, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Unrecognized {
+    /// A field not known to be emitted by `wptrunner`, written as a pattern like
+    /// `results[].subtests[].name`.
+    Field(String),
+    TestStatus(String),
+    SubtestStatus(String),
+}
+
+impl Display for Unrecognized {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Field(field) => write!(f, "field `{field}`"),
+            Self::TestStatus(status) => write!(f, "test status {status:?}"),
+            Self::SubtestStatus(status) => write!(f, "subtest status {status:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawExecutionReport<'a> {
+    run_info: RawRunInfo,
+    #[serde(borrow)]
+    results: Vec<RawTestExecutionEntry<'a>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTestExecutionEntry<'a> {
+    #[serde(rename = "test", borrow)]
+    test_name: Cow<'a, str>,
+    #[serde(default, borrow)]
+    subsuite: Cow<'a, str>,
+    #[serde(borrow)]
+    status: Cow<'a, str>,
+    #[serde(default, borrow)]
+    message: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    subtests: Vec<RawSubtestExecutionResult<'a>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSubtestExecutionResult<'a> {
+    #[serde(rename = "name", borrow)]
+    subtest_name: Cow<'a, str>,
+    #[serde(borrow)]
+    status: Cow<'a, str>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RunInfo {
+    pub platform: Platform,
+    pub build_profile: BuildProfile,
+    /// The `wptrunner` subsuite that tests were run in, if any. Individual
+    /// [`TestExecutionEntry`]s may override this.
+    pub subsuite: Option<String>,
+    /// Other `run_info` keys with boolean or string values, i.e., `fission` or `headless`, which
+    /// metadata may condition expectations on.
+    pub properties: RunInfoVariant,
+}
+
+/// A `run_info` object, kept as-is until [`PlatformOverride`]s are applied to it, since they may
+/// refer to any of its keys.
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+struct RawRunInfo(Map<String, Value>);
+
+impl RawRunInfo {
+    /// Returns `Ok(Err(…))` if no platform could be determined for this run.
+    fn resolve(
+        self,
+        platform_overrides: &[PlatformOverride],
+    ) -> Result<Result<RunInfo, UnknownPlatform>, serde_json::Error> {
         #[derive(Debug, Deserialize)]
         struct ActualRunInfo {
             os: String,
+            #[serde(default)]
             processor: String,
+            #[serde(default)]
             win11_2009: bool,
             debug: bool,
+            #[serde(default)]
+            subsuite: String,
+        }
+
+        impl ActualRunInfo {
+            const KEYS: &'static [&'static str] =
+                &["os", "processor", "win11_2009", "debug", "subsuite"];
         }
 
+        let Self(run_info) = self;
+        let properties = RunInfoVariant(
+            run_info
+                .iter()
+                .filter(|(key, _value)| !ActualRunInfo::KEYS.contains(&&***key))
+                .filter_map(|(key, value)| {
+                    let value = match value {
+                        Value::Bool(value) => RunInfoValue::Bool(*value),
+                        Value::String(value) if key == WEBGPU_BACKEND_KEY => {
+                            let backend = value.parse::<WebGpuBackend>();
+                            RunInfoValue::String(backend.map_or_else(
+                                |_e| value.clone(),
+                                |backend| backend.name().to_owned(),
+                            ))
+                        }
+                        Value::String(value) => RunInfoValue::String(value.clone()),
+                        _ => return None,
+                    };
+                    Some((key.clone(), value))
+                })
+                .collect(),
+        );
+        let overridden = platform_overrides
+            .iter()
+            .find(|over| over.matches(&run_info))
+            .map(|over| over.platform);
+
         let ActualRunInfo {
             os,
             processor,
             win11_2009,
             debug,
-        } = ActualRunInfo::deserialize(deserializer)?;
-
-        let platform = match &*os {
-            "win" => {
-                if processor == "x86_64" && win11_2009 {
-                    Platform::Windows
-                } else {
-                    return Err(D::Error::custom("asdf"));
-                }
+            subsuite,
+        } = serde_json::from_value(Value::Object(run_info))?;
+
+        let platform = match (overridden, &*os) {
+            (Some(platform), _) => platform,
+            (None, "win") if processor == "x86_64" && win11_2009 => Platform::Windows,
+            (None, "mac") => Platform::MacOs,
+            (None, "linux") => Platform::Linux,
+            (None, "android") => Platform::Android,
+            (None, _) => {
+                return Ok(Err(UnknownPlatform {
+                    os,
+                    processor,
+                    win11_2009,
+                }))
             }
-            "mac" => Platform::MacOs,
-            "linux" => Platform::Linux,
-            other => return Err(D::Error::custom(format!("unrecognized platform {other:?}"))),
         };
 
         let build_profile = if debug {
@@ -57,39 +877,630 @@ impl<'de> Deserialize<'de> for RunInfo {
             BuildProfile::Optimized
         };
 
-        Ok(RunInfo {
+        Ok(Ok(RunInfo {
             platform,
             build_profile,
+            subsuite: Some(subsuite).filter(|subsuite| !subsuite.is_empty()),
+            properties,
+        }))
+    }
+}
+
+/// The `run_info` values used to determine the [`Platform`] of a run, when they don't correspond
+/// to any platform this tool knows about, and no [`PlatformOverride`] matched.
+#[derive(Clone, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct UnknownPlatform {
+    pub os: String,
+    pub processor: String,
+    pub win11_2009: bool,
+}
+
+impl Display for UnknownPlatform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            os,
+            processor,
+            win11_2009,
+        } = self;
+        write!(
+            f,
+            "os = {os:?}, processor = {processor:?}, win11_2009 = {win11_2009}"
+        )
+    }
+}
+
+/// The `run_info` key naming the backend that WebGPU ran on, i.e., `"vulkan"`. Its values are
+/// normalized to the names of [`WebGpuBackend`]s where recognized, so that conditions on it, like
+/// `webgpu_backend == "vulkan"`, don't depend on how a harness spells backends.
+pub const WEBGPU_BACKEND_KEY: &str = "webgpu_backend";
+
+/// A graphics API that `wgpu` may run WebGPU on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WebGpuBackend {
+    D3d12,
+    Vulkan,
+    Metal,
+    Gl,
+}
+
+impl WebGpuBackend {
+    /// The name of this backend as written in conditions on [`WEBGPU_BACKEND_KEY`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::D3d12 => "d3d12",
+            Self::Vulkan => "vulkan",
+            Self::Metal => "metal",
+            Self::Gl => "gl",
+        }
+    }
+}
+
+impl FromStr for WebGpuBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &*s.to_ascii_lowercase() {
+            "d3d12" | "dx12" => Ok(Self::D3d12),
+            "vulkan" | "vk" => Ok(Self::Vulkan),
+            "metal" | "mtl" => Ok(Self::Metal),
+            "gl" | "gles" | "opengl" => Ok(Self::Gl),
+            _ => Err(format!(
+                "unrecognized WebGPU backend {s:?}; expected one of `d3d12`, `vulkan`, `metal`, \
+                or `gl`"
+            )),
+        }
+    }
+}
+
+/// Attributes runs whose `run_info` has `key` set to `value` to `platform`, regardless of what
+/// the rest of `run_info` says. Parsed from `KEY=VALUE:PLATFORM`, i.e., `os=win11:win`, where
+/// `PLATFORM` is spelled as in metadata conditions.
+///
+/// Values that aren't strings in `run_info` are compared against `value` parsed as JSON, i.e.,
+/// `win11_2009=true:win`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PlatformOverride {
+    pub key: String,
+    pub value: String,
+    pub platform: Platform,
+}
+
+impl PlatformOverride {
+    fn matches(&self, run_info: &Map<String, Value>) -> bool {
+        let Self {
+            key,
+            value,
+            platform: _,
+        } = self;
+        run_info.get(key).map_or(false, |actual| match actual {
+            Value::String(actual) => actual == value,
+            actual => serde_json::from_str::<Value>(value).map_or(false, |value| *actual == value),
         })
     }
 }
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct TestExecutionEntry {
-    #[serde(rename = "test")]
-    pub test_name: String,
-    #[serde(flatten)]
-    pub result: TestExecutionResult,
+impl FromStr for PlatformOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (condition, platform) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("expected `KEY=VALUE:PLATFORM`, got {s:?}"))?;
+        let (key, value) = condition
+            .split_once('=')
+            .filter(|(key, _value)| !key.is_empty())
+            .ok_or_else(|| format!("expected `KEY=VALUE` before `:`, got {condition:?}"))?;
+        let platform = platform.parse::<Platform>()?;
+        Ok(Self {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            platform,
+        })
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-pub(crate) enum TestExecutionResult {
+impl Display for PlatformOverride {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            key,
+            value,
+            platform,
+        } = self;
+        write!(f, "{key}={value}:{}", platform.os_name())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TestExecutionEntry<'a> {
+    #[serde(borrow)]
+    pub test_name: Cow<'a, str>,
+    /// The `wptrunner` subsuite this test was run in, if reported for this test specifically.
+    /// Takes precedence over [`RunInfo::subsuite`].
+    #[serde(borrow)]
+    pub subsuite: Option<Cow<'a, str>>,
+    /// The message reported along with an `ERROR` outcome, if any. Messages of other outcomes
+    /// aren't kept.
+    #[serde(borrow)]
+    pub message: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub result: TestExecutionResult<'a>,
+}
+
+impl TestExecutionEntry<'_> {
+    pub fn into_owned(self) -> TestExecutionEntry<'static> {
+        let Self {
+            test_name,
+            subsuite,
+            message,
+            result,
+        } = self;
+        let subtests_into_owned = |subtests: Vec<SubtestExecutionResult<'_>>| {
+            subtests
+                .into_iter()
+                .map(
+                    |SubtestExecutionResult {
+                         subtest_name,
+                         outcome,
+                     }| SubtestExecutionResult {
+                        subtest_name: Cow::Owned(subtest_name.into_owned()),
+                        outcome,
+                    },
+                )
+                .collect()
+        };
+        TestExecutionEntry {
+            test_name: Cow::Owned(test_name.into_owned()),
+            subsuite: subsuite.map(|subsuite| Cow::Owned(subsuite.into_owned())),
+            message: message.map(|message| Cow::Owned(message.into_owned())),
+            result: match result {
+                TestExecutionResult::Complete { outcome, subtests } => {
+                    TestExecutionResult::Complete {
+                        outcome,
+                        subtests: subtests_into_owned(subtests),
+                    }
+                }
+                TestExecutionResult::JobMaybeTimedOut { subtests } => {
+                    TestExecutionResult::JobMaybeTimedOut {
+                        subtests: subtests_into_owned(subtests),
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum TestExecutionResult<'a> {
     Complete {
-        #[serde(rename = "status")]
         outcome: TestOutcome,
-        subtests: Vec<SubtestExecutionResult>,
+        #[serde(borrow)]
+        subtests: Vec<SubtestExecutionResult<'a>>,
     },
+    /// The test harness reported an empty `status`, which happens when the job running the test
+    /// timed out.
     JobMaybeTimedOut {
-        status: String,
-        subtests: Vec<SubtestExecutionResult>,
+        #[serde(borrow)]
+        subtests: Vec<SubtestExecutionResult<'a>>,
     },
 }
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct SubtestExecutionResult {
-    #[serde(rename = "name")]
-    pub subtest_name: String,
-    #[serde(rename = "status")]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SubtestExecutionResult<'a> {
+    #[serde(borrow)]
+    pub subtest_name: Cow<'a, str>,
     pub outcome: SubtestOutcome,
 }
+
+#[test]
+fn borrows_strings() {
+    let contents = r#"{
+        "run_info": {
+            "os": "linux",
+            "processor": "x86_64",
+            "win11_2009": false,
+            "debug": true
+        },
+        "results": [
+            {
+                "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                "status": "OK",
+                "subtests": [
+                    { "name": ":a", "status": "PASS" },
+                    { "name": ":\"escaped\"", "status": "FAIL" }
+                ]
+            }
+        ]
+    }"#;
+    let report =
+        ExecutionReport::from_json(Path::new("wptreport.json"), contents, &Default::default())
+            .unwrap();
+
+    let [entry] = &report.entries[..] else {
+        panic!("expected exactly one entry, got {:#?}", report.entries);
+    };
+    assert!(matches!(entry.test_name, Cow::Borrowed(_)));
+    let TestExecutionResult::Complete { subtests, .. } = &entry.result else {
+        panic!("expected a complete result, got {:#?}", entry.result);
+    };
+    assert!(matches!(subtests[0].subtest_name, Cow::Borrowed(":a")));
+    assert_eq!(subtests[1].subtest_name, ":\"escaped\"");
+}
+
+#[test]
+fn unrecognized_contents() {
+    let contents = r#"{
+        "run_info": {
+            "os": "linux",
+            "processor": "x86_64",
+            "win11_2009": false,
+            "debug": false,
+            "headless": true
+        },
+        "time_start": 0,
+        "shiny_new_field": {},
+        "results": [
+            {
+                "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                "status": "OK",
+                "duration": 1234,
+                "subtests": [
+                    { "name": ":a", "status": "PASS", "message": null },
+                    { "name": ":b", "status": "PRECONDITION_FAILED", "retries": 2 }
+                ]
+            },
+            {
+                "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,labels:*",
+                "status": "NEWFANGLED",
+                "subtests": []
+            }
+        ]
+    }"#;
+    let path = Path::new("wptreport.json");
+
+    let report = ExecutionReport::from_json(path, contents, &Default::default()).unwrap();
+    assert_eq!(
+        report
+            .unrecognized
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        [
+            "field `results[].subtests[].retries`",
+            "field `shiny_new_field`",
+            "test status \"NEWFANGLED\"",
+            "subtest status \"PRECONDITION_FAILED\"",
+        ]
+    );
+    let [entry] = &report.entries[..] else {
+        panic!("expected exactly one entry, got {:#?}", report.entries);
+    };
+    let TestExecutionResult::Complete { subtests, .. } = &entry.result else {
+        panic!("expected a complete result, got {:#?}", entry.result);
+    };
+    assert_eq!(subtests.len(), 1);
+
+    assert!(matches!(
+        ExecutionReport::from_json(
+            path,
+            contents,
+            &ParseOptions {
+                deny_unrecognized: true,
+                ..Default::default()
+            }
+        ),
+        Err(Error::UnrecognizedReportContents { .. })
+    ));
+}
+
+#[test]
+fn merged_runs() {
+    let run = |os, debug| {
+        format!(
+            r#"{{
+                "run_info": {{
+                    "os": "{os}",
+                    "processor": "x86_64",
+                    "win11_2009": true,
+                    "debug": {debug}
+                }},
+                "results": [
+                    {{
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+                        "status": "OK",
+                        "subtests": []
+                    }}
+                ]
+            }}"#
+        )
+    };
+    let configs = |runs: Vec<Result<ExecutionReport<'_>, UnknownPlatform>>| {
+        runs.into_iter()
+            .map(|run| {
+                let run = run.unwrap();
+                let RunInfo {
+                    platform,
+                    build_profile,
+                    subsuite: _,
+                    properties: _,
+                } = run.run_info;
+                (platform, build_profile, run.entries.len())
+            })
+            .collect::<Vec<_>>()
+    };
+    let path = Path::new("wptreport.json");
+    let expected = [
+        (Platform::Linux, BuildProfile::Debug, 1),
+        (Platform::Windows, BuildProfile::Optimized, 1),
+    ];
+
+    let single = run("linux", true);
+    assert_eq!(
+        configs(ExecutionReport::all_from_json(path, &single, &Default::default()).unwrap()),
+        expected[..1]
+    );
+
+    let concatenated = format!("{}\n{}\n", run("linux", true), run("win", false));
+    assert_eq!(
+        configs(ExecutionReport::all_from_json(path, &concatenated, &Default::default()).unwrap()),
+        expected
+    );
+
+    let array = format!("[{}, {}]", run("linux", true), run("win", false));
+    assert_eq!(
+        configs(ExecutionReport::all_from_json(path, &array, &Default::default()).unwrap()),
+        expected
+    );
+
+    let single_array = format!("[{}]", run("linux", true));
+    assert_eq!(
+        configs(ExecutionReport::all_from_json(path, &single_array, &Default::default()).unwrap()),
+        expected[..1]
+    );
+
+    assert!(ExecutionReport::all_from_json(path, "", &Default::default()).is_err());
+}
+
+#[test]
+fn newline_delimited_runs() {
+    let contents = concat!(
+        r#"{"run_info": {"os": "linux", "processor": "x86_64", "win11_2009": false, "debug": true}}"#,
+        "\n",
+        r#"{"test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:a:*", "status": "OK", "subtests": []}"#,
+        "\n",
+        r#"{"test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:b:*", "status": "OK", "subtests": [], "extra": 1}"#,
+        "\n",
+        r#"{"run_info": {"os": "mac", "processor": "x86_64", "win11_2009": false, "debug": false}}"#,
+        "\n",
+        r#"{"test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:a:*", "status": "OK", "subtests": []}"#,
+        "\n",
+    );
+    let path = Path::new("wptreport.jsonl");
+
+    let runs = ExecutionReport::all_from_json(path, contents, &Default::default())
+        .unwrap()
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let [linux, mac] = &runs[..] else {
+        panic!("expected exactly two runs, got {runs:#?}");
+    };
+    assert_eq!(linux.run_info.platform, Platform::Linux);
+    assert_eq!(linux.entries.len(), 2);
+    assert_eq!(
+        linux.unrecognized,
+        [Unrecognized::Field("results[].extra".to_owned())].into()
+    );
+    assert_eq!(mac.run_info.platform, Platform::MacOs);
+    assert_eq!(mac.entries.len(), 1);
+    assert!(mac.unrecognized.is_empty());
+
+    let headless = &contents[contents.find('\n').unwrap() + 1..];
+    assert!(ExecutionReport::all_from_json(path, headless, &Default::default()).is_err());
+}
+
+#[test]
+fn platform_overrides() {
+    let run = |run_info| format!(r#"{{"run_info": {run_info}, "results": []}}"#);
+    let path = Path::new("wptreport.json");
+    let platform = |contents: &str, platform_overrides: &[&str]| {
+        let options = ParseOptions {
+            deny_unrecognized: false,
+            platform_overrides: platform_overrides
+                .iter()
+                .map(|over| over.parse().unwrap())
+                .collect(),
+            summary_cache: None,
+            stream: false,
+            format: None,
+        };
+        ExecutionReport::from_json(path, contents, &options).map(|report| report.run_info.platform)
+    };
+
+    let win11 = run(r#"{"os": "win11", "processor": "aarch64", "debug": false}"#);
+    assert!(matches!(
+        platform(&win11, &[]),
+        Err(Error::UnknownPlatform { .. })
+    ));
+    assert_eq!(
+        platform(&win11, &["os=win10:linux", "os=win11:win"]).unwrap(),
+        Platform::Windows
+    );
+
+    let linux = run(r#"{"os": "linux", "processor": "x86_64", "debug": false, "headless": true}"#);
+    assert_eq!(platform(&linux, &[]).unwrap(), Platform::Linux);
+    assert_eq!(
+        platform(&linux, &["headless=true:mac"]).unwrap(),
+        Platform::MacOs
+    );
+
+    let over = "os=win11:win".parse::<PlatformOverride>().unwrap();
+    assert_eq!(over.to_string(), "os=win11:win");
+    assert!("os=win11".parse::<PlatformOverride>().is_err());
+    assert!("=win11:win".parse::<PlatformOverride>().is_err());
+    assert!("os=win11:windows".parse::<PlatformOverride>().is_err());
+}
+
+#[test]
+fn webgpu_backends() {
+    let run = |backend: &str| {
+        format!(
+            r#"{{
+                "run_info": {{
+                    "os": "win",
+                    "processor": "x86_64",
+                    "win11_2009": true,
+                    "debug": false,
+                    "webgpu_backend": "{backend}"
+                }},
+                "results": []
+            }}"#
+        )
+    };
+    let backend = |backend: &str| {
+        let contents = run(backend);
+        let report =
+            ExecutionReport::from_json(Path::new("wptreport.json"), &contents, &Default::default())
+                .unwrap();
+        report.run_info.properties.0[WEBGPU_BACKEND_KEY].clone()
+    };
+    assert_eq!(backend("DX12"), RunInfoValue::String("d3d12".to_owned()));
+    assert_eq!(backend("Vulkan"), RunInfoValue::String("vulkan".to_owned()));
+    assert_eq!(
+        backend("software"),
+        RunInfoValue::String("software".to_owned())
+    );
+}
+
+#[test]
+fn unknown_platforms() {
+    let contents = concat!(
+        r#"{"run_info": {"os": "linux", "processor": "x86_64", "debug": true}, "results": []}"#,
+        "\n",
+        r#"{"run_info": {"os": "win", "processor": "x86", "debug": false}, "results": []}"#,
+        "\n",
+        r#"{"run_info": {"os": "android", "processor": "aarch64", "debug": false}, "results": []}"#,
+        "\n",
+        r#"{"run_info": {"os": "ios", "processor": "aarch64", "debug": false}, "results": []}"#,
+        "\n",
+    );
+    let runs =
+        ExecutionReport::all_from_json(Path::new("wptreport.json"), contents, &Default::default())
+            .unwrap()
+            .into_iter()
+            .map(|run| {
+                run.map(|run| run.run_info.platform)
+                    .map_err(|unknown| unknown.to_string())
+            })
+            .collect::<Vec<_>>();
+    assert_eq!(
+        runs,
+        [
+            Ok(Platform::Linux),
+            Err(r#"os = "win", processor = "x86", win11_2009 = false"#.to_owned()),
+            Ok(Platform::Android),
+            Err(r#"os = "ios", processor = "aarch64", win11_2009 = false"#.to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn android_runs() {
+    let contents = r#"{
+        "run_info": { "os": "android", "processor": "aarch64", "debug": true },
+        "results": [{ "test": "/foo.html", "status": "CRASH", "subtests": [] }]
+    }"#;
+    let report =
+        ExecutionReport::from_json(Path::new("wptreport.json"), contents, &Default::default())
+            .unwrap();
+    assert_eq!(report.run_info.platform, Platform::Android);
+    assert_eq!(report.run_info.build_profile, BuildProfile::Debug);
+    assert_eq!(report.entries.len(), 1);
+}
+
+#[test]
+fn streamed_runs() {
+    let stream = |contents: &str| {
+        let mut parts = Vec::new();
+        let run = stream_from_json(
+            Path::new("wptreport.json"),
+            contents.as_bytes(),
+            &Default::default(),
+            &mut |part| {
+                parts.push(match part {
+                    RunPart::Start(run_info) => format!("start {:?}", run_info.platform),
+                    RunPart::Entry(entry) => format!("entry {}", entry.test_name),
+                    RunPart::End(unrecognized) => format!(
+                        "end [{}]",
+                        unrecognized
+                            .iter()
+                            .map(|unrecognized| unrecognized.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                })
+            },
+        )
+        .unwrap()
+        .map_err(|unknown| unknown.to_string());
+        (run, parts)
+    };
+
+    let run_info = r#""run_info": {"os": "linux", "processor": "x86_64", "debug": true}"#;
+    let results = r#""results": [
+        {"test": "/a", "status": "OK", "subtests": []},
+        {"test": "/b", "status": "NEW", "subtests": []},
+        {"test": "/c", "status": "CRASH", "subtests": [], "new": true}
+    ]"#;
+    let expected_parts = [
+        "start Linux",
+        "entry /a",
+        "entry /c",
+        r#"end [field `new`, field `results[].new`, test status "NEW"]"#,
+    ];
+    assert_eq!(
+        stream(&format!(r#"{{{run_info}, {results}, "new": 1}}"#)),
+        (Ok(()), expected_parts.map(String::from).to_vec())
+    );
+    // Entries can't be attributed to a run before its `run_info` is known, so they're held until
+    // then.
+    assert_eq!(
+        stream(&format!(r#"{{{results}, "new": 1, {run_info}}}"#)),
+        (Ok(()), expected_parts.map(String::from).to_vec())
+    );
+
+    let unknown_run_info = r#""run_info": {"os": "ios", "processor": "aarch64", "debug": false}"#;
+    assert_eq!(
+        stream(&format!(r#"{{{unknown_run_info}, {results}}}"#)),
+        (
+            Err(r#"os = "ios", processor = "aarch64", win11_2009 = false"#.to_owned()),
+            Vec::new()
+        )
+    );
+}
+
+#[test]
+fn summaries() {
+    let contents = concat!(
+        r#"{"run_info": {"os": "linux", "processor": "x86_64", "win11_2009": false, "debug": true}}"#,
+        "\n",
+        r#"{"test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:a:*", "status": "OK", "subtests": [{"name": "x", "status": "FAIL"}]}"#,
+        "\n",
+        r#"{"run_info": {"os": "ios", "processor": "aarch64", "debug": false}}"#,
+        "\n",
+    );
+    let path = Path::new("wptreport.jsonl");
+    let runs = ExecutionReport::all_from_json(path, contents, &Default::default()).unwrap();
+    assert!(matches!(&runs[..], [Ok(_), Err(UnknownPlatform { .. })]));
+
+    let summary = summarize_runs(&runs);
+    assert!(summary.starts_with(SUMMARY_PREFIX));
+    let resummarized = ExecutionReport::all_from_json(path, &summary, &Default::default()).unwrap();
+    assert_eq!(summarize_runs(&resummarized), summary);
+
+    let unsupported = summary.replacen(
+        &format!("{SUMMARY_PREFIX}{SUMMARY_VERSION}"),
+        &format!("{SUMMARY_PREFIX}0"),
+        1,
+    );
+    assert!(ExecutionReport::all_from_json(path, &unsupported, &Default::default()).is_err());
+}
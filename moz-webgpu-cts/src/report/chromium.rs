@@ -0,0 +1,229 @@
+//! Reading of results in Chromium's [JSON test results format] (i.e., `full_results.json`) as
+//! reports, for comparing against or importing Chromium's WebGPU runs.
+//!
+//! [JSON test results format]: https://chromium.googlesource.com/chromium/src/+/main/docs/testing/json_test_results_format.md
+
+use std::{borrow::Cow, collections::BTreeSet, path::Path};
+
+use serde::{de::Error as _, Deserialize};
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+
+use super::{
+    ExecutionReport, ParseOptions, RawExecutionReport, RawRunInfo, RawTestExecutionEntry,
+    UnknownPlatform,
+};
+
+/// The only version of the format in use.
+const VERSION: u64 = 3;
+
+/// The directory that Chromium runs the upstream WPT suite from, which test names are relative to.
+const WPT_DIR: &str = "external/wpt/";
+
+#[derive(Debug, Deserialize)]
+struct ChromiumResults {
+    version: u64,
+    #[serde(default = "default_path_delimiter")]
+    path_delimiter: String,
+    #[serde(default)]
+    metadata: Map<String, Value>,
+    tests: Map<String, Value>,
+}
+
+fn default_path_delimiter() -> String {
+    "/".to_owned()
+}
+
+/// Parses `contents` as Chromium JSON test results.
+///
+/// Chromium reports neither subtests nor a harness status separate from a test's result, so each
+/// test is mapped onto a test without subtests: `PASS` to `OK`, `FAIL` to `ERROR`, and other
+/// statuses to the outcome of the same name. Chromium retries tests that don't run as expected,
+/// listing each attempt's status in `actual`; the `N`th attempts of all tests are returned as the
+/// `N`th run, so that flaky tests are reconciled like those in repeated runs.
+///
+/// The `metadata` of the results is used as `run_info`, with `debug` defaulting to `false`. Since
+/// Chromium doesn't record `os` there, runs are usually returned as [`UnknownPlatform`]s unless a
+/// [`PlatformOverride`] attributes them to a platform by another key, i.e., `builder_name`.
+///
+/// Names of tests in the upstream WPT suite (i.e., `external/wpt/webgpu/cts.https.html?q=…`) are
+/// made relative to it, like `/webgpu/cts.https.html?q=…`.
+///
+/// See [`ExecutionReport::all_from_json`] for details on arguments and return values.
+///
+/// [`PlatformOverride`]: super::PlatformOverride
+pub(super) fn runs_from_chromium<'a>(
+    path: &Path,
+    contents: &str,
+    options: &ParseOptions,
+) -> Result<Vec<Result<ExecutionReport<'a>, UnknownPlatform>>, Error> {
+    let parse_err = |source| Error::ParseReport {
+        path: path.to_owned(),
+        source,
+    };
+
+    let ChromiumResults {
+        version,
+        path_delimiter,
+        mut metadata,
+        tests,
+    } = serde_json::from_str(contents).map_err(parse_err)?;
+    if version != VERSION {
+        return Err(parse_err(serde_json::Error::custom(format_args!(
+            "unsupported Chromium results version {version}, expected {VERSION}"
+        ))));
+    }
+    metadata
+        .entry("os")
+        .or_insert_with(|| Value::String(String::new()));
+    metadata.entry("debug").or_insert(Value::Bool(false));
+
+    let mut results = Vec::new();
+    collect_results(&path_delimiter, "", &tests, &mut results).map_err(parse_err)?;
+
+    let num_attempts = results
+        .iter()
+        .map(|(_test_name, statuses)| statuses.len())
+        .max()
+        .unwrap_or(1);
+    (0..num_attempts)
+        .map(|attempt| {
+            let results = results
+                .iter()
+                .filter_map(|(test_name, statuses)| {
+                    let status = match *statuses.get(attempt)? {
+                        "PASS" => "OK",
+                        "FAIL" => "ERROR",
+                        status => status,
+                    };
+                    Some(RawTestExecutionEntry {
+                        test_name: Cow::Owned(test_name.clone()),
+                        subsuite: Cow::Borrowed(""),
+                        status: Cow::Owned(status.to_owned()),
+                        message: None,
+                        subtests: Vec::new(),
+                    })
+                })
+                .collect();
+            let run = RawExecutionReport {
+                run_info: RawRunInfo(metadata.clone()),
+                results,
+            };
+            ExecutionReport::from_raw(path, run, BTreeSet::new(), options)
+        })
+        .collect()
+}
+
+/// Collects the name and the status of each attempt of every test under `node` of the trie of
+/// tests into `results`, where `prefix` is the name of `node`. A node is a test if it has an
+/// `actual` result.
+fn collect_results<'t>(
+    delimiter: &str,
+    prefix: &str,
+    node: &'t Map<String, Value>,
+    results: &mut Vec<(String, Vec<&'t str>)>,
+) -> Result<(), serde_json::Error> {
+    if let Some(actual) = node.get("actual") {
+        let actual = actual.as_str().ok_or_else(|| {
+            serde_json::Error::custom(format_args!("`actual` of {prefix:?} is not a string"))
+        })?;
+        let test_name = format!("/{}", prefix.strip_prefix(WPT_DIR).unwrap_or(prefix));
+        results.push((test_name, actual.split_whitespace().collect()));
+        return Ok(());
+    }
+    for (name, child) in node {
+        let Value::Object(child) = child else {
+            continue;
+        };
+        let name = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{prefix}{delimiter}{name}")
+        };
+        collect_results(delimiter, &name, child, results)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn reads_chromium_results() {
+    use crate::metadata::{Platform, TestOutcome};
+
+    use super::{PlatformOverride, ReportFormat, TestExecutionResult};
+
+    let contents = r#"{
+        "version": 3,
+        "interrupted": false,
+        "path_delimiter": "/",
+        "seconds_since_epoch": 1700000000,
+        "num_failures_by_type": {"PASS": 1, "FAIL": 1},
+        "metadata": {"builder_name": "win-rel", "chromium_revision": "abc"},
+        "tests": {
+            "external": {
+                "wpt": {
+                    "webgpu": {
+                        "cts.https.html?q=webgpu:a:*": {
+                            "expected": "PASS",
+                            "actual": "PASS",
+                            "artifacts": {}
+                        },
+                        "cts.https.html?q=webgpu:b:*": {
+                            "expected": "PASS",
+                            "actual": "FAIL TIMEOUT",
+                            "is_unexpected": true
+                        }
+                    }
+                }
+            }
+        }
+    }"#;
+    let path = Path::new("full_results.json");
+    let options = |platform_overrides: Vec<PlatformOverride>| ParseOptions {
+        platform_overrides,
+        format: Some(ReportFormat::Chromium),
+        ..Default::default()
+    };
+
+    let runs = ExecutionReport::all_from_json(path, contents, &options(Vec::new())).unwrap();
+    assert!(matches!(
+        &runs[..],
+        [Err(UnknownPlatform { .. }), Err(UnknownPlatform { .. })]
+    ));
+
+    let over = "builder_name=win-rel:win".parse().unwrap();
+    let runs = ExecutionReport::all_from_json(path, contents, &options(vec![over]))
+        .unwrap()
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let outcomes = runs
+        .iter()
+        .map(|run| {
+            assert_eq!(run.run_info.platform, Platform::Windows);
+            run.entries
+                .iter()
+                .map(|entry| {
+                    let TestExecutionResult::Complete { outcome, subtests } = &entry.result else {
+                        panic!("expected a complete result, got {:#?}", entry.result);
+                    };
+                    assert!(subtests.is_empty());
+                    (&*entry.test_name, *outcome)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        outcomes,
+        [
+            vec![
+                ("/webgpu/cts.https.html?q=webgpu:a:*", TestOutcome::Ok),
+                ("/webgpu/cts.https.html?q=webgpu:b:*", TestOutcome::Error),
+            ],
+            vec![("/webgpu/cts.https.html?q=webgpu:b:*", TestOutcome::Timeout)],
+        ]
+    );
+
+    let unsupported = contents.replacen("\"version\": 3", "\"version\": 4", 1);
+    assert!(ExecutionReport::all_from_json(path, &unsupported, &options(Vec::new())).is_err());
+}
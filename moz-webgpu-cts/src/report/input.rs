@@ -0,0 +1,267 @@
+//! Detection of report file formats by content, and extraction of reports from compressed files
+//! and archives.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, Cursor, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::error::Error;
+
+/// The format of a report file, as detected by [`ReportFormat::sniff`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ReportFormat {
+    /// JSON in any of the layouts accepted by [`ExecutionReport::all_from_json`].
+    ///
+    /// [`ExecutionReport::all_from_json`]: super::ExecutionReport::all_from_json
+    WptReport,
+    /// A `mozlog` action stream, i.e., `wptrunner`'s `raw.log`.
+    MozLog,
+    /// Chromium's [JSON test results format], i.e., `full_results.json`. Never detected by
+    /// content, since it is JSON like [`Self::WptReport`].
+    ///
+    /// [JSON test results format]: https://chromium.googlesource.com/chromium/src/+/main/docs/testing/json_test_results_format.md
+    Chromium,
+    Gzip,
+    Zstd,
+    Zip,
+}
+
+impl ReportFormat {
+    /// Detects the format of `contents` from its leading bytes, irrespective of the name of the
+    /// file it came from. Returns `None` if no supported format is detected.
+    pub fn sniff(contents: &[u8]) -> Option<Self> {
+        const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+        const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+        const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+        fn skip_whitespace(bytes: &[u8]) -> &[u8] {
+            let start = bytes
+                .iter()
+                .position(|b| !b.is_ascii_whitespace())
+                .unwrap_or(bytes.len());
+            &bytes[start..]
+        }
+
+        if contents.starts_with(GZIP_MAGIC) {
+            Some(Self::Gzip)
+        } else if contents.starts_with(ZSTD_MAGIC) {
+            Some(Self::Zstd)
+        } else if contents.starts_with(ZIP_MAGIC) {
+            Some(Self::Zip)
+        } else {
+            match skip_whitespace(contents) {
+                [b'[', ..] => Some(Self::WptReport),
+                // N.B.: `mozlog` always writes `action` as the first key of every line.
+                [b'{', rest @ ..] if skip_whitespace(rest).starts_with(b"\"action\"") => {
+                    Some(Self::MozLog)
+                }
+                [b'{', ..] => Some(Self::WptReport),
+                _ => None,
+            }
+        }
+    }
+
+    /// Whether this format contains other files, rather than report data itself.
+    pub fn is_container(self) -> bool {
+        match self {
+            Self::WptReport | Self::MozLog | Self::Chromium => false,
+            Self::Gzip | Self::Zstd | Self::Zip => true,
+        }
+    }
+}
+
+impl Display for ReportFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::WptReport => "wptreport JSON",
+            Self::MozLog => "mozlog",
+            Self::Chromium => "Chromium JSON test results",
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::Zip => "zip",
+        })
+    }
+}
+
+/// Parses the name of a format that reports may be forced to be parsed as, i.e., with
+/// `--report-format`: one of `wptreport`, `mozlog`, or `chromium`. Containers are always detected by content.
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wptreport" => Ok(Self::WptReport),
+            "mozlog" => Ok(Self::MozLog),
+            "chromium" => Ok(Self::Chromium),
+            _ => Err(format!(
+                "unrecognized report format {s:?}; expected one of `wptreport`, `mozlog`, or \
+                `chromium`"
+            )),
+        }
+    }
+}
+
+/// A file found by [`extract_reports`].
+#[derive(Debug)]
+pub struct ReportFile {
+    /// The path of the file on disk, followed by its path inside of the archive it was found in,
+    /// if any.
+    pub path: PathBuf,
+    /// Never a [container](ReportFormat::is_container) format. `None` if no supported format was
+    /// detected.
+    pub format: Option<ReportFormat>,
+    pub contents: Vec<u8>,
+}
+
+/// Recursively decompresses and unpacks the file at `path`, returning all files found inside of
+/// it, or the file itself if it is not a [container](ReportFormat::is_container).
+///
+/// Files inside of archives that are not in a supported format are omitted, since artifact
+/// bundles commonly contain other files, like screenshots.
+pub fn extract_reports(path: &Path, contents: Vec<u8>) -> Result<Vec<ReportFile>, Error> {
+    let mut found = Vec::new();
+    extract_reports_into(path.to_owned(), contents, false, &mut found)?;
+    Ok(found)
+}
+
+fn extract_reports_into(
+    path: PathBuf,
+    contents: Vec<u8>,
+    in_archive: bool,
+    found: &mut Vec<ReportFile>,
+) -> Result<(), Error> {
+    let read_err = |path: &Path| {
+        let path = path.to_owned();
+        move |source| Error::ReadFile { path, source }
+    };
+
+    let format = ReportFormat::sniff(&contents);
+    match format {
+        None if in_archive => {
+            tracing::debug!("skipping unrecognized file {}", path.display());
+        }
+        None | Some(ReportFormat::WptReport | ReportFormat::MozLog | ReportFormat::Chromium) => {
+            found.push(ReportFile {
+                path,
+                format,
+                contents,
+            })
+        }
+        Some(ReportFormat::Gzip) => {
+            let mut decompressed = Vec::new();
+            flate2::read::MultiGzDecoder::new(&contents[..])
+                .read_to_end(&mut decompressed)
+                .map_err(read_err(&path))?;
+            extract_reports_into(path, decompressed, in_archive, found)?;
+        }
+        Some(ReportFormat::Zstd) => {
+            let decompressed = zstd::decode_all(&contents[..]).map_err(read_err(&path))?;
+            extract_reports_into(path, decompressed, in_archive, found)?;
+        }
+        Some(ReportFormat::Zip) => {
+            let mut archive = zip::ZipArchive::new(Cursor::new(contents))
+                .map_err(io::Error::from)
+                .map_err(read_err(&path))?;
+            for idx in 0..archive.len() {
+                let mut file = archive
+                    .by_index(idx)
+                    .map_err(io::Error::from)
+                    .map_err(read_err(&path))?;
+                if file.is_dir() {
+                    continue;
+                }
+                let Some(file_path) = file.enclosed_name().map(|p| path.join(p)) else {
+                    tracing::warn!(
+                        "skipping file with unsafe path {:?} in {}",
+                        file.name(),
+                        path.display()
+                    );
+                    continue;
+                };
+                let mut contents = Vec::new();
+                file.read_to_end(&mut contents)
+                    .map_err(read_err(&file_path))?;
+                extract_reports_into(file_path, contents, true, found)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn sniff() {
+    let sniff = |contents: &[u8]| ReportFormat::sniff(contents);
+
+    assert_eq!(sniff(b"{\"run_info\": {}}"), Some(ReportFormat::WptReport));
+    assert_eq!(
+        sniff(b"\n  [{\"run_info\": {}}]"),
+        Some(ReportFormat::WptReport)
+    );
+    assert_eq!(
+        sniff(b"{\"action\": \"suite_start\", \"time\": 0}\n"),
+        Some(ReportFormat::MozLog)
+    );
+    assert_eq!(sniff(&[0x1f, 0x8b, 0x08]), Some(ReportFormat::Gzip));
+    assert_eq!(sniff(&[0x28, 0xb5, 0x2f, 0xfd]), Some(ReportFormat::Zstd));
+    assert_eq!(sniff(b"PK\x03\x04"), Some(ReportFormat::Zip));
+    assert_eq!(sniff(b"\x89PNG\r\n"), None);
+    assert_eq!(sniff(b""), None);
+
+    assert_eq!("mozlog".parse(), Ok(ReportFormat::MozLog));
+    assert_eq!("wptreport".parse(), Ok(ReportFormat::WptReport));
+    assert_eq!("chromium".parse(), Ok(ReportFormat::Chromium));
+    assert!("zip".parse::<ReportFormat>().is_err());
+}
+
+#[test]
+fn extract_nested() {
+    use std::io::Write;
+
+    let report = br#"{"run_info": {}, "results": []}"#;
+    let gzipped = {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(report).unwrap();
+        encoder.finish().unwrap()
+    };
+    let zipped = {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        writer.start_file("a/wptreport.json.gz", options).unwrap();
+        writer.write_all(&gzipped).unwrap();
+        writer.start_file("a/screenshot.png", options).unwrap();
+        writer.write_all(b"\x89PNG\r\n").unwrap();
+        writer.start_file("b/raw.log", options).unwrap();
+        writer
+            .write_all(b"{\"action\": \"suite_start\"}\n")
+            .unwrap();
+        writer.finish().unwrap().into_inner()
+    };
+    let compressed = zstd::encode_all(&zipped[..], 0).unwrap();
+
+    let found = extract_reports(Path::new("artifacts.zip.zst"), compressed)
+        .unwrap()
+        .into_iter()
+        .map(|file| (file.path, file.format, file.contents))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        [
+            (
+                Path::new("artifacts.zip.zst/a/wptreport.json.gz").to_owned(),
+                Some(ReportFormat::WptReport),
+                report.to_vec(),
+            ),
+            (
+                Path::new("artifacts.zip.zst/b/raw.log").to_owned(),
+                Some(ReportFormat::MozLog),
+                b"{\"action\": \"suite_start\"}\n".to_vec(),
+            ),
+        ]
+    );
+
+    let found = extract_reports(Path::new("notes.txt"), b"hello".to_vec()).unwrap();
+    assert!(matches!(&found[..], [ReportFile { format: None, .. }]));
+}
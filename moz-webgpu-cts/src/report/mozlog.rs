@@ -0,0 +1,228 @@
+//! Reconstruction of reports from `mozlog` action streams, i.e., `wptrunner`'s `raw.log`, for CI
+//! jobs that archive those instead of `wptreport.json` files.
+
+use std::{borrow::Cow, collections::BTreeSet, path::Path};
+
+use indexmap::IndexMap;
+use serde::{de::Error as _, Deserialize};
+
+use crate::error::Error;
+
+use super::{
+    ExecutionReport, ParseOptions, RawExecutionReport, RawRunInfo, RawSubtestExecutionResult,
+    RawTestExecutionEntry, UnknownPlatform,
+};
+
+/// A single line of a `mozlog` action stream. Only the actions and fields that `wptreport.json`
+/// files are built from are kept.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum Action<'a> {
+    SuiteStart {
+        run_info: RawRunInfo,
+    },
+    TestStart {
+        #[serde(borrow)]
+        test: Cow<'a, str>,
+        #[serde(default, borrow)]
+        subsuite: Cow<'a, str>,
+    },
+    TestStatus {
+        #[serde(borrow)]
+        test: Cow<'a, str>,
+        #[serde(borrow)]
+        subtest: Cow<'a, str>,
+        #[serde(borrow)]
+        status: Cow<'a, str>,
+    },
+    TestEnd {
+        #[serde(borrow)]
+        test: Cow<'a, str>,
+        #[serde(borrow)]
+        status: Cow<'a, str>,
+        #[serde(default, borrow)]
+        message: Option<Cow<'a, str>>,
+    },
+    SuiteEnd,
+    /// Any other action, i.e., `log` or `process_output`.
+    #[serde(other)]
+    Other,
+}
+
+/// The part of a test [`Action`] that applies to the test it names.
+enum TestEvent<'a> {
+    Start {
+        subsuite: Cow<'a, str>,
+    },
+    Status {
+        subtest: Cow<'a, str>,
+        status: Cow<'a, str>,
+    },
+    End {
+        status: Cow<'a, str>,
+        message: Option<Cow<'a, str>>,
+    },
+}
+
+/// Parses `contents` as a `mozlog` action stream, reconstructing one run per `suite_start` action
+/// from the `test_start`, `test_status`, and `test_end` actions that follow it, like `wptrunner`
+/// does when writing `wptreport.json` files. Tests that started but never ended are reported with
+/// an empty status, as when the job running them timed out.
+///
+/// See [`ExecutionReport::all_from_json`] for details on arguments and return values.
+pub(super) fn runs_from_mozlog<'a>(
+    path: &Path,
+    contents: &'a str,
+    options: &ParseOptions,
+) -> Result<Vec<Result<ExecutionReport<'a>, UnknownPlatform>>, Error> {
+    let parse_err = |source| Error::ParseReport {
+        path: path.to_owned(),
+        source,
+    };
+
+    let mut runs = Vec::new();
+    let mut current = None::<(
+        RawRunInfo,
+        IndexMap<Cow<'a, str>, RawTestExecutionEntry<'a>>,
+    )>;
+    let mut finish = |current: Option<(RawRunInfo, IndexMap<_, _>)>| -> Result<(), Error> {
+        if let Some((run_info, results)) = current {
+            let run = RawExecutionReport {
+                run_info,
+                results: results.into_values().collect(),
+            };
+            runs.push(ExecutionReport::from_raw(
+                path,
+                run,
+                BTreeSet::new(),
+                options,
+            )?);
+        }
+        Ok(())
+    };
+
+    for action in serde_json::Deserializer::from_str(contents).into_iter::<Action<'a>>() {
+        let (test, event) = match action.map_err(parse_err)? {
+            Action::SuiteStart { run_info } => {
+                finish(current.take())?;
+                current = Some((run_info, IndexMap::new()));
+                continue;
+            }
+            Action::SuiteEnd => {
+                finish(current.take())?;
+                continue;
+            }
+            Action::Other => continue,
+            Action::TestStart { test, subsuite } => (test, TestEvent::Start { subsuite }),
+            Action::TestStatus {
+                test,
+                subtest,
+                status,
+            } => (test, TestEvent::Status { subtest, status }),
+            Action::TestEnd {
+                test,
+                status,
+                message,
+            } => (test, TestEvent::End { status, message }),
+        };
+        let Some((_run_info, results)) = &mut current else {
+            return Err(parse_err(serde_json::Error::custom(
+                "found a test action before any `suite_start`",
+            )));
+        };
+        let entry = results
+            .entry(test.clone())
+            .or_insert_with(|| RawTestExecutionEntry {
+                test_name: test,
+                subsuite: Cow::Borrowed(""),
+                status: Cow::Borrowed(""),
+                message: None,
+                subtests: Vec::new(),
+            });
+        match event {
+            TestEvent::Start { subsuite } => entry.subsuite = subsuite,
+            TestEvent::Status { subtest, status } => {
+                entry.subtests.push(RawSubtestExecutionResult {
+                    subtest_name: subtest,
+                    status,
+                })
+            }
+            TestEvent::End { status, message } => {
+                entry.status = status;
+                entry.message = message;
+            }
+        }
+    }
+    finish(current)?;
+
+    Ok(runs)
+}
+
+#[test]
+fn reconstructs_runs() {
+    use crate::{
+        metadata::{Platform, SubtestOutcome, TestOutcome},
+        report::TestExecutionResult,
+    };
+
+    let contents = concat!(
+        r#"{"action": "suite_start", "time": 0, "thread": "MainThread", "pid": 1, "source": "web-platform-tests", "tests": {}, "run_info": {"os": "linux", "processor": "x86_64", "win11_2009": false, "debug": true}}"#,
+        "\n",
+        r#"{"action": "test_start", "time": 1, "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:a:*"}"#,
+        "\n",
+        r#"{"action": "log", "time": 2, "level": "INFO", "message": "hello"}"#,
+        "\n",
+        r#"{"action": "test_status", "time": 3, "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:a:*", "subtest": "x", "status": "FAIL", "expected": "PASS", "message": "oops"}"#,
+        "\n",
+        r#"{"action": "test_end", "time": 4, "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:a:*", "status": "ERROR", "message": "uncaught exception"}"#,
+        "\n",
+        r#"{"action": "test_start", "time": 5, "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:b:*"}"#,
+        "\n",
+        r#"{"action": "suite_end", "time": 6}"#,
+        "\n",
+        r#"{"action": "suite_start", "time": 7, "run_info": {"os": "mac", "processor": "x86_64", "win11_2009": false, "debug": false}}"#,
+        "\n",
+        r#"{"action": "test_start", "time": 8, "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:a:*", "subsuite": "gpu"}"#,
+        "\n",
+        r#"{"action": "test_end", "time": 9, "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:a:*", "status": "OK"}"#,
+        "\n",
+    );
+    let path = Path::new("raw.log");
+
+    let runs = ExecutionReport::all_from_json(path, contents, &Default::default())
+        .unwrap()
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let [linux, mac] = &runs[..] else {
+        panic!("expected exactly two runs, got {runs:#?}");
+    };
+
+    assert_eq!(linux.run_info.platform, Platform::Linux);
+    let [errored, timed_out] = &linux.entries[..] else {
+        panic!("expected exactly two entries, got {:#?}", linux.entries);
+    };
+    assert_eq!(errored.message.as_deref(), Some("uncaught exception"));
+    let TestExecutionResult::Complete {
+        outcome: TestOutcome::Error,
+        subtests,
+    } = &errored.result
+    else {
+        panic!("expected an `ERROR` result, got {:#?}", errored.result);
+    };
+    assert_eq!(subtests[0].subtest_name, "x");
+    assert_eq!(subtests[0].outcome, SubtestOutcome::Fail);
+    assert!(matches!(
+        timed_out.result,
+        TestExecutionResult::JobMaybeTimedOut { .. }
+    ));
+
+    assert_eq!(mac.run_info.platform, Platform::MacOs);
+    let [entry] = &mac.entries[..] else {
+        panic!("expected exactly one entry, got {:#?}", mac.entries);
+    };
+    assert_eq!(entry.subsuite.as_deref(), Some("gpu"));
+
+    let headless = &contents[contents.find('\n').unwrap() + 1..];
+    assert!(ExecutionReport::all_from_json(path, headless, &Default::default()).is_err());
+}
@@ -0,0 +1,229 @@
+//! Derivation of CTS queries that select just the failing subtests of a test, so that developers
+//! can run only the failing slice of a large test locally.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{
+    error::Error,
+    metadata::{Platform, SubtestOutcome},
+    report::{ExecutionReport, RunInfo, TestExecutionEntry, TestExecutionResult},
+    shared::{split_cts_params, Browser, TestPath},
+};
+
+/// Splits the name of a CTS subtest into its parameters, i.e., `:format="r8unorm";dims=2` into
+/// `[("format", "\"r8unorm\""), ("dims", "2")]`. Returns `None` if `subtest_name` isn't a list
+/// of parameters.
+pub fn subtest_params(subtest_name: &str) -> Option<Vec<(&str, &str)>> {
+    let params = subtest_name.strip_prefix(':')?;
+    split_cts_params(params)
+        .into_iter()
+        .map(|param| param.split_once('='))
+        .collect()
+}
+
+/// Returns the narrowest query under `test_query` (i.e., `webgpu:api,operation,foo:bar:*`) that
+/// selects exactly the subtests in `failing` out of all those in `subtests`: one with every
+/// parameter that all failing subtests share. Returns `None` if no such query exists, or if it
+/// would select all of `subtests`.
+pub fn repro_query(
+    test_query: &str,
+    subtests: &BTreeSet<String>,
+    failing: &BTreeSet<String>,
+) -> Option<String> {
+    let prefix = test_query.strip_suffix('*')?;
+    if failing.is_empty() || failing.len() == subtests.len() {
+        return None;
+    }
+
+    let mut failing_params = failing.iter().map(|name| subtest_params(name));
+    let mut shared = failing_params.next()??;
+    for params in failing_params {
+        let params = params?;
+        shared.retain(|param| params.contains(param));
+    }
+    if shared.is_empty() {
+        return None;
+    }
+
+    let selects_exactly_failing = subtests.iter().all(|name| {
+        let selected = subtest_params(name).map_or(false, |params| {
+            shared.iter().all(|param| params.contains(param))
+        });
+        selected == failing.contains(name)
+    });
+    selects_exactly_failing.then(|| {
+        let params = shared
+            .iter()
+            .map(|(key, val)| format!("{key}={val};"))
+            .collect::<String>();
+        format!("{prefix}{params}*")
+    })
+}
+
+/// A test with failing subtests, as found by [`failing_tests`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FailingTest {
+    /// The CTS query of the test, i.e., `webgpu:api,operation,foo:bar:*`.
+    pub query: String,
+    pub num_subtests: usize,
+    pub num_failing: usize,
+    /// See [`repro_query`].
+    pub repro_query: Option<String>,
+}
+
+/// Finds CTS tests in `exec_reports` with at least `min_failing` subtests that had an outcome
+/// other than `PASS` in some run (on `platform`, if given), resolving tests as run in `browser`.
+pub fn failing_tests<'a>(
+    browser: Browser,
+    exec_reports: impl IntoIterator<Item = Result<ExecutionReport<'a>, Error>>,
+    platform: Option<Platform>,
+    min_failing: usize,
+) -> Result<Vec<FailingTest>, Error> {
+    let mut subtests_by_query = BTreeMap::<String, (BTreeSet<String>, BTreeSet<String>)>::new();
+    for exec_report in exec_reports {
+        let ExecutionReport {
+            run_info:
+                RunInfo {
+                    platform: run_platform,
+                    build_profile: _,
+                    subsuite: _,
+                    properties: _,
+                },
+            entries,
+            unrecognized: _,
+        } = exec_report?;
+        if platform.map_or(false, |platform| platform != run_platform) {
+            continue;
+        }
+        for entry in entries {
+            let TestExecutionEntry {
+                test_name,
+                subsuite: _,
+                message: _,
+                result,
+            } = entry;
+            let Some(query) = TestPath::from_execution_report(browser, &test_name)
+                .unwrap()
+                .cts_path()
+            else {
+                continue;
+            };
+            let subtests = match result {
+                TestExecutionResult::Complete {
+                    outcome: _,
+                    subtests,
+                } => subtests,
+                TestExecutionResult::JobMaybeTimedOut { subtests } => subtests,
+            };
+            let (all, failing) = subtests_by_query.entry(query).or_default();
+            for subtest in subtests {
+                let name = subtest.subtest_name.into_owned();
+                if subtest.outcome != SubtestOutcome::Pass {
+                    failing.insert(name.clone());
+                }
+                all.insert(name);
+            }
+        }
+    }
+
+    Ok(subtests_by_query
+        .into_iter()
+        .filter(|(_query, (_all, failing))| !failing.is_empty() && failing.len() >= min_failing)
+        .map(|(query, (all, failing))| FailingTest {
+            repro_query: repro_query(&query, &all, &failing),
+            num_subtests: all.len(),
+            num_failing: failing.len(),
+            query,
+        })
+        .collect())
+}
+
+impl Display for FailingTest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            query,
+            num_subtests,
+            num_failing,
+            repro_query,
+        } = self;
+        writeln!(
+            f,
+            "{query} ({num_failing} of {num_subtests} subtest(s) failing)"
+        )?;
+        match repro_query {
+            Some(repro_query) => writeln!(f, "  {repro_query}"),
+            None if num_failing == num_subtests => writeln!(f, "  (all subtests fail)"),
+            None => writeln!(f, "  (no query selects exactly the failing subtests)"),
+        }
+    }
+}
+
+#[test]
+fn parse_subtest_params() {
+    assert_eq!(
+        subtest_params(r#":format="r8unorm";dims=2"#),
+        Some(vec![("format", r#""r8unorm""#), ("dims", "2")])
+    );
+    assert_eq!(
+        subtest_params(r#":label="a;b=\"c\"";n=1"#),
+        Some(vec![("label", r#""a;b=\"c\"""#), ("n", "1")])
+    );
+    assert_eq!(subtest_params(":"), Some(vec![]));
+    assert_eq!(subtest_params(":nope"), None);
+    assert_eq!(subtest_params("nope=1"), None);
+}
+
+#[test]
+fn derive_repro_queries() {
+    let set = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+    let subtests = set(&[
+        r#":format="r8unorm";dims=1"#,
+        r#":format="r8unorm";dims=2"#,
+        r#":format="rgba8unorm";dims=1"#,
+        r#":format="rgba8unorm";dims=2"#,
+        r#":format="bgra8unorm";dims=1"#,
+    ]);
+    let query = "webgpu:api,operation,texture:copy:*";
+
+    assert_eq!(
+        repro_query(
+            query,
+            &subtests,
+            &set(&[r#":format="r8unorm";dims=1"#, r#":format="r8unorm";dims=2"#])
+        )
+        .as_deref(),
+        Some(r#"webgpu:api,operation,texture:copy:format="r8unorm";*"#)
+    );
+    assert_eq!(
+        repro_query(query, &subtests, &set(&[r#":format="rgba8unorm";dims=2"#])).as_deref(),
+        Some(r#"webgpu:api,operation,texture:copy:format="rgba8unorm";dims=2;*"#)
+    );
+    // Nothing is shared by all failing subtests.
+    assert_eq!(
+        repro_query(
+            query,
+            &subtests,
+            &set(&[
+                r#":format="r8unorm";dims=1"#,
+                r#":format="rgba8unorm";dims=2"#
+            ])
+        ),
+        None
+    );
+    // `dims=1` is shared, but also selects a passing subtest.
+    assert_eq!(
+        repro_query(
+            query,
+            &subtests,
+            &set(&[
+                r#":format="r8unorm";dims=1"#,
+                r#":format="rgba8unorm";dims=1"#
+            ])
+        ),
+        None
+    );
+    assert_eq!(repro_query(query, &subtests, &subtests), None);
+}
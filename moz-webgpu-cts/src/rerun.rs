@@ -0,0 +1,346 @@
+//! Manifests of tests with intermittent expectations, for running them again many times (i.e.,
+//! with `wptrunner --rerun`) to confirm or retire those expectations.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use enumset::EnumSetType;
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::{
+    error::Error,
+    metadata::{BuildProfile, File, Platform, Subtest, Test, TestOutcome, TestProps},
+    report::{ExecutionReport, RunInfo, TestExecutionEntry, TestExecutionResult},
+    shared::{Browser, TestPath},
+};
+
+/// The probability with which [`RerunManifest::new`] suggests enough runs to observe an outcome
+/// at least once, given the rate at which it was observed in reports.
+pub const CONFIDENCE: f64 = 0.95;
+
+/// How often tests' outcomes varied between runs of the same configuration in reports, keyed by
+/// test as in [`RerunTest::test`].
+#[derive(Clone, Debug, Default)]
+pub struct FlakeRates(BTreeMap<String, f64>);
+
+impl FlakeRates {
+    /// Tallies outcomes of tests and subtests in `exec_reports`, by configuration. A test's flake
+    /// rate is the largest fraction of runs of any configuration in which it or any of its
+    /// subtests had an outcome other than its most common one. Tests are resolved as run in
+    /// `browser`.
+    pub fn from_reports<'a>(
+        browser: Browser,
+        exec_reports: impl IntoIterator<Item = Result<ExecutionReport<'a>, Error>>,
+    ) -> Result<Self, Error> {
+        type Tally = BTreeMap<String, usize>;
+
+        let mut tallies =
+            BTreeMap::<String, BTreeMap<(Platform, BuildProfile, Option<String>), Tally>>::new();
+        for exec_report in exec_reports {
+            let ExecutionReport {
+                run_info:
+                    RunInfo {
+                        platform,
+                        build_profile,
+                        subsuite: _,
+                        properties: _,
+                    },
+                entries,
+                unrecognized: _,
+            } = exec_report?;
+            for entry in entries {
+                let TestExecutionEntry {
+                    test_name,
+                    subsuite: _,
+                    message: _,
+                    result,
+                } = entry;
+                let test_path = TestPath::from_execution_report(browser, &test_name).unwrap();
+                let (outcome, subtests) = match result {
+                    TestExecutionResult::Complete { outcome, subtests } => (outcome, subtests),
+                    TestExecutionResult::JobMaybeTimedOut { subtests } => {
+                        (TestOutcome::Timeout, subtests)
+                    }
+                };
+                let test_tallies = tallies
+                    .entry(format!("/{}", test_path.runner_url_path()))
+                    .or_default();
+                let mut tally = |subtest: Option<String>, outcome: String| {
+                    *test_tallies
+                        .entry((platform, build_profile, subtest))
+                        .or_default()
+                        .entry(outcome)
+                        .or_default() += 1;
+                };
+                tally(None, outcome.to_string());
+                for subtest in subtests {
+                    tally(
+                        Some(subtest.subtest_name.into_owned()),
+                        subtest.outcome.to_string(),
+                    );
+                }
+            }
+        }
+
+        let rates = tallies
+            .into_iter()
+            .map(|(test, tallies)| {
+                let rate = tallies
+                    .values()
+                    .map(|tally| {
+                        let runs = tally.values().sum::<usize>();
+                        let most_common = tally.values().copied().max().unwrap_or_default();
+                        (runs - most_common) as f64 / runs as f64
+                    })
+                    .fold(0.0, f64::max);
+                (test, rate)
+            })
+            .collect();
+        Ok(Self(rates))
+    }
+
+    /// Returns the flake rate of `test`, or `None` if it wasn't run.
+    pub fn get(&self, test: &str) -> Option<f64> {
+        self.0.get(test).copied()
+    }
+}
+
+/// A list of tests to run again, serialized as JSON. See [`RerunManifest::new`].
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RerunManifest {
+    pub tests: Vec<RerunTest>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RerunTest {
+    /// The test, as run by `wptrunner`, i.e.,
+    /// `/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter,info:*`.
+    pub test: String,
+    /// Configurations in which the test or any of its subtests is expected to have more than one
+    /// outcome.
+    pub configurations: Vec<Configuration>,
+    /// See [`FlakeRates`]. `None` if the test wasn't found in reports.
+    pub flake_rate: Option<f64>,
+    /// The suggested number of times to run the test.
+    pub repeat: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Configuration {
+    pub platform: Platform,
+    pub build_profile: BuildProfile,
+}
+
+impl RerunManifest {
+    /// Lists tests in `files`, read from `gecko_checkout`, that are expected to have more than one
+    /// outcome in some configuration. Disabled tests and subtests are skipped, since they are not
+    /// run.
+    ///
+    /// Each test is suggested enough runs to observe an outcome with its [flake
+    /// rate](FlakeRates) at least once with [`CONFIDENCE`], up to `max_repeat`. Tests that did not
+    /// vary in reports, or weren't found in them, are suggested `max_repeat` runs, which is what
+    /// it takes to be confident that an intermittent outcome no longer happens.
+    pub fn new(
+        files: &IndexMap<Arc<PathBuf>, File>,
+        gecko_checkout: &Path,
+        flake_rates: &FlakeRates,
+        max_repeat: usize,
+    ) -> Self {
+        fn record<Out>(configurations: &mut BTreeSet<Configuration>, properties: &TestProps<Out>)
+        where
+            Out: EnumSetType,
+        {
+            if properties.is_disabled.is_some() {
+                return;
+            }
+            let Some(expected) = &properties.expected else {
+                return;
+            };
+            for ((platform, build_profile), expected) in expected.iter() {
+                if expected.as_permanent().is_none() {
+                    configurations.insert(Configuration {
+                        platform,
+                        build_profile,
+                    });
+                }
+            }
+        }
+
+        let mut tests = Vec::new();
+        for (path, file) in files {
+            let rel_path = path.strip_prefix(gecko_checkout).unwrap_or(path);
+            for (name, test) in &file.tests {
+                let Test {
+                    properties,
+                    subtests,
+                } = test;
+                if properties.is_disabled.is_some() {
+                    continue;
+                }
+                let Ok(test_path) = TestPath::from_metadata_test(rel_path, &name.0) else {
+                    continue;
+                };
+
+                let mut configurations = BTreeSet::new();
+                record(&mut configurations, properties);
+                for Subtest { properties } in subtests.values() {
+                    record(&mut configurations, properties);
+                }
+                if configurations.is_empty() {
+                    continue;
+                }
+
+                let test = format!("/{}", test_path.runner_url_path());
+                let flake_rate = flake_rates.get(&test);
+                tests.push(RerunTest {
+                    repeat: repeat_for(flake_rate, max_repeat),
+                    test,
+                    configurations: configurations.into_iter().collect(),
+                    flake_rate,
+                });
+            }
+        }
+        Self { tests }
+    }
+}
+
+/// Returns the number of runs needed to observe an outcome happening at `flake_rate` at least
+/// once, with [`CONFIDENCE`], clamped to at least 2 and at most `max_repeat`.
+fn repeat_for(flake_rate: Option<f64>, max_repeat: usize) -> usize {
+    match flake_rate {
+        Some(rate) if rate > 0.0 => {
+            let runs = (1.0 - CONFIDENCE).ln() / (1.0 - rate).ln();
+            (runs.ceil() as usize).clamp(2.min(max_repeat), max_repeat)
+        }
+        _ => max_repeat,
+    }
+}
+
+#[test]
+fn rerun_manifest() {
+    use crate::{
+        checkout::parse_metadata, metadata::SubtestOutcome, report::SubtestExecutionResult,
+    };
+
+    let gecko_checkout = Path::new("gecko");
+    let (meta_path, file) = parse_metadata(
+        gecko_checkout
+            .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu/cts.https.html.ini"),
+        concat!(
+            "[cts.https.html?q=webgpu:api,flaky:*]\n",
+            "  [:a]\n",
+            "    expected:\n",
+            "      if os == \"win\" and debug: [PASS, FAIL]\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,unobserved:*]\n",
+            "  expected: [OK, TIMEOUT]\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,permanent:*]\n",
+            "  [:a]\n",
+            "    expected: FAIL\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,disabled:*]\n",
+            "  disabled: true\n",
+            "  expected: [OK, TIMEOUT]\n",
+        )
+        .to_owned(),
+    )
+    .unwrap();
+    let files = [(meta_path, file)].into_iter().collect::<IndexMap<_, _>>();
+
+    let run = |outcome| {
+        Ok(ExecutionReport {
+            run_info: RunInfo {
+                platform: Platform::Windows,
+                build_profile: BuildProfile::Debug,
+                subsuite: None,
+                properties: Default::default(),
+            },
+            entries: vec![TestExecutionEntry {
+                test_name: "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,flaky:*".into(),
+                subsuite: None,
+                message: None,
+                result: TestExecutionResult::Complete {
+                    outcome: TestOutcome::Ok,
+                    subtests: vec![SubtestExecutionResult {
+                        subtest_name: ":a".into(),
+                        outcome,
+                    }],
+                },
+            }],
+            unrecognized: Default::default(),
+        })
+    };
+    let flake_rates = FlakeRates::from_reports(
+        Browser::Firefox,
+        [
+            run(SubtestOutcome::Pass),
+            run(SubtestOutcome::Pass),
+            run(SubtestOutcome::Pass),
+            run(SubtestOutcome::Fail),
+        ],
+    )
+    .unwrap();
+
+    let manifest = RerunManifest::new(&files, gecko_checkout, &flake_rates, 50);
+    insta::assert_snapshot!(serde_json::to_string_pretty(&manifest).unwrap(), @r###"
+    {
+      "tests": [
+        {
+          "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,flaky:*",
+          "configurations": [
+            {
+              "platform": "win",
+              "build_profile": "debug"
+            }
+          ],
+          "flake_rate": 0.25,
+          "repeat": 11
+        },
+        {
+          "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,unobserved:*",
+          "configurations": [
+            {
+              "platform": "win",
+              "build_profile": "debug"
+            },
+            {
+              "platform": "win",
+              "build_profile": "optimized"
+            },
+            {
+              "platform": "linux",
+              "build_profile": "debug"
+            },
+            {
+              "platform": "linux",
+              "build_profile": "optimized"
+            },
+            {
+              "platform": "mac",
+              "build_profile": "debug"
+            },
+            {
+              "platform": "mac",
+              "build_profile": "optimized"
+            },
+            {
+              "platform": "android",
+              "build_profile": "debug"
+            },
+            {
+              "platform": "android",
+              "build_profile": "optimized"
+            }
+          ],
+          "flake_rate": null,
+          "repeat": 50
+        }
+      ]
+    }
+    "###);
+}
@@ -0,0 +1,284 @@
+//! Recovery from failures partway through writing metadata (i.e., a full disk, or missing
+//! permissions), so that `update-expected --resume` can finish writing without processing
+//! reports again.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{self, AtomicBool},
+};
+
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use tracing::Span;
+
+use crate::{checkout::state_dir, error::Error, filesystem::FileSystem, progress};
+
+/// Writes to metadata files, split into those completed and those still pending.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct WriteManifest {
+    pub completed: Vec<PathBuf>,
+    pub pending: Vec<PendingWrite>,
+    /// Tests moved between metadata files by these writes, to summarize once all are complete.
+    #[serde(default)]
+    pub relocations: Vec<RelocatedTest>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PendingWrite {
+    pub path: PathBuf,
+    /// The new contents of the file, or `None` if it is to be removed.
+    pub contents: Option<String>,
+}
+
+/// A test whose section is moved from one metadata file to another.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RelocatedTest {
+    /// The URL path of the test, where it was reported.
+    pub test: String,
+    /// The metadata file the test's section is removed from, relative to the Gecko checkout.
+    pub from: PathBuf,
+    /// The metadata file the test's section is moved to, relative to the Gecko checkout.
+    pub to: PathBuf,
+}
+
+impl WriteManifest {
+    /// Returns the path where the manifest of an interrupted write is stored, under the same
+    /// state directory as sessions.
+    pub fn path() -> Result<PathBuf, Error> {
+        Ok(state_dir()?.join("pending-writes.json"))
+    }
+
+    /// Reads the manifest at `path`, or returns `None` if none exists.
+    pub fn load(fs: &dyn FileSystem, path: &Path) -> Result<Option<Self>, Error> {
+        let contents = match fs.read_to_string(path) {
+            Ok(contents) => contents,
+            Err(Error::ReadFile { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        };
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|source| Error::ParseWriteManifest {
+                path: path.to_owned(),
+                source,
+            })
+    }
+
+    pub fn save(&self, fs: &dyn FileSystem, path: &Path) -> Result<(), Error> {
+        fs.write(path, &serde_json::to_string_pretty(self).unwrap())
+    }
+
+    /// Renders pending writes as a unified diff against the current contents of each file, with
+    /// paths relative to `gecko_checkout`. Writes that wouldn't change a file are omitted, so the
+    /// diff is empty if nothing would change.
+    pub fn diff(&self, fs: &dyn FileSystem, gecko_checkout: &Path) -> Result<String, Error> {
+        let mut diff = String::new();
+        for PendingWrite { path, contents } in &self.pending {
+            let current = match fs.read_to_string(path) {
+                Ok(contents) => Some(contents),
+                Err(Error::ReadFile { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                    None
+                }
+                Err(e) => return Err(e),
+            };
+            if current == *contents {
+                continue;
+            }
+            let rel_path = path.strip_prefix(gecko_checkout).unwrap_or(path).display();
+            let header = |prefix, contents: &Option<String>| match contents {
+                Some(_) => format!("{prefix}/{rel_path}"),
+                None => "/dev/null".to_owned(),
+            };
+            let unified_diff = TextDiff::from_lines(
+                current.as_deref().unwrap_or_default(),
+                contents.as_deref().unwrap_or_default(),
+            )
+            .unified_diff()
+            .header(&header("a", &current), &header("b", contents))
+            .to_string();
+            diff.push_str(&unified_diff);
+        }
+        Ok(diff)
+    }
+
+    /// Performs pending writes in order, moving each to [`Self::completed`] once it succeeds.
+    /// All pending writes are attempted, even if some fail; those that fail are left pending.
+    ///
+    /// If `cancelled` is set while writing, no further files are touched after the one currently
+    /// being written, and [`Error::WriteCancelled`] is returned.
+    #[tracing::instrument(skip_all, fields(num_written, num_removed))]
+    pub fn write(&mut self, fs: &dyn FileSystem, cancelled: &AtomicBool) -> Result<(), Error> {
+        tracing::info!("writing new metadata to file system…");
+
+        let num_total = self.completed.len() + self.pending.len();
+        let mut num_written = 0;
+        let mut num_removed = 0;
+        let mut errors = Vec::new();
+        let mut failed = Vec::new();
+        let progress = progress::bar("writing metadata", Some(self.pending.len()));
+        let mut pending = std::mem::take(&mut self.pending).into_iter();
+        while let Some(write) = pending.next() {
+            progress.inc(1);
+            if cancelled.load(atomic::Ordering::Relaxed) {
+                self.pending = failed.into_iter().chain([write]).chain(pending).collect();
+                return Err(Error::WriteCancelled {
+                    num_done: self.completed.len(),
+                    num_total,
+                    errors,
+                });
+            }
+            let PendingWrite { path, contents } = &write;
+            let res = match contents {
+                None => {
+                    num_removed += 1;
+                    fs.remove_file(path)
+                }
+                Some(contents) => {
+                    tracing::debug!("writing new metadata to {}", path.display());
+                    num_written += 1;
+                    fs.write(path, contents)
+                }
+            };
+            match res {
+                Ok(()) => self.completed.push(write.path),
+                Err(e) => {
+                    errors.push(e);
+                    failed.push(write);
+                }
+            }
+        }
+        self.pending = failed;
+        progress.finish();
+
+        let span = Span::current();
+        span.record("num_written", num_written);
+        span.record("num_removed", num_removed);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::WriteMetadata { errors })
+        }
+    }
+}
+
+#[test]
+fn failed_writes_stay_pending() {
+    use crate::filesystem::MemoryFileSystem;
+
+    /// A [`FileSystem`] that refuses to write to paths containing `readonly`.
+    struct PartlyReadOnly(MemoryFileSystem);
+
+    impl FileSystem for PartlyReadOnly {
+        fn glob(&self, base: &Path, glob: &wax::Glob<'_>) -> Vec<Result<PathBuf, Error>> {
+            self.0.glob(base, glob)
+        }
+
+        fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+            self.0.read(path)
+        }
+
+        fn read_to_string(&self, path: &Path) -> Result<String, Error> {
+            self.0.read_to_string(path)
+        }
+
+        fn write(&self, path: &Path, contents: &dyn std::fmt::Display) -> Result<(), Error> {
+            if path.to_string_lossy().contains("readonly") {
+                return Err(Error::WriteFile {
+                    path: path.to_owned(),
+                    source: io::ErrorKind::PermissionDenied.into(),
+                });
+            }
+            self.0.write(path, contents)
+        }
+
+        fn remove_file(&self, path: &Path) -> Result<(), Error> {
+            self.0.remove_file(path)
+        }
+    }
+
+    let fs = PartlyReadOnly(MemoryFileSystem::new());
+    let write = |path: &str| PendingWrite {
+        path: path.into(),
+        contents: Some(format!("contents of {path}")),
+    };
+    let mut manifest = WriteManifest {
+        completed: vec![],
+        pending: vec![write("a.ini"), write("readonly/b.ini"), write("c.ini")],
+        relocations: vec![],
+    };
+
+    assert!(matches!(
+        manifest.write(&fs, &AtomicBool::new(false)),
+        Err(Error::WriteMetadata { errors }) if errors.len() == 1
+    ));
+    assert_eq!(
+        manifest.completed,
+        [PathBuf::from("a.ini"), PathBuf::from("c.ini")]
+    );
+    assert_eq!(manifest.pending, [write("readonly/b.ini")]);
+
+    let path = Path::new("pending-writes.json");
+    manifest.save(&fs, path).unwrap();
+    assert_eq!(WriteManifest::load(&fs, path).unwrap(), Some(manifest));
+    assert_eq!(
+        WriteManifest::load(&fs, Path::new("missing.json")).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn diff_pending_writes() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let gecko_checkout = Path::new("gecko");
+    let fs = [
+        (
+            gecko_checkout.join("changed.ini"),
+            "[a]\n  expected: FAIL\n",
+        ),
+        (gecko_checkout.join("unchanged.ini"), "[b]\n"),
+        (gecko_checkout.join("removed.ini"), "[c]\n"),
+    ]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+    let write = |path: &str, contents: Option<&str>| PendingWrite {
+        path: gecko_checkout.join(path),
+        contents: contents.map(ToOwned::to_owned),
+    };
+    let manifest = WriteManifest {
+        completed: vec![],
+        pending: vec![
+            write("changed.ini", Some("[a]\n  expected: PASS\n")),
+            write("unchanged.ini", Some("[b]\n")),
+            write("removed.ini", None),
+            write("created.ini", Some("[d]\n")),
+            write("never_existed.ini", None),
+        ],
+        relocations: vec![],
+    };
+    insta::assert_snapshot!(manifest.diff(&fs, gecko_checkout).unwrap(), @r###"
+    --- a/changed.ini
+    +++ b/changed.ini
+    @@ -1,2 +1,2 @@
+     [a]
+    -  expected: FAIL
+    +  expected: PASS
+    --- a/removed.ini
+    +++ /dev/null
+    @@ -1 +0,0 @@
+    -[c]
+    --- /dev/null
+    +++ b/created.ini
+    @@ -0,0 +1 @@
+    +[d]
+    "###);
+
+    let manifest = WriteManifest {
+        pending: vec![write("unchanged.ini", Some("[b]\n"))],
+        ..manifest
+    };
+    assert_eq!(manifest.diff(&fs, gecko_checkout).unwrap(), "");
+}
@@ -0,0 +1,458 @@
+//! Reconciliation policies written as small scripts, selected with `--preset script:<file>`, for
+//! per-outcome control over a built-in [`ReportProcessingPreset`] without recompiling.
+//!
+//! A script has one statement per line. Blank lines and lines starting with `#` are ignored:
+//!
+//! ```text
+//! # The built-in preset resolving anything the rules below don't cover. Defaults to
+//! # `reset-contradictory`.
+//! preset new-fx
+//! # Never remove `CRASH` expectations.
+//! keep CRASH
+//! # Always merge reported outcomes into current expectations when `TIMEOUT` is reported.
+//! merge TIMEOUT
+//! ```
+//!
+//! Outcomes are spelled as in metadata, and rules apply to tests, subtests, or both, depending on
+//! which of them have the outcome (i.e., `CRASH` applies to both, while `FAIL` only applies to
+//! subtests).
+//!
+//! The same rules can be layered over any preset, including a script, with `--never-remove` and
+//! `--always-merge`.
+
+use std::{
+    ffi::OsStr,
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use clap::{
+    builder::{PossibleValue, StringValueParser, TypedValueParser, ValueParserFactory},
+    ValueEnum,
+};
+use enumset::{EnumSet, EnumSetType};
+
+use crate::{
+    error::Error,
+    filesystem::FileSystem,
+    metadata::{SubtestOutcome, TestOutcome},
+    process_reports::ReportProcessingPreset,
+    shared::Expected,
+    strategy::{ConfigOutcomes, ReconciliationStrategy},
+    test_pattern::TestPattern,
+};
+
+/// A `--preset` argument: either a built-in [`ReportProcessingPreset`], or a path to a script
+/// prefixed with `script:`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PresetArg {
+    Builtin(ReportProcessingPreset),
+    Script(PathBuf),
+}
+
+impl PresetArg {
+    /// Returns the [`ReconciliationStrategy`] for this preset, reading and parsing its script if
+    /// it has one.
+    pub fn strategy(&self, fs: &dyn FileSystem) -> Result<Arc<dyn ReconciliationStrategy>, Error> {
+        match self {
+            Self::Builtin(preset) => Ok(preset.strategy()),
+            Self::Script(path) => {
+                let contents = fs.read_to_string(path)?;
+                Ok(Arc::new(OutcomePolicy::parse(path, &contents)?))
+            }
+        }
+    }
+}
+
+impl FromStr for PresetArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("script:") {
+            Some("") => Err("expected a path after `script:`".to_owned()),
+            Some(path) => Ok(Self::Script(path.into())),
+            None => ReportProcessingPreset::from_str(s, true)
+                .map(Self::Builtin)
+                .map_err(|_e| {
+                    format!(
+                        "unrecognized preset {s:?}; expected one of `reset-contradictory` \
+                        (`new-fx`), `merge` (`same-fx`), `reset-all`, `prune-unobserved`, \
+                        `reset-timeouts`, `prune-stale-intermittents`, or `script:<file>`"
+                    )
+                }),
+        }
+    }
+}
+
+/// Parses [`PresetArg`]s, offering the names of built-in presets as possible values, so that shell
+/// completions can suggest them.
+#[derive(Clone, Copy, Debug)]
+pub struct PresetArgParser;
+
+impl TypedValueParser for PresetArgParser {
+    type Value = PresetArg;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        StringValueParser::new()
+            .try_map(|s| s.parse::<PresetArg>())
+            .parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let builtin = ReportProcessingPreset::value_variants()
+            .iter()
+            .filter_map(ValueEnum::to_possible_value);
+        Some(Box::new(builtin))
+    }
+}
+
+impl ValueParserFactory for PresetArg {
+    type Parser = PresetArgParser;
+
+    fn value_parser() -> Self::Parser {
+        PresetArgParser
+    }
+}
+
+/// A `--preset-override` argument: `PATTERN=PRESET`, where `PATTERN` is a [`TestPattern`] and
+/// `PRESET` is as for `--preset`, i.e., `webgpu:api,validation,*=merge`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PresetOverride {
+    pub pattern: TestPattern,
+    pub preset: PresetArg,
+}
+
+impl FromStr for PresetOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // N.B.: CTS queries may contain `=` themselves, but presets never do.
+        let (pattern, preset) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected `PATTERN=PRESET`, got {s:?}"))?;
+        Ok(Self {
+            pattern: pattern.parse()?,
+            preset: preset.parse()?,
+        })
+    }
+}
+
+/// What an [`OutcomePolicy`] rule does for its outcome.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RuleAction {
+    /// Current expectations of the outcome are never removed.
+    Keep,
+    /// When the outcome is reported, reported outcomes are added to current expectations.
+    Merge,
+}
+
+/// The name of an outcome, as spelled in metadata, resolved to the test and subtest outcomes it
+/// may refer to, of which there's always at least one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OutcomeName {
+    test: Option<TestOutcome>,
+    subtest: Option<SubtestOutcome>,
+}
+
+impl OutcomeName {
+    /// The test outcome this names, if any.
+    pub fn test(&self) -> Option<TestOutcome> {
+        self.test
+    }
+
+    /// The subtest outcome this names, if any.
+    pub fn subtest(&self) -> Option<SubtestOutcome> {
+        self.subtest
+    }
+}
+
+impl FromStr for OutcomeName {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn find_outcome<Out>(name: &str) -> Option<Out>
+        where
+            Out: EnumSetType + ToString,
+        {
+            EnumSet::<Out>::all()
+                .iter()
+                .find(|outcome| outcome.to_string() == name)
+        }
+
+        let test = find_outcome(s);
+        let subtest = find_outcome(s);
+        if test.is_none() && subtest.is_none() {
+            return Err(format!("unrecognized outcome {s:?}"));
+        }
+        Ok(Self { test, subtest })
+    }
+}
+
+impl Display for OutcomeName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self { test, subtest } = self;
+        match (test, subtest) {
+            (Some(test), _) => write!(f, "{test}"),
+            (None, Some(subtest)) => write!(f, "{subtest}"),
+            // N.B.: Parsing never resolves to neither, and there's no other way to construct one.
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+/// A [`ReconciliationStrategy`] that adjusts the resolutions of another with per-outcome rules,
+/// either parsed from a script (see the [module documentation](self) for syntax), or added with
+/// [`Self::add_rule`].
+#[derive(Clone, Debug)]
+pub struct OutcomePolicy {
+    base: Arc<dyn ReconciliationStrategy>,
+    test_rules: Vec<(RuleAction, TestOutcome)>,
+    subtest_rules: Vec<(RuleAction, SubtestOutcome)>,
+}
+
+impl OutcomePolicy {
+    /// Returns a policy resolving exactly as `base` does, until rules are added.
+    pub fn new(base: Arc<dyn ReconciliationStrategy>) -> Self {
+        Self {
+            base,
+            test_rules: Vec::new(),
+            subtest_rules: Vec::new(),
+        }
+    }
+
+    /// Adds a rule, applied after those added before it.
+    pub fn add_rule(&mut self, action: RuleAction, outcome: OutcomeName) {
+        let OutcomeName { test, subtest } = outcome;
+        self.test_rules
+            .extend(test.map(|outcome| (action, outcome)));
+        self.subtest_rules
+            .extend(subtest.map(|outcome| (action, outcome)));
+    }
+
+    pub fn parse(path: &Path, contents: &str) -> Result<Self, Error> {
+        let mut preset = None;
+        let mut rules = Vec::new();
+
+        for (line_num, line) in contents
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|(_line_num, line)| !line.is_empty() && !line.starts_with('#'))
+        {
+            let err = |reason: &str| Error::ParseStrategyScript {
+                path: path.to_owned(),
+                line_num,
+                line: line.to_owned(),
+                reason: reason.to_owned(),
+            };
+
+            let (keyword, arg) = line
+                .split_once(char::is_whitespace)
+                .map(|(keyword, arg)| (keyword, arg.trim()))
+                .ok_or_else(|| err("expected a keyword followed by an argument"))?;
+            let action = match keyword {
+                "preset" => {
+                    if preset.is_some() {
+                        return Err(err("`preset` may only be specified once"));
+                    }
+                    preset = Some(
+                        ReportProcessingPreset::from_str(arg, true)
+                            .map_err(|_e| err("unrecognized built-in preset"))?,
+                    );
+                    continue;
+                }
+                "keep" => RuleAction::Keep,
+                "merge" => RuleAction::Merge,
+                _ => return Err(err("unrecognized keyword")),
+            };
+            let outcome = arg
+                .parse()
+                .map_err(|_e: String| err("unrecognized outcome"))?;
+            rules.push((action, outcome));
+        }
+
+        let mut policy = Self::new(preset.unwrap_or_default().strategy());
+        for (action, outcome) in rules {
+            policy.add_rule(action, outcome);
+        }
+        Ok(policy)
+    }
+}
+
+fn apply_rules<Out>(
+    rules: &[(RuleAction, Out)],
+    outcomes: ConfigOutcomes<Out>,
+    mut resolved: Expected<Out>,
+) -> Expected<Out>
+where
+    Out: EnumSetType,
+{
+    let ConfigOutcomes {
+        current, reported, ..
+    } = outcomes;
+    for &(action, outcome) in rules {
+        match action {
+            RuleAction::Keep => {
+                if current.inner().contains(outcome) {
+                    resolved |= outcome;
+                }
+            }
+            RuleAction::Merge => {
+                if let Some(reported) =
+                    reported.filter(|reported| reported.inner().contains(outcome))
+                {
+                    resolved |= current | reported;
+                }
+            }
+        }
+    }
+    resolved
+}
+
+impl ReconciliationStrategy for OutcomePolicy {
+    fn resolve_test(&self, outcomes: ConfigOutcomes<TestOutcome>) -> Expected<TestOutcome> {
+        let resolved = self.base.resolve_test(outcomes);
+        apply_rules(&self.test_rules, outcomes, resolved)
+    }
+
+    fn resolve_subtest(
+        &self,
+        outcomes: ConfigOutcomes<SubtestOutcome>,
+    ) -> Expected<SubtestOutcome> {
+        let resolved = self.base.resolve_subtest(outcomes);
+        apply_rules(&self.subtest_rules, outcomes, resolved)
+    }
+
+    fn keeps_unreported_tests(&self) -> bool {
+        self.base.keeps_unreported_tests()
+    }
+}
+
+#[test]
+fn scripted_rules() {
+    use crate::metadata::{BuildProfile, Platform};
+
+    let strategy = OutcomePolicy::parse(
+        Path::new("policy.txt"),
+        concat!(
+            "# Never remove crashes, and always merge timeouts.\n",
+            "preset new-fx\n",
+            "keep CRASH\n",
+            "\n",
+            "merge TIMEOUT\n",
+        ),
+    )
+    .unwrap();
+    assert_eq!(strategy.test_rules.len(), 2);
+    assert_eq!(strategy.subtest_rules.len(), 2);
+
+    let outcomes = |current, reported| ConfigOutcomes {
+        platform: Platform::Linux,
+        build_profile: BuildProfile::Debug,
+        current,
+        reported: Some(reported),
+        covered: true,
+    };
+    let resolve = |current, reported| strategy.resolve_subtest(outcomes(current, reported));
+
+    // Without rules applying, `reset-contradictory` resolves as usual.
+    assert_eq!(
+        resolve(
+            Expected::permanent(SubtestOutcome::Pass),
+            Expected::permanent(SubtestOutcome::Fail)
+        ),
+        Expected::permanent(SubtestOutcome::Fail)
+    );
+    // `CRASH` is kept, even though it wasn't reported.
+    assert_eq!(
+        resolve(
+            Expected::permanent(SubtestOutcome::Crash),
+            Expected::permanent(SubtestOutcome::Pass)
+        ),
+        Expected::intermittent(SubtestOutcome::Pass | SubtestOutcome::Crash).unwrap()
+    );
+    // `CRASH` is kept alongside the contradicting `FAIL`.
+    assert_eq!(
+        resolve(
+            Expected::permanent(SubtestOutcome::Crash),
+            Expected::permanent(SubtestOutcome::Fail)
+        ),
+        Expected::intermittent(SubtestOutcome::Crash | SubtestOutcome::Fail).unwrap()
+    );
+    // `TIMEOUT` was reported, so it is merged instead of replacing `PASS`.
+    assert_eq!(
+        resolve(
+            Expected::permanent(SubtestOutcome::Pass),
+            Expected::permanent(SubtestOutcome::Timeout)
+        ),
+        Expected::intermittent(SubtestOutcome::Pass | SubtestOutcome::Timeout).unwrap()
+    );
+}
+
+#[test]
+fn scripted_errors() {
+    let parse = |contents| {
+        OutcomePolicy::parse(Path::new("policy.txt"), contents)
+            .unwrap_err()
+            .to_string()
+    };
+    insta::assert_snapshot!(parse("keep\n"), @r###"failed to parse line 1 of reconciliation script at policy.txt: expected a keyword followed by an argument: "keep""###);
+    insta::assert_snapshot!(parse("keep PASS\nforget FAIL\n"), @r###"failed to parse line 2 of reconciliation script at policy.txt: unrecognized keyword: "forget FAIL""###);
+    insta::assert_snapshot!(parse("keep FLAKE\n"), @r###"failed to parse line 1 of reconciliation script at policy.txt: unrecognized outcome: "keep FLAKE""###);
+    insta::assert_snapshot!(parse("preset new-fx\npreset same-fx\n"), @r###"failed to parse line 2 of reconciliation script at policy.txt: `preset` may only be specified once: "preset same-fx""###);
+
+    assert_eq!(
+        "script:policy.txt".parse::<PresetArg>(),
+        Ok(PresetArg::Script("policy.txt".into()))
+    );
+    assert_eq!(
+        "same-fx".parse::<PresetArg>(),
+        Ok(PresetArg::Builtin(ReportProcessingPreset::Merge))
+    );
+    assert!("script:".parse::<PresetArg>().is_err());
+}
+
+#[test]
+fn preset_overrides() {
+    assert_eq!(
+        r#"webgpu:api,validation,texture:format="r8unorm";*=merge"#.parse::<PresetOverride>(),
+        Ok(PresetOverride {
+            pattern: TestPattern::CtsQuery(
+                r#"webgpu:api,validation,texture:format="r8unorm";*"#.to_owned()
+            ),
+            preset: PresetArg::Builtin(ReportProcessingPreset::Merge),
+        })
+    );
+    assert!("webgpu:api,*".parse::<PresetOverride>().is_err());
+    assert!("api,*=merge".parse::<PresetOverride>().is_err());
+    assert!("webgpu:api,*=nope".parse::<PresetOverride>().is_err());
+}
+
+#[test]
+fn outcome_names() {
+    assert_eq!(
+        "CRASH".parse::<OutcomeName>(),
+        Ok(OutcomeName {
+            test: Some(TestOutcome::Crash),
+            subtest: Some(SubtestOutcome::Crash),
+        })
+    );
+    assert_eq!(
+        "FAIL".parse::<OutcomeName>(),
+        Ok(OutcomeName {
+            test: None,
+            subtest: Some(SubtestOutcome::Fail),
+        })
+    );
+    assert_eq!(
+        "crash".parse::<OutcomeName>(),
+        Err("unrecognized outcome \"crash\"".to_owned())
+    );
+}
@@ -0,0 +1,289 @@
+//! Searching expectations in metadata, i.e., for tests expected to crash in Linux debug builds,
+//! without reading metadata files by hand.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use enumset::EnumSetType;
+use strum::IntoEnumIterator;
+use whippit::metadata::SectionHeader;
+
+use crate::{
+    metadata::{BuildProfile, File, Platform, Subtest, Test, TestOutcome, TestProps},
+    script::OutcomeName,
+    shared::{Expected, TestPath},
+    test_pattern::TestPattern,
+};
+
+/// Filters for [`search`]. Empty filters match everything.
+#[derive(Clone, Debug, Default)]
+pub struct SearchFilters {
+    /// Matches configurations in which any of these outcomes is expected.
+    pub outcomes: Vec<OutcomeName>,
+    pub platforms: Vec<Platform>,
+    pub build_profiles: Vec<BuildProfile>,
+    /// Matches configurations that are disabled (`Some(true)`) or that aren't (`Some(false)`).
+    /// Subtests are disabled wherever their test is.
+    pub disabled: Option<bool>,
+    /// Matches tests, and their subtests, matching any of these patterns.
+    pub patterns: Vec<TestPattern>,
+}
+
+/// A test or subtest found by [`search`].
+#[derive(Clone, Debug)]
+pub struct SearchHit {
+    pub test: TestPath<'static>,
+    /// The name of the subtest found, or `None` if it's the test itself.
+    pub subtest: Option<String>,
+    /// Expectations in each configuration matching the filters, in the order of [`Platform`] and
+    /// [`BuildProfile`].
+    pub configurations: Vec<ConfigurationExpectation>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConfigurationExpectation {
+    pub platform: Platform,
+    pub build_profile: BuildProfile,
+    /// The expected outcomes, as spelled in metadata, resolved as in [`TestProps::expected`], with
+    /// outcomes expected when no expectations are set (i.e., `PASS`) filled in.
+    pub expected: Vec<String>,
+    /// Why the test or subtest is disabled, if it is.
+    pub disabled: Option<String>,
+}
+
+/// Finds tests and subtests in `files` with configurations matching all of `filters`.
+///
+/// Tests without metadata are expected to pass everywhere, but are not found, since there's no
+/// record of them.
+pub fn search<'a>(
+    gecko_checkout: &Path,
+    files: impl IntoIterator<Item = (&'a Arc<PathBuf>, &'a File)>,
+    filters: &SearchFilters,
+) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+    for (path, file) in files {
+        let Ok(rel_path) = path.strip_prefix(gecko_checkout) else {
+            continue;
+        };
+        for (SectionHeader(name), test) in &file.tests {
+            let Ok(test_path) = TestPath::from_metadata_test(rel_path, name) else {
+                continue;
+            };
+            if !filters.patterns.is_empty()
+                && !filters
+                    .patterns
+                    .iter()
+                    .any(|pattern| pattern.matches(&test_path))
+            {
+                continue;
+            }
+            let Test {
+                properties,
+                subtests,
+            } = test;
+            let test_path = test_path.into_owned();
+            let configurations =
+                matching_configurations(filters, properties, None, |name| name.test());
+            if !configurations.is_empty() {
+                hits.push(SearchHit {
+                    test: test_path.clone(),
+                    subtest: None,
+                    configurations,
+                });
+            }
+            for (SectionHeader(subtest_name), subtest) in subtests {
+                let Subtest {
+                    properties: subtest_properties,
+                } = subtest;
+                let configurations = matching_configurations(
+                    filters,
+                    subtest_properties,
+                    Some(properties),
+                    |name| name.subtest(),
+                );
+                if !configurations.is_empty() {
+                    hits.push(SearchHit {
+                        test: test_path.clone(),
+                        subtest: Some(subtest_name.clone()),
+                        configurations,
+                    });
+                }
+            }
+        }
+    }
+    hits
+}
+
+/// Returns expectations of `properties` in configurations matching `filters`. `parent` is the
+/// properties of the test, for subtests. `outcome` resolves names in [`SearchFilters::outcomes`]
+/// to outcomes of `Out`.
+fn matching_configurations<Out>(
+    filters: &SearchFilters,
+    properties: &TestProps<Out>,
+    parent: Option<&TestProps<TestOutcome>>,
+    outcome: impl Fn(&OutcomeName) -> Option<Out>,
+) -> Vec<ConfigurationExpectation>
+where
+    Out: Default + Display + EnumSetType,
+{
+    let SearchFilters {
+        outcomes,
+        platforms,
+        build_profiles,
+        disabled,
+        patterns: _,
+    } = filters;
+    let mut configurations = Vec::new();
+    for platform in Platform::iter() {
+        if !platforms.is_empty() && !platforms.contains(&platform) {
+            continue;
+        }
+        for build_profile in BuildProfile::iter() {
+            if !build_profiles.is_empty() && !build_profiles.contains(&build_profile) {
+                continue;
+            }
+            let disabled_reason = properties
+                .disabled_reason_on(platform, build_profile)
+                .or_else(|| {
+                    parent.and_then(|parent| parent.disabled_reason_on(platform, build_profile))
+                });
+            if disabled.is_some_and(|disabled| disabled != disabled_reason.is_some()) {
+                continue;
+            }
+            let expected = properties
+                .expected
+                .as_ref()
+                .map_or_else(Expected::default, |expected| {
+                    expected.get(platform, build_profile)
+                });
+            if !outcomes.is_empty()
+                && !outcomes
+                    .iter()
+                    .filter_map(&outcome)
+                    .any(|outcome| expected.inner().contains(outcome))
+            {
+                continue;
+            }
+            configurations.push(ConfigurationExpectation {
+                platform,
+                build_profile,
+                expected: expected.iter().map(|outcome| outcome.to_string()).collect(),
+                disabled: disabled_reason.map(|reason| reason.to_string()),
+            });
+        }
+    }
+    configurations
+}
+
+impl Display for SearchHit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            test,
+            subtest,
+            configurations,
+        } = self;
+        write!(f, "/{}", test.runner_url_path())?;
+        if let Some(subtest) = subtest {
+            write!(f, " [{subtest}]")?;
+        }
+        writeln!(f)?;
+        for configuration in configurations {
+            let ConfigurationExpectation {
+                platform,
+                build_profile,
+                expected,
+                disabled,
+            } = configuration;
+            write!(f, "  {platform:?} {build_profile:?}: ")?;
+            match &expected[..] {
+                [outcome] => write!(f, "{outcome}")?,
+                outcomes => write!(f, "[{}]", outcomes.join(", "))?,
+            }
+            if let Some(reason) = disabled {
+                write!(f, " (disabled: {reason})")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn search_expectations() {
+    use indexmap::IndexMap;
+
+    use crate::metadata::{FileBuilder, SubtestBuilder, SubtestOutcome, TestBuilder};
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = Arc::new(
+        gecko_checkout
+            .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu")
+            .join("cts.https.html.ini"),
+    );
+    let file = FileBuilder::new()
+        .test(
+            TestBuilder::new("cts.https.html?q=webgpu:api,operation,adapter:*")
+                .expected(Expected::permanent(TestOutcome::Crash))
+                .subtest(SubtestBuilder::new(":a").expected_on(
+                    Platform::Linux,
+                    BuildProfile::Debug,
+                    Expected::new(SubtestOutcome::Pass | SubtestOutcome::Fail).unwrap(),
+                )),
+        )
+        .test(
+            TestBuilder::new("cts.https.html?q=webgpu:api,operation,buffers:*")
+                .subtest(SubtestBuilder::new(":b"))
+                .subtest(SubtestBuilder::new(":c").disabled_because("bug 1")),
+        )
+        .build()
+        .unwrap();
+    let files = [(meta_path, file)].into_iter().collect::<IndexMap<_, _>>();
+
+    let render = |filters: &SearchFilters| {
+        search(gecko_checkout, &files, filters)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<String>()
+    };
+
+    insta::assert_snapshot!(render(&SearchFilters {
+        outcomes: vec!["CRASH".parse().unwrap(), "FAIL".parse().unwrap()],
+        platforms: vec![Platform::Linux],
+        build_profiles: vec![BuildProfile::Debug],
+        ..Default::default()
+    }), @r###"
+    /_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*
+      Linux Debug: CRASH
+    /_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:* [:a]
+      Linux Debug: [PASS, FAIL]
+    "###);
+
+    insta::assert_snapshot!(render(&SearchFilters {
+        patterns: vec!["webgpu:api,operation,buffers:*".parse().unwrap()],
+        platforms: vec![Platform::Windows],
+        ..Default::default()
+    }), @r###"
+    /_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:*
+      Windows Debug: OK
+      Windows Optimized: OK
+    /_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:* [:b]
+      Windows Debug: PASS
+      Windows Optimized: PASS
+    /_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:* [:c]
+      Windows Debug: PASS (disabled: bug 1)
+      Windows Optimized: PASS (disabled: bug 1)
+    "###);
+
+    insta::assert_snapshot!(render(&SearchFilters {
+        disabled: Some(true),
+        platforms: vec![Platform::MacOs],
+        ..Default::default()
+    }), @r###"
+    /_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,buffers:* [:c]
+      MacOs Debug: PASS (disabled: bug 1)
+      MacOs Optimized: PASS (disabled: bug 1)
+    "###);
+}
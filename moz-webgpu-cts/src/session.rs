@@ -0,0 +1,101 @@
+//! State shared between `update-expected --session` invocations, so that reports for different
+//! configurations can be processed across several invocations (i.e., as CI runs complete over
+//! several days) without losing track of which configurations still lack data.
+
+use std::{
+    collections::BTreeSet,
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::{
+    checkout::state_dir,
+    error::Error,
+    filesystem::FileSystem,
+    metadata::{BuildProfile, Platform},
+};
+
+/// The configurations covered by reports processed so far in a session.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Session {
+    pub processed: BTreeSet<(Platform, BuildProfile)>,
+}
+
+impl Session {
+    /// Returns the path where the session named `name` is stored, under [`state_dir`].
+    pub fn path(name: &str) -> Result<PathBuf, Error> {
+        Ok(state_dir()?.join("sessions").join(format!("{name}.json")))
+    }
+
+    /// Reads the session at `path`, or returns an empty session if none exists yet.
+    pub fn load(fs: &dyn FileSystem, path: &Path) -> Result<Self, Error> {
+        let contents = match fs.read_to_string(path) {
+            Ok(contents) => contents,
+            Err(Error::ReadFile { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(e) => return Err(e),
+        };
+        serde_json::from_str(&contents).map_err(|source| Error::ParseSession {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    pub fn save(&self, fs: &dyn FileSystem, path: &Path) -> Result<(), Error> {
+        fs.write(path, &serde_json::to_string_pretty(self).unwrap())
+    }
+
+    /// Returns the configurations for which no reports have been processed yet.
+    pub fn missing(&self) -> impl Iterator<Item = (Platform, BuildProfile)> + '_ {
+        Platform::iter()
+            .flat_map(|platform| {
+                BuildProfile::iter().map(move |build_profile| (platform, build_profile))
+            })
+            .filter(|config| !self.processed.contains(config))
+    }
+}
+
+#[test]
+fn round_trip() {
+    use crate::filesystem::MemoryFileSystem;
+
+    let fs = MemoryFileSystem::new();
+    let path = Path::new("sessions/new-fx.json");
+
+    let mut session = Session::load(&fs, path).unwrap();
+    assert_eq!(session, Session::default());
+    assert_eq!(session.missing().count(), 8);
+
+    session
+        .processed
+        .insert((Platform::Linux, BuildProfile::Debug));
+    session.save(&fs, path).unwrap();
+    insta::assert_snapshot!(fs.files()[path], @r###"
+    {
+      "processed": [
+        [
+          "linux",
+          "debug"
+        ]
+      ]
+    }
+    "###);
+
+    let session = Session::load(&fs, path).unwrap();
+    assert_eq!(
+        session.missing().collect::<Vec<_>>(),
+        [
+            (Platform::Windows, BuildProfile::Debug),
+            (Platform::Windows, BuildProfile::Optimized),
+            (Platform::Linux, BuildProfile::Optimized),
+            (Platform::MacOs, BuildProfile::Debug),
+            (Platform::MacOs, BuildProfile::Optimized),
+            (Platform::Android, BuildProfile::Debug),
+            (Platform::Android, BuildProfile::Optimized),
+        ]
+    );
+}
@@ -0,0 +1,88 @@
+//! Orderings of outcomes by how bad they are, for ranking tests by their most severe expected
+//! outcome (i.e., in `triage --severity`).
+
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use joinery::JoinableIterator;
+
+use crate::{
+    metadata::{SubtestOutcome, TestOutcome},
+    script::OutcomeName,
+};
+
+/// Outcomes that count as bad, from most to least severe. Outcomes not in the ordering are not
+/// bad.
+///
+/// Parsed from outcomes separated by commas, as spelled in metadata, i.e.,
+/// `CRASH,ERROR,TIMEOUT,NOTRUN,FAIL`, which is also the default.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SeverityOrder(Vec<OutcomeName>);
+
+impl Default for SeverityOrder {
+    fn default() -> Self {
+        "CRASH,ERROR,TIMEOUT,NOTRUN,FAIL".parse().unwrap()
+    }
+}
+
+impl SeverityOrder {
+    /// The bad outcomes, from most to least severe.
+    pub fn outcomes(&self) -> &[OutcomeName] {
+        &self.0
+    }
+
+    /// Returns the index of `outcome` in [`Self::outcomes`], or `None` if it's not bad. Lower is
+    /// more severe.
+    pub fn rank_test_outcome(&self, outcome: TestOutcome) -> Option<usize> {
+        self.0.iter().position(|name| name.test() == Some(outcome))
+    }
+
+    /// Like [`Self::rank_test_outcome`], but for subtests.
+    pub fn rank_subtest_outcome(&self, outcome: SubtestOutcome) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|name| name.subtest() == Some(outcome))
+    }
+}
+
+impl FromStr for SeverityOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut outcomes = Vec::new();
+        for name in s.split(',').map(str::trim) {
+            let outcome = name.parse::<OutcomeName>()?;
+            if outcomes.contains(&outcome) {
+                return Err(format!("outcome {name:?} is listed more than once"));
+            }
+            outcomes.push(outcome);
+        }
+        Ok(Self(outcomes))
+    }
+}
+
+impl Display for SeverityOrder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.iter().join_with(','))
+    }
+}
+
+#[test]
+fn parse_severity_order() {
+    let order = "ERROR, CRASH,FAIL".parse::<SeverityOrder>().unwrap();
+    assert_eq!(order.to_string(), "ERROR,CRASH,FAIL");
+    assert_eq!(order.rank_test_outcome(TestOutcome::Error), Some(0));
+    assert_eq!(order.rank_test_outcome(TestOutcome::Crash), Some(1));
+    assert_eq!(order.rank_subtest_outcome(SubtestOutcome::Crash), Some(1));
+    assert_eq!(order.rank_subtest_outcome(SubtestOutcome::Fail), Some(2));
+    assert_eq!(order.rank_test_outcome(TestOutcome::Timeout), None);
+
+    assert_eq!(
+        SeverityOrder::default().to_string(),
+        "CRASH,ERROR,TIMEOUT,NOTRUN,FAIL"
+    );
+    assert!("CRASH,CRASH".parse::<SeverityOrder>().is_err());
+    assert!("CRASH,".parse::<SeverityOrder>().is_err());
+}
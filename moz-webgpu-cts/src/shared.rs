@@ -4,15 +4,18 @@ use std::{
     fmt::{self, Debug, Display, Formatter},
     num::NonZeroUsize,
     ops::{BitOr, BitOrAssign, Index, IndexMut},
-    path::Path,
+    path::{Path, PathBuf},
+    str::FromStr,
 };
 
-use camino::{Utf8Component, Utf8Path};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use clap::ValueEnum;
 
 use enum_map::EnumMap;
 use enumset::{EnumSet, EnumSetType};
 use format::lazy_format;
 use joinery::JoinableIterator;
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 
 use crate::metadata::{BuildProfile, Platform};
@@ -24,7 +27,7 @@ use crate::metadata::{BuildProfile, Platform};
 ///
 /// [`Test`]: crate::metadata::Test
 /// [`Subtest`]: crate::metadata::Subtest
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Expected<Out>(EnumSet<Out>)
 where
     Out: EnumSetType;
@@ -210,7 +213,7 @@ where
 
 /// A completely flat representation of [`NormalizedExpectedPropertyValueData`] suitable for
 /// byte representation in memory.
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Deserialize, Eq, PartialEq, Serialize)]
 pub struct FullyExpandedExpectedPropertyValue<Out>(
     EnumMap<Platform, EnumMap<BuildProfile, Expected<Out>>>,
 )
@@ -261,9 +264,7 @@ where
         self.0[platform][build_profile]
     }
 
-    pub(crate) fn iter(
-        &self,
-    ) -> impl Iterator<Item = ((Platform, BuildProfile), Expected<Out>)> + '_ {
+    pub fn iter(&self) -> impl Iterator<Item = ((Platform, BuildProfile), Expected<Out>)> + '_ {
         self.0.iter().flat_map(|(platform, exps_by_bp)| {
             exps_by_bp
                 .iter()
@@ -271,7 +272,7 @@ where
         })
     }
 
-    pub(crate) fn iter_mut(
+    pub fn iter_mut(
         &mut self,
     ) -> impl Iterator<Item = ((Platform, BuildProfile), &mut Expected<Out>)> + '_ {
         self.0.iter_mut().flat_map(|(platform, exps_by_bp)| {
@@ -309,11 +310,11 @@ fn fully_expanded_is_tiny() {
 
     assert_eq!(
         size_of::<FullyExpandedExpectedPropertyValue<TestOutcome>>(),
-        6
+        8
     );
     assert_eq!(
         size_of::<FullyExpandedExpectedPropertyValue<SubtestOutcome>>(),
-        6
+        8
     );
 }
 
@@ -418,7 +419,10 @@ where
 /// This API is useful as a common representation of a path for [`crate::report::ExecutionReport`]s
 /// and [`crate::metadata::File`]s.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub(crate) struct TestPath<'a> {
+pub struct TestPath<'a> {
+    /// The browser whose checkout the test and its metadata are in, which determines the
+    /// directory layout of `scope`.
+    pub browser: Browser,
     pub scope: TestScope,
     /// A relative offset into `scope`.
     pub path: Cow<'a, Utf8Path>,
@@ -431,19 +435,80 @@ pub(crate) struct TestPath<'a> {
     pub variant: Option<Cow<'a, str>>,
 }
 
-const SCOPE_DIR_FX_PRIVATE_STR: &str = "testing/web-platform/mozilla";
-const SCOPE_DIR_FX_PRIVATE_COMPONENTS: &[&str] = &["testing", "web-platform", "mozilla"];
-const SCOPE_DIR_FX_PUBLIC_STR: &str = "testing/web-platform";
-const SCOPE_DIR_FX_PUBLIC_COMPONENTS: &[&str] = &["testing", "web-platform"];
+/// A browser whose checkout metadata can be read from and written to.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, ValueEnum)]
+pub enum Browser {
+    /// Tests are under `testing/web-platform/mozilla/tests` (at `/_mozilla/…`) and
+    /// `testing/web-platform/tests`, with metadata in sibling `meta` directories.
+    #[default]
+    Firefox,
+    /// Tests are under `third_party/blink/web_tests/wpt_internal` (at `/wpt_internal/…`) and
+    /// `third_party/blink/web_tests/external/wpt`, with metadata beside them.
+    Chromium,
+}
+
+impl Browser {
+    /// The first component of URL paths of tests in [`TestScope::Private`].
+    fn private_url_dir(self) -> &'static str {
+        match self {
+            Self::Firefox => "_mozilla",
+            Self::Chromium => "wpt_internal",
+        }
+    }
+
+    /// The directory from which tests in `scope` are based, relative to the root of a checkout.
+    fn scope_dir(self, scope: &TestScope) -> &'static [&'static str] {
+        match (self, scope) {
+            (Self::Firefox, TestScope::Public) => &["testing", "web-platform"],
+            (Self::Firefox, TestScope::Private) => &["testing", "web-platform", "mozilla"],
+            (Self::Chromium, TestScope::Public) => {
+                &["third_party", "blink", "web_tests", "external", "wpt"]
+            }
+            (Self::Chromium, TestScope::Private) => {
+                &["third_party", "blink", "web_tests", "wpt_internal"]
+            }
+        }
+    }
+
+    /// The directory containing tests in `scope`, relative to the root of a checkout.
+    fn tests_dir(self, scope: &TestScope) -> impl Iterator<Item = &'static str> + Clone {
+        let subdir = match self {
+            Self::Firefox => Some("tests"),
+            Self::Chromium => None,
+        };
+        self.scope_dir(scope).iter().copied().chain(subdir)
+    }
+
+    /// The directory containing metadata for tests in `scope`, relative to the root of a
+    /// checkout.
+    fn meta_dir(self, scope: &TestScope) -> impl Iterator<Item = &'static str> + Clone {
+        let subdir = match self {
+            Self::Firefox => Some("meta"),
+            Self::Chromium => None,
+        };
+        self.scope_dir(scope).iter().copied().chain(subdir)
+    }
+
+    /// Returns the directory containing WebGPU CTS metadata in `checkout`.
+    pub fn webgpu_cts_meta_dir(self, checkout: &Path) -> PathBuf {
+        let mut dir = checkout.to_owned();
+        dir.extend(self.meta_dir(&TestScope::Private));
+        dir.push("webgpu");
+        dir
+    }
+}
 
 impl<'a> TestPath<'a> {
     pub fn from_execution_report(
+        browser: Browser,
         test_url_path: &'a str,
     ) -> Result<Self, ExecutionReportPathError<'a>> {
         let err = || ExecutionReportPathError { test_url_path };
         let Some((scope, path)) = test_url_path
-            .strip_prefix("/_mozilla/")
-            .map(|stripped| (TestScope::FirefoxPrivate, stripped))
+            .strip_prefix('/')
+            .and_then(|stripped| stripped.strip_prefix(browser.private_url_dir()))
+            .and_then(|stripped| stripped.strip_prefix('/'))
+            .map(|stripped| (TestScope::Private, stripped))
             .or_else(|| {
                 test_url_path
                     .strip_prefix('/')
@@ -469,13 +534,16 @@ impl<'a> TestPath<'a> {
         };
 
         Ok(Self {
+            browser,
             scope,
             path: Utf8Path::new(path).into(),
             variant: variant.map(Into::into),
         })
     }
 
-    pub fn from_fx_metadata_test(
+    /// Derives a test path from the name of a test's section in the metadata file at
+    /// `rel_meta_file_path`, relative to the root of a checkout of any [`Browser`].
+    pub fn from_metadata_test(
         rel_meta_file_path: &'a Path,
         test_name: &'a str,
     ) -> Result<Self, MetadataTestPathError<'a>> {
@@ -495,16 +563,18 @@ impl<'a> TestPath<'a> {
                 .ok_or(err())?,
         );
 
-        let (scope, path) = {
-            if let Ok(path) = rel_meta_file_path.strip_prefix(SCOPE_DIR_FX_PRIVATE_STR) {
-                (TestScope::FirefoxPrivate, path)
-            } else if let Ok(path) = rel_meta_file_path.strip_prefix(SCOPE_DIR_FX_PUBLIC_STR) {
-                (TestScope::Public, path)
-            } else {
-                return Err(err());
-            }
-        };
-        let Ok(path) = path.strip_prefix("meta/") else {
+        // N.B.: Private scopes are checked first, since Firefox's is nested in its public one.
+        let Some((browser, scope, path)) = Browser::value_variants()
+            .iter()
+            .flat_map(|&browser| {
+                [TestScope::Private, TestScope::Public].map(|scope| (browser, scope))
+            })
+            .find_map(|(browser, scope)| {
+                let meta_dir = browser.meta_dir(&scope).collect::<Utf8PathBuf>();
+                let path = rel_meta_file_path.strip_prefix(meta_dir).ok()?;
+                Some((browser, scope, path))
+            })
+        else {
             return Err(err());
         };
 
@@ -515,6 +585,7 @@ impl<'a> TestPath<'a> {
         }
 
         Ok(Self {
+            browser,
             scope,
             path: Utf8Path::new(path).into(),
             variant: variant.map(Into::into),
@@ -533,22 +604,25 @@ impl<'a> TestPath<'a> {
 
     pub fn into_owned(self) -> TestPath<'static> {
         let Self {
+            browser,
             scope,
             path,
             variant,
         } = self;
 
         TestPath {
+            browser,
             scope: scope.clone(),
             path: path.clone().into_owned().into(),
             variant: variant.clone().map(|v| v.into_owned().into()),
         }
     }
 
-    pub(crate) fn test_name(&self) -> impl Display + '_ {
+    pub fn test_name(&self) -> impl Display + '_ {
         let Self {
             path,
             variant,
+            browser: _,
             scope: _,
         } = self;
         let base_name = path.file_name().unwrap();
@@ -562,18 +636,18 @@ impl<'a> TestPath<'a> {
         })
     }
 
-    pub(crate) fn runner_url_path(&self) -> impl Display + '_ {
+    pub fn runner_url_path(&self) -> impl Display + '_ {
         let Self {
             path,
             variant,
+            browser,
             scope,
         } = self;
         lazy_format!(move |f| {
-            let scope_prefix = match scope {
-                TestScope::Public => "",
-                TestScope::FirefoxPrivate => "_mozilla/",
-            };
-            write!(f, "{scope_prefix}{}", path.components().join_with('/'))?;
+            if let TestScope::Private = scope {
+                write!(f, "{}/", browser.private_url_dir())?;
+            }
+            write!(f, "{}", path.components().join_with('/'))?;
             if let Some(variant) = variant.as_ref() {
                 write!(f, "{}", variant)?;
             }
@@ -581,22 +655,49 @@ impl<'a> TestPath<'a> {
         })
     }
 
-    pub(crate) fn rel_metadata_path_fx(&self) -> impl Display + '_ {
+    /// Like [`Self::rel_metadata_path`], but for the file containing the test itself.
+    pub fn rel_test_path(&self) -> impl Display + '_ {
         let Self {
             path,
             variant: _,
+            browser,
             scope,
         } = self;
 
-        let scope_dir = match scope {
-            TestScope::Public => SCOPE_DIR_FX_PUBLIC_COMPONENTS,
-            TestScope::FirefoxPrivate => SCOPE_DIR_FX_PRIVATE_COMPONENTS,
-        }
-        .iter()
-        .chain(&["meta"])
-        .join_with(std::path::MAIN_SEPARATOR);
+        let tests_dir = browser
+            .tests_dir(scope)
+            .join_with(std::path::MAIN_SEPARATOR);
+
+        lazy_format!(move |f| { write!(f, "{tests_dir}{}{path}", std::path::MAIN_SEPARATOR) })
+    }
+
+    /// Returns the query of the WebGPU CTS test this refers to (i.e., `webgpu:api,operation,*`),
+    /// or `None` if this is not a CTS test.
+    pub fn cts_path(&self) -> Option<String> {
+        self.variant
+            .as_ref()
+            .filter(|v| v.starts_with("?q=webgpu:"))
+            .map(|v| v.strip_prefix("?q=").unwrap().to_owned())
+            .filter(|_q| self.path.ends_with("cts.https.html"))
+    }
+
+    /// Like [`Self::cts_path`], but parsed. Returns `None` if this is not a CTS test, or its
+    /// query is malformed.
+    pub fn cts_query(&self) -> Option<CtsQuery> {
+        self.cts_path()?.parse().ok()
+    }
+
+    pub fn rel_metadata_path(&self) -> impl Display + '_ {
+        let Self {
+            path,
+            variant: _,
+            browser,
+            scope,
+        } = self;
 
-        lazy_format!(move |f| { write!(f, "{scope_dir}{}{path}.ini", std::path::MAIN_SEPARATOR) })
+        let meta_dir = browser.meta_dir(scope).join_with(std::path::MAIN_SEPARATOR);
+
+        lazy_format!(move |f| { write!(f, "{meta_dir}{}{path}.ini", std::path::MAIN_SEPARATOR) })
     }
 }
 
@@ -639,40 +740,278 @@ impl Display for MetadataTestPathError<'_> {
     }
 }
 
+/// The markup used by subcommands whose output is meant to be read by people.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Plain text, or Markdown where output is meant to be pasted into bugs.
+    #[default]
+    Text,
+    /// Phabricator's Remarkup, for pasting into revisions.
+    Remarkup,
+    /// Markdown, for pasting into GitHub issues or Bugzilla comments, with headings and lists
+    /// instead of indentation, and test names linking to where they're served.
+    Markdown,
+}
+
 /// Symbolically represents a file root from which tests and metadata are based.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub(crate) enum TestScope {
+pub enum TestScope {
     /// A public test available at some point in the history of [WPT upstream]. Note that while
     /// a test may be public, metadata associated with it is in a private location.
     ///
     /// [WPT upstream]: https://github.com/web-platform-tests/wpt
     Public,
-    /// A private test specific to Firefox.
-    FirefoxPrivate,
+    /// A private test specific to the [`Browser`] whose checkout it's in.
+    Private,
+}
+
+/// A query of the [WebGPU CTS], selecting the tests and subtests it matches, i.e.,
+/// `webgpu:api,operation,adapter:requestDevice:*` (as found after `?q=` in the variants of test
+/// URLs).
+///
+/// Queries have up to four `:`-separated levels: the suite, a `,`-separated path to a file of
+/// tests, a `,`-separated path to a test in that file, and `;`-separated parameters of a subtest.
+/// The last level may end with a wildcard `*`, matching anything nested under the levels before
+/// it; otherwise, the query matches a single file, test, or subtest exactly.
+///
+/// [WebGPU CTS]: https://github.com/gpuweb/cts/blob/main/docs/terms.md#queries
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CtsQuery {
+    /// The suite of tests, i.e., `webgpu`.
+    pub suite: String,
+    /// Segments of the path to a file of tests, i.e., `["api", "operation", "adapter"]`.
+    pub file_path: Vec<String>,
+    /// Segments of the path to a test in the file, or `None` if the query selects files.
+    pub test_path: Option<Vec<String>>,
+    /// Names and values of subtest parameters, or `None` if the query selects tests. Values are
+    /// kept as written, i.e., `"r8unorm"` with quotes, since they're JSON.
+    pub params: Option<Vec<(String, String)>>,
+    /// Whether the query ends with a wildcard `*`.
+    pub wildcard: bool,
+}
+
+impl CtsQuery {
+    /// Returns a query for the files under the first `depth` segments of this query's file path,
+    /// i.e., `webgpu:api,*` for `webgpu:api,operation,adapter:*` with a `depth` of 1.
+    pub fn file_prefix(&self, depth: usize) -> Self {
+        let Self {
+            suite,
+            file_path,
+            test_path: _,
+            params: _,
+            wildcard: _,
+        } = self;
+        Self {
+            suite: suite.clone(),
+            file_path: file_path.iter().take(depth).cloned().collect(),
+            test_path: None,
+            params: None,
+            wildcard: true,
+        }
+    }
+
+    /// Returns a query for the file this query selects tests in, i.e.,
+    /// `webgpu:api,operation,adapter` for `webgpu:api,operation,adapter:requestDevice:*`.
+    pub fn file(&self) -> Self {
+        let Self {
+            suite,
+            file_path,
+            test_path: _,
+            params: _,
+            wildcard: _,
+        } = self;
+        Self {
+            suite: suite.clone(),
+            file_path: file_path.clone(),
+            test_path: None,
+            params: None,
+            wildcard: false,
+        }
+    }
+}
+
+impl FromStr for CtsQuery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // N.B.: Parameter values are JSON, which may contain `:`, so only split up to them.
+        let mut levels = s.splitn(4, ':');
+        let suite = levels.next().unwrap();
+        let Some(file_path) = levels.next() else {
+            return Err(format!("expected a suite followed by `:`, got {s:?}"));
+        };
+        let test_path = levels.next();
+        let params = levels.next();
+        if suite.is_empty() || suite.contains(['*', ',']) {
+            return Err(format!("invalid suite name {suite:?}"));
+        }
+
+        /// Splits a wildcard `*` ending `level` off, returning whether there was one.
+        fn strip_wildcard(level: &str, sep: char) -> Result<(&str, bool), String> {
+            match level.strip_suffix('*') {
+                Some("") => Ok(("", true)),
+                Some(rest) => rest
+                    .strip_suffix(sep)
+                    .map(|rest| (rest, true))
+                    .ok_or_else(|| format!("expected `{sep}` before `*` in {level:?}")),
+                None => Ok((level, false)),
+            }
+        }
+        fn segments(level: &str) -> Result<Vec<String>, String> {
+            if level.is_empty() {
+                return Ok(Vec::new());
+            }
+            let segments = level.split(',').map(ToOwned::to_owned).collect::<Vec<_>>();
+            if segments
+                .iter()
+                .any(|segment| segment.is_empty() || segment.contains('*'))
+            {
+                return Err(format!("invalid path {level:?}"));
+            }
+            Ok(segments)
+        }
+
+        let (file_path, file_wildcard) = strip_wildcard(file_path, ',')?;
+        let (test_path, test_wildcard) = match test_path {
+            Some(test_path) => {
+                let (test_path, wildcard) = strip_wildcard(test_path, ',')?;
+                (Some(test_path), wildcard)
+            }
+            None => (None, false),
+        };
+        let (params, params_wildcard) = match params {
+            Some(params) => {
+                let (params, wildcard) = strip_wildcard(params, ';')?;
+                (Some(params), wildcard)
+            }
+            None => (None, false),
+        };
+        if (file_wildcard && test_path.is_some()) || (test_wildcard && params.is_some()) {
+            return Err(format!("wildcard `*` must be at the end of {s:?}"));
+        }
+        let wildcard = file_wildcard || test_wildcard || params_wildcard;
+
+        let file_path = segments(file_path)?;
+        let test_path = test_path.map(segments).transpose()?;
+        if test_path.is_some() && file_path.is_empty() {
+            return Err(format!(
+                "expected a file path before the test path in {s:?}"
+            ));
+        }
+        let params = params
+            .map(|params| {
+                if test_path.as_ref().map_or(true, Vec::is_empty) {
+                    return Err(format!("expected a test path before parameters in {s:?}"));
+                }
+                split_cts_params(params)
+                    .into_iter()
+                    .map(|param| match param.split_once('=') {
+                        Some((name, value)) if !name.is_empty() => {
+                            Ok((name.to_owned(), value.to_owned()))
+                        }
+                        _ => Err(format!(
+                            "expected a parameter like `name=value`, got {param:?}"
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+        if !wildcard && file_path.is_empty() {
+            return Err(format!(
+                "expected a file path or `*` after the suite in {s:?}"
+            ));
+        }
+        Ok(Self {
+            suite: suite.to_owned(),
+            file_path,
+            test_path,
+            params,
+            wildcard,
+        })
+    }
+}
+
+impl Display for CtsQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            suite,
+            file_path,
+            test_path,
+            params,
+            wildcard,
+        } = self;
+        let wildcard = |f: &mut Formatter<'_>, is_empty: bool, sep| match (wildcard, is_empty) {
+            (false, _) => Ok(()),
+            (true, true) => write!(f, "*"),
+            (true, false) => write!(f, "{sep}*"),
+        };
+        write!(f, "{suite}:{}", file_path.iter().join_with(','))?;
+        let Some(test_path) = test_path else {
+            return wildcard(f, file_path.is_empty(), ',');
+        };
+        write!(f, ":{}", test_path.iter().join_with(','))?;
+        let Some(params) = params else {
+            return wildcard(f, test_path.is_empty(), ',');
+        };
+        let joined = params
+            .iter()
+            .map(|(name, value)| lazy_format!("{name}={value}"))
+            .join_with(';');
+        write!(f, ":{joined}")?;
+        wildcard(f, params.is_empty(), ';')
+    }
+}
+
+/// Splits the parameters of a CTS subtest (i.e., `format="r8unorm";dims=2`) at each `;`, except
+/// for those in JSON strings.
+pub(crate) fn split_cts_params(params: &str) -> Vec<&str> {
+    if params.is_empty() {
+        return Vec::new();
+    }
+    let mut split = Vec::new();
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (idx, c) in params.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => {
+                split.push(&params[start..idx]);
+                start = idx + 1;
+            }
+            _ => (),
+        }
+    }
+    split.push(&params[start..]);
+    split
 }
 
 #[test]
 fn parse_test_path() {
     assert_eq!(
-        TestPath::from_fx_metadata_test(
+        TestPath::from_metadata_test(
             Path::new("testing/web-platform/mozilla/meta/blarg/cts.https.html.ini"),
             "cts.https.html?stuff=things"
         )
         .unwrap(),
         TestPath {
-            scope: TestScope::FirefoxPrivate,
+            browser: Browser::Firefox,
+            scope: TestScope::Private,
             path: Utf8Path::new("blarg/cts.https.html").into(),
             variant: Some("?stuff=things".into()),
         }
     );
 
     assert_eq!(
-        TestPath::from_fx_metadata_test(
+        TestPath::from_metadata_test(
             Path::new("testing/web-platform/meta/stuff/things/cts.https.html.ini"),
             "cts.https.html"
         )
         .unwrap(),
         TestPath {
+            browser: Browser::Firefox,
             scope: TestScope::Public,
             path: Utf8Path::new("stuff/things/cts.https.html").into(),
             variant: None,
@@ -685,8 +1024,8 @@ fn report_meta_match() {
     macro_rules! assert_test_matches_meta {
         ($test_run_path:expr, $rel_meta_path:expr, $test_section_header:expr) => {
             assert_eq!(
-                TestPath::from_execution_report($test_run_path).unwrap(),
-                TestPath::from_fx_metadata_test(Path::new($rel_meta_path), $test_section_header)
+                TestPath::from_execution_report(Browser::Firefox, $test_run_path).unwrap(),
+                TestPath::from_metadata_test(Path::new($rel_meta_path), $test_section_header)
                     .unwrap()
             )
         };
@@ -710,8 +1049,8 @@ fn report_meta_reject() {
     macro_rules! assert_test_rejects_meta {
         ($test_run_path:expr, $rel_meta_path:expr, $test_section_header:expr) => {
             assert_ne!(
-                TestPath::from_execution_report($test_run_path).unwrap(),
-                TestPath::from_fx_metadata_test(Path::new($rel_meta_path), $test_section_header)
+                TestPath::from_execution_report(Browser::Firefox, $test_run_path).unwrap(),
+                TestPath::from_metadata_test(Path::new($rel_meta_path), $test_section_header)
                     .unwrap()
             )
         };
@@ -735,7 +1074,7 @@ fn report_meta_reject() {
 #[test]
 fn runner_url_path() {
     assert_eq!(
-        TestPath::from_fx_metadata_test(
+        TestPath::from_metadata_test(
             Path::new("testing/web-platform/meta/blarg/stuff.https.html.ini"),
             "stuff.https.html"
         )
@@ -746,7 +1085,7 @@ fn runner_url_path() {
     );
 
     assert_eq!(
-        TestPath::from_fx_metadata_test(
+        TestPath::from_metadata_test(
             Path::new("testing/web-platform/meta/blarg/stuff.https.html.ini"),
             "stuff.https.html?win"
         )
@@ -757,7 +1096,7 @@ fn runner_url_path() {
     );
 
     assert_eq!(
-        TestPath::from_fx_metadata_test(
+        TestPath::from_metadata_test(
             Path::new("testing/web-platform/mozilla/meta/blarg/stuff.https.html.ini"),
             "stuff.https.html"
         )
@@ -768,7 +1107,7 @@ fn runner_url_path() {
     );
 
     assert_eq!(
-        TestPath::from_fx_metadata_test(
+        TestPath::from_metadata_test(
             Path::new("testing/web-platform/mozilla/meta/blarg/stuff.https.html.ini"),
             "stuff.https.html?win"
         )
@@ -778,3 +1117,120 @@ fn runner_url_path() {
         "_mozilla/blarg/stuff.https.html?win",
     );
 }
+
+#[test]
+fn chromium_test_path() {
+    let test = TestPath::from_execution_report(
+        Browser::Chromium,
+        "/wpt_internal/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+    )
+    .unwrap();
+    assert_eq!(
+        test,
+        TestPath::from_metadata_test(
+            Path::new("third_party/blink/web_tests/wpt_internal/webgpu/cts.https.html.ini"),
+            "cts.https.html?q=webgpu:api,operation,adapter:*"
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        test.runner_url_path().to_string(),
+        "wpt_internal/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*"
+    );
+    assert_eq!(
+        test.rel_metadata_path().to_string(),
+        Path::new("third_party/blink/web_tests/wpt_internal/webgpu/cts.https.html.ini")
+            .display()
+            .to_string()
+    );
+    assert_eq!(
+        test.rel_test_path().to_string(),
+        Path::new("third_party/blink/web_tests/wpt_internal/webgpu/cts.https.html")
+            .display()
+            .to_string()
+    );
+    assert_ne!(
+        test,
+        TestPath::from_execution_report(
+            Browser::Firefox,
+            "/_mozilla/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+        )
+        .unwrap()
+    );
+
+    // Firefox's private URL prefix isn't special in Chromium.
+    assert_eq!(
+        TestPath::from_execution_report(Browser::Chromium, "/_mozilla/blarg/cts.https.html")
+            .unwrap()
+            .scope,
+        TestScope::Public,
+    );
+    assert_eq!(
+        TestPath::from_metadata_test(
+            Path::new("third_party/blink/web_tests/external/wpt/blarg/cts.https.html.ini"),
+            "cts.https.html"
+        )
+        .unwrap(),
+        TestPath::from_execution_report(Browser::Chromium, "/blarg/cts.https.html").unwrap()
+    );
+}
+
+#[test]
+fn cts_queries() {
+    for query in [
+        "webgpu:*",
+        "webgpu:api,*",
+        "webgpu:api,operation,adapter:*",
+        "webgpu:api,operation,adapter:requestDevice:*",
+        "webgpu:api,operation,adapter:request,device,*",
+        r#"webgpu:api,operation,texture:copy:format="r8unorm";*"#,
+        r#"webgpu:api,operation,texture:copy:label="a:b;c";dims=2"#,
+        "webgpu:api,operation,adapter",
+        "webgpu:api,operation,adapter:requestDevice:",
+    ] {
+        assert_eq!(
+            query.parse::<CtsQuery>().unwrap().to_string(),
+            query,
+            "{query:?} should round-trip"
+        );
+    }
+
+    let query = r#"webgpu:api,operation,texture:copy:label="a:b;c";dims=2"#
+        .parse::<CtsQuery>()
+        .unwrap();
+    assert_eq!(
+        query,
+        CtsQuery {
+            suite: "webgpu".to_owned(),
+            file_path: vec![
+                "api".to_owned(),
+                "operation".to_owned(),
+                "texture".to_owned()
+            ],
+            test_path: Some(vec!["copy".to_owned()]),
+            params: Some(vec![
+                ("label".to_owned(), r#""a:b;c""#.to_owned()),
+                ("dims".to_owned(), "2".to_owned()),
+            ]),
+            wildcard: false,
+        }
+    );
+    assert_eq!(query.file_prefix(2).to_string(), "webgpu:api,operation,*");
+    assert_eq!(query.file().to_string(), "webgpu:api,operation,texture");
+
+    for query in [
+        "webgpu",
+        ":api:*",
+        "webgpu:",
+        "webgpu:api*",
+        "webgpu:api,*:foo:*",
+        "webgpu:api,,operation:*",
+        "webgpu:*:foo",
+        "webgpu:api:foo:bar",
+    ] {
+        assert!(
+            query.parse::<CtsQuery>().is_err(),
+            "{query:?} should be rejected"
+        );
+    }
+}
@@ -0,0 +1,422 @@
+//! Self-contained bundles of the metadata and reports relevant to a single test, so that bugs
+//! against this tool can be filed with inputs that reproduce them.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    error::Error,
+    explain::same_test,
+    metadata::{format_dir_file, format_file, BuildProfile, DirFile, File, Platform, RunInfoValue},
+    report::{ExecutionReport, RunInfo, TestExecutionEntry, TestExecutionResult},
+    shared::TestPath,
+};
+
+/// The directory that all files of a [`ReproSnapshot`] are archived under.
+pub const ARCHIVE_ROOT: &str = "moz-webgpu-cts-repro";
+
+/// The files of a snapshot taken by [`snapshot_repro`], by their path in the archive.
+#[derive(Clone, Debug, Default)]
+pub struct ReproSnapshot {
+    pub files: BTreeMap<PathBuf, String>,
+    pub num_metadata_files: usize,
+    pub num_reports: usize,
+}
+
+/// Takes a snapshot of the inputs relevant to `test`:
+///
+/// * The metadata file with `test`'s section, reduced to that section and the file's own
+///   properties, and `__dir__.ini` files of the directories it's in, from `meta_files_by_path` and
+///   `dir_files_by_path`, respectively. They're placed under `gecko/` at the same paths relative
+///   to `gecko_checkout`.
+/// * Each run in `exec_reports` that ran `test`, reduced to `test`'s entries, as its own report
+///   under `reports/`. One run with no entries for `test` is kept for each other configuration
+///   that was run, so that configurations covered by reports stay the same.
+///
+/// Reports are redacted: `run_info` is rewritten to just the keys that determine each run's
+/// configuration (so that `--platform-map` is no longer needed to reproduce it) and
+/// `run_info_keys`, and messages of `ERROR` outcomes are dropped.
+pub fn snapshot_repro<'a>(
+    gecko_checkout: &Path,
+    meta_files_by_path: &IndexMap<Arc<PathBuf>, File>,
+    dir_files_by_path: &IndexMap<Arc<PathBuf>, DirFile>,
+    exec_reports: impl IntoIterator<Item = Result<ExecutionReport<'a>, Error>>,
+    test: &TestPath<'_>,
+    run_info_keys: &BTreeSet<String>,
+) -> Result<ReproSnapshot, Error> {
+    let mut snapshot = ReproSnapshot::default();
+    let root = Path::new(ARCHIVE_ROOT);
+
+    let found = meta_files_by_path.iter().find_map(|(path, file)| {
+        let rel_path = path.strip_prefix(gecko_checkout).ok()?;
+        file.tests
+            .iter()
+            .find(|(name, _test)| {
+                TestPath::from_metadata_test(rel_path, &name.0)
+                    .map_or(false, |meta_path| same_test(&meta_path, test))
+            })
+            .map(|(name, test)| (rel_path, file, name, test))
+    });
+    if let Some((rel_path, file, name, found_test)) = found {
+        let reduced = File {
+            properties: file.properties.clone(),
+            tests: [(name.clone(), found_test.clone())].into_iter().collect(),
+        };
+        snapshot.files.insert(
+            root.join("gecko").join(rel_path),
+            format_file(&reduced).to_string(),
+        );
+        snapshot.num_metadata_files += 1;
+
+        for (dir_path, dir_file) in dir_files_by_path {
+            let Ok(dir_rel_path) = dir_path.strip_prefix(gecko_checkout) else {
+                continue;
+            };
+            let applies = dir_rel_path
+                .parent()
+                .map_or(false, |dir| rel_path.starts_with(dir));
+            if applies {
+                snapshot.files.insert(
+                    root.join("gecko").join(dir_rel_path),
+                    format_dir_file(dir_file).to_string(),
+                );
+                snapshot.num_metadata_files += 1;
+            }
+        }
+    }
+
+    let mut runs_with_test = Vec::new();
+    let mut runs_without_test = BTreeMap::new();
+    for exec_report in exec_reports {
+        let ExecutionReport {
+            run_info,
+            entries,
+            unrecognized: _,
+        } = exec_report?;
+        let entries = entries
+            .into_iter()
+            .filter(|entry| {
+                TestPath::from_execution_report(test.browser, &entry.test_name)
+                    .map_or(false, |reported| same_test(&reported, test))
+            })
+            .collect::<Vec<_>>();
+        let config = (
+            run_info.platform,
+            run_info.build_profile,
+            run_info.subsuite.clone(),
+        );
+        if entries.is_empty() {
+            runs_without_test.entry(config).or_insert(run_info);
+        } else {
+            runs_with_test.push((config, redacted_report(&run_info, &entries, run_info_keys)));
+        }
+    }
+    let configs_with_test = runs_with_test
+        .iter()
+        .map(|(config, _report)| config.clone())
+        .collect::<BTreeSet<_>>();
+    let reports = runs_with_test
+        .into_iter()
+        .map(|(_config, report)| report)
+        .chain(
+            runs_without_test
+                .into_iter()
+                .filter(|(config, _run_info)| !configs_with_test.contains(config))
+                .map(|(_config, run_info)| redacted_report(&run_info, &[], run_info_keys)),
+        );
+    let mut report_args = String::new();
+    for (idx, report) in reports.enumerate() {
+        let rel_path = format!("reports/wptreport-{:03}.json", idx + 1);
+        snapshot.files.insert(
+            root.join(&rel_path),
+            serde_json::to_string_pretty(&report).unwrap(),
+        );
+        report_args += &format!(" --report {rel_path}");
+        snapshot.num_reports += 1;
+    }
+
+    snapshot.files.insert(
+        root.join("README.txt"),
+        format!(
+            concat!(
+                "Inputs for reproducing the handling of `{}` by moz-webgpu-cts.\n",
+                "\n",
+                "From this directory, run (i.e.):\n",
+                "\n",
+                "    moz-webgpu-cts --gecko-checkout gecko explain '/{}'{}\n",
+            ),
+            test.test_name(),
+            test.runner_url_path(),
+            report_args,
+        ),
+    );
+
+    Ok(snapshot)
+}
+
+/// Renders `run_info` and `entries` as a `wptreport.json` file; see [`snapshot_repro`].
+fn redacted_report(
+    run_info: &RunInfo,
+    entries: &[TestExecutionEntry<'_>],
+    run_info_keys: &BTreeSet<String>,
+) -> Value {
+    let RunInfo {
+        platform,
+        build_profile,
+        subsuite,
+        properties,
+    } = run_info;
+
+    let mut run_info = Map::new();
+    run_info.insert("os".to_owned(), json!(platform.os_name()));
+    run_info.insert("processor".to_owned(), json!("x86_64"));
+    run_info.insert(
+        "win11_2009".to_owned(),
+        json!(*platform == Platform::Windows),
+    );
+    run_info.insert(
+        "debug".to_owned(),
+        json!(*build_profile == BuildProfile::Debug),
+    );
+    if let Some(subsuite) = subsuite {
+        run_info.insert("subsuite".to_owned(), json!(subsuite));
+    }
+    let kept = properties
+        .0
+        .iter()
+        .filter(|(key, _value)| run_info_keys.contains(*key));
+    for (key, value) in kept {
+        let value = match value {
+            RunInfoValue::Bool(value) => json!(value),
+            RunInfoValue::String(value) => json!(value),
+        };
+        run_info.insert(key.clone(), value);
+    }
+
+    let results = entries
+        .iter()
+        .map(|entry| {
+            let TestExecutionEntry {
+                test_name,
+                subsuite,
+                message: _,
+                result,
+            } = entry;
+            let (status, subtests) = match result {
+                TestExecutionResult::Complete { outcome, subtests } => {
+                    (outcome.to_string(), subtests)
+                }
+                TestExecutionResult::JobMaybeTimedOut { subtests } => (String::new(), subtests),
+            };
+            let subtests = subtests
+                .iter()
+                .map(|subtest| {
+                    json!({
+                        "name": subtest.subtest_name,
+                        "status": subtest.outcome.to_string(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut result = json!({
+                "test": test_name,
+                "status": status,
+                "subtests": subtests,
+            });
+            if let Some(subsuite) = subsuite {
+                result["subsuite"] = json!(subsuite);
+            }
+            result
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "run_info": run_info,
+        "results": results,
+    })
+}
+
+impl ReproSnapshot {
+    /// Archives the files of this snapshot as a gzipped tarball. Entries have fixed metadata, so
+    /// that the same snapshot always produces the same archive.
+    pub fn to_tar_gz(&self) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (path, contents) in &self.files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len().try_into().unwrap());
+            header.set_mode(0o644);
+            header.set_mtime(0);
+            header.set_cksum();
+            // N.B.: Writing to a `Vec` can't fail.
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        let mut encoder = builder.into_inner().unwrap();
+        encoder.flush().unwrap();
+        encoder.finish().unwrap()
+    }
+}
+
+#[test]
+fn snapshot_of_single_test() {
+    use std::io::Read;
+
+    use crate::{report::ParseOptions, shared::Browser};
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_dir = gecko_checkout.join("testing/web-platform/mozilla/meta/webgpu");
+    let parse = |contents: &str| {
+        use whippit::reexport::chumsky::Parser as _;
+        File::parser().parse(contents).into_result().unwrap()
+    };
+    let meta_files_by_path = [(
+        Arc::new(meta_dir.join("cts/webgpu/cts.https.html.ini")),
+        parse(concat!(
+            "[cts.https.html?q=webgpu:api,operation,adapter:*]\n",
+            "  [:]\n",
+            "    expected: FAIL\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,operation,buffers:*]\n",
+            "  expected: TIMEOUT\n",
+        )),
+    )]
+    .into_iter()
+    .collect();
+    let parse_dir = |contents: &str| {
+        use whippit::reexport::chumsky::Parser as _;
+        DirFile::parser().parse(contents).into_result().unwrap()
+    };
+    let dir_files_by_path = [
+        (
+            Arc::new(meta_dir.join("cts/__dir__.ini")),
+            parse_dir("tags: [webgpu]\n"),
+        ),
+        (
+            Arc::new(meta_dir.join("other/__dir__.ini")),
+            parse_dir("disabled: true\n"),
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    let report = |os: &str, test: &str| {
+        format!(
+            r#"{{
+                "run_info": {{
+                    "os": "{os}",
+                    "processor": "x86_64",
+                    "win11_2009": true,
+                    "debug": false,
+                    "fission": true,
+                    "revision": "0123456789abcdef"
+                }},
+                "results": [
+                    {{
+                        "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,{test}:*",
+                        "status": "ERROR",
+                        "message": "failed at /home/someone/gecko/dom/webgpu/Device.cpp",
+                        "subtests": [{{ "name": ":", "status": "FAIL" }}]
+                    }}
+                ]
+            }}"#
+        )
+    };
+    let reports = [
+        report("win", "adapter"),
+        report("win", "buffers"),
+        report("linux", "buffers"),
+    ];
+    let runs = reports.iter().map(|contents| {
+        ExecutionReport::from_json(
+            Path::new("wptreport.json"),
+            contents,
+            &ParseOptions::default(),
+        )
+    });
+
+    let test = TestPath::from_execution_report(
+        Browser::Firefox,
+        "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*",
+    )
+    .unwrap();
+    let snapshot = snapshot_repro(
+        gecko_checkout,
+        &meta_files_by_path,
+        &dir_files_by_path,
+        runs,
+        &test,
+        &["fission".to_owned()].into_iter().collect(),
+    )
+    .unwrap();
+    assert_eq!(snapshot.num_metadata_files, 2);
+    assert_eq!(snapshot.num_reports, 2);
+
+    let mut archived = BTreeMap::new();
+    let archive = snapshot.to_tar_gz();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(&archive[..]));
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents).unwrap();
+        archived.insert(entry.path().unwrap().into_owned(), contents);
+    }
+    assert_eq!(archived, snapshot.files);
+
+    let rendered = snapshot
+        .files
+        .iter()
+        .filter(|(path, _contents)| !path.ends_with("README.txt"))
+        .map(|(path, contents)| format!("--- {}\n{contents}\n", path.display()))
+        .collect::<String>();
+    insta::assert_snapshot!(rendered, @r###"
+    --- moz-webgpu-cts-repro/gecko/testing/web-platform/mozilla/meta/webgpu/cts/__dir__.ini
+    tags: [webgpu]
+
+    --- moz-webgpu-cts-repro/gecko/testing/web-platform/mozilla/meta/webgpu/cts/webgpu/cts.https.html.ini
+    [cts.https.html?q=webgpu:api,operation,adapter:*]
+      [:]
+        expected: FAIL
+
+    --- moz-webgpu-cts-repro/reports/wptreport-001.json
+    {
+      "results": [
+        {
+          "status": "ERROR",
+          "subtests": [
+            {
+              "name": ":",
+              "status": "FAIL"
+            }
+          ],
+          "test": "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*"
+        }
+      ],
+      "run_info": {
+        "debug": false,
+        "fission": true,
+        "os": "win",
+        "processor": "x86_64",
+        "win11_2009": true
+      }
+    }
+    --- moz-webgpu-cts-repro/reports/wptreport-002.json
+    {
+      "results": [],
+      "run_info": {
+        "debug": false,
+        "fission": true,
+        "os": "linux",
+        "processor": "x86_64",
+        "win11_2009": false
+      }
+    }
+    "###);
+}
@@ -0,0 +1,442 @@
+//! Statistics about expectations in metadata, for tracking its health over time.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display, Formatter},
+    hash::Hash,
+};
+
+use enumset::{EnumSet, EnumSetType};
+use strum::IntoEnumIterator;
+
+use crate::{
+    metadata::{
+        BuildProfile, File, Platform, Subtest, SubtestOutcome, Test, TestOutcome, TestProps,
+    },
+    shared::Expected,
+};
+
+/// Counts of expectations by how many outcomes they allow, in a single configuration. More than
+/// one outcome is expected where outcomes are intermittent, so this is a proxy for how much flaky
+/// behavior has accumulated in metadata.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ExpectedOutcomesHistogram {
+    pub one: usize,
+    pub two: usize,
+    pub three_or_more: usize,
+}
+
+impl ExpectedOutcomesHistogram {
+    fn record<Out>(&mut self, expected: Expected<Out>)
+    where
+        Out: EnumSetType,
+    {
+        let Self {
+            one,
+            two,
+            three_or_more,
+        } = self;
+        let count = match expected.len().get() {
+            1 => one,
+            2 => two,
+            _ => three_or_more,
+        };
+        *count += 1;
+    }
+
+    pub fn total(&self) -> usize {
+        let Self {
+            one,
+            two,
+            three_or_more,
+        } = self;
+        one + two + three_or_more
+    }
+}
+
+/// Counts of entries (i.e., tests or subtests) by the outcome they're expected to have, in a
+/// single configuration.
+#[derive(Clone, Debug)]
+pub struct OutcomeCounts<Out>
+where
+    Out: EnumSetType + Hash,
+{
+    /// Entries expected to always have one outcome, by that outcome. Entries expected to have
+    /// the default outcome (i.e., `PASS`) are counted under it.
+    pub permanent: HashMap<Out, usize>,
+    /// Entries expected to have more than one outcome.
+    pub intermittent: usize,
+}
+
+impl<Out> Default for OutcomeCounts<Out>
+where
+    Out: EnumSetType + Hash,
+{
+    fn default() -> Self {
+        Self {
+            permanent: Default::default(),
+            intermittent: Default::default(),
+        }
+    }
+}
+
+impl<Out> OutcomeCounts<Out>
+where
+    Out: EnumSetType + Hash,
+{
+    fn record(&mut self, expected: Expected<Out>) {
+        let Self {
+            permanent,
+            intermittent,
+        } = self;
+        match expected.as_permanent() {
+            Some(outcome) => *permanent.entry(outcome).or_default() += 1,
+            None => *intermittent += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        let Self {
+            permanent,
+            intermittent,
+        } = self;
+        permanent.values().sum::<usize>() + intermittent
+    }
+}
+
+/// Statistics about a set of metadata files. See [`Stats::from_files`].
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    /// How many outcomes subtests are expected to have, by configuration.
+    pub subtest_outcome_counts: BTreeMap<(Platform, BuildProfile), ExpectedOutcomesHistogram>,
+    /// Tests by the outcome they're expected to have, by configuration.
+    pub tests_by_outcome: BTreeMap<(Platform, BuildProfile), OutcomeCounts<TestOutcome>>,
+    /// Like `tests_by_outcome`, but for subtests.
+    pub subtests_by_outcome: BTreeMap<(Platform, BuildProfile), OutcomeCounts<SubtestOutcome>>,
+}
+
+impl Stats {
+    /// Gathers statistics about the tests in `files`. Disabled tests and subtests are not
+    /// counted, since their expectations are never checked. Neither are tests and subtests
+    /// without metadata, which are expected to pass, so pass rates are only of those with
+    /// metadata.
+    pub fn from_files<'a>(files: impl IntoIterator<Item = &'a File>) -> Self {
+        let mut stats = Self::default();
+        let tests = files
+            .into_iter()
+            .flat_map(|file| file.tests.values())
+            .collect::<Vec<_>>();
+        for Test { properties, .. } in &tests {
+            for platform in Platform::iter() {
+                for build_profile in BuildProfile::iter() {
+                    if properties.is_disabled_on(platform, build_profile) {
+                        continue;
+                    }
+                    stats
+                        .tests_by_outcome
+                        .entry((platform, build_profile))
+                        .or_default()
+                        .record(expected_on(properties, platform, build_profile));
+                }
+            }
+        }
+        let subtests = tests.into_iter().flat_map(
+            |Test {
+                 properties,
+                 subtests,
+             }| { subtests.values().map(move |subtest| (properties, subtest)) },
+        );
+        for (test_properties, Subtest { properties }) in subtests {
+            for platform in Platform::iter() {
+                for build_profile in BuildProfile::iter() {
+                    if test_properties.is_disabled_on(platform, build_profile)
+                        || properties.is_disabled_on(platform, build_profile)
+                    {
+                        continue;
+                    }
+                    let expected = expected_on(properties, platform, build_profile);
+                    stats
+                        .subtest_outcome_counts
+                        .entry((platform, build_profile))
+                        .or_default()
+                        .record(expected);
+                    stats
+                        .subtests_by_outcome
+                        .entry((platform, build_profile))
+                        .or_default()
+                        .record(expected);
+                }
+            }
+        }
+        stats
+    }
+}
+
+fn expected_on<Out>(
+    properties: &TestProps<Out>,
+    platform: Platform,
+    build_profile: BuildProfile,
+) -> Expected<Out>
+where
+    Out: Default + EnumSetType,
+{
+    properties
+        .expected
+        .as_ref()
+        .map_or_else(Expected::default, |expected| {
+            expected.get(platform, build_profile)
+        })
+}
+
+/// Counts of entries (i.e., tests and subtests) in a single metadata file that need attention.
+/// An entry is counted once in each category it falls into in any configuration.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FileHealth {
+    /// Entries expected to permanently have an outcome other than the default (i.e., `FAIL`).
+    pub failing: usize,
+    /// Entries expected to have more than one outcome.
+    pub intermittent: usize,
+    pub disabled: usize,
+}
+
+impl FileHealth {
+    pub fn from_file(file: &File) -> Self {
+        fn record<Out>(health: &mut FileHealth, properties: &TestProps<Out>)
+        where
+            Out: Default + EnumSetType,
+        {
+            let FileHealth {
+                failing,
+                intermittent,
+                disabled,
+            } = health;
+            if properties.is_disabled.is_some() {
+                *disabled += 1;
+            }
+            let Some(expected) = &properties.expected else {
+                return;
+            };
+            let (mut is_failing, mut is_intermittent) = (false, false);
+            for (_config, expected) in expected.iter() {
+                match expected.as_permanent() {
+                    Some(outcome) => is_failing |= outcome != Out::default(),
+                    None => is_intermittent = true,
+                }
+            }
+            *failing += usize::from(is_failing);
+            *intermittent += usize::from(is_intermittent);
+        }
+
+        let mut health = Self::default();
+        for Test {
+            properties,
+            subtests,
+        } in file.tests.values()
+        {
+            record(&mut health, properties);
+            for Subtest { properties } in subtests.values() {
+                record(&mut health, properties);
+            }
+        }
+        health
+    }
+
+    pub fn total(&self) -> usize {
+        let Self {
+            failing,
+            intermittent,
+            disabled,
+        } = self;
+        failing + intermittent + disabled
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            subtest_outcome_counts,
+            tests_by_outcome,
+            subtests_by_outcome,
+        } = self;
+
+        fn outcome_table<Out>(
+            f: &mut Formatter<'_>,
+            title: &str,
+            counts_by_config: &BTreeMap<(Platform, BuildProfile), OutcomeCounts<Out>>,
+        ) -> fmt::Result
+        where
+            Out: Display + EnumSetType + Hash,
+        {
+            writeln!(f, "{title}")?;
+            write!(f, "{:<20}", "configuration")?;
+            for outcome in EnumSet::<Out>::all() {
+                write!(f, " {:>17}", outcome.to_string())?;
+            }
+            writeln!(f, " {:>17}", "intermittent")?;
+            for ((platform, build_profile), counts) in counts_by_config {
+                let OutcomeCounts {
+                    permanent,
+                    intermittent,
+                } = counts;
+                let total = counts.total();
+                let column = |count: usize| {
+                    let percentage = count as f64 * 100.0 / total as f64;
+                    format!("{count} ({percentage:.1}%)")
+                };
+                write!(f, "{:<20}", format!("{platform:?} {build_profile:?}"))?;
+                for outcome in EnumSet::<Out>::all() {
+                    let count = permanent.get(&outcome).copied().unwrap_or_default();
+                    write!(f, " {:>17}", column(count))?;
+                }
+                writeln!(f, " {:>17}", column(*intermittent))?;
+            }
+            Ok(())
+        }
+
+        outcome_table(f, "Tests by expected outcome:", tests_by_outcome)?;
+        writeln!(f)?;
+        outcome_table(f, "Subtests by expected outcome:", subtests_by_outcome)?;
+        writeln!(f)?;
+
+        writeln!(f, "Subtests by number of expected outcomes:")?;
+        writeln!(
+            f,
+            "{:<20} {:>17} {:>17} {:>17}",
+            "configuration", "1", "2", "3+"
+        )?;
+        for ((platform, build_profile), histogram) in subtest_outcome_counts {
+            let ExpectedOutcomesHistogram {
+                one,
+                two,
+                three_or_more,
+            } = histogram;
+            let total = histogram.total();
+            let column = |count: &usize| {
+                let percentage = *count as f64 * 100.0 / total as f64;
+                format!("{count} ({percentage:.1}%)")
+            };
+            writeln!(
+                f,
+                "{:<20} {:>17} {:>17} {:>17}",
+                format!("{platform:?} {build_profile:?}"),
+                column(one),
+                column(two),
+                column(three_or_more),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn outcome_count_histograms() {
+    use std::path::Path;
+
+    use crate::checkout::parse_metadata;
+
+    let (_path, file) = parse_metadata(
+        Path::new("testing/web-platform/mozilla/meta/webgpu/cts.https.html.ini").to_owned(),
+        concat!(
+            "[cts.https.html?q=webgpu:api,a:*]\n",
+            "  [:permanent]\n",
+            "    expected: FAIL\n",
+            "\n",
+            "  [:flaky_on_windows]\n",
+            "    expected:\n",
+            "      if os == \"win\": [PASS, FAIL]\n",
+            "\n",
+            "  [:very_flaky_in_debug]\n",
+            "    expected:\n",
+            "      if debug: [PASS, FAIL, TIMEOUT]\n",
+            "\n",
+            "  [:disabled]\n",
+            "    disabled: true\n",
+            "    expected: [PASS, FAIL]\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,disabled:*]\n",
+            "  disabled: true\n",
+            "  [:flaky]\n",
+            "    expected: [PASS, FAIL]\n",
+        )
+        .to_owned(),
+    )
+    .unwrap();
+
+    let stats = Stats::from_files([&file]);
+    insta::assert_snapshot!(stats, @r###"
+    Tests by expected outcome:
+    configuration                       OK           TIMEOUT             CRASH             ERROR              SKIP      intermittent
+    Windows Debug               1 (100.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+    Windows Optimized           1 (100.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+    Linux Debug                 1 (100.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+    Linux Optimized             1 (100.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+    MacOs Debug                 1 (100.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+    MacOs Optimized             1 (100.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+    Android Debug               1 (100.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+    Android Optimized           1 (100.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+
+    Subtests by expected outcome:
+    configuration                     PASS              FAIL           TIMEOUT             CRASH            NOTRUN      intermittent
+    Windows Debug                 0 (0.0%)         1 (33.3%)          0 (0.0%)          0 (0.0%)          0 (0.0%)         2 (66.7%)
+    Windows Optimized            1 (33.3%)         1 (33.3%)          0 (0.0%)          0 (0.0%)          0 (0.0%)         1 (33.3%)
+    Linux Debug                  1 (33.3%)         1 (33.3%)          0 (0.0%)          0 (0.0%)          0 (0.0%)         1 (33.3%)
+    Linux Optimized              2 (66.7%)         1 (33.3%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+    MacOs Debug                  1 (33.3%)         1 (33.3%)          0 (0.0%)          0 (0.0%)          0 (0.0%)         1 (33.3%)
+    MacOs Optimized              2 (66.7%)         1 (33.3%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+    Android Debug                1 (33.3%)         1 (33.3%)          0 (0.0%)          0 (0.0%)          0 (0.0%)         1 (33.3%)
+    Android Optimized            2 (66.7%)         1 (33.3%)          0 (0.0%)          0 (0.0%)          0 (0.0%)          0 (0.0%)
+
+    Subtests by number of expected outcomes:
+    configuration                        1                 2                3+
+    Windows Debug                1 (33.3%)         1 (33.3%)         1 (33.3%)
+    Windows Optimized            2 (66.7%)         1 (33.3%)          0 (0.0%)
+    Linux Debug                  2 (66.7%)          0 (0.0%)         1 (33.3%)
+    Linux Optimized             3 (100.0%)          0 (0.0%)          0 (0.0%)
+    MacOs Debug                  2 (66.7%)          0 (0.0%)         1 (33.3%)
+    MacOs Optimized             3 (100.0%)          0 (0.0%)          0 (0.0%)
+    Android Debug                2 (66.7%)          0 (0.0%)         1 (33.3%)
+    Android Optimized           3 (100.0%)          0 (0.0%)          0 (0.0%)
+    "###);
+}
+
+#[test]
+fn file_health() {
+    use std::path::Path;
+
+    use crate::checkout::parse_metadata;
+
+    let (_path, file) = parse_metadata(
+        Path::new("testing/web-platform/mozilla/meta/webgpu/cts.https.html.ini").to_owned(),
+        concat!(
+            "[cts.https.html?q=webgpu:api,a:*]\n",
+            "  expected:\n",
+            "    if os == \"win\": TIMEOUT\n",
+            "  [:failing]\n",
+            "    expected: FAIL\n",
+            "\n",
+            "  [:flaky_and_failing]\n",
+            "    expected:\n",
+            "      if os == \"win\": [PASS, FAIL]\n",
+            "      FAIL\n",
+            "\n",
+            "  [:passing]\n",
+            "    expected: PASS\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,b:*]\n",
+            "  disabled: true\n",
+        )
+        .to_owned(),
+    )
+    .unwrap();
+
+    let health = FileHealth::from_file(&file);
+    assert_eq!(
+        health,
+        FileHealth {
+            failing: 3,
+            intermittent: 1,
+            disabled: 1,
+        }
+    );
+    assert_eq!(health.total(), 5);
+}
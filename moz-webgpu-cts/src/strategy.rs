@@ -0,0 +1,416 @@
+//! Policies for resolving differences between expected outcomes in metadata and those reported,
+//! used by [`reconcile`].
+//!
+//! The [`ReportProcessingPreset`]s offered on the command line are implemented here. Embedders can
+//! supply their own policy by implementing [`ReconciliationStrategy`], and setting it as
+//! [`ReconcileOptions::strategy`].
+//!
+//! [`reconcile`]: crate::process_reports::reconcile
+//! [`ReportProcessingPreset`]: crate::process_reports::ReportProcessingPreset
+//! [`ReconcileOptions::strategy`]: crate::process_reports::ReconcileOptions::strategy
+
+use std::{fmt::Debug, sync::Arc};
+
+use enumset::{enum_set, EnumSet, EnumSetType};
+
+use crate::{
+    metadata::{BuildProfile, Platform, SubtestOutcome, TestOutcome},
+    shared::Expected,
+};
+
+/// The outcomes of a test or subtest in a single configuration, to be resolved by a
+/// [`ReconciliationStrategy`].
+#[derive(Clone, Copy, Debug)]
+pub struct ConfigOutcomes<Out>
+where
+    Out: EnumSetType,
+{
+    pub platform: Platform,
+    pub build_profile: BuildProfile,
+    /// Expected outcomes in current metadata.
+    pub current: Expected<Out>,
+    /// Outcomes observed in reports, or `None` if the test or subtest was not run in this
+    /// configuration.
+    pub reported: Option<Expected<Out>>,
+    /// Whether any report was for this configuration, regardless of whether it ran the test.
+    pub covered: bool,
+}
+
+/// A policy for resolving differences between expected outcomes in metadata and those reported.
+///
+/// Strategies are only consulted for tests and subtests with expectations in metadata; others
+/// always take reported outcomes.
+pub trait ReconciliationStrategy: Debug + Send + Sync {
+    /// Returns the new expected outcomes of a test in a single configuration.
+    fn resolve_test(&self, outcomes: ConfigOutcomes<TestOutcome>) -> Expected<TestOutcome>;
+
+    /// Like [`Self::resolve_test`], but for subtests.
+    fn resolve_subtest(&self, outcomes: ConfigOutcomes<SubtestOutcome>)
+        -> Expected<SubtestOutcome>;
+
+    /// Whether tests with metadata that were not found in any report are kept, instead of being
+    /// removed. Defaults to `false`.
+    fn keeps_unreported_tests(&self) -> bool {
+        false
+    }
+}
+
+/// Dispatches to the method of a [`ReconciliationStrategy`] for an outcome type.
+pub(crate) trait ResolveWith: EnumSetType {
+    fn resolve_with(
+        strategy: &dyn ReconciliationStrategy,
+        outcomes: ConfigOutcomes<Self>,
+    ) -> Expected<Self>;
+}
+
+impl ResolveWith for TestOutcome {
+    fn resolve_with(
+        strategy: &dyn ReconciliationStrategy,
+        outcomes: ConfigOutcomes<Self>,
+    ) -> Expected<Self> {
+        strategy.resolve_test(outcomes)
+    }
+}
+
+impl ResolveWith for SubtestOutcome {
+    fn resolve_with(
+        strategy: &dyn ReconciliationStrategy,
+        outcomes: ConfigOutcomes<Self>,
+    ) -> Expected<Self> {
+        strategy.resolve_subtest(outcomes)
+    }
+}
+
+/// Replace expected outcomes with reported ones where they contradict, i.e., where an outcome
+/// was reported that isn't expected. Suited to processing reports from a new build of Firefox.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResetContradictory;
+
+impl ResetContradictory {
+    fn resolve<Out>(outcomes: ConfigOutcomes<Out>) -> Expected<Out>
+    where
+        Out: Debug + Default + EnumSetType,
+    {
+        let ConfigOutcomes {
+            current, reported, ..
+        } = outcomes;
+        reported
+            .filter(|reported| !current.is_superset(reported))
+            .unwrap_or(current)
+    }
+}
+
+impl ReconciliationStrategy for ResetContradictory {
+    fn resolve_test(&self, outcomes: ConfigOutcomes<TestOutcome>) -> Expected<TestOutcome> {
+        Self::resolve(outcomes)
+    }
+
+    fn resolve_subtest(
+        &self,
+        outcomes: ConfigOutcomes<SubtestOutcome>,
+    ) -> Expected<SubtestOutcome> {
+        Self::resolve(outcomes)
+    }
+}
+
+/// Add reported outcomes to expected ones. Suited to processing further reports from the same
+/// build of Firefox, to discover intermittent outcomes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Merge;
+
+impl Merge {
+    fn resolve<Out>(outcomes: ConfigOutcomes<Out>) -> Expected<Out>
+    where
+        Out: EnumSetType,
+    {
+        let ConfigOutcomes {
+            current, reported, ..
+        } = outcomes;
+        match reported {
+            Some(reported) => current | reported,
+            None => current,
+        }
+    }
+}
+
+impl ReconciliationStrategy for Merge {
+    fn resolve_test(&self, outcomes: ConfigOutcomes<TestOutcome>) -> Expected<TestOutcome> {
+        Self::resolve(outcomes)
+    }
+
+    fn resolve_subtest(
+        &self,
+        outcomes: ConfigOutcomes<SubtestOutcome>,
+    ) -> Expected<SubtestOutcome> {
+        Self::resolve(outcomes)
+    }
+
+    fn keeps_unreported_tests(&self) -> bool {
+        true
+    }
+}
+
+/// Replace expected outcomes with reported ones, discarding current expectations entirely.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResetAll;
+
+impl ReconciliationStrategy for ResetAll {
+    fn resolve_test(&self, outcomes: ConfigOutcomes<TestOutcome>) -> Expected<TestOutcome> {
+        outcomes.reported.unwrap_or_default()
+    }
+
+    fn resolve_subtest(
+        &self,
+        outcomes: ConfigOutcomes<SubtestOutcome>,
+    ) -> Expected<SubtestOutcome> {
+        outcomes.reported.unwrap_or_default()
+    }
+}
+
+/// Replace expected outcomes with reported ones in configurations that reports were provided for,
+/// and leave other configurations alone.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PruneUnobserved;
+
+impl PruneUnobserved {
+    fn resolve<Out>(outcomes: ConfigOutcomes<Out>) -> Expected<Out>
+    where
+        Out: Default + EnumSetType,
+    {
+        let ConfigOutcomes {
+            current,
+            reported,
+            covered,
+            ..
+        } = outcomes;
+        if covered {
+            reported.unwrap_or_default()
+        } else {
+            current
+        }
+    }
+}
+
+impl ReconciliationStrategy for PruneUnobserved {
+    fn resolve_test(&self, outcomes: ConfigOutcomes<TestOutcome>) -> Expected<TestOutcome> {
+        Self::resolve(outcomes)
+    }
+
+    fn resolve_subtest(
+        &self,
+        outcomes: ConfigOutcomes<SubtestOutcome>,
+    ) -> Expected<SubtestOutcome> {
+        Self::resolve(outcomes)
+    }
+}
+
+/// Replace expected outcomes with reported ones only where either has an outcome of running out
+/// of time (`TIMEOUT`, or `NOTRUN` for subtests), and leave other expectations alone. Suited to
+/// refreshing expectations after a change to the runner's timeout budget, without touching
+/// curated `PASS` and `FAIL` expectations.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResetTimeouts;
+
+impl ResetTimeouts {
+    /// Outcomes of tests that [`ResetTimeouts`] considers.
+    pub const TEST_OUTCOMES: EnumSet<TestOutcome> = enum_set!(TestOutcome::Timeout);
+    /// Outcomes of subtests that [`ResetTimeouts`] considers.
+    pub const SUBTEST_OUTCOMES: EnumSet<SubtestOutcome> =
+        enum_set!(SubtestOutcome::Timeout | SubtestOutcome::NotRun);
+
+    /// Whether `current` or `reported` has one of `timeouts`, in which case `reported` replaces
+    /// `current`.
+    pub fn applies<Out>(
+        timeouts: EnumSet<Out>,
+        current: Expected<Out>,
+        reported: Expected<Out>,
+    ) -> bool
+    where
+        Out: EnumSetType,
+    {
+        !current.is_disjoint(timeouts) || !reported.is_disjoint(timeouts)
+    }
+
+    fn resolve<Out>(outcomes: ConfigOutcomes<Out>, timeouts: EnumSet<Out>) -> Expected<Out>
+    where
+        Out: EnumSetType,
+    {
+        let ConfigOutcomes {
+            current, reported, ..
+        } = outcomes;
+        reported
+            .filter(|reported| Self::applies(timeouts, current, *reported))
+            .unwrap_or(current)
+    }
+}
+
+impl ReconciliationStrategy for ResetTimeouts {
+    fn resolve_test(&self, outcomes: ConfigOutcomes<TestOutcome>) -> Expected<TestOutcome> {
+        Self::resolve(outcomes, Self::TEST_OUTCOMES)
+    }
+
+    fn resolve_subtest(
+        &self,
+        outcomes: ConfigOutcomes<SubtestOutcome>,
+    ) -> Expected<SubtestOutcome> {
+        Self::resolve(outcomes, Self::SUBTEST_OUTCOMES)
+    }
+}
+
+/// Remove outcomes of intermittent expectations that weren't reported, collapsing them to
+/// permanent ones where only one outcome remains, and leave other expectations alone. The inverse
+/// of [`Merge`], suited to cleaning up after fixes land.
+///
+/// Expectations are left alone where none of their outcomes were reported, since there is nothing
+/// to prune them to.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PruneStaleIntermittents;
+
+impl PruneStaleIntermittents {
+    /// Returns `current` without outcomes missing from `reported`, if it's intermittent and any
+    /// of its outcomes were reported, or `current` otherwise.
+    pub fn prune<Out>(current: Expected<Out>, reported: Expected<Out>) -> Expected<Out>
+    where
+        Out: EnumSetType,
+    {
+        if current.is_permanent() {
+            return current;
+        }
+        Expected::new(current.inner() & reported.inner()).unwrap_or(current)
+    }
+}
+
+impl ReconciliationStrategy for PruneStaleIntermittents {
+    fn resolve_test(&self, outcomes: ConfigOutcomes<TestOutcome>) -> Expected<TestOutcome> {
+        let ConfigOutcomes {
+            current, reported, ..
+        } = outcomes;
+        reported.map_or(current, |reported| Self::prune(current, reported))
+    }
+
+    fn resolve_subtest(
+        &self,
+        outcomes: ConfigOutcomes<SubtestOutcome>,
+    ) -> Expected<SubtestOutcome> {
+        let ConfigOutcomes {
+            current, reported, ..
+        } = outcomes;
+        reported.map_or(current, |reported| Self::prune(current, reported))
+    }
+
+    fn keeps_unreported_tests(&self) -> bool {
+        true
+    }
+}
+
+/// Resolves test-level expectations with one strategy, and subtest-level expectations with
+/// another, i.e., to merge harness statuses while resetting contradictory CTS case results.
+#[derive(Clone, Debug)]
+pub struct PerLevel {
+    pub tests: Arc<dyn ReconciliationStrategy>,
+    pub subtests: Arc<dyn ReconciliationStrategy>,
+}
+
+impl ReconciliationStrategy for PerLevel {
+    fn resolve_test(&self, outcomes: ConfigOutcomes<TestOutcome>) -> Expected<TestOutcome> {
+        self.tests.resolve_test(outcomes)
+    }
+
+    fn resolve_subtest(
+        &self,
+        outcomes: ConfigOutcomes<SubtestOutcome>,
+    ) -> Expected<SubtestOutcome> {
+        self.subtests.resolve_subtest(outcomes)
+    }
+
+    /// Defers to [`Self::tests`], since whether a test is kept is a test-level decision.
+    fn keeps_unreported_tests(&self) -> bool {
+        self.tests.keeps_unreported_tests()
+    }
+}
+
+#[test]
+fn presets() {
+    use crate::process_reports::ReportProcessingPreset;
+
+    let outcomes = |current: &[SubtestOutcome], reported: Option<SubtestOutcome>| ConfigOutcomes {
+        platform: Platform::Linux,
+        build_profile: BuildProfile::Optimized,
+        current: Expected::new(current.iter().copied().collect()).unwrap(),
+        reported: reported.map(Expected::permanent),
+        covered: true,
+    };
+    let cases = [
+        // An intermittent expectation, of which one outcome was reported.
+        outcomes(
+            &[SubtestOutcome::Pass, SubtestOutcome::Fail],
+            Some(SubtestOutcome::Fail),
+        ),
+        // A permanent expectation, contradicted by a timeout.
+        outcomes(&[SubtestOutcome::Pass], Some(SubtestOutcome::Timeout)),
+        // A subtest that wasn't run in a configuration that reports were given for.
+        outcomes(&[SubtestOutcome::Fail], None),
+    ];
+    let resolve = |preset: ReportProcessingPreset| {
+        let strategy = preset.strategy();
+        let resolved = cases
+            .iter()
+            .map(|outcomes| strategy.resolve_subtest(*outcomes).to_string())
+            .collect::<Vec<_>>();
+        (resolved, strategy.keeps_unreported_tests())
+    };
+    assert_eq!(
+        resolve(ReportProcessingPreset::ResetContradictory),
+        (
+            vec![
+                "[PASS, FAIL]".to_owned(),
+                "TIMEOUT".to_owned(),
+                "FAIL".to_owned()
+            ],
+            false
+        )
+    );
+    assert_eq!(
+        resolve(ReportProcessingPreset::Merge),
+        (
+            vec![
+                "[PASS, FAIL]".to_owned(),
+                "[PASS, TIMEOUT]".to_owned(),
+                "FAIL".to_owned()
+            ],
+            true
+        )
+    );
+    assert_eq!(
+        resolve(ReportProcessingPreset::ResetAll),
+        (
+            vec!["FAIL".to_owned(), "TIMEOUT".to_owned(), "PASS".to_owned()],
+            false
+        )
+    );
+    assert_eq!(
+        resolve(ReportProcessingPreset::PruneUnobserved),
+        (
+            vec!["FAIL".to_owned(), "TIMEOUT".to_owned(), "PASS".to_owned()],
+            false
+        )
+    );
+    assert_eq!(
+        resolve(ReportProcessingPreset::ResetTimeouts),
+        (
+            vec![
+                "[PASS, FAIL]".to_owned(),
+                "TIMEOUT".to_owned(),
+                "FAIL".to_owned()
+            ],
+            false
+        )
+    );
+    assert_eq!(
+        resolve(ReportProcessingPreset::PruneStaleIntermittents),
+        (
+            vec!["FAIL".to_owned(), "PASS".to_owned(), "FAIL".to_owned()],
+            true
+        )
+    );
+}
@@ -0,0 +1,150 @@
+//! Tables of expectations as delimiter-separated values, for pivoting in spreadsheets (i.e., with
+//! `search --table` and `triage --export-table`).
+
+use std::{
+    fmt::{self, Display, Formatter},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use format::lazy_format;
+
+use crate::search::{ConfigurationExpectation, SearchHit};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum TableFormat {
+    /// Comma-separated values, quoted where needed as per RFC 4180.
+    Csv,
+    /// Tab-separated values. Tabs and line breaks in values are replaced with spaces.
+    Tsv,
+}
+
+impl TableFormat {
+    /// Returns the format for a file at `path`: TSV if its extension is `.tsv`, and CSV otherwise.
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("tsv") => Self::Tsv,
+            _ => Self::Csv,
+        }
+    }
+
+    fn write_row(self, f: &mut Formatter<'_>, values: &[&str]) -> fmt::Result {
+        for (idx, value) in values.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(match self {
+                    Self::Csv => ",",
+                    Self::Tsv => "\t",
+                })?;
+            }
+            match self {
+                Self::Csv if value.contains([',', '"', '\n', '\r']) => {
+                    write!(f, "\"{}\"", value.replace('"', "\"\""))?
+                }
+                Self::Csv => f.write_str(value)?,
+                Self::Tsv => f.write_str(&value.replace(['\t', '\n', '\r'], " "))?,
+            }
+        }
+        f.write_str("\n")
+    }
+}
+
+/// Renders `hits` as a table with a header and one row for each outcome expected of each test or
+/// subtest in each configuration, with columns:
+///
+/// * `test`: the test's runner URL path, i.e.,
+///   `/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:*`.
+/// * `subtest`: the subtest's name, or empty for the test itself.
+/// * `platform` and `build_profile`: as spelled on the command line, i.e., `win` and `opt`.
+/// * `outcome`: an expected outcome, as spelled in metadata.
+/// * `intermittent`: `true` if other outcomes are expected, too, and `false` otherwise.
+/// * `disabled`: why the test or subtest is disabled in the configuration, or empty.
+pub fn expectations(format: TableFormat, hits: &[SearchHit]) -> impl Display + '_ {
+    lazy_format!(move |f| {
+        format.write_row(
+            f,
+            &[
+                "test",
+                "subtest",
+                "platform",
+                "build_profile",
+                "outcome",
+                "intermittent",
+                "disabled",
+            ],
+        )?;
+        for hit in hits {
+            let SearchHit {
+                test,
+                subtest,
+                configurations,
+            } = hit;
+            let test = format!("/{}", test.runner_url_path());
+            for configuration in configurations {
+                let ConfigurationExpectation {
+                    platform,
+                    build_profile,
+                    expected,
+                    disabled,
+                } = configuration;
+                let intermittent = if expected.len() > 1 { "true" } else { "false" };
+                for outcome in expected {
+                    format.write_row(
+                        f,
+                        &[
+                            &test,
+                            subtest.as_deref().unwrap_or_default(),
+                            platform.os_name(),
+                            build_profile.name(),
+                            outcome,
+                            intermittent,
+                            disabled.as_deref().unwrap_or_default(),
+                        ],
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn expectations_table() {
+    use crate::{
+        metadata::{BuildProfile, Platform},
+        shared::{Browser, TestPath},
+    };
+
+    let hits = [SearchHit {
+        test: TestPath::from_execution_report(
+            Browser::Firefox,
+            "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:a:*",
+        )
+        .unwrap()
+        .into_owned(),
+        subtest: Some(r#":label="a,b""#.to_owned()),
+        configurations: vec![ConfigurationExpectation {
+            platform: Platform::MacOs,
+            build_profile: BuildProfile::Optimized,
+            expected: vec!["PASS".to_owned(), "FAIL".to_owned()],
+            disabled: Some("bug\t1".to_owned()),
+        }],
+    }];
+    insta::assert_snapshot!(expectations(TableFormat::Csv, &hits), @r###"
+    test,subtest,platform,build_profile,outcome,intermittent,disabled
+    "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:a:*",":label=""a,b""",mac,opt,PASS,true,bug	1
+    "/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:a:*",":label=""a,b""",mac,opt,FAIL,true,bug	1
+    "###);
+    insta::assert_snapshot!(expectations(TableFormat::Tsv, &hits), @r###"
+    test	subtest	platform	build_profile	outcome	intermittent	disabled
+    /_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:a:*	:label="a,b"	mac	opt	PASS	true	bug 1
+    /_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,adapter:a:*	:label="a,b"	mac	opt	FAIL	true	bug 1
+    "###);
+    assert_eq!(
+        TableFormat::for_path(Path::new("out.TSV")),
+        TableFormat::Tsv
+    );
+    assert_eq!(
+        TableFormat::for_path(Path::new("out.txt")),
+        TableFormat::Csv
+    );
+}
@@ -0,0 +1,312 @@
+//! Fetching of `wptreport.json` files from [Taskcluster], so that reports from CI don't have to
+//! be downloaded by hand before processing them.
+//!
+//! [Taskcluster]: https://firefox-ci-tc.services.mozilla.com
+
+use std::{
+    fmt::{self, Display, Formatter},
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{download, error::Error, filesystem::FileSystem};
+
+/// The root URL of the Taskcluster deployment that runs Firefox's CI.
+pub const FIREFOX_CI_ROOT_URL: &str = "https://firefox-ci-tc.services.mozilla.com";
+
+/// A push to a Gecko repository, as specified with `--from-push`, i.e., `try:0123abcd`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Push {
+    /// The repository pushed to, as named in the Taskcluster index (i.e., `try`, or
+    /// `mozilla-central`).
+    pub project: String,
+    pub revision: String,
+}
+
+impl Push {
+    /// The Taskcluster index path of the decision task of this push, whose task ID is also the
+    /// ID of the task group containing all tasks of the push.
+    pub fn decision_index_path(&self) -> String {
+        let Self { project, revision } = self;
+        format!("gecko.v2.{project}.revision.{revision}.taskgraph.decision")
+    }
+}
+
+/// Parsed from `[PROJECT:]REVISION`, where `PROJECT` defaults to `try`.
+impl FromStr for Push {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (project, revision) = s.split_once(':').unwrap_or(("try", s));
+        if project.is_empty() {
+            return Err("expected a project before `:`".to_owned());
+        }
+        if revision.is_empty() || !revision.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("{revision:?} is not a hexadecimal revision"));
+        }
+        Ok(Self {
+            project: project.to_owned(),
+            revision: revision.to_owned(),
+        })
+    }
+}
+
+impl Display for Push {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self { project, revision } = self;
+        write!(f, "{project}:{revision}")
+    }
+}
+
+/// Returns whether the task named `name` runs WebGPU tests with `wptrunner`, i.e.,
+/// `test-windows11-64-2009-qr/debug-web-platform-tests-webgpu-1`.
+pub fn is_webgpu_wpt_task(name: &str) -> bool {
+    name.contains("web-platform-tests") && name.contains("webgpu")
+}
+
+/// Returns whether the artifact named `name` is a report from `wptrunner`.
+pub fn is_report_artifact(name: &str) -> bool {
+    name.rsplit('/')
+        .next()
+        .map_or(false, |file_name| file_name.ends_with("wptreport.json"))
+}
+
+/// A client for the parts of the Taskcluster API used to find and download reports.
+#[derive(Clone, Debug)]
+pub struct Taskcluster {
+    root_url: String,
+    agent: ureq::Agent,
+}
+
+/// A task found by [`Taskcluster::tasks_in_group`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Task {
+    pub id: String,
+    pub name: String,
+}
+
+impl Taskcluster {
+    pub fn new(root_url: &str) -> Self {
+        Self {
+            root_url: root_url.trim_end_matches('/').to_owned(),
+            agent: download::agent(),
+        }
+    }
+
+    /// Returns the ID of the task group containing all tasks of `push`.
+    pub fn task_group_of_push(&self, push: &Push) -> Result<String, Error> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct IndexedTask {
+            task_id: String,
+        }
+
+        let IndexedTask { task_id } = self.get_json(
+            &format!("api/index/v1/task/{}", push.decision_index_path()),
+            None,
+        )?;
+        Ok(task_id)
+    }
+
+    /// Lists all tasks in `task_group`.
+    pub fn tasks_in_group(&self, task_group: &str) -> Result<Vec<Task>, Error> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Page {
+            tasks: Vec<TaskAndStatus>,
+            continuation_token: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct TaskAndStatus {
+            status: Status,
+            task: TaskDefinition,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Status {
+            task_id: String,
+        }
+        #[derive(Deserialize)]
+        struct TaskDefinition {
+            metadata: Metadata,
+        }
+        #[derive(Deserialize)]
+        struct Metadata {
+            name: String,
+        }
+
+        let path = format!("api/queue/v1/task-group/{task_group}/list");
+        let mut tasks = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let Page {
+                tasks: page,
+                continuation_token: next,
+            } = self.get_json(&path, continuation_token.as_deref())?;
+            tasks.extend(page.into_iter().map(|TaskAndStatus { status, task }| Task {
+                id: status.task_id,
+                name: task.metadata.name,
+            }));
+            match next {
+                Some(next) => continuation_token = Some(next),
+                None => break,
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Lists the names of artifacts of the latest run of `task_id`.
+    pub fn artifacts(&self, task_id: &str) -> Result<Vec<String>, Error> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Page {
+            artifacts: Vec<Artifact>,
+            continuation_token: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct Artifact {
+            name: String,
+        }
+
+        let path = format!("api/queue/v1/task/{task_id}/artifacts");
+        let mut artifacts = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let Page {
+                artifacts: page,
+                continuation_token: next,
+            } = self.get_json(&path, continuation_token.as_deref())?;
+            artifacts.extend(page.into_iter().map(|Artifact { name }| name));
+            match next {
+                Some(next) => continuation_token = Some(next),
+                None => break,
+            }
+        }
+        Ok(artifacts)
+    }
+
+    /// Downloads the artifact named `name` from the latest run of `task_id`.
+    pub fn artifact(&self, task_id: &str, name: &str) -> Result<String, Error> {
+        self.get(
+            &format!("api/queue/v1/task/{task_id}/artifacts/{name}"),
+            None,
+        )
+    }
+
+    fn get(&self, path: &str, continuation_token: Option<&str>) -> Result<String, Error> {
+        let url = format!("{}/{path}", self.root_url);
+        tracing::debug!("requesting {url}");
+        let mut request = self.agent.get(&url);
+        if let Some(continuation_token) = continuation_token {
+            request = request.query("continuationToken", continuation_token);
+        }
+        let response = request.call().map_err(|source| Error::TaskclusterRequest {
+            url: url.clone(),
+            source: Box::new(source),
+        })?;
+        // N.B.: `Response::into_string` refuses responses over 10 MB, which reports often are.
+        let mut body = String::new();
+        response
+            .into_reader()
+            .read_to_string(&mut body)
+            .map_err(|source| Error::ReadTaskclusterResponse { url, source })?;
+        Ok(body)
+    }
+
+    fn get_json<T>(&self, path: &str, continuation_token: Option<&str>) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let body = self.get(path, continuation_token)?;
+        serde_json::from_str(&body).map_err(|source| Error::ParseTaskclusterResponse {
+            url: format!("{}/{path}", self.root_url),
+            source,
+        })
+    }
+}
+
+/// Downloads reports from WebGPU test tasks in `task_group` into `dir`, returning the paths of
+/// downloaded reports. Reports already downloaded into `dir` by an earlier invocation are not
+/// downloaded again.
+///
+/// If `cancelled` is set, no further reports are downloaded.
+pub fn fetch_reports(
+    fs: &dyn FileSystem,
+    taskcluster: &Taskcluster,
+    task_group: &str,
+    dir: &Path,
+    cancelled: &AtomicBool,
+) -> Result<Vec<PathBuf>, Error> {
+    let tasks = taskcluster
+        .tasks_in_group(task_group)?
+        .into_iter()
+        .filter(|task| is_webgpu_wpt_task(&task.name))
+        .collect::<Vec<_>>();
+    tracing::info!(
+        "found {} WebGPU test task(s) in task group {task_group}",
+        tasks.len()
+    );
+
+    let mut paths = Vec::new();
+    for Task { id, name } in tasks {
+        if cancelled.load(Ordering::SeqCst) {
+            return Err(Error::Cancelled);
+        }
+        for artifact in taskcluster.artifacts(&id)? {
+            if !is_report_artifact(&artifact) {
+                continue;
+            }
+            let file_name = artifact.rsplit('/').next().unwrap();
+            let path = dir.join(task_group).join(&id).join(file_name);
+            if fs.read_to_string(&path).is_ok() {
+                tracing::debug!("using report of {name} ({id}) downloaded earlier");
+            } else {
+                tracing::info!("downloading report of {name} ({id})…");
+                let contents = taskcluster.artifact(&id, &artifact)?;
+                fs.write(&path, &contents)?;
+            }
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+#[test]
+fn parse_push() {
+    assert_eq!(
+        "0123abcd".parse::<Push>().unwrap(),
+        Push {
+            project: "try".to_owned(),
+            revision: "0123abcd".to_owned(),
+        }
+    );
+    let push = "mozilla-central:0123abcd".parse::<Push>().unwrap();
+    assert_eq!(push.to_string(), "mozilla-central:0123abcd");
+    assert_eq!(
+        push.decision_index_path(),
+        "gecko.v2.mozilla-central.revision.0123abcd.taskgraph.decision"
+    );
+    assert!(":0123abcd".parse::<Push>().is_err());
+    assert!("try:".parse::<Push>().is_err());
+    assert!("try:main".parse::<Push>().is_err());
+}
+
+#[test]
+fn filter_tasks_and_artifacts() {
+    assert!(is_webgpu_wpt_task(
+        "test-windows11-64-2009-qr/debug-web-platform-tests-webgpu-1"
+    ));
+    assert!(!is_webgpu_wpt_task(
+        "test-windows11-64-2009-qr/debug-web-platform-tests-1"
+    ));
+    assert!(!is_webgpu_wpt_task(
+        "test-linux1804-64-qr/opt-mochitest-webgpu"
+    ));
+
+    assert!(is_report_artifact("public/test_info/wptreport.json"));
+    assert!(!is_report_artifact("public/test_info/wpt_raw.log"));
+}
@@ -0,0 +1,104 @@
+//! Patterns for selecting tests on the command line and in lists like [`NeverModifyList`].
+//!
+//! [`NeverModifyList`]: crate::never_modify::NeverModifyList
+
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::shared::TestPath;
+
+/// A pattern matching tests, as taken by subcommands and options that select tests, and in lists
+/// like [`NeverModifyList`]. One of:
+///
+/// * A test URL path, as found in reports, in which `*` matches any run of characters, i.e.,
+///   `/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,operation,*`.
+/// * A CTS query, i.e., `webgpu:api,operation,adapter:*`. Queries ending in `*` match all CTS tests
+///   nested under them; others must match a test's query exactly.
+///
+/// [`NeverModifyList`]: crate::never_modify::NeverModifyList
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TestPattern {
+    UrlGlob(String),
+    CtsQuery(String),
+}
+
+impl TestPattern {
+    pub fn matches(&self, test_path: &TestPath<'_>) -> bool {
+        match self {
+            Self::UrlGlob(glob) => glob_matches(glob, &format!("/{}", test_path.runner_url_path())),
+            Self::CtsQuery(query) => test_path
+                .variant
+                .as_deref()
+                .and_then(|variant| variant.strip_prefix("?q="))
+                .filter(|cts_query| cts_query.starts_with("webgpu:"))
+                .map_or(false, |cts_query| match query.strip_suffix('*') {
+                    Some(prefix) => cts_query.starts_with(prefix),
+                    None => cts_query == query,
+                }),
+        }
+    }
+}
+
+impl FromStr for TestPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("webgpu:") {
+            Ok(Self::CtsQuery(s.to_owned()))
+        } else if s.starts_with('/') {
+            Ok(Self::UrlGlob(s.to_owned()))
+        } else {
+            Err(format!(
+                concat!(
+                    "expected a test URL path starting with `/` ",
+                    "or a CTS query starting with `webgpu:`, got {:?}"
+                ),
+                s
+            ))
+        }
+    }
+}
+
+impl Display for TestPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UrlGlob(glob) => f.write_str(glob),
+            Self::CtsQuery(query) => f.write_str(query),
+        }
+    }
+}
+
+/// Matches `text` against `glob`, where `*` matches any run of characters and all other characters
+/// match themselves.
+fn glob_matches(glob: &str, text: &str) -> bool {
+    let mut literals = glob.split('*');
+    let first = literals.next().unwrap();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut literals = literals.collect::<Vec<_>>();
+    let Some(last) = literals.pop() else {
+        return rest.is_empty();
+    };
+    for literal in literals {
+        match rest.find(literal) {
+            Some(idx) => rest = &rest[idx + literal.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[test]
+fn glob() {
+    assert!(glob_matches("abc", "abc"));
+    assert!(!glob_matches("abc", "abcd"));
+    assert!(glob_matches("a*", "abcd"));
+    assert!(glob_matches("*d", "abcd"));
+    assert!(glob_matches("a*c*", "abcd"));
+    assert!(glob_matches("a*b*b", "abcbb"));
+    assert!(!glob_matches("a*bc*c", "abc"));
+    assert!(glob_matches("*", ""));
+}
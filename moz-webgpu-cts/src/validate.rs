@@ -0,0 +1,408 @@
+//! Cross-checking of metadata against the tests that exist, to find metadata left stale by CTS
+//! updates.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use indexmap::IndexMap;
+use whippit::metadata::SectionHeader;
+
+use crate::{error::Error, filesystem::FileSystem, metadata::File, shared::TestPath};
+
+/// Where the tests that exist are enumerated from.
+#[derive(Clone, Debug)]
+pub enum TestListing {
+    /// The test files metadata files belong to, in the Gecko checkout. Tests with variants are
+    /// enumerated from the `<meta name=variant content=…>` tags in their files.
+    TestFiles,
+    /// CTS queries, as parsed by [`TestListing::parse_queries`]. Only CTS tests are checked.
+    Queries(BTreeSet<String>),
+}
+
+impl TestListing {
+    /// Parses CTS queries from `contents`, read from `path`: either `listing_meta.json` from a CTS
+    /// build, whose keys are queries, or a file with one query per line. Blank lines and lines
+    /// starting with `#` are ignored.
+    pub fn parse_queries(path: &Path, contents: &str) -> Result<Self, Error> {
+        let queries = if contents.trim_start().starts_with('{') {
+            serde_json::from_str::<BTreeMap<String, serde::de::IgnoredAny>>(contents)
+                .map_err(|source| Error::ParseCtsListing {
+                    path: path.to_owned(),
+                    source,
+                })?
+                .into_keys()
+                .collect()
+        } else {
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(ToOwned::to_owned)
+                .collect()
+        };
+        Ok(Self::Queries(queries))
+    }
+}
+
+/// A problem with a metadata section found by [`validate`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Finding {
+    pub path: Arc<PathBuf>,
+    pub name: SectionHeader,
+    pub kind: FindingKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FindingKind {
+    /// The section does not correspond to any test in the listing.
+    StaleTest,
+    /// The test file the section belongs to does not exist.
+    MissingTestFile { test_file: PathBuf },
+    /// The section's name is not a well-formed CTS query.
+    MalformedQuery { reason: &'static str },
+    /// The section's name doesn't name a test in the file the metadata file belongs to, so the
+    /// harness never applies it.
+    UnmappedSection,
+    /// The URL path the harness runs the test at doesn't map back to the section, so the harness
+    /// never applies it.
+    RunnerUrlMismatch {
+        runner_url_path: String,
+        reason: String,
+    },
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self { path, name, kind } = self;
+        write!(f, "[{}] in {}: ", name.0, path.display())?;
+        match kind {
+            FindingKind::StaleTest => write!(f, "no such test exists"),
+            FindingKind::MissingTestFile { test_file } => {
+                write!(f, "test file {} does not exist", test_file.display())
+            }
+            FindingKind::MalformedQuery { reason } => write!(f, "malformed CTS query: {reason}"),
+            FindingKind::UnmappedSection => write!(
+                f,
+                "section name does not correspond to the test file of this metadata file"
+            ),
+            FindingKind::RunnerUrlMismatch {
+                runner_url_path,
+                reason,
+            } => write!(f, "test is run as `{runner_url_path}`, which {reason}"),
+        }
+    }
+}
+
+/// Checks the tests in `files`, read from `gecko_checkout`, against `listing`. Test files are read
+/// from `fs` when using [`TestListing::TestFiles`].
+///
+/// Regardless of `listing`, each section is checked to map back from the URL path the harness
+/// would run its test at (see [`check_runner_url`]).
+pub fn validate(
+    fs: &dyn FileSystem,
+    gecko_checkout: &Path,
+    files: &IndexMap<Arc<PathBuf>, File>,
+    listing: &TestListing,
+) -> Result<Vec<Finding>, Error> {
+    let mut findings = Vec::new();
+    let mut variants_by_test_file = BTreeMap::<PathBuf, Option<BTreeSet<String>>>::new();
+    for (path, file) in files {
+        let rel_path = path.strip_prefix(gecko_checkout).unwrap_or(path);
+        for name in file.tests.keys() {
+            let mut finding = |kind| {
+                findings.push(Finding {
+                    path: path.clone(),
+                    name: name.clone(),
+                    kind,
+                })
+            };
+            let Ok(test_path) = TestPath::from_metadata_test(rel_path, &name.0) else {
+                finding(FindingKind::UnmappedSection);
+                continue;
+            };
+            if let Err(kind) = check_runner_url(&test_path, rel_path, &name.0) {
+                finding(kind);
+                continue;
+            }
+
+            let cts_path = test_path.cts_path();
+            if test_path.path.ends_with("cts.https.html") {
+                if let Err(reason) = check_cts_query(test_path.variant.as_deref().unwrap_or("")) {
+                    finding(FindingKind::MalformedQuery { reason });
+                    continue;
+                }
+            }
+
+            match listing {
+                TestListing::Queries(queries) => {
+                    if let Some(cts_path) = cts_path {
+                        if !queries
+                            .iter()
+                            .any(|query| queries_overlap(query, &cts_path))
+                        {
+                            finding(FindingKind::StaleTest);
+                        }
+                    }
+                }
+                TestListing::TestFiles => {
+                    let test_file = gecko_checkout.join(test_path.rel_test_path().to_string());
+                    if !variants_by_test_file.contains_key(&test_file) {
+                        let variants = match fs.read_to_string(&test_file) {
+                            Ok(contents) => Some(parse_variants(&contents)),
+                            Err(Error::ReadFile { source, .. })
+                                if source.kind() == io::ErrorKind::NotFound =>
+                            {
+                                None
+                            }
+                            Err(e) => return Err(e),
+                        };
+                        variants_by_test_file.insert(test_file.clone(), variants);
+                    }
+                    match (&variants_by_test_file[&test_file], &test_path.variant) {
+                        (None, _) => finding(FindingKind::MissingTestFile { test_file }),
+                        (Some(variants), Some(variant)) if !variants.contains(&**variant) => {
+                            finding(FindingKind::StaleTest)
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+    Ok(findings)
+}
+
+/// Checks that the URL path the harness would run `test_path` at, as derived from the section
+/// named `name` in the metadata file at `rel_meta_path`, maps back to the same section, and that
+/// `name` is spelled the way test IDs in reports are. Otherwise, the harness silently ignores the
+/// section.
+fn check_runner_url(
+    test_path: &TestPath<'_>,
+    rel_meta_path: &Path,
+    name: &str,
+) -> Result<(), FindingKind> {
+    let runner_url_path = format!("/{}", test_path.runner_url_path());
+    let mismatch = |reason: String| FindingKind::RunnerUrlMismatch {
+        runner_url_path: runner_url_path.clone(),
+        reason,
+    };
+
+    let Ok(resolved) = TestPath::from_execution_report(test_path.browser, &runner_url_path) else {
+        return Err(mismatch("is not recognized as a test URL path".to_owned()));
+    };
+    let resolved_name = resolved.test_name().to_string();
+    let resolved_meta_path = resolved.rel_metadata_path().to_string();
+    if resolved_name != name || Path::new(&resolved_meta_path) != rel_meta_path {
+        return Err(mismatch(format!(
+            "maps back to [{resolved_name}] in {resolved_meta_path}"
+        )));
+    }
+
+    if name.trim() != name {
+        return Err(mismatch(
+            "has leading or trailing whitespace, unlike test IDs in reports".to_owned(),
+        ));
+    }
+    let is_percent_encoded = name.as_bytes().windows(3).any(|window| {
+        window[0] == b'%' && window[1].is_ascii_hexdigit() && window[2].is_ascii_hexdigit()
+    });
+    if is_percent_encoded {
+        return Err(mismatch(
+            "is percent-encoded, unlike test IDs in reports".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the contents of all `<meta name=variant content=…>` tags in `html`, i.e.,
+/// `?q=webgpu:api,operation,adapter,info:*`.
+fn parse_variants(html: &str) -> BTreeSet<String> {
+    html.split('<')
+        .filter_map(|tag| tag.strip_prefix("meta"))
+        .filter(|attrs| {
+            attrs.contains("name=variant")
+                || attrs.contains("name=\"variant\"")
+                || attrs.contains("name='variant'")
+        })
+        .filter_map(|attrs| {
+            let value = &attrs[attrs.find("content=")? + "content=".len()..];
+            let value = match value.chars().next()? {
+                quote @ ('"' | '\'') => value[1..].split(quote).next()?,
+                _ => value
+                    .split(|c: char| c.is_whitespace() || c == '>')
+                    .next()?,
+            };
+            Some(value.to_owned())
+        })
+        .collect()
+}
+
+/// Checks that `variant` is a CTS query in the `webgpu` suite, i.e.,
+/// `?q=webgpu:api,operation,adapter,info:*`, returning why if it isn't.
+fn check_cts_query(variant: &str) -> Result<(), &'static str> {
+    let query = variant
+        .strip_prefix("?q=")
+        .ok_or("expected a variant starting with `?q=`")?;
+    let query = query
+        .strip_prefix("webgpu:")
+        .ok_or("expected a query in the `webgpu` suite")?;
+    if query.contains(char::is_whitespace) {
+        return Err("contains whitespace");
+    }
+    if query.find('*').map_or(false, |idx| idx != query.len() - 1) {
+        return Err("wildcard `*` must be at the end");
+    }
+    let levels = query.split(':').collect::<Vec<_>>();
+    if levels.len() > 3 {
+        return Err("more than 3 `:`-separated levels after the suite");
+    }
+    let [file, ..] = &levels[..] else {
+        unreachable!()
+    };
+    if *file != "*" && file.split(',').any(|segment| segment.is_empty()) {
+        return Err("empty file path segment");
+    }
+    Ok(())
+}
+
+/// Returns whether the CTS queries `a` and `b` may match any of the same tests, i.e., when one is
+/// a wildcard query covering the other.
+fn queries_overlap(a: &str, b: &str) -> bool {
+    let a = a.strip_suffix('*').unwrap_or(a);
+    let b = b.strip_suffix('*').unwrap_or(b);
+    a.starts_with(b) || b.starts_with(a)
+}
+
+#[test]
+fn validate_against_listings() {
+    use crate::{checkout::parse_metadata, filesystem::MemoryFileSystem};
+
+    let gecko_checkout = Path::new("gecko");
+    let meta_path = gecko_checkout
+        .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu/cts.https.html.ini");
+    let (meta_path, file) = parse_metadata(
+        meta_path,
+        concat!(
+            "[cts.https.html?q=webgpu:api,exists:*]\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,gone:*]\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,,empty:*]\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,*:wild]\n",
+        )
+        .to_owned(),
+    )
+    .unwrap();
+    let files = [(meta_path, file)].into_iter().collect::<IndexMap<_, _>>();
+
+    let describe = |findings: Vec<Finding>| {
+        findings
+            .into_iter()
+            .map(|finding| format!("{}: {:?}", finding.name.0, finding.kind))
+            .collect::<Vec<_>>()
+    };
+
+    let fs = [(
+        gecko_checkout.join("testing/web-platform/mozilla/tests/webgpu/cts/webgpu/cts.https.html"),
+        concat!(
+            "<!doctype html>\n",
+            "<meta name=variant content='?q=webgpu:api,exists:*'>\n",
+            "<meta name=\"variant\" content=\"?q=webgpu:api,also_exists:*\">\n",
+        ),
+    )]
+    .into_iter()
+    .collect::<MemoryFileSystem>();
+    let findings = validate(&fs, gecko_checkout, &files, &TestListing::TestFiles).unwrap();
+    insta::assert_debug_snapshot!(describe(findings), @r###"
+    [
+        "cts.https.html?q=webgpu:api,*:wild: MalformedQuery { reason: \"wildcard `*` must be at the end\" }",
+        "cts.https.html?q=webgpu:api,,empty:*: MalformedQuery { reason: \"empty file path segment\" }",
+        "cts.https.html?q=webgpu:api,gone:*: StaleTest",
+    ]
+    "###);
+
+    let listing = TestListing::parse_queries(
+        Path::new("listing.txt"),
+        "# Comment\nwebgpu:api,exists:a:*\nwebgpu:api,other:*\n",
+    )
+    .unwrap();
+    let findings = validate(&fs, gecko_checkout, &files, &listing).unwrap();
+    insta::assert_debug_snapshot!(describe(findings), @r###"
+    [
+        "cts.https.html?q=webgpu:api,*:wild: MalformedQuery { reason: \"wildcard `*` must be at the end\" }",
+        "cts.https.html?q=webgpu:api,,empty:*: MalformedQuery { reason: \"empty file path segment\" }",
+        "cts.https.html?q=webgpu:api,gone:*: StaleTest",
+    ]
+    "###);
+
+    let listing = TestListing::parse_queries(
+        Path::new("listing_meta.json"),
+        r#"{"webgpu:api,gone:a:*": {"subcaseMS": 1.0}}"#,
+    )
+    .unwrap();
+    let findings = validate(&MemoryFileSystem::new(), gecko_checkout, &files, &listing).unwrap();
+    insta::assert_debug_snapshot!(describe(findings), @r###"
+    [
+        "cts.https.html?q=webgpu:api,*:wild: MalformedQuery { reason: \"wildcard `*` must be at the end\" }",
+        "cts.https.html?q=webgpu:api,,empty:*: MalformedQuery { reason: \"empty file path segment\" }",
+        "cts.https.html?q=webgpu:api,exists:*: StaleTest",
+    ]
+    "###);
+
+    let findings = validate(
+        &MemoryFileSystem::new(),
+        gecko_checkout,
+        &files,
+        &TestListing::TestFiles,
+    )
+    .unwrap();
+    assert!(findings
+        .iter()
+        .any(|finding| matches!(finding.kind, FindingKind::MissingTestFile { .. })));
+}
+
+#[test]
+fn audit_runner_urls() {
+    use crate::{checkout::parse_metadata, filesystem::MemoryFileSystem};
+
+    let gecko_checkout = Path::new("gecko");
+    let (meta_path, file) = parse_metadata(
+        gecko_checkout
+            .join("testing/web-platform/mozilla/meta/webgpu/cts/webgpu/cts.https.html.ini"),
+        concat!(
+            "[cts.https.html?q=webgpu:api,fine:*]\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,a%2Cb:*]\n",
+            "\n",
+            "[cts.https.html?q=webgpu:api,trailing:* ]\n",
+            "\n",
+            "[cts.html?q=webgpu:api,wrong_file:*]\n",
+            "\n",
+            "[cts.https.html/?q=webgpu:api,slash:*]\n",
+        )
+        .to_owned(),
+    )
+    .unwrap();
+    let files = [(meta_path, file)].into_iter().collect::<IndexMap<_, _>>();
+
+    let listing = TestListing::parse_queries(Path::new("listing.txt"), "webgpu:*").unwrap();
+    let findings = validate(&MemoryFileSystem::new(), gecko_checkout, &files, &listing).unwrap();
+    let findings = findings
+        .into_iter()
+        .map(|finding| format!("{}: {:?}", finding.name.0, finding.kind))
+        .collect::<Vec<_>>();
+    insta::assert_debug_snapshot!(findings, @r###"
+    [
+        "cts.html?q=webgpu:api,wrong_file:*: UnmappedSection",
+        "cts.https.html/?q=webgpu:api,slash:*: UnmappedSection",
+        "cts.https.html?q=webgpu:api,a%2Cb:*: RunnerUrlMismatch { runner_url_path: \"/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,a%2Cb:*\", reason: \"is percent-encoded, unlike test IDs in reports\" }",
+        "cts.https.html?q=webgpu:api,trailing:* : RunnerUrlMismatch { runner_url_path: \"/_mozilla/webgpu/cts/webgpu/cts.https.html?q=webgpu:api,trailing:* \", reason: \"has leading or trailing whitespace, unlike test IDs in reports\" }",
+    ]
+    "###);
+}